@@ -0,0 +1,275 @@
+//! Benchmark to measure the throughput of replication updates
+//!
+//! Each case spawns N replicated entities on a server connected to one client, with the local channel
+//! transport so that the results are deterministic. Every entity has M float components and one
+//! [`Stats`] component, which can be replicated with delta compression; every frame, all the
+//! float components and one field of the stats are updated.
+//!
+//! For each case we measure:
+//! - `replication_throughput/send`: the time spent by the server to buffer and send the updates of one frame
+//! - `replication_throughput/receive`: the time spent by the client to receive and apply them
+//! - the number of replication bytes sent per frame, which criterion reports as the throughput of the benchmark.
+//!   The channel statistics are only collected with the `trace` feature, so the bytes are only measured
+//!   when running with `--features trace`
+//!
+//! Run with `cargo bench --bench=replication_throughput [--features trace]`.
+//! Use `-- --save-baseline <name>` before a change and `-- --baseline <name>` after it to compare.
+//!
+//! A baseline with the hardware it was measured on is recorded in `REPLICATION_THROUGHPUT.md`.
+use std::fmt::{Display, Formatter};
+use std::time::Instant;
+
+use bevy::prelude::{default, With};
+use bevy::utils::Duration;
+#[cfg(feature = "trace")]
+use criterion::Throughput;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+#[cfg(feature = "trace")]
+use lightyear::channel::builder::{EntityActionsChannel, EntityUpdatesChannel};
+use lightyear::client::sync::SyncConfig;
+use lightyear::prelude::client::{InterpolationConfig, PredictionConfig};
+use lightyear::prelude::server::{ConnectionManager, Replicate, ServerConfig};
+use lightyear::prelude::{
+    ClientId, DeltaCompression, Replicating, ReplicationGroup, SharedConfig, TickConfig,
+};
+use lightyear_benches::local_stepper::{LocalBevyStepper, Step as LocalStep};
+use lightyear_benches::protocol::*;
+
+criterion_group!(
+    name = replication_throughput_benches;
+    config = Criterion::default();
+    targets = send_updates, receive_updates,
+);
+criterion_main!(replication_throughput_benches);
+
+const NUM_ENTITIES: &[usize] = &[100, 1000];
+const NUM_COMPONENTS: &[usize] = &[1, 3];
+/// Number of frames used to compute the average number of bytes sent per frame
+#[cfg(feature = "trace")]
+const BYTES_SAMPLE_FRAMES: usize = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum PriorityMode {
+    /// No bandwidth cap: all the updates are sent every frame
+    Uncapped,
+    /// Bandwidth cap enabled, all the entities have the same priority
+    CappedUniform,
+    /// Bandwidth cap enabled, the entities have priorities between 1 and 10
+    CappedSpread,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Case {
+    num_entities: usize,
+    num_components: usize,
+    delta_compression: bool,
+    priority: PriorityMode,
+}
+
+impl Display for Case {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "entities={}/components={}/delta={}/priority={:?}",
+            self.num_entities, self.num_components, self.delta_compression, self.priority
+        )
+    }
+}
+
+fn cases() -> Vec<Case> {
+    let mut cases = vec![];
+    for &num_entities in NUM_ENTITIES {
+        for &num_components in NUM_COMPONENTS {
+            for delta_compression in [false, true] {
+                for priority in [
+                    PriorityMode::Uncapped,
+                    PriorityMode::CappedUniform,
+                    PriorityMode::CappedSpread,
+                ] {
+                    cases.push(Case {
+                        num_entities,
+                        num_components,
+                        delta_compression,
+                        priority,
+                    });
+                }
+            }
+        }
+    }
+    cases
+}
+
+/// Connect a client and replicate the entities of the case, so that only the updates are measured
+fn setup(case: &Case) -> LocalBevyStepper {
+    let frame_duration = Duration::from_secs_f64(1.0 / 60.0);
+    let tick_duration = Duration::from_secs_f64(1.0 / 64.0);
+    let shared_config = SharedConfig {
+        tick: TickConfig::new(tick_duration),
+        ..default()
+    };
+    let mut stepper = LocalBevyStepper::new(
+        1,
+        shared_config,
+        SyncConfig::default(),
+        PredictionConfig::default(),
+        InterpolationConfig::default(),
+        frame_duration,
+    );
+    stepper
+        .server_app
+        .world_mut()
+        .resource_mut::<ServerConfig>()
+        .packet
+        .bandwidth_cap_enabled = case.priority != PriorityMode::Uncapped;
+    stepper.init();
+
+    let world = stepper.server_app.world_mut();
+    for i in 0..case.num_entities {
+        let priority = match case.priority {
+            PriorityMode::CappedSpread => 1.0 + (i % 10) as f32,
+            _ => 1.0,
+        };
+        let mut entity = world.spawn(Replicate {
+            group: ReplicationGroup::new_id(i as u64 + 1).set_priority(priority),
+            ..default()
+        });
+        entity.insert(Component1(0.0));
+        if case.num_components > 1 {
+            entity.insert(Component2(0.0));
+        }
+        if case.num_components > 2 {
+            entity.insert(Component3(0.0));
+        }
+        entity.insert(Stats([0.0; 8]));
+        if case.delta_compression {
+            entity.insert(DeltaCompression::<Stats>::default());
+        }
+    }
+    // replicate the spawns and wait for them to be acked
+    for _ in 0..10 {
+        stepper.frame_step();
+    }
+    stepper
+}
+
+/// Update the float components and one field of the stats of every entity
+fn update_components(stepper: &mut LocalBevyStepper, frame: usize) {
+    let value = frame as f32;
+    let world = stepper.server_app.world_mut();
+    for mut c in world
+        .query_filtered::<&mut Component1, With<Replicating>>()
+        .iter_mut(world)
+    {
+        c.0 = value;
+    }
+    for mut c in world
+        .query_filtered::<&mut Component2, With<Replicating>>()
+        .iter_mut(world)
+    {
+        c.0 = value;
+    }
+    for mut c in world
+        .query_filtered::<&mut Component3, With<Replicating>>()
+        .iter_mut(world)
+    {
+        c.0 = value;
+    }
+    for mut c in world
+        .query_filtered::<&mut Stats, With<Replicating>>()
+        .iter_mut(world)
+    {
+        c.0[frame % 8] = value;
+    }
+}
+
+/// Total number of replication bytes sent by the server to the client
+#[cfg(feature = "trace")]
+fn replication_bytes_sent(stepper: &LocalBevyStepper) -> usize {
+    let message_manager = &stepper
+        .server_app
+        .world()
+        .resource::<ConnectionManager>()
+        .connection(ClientId::Netcode(0))
+        .unwrap()
+        .message_manager;
+    [
+        message_manager.channel_send_stats::<EntityActionsChannel>(),
+        message_manager.channel_send_stats::<EntityUpdatesChannel>(),
+    ]
+    .into_iter()
+    .flatten()
+    .map(|stats| stats.bytes_sent())
+    .sum()
+}
+
+/// Average number of replication bytes sent per frame for a case
+#[cfg(feature = "trace")]
+fn bytes_per_frame(case: &Case) -> u64 {
+    let mut stepper = setup(case);
+    let before = replication_bytes_sent(&stepper);
+    for frame in 0..BYTES_SAMPLE_FRAMES {
+        update_components(&mut stepper, frame + 1);
+        stepper.frame_step();
+    }
+    let bytes = replication_bytes_sent(&stepper) - before;
+    (bytes / BYTES_SAMPLE_FRAMES) as u64
+}
+
+/// Which part of the frame is measured
+#[derive(Clone, Copy, PartialEq)]
+enum Side {
+    Send,
+    Receive,
+}
+
+fn bench_updates(criterion: &mut Criterion, side: Side) {
+    let name = match side {
+        Side::Send => "replication_throughput/send",
+        Side::Receive => "replication_throughput/receive",
+    };
+    let mut group = criterion.benchmark_group(name);
+    group.warm_up_time(Duration::from_millis(500));
+    group.measurement_time(Duration::from_millis(4000));
+    for case in cases() {
+        #[cfg(feature = "trace")]
+        {
+            let bytes = bytes_per_frame(&case);
+            println!("{case}: {bytes} bytes/frame");
+            group.throughput(Throughput::Bytes(bytes));
+        }
+        group.bench_with_input(BenchmarkId::from_parameter(case), &case, |bencher, case| {
+            bencher.iter_custom(|iter| {
+                let mut stepper = setup(case);
+                let mut elapsed = Duration::ZERO;
+                for frame in 0..iter as usize {
+                    update_components(&mut stepper, frame + 1);
+                    stepper.advance_time(stepper.frame_duration);
+
+                    let instant = Instant::now();
+                    stepper.server_update();
+                    if side == Side::Send {
+                        elapsed += instant.elapsed();
+                    }
+
+                    let instant = Instant::now();
+                    stepper.client_update();
+                    if side == Side::Receive {
+                        elapsed += instant.elapsed();
+                    }
+                }
+                elapsed
+            });
+        });
+    }
+    group.finish();
+}
+
+/// Time spent by the server to send the replication updates of one frame
+fn send_updates(criterion: &mut Criterion) {
+    bench_updates(criterion, Side::Send);
+}
+
+/// Time spent by the client to receive and apply the replication updates of one frame
+fn receive_updates(criterion: &mut Criterion) {
+    bench_updates(criterion, Side::Receive);
+}
@@ -3,6 +3,7 @@ use bevy::prelude::Component;
 use bevy::utils::default;
 use lightyear::client::components::ComponentSyncMode;
 use lightyear::client::prediction::plugin::add_prediction_systems;
+use lightyear::shared::replication::delta::Diffable;
 use serde::{Deserialize, Serialize};
 use std::ops::{Add, Mul};
 
@@ -41,6 +42,37 @@ pub struct Component2(pub f32);
 #[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct Component3(pub f32);
 
+/// Component with several fields, where only a few of them change every frame.
+///
+/// It is replicated with delta compression on the entities that have a [`DeltaCompression<Stats>`] component.
+#[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Stats(pub [f32; 8]);
+
+impl Diffable for Stats {
+    /// The index and new value of the fields that changed
+    type Delta = Vec<(u8, f32)>;
+
+    fn base_value() -> Self {
+        Self([0.0; 8])
+    }
+
+    fn diff(&self, new: &Self) -> Self::Delta {
+        self.0
+            .iter()
+            .zip(new.0.iter())
+            .enumerate()
+            .filter(|(_, (old, new))| old != new)
+            .map(|(i, (_, new))| (i as u8, *new))
+            .collect()
+    }
+
+    fn apply_diff(&mut self, delta: &Self::Delta) {
+        for (i, value) in delta {
+            self.0[*i as usize] = *value;
+        }
+    }
+}
+
 // Inputs
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
@@ -70,6 +102,8 @@ impl Plugin for ProtocolPlugin {
             .add_prediction(ComponentSyncMode::Simple);
         app.register_component::<Component3>(ChannelDirection::ServerToClient)
             .add_prediction(ComponentSyncMode::Once);
+        app.register_component::<Stats>(ChannelDirection::ServerToClient)
+            .add_delta_compression();
         // channels
         app.add_channel::<Channel1>(ChannelSettings {
             mode: ChannelMode::OrderedReliable(ReliableSettings::default()),
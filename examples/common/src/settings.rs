@@ -158,6 +158,7 @@ pub(crate) fn build_server_netcode_config(
         transport: transport_config,
         conditioner,
         compression: shared.compression,
+        transform: None,
     };
     server::NetConfig::Netcode {
         config: netcode_config,
@@ -263,6 +264,7 @@ pub(crate) fn build_client_netcode_config(
         transport: transport_config,
         conditioner,
         compression: shared.compression,
+        transform: None,
     };
     client::NetConfig::Netcode {
         auth,
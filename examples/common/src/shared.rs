@@ -10,9 +10,8 @@ pub fn shared_config(mode: Mode) -> SharedConfig {
     SharedConfig {
         // send replication updates every 100ms
         server_replication_send_interval: REPLICATION_INTERVAL,
-        tick: TickConfig {
-            tick_duration: Duration::from_secs_f64(1.0 / FIXED_TIMESTEP_HZ),
-        },
+        tick: TickConfig::new(Duration::from_secs_f64(1.0 / FIXED_TIMESTEP_HZ)),
         mode,
+        ..Default::default()
     }
 }
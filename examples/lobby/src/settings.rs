@@ -157,6 +157,7 @@ pub fn build_server_netcode_config(
         transport: transport_config,
         conditioner,
         compression: shared.compression,
+        transform: None,
     };
     server::NetConfig::Netcode {
         config: netcode_config,
@@ -334,6 +335,7 @@ pub fn build_client_netcode_config(
         transport: transport_config,
         conditioner,
         compression: shared.compression,
+        transform: None,
     };
     client::NetConfig::Netcode {
         auth,
@@ -15,10 +15,9 @@ pub fn shared_config() -> SharedConfig {
     SharedConfig {
         // send an update every 100ms
         server_replication_send_interval: SERVER_REPLICATION_INTERVAL,
-        tick: TickConfig {
-            tick_duration: Duration::from_secs_f64(1.0 / FIXED_TIMESTEP_HZ),
-        },
+        tick: TickConfig::new(Duration::from_secs_f64(1.0 / FIXED_TIMESTEP_HZ)),
         mode: Mode::Separate,
+        ..Default::default()
     }
 }
 
@@ -1,4 +1,5 @@
 //! This module contains the [`Channel`] trait
+use bevy::reflect::Reflect;
 use bevy::utils::Duration;
 
 use lightyear_macros::ChannelInternal;
@@ -16,6 +17,7 @@ use crate::channel::senders::unordered_unreliable_with_acks::UnorderedUnreliable
 use crate::channel::senders::ChannelSender;
 #[cfg(feature = "trace")]
 use crate::channel::stats::send::ChannelSendStats;
+use crate::channel::stats::ChannelStats;
 use crate::prelude::ChannelKind;
 
 /// A ChannelContainer is a struct that implements the [`Channel`] trait
@@ -24,6 +26,7 @@ pub struct ChannelContainer {
     pub setting: ChannelSettings,
     pub(crate) receiver: ChannelReceiver,
     pub(crate) sender: ChannelSender,
+    pub(crate) stats: ChannelStats,
     // we will put this behind the trace feature for now, as this is pretty niche
     // and might be performance heavy
     #[cfg(feature = "trace")]
@@ -45,7 +48,7 @@ pub struct ChannelContainer {
 /// app.add_channel::<MyChannel>(ChannelSettings {
 ///     mode: ChannelMode::UnorderedUnreliable,
 ///     direction: ChannelDirection::Bidirectional,
-///     priority: 1.0,
+///     ..default()
 /// });
 /// ```
 pub trait Channel: 'static {
@@ -110,6 +113,7 @@ impl ChannelContainer {
             setting: settings_clone,
             receiver,
             sender,
+            stats: ChannelStats::default(),
             #[cfg(feature = "trace")]
             sender_stats: ChannelSendStats::default(),
         }
@@ -120,6 +124,8 @@ impl ChannelContainer {
 #[derive(Clone, Debug, PartialEq)]
 pub struct ChannelSettings {
     pub mode: ChannelMode,
+    /// Direction in which messages can be sent on this channel
+    pub direction: ChannelDirection,
     /// How often should we try to send messages on this channel.
     /// Set to `Duration::default()` to send messages every frame if possible.
     pub send_frequency: Duration,
@@ -131,6 +137,7 @@ impl Default for ChannelSettings {
     fn default() -> Self {
         Self {
             mode: ChannelMode::UnorderedUnreliable,
+            direction: ChannelDirection::Bidirectional,
             send_frequency: Duration::default(),
             priority: 1.0,
         }
@@ -191,6 +198,35 @@ pub enum ChannelDirection {
     Bidirectional,
 }
 
+impl ChannelDirection {
+    /// Returns true if the client is allowed to send messages in this direction
+    pub fn client_can_send(&self) -> bool {
+        matches!(
+            self,
+            ChannelDirection::ClientToServer | ChannelDirection::Bidirectional
+        )
+    }
+
+    /// Returns true if the server is allowed to send messages in this direction
+    pub fn server_can_send(&self) -> bool {
+        matches!(
+            self,
+            ChannelDirection::ServerToClient | ChannelDirection::Bidirectional
+        )
+    }
+}
+
+/// What to do when trying to send a message on a [`Channel`] whose [`ChannelDirection`]
+/// does not allow sending from this peer (for example a client sending on a `ServerToClient` channel)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Reflect)]
+pub enum WrongDirectionPolicy {
+    /// Return a [`MessageError::WrongChannelDirection`](crate::protocol::message::MessageError::WrongChannelDirection) error
+    #[default]
+    Error,
+    /// Log an error and drop the message
+    Drop,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct ReliableSettings {
     /// Duration to wait before resending a packet if it has not been acked
@@ -226,6 +262,13 @@ pub struct EntityActionsChannel;
 /// This is a Sequenced Unreliable channel
 pub struct EntityUpdatesChannel;
 
+/// Default channel to send the initial snapshot of the replicated entities to a peer that just connected,
+/// when [`ReplicationConfig::initial_snapshot_compression`](crate::prelude::ReplicationConfig::initial_snapshot_compression)
+/// is enabled.
+/// This is an Unordered Reliable channel.
+#[derive(ChannelInternal)]
+pub struct SnapshotChannel;
+
 /// Default channel to send pings. This is a Sequenced Unreliable channel, because
 /// there is no point in getting older pings.
 #[derive(ChannelInternal)]
@@ -237,10 +280,35 @@ pub struct PingChannel;
 pub struct PongChannel;
 
 #[derive(ChannelInternal)]
-/// Default channel to send inputs from client to server. This is a Sequenced Unreliable channel.
+/// Default channel to send inputs from client to server. This is an Unordered Unreliable channel.
+///
+/// Input messages that arrive late (after a more recent input message) are not discarded: each message
+/// contains the inputs for a range of ticks, and the ticks that the server has not simulated yet are
+/// still applied.
 pub struct InputChannel;
 
+#[derive(ChannelInternal)]
+/// Channel used to recover lost inputs: the server sends an [`InputNack`](crate::inputs::native::InputNack)
+/// on this channel when it detects missing inputs, and the client re-sends the requested inputs on it.
+/// The client also sends its [`ViewDelay`](crate::shared::input::ViewDelay) on this channel.
+/// This is an Unordered Reliable channel.
+pub struct InputRecoveryChannel;
+
 #[derive(ChannelInternal)]
 /// Channel to send messages related to Authority transfers
 /// This is an Ordered Reliable channel
 pub struct AuthorityChannel;
+
+#[derive(ChannelInternal)]
+/// Channel to send the [`ServerNotification`](crate::shared::notification::ServerNotification)s
+/// and the [`InputDelayCommand`](crate::shared::input::InputDelayCommand)s
+/// This is an Ordered Reliable channel
+pub struct NotificationChannel;
+
+#[derive(ChannelInternal)]
+/// General-purpose channel that is registered by default, to send your own messages
+/// (for example gameplay RPCs) without having to define a channel.
+/// This is an Ordered Reliable channel.
+///
+/// This channel is not used by lightyear internally.
+pub struct DefaultOrderedReliableChannel;
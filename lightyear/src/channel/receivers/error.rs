@@ -5,4 +5,12 @@ pub type Result<T> = core::result::Result<T, ChannelReceiveError>;
 pub enum ChannelReceiveError {
     #[error("A message was received without a message ID")]
     MissingMessageId,
+    /// The fragment doesn't match the local fragment size or the other fragments of the message.
+    /// This happens if the remote uses a different MTU.
+    #[error("Received an invalid fragment ({fragment_id}/{num_fragments}) of {len} bytes")]
+    InvalidFragment {
+        fragment_id: usize,
+        num_fragments: usize,
+        len: usize,
+    },
 }
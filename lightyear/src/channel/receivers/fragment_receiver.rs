@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use bytes::Bytes;
 use tracing::trace;
 
+use crate::channel::receivers::error::{ChannelReceiveError, Result};
 use crate::packet::message::{FragmentData, MessageId};
 use crate::packet::packet::FRAGMENT_SIZE;
 use crate::prelude::Tick;
@@ -12,12 +13,15 @@ use crate::shared::time_manager::WrappedTime;
 #[derive(Debug)]
 pub struct FragmentReceiver {
     fragment_messages: HashMap<MessageId, FragmentConstructor>,
+    /// Size of the fragments sent by the remote (all fragments except the last one have this size)
+    pub(crate) fragment_size: usize,
 }
 
 impl FragmentReceiver {
     pub fn new() -> Self {
         Self {
             fragment_messages: HashMap::new(),
+            fragment_size: FRAGMENT_SIZE,
         }
     }
 
@@ -37,30 +41,45 @@ impl FragmentReceiver {
     ///
     /// When we complete the final message by aggregating all fragments, we will return the
     /// `remote_sent_tick` associated with the first fragment received.
+    ///
+    /// Returns an error if the fragment is not consistent with the local fragment size (for example
+    /// if the remote uses a different MTU).
     pub fn receive_fragment(
         &mut self,
         fragment: FragmentData,
         remote_sent_tick: Tick,
         current_time: Option<WrappedTime>,
-    ) -> Option<(Tick, Bytes)> {
+    ) -> Result<Option<(Tick, Bytes)>> {
+        let fragment_size = self.fragment_size;
         let fragment_message = self
             .fragment_messages
             .entry(fragment.message_id)
             .or_insert_with(|| {
-                FragmentConstructor::new(remote_sent_tick, fragment.num_fragments as usize)
+                FragmentConstructor::new(
+                    remote_sent_tick,
+                    fragment.num_fragments as usize,
+                    fragment_size,
+                )
             });
 
-        // completed the fragmented message!
-        if let Some(payload) = fragment_message.receive_fragment(
+        match fragment_message.receive_fragment(
             fragment.fragment_id as usize,
+            fragment.num_fragments as usize,
             fragment.bytes.as_ref(),
             current_time,
         ) {
-            self.fragment_messages.remove(&fragment.message_id);
-            return Some(payload);
+            // completed the fragmented message!
+            Ok(Some(payload)) => {
+                self.fragment_messages.remove(&fragment.message_id);
+                Ok(Some(payload))
+            }
+            Ok(None) => Ok(None),
+            // the message can never be reconstructed, drop it
+            Err(e) => {
+                self.fragment_messages.remove(&fragment.message_id);
+                Err(e)
+            }
         }
-
-        None
     }
 }
 
@@ -68,6 +87,7 @@ impl FragmentReceiver {
 /// Data structure to reconstruct a single fragmented message from individual fragments
 pub struct FragmentConstructor {
     num_fragments: usize,
+    fragment_size: usize,
     num_received_fragments: usize,
     received: Vec<bool>,
     // bytes: Bytes,
@@ -78,12 +98,13 @@ pub struct FragmentConstructor {
 }
 
 impl FragmentConstructor {
-    pub fn new(tick: Tick, num_fragments: usize) -> Self {
+    pub fn new(tick: Tick, num_fragments: usize, fragment_size: usize) -> Self {
         Self {
             num_fragments,
+            fragment_size,
             num_received_fragments: 0,
             received: vec![false; num_fragments],
-            bytes: vec![0; num_fragments * FRAGMENT_SIZE],
+            bytes: vec![0; num_fragments * fragment_size],
             tick,
             last_received: None,
         }
@@ -92,25 +113,37 @@ impl FragmentConstructor {
     pub fn receive_fragment(
         &mut self,
         fragment_index: usize,
+        num_fragments: usize,
         bytes: &[u8],
         received_time: Option<WrappedTime>,
-    ) -> Option<(Tick, Bytes)> {
+    ) -> Result<Option<(Tick, Bytes)>> {
+        let is_last_fragment = fragment_index + 1 == self.num_fragments;
+        // all the fragments except the last one have exactly the fragment size
+        let valid_len = if is_last_fragment {
+            !bytes.is_empty() && bytes.len() <= self.fragment_size
+        } else {
+            bytes.len() == self.fragment_size
+        };
+        if num_fragments != self.num_fragments || fragment_index >= self.num_fragments || !valid_len
+        {
+            return Err(ChannelReceiveError::InvalidFragment {
+                fragment_id: fragment_index,
+                num_fragments,
+                len: bytes.len(),
+            });
+        }
         self.last_received = received_time;
 
-        let is_last_fragment = fragment_index == self.num_fragments - 1;
-
-        // TODO: check sizes?
-
         if !self.received[fragment_index] {
             self.received[fragment_index] = true;
             self.num_received_fragments += 1;
 
             if is_last_fragment {
-                let len = (self.num_fragments - 1) * FRAGMENT_SIZE + bytes.len();
+                let len = (self.num_fragments - 1) * self.fragment_size + bytes.len();
                 self.bytes.resize(len, 0);
             }
 
-            let start = fragment_index * FRAGMENT_SIZE;
+            let start = fragment_index * self.fragment_size;
             let end = start + bytes.len();
             self.bytes[start..end].copy_from_slice(bytes);
         }
@@ -118,10 +151,10 @@ impl FragmentConstructor {
         if self.num_received_fragments == self.num_fragments {
             trace!("Received all fragments!");
             let payload = std::mem::take(&mut self.bytes);
-            return Some((self.tick, payload.into()));
+            return Ok(Some((self.tick, payload.into())));
         }
 
-        None
+        Ok(None)
     }
 }
 
@@ -141,12 +174,45 @@ mod tests {
             .unwrap();
 
         assert_eq!(
-            receiver.receive_fragment(fragments[0].clone(), Tick(0), None),
+            receiver
+                .receive_fragment(fragments[0].clone(), Tick(0), None)
+                .unwrap(),
             None
         );
         assert_eq!(
-            receiver.receive_fragment(fragments[1].clone(), Tick(1), None),
+            receiver
+                .receive_fragment(fragments[1].clone(), Tick(1), None)
+                .unwrap(),
             Some((Tick(0), message_bytes.clone()))
         );
     }
+
+    /// Fragments sent by a remote that uses a bigger fragment size (i.e. a bigger MTU) are rejected
+    /// instead of making the receiver panic
+    #[test]
+    fn test_receiver_invalid_fragment() {
+        let mut receiver = FragmentReceiver::new();
+        receiver.fragment_size = FRAGMENT_SIZE / 2;
+        let message_bytes = Bytes::from(vec![1u8; FRAGMENT_SIZE * 2]);
+        let fragments = FragmentSender::new()
+            .build_fragments(MessageId(0), None, message_bytes)
+            .unwrap();
+        assert!(matches!(
+            receiver.receive_fragment(fragments[0].clone(), Tick(0), None),
+            Err(ChannelReceiveError::InvalidFragment { .. })
+        ));
+        assert!(matches!(
+            receiver.receive_fragment(fragments[1].clone(), Tick(0), None),
+            Err(ChannelReceiveError::InvalidFragment { .. })
+        ));
+
+        // fragment index out of bounds
+        let mut fragment = fragments[0].clone();
+        fragment.fragment_id = fragment.num_fragments;
+        receiver.fragment_size = FRAGMENT_SIZE;
+        assert!(matches!(
+            receiver.receive_fragment(fragment, Tick(0), None),
+            Err(ChannelReceiveError::InvalidFragment { .. })
+        ));
+    }
 }
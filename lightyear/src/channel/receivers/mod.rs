@@ -38,6 +38,9 @@ pub trait ChannelReceive {
 
     /// Reads a message from the internal buffer to get its content
     fn read_message(&mut self) -> Option<(Tick, Bytes)>;
+
+    /// Set the size of the fragments sent by the remote, used to reassemble fragmented messages
+    fn set_fragment_size(&mut self, fragment_size: usize);
 }
 
 /// This enum contains the various types of receivers available
@@ -1,6 +1,7 @@
 use std::collections::{btree_map, BTreeMap};
 
 use bytes::Bytes;
+use tracing::trace;
 
 use super::error::{ChannelReceiveError, Result};
 use crate::channel::receivers::fragment_receiver::FragmentReceiver;
@@ -59,7 +60,7 @@ impl ChannelReceive for OrderedReliableReceiver {
                         fragment,
                         message.remote_sent_tick,
                         None,
-                    ) {
+                    )? {
                         entry.insert(res);
                     }
                 }
@@ -80,9 +81,14 @@ impl ChannelReceive for OrderedReliableReceiver {
 
         // if we have finally received the message we are waiting for, return it and
         // wait for the next one
+        trace!(message_id = ?self.pending_recv_message_id, "read message");
         self.pending_recv_message_id += 1;
         Some(message)
     }
+
+    fn set_fragment_size(&mut self, fragment_size: usize) {
+        self.fragment_receiver.fragment_size = fragment_size;
+    }
 }
 
 #[cfg(test)]
@@ -1,6 +1,7 @@
 use std::collections::{btree_map, BTreeMap};
 
 use bytes::Bytes;
+use tracing::trace;
 
 use super::error::{ChannelReceiveError, Result};
 
@@ -65,7 +66,7 @@ impl ChannelReceive for SequencedReliableReceiver {
                         fragment,
                         message.remote_sent_tick,
                         None,
-                    ) {
+                    )? {
                         entry.insert(res);
                     }
                 }
@@ -78,10 +79,15 @@ impl ChannelReceive for SequencedReliableReceiver {
         loop {
             let (message_id, message) = self.recv_message_buffer.pop_first()?;
             if message_id >= self.most_recent_message_id {
+                trace!(?message_id, "read message");
                 return Some(message);
             }
         }
     }
+
+    fn set_fragment_size(&mut self, fragment_size: usize) {
+        self.fragment_receiver.fragment_size = fragment_size;
+    }
 }
 
 #[cfg(test)]
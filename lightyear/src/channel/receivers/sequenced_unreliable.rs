@@ -71,7 +71,7 @@ impl ChannelReceive for SequencedUnreliableReceiver {
                     fragment,
                     message.remote_sent_tick,
                     Some(self.current_time),
-                ) {
+                )? {
                     self.recv_message_buffer.push_back(res);
                 }
             }
@@ -82,6 +82,10 @@ impl ChannelReceive for SequencedUnreliableReceiver {
         self.recv_message_buffer.pop_front()
         // TODO: naia does a more optimized version by return a Vec<Message> instead of Option<Message>
     }
+
+    fn set_fragment_size(&mut self, fragment_size: usize) {
+        self.fragment_receiver.fragment_size = fragment_size;
+    }
 }
 
 #[cfg(test)]
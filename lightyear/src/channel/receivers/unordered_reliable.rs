@@ -1,6 +1,7 @@
 use std::collections::{btree_map, BTreeMap, HashSet};
 
 use bytes::Bytes;
+use tracing::trace;
 
 use super::error::ChannelReceiveError;
 
@@ -69,7 +70,7 @@ impl ChannelReceive for UnorderedReliableReceiver {
                         fragment,
                         message.remote_sent_tick,
                         None,
-                    ) {
+                    )? {
                         // receive the message if we haven't received it already
                         if !self.received_message_ids.contains(&message_id) {
                             self.received_message_ids.insert(message_id);
@@ -100,8 +101,13 @@ impl ChannelReceive for UnorderedReliableReceiver {
         }
 
         // receive oldest message in the buffer
+        trace!(?message_id, "read message");
         Some(data)
     }
+
+    fn set_fragment_size(&mut self, fragment_size: usize) {
+        self.fragment_receiver.fragment_size = fragment_size;
+    }
 }
 
 #[cfg(test)]
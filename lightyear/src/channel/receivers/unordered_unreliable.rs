@@ -46,7 +46,7 @@ impl ChannelReceive for UnorderedUnreliableReceiver {
                     fragment,
                     message.remote_sent_tick,
                     Some(self.current_time),
-                ) {
+                )? {
                     self.recv_message_buffer.push_back(data);
                 }
             }
@@ -57,6 +57,10 @@ impl ChannelReceive for UnorderedUnreliableReceiver {
     fn read_message(&mut self) -> Option<(Tick, Bytes)> {
         self.recv_message_buffer.pop_front()
     }
+
+    fn set_fragment_size(&mut self, fragment_size: usize) {
+        self.fragment_receiver.fragment_size = fragment_size;
+    }
 }
 
 #[cfg(test)]
@@ -58,6 +58,9 @@ pub trait ChannelSend {
 
     /// Send nacks to the subscribers of nacks
     fn send_nacks(&mut self, nack: MessageId);
+
+    /// Set the maximum number of bytes of a message before it gets fragmented
+    fn set_fragment_size(&mut self, fragment_size: usize);
 }
 
 /// Enum dispatch lets us derive ChannelSend on each enum variant
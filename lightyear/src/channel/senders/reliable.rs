@@ -101,6 +101,31 @@ impl ReliableSender {
             priority_multiplier: 1.0,
         }
     }
+
+    /// Number of messages that haven't been acked yet, including the ones that haven't been sent yet
+    pub(crate) fn num_unacked_messages(&self) -> usize {
+        self.unacked_messages.len()
+    }
+
+    /// Number of messages that have been sent at least once but haven't been acked yet
+    pub(crate) fn num_unacked_sent_messages(&self) -> usize {
+        self.unacked_messages
+            .values()
+            .filter(|message| match &message.unacked_message {
+                UnackedMessage::Single { last_sent, .. } => last_sent.is_some(),
+                UnackedMessage::Fragmented(fragments) => fragments
+                    .iter()
+                    .any(|fragment| fragment.last_sent.is_some()),
+            })
+            .count()
+    }
+
+    /// Stop sending a message that hasn't been acked yet.
+    ///
+    /// Returns false if the message was already acked (or never existed)
+    pub(crate) fn cancel_message(&mut self, message_id: MessageId) -> bool {
+        self.unacked_messages.remove(&message_id).is_some()
+    }
 }
 
 impl ChannelSend for ReliableSender {
@@ -333,6 +358,10 @@ impl ChannelSend for ReliableSender {
             sender.send(nack).unwrap();
         }
     }
+
+    fn set_fragment_size(&mut self, fragment_size: usize) {
+        self.fragment_sender.fragment_size = fragment_size;
+    }
 }
 
 #[cfg(test)]
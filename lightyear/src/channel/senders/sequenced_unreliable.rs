@@ -122,6 +122,10 @@ impl ChannelSend for SequencedUnreliableSender {
             sender.send(nack).unwrap();
         }
     }
+
+    fn set_fragment_size(&mut self, fragment_size: usize) {
+        self.fragment_sender.fragment_size = fragment_size;
+    }
 }
 
 #[cfg(test)]
@@ -123,6 +123,10 @@ impl ChannelSend for UnorderedUnreliableSender {
             sender.send(nack).unwrap();
         }
     }
+
+    fn set_fragment_size(&mut self, fragment_size: usize) {
+        self.fragment_sender.fragment_size = fragment_size;
+    }
 }
 
 #[cfg(test)]
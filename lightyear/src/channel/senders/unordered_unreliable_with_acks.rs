@@ -163,6 +163,10 @@ impl ChannelSend for UnorderedUnreliableWithAcksSender {
             sender.send(nack).unwrap();
         }
     }
+
+    fn set_fragment_size(&mut self, fragment_size: usize) {
+        self.fragment_sender.fragment_size = fragment_size;
+    }
 }
 
 #[cfg(test)]
@@ -1,3 +1,52 @@
+//! Statistics about the messages sent and received on each channel
+
+/// Lightweight counters of the messages and bytes sent and received on a channel.
+///
+/// The counters are always enabled and are cumulative since the connection started; to draw a
+/// bandwidth graph, sample them at regular intervals and use the difference between two samples.
+///
+/// The bytes only count the message payloads, not the packet headers.
+#[derive(Default, Copy, Clone, Debug, PartialEq)]
+pub struct ChannelStats {
+    messages_sent: usize,
+    messages_received: usize,
+    bytes_sent: usize,
+    bytes_received: usize,
+}
+
+impl ChannelStats {
+    pub(crate) fn add_sent(&mut self, num_messages: usize, num_bytes: usize) {
+        self.messages_sent = self.messages_sent.saturating_add(num_messages);
+        self.bytes_sent = self.bytes_sent.saturating_add(num_bytes);
+    }
+
+    pub(crate) fn add_received(&mut self, num_bytes: usize) {
+        self.messages_received = self.messages_received.saturating_add(1);
+        self.bytes_received = self.bytes_received.saturating_add(num_bytes);
+    }
+
+    /// Number of messages sent (each fragment of a fragmented message counts as one message)
+    pub fn messages_sent(&self) -> usize {
+        self.messages_sent
+    }
+
+    /// Number of messages received (each fragment of a fragmented message counts as one message)
+    pub fn messages_received(&self) -> usize {
+        self.messages_received
+    }
+
+    /// Number of bytes of message payloads sent
+    pub fn bytes_sent(&self) -> usize {
+        self.bytes_sent
+    }
+
+    /// Number of bytes of message payloads received
+    pub fn bytes_received(&self) -> usize {
+        self.bytes_received
+    }
+}
+
+#[cfg(feature = "trace")]
 pub(crate) mod send {
     /// TODO: maybe this should be directly on the ChannelSender?
     #[derive(Default, Copy, Clone, Debug, PartialEq)]
@@ -27,5 +76,9 @@ pub(crate) mod send {
         pub fn messages_sent(&self) -> usize {
             self.num_single_messages_sent + self.num_fragment_messages_sent
         }
+
+        pub fn bytes_sent(&self) -> usize {
+            self.num_bytes_sent
+        }
     }
 }
@@ -55,7 +55,9 @@ pub enum ComponentSyncMode {
     /// Simple sync: whenever the confirmed entity gets updated, we propagate the update to the interpolated/predicted entity
     /// Use this for components that don't get updated often or are not time-sensitive
     ///
-    /// Predicted: that means the component's state will be ~1-RTT behind the predicted entity's timeline
+    /// Predicted: that means the component's state will be ~1-RTT behind the predicted entity's timeline.
+    /// The component is not rolled back; use [`keep_confirmed_during_rollback`](crate::prelude::ComponentRegistration::keep_confirmed_during_rollback)
+    /// to also prevent it from being re-computed during rollback, for values that only the remote can compute.
     /// Interpolated: that means the component might not be rendered smoothly as it will only be updated after we receive a server update
     Simple,
 
@@ -1,9 +1,11 @@
 //! Defines client-specific configuration options
 use bevy::prelude::Resource;
 use bevy::reflect::Reflect;
+use bevy::utils::Duration;
 use governor::Quota;
 use nonzero_ext::nonzero;
 
+use crate::channel::builder::WrongDirectionPolicy;
 use crate::client::input::native::InputConfig;
 use crate::client::interpolation::plugin::InterpolationConfig;
 use crate::client::prediction::plugin::PredictionConfig;
@@ -60,6 +62,28 @@ pub struct PacketConfig {
     pub send_bandwidth_cap: Quota,
     /// If false, there is no bandwidth cap and all messages are sent as soon as possible
     pub bandwidth_cap_enabled: bool,
+    /// What to do when trying to send a message on a channel whose [`ChannelDirection`](crate::prelude::ChannelDirection)
+    /// does not allow it
+    pub wrong_direction_policy: WrongDirectionPolicy,
+    /// If true, keep a copy of the raw bytes of the last packet sent and received on each connection,
+    /// which can be inspected via the `ConnectionManager` to debug protocol mismatches.
+    ///
+    /// This is disabled by default because it copies every packet.
+    pub capture_packets: bool,
+    /// If true, include in the header of every packet the time elapsed since the start of the current tick
+    /// (as a fraction of the tick duration), so that the receiver can estimate more precisely when the
+    /// packet was sent.
+    ///
+    /// This costs 1 extra byte per packet. It is useful on the server, so that the clients can place the
+    /// received updates more accurately on their interpolation timeline.
+    pub send_sub_tick_fraction: bool,
+    /// If set, a packet that only contains the acks is sent at most once per interval when there is nothing
+    /// else to send, so that the remote still receives acks while this peer is idle.
+    ///
+    /// Enable it if the remote uses a [`backpressure_timeout`](crate::prelude::ReplicationConfig::backpressure_timeout),
+    /// otherwise the remote pauses its replication updates while this peer is idle.
+    /// Disabled by default.
+    pub ack_packet_interval: Option<Duration>,
 }
 
 impl Default for PacketConfig {
@@ -69,6 +93,10 @@ impl Default for PacketConfig {
             // 56 KB/s bandwidth cap
             send_bandwidth_cap: Quota::per_second(nonzero!(56000u32)),
             bandwidth_cap_enabled: false,
+            wrong_direction_policy: WrongDirectionPolicy::default(),
+            capture_packets: false,
+            send_sub_tick_fraction: false,
+            ack_packet_interval: None,
         }
     }
 }
@@ -89,6 +117,21 @@ impl PacketConfig {
         self.bandwidth_cap_enabled = true;
         self
     }
+
+    pub fn enable_packet_capture(mut self) -> Self {
+        self.capture_packets = true;
+        self
+    }
+
+    pub fn enable_sub_tick_fraction(mut self) -> Self {
+        self.send_sub_tick_fraction = true;
+        self
+    }
+
+    pub fn with_ack_packet_interval(mut self, interval: Duration) -> Self {
+        self.ack_packet_interval = Some(interval);
+        self
+    }
 }
 
 /// The configuration object that lets you create a `ClientPlugin` with the desired settings.
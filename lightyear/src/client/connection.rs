@@ -1,20 +1,22 @@
 //! Specify how a Client sends/receives messages with a Server
 use bevy::ecs::component::Tick as BevyTick;
 use bevy::ecs::entity::MapEntities;
-use bevy::prelude::{Resource, World};
+use bevy::prelude::{Component, Entity, Resource, World};
 use bevy::utils::{Duration, HashMap};
 use bytes::Bytes;
-use tracing::{debug, trace, trace_span};
+use tracing::{debug, error, trace, trace_span};
 
 use crate::channel::builder::{
-    EntityActionsChannel, EntityUpdatesChannel, PingChannel, PongChannel,
+    EntityActionsChannel, EntityUpdatesChannel, PingChannel, PongChannel, SnapshotChannel,
+    WrongDirectionPolicy,
 };
 
 use crate::channel::receivers::ChannelReceive;
 use crate::channel::senders::ChannelSend;
+use crate::channel::stats::ChannelStats;
 use crate::client::config::ClientConfig;
 use crate::client::error::ClientError;
-use crate::client::sync::SyncConfig;
+use crate::client::sync::{SyncConfig, SyncStatsSnapshot};
 use crate::connection::netcode::MAX_PACKET_SIZE;
 use crate::packet::message_manager::MessageManager;
 use crate::packet::packet_builder::{Payload, RecvPayload};
@@ -22,18 +24,21 @@ use crate::packet::priority_manager::PriorityConfig;
 use crate::prelude::client::PredictionConfig;
 use crate::prelude::{Channel, ChannelKind, ClientId, Message, ReplicationConfig};
 use crate::protocol::channel::ChannelRegistry;
-use crate::protocol::component::ComponentRegistry;
-use crate::protocol::message::{MessageRegistry, MessageType};
+use crate::protocol::component::{ComponentKind, ComponentRegistry};
+use crate::protocol::message::{
+    MessageRegistry, MessageType, UnknownMessage, SCHEDULED_MESSAGE_NET_ID,
+};
 use crate::protocol::registry::NetId;
 use crate::serialize::reader::Reader;
 use crate::serialize::writer::Writer;
 use crate::serialize::{SerializationError, ToBytes};
 use crate::server::error::ServerError;
+use crate::shared::config::Mode;
 use crate::shared::events::connection::ConnectionEvents;
 use crate::shared::message::MessageSend;
 use crate::shared::ping::manager::{PingConfig, PingManager};
 use crate::shared::ping::message::{Ping, Pong};
-use crate::shared::replication::delta::DeltaManager;
+use crate::shared::replication::delta::{DeltaCompressionStats, DeltaManager};
 use crate::shared::replication::network_target::NetworkTarget;
 use crate::shared::replication::receive::ReplicationReceiver;
 use crate::shared::replication::send::ReplicationSender;
@@ -81,6 +86,10 @@ pub struct ConnectionManager {
     pub(crate) received_leafwing_input_messages: HashMap<NetId, Vec<Bytes>>,
     /// Used to transfer raw bytes to a system that can convert the bytes to the actual type
     pub(crate) received_messages: HashMap<NetId, Vec<Bytes>>,
+    /// Received messages with an unknown net id, that will be passed to the [`UnknownMessageFn`](crate::prelude::UnknownMessageFn)
+    pub(crate) unknown_messages: Vec<UnknownMessage>,
+    /// Messages that were sent for a specific tick, and that are held until the client reaches that tick
+    scheduled_messages: Vec<(Tick, Bytes)>,
     pub(crate) writer: Writer,
 
     /// Internal buffer of the messages that we want to send.
@@ -88,6 +97,10 @@ pub struct ConnectionManager {
     /// - in host server mode, we deserialize the bytes and push them to the server's Message Events queue directly
     /// - in non-host server mode, we buffer the bytes to the message manager as usual
     pub(crate) messages_to_send: Vec<(Bytes, ChannelKind)>,
+    wrong_direction_policy: WrongDirectionPolicy,
+    /// True if this is the local client of a HostServer app, which shares the server's tick and
+    /// does not need to sync
+    is_host_server: bool,
 }
 
 // NOTE: useful when we sometimes need to create a temporary fake ConnectionManager
@@ -118,8 +131,12 @@ impl Default for ConnectionManager {
             #[cfg(feature = "leafwing")]
             received_leafwing_input_messages: HashMap::default(),
             received_messages: HashMap::default(),
+            unknown_messages: vec![],
+            scheduled_messages: vec![],
             writer: Writer::with_capacity(0),
             messages_to_send: Vec::default(),
+            wrong_direction_policy: WrongDirectionPolicy::default(),
+            is_host_server: false,
         }
     }
 }
@@ -139,6 +156,16 @@ impl ConnectionManager {
             client_config.packet.nack_rtt_multiple,
             client_config.packet.into(),
         );
+        message_manager.set_mtu(client_config.shared.mtu);
+        if client_config.packet.capture_packets {
+            message_manager.enable_packet_capture();
+        }
+        if client_config.packet.send_sub_tick_fraction {
+            message_manager.enable_sub_tick_fraction();
+        }
+        if let Some(interval) = client_config.packet.ack_packet_interval {
+            message_manager.enable_ack_packets(interval);
+        }
         // get notified when a replication-update message gets acked/nacked
         let entity_updates_sender = &mut message_manager
             .channels
@@ -157,7 +184,10 @@ impl ConnectionManager {
             client_config.replication,
             bandwidth_cap_enabled,
         );
-        let replication_receiver = ReplicationReceiver::new();
+        let replication_receiver = ReplicationReceiver::new()
+            .with_duplicate_spawn_policy(client_config.replication.duplicate_spawn_policy)
+            .with_max_spawns_per_frame(client_config.replication.max_spawns_per_frame)
+            .with_early_updates_policy(client_config.replication.early_updates_policy);
         Self {
             component_registry: component_registry.clone(),
             message_registry: message_registry.clone(),
@@ -171,8 +201,12 @@ impl ConnectionManager {
             #[cfg(feature = "leafwing")]
             received_leafwing_input_messages: HashMap::default(),
             received_messages: HashMap::default(),
+            unknown_messages: vec![],
+            scheduled_messages: vec![],
             writer: Writer::with_capacity(MAX_PACKET_SIZE),
             messages_to_send: Vec::default(),
+            wrong_direction_policy: client_config.packet.wrong_direction_policy,
+            is_host_server: client_config.shared.mode == Mode::HostServer,
         }
     }
 
@@ -182,6 +216,109 @@ impl ConnectionManager {
         self.sync_manager.is_synced()
     }
 
+    /// Returns the statistics (rtt, jitter) that are used to sync the client with the server
+    pub fn sync_stats(&self) -> SyncStatsSnapshot {
+        SyncStatsSnapshot {
+            sample_count: self.ping_manager.sample_count(),
+            rtt: self.ping_manager.rtt(),
+            jitter: self.ping_manager.jitter(),
+        }
+    }
+
+    /// Returns the latest estimate of the round-trip time to the server.
+    ///
+    /// Returns `Duration::ZERO` until the first pong is received from the server (or until the RTT
+    /// is provided externally if pings are disabled).
+    pub fn rtt(&self) -> Duration {
+        if !self.ping_manager.has_stats() {
+            return Duration::ZERO;
+        }
+        self.ping_manager.rtt()
+    }
+
+    /// Returns the latest estimate of the jitter of the connection to the server.
+    ///
+    /// Returns `Duration::ZERO` until the first pong is received from the server (or until the RTT
+    /// is provided externally if pings are disabled).
+    pub fn jitter(&self) -> Duration {
+        if !self.ping_manager.has_stats() {
+            return Duration::ZERO;
+        }
+        self.ping_manager.jitter()
+    }
+
+    /// Returns how many delta-compressed updates of the component `C` were sent to the server
+    /// as incremental diffs vs full diffs from the base value.
+    ///
+    /// See [`DeltaCompressionStats`] for how to use it to detect ineffective delta compression.
+    pub fn delta_compression_stats<C: Component>(&self) -> DeltaCompressionStats {
+        self.replication_sender
+            .delta_compression_stats
+            .get(&ComponentKind::of::<C>())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Provide the RTT and jitter of the connection to the server from an external source.
+    ///
+    /// This is meant to be used when pings are disabled with [`PingConfig::enabled`](crate::prelude::PingConfig::enabled),
+    /// and the transport does not report the RTT itself. The values are used to sync the client with the server
+    /// until they are updated again.
+    pub fn set_rtt(&mut self, rtt: Duration, jitter: Duration) {
+        self.ping_manager.set_external_stats(rtt, jitter);
+    }
+
+    /// Discard the statistics used to sync the client with the server, for example after a known
+    /// change of network conditions.
+    ///
+    /// The statistics will be re-computed from the next pongs received from the server.
+    pub fn reset_sync_stats(&mut self) {
+        self.ping_manager.reset_stats();
+    }
+
+    /// Returns the latest tick at which the server acknowledged the replication group of an entity
+    /// that the client replicates to the server.
+    ///
+    /// Returns `None` if the entity is not replicated, or if nothing was acknowledged yet.
+    pub fn replication_group_acked_tick(&self, entity: Entity) -> Option<Tick> {
+        self.replication_sender
+            .entity_group_id(entity)
+            .and_then(|group_id| self.replication_sender.group_acked_tick(group_id))
+    }
+
+    /// Number of ticks of input delay that the client currently applies.
+    ///
+    /// This is computed from the [`PredictionConfig`] and the current RTT, unless the server
+    /// set the input delay of the client with an [`InputDelayCommand`](crate::shared::input::InputDelayCommand).
+    pub fn input_delay_ticks(&self, tick_duration: Duration) -> u16 {
+        self.sync_manager
+            .input_delay_ticks(self.ping_manager.rtt(), tick_duration)
+    }
+
+    /// The raw bytes of the last packet sent to the server.
+    ///
+    /// Only available if [`PacketConfig::capture_packets`](crate::client::config::PacketConfig::capture_packets) is enabled.
+    pub fn last_sent_packet(&self) -> Option<&[u8]> {
+        self.message_manager.last_sent_packet()
+    }
+
+    /// The raw bytes of the last packet received from the server.
+    ///
+    /// Only available if [`PacketConfig::capture_packets`](crate::client::config::PacketConfig::capture_packets) is enabled.
+    pub fn last_received_packet(&self) -> Option<&[u8]> {
+        self.message_manager.last_received_packet()
+    }
+
+    /// The [`ChannelStats`] (number of messages and bytes sent/received) of a given channel
+    pub fn channel_stats<C: Channel>(&self) -> Option<&ChannelStats> {
+        self.message_manager.channel_stats::<C>()
+    }
+
+    /// Iterate through the [`ChannelStats`] of all the channels
+    pub fn all_channel_stats(&self) -> impl Iterator<Item = (&ChannelKind, &ChannelStats)> {
+        self.message_manager.all_channel_stats()
+    }
+
     /// Returns true if we received a new server packet on this frame
     pub(crate) fn received_new_server_tick(&self) -> bool {
         self.sync_manager.duration_since_latest_received_server_tick == Duration::default()
@@ -242,6 +379,22 @@ impl ConnectionManager {
         self.send_message_to_target::<C, M>(message, NetworkTarget::None)
     }
 
+    /// Send a [`Message`] to the server using a specific [`Channel`], only if the client is synced
+    /// with the server.
+    ///
+    /// Returns [`ClientError::NotSynced`] if the client is not synced yet; this is useful for messages that
+    /// are correlated with the client tick, which is meaningless before the sync is complete.
+    /// The local client of a HostServer app shares the server's tick, so it is always considered synced.
+    pub fn send_message_if_synced<C: Channel, M: Message>(
+        &mut self,
+        message: &mut M,
+    ) -> Result<(), ClientError> {
+        if !self.is_host_server && !self.is_synced() {
+            return Err(ClientError::NotSynced);
+        }
+        self.send_message::<C, M>(message)
+    }
+
     /// Send a [`Message`] to the server using a specific [`Channel`]
     ///
     /// The message will be sent to the server and re-broadcasted to all clients that match the [`NetworkTarget`]
@@ -250,19 +403,53 @@ impl ConnectionManager {
         message: &mut M,
         target: NetworkTarget,
     ) -> Result<(), ClientError> {
-        self.erased_send_message_to_target(message, ChannelKind::of::<C>(), target)
+        self.erased_send_message_to_target(message, ChannelKind::of::<C>(), target, None)
+    }
+
+    /// Send a [`Message`] to the server using a specific [`Channel`]
+    ///
+    /// The server will hold the message until its own tick reaches `tick`, and only then emit the
+    /// [`MessageEvent`](crate::server::events::MessageEvent) and rebroadcast the message to the
+    /// clients that match the [`NetworkTarget`]. If the message arrives after `tick`, it is processed immediately.
+    pub fn send_message_at_tick<C: Channel, M: Message>(
+        &mut self,
+        message: &mut M,
+        target: NetworkTarget,
+        tick: Tick,
+    ) -> Result<(), ClientError> {
+        self.erased_send_message_to_target(message, ChannelKind::of::<C>(), target, Some(tick))
     }
 
     /// Serialize a message and buffer it internally so that it can be sent later
+    ///
+    /// If a `tick` is provided, the server will only process the message once it reaches that tick.
     fn erased_send_message_to_target<M: Message>(
         &mut self,
         message: &M,
         channel_kind: ChannelKind,
         target: NetworkTarget,
+        tick: Option<Tick>,
     ) -> Result<(), ClientError> {
+        if let Err(e) = self
+            .message_manager
+            .channel_registry
+            .check_send_direction(&channel_kind, false)
+        {
+            match self.wrong_direction_policy {
+                WrongDirectionPolicy::Error => return Err(e.into()),
+                WrongDirectionPolicy::Drop => {
+                    error!("Dropping message: {e}");
+                    return Ok(());
+                }
+            }
+        }
         // write the target first
         // NOTE: this is ok to do because most of the time (without rebroadcast, this just adds 1 byte)
         target.to_bytes(&mut self.writer)?;
+        if let Some(tick) = tick {
+            SCHEDULED_MESSAGE_NET_ID.to_bytes(&mut self.writer)?;
+            tick.to_bytes(&mut self.writer)?;
+        }
         // then write the message
         self.message_registry.serialize(
             message,
@@ -334,11 +521,15 @@ impl ConnectionManager {
     /// Send packets that are ready to be sent.
     /// In non-host-server mode:
     /// - go through messages_to_send, buffer them to the message manager and then send packets that are ready
+    ///
+    /// `split_reliable` should be true if the transport has its own reliability layer
+    /// (see [`NetClient::supports_reliable_send`](crate::connection::client::NetClient::supports_reliable_send))
     pub(crate) fn send_packets(
         &mut self,
         time_manager: &TimeManager,
         tick_manager: &TickManager,
-    ) -> Result<Vec<Payload>, ClientError> {
+        split_reliable: bool,
+    ) -> Result<Vec<(Payload, bool)>, ClientError> {
         // TODO: issues here: we would like to send the ping/pong messages immediately, otherwise the recorded current time is incorrect
         //   - can give infinity priority to this channel?
         //   - can write directly to io otherwise?
@@ -372,7 +563,10 @@ impl ConnectionManager {
             })?;
 
         // get the payloads from the message manager
-        let payloads = self.message_manager.send_packets(tick_manager.tick());
+        self.message_manager.set_overstep(time_manager.overstep());
+        let payloads = self
+            .message_manager
+            .send_packets_with_reliability(tick_manager.tick(), split_reliable);
 
         // update the replication sender about which messages were actually sent, and accumulate priority
         self.replication_sender.recv_send_notification();
@@ -392,14 +586,13 @@ impl ConnectionManager {
             .channels
             .iter_mut()
             .try_for_each(|(channel_kind, channel)| {
+                let channel_name = self
+                    .message_manager
+                    .channel_registry
+                    .name(channel_kind)
+                    .unwrap_or("unknown");
+                let _span_channel = trace_span!("read_messages", channel = channel_name).entered();
                 while let Some((tick, single_data)) = channel.receiver.read_message() {
-                    // let channel_name = self
-                    //     .message_manager
-                    //     .channel_registry
-                    //     .name(&channel_kind)
-                    //     .unwrap_or("unknown");
-                    // let _span_channel = trace_span!("channel", channel = channel_name).entered();
-
                     trace!(?channel_kind, ?tick, ?single_data, "Received message");
                     let mut reader = Reader::from(single_data);
                     if *channel_kind == ChannelKind::of::<PingChannel>() {
@@ -431,6 +624,9 @@ impl ConnectionManager {
                     } else if *channel_kind == ChannelKind::of::<EntityActionsChannel>() {
                         let actions = EntityActionsMessage::from_bytes(&mut reader)?;
                         self.replication_receiver.recv_actions(actions, tick);
+                    } else if *channel_kind == ChannelKind::of::<SnapshotChannel>() {
+                        self.replication_receiver
+                            .recv_snapshot(&reader.consume(), tick)?;
                     } else if *channel_kind == ChannelKind::of::<EntityUpdatesChannel>() {
                         let updates = EntityUpdatesMessage::from_bytes(&mut reader)?;
                         self.replication_receiver.recv_updates(updates, tick);
@@ -438,19 +634,31 @@ impl ConnectionManager {
                         // TODO: this code is copy-pasted from self.receive_message because of borrow checker limitations
                         // identify the type of message
                         let net_id = NetId::from_bytes(&mut reader)?;
+                        if net_id == SCHEDULED_MESSAGE_NET_ID {
+                            let tick = Tick::from_bytes(&mut reader)?;
+                            let remaining = reader.remaining();
+                            self.scheduled_messages
+                                .push((tick, reader.split_len(remaining)));
+                            continue;
+                        }
                         let single_data = reader.consume();
                         match self.message_registry.message_type(net_id) {
+                            None => self.unknown_messages.push(UnknownMessage {
+                                sender: None,
+                                net_id,
+                                bytes: single_data,
+                            }),
                             #[cfg(feature = "leafwing")]
-                            MessageType::LeafwingInput => {
+                            Some(MessageType::LeafwingInput) => {
                                 self.received_leafwing_input_messages
                                     .entry(net_id)
                                     .or_default()
                                     .push(single_data);
                             }
-                            MessageType::NativeInput => {
+                            Some(MessageType::NativeInput) => {
                                 todo!()
                             }
-                            MessageType::Normal => {
+                            Some(MessageType::Normal) => {
                                 self.received_messages
                                     .entry(net_id)
                                     .or_default()
@@ -461,6 +669,7 @@ impl ConnectionManager {
                 }
                 Ok::<(), SerializationError>(())
             })?;
+        self.release_scheduled_messages(tick_manager.tick())?;
 
         if self.sync_manager.is_synced() {
             // Check if we have any replication messages we can apply to the World (and emit events)
@@ -479,19 +688,31 @@ impl ConnectionManager {
     pub(crate) fn receive_message(&mut self, mut reader: Reader) -> Result<(), SerializationError> {
         // identify the type of message
         let net_id = NetId::from_bytes(&mut reader)?;
+        if net_id == SCHEDULED_MESSAGE_NET_ID {
+            let tick = Tick::from_bytes(&mut reader)?;
+            let remaining = reader.remaining();
+            self.scheduled_messages
+                .push((tick, reader.split_len(remaining)));
+            return Ok(());
+        }
         let single_data = reader.consume();
         match self.message_registry.message_type(net_id) {
+            None => self.unknown_messages.push(UnknownMessage {
+                sender: None,
+                net_id,
+                bytes: single_data,
+            }),
             #[cfg(feature = "leafwing")]
-            MessageType::LeafwingInput => {
+            Some(MessageType::LeafwingInput) => {
                 self.received_leafwing_input_messages
                     .entry(net_id)
                     .or_default()
                     .push(single_data);
             }
-            MessageType::NativeInput => {
+            Some(MessageType::NativeInput) => {
                 todo!()
             }
-            MessageType::Normal => {
+            Some(MessageType::Normal) => {
                 self.received_messages
                     .entry(net_id)
                     .or_default()
@@ -501,6 +722,21 @@ impl ConnectionManager {
         Ok(())
     }
 
+    /// Read the scheduled messages whose tick has been reached, so that they are processed
+    /// like the other received messages
+    pub(crate) fn release_scheduled_messages(
+        &mut self,
+        tick: Tick,
+    ) -> Result<(), SerializationError> {
+        let (ready, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.scheduled_messages)
+            .into_iter()
+            .partition(|(message_tick, _)| *message_tick <= tick);
+        self.scheduled_messages = pending;
+        ready
+            .into_iter()
+            .try_for_each(|(_, message)| self.receive_message(Reader::from(message)))
+    }
+
     pub(crate) fn recv_packet(
         &mut self,
         packet: RecvPayload,
@@ -516,7 +752,23 @@ impl ConnectionManager {
             .map_or(true, |server_tick| tick >= server_tick)
         {
             trace!("new last recv server tick: {:?}", tick);
+            if !self.ping_manager.is_enabled() {
+                // without pongs, we track the tick generation from the server ticks directly.
+                // The starting generation is sent by the server when we connect (see `ServerWideTick`)
+                if self
+                    .sync_manager
+                    .latest_received_server_tick
+                    .is_some_and(|server_tick| tick.0 < server_tick.0)
+                {
+                    self.sync_manager.server_pong_generation += 1;
+                }
+                self.sync_manager.server_pong_tick = tick;
+            }
             self.sync_manager.latest_received_server_tick = Some(tick);
+            self.sync_manager.latest_received_server_sub_tick = self
+                .message_manager
+                .last_received_sub_tick_fraction()
+                .unwrap_or_default();
             // TODO: add 'received_new_server_tick' ?
             // we probably actually physically received the packet some time between our last `receive` and now.
             // Let's add delta / 2 as a compromise
@@ -551,7 +803,7 @@ impl MessageSend for ConnectionManager {
         channel_kind: ChannelKind,
         target: NetworkTarget,
     ) -> Result<(), ClientError> {
-        self.erased_send_message_to_target(message, channel_kind, target)
+        self.erased_send_message_to_target(message, channel_kind, target, None)
     }
 }
 
@@ -1,14 +1,19 @@
 use crate::client::connection::ConnectionManager;
 use crate::client::prediction::diagnostics::PredictionDiagnosticsPlugin;
+use crate::client::sync::SyncSet;
 use bevy::app::{App, Plugin, PostUpdate};
 use bevy::diagnostic::Diagnostics;
-use bevy::prelude::{not, Condition, IntoSystemConfigs, Real, Res, ResMut, Time};
+use bevy::prelude::{
+    not, Condition, IntoSystemConfigs, Real, Reflect, ReflectResource, Res, ResMut, Resource, Time,
+};
 use bevy::time::common_conditions::on_timer;
-use bevy::utils::Duration;
+use bevy::utils::{Duration, HashMap};
 
 use crate::connection::client::{ClientConnection, NetClient};
-use crate::prelude::{client::is_disconnected, is_host_server};
+use crate::prelude::{client::is_disconnected, is_host_server, Tick, TickManager};
 use crate::shared::ping::diagnostics::PingDiagnosticsPlugin;
+use crate::shared::ping::manager::FinalStats;
+use crate::shared::replication::components::ReplicationGroupId;
 use crate::transport::io::IoDiagnosticsPlugin;
 
 // TODO: ideally make this a plugin group? but nested plugin groups are not supported
@@ -35,6 +40,71 @@ fn io_diagnostics_system(
     }
 }
 
+/// Snapshot of the client's connection and replication state, refreshed every frame.
+///
+/// This resource can be inspected via reflection (for example with `bevy-inspector-egui`)
+/// to build a network debug panel.
+#[derive(Resource, Debug, Default, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct NetworkDebugState {
+    /// Whether the client's tick/time is synced with the server
+    pub synced: bool,
+    /// Latest estimates of RTT and jitter
+    pub ping: FinalStats,
+    /// Number of pings sent to the server
+    pub pings_sent: u32,
+    /// Number of pongs received from the server
+    pub pongs_received: u32,
+    /// Current client (prediction) tick
+    pub tick: Tick,
+    /// Current interpolation tick
+    pub interpolation_tick: Tick,
+    /// Tick of the server that we last received in any packet from the server
+    pub latest_received_server_tick: Option<Tick>,
+    /// State of each replication group received from the server
+    pub replication_groups: HashMap<ReplicationGroupId, ReplicationGroupDebugState>,
+}
+
+/// Debug information about a [`ReplicationGroup`](crate::prelude::ReplicationGroup) received from the server
+#[derive(Debug, Default, Clone, PartialEq, Reflect)]
+pub struct ReplicationGroupDebugState {
+    /// Number of remote entities in the group
+    pub num_entities: usize,
+    /// Remote tick of the latest update/action that was applied to the group
+    pub latest_tick: Option<Tick>,
+}
+
+fn network_debug_state_system(
+    connection: Res<ConnectionManager>,
+    tick_manager: Res<TickManager>,
+    mut debug_state: ResMut<NetworkDebugState>,
+) {
+    let debug_state = debug_state.as_mut();
+    debug_state.synced = connection.sync_manager.is_synced();
+    debug_state.ping = connection.ping_manager.final_stats;
+    debug_state.pings_sent = connection.ping_manager.pings_sent;
+    debug_state.pongs_received = connection.ping_manager.pongs_recv;
+    debug_state.tick = tick_manager.tick();
+    debug_state.interpolation_tick = connection
+        .sync_manager
+        .interpolation_tick(tick_manager.as_ref());
+    debug_state.latest_received_server_tick = connection.sync_manager.latest_received_server_tick;
+    debug_state.replication_groups = connection
+        .replication_receiver
+        .group_channels
+        .iter()
+        .map(|(group_id, channel)| {
+            (
+                *group_id,
+                ReplicationGroupDebugState {
+                    num_entities: channel.remote_entities.len(),
+                    latest_tick: channel.latest_tick,
+                },
+            )
+        })
+        .collect();
+}
+
 fn ping_diagnostics_system(connection: Res<ConnectionManager>, diagnostics: Diagnostics) {
     PingDiagnosticsPlugin::add_measurements(&connection.ping_manager, diagnostics);
 }
@@ -54,6 +124,15 @@ impl Plugin for ClientDiagnosticsPlugin {
         }
         app.add_plugins(PredictionDiagnosticsPlugin::default());
 
+        app.register_type::<NetworkDebugState>();
+        app.init_resource::<NetworkDebugState>();
+        app.add_systems(
+            PostUpdate,
+            network_debug_state_system
+                .after(SyncSet)
+                .run_if(not(is_disconnected)),
+        );
+
         {
             app.add_plugins(IoDiagnosticsPlugin);
             app.add_systems(
@@ -66,3 +145,64 @@ impl Plugin for ClientDiagnosticsPlugin {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::AppTypeRegistry;
+    use bevy::reflect::ReflectRef;
+
+    use crate::prelude::server::Replicate;
+    use crate::tests::protocol::*;
+    use crate::tests::stepper::BevyStepper;
+
+    use super::*;
+
+    #[test]
+    fn test_network_debug_state() {
+        let mut stepper = BevyStepper::default();
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((ComponentSyncModeFull(0.0), Replicate::default()))
+            .id();
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+
+        let debug_state = stepper.client_app.world().resource::<NetworkDebugState>();
+        assert!(debug_state.synced);
+        assert!(debug_state.pongs_received > 0);
+        assert_eq!(debug_state.tick, stepper.client_tick());
+        assert!(debug_state.latest_received_server_tick.is_some());
+        assert_eq!(
+            debug_state
+                .replication_groups
+                .get(&ReplicationGroupId(server_entity.to_bits()))
+                .unwrap()
+                .num_entities,
+            1
+        );
+
+        // the resource can be accessed via reflection
+        let type_registry = stepper.client_app.world().resource::<AppTypeRegistry>();
+        let type_registry = type_registry.read();
+        let reflect_resource = type_registry
+            .get_type_data::<ReflectResource>(std::any::TypeId::of::<NetworkDebugState>())
+            .unwrap();
+        let reflected = reflect_resource
+            .reflect(stepper.client_app.world())
+            .unwrap();
+        let ReflectRef::Struct(reflected) = reflected.reflect_ref() else {
+            panic!("NetworkDebugState should be reflected as a struct");
+        };
+        assert_eq!(
+            reflected.field("synced").unwrap().downcast_ref::<bool>(),
+            Some(&true)
+        );
+        assert!(reflected
+            .field("ping")
+            .unwrap()
+            .downcast_ref::<FinalStats>()
+            .is_some());
+    }
+}
@@ -16,4 +16,6 @@ pub enum ClientError {
     MessageProtocolError(#[from] crate::protocol::message::MessageError),
     #[error(transparent)]
     ComponentProtocolError(#[from] crate::protocol::component::ComponentError),
+    #[error("the client is not synced with the server yet")]
+    NotSynced,
 }
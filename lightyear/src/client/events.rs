@@ -14,13 +14,16 @@
 //! ```
 
 use bevy::app::{App, Plugin, PreUpdate};
-use bevy::prelude::{Component, Event, IntoSystemConfigs};
+use bevy::prelude::{
+    Commands, Component, Entity, Event, EventWriter, Events, IntoSystemConfigs, ResMut,
+};
 
 use crate::client::connection::ConnectionManager;
 use crate::connection::client::DisconnectReason;
 use crate::prelude::ClientId;
 use crate::shared::events::plugin::EventsPlugin;
 use crate::shared::events::systems::push_component_events;
+use crate::shared::notification::ServerNotification;
 use crate::shared::sets::{ClientMarker, InternalMainSet};
 
 /// Plugin that handles generating bevy [`Events`](Event) related to networking and replication
@@ -33,8 +36,42 @@ impl Plugin for ClientEventsPlugin {
             // EVENTS
             .add_event::<ConnectEvent>()
             .add_event::<DisconnectEvent>()
+            .add_event::<SyncEvent>()
+            .add_event::<GroupAckEvent>()
+            .add_event::<ServerNotification>()
             // PLUGIN
-            .add_plugins(EventsPlugin::<ConnectionManager>::default());
+            .add_plugins(EventsPlugin::<ConnectionManager>::default())
+            // SYSTEMS
+            .add_systems(
+                PreUpdate,
+                (
+                    emit_group_ack_events.in_set(InternalMainSet::<ClientMarker>::EmitEvents),
+                    emit_server_notifications.after(InternalMainSet::<ClientMarker>::EmitEvents),
+                ),
+            );
+    }
+}
+
+/// Emit a [`ServerNotification`] event for every notification broadcast by the server
+fn emit_server_notifications(
+    mut messages: ResMut<Events<MessageEvent<ServerNotification>>>,
+    mut notifications: EventWriter<ServerNotification>,
+) {
+    for message in messages.drain() {
+        notifications.send(message.message);
+    }
+}
+
+/// Emit a [`GroupAckEvent`] for every replication update message that was acked by the server
+fn emit_group_ack_events(
+    mut commands: Commands,
+    mut group_ack_events: EventWriter<GroupAckEvent>,
+    mut connection_manager: ResMut<ConnectionManager>,
+) {
+    for (group_id, tick) in connection_manager.replication_sender.acked_groups.drain(..) {
+        let event = GroupAckEvent::new(group_id, tick, ());
+        group_ack_events.send(event);
+        commands.trigger(event);
     }
 }
 
@@ -71,8 +108,34 @@ pub struct DisconnectEvent {
     pub reason: Option<DisconnectReason>,
 }
 
+/// Bevy [`Event`] emitted on the client on the frame where the client becomes synced with the server
+/// (i.e. the client tick is synced with the server tick).
+///
+/// It is emitted once per connection, so it is emitted again after a reconnection.
+/// The local client of a HostServer app shares the server's tick, so it emits it as soon as it connects.
+/// You can use the [`just_synced`](crate::client::run_conditions::just_synced) run condition to run
+/// a system once when the client becomes synced.
+#[derive(Event, Debug, Default, Clone, Copy, PartialEq)]
+pub struct SyncEvent;
+
+/// Bevy [`Event`] emitted on the client when a replication update changes the value of a component.
+///
+/// This is useful for components that represent a state machine, to react to the transitions
+/// instead of only observing the latest state.
+/// It is only emitted for components registered with
+/// [`add_state_transition_events`](crate::prelude::ComponentRegistration::add_state_transition_events).
+#[derive(Event, Debug, Clone, PartialEq)]
+pub struct ComponentStateTransition<C: Component> {
+    pub entity: Entity,
+    pub old: C,
+    pub new: C,
+}
+
 /// Bevy [`Event`] emitted on the client to indicate the user input for the tick
 pub type InputEvent<I> = crate::shared::events::components::InputEvent<I, ()>;
+/// Bevy [`Event`] emitted on the client to indicate the input of a remote client for the tick
+/// (see [`ServerInputConfig::rebroadcast_inputs`](crate::prelude::server::ServerInputConfig::rebroadcast_inputs))
+pub type RemoteInputEvent<I> = crate::shared::events::components::InputEvent<I, ClientId>;
 /// Bevy [`Event`] emitted on the client when a EntitySpawn replication message is received
 pub type EntitySpawnEvent = crate::shared::events::components::EntitySpawnEvent<()>;
 /// Bevy [`Event`] emitted on the client when a EntityDespawn replication message is received
@@ -83,5 +146,7 @@ pub type ComponentUpdateEvent<C> = crate::shared::events::components::ComponentU
 pub type ComponentInsertEvent<C> = crate::shared::events::components::ComponentInsertEvent<C, ()>;
 /// Bevy [`Event`] emitted on the client when a ComponentRemove replication message is received
 pub type ComponentRemoveEvent<C> = crate::shared::events::components::ComponentRemoveEvent<C, ()>;
+/// Bevy [`Event`] emitted on the client when the server acked a replication update message
+pub type GroupAckEvent = crate::shared::events::components::GroupAckEvent<()>;
 /// Bevy [`Event`] emitted on the client when a (non-replication) message is received
 pub type MessageEvent<M> = crate::shared::events::components::MessageEvent<M, ()>;
@@ -31,7 +31,9 @@
 //! The networking of inputs is completely handled for you. You just need to add the `LeafwingInputPlugin` to your app.
 //! Make sure that all your systems that depend on user inputs are added to the [`FixedUpdate`] [`Schedule`].
 //!
-//! Currently, global inputs (that are stored in a [`Resource`] instead of being attached to a specific [`Entity`] are not supported)
+//! Global inputs (that are stored in a [`Resource`] instead of being attached to a specific [`Entity`]) are also supported:
+//! insert the `InputMap<A>` and `ActionState<A>` resources on the client. On the server, the global inputs of each client
+//! are available in the [`GlobalActions`](crate::server::input::leafwing::GlobalActions) resource.
 //!
 //! There are some edge-cases to be careful of:
 //! - the `leafwing_input_manager` crate handles inputs every frame, but `lightyear` needs to store and send inputs for each tick.
@@ -39,6 +41,9 @@
 //!   For instance, let's say you have a system in the `FixedUpdate` schedule that reacts on a button press when the button was `JustPressed`.
 //!   If we have 2 frames with no FixedUpdate in between (because the framerate is high compared to the tickrate), then on the second frame
 //!   the button won't be `JustPressed` anymore (it will simply be `Pressed`) so your system might not react correctly to it.
+//!   To avoid this, you can react to the inputs in the `Update` schedule by reading the [`InputBuffer`] with an
+//!   [`InputBufferReader`](crate::inputs::leafwing::input_buffer::InputBufferReader): every buffered tick is returned exactly once,
+//!   along with the tick that the input is associated with.
 //!
 use std::fmt::Debug;
 use std::marker::PhantomData;
@@ -51,6 +56,7 @@ use crate::channel::builder::InputChannel;
 use crate::client::components::Confirmed;
 use crate::client::config::ClientConfig;
 use crate::client::connection::ConnectionManager;
+use crate::client::input::{check_send_interval, message_ticks};
 use crate::client::prediction::plugin::{is_in_rollback, PredictionSet};
 use crate::client::prediction::resource::PredictionManager;
 use crate::client::prediction::rollback::Rollback;
@@ -67,17 +73,20 @@ use crate::prelude::{
 use crate::protocol::message::MessageKind;
 use crate::serialize::reader::Reader;
 use crate::shared::replication::components::PrePredicted;
+use crate::shared::replication::error::{ReplicationErrors, ReplicationSkipReason};
 use crate::shared::sets::{ClientMarker, InternalMainSet};
 use crate::shared::tick_manager::TickEvent;
 
 // TODO: the resource should have a generic param, but not the user-facing config struct
 #[derive(Debug, Copy, Clone, Resource)]
 pub struct LeafwingInputConfig<A> {
-    // TODO: right now the input-delay causes the client timeline to be more in the past than it should be
-    //  I'm not sure if we can have different input_delay_ticks per ActionType
-    // /// The amount of ticks that the player's inputs will be delayed by.
-    // /// This can be useful to mitigate the amount of client-prediction
-    // pub input_delay_ticks: u16,
+    /// The amount of ticks that the inputs of this action type will be delayed by.
+    ///
+    /// This can be used to have different input delays for different action types: for example no delay
+    /// for the movement inputs, but a couple ticks of delay for the camera inputs to hide prediction artifacts.
+    /// If `None`, the input delay computed from the [`PredictionConfig`](crate::client::prediction::plugin::PredictionConfig)
+    /// is used.
+    pub input_delay_ticks: Option<u16>,
     /// How many consecutive packets losses do we want to handle?
     /// This is used to compute the redundancy of the input messages.
     /// For instance, a value of 3 means that each input packet will contain the inputs for all the ticks
@@ -85,6 +94,39 @@ pub struct LeafwingInputConfig<A> {
     // TODO: this seems unused now
     pub packet_redundancy: u16,
 
+    /// If true, the axis and axis-pair values of the inputs are quantized to 8 bits per axis
+    /// when they are sent to the server, which reduces the bandwidth used by analog controls.
+    ///
+    /// The values are clamped to the range [-1.0, 1.0] and restored with a precision of `1.0 / 254.0`.
+    /// Changes that are smaller than a quantization step (`1.0 / 127.0`) are not sent, which acts
+    /// as a small dead-zone. Note that the client still predicts with the full-precision values.
+    pub quantize_axis: bool,
+    /// Maximum number of ticks of inputs that are kept in the [`InputBuffer`]s.
+    ///
+    /// The inputs are usually removed once they are older than the interpolation tick, but this is a safeguard
+    /// to prevent the buffers from growing indefinitely if the client is not synced or the interpolation tick stalls.
+    pub max_buffer_ticks: u16,
+    /// Minimum number of ticks of inputs that an input message covers (before redundancy).
+    /// Values below 1 are treated as 1.
+    ///
+    /// This is the equivalent of [`InputConfig::min_message_ticks`](crate::client::input::native::InputConfig::min_message_ticks)
+    /// for the leafwing inputs.
+    pub min_message_ticks: u16,
+    /// Maximum number of ticks of inputs that an input message covers (before redundancy).
+    ///
+    /// This is the equivalent of [`InputConfig::max_message_ticks`](crate::client::input::native::InputConfig::max_message_ticks)
+    /// for the leafwing inputs.
+    pub max_message_ticks: u16,
+    /// If true, the server replicates the authoritative [`ActionState`] of the entities to the clients
+    /// as a regular component, so that spectators (who don't predict) can drive animations or effects from the inputs.
+    ///
+    /// The clients that control the entity (see [`ControlledBy`](crate::prelude::server::ControlledBy)) don't receive
+    /// the [`ActionState`], since they already have their own inputs.
+    /// The [`ActionState`] is updated every tick, so this uses bandwidth for every entity that has one.
+    ///
+    /// This must be set to the same value on the client and the server.
+    pub replicate_action_state: bool,
+
     // TODO: add an option where we send all diffs vs send only just-pressed diffs
     pub(crate) _marker: PhantomData<A>,
 }
@@ -111,13 +153,42 @@ impl<A: LeafwingUserAction> Default for MessageBuffer<A> {
 impl<A> Default for LeafwingInputConfig<A> {
     fn default() -> Self {
         LeafwingInputConfig {
-            // input_delay_ticks: 0,
+            input_delay_ticks: None,
             packet_redundancy: 4,
+            quantize_axis: false,
+            max_buffer_ticks: 256,
+            min_message_ticks: 1,
+            max_message_ticks: 64,
+            replicate_action_state: false,
             _marker: PhantomData,
         }
     }
 }
 
+impl<A> LeafwingInputConfig<A> {
+    pub fn with_input_delay_ticks(mut self, input_delay_ticks: u16) -> Self {
+        self.input_delay_ticks = Some(input_delay_ticks);
+        self
+    }
+
+    pub fn with_max_buffer_ticks(mut self, max_buffer_ticks: u16) -> Self {
+        self.max_buffer_ticks = max_buffer_ticks;
+        self
+    }
+
+    pub fn with_replicate_action_state(mut self, replicate_action_state: bool) -> Self {
+        self.replicate_action_state = replicate_action_state;
+        self
+    }
+
+    /// Number of ticks that the inputs of this action type are delayed by, considering the current RTT
+    /// (or the input delay set by the server)
+    fn delay_ticks(&self, config: &ClientConfig, connection: &ConnectionManager) -> u16 {
+        self.input_delay_ticks
+            .unwrap_or_else(|| connection.input_delay_ticks(config.shared.tick.tick_duration))
+    }
+}
+
 /// Adds a plugin to handle inputs using the LeafwingInputManager
 pub struct LeafwingInputPlugin<A> {
     config: LeafwingInputConfig<A>,
@@ -135,11 +206,23 @@ impl<A> Default for LeafwingInputPlugin<A> {
     }
 }
 
-/// Returns true if there is input delay present
-fn is_input_delay(config: Res<ClientConfig>) -> bool {
-    config.prediction.minimum_input_delay_ticks > 0
-        || config.prediction.maximum_input_delay_before_prediction > 0
-        || config.prediction.maximum_predicted_ticks < 30
+/// Returns true if there is input delay present for the action type
+fn is_input_delay<A: LeafwingUserAction>(
+    config: Res<ClientConfig>,
+    input_config: Res<LeafwingInputConfig<A>>,
+    connection: Res<ConnectionManager>,
+) -> bool {
+    match input_config
+        .input_delay_ticks
+        .or(connection.sync_manager.server_input_delay_ticks)
+    {
+        Some(input_delay_ticks) => input_delay_ticks > 0,
+        None => {
+            config.prediction.minimum_input_delay_ticks > 0
+                || config.prediction.maximum_input_delay_before_prediction > 0
+                || config.prediction.maximum_predicted_ticks < 30
+        }
+    }
 }
 
 impl<A: LeafwingUserAction> Plugin for LeafwingInputPlugin<A>
@@ -198,9 +281,13 @@ impl<A: LeafwingUserAction> Plugin for LeafwingInputPlugin<A>
             (
                 SyncSet,
                 // run after SyncSet to make sure that the TickEvents are handled
-                (InputSystemSet::SendInputMessage, InputSystemSet::CleanUp)
+                (
+                    InputSystemSet::SendInputMessage.run_if(is_synced),
+                    // the buffers must be cleaned even if we are not synced, so that they don't grow indefinitely
+                    InputSystemSet::CleanUp,
+                )
                     .chain()
-                    .run_if(should_run.clone().and_then(is_synced)),
+                    .run_if(should_run.clone()),
                 InternalMainSet::<ClientMarker>::Send,
             )
                 .chain(),
@@ -234,7 +321,7 @@ impl<A: LeafwingUserAction> Plugin for LeafwingInputPlugin<A>
                     buffer_action_state::<A>,
                     // If InputDelay is enabled, we get the ActionState for the current tick
                     // from the InputBuffer (which was added to the InputBuffer input_delay ticks ago)
-                    get_non_rollback_action_state::<A>.run_if(is_input_delay),
+                    get_non_rollback_action_state::<A>.run_if(is_input_delay::<A>),
                 )
                     .chain()
                     .run_if(not(is_in_rollback)),
@@ -251,7 +338,7 @@ impl<A: LeafwingUserAction> Plugin for LeafwingInputPlugin<A>
             // - next frame's input-map (in PreUpdate) to act on the delayed tick, so re-fetch the delayed action-state
             (
                 get_delayed_action_state::<A>.run_if(
-                    is_input_delay
+                    is_input_delay::<A>
                         .and_then(should_run.clone())
                         .and_then(not(is_in_rollback)),
                 ),
@@ -353,19 +440,17 @@ fn add_action_state_buffer<A: LeafwingUserAction>(
 /// (e.g. the delayed action state) because all inputs (i.e. diffs) are applied to the delayed action-state.
 fn get_delayed_action_state<A: LeafwingUserAction>(
     config: Res<ClientConfig>,
+    input_config: Res<LeafwingInputConfig<A>>,
     tick_manager: Res<TickManager>,
     connection_manager: Res<ConnectionManager>,
-    // global_input_buffer: Res<InputBuffer<A>>,
-    // global_action_state: Option<ResMut<ActionState<A>>>,
+    global_input_buffer: Res<InputBuffer<A>>,
+    global_action_state: Option<ResMut<ActionState<A>>>,
     mut action_state_query: Query<
         (Entity, &mut ActionState<A>, &InputBuffer<A>),
         With<InputMap<A>>,
     >,
 ) {
-    let input_delay_ticks = config.prediction.input_delay_ticks(
-        connection_manager.ping_manager.rtt(),
-        config.shared.tick.tick_duration,
-    ) as i16;
+    let input_delay_ticks = input_config.delay_ticks(&config, &connection_manager) as i16;
     let delayed_tick = tick_manager.tick() + input_delay_ticks;
     for (entity, mut action_state, input_buffer) in action_state_query.iter_mut() {
         // TODO: lots of clone + is complicated. Shouldn't we just have a DelayedActionState component + resource?
@@ -381,9 +466,11 @@ fn get_delayed_action_state<A: LeafwingUserAction>(
             );
         }
     }
-    // if let Some(mut action_state) = global_action_state {
-    //     *action_state = global_input_buffer.get_last().unwrap().clone();
-    // }
+    if let Some(mut action_state) = global_action_state {
+        if let Some(delayed_action_state) = global_input_buffer.get(delayed_tick) {
+            *action_state = delayed_action_state.clone();
+        }
+    }
 }
 
 /// Write the value of the ActionState in the InputBuffer.
@@ -395,10 +482,11 @@ fn get_delayed_action_state<A: LeafwingUserAction>(
 /// We do not need to buffer inputs during rollback, as they have already been buffered
 fn buffer_action_state<A: LeafwingUserAction>(
     config: Res<ClientConfig>,
+    input_config: Res<LeafwingInputConfig<A>>,
     connection_manager: Res<ConnectionManager>,
     tick_manager: Res<TickManager>,
-    // mut global_input_buffer: ResMut<InputBuffer<A>>,
-    // global_action_state: Option<Res<ActionState<A>>>,
+    mut global_input_buffer: ResMut<InputBuffer<A>>,
+    global_action_state: Option<Res<ActionState<A>>>,
     mut action_state_query: Query<
         (Entity, &ActionState<A>, &mut InputBuffer<A>),
         With<InputMap<A>>,
@@ -406,10 +494,7 @@ fn buffer_action_state<A: LeafwingUserAction>(
 ) {
     // TODO: if the input delay changes, this could override a previous tick's input in the InputBuffer
     //  or leave gaps
-    let input_delay_ticks = config.prediction.input_delay_ticks(
-        connection_manager.ping_manager.rtt(),
-        config.shared.tick.tick_duration,
-    ) as i16;
+    let input_delay_ticks = input_config.delay_ticks(&config, &connection_manager) as i16;
     let tick = tick_manager.tick() + input_delay_ticks;
     for (entity, action_state, mut input_buffer) in action_state_query.iter_mut() {
         input_buffer.set(tick, action_state);
@@ -421,9 +506,15 @@ fn buffer_action_state<A: LeafwingUserAction>(
             input_buffer.as_ref()
         );
     }
-    // if let Some(action_state) = global_action_state {
-    //     global_input_buffer.set(tick, action_state.as_ref());
-    // }
+    if let Some(action_state) = global_action_state {
+        global_input_buffer.set(tick, action_state.as_ref());
+        debug!(
+            current_tick = ?tick_manager.tick(),
+            delayed_tick = ?tick,
+            "set global action state in input buffer: {}",
+            global_input_buffer.as_ref()
+        );
+    }
 }
 
 /// Retrieve the ActionState from the InputBuffer (if input_delay is enabled)
@@ -432,6 +523,8 @@ fn buffer_action_state<A: LeafwingUserAction>(
 /// using the value stored in the buffer (since the local ActionState is for the delayed tick)
 fn get_non_rollback_action_state<A: LeafwingUserAction>(
     tick_manager: Res<TickManager>,
+    global_input_buffer: Res<InputBuffer<A>>,
+    global_action_state: Option<ResMut<ActionState<A>>>,
     // NOTE: we want to apply the Inputs for BOTH the local player and the remote player.
     // - local player: we need to get the input from the InputBuffer because of input delay
     // - remote player: we want to reduce the amount of rollbacks by updating the ActionState
@@ -457,6 +550,11 @@ fn get_non_rollback_action_state<A: LeafwingUserAction>(
             );
         }
     }
+    if let Some(mut action_state) = global_action_state {
+        if let Some(action) = global_input_buffer.get(tick) {
+            *action_state = action.clone();
+        }
+    }
 }
 
 /// During rollback, fetch the action-state from the InputBuffer for the corresponding tick and use that
@@ -486,11 +584,22 @@ fn get_rollback_action_state<A: LeafwingUserAction>(
         (Entity, &mut ActionState<A>, &InputBuffer<A>),
         Without<InputMap<A>>,
     >,
+    global_input_buffer: Res<InputBuffer<A>>,
+    global_action_state: Option<ResMut<ActionState<A>>>,
     rollback: Res<Rollback>,
 ) {
     let tick = rollback
         .get_rollback_tick()
         .expect("we should be in rollback");
+    if let Some(mut action_state) = global_action_state {
+        *action_state = global_input_buffer.get(tick).cloned().unwrap_or_default();
+        debug!(
+            ?tick,
+            pressed = ?action_state.get_pressed(),
+            "updated global action state for rollback using input_buffer: {}",
+            global_input_buffer.as_ref()
+        );
+    }
     for (entity, mut action_state, input_buffer) in player_action_state_query.iter_mut() {
         *action_state = input_buffer.get(tick).cloned().unwrap_or_default();
         debug!(
@@ -514,29 +623,50 @@ fn get_rollback_action_state<A: LeafwingUserAction>(
     }
 }
 
-/// System that removes old entries from the ActionDiffBuffer and the InputBuffer
+/// System that removes old entries from the InputBuffers
+///
+/// The entries older than the interpolation tick are removed, and we never keep more than
+/// [`LeafwingInputConfig::max_buffer_ticks`] ticks of inputs.
 fn clean_buffers<A: LeafwingUserAction>(
     connection: Res<ConnectionManager>,
+    input_config: Res<LeafwingInputConfig<A>>,
     tick_manager: Res<TickManager>,
     global_input_buffer: Option<ResMut<InputBuffer<A>>>,
     mut input_buffer_query: Query<(Entity, &mut InputBuffer<A>)>,
 ) {
     // delete old input values
     // anything beyond interpolation tick should be safe to be deleted
-    let interpolation_tick = connection.sync_manager.interpolation_tick(&tick_manager);
+    // (the interpolation tick is only meaningful once we are synced)
+    let interpolation_tick = connection
+        .is_synced()
+        .then(|| connection.sync_manager.interpolation_tick(&tick_manager));
+    // in case the interpolation tick stalls, we still drop the inputs that are too old
+    let oldest_tick = tick_manager.tick() - input_config.max_buffer_ticks;
     trace!(
-        "popping all input buffers since interpolation tick: {:?}",
-        interpolation_tick
+        ?interpolation_tick,
+        ?oldest_tick,
+        "popping all input buffers since interpolation tick"
     );
-    for (entity, mut input_buffer) in input_buffer_query.iter_mut() {
-        input_buffer.pop(interpolation_tick);
+    let mut pop = |input_buffer: &mut InputBuffer<A>| {
+        if let Some(interpolation_tick) = interpolation_tick {
+            input_buffer.pop(interpolation_tick);
+        }
+        // NOTE: this does nothing if the tick is older than the start of the buffer
+        input_buffer.pop(oldest_tick);
+    };
+    for (_, mut input_buffer) in input_buffer_query.iter_mut() {
+        pop(&mut input_buffer);
     }
     if let Some(mut input_buffer) = global_input_buffer {
-        input_buffer.pop(interpolation_tick);
+        pop(&mut input_buffer);
     }
 }
 
 /// Send a message to the server containing the ActionDiffs for the last few ticks
+///
+/// Each input message starts with the full `ActionState` of its first tick (not a diff), followed by the diffs
+/// of the next ticks, and the server applies that `ActionState` as-is. So if the message containing a `Pressed`
+/// diff is lost while the key is held, the server catches up with the next message it receives.
 fn prepare_input_message<A: LeafwingUserAction>(
     connection: Res<ConnectionManager>,
     mut message_buffer: ResMut<MessageBuffer<A>>,
@@ -544,6 +674,9 @@ fn prepare_input_message<A: LeafwingUserAction>(
     config: Res<ClientConfig>,
     input_config: Res<LeafwingInputConfig<A>>,
     tick_manager: Res<TickManager>,
+    mut replication_errors: ResMut<ReplicationErrors>,
+    global_input_buffer: Res<InputBuffer<A>>,
+    global_action_state: Option<Res<ActionState<A>>>,
     input_buffer_query: Query<
         (
             Entity,
@@ -553,11 +686,9 @@ fn prepare_input_message<A: LeafwingUserAction>(
         ),
         With<InputMap<A>>,
     >,
+    mut checked_send_interval: Local<bool>,
 ) {
-    let input_delay_ticks = config.prediction.input_delay_ticks(
-        connection.ping_manager.rtt(),
-        config.shared.tick.tick_duration,
-    ) as i16;
+    let input_delay_ticks = input_config.delay_ticks(&config, &connection) as i16;
     let tick = tick_manager.tick() + input_delay_ticks;
     // TODO: the number of messages should be in SharedConfig
     trace!(tick = ?tick, "prepare_input_message");
@@ -571,12 +702,33 @@ fn prepare_input_message<A: LeafwingUserAction>(
         .send_frequency;
     // we send redundant inputs, so that if a packet is lost, we can still recover
     // A redundancy of 2 means that we can recover from 1 lost packet
-    let mut num_tick: u16 =
-        ((input_send_interval.as_nanos() / config.shared.tick.tick_duration.as_nanos()) + 1)
-            .try_into()
-            .unwrap();
-    num_tick = num_tick * input_config.packet_redundancy;
+    let tick_duration = config.shared.tick.tick_duration;
+    if !*checked_send_interval {
+        check_send_interval(
+            input_config.min_message_ticks,
+            input_config.max_message_ticks,
+            input_send_interval,
+            tick_duration,
+        );
+        *checked_send_interval = true;
+    }
+    let num_tick = message_ticks(
+        input_config.min_message_ticks,
+        input_config.max_message_ticks,
+        input_send_interval,
+        tick_duration,
+    )
+    .saturating_mul(input_config.packet_redundancy);
     let mut message = InputMessage::<A>::new(tick);
+    if global_action_state.is_some() {
+        // the global inputs are not attached to an entity, the server will associate them with our ClientId
+        message.add_inputs(
+            num_tick,
+            InputTarget::Global,
+            global_input_buffer.as_ref(),
+            input_config.quantize_axis,
+        );
+    }
     for (entity, input_buffer, predicted, pre_predicted) in input_buffer_query.iter() {
         debug!(
             ?tick,
@@ -608,6 +760,7 @@ fn prepare_input_message<A: LeafwingUserAction>(
                 num_tick,
                 InputTarget::PrePredictedEntity(entity),
                 input_buffer,
+                input_config.quantize_axis,
             );
         } else {
             // 1. if the entity is confirmed, we need to convert the entity to the server's entity
@@ -623,11 +776,24 @@ fn prepare_input_message<A: LeafwingUserAction>(
                     //     "preparing input message using input_buffer: {}",
                     //     input_buffer
                     // );
-                    message.add_inputs(num_tick, InputTarget::Entity(server_entity), input_buffer);
+                    message.add_inputs(
+                        num_tick,
+                        InputTarget::Entity(server_entity),
+                        input_buffer,
+                        input_config.quantize_axis,
+                    );
+                } else {
+                    debug!(
+                        ?entity,
+                        "not sending inputs because couldnt find server entity"
+                    );
+                    replication_errors
+                        .record_local(entity, ReplicationSkipReason::UnmappedInputEntity);
                 }
             } else {
                 // TODO: entity is not predicted or not confirmed? also need to do the conversion, no?
                 debug!("not sending inputs because couldnt find server entity");
+                replication_errors.record_local(entity, ReplicationSkipReason::UnmappedInputEntity);
             }
         }
     }
@@ -748,6 +914,7 @@ fn receive_remote_player_input_messages<A: LeafwingUserAction>(
                                     .get_local(*entity)
                             }
                             InputTarget::PrePredictedEntity(entity) => Some(*entity),
+                            // the global inputs of other clients are only tracked by the server
                             InputTarget::Global => continue,
                         };
                         if let Some(entity) = entity {
@@ -804,9 +971,10 @@ mod tests {
     use leafwing_input_manager::input_map::InputMap;
     use std::time::Duration;
 
+    use crate::inputs::leafwing::input_buffer::InputBufferReader;
     use crate::prelude::client::PredictionConfig;
     use crate::prelude::server::Replicate;
-    use crate::prelude::{client, SharedConfig, TickConfig};
+    use crate::prelude::{client, SharedConfig, Tick, TickConfig};
     use crate::tests::protocol::*;
     use crate::tests::stepper::BevyStepper;
 
@@ -881,6 +1049,85 @@ mod tests {
         (server_entity, client_entity)
     }
 
+    /// Check that the InputBuffer does not grow indefinitely if the interpolation tick lags far behind
+    #[test]
+    fn test_buffer_inputs_max_buffer_ticks() {
+        let frame_duration = Duration::from_millis(10);
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..default()
+        };
+        let client_config = ClientConfig {
+            interpolation: client::InterpolationConfig {
+                delay: client::InterpolationDelay {
+                    min_delay: Duration::from_secs(10),
+                    send_interval_ratio: 0.0,
+                },
+                ..default()
+            },
+            ..default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, client_config, frame_duration);
+        // the client input plugin (and its config) is only added when the app is finished
+        stepper.init();
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<LeafwingInputConfig<LeafwingInput1>>()
+            .max_buffer_ticks = 20;
+        let (_, client_entity) = setup(&mut stepper);
+
+        for _ in 0..100 {
+            stepper.frame_step();
+        }
+        let input_buffer = stepper
+            .client_app
+            .world()
+            .entity(client_entity)
+            .get::<InputBuffer<LeafwingInput1>>()
+            .unwrap();
+        assert!(
+            input_buffer.buffer.len() <= 21,
+            "{}",
+            input_buffer.buffer.len()
+        );
+        assert_eq!(input_buffer.start_tick, Some(stepper.client_tick() - 19u16));
+    }
+
+    /// Check that the number of ticks covered by the leafwing input messages is clamped
+    /// by the bounds of the `LeafwingInputConfig`
+    #[test]
+    fn test_message_ticks_clamp() {
+        let config = LeafwingInputConfig::<LeafwingInput1> {
+            min_message_ticks: 3,
+            max_message_ticks: 5,
+            ..default()
+        };
+        let (min, max) = (config.min_message_ticks, config.max_message_ticks);
+        let tick_duration = Duration::from_millis(10);
+        // a send interval shorter than the tick duration is raised to the minimum
+        assert_eq!(
+            message_ticks(min, max, Duration::from_millis(5), tick_duration),
+            3
+        );
+        assert_eq!(message_ticks(min, max, Duration::ZERO, Duration::ZERO), 3);
+        assert_eq!(
+            message_ticks(min, max, Duration::from_millis(30), tick_duration),
+            4
+        );
+        // a very long send interval is clamped to the maximum
+        assert_eq!(
+            message_ticks(min, max, Duration::from_secs(10), tick_duration),
+            5
+        );
+        // a minimum of 0 is treated as 1, and a maximum below the minimum is raised to the minimum
+        assert_eq!(
+            message_ticks(0, 0, Duration::from_secs(10), tick_duration),
+            1
+        );
+    }
+
     /// Check that ActionStates are stored correctly in the InputBuffer
     #[test]
     fn test_buffer_inputs_no_delay() {
@@ -1022,4 +1269,187 @@ mod tests {
             .get_pressed()
             .is_empty());
     }
+
+    /// Check that each action type can have its own input delay
+    #[test]
+    fn test_buffer_inputs_per_action_delay() {
+        let mut stepper = BevyStepper::default();
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<LeafwingInputConfig<LeafwingInput1>>()
+            .input_delay_ticks = Some(0);
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<LeafwingInputConfig<LeafwingInput2>>()
+            .input_delay_ticks = Some(2);
+        let (_, client_entity) = setup(&mut stepper);
+        stepper
+            .client_app
+            .world_mut()
+            .entity_mut(client_entity)
+            .insert(InputMap::<LeafwingInput2>::new([(
+                LeafwingInput2::Crouch,
+                KeyCode::KeyB,
+            )]));
+        stepper.frame_step();
+
+        // press on both keys
+        let mut keys = stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>();
+        keys.press(KeyCode::KeyA);
+        keys.press(KeyCode::KeyB);
+        stepper.frame_step();
+        let client_tick = stepper.client_tick();
+        let entity = stepper.client_app.world().entity(client_entity);
+
+        // the first action is not delayed
+        let input_buffer = entity.get::<InputBuffer<LeafwingInput1>>().unwrap();
+        assert_eq!(
+            input_buffer.get(client_tick).unwrap().get_pressed(),
+            &[LeafwingInput1::Jump]
+        );
+        // the second action is stored 2 ticks later
+        let input_buffer = entity.get::<InputBuffer<LeafwingInput2>>().unwrap();
+        assert_eq!(
+            input_buffer.get(client_tick + 2).unwrap().get_pressed(),
+            &[LeafwingInput2::Crouch]
+        );
+        assert!(input_buffer
+            .get(client_tick + 1)
+            .map_or(true, |action_state| action_state.get_pressed().is_empty()));
+    }
+
+    #[derive(Resource, Default)]
+    struct HandledJumps(Vec<Tick>);
+
+    #[derive(Resource, Default)]
+    struct PressedTicks(Vec<Tick>);
+
+    /// React to the jump inputs in `Update`, by reading them from the InputBuffer
+    fn handle_jumps(
+        mut handled: ResMut<HandledJumps>,
+        mut query: Query<(
+            &InputBuffer<LeafwingInput1>,
+            &mut InputBufferReader<LeafwingInput1>,
+        )>,
+    ) {
+        for (input_buffer, mut reader) in query.iter_mut() {
+            for input in reader.read(input_buffer) {
+                if input.just_pressed(&LeafwingInput1::Jump) {
+                    handled.0.push(input.tick);
+                }
+            }
+        }
+    }
+
+    /// Record the ticks where the jump input is pressed in `FixedUpdate`
+    fn record_pressed_ticks(
+        tick_manager: Res<TickManager>,
+        mut pressed: ResMut<PressedTicks>,
+        query: Query<&ActionState<LeafwingInput1>, With<InputMap<LeafwingInput1>>>,
+    ) {
+        for action_state in query.iter() {
+            if action_state.pressed(&LeafwingInput1::Jump) {
+                pressed.0.push(tick_manager.tick());
+            }
+        }
+    }
+
+    /// Check that with multiple frames per tick, a press read from the InputBuffer in `Update`
+    /// is handled exactly once, with the tick where the input was pressed
+    #[test]
+    fn test_read_inputs_in_update() {
+        let frame_duration = Duration::from_millis(2);
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, ClientConfig::default(), frame_duration);
+        stepper.init();
+        let (_, client_entity) = setup(&mut stepper);
+        stepper
+            .client_app
+            .world_mut()
+            .entity_mut(client_entity)
+            .insert(InputBufferReader::<LeafwingInput1>::default());
+        stepper
+            .client_app
+            .init_resource::<HandledJumps>()
+            .init_resource::<PressedTicks>()
+            .add_systems(Update, handle_jumps)
+            .add_systems(FixedUpdate, record_pressed_ticks);
+        // let the reader read a few ticks where the key is not pressed
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+
+        // hold the key for several ticks
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::KeyA);
+        for _ in 0..20 {
+            stepper.frame_step();
+        }
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .release(KeyCode::KeyA);
+        for _ in 0..20 {
+            stepper.frame_step();
+        }
+
+        let pressed = &stepper.client_app.world().resource::<PressedTicks>().0;
+        assert!(pressed.len() > 1);
+        assert_eq!(
+            stepper.client_app.world().resource::<HandledJumps>().0,
+            vec![pressed[0]]
+        );
+    }
+
+    /// Check that a key that is already held when the buffer starts (for example on the first tick
+    /// after the client is synced) is not reported as just pressed
+    #[test]
+    fn test_read_inputs_in_update_held_before_start() {
+        let mut stepper = BevyStepper::default();
+        let (_, client_entity) = setup(&mut stepper);
+        stepper
+            .client_app
+            .init_resource::<HandledJumps>()
+            .add_systems(Update, handle_jumps);
+
+        // the key is held before the client starts buffering inputs
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::KeyA);
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        stepper
+            .client_app
+            .world_mut()
+            .entity_mut(client_entity)
+            .insert((
+                InputBuffer::<LeafwingInput1>::default(),
+                InputBufferReader::<LeafwingInput1>::default(),
+            ));
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        assert!(stepper
+            .client_app
+            .world()
+            .resource::<HandledJumps>()
+            .0
+            .is_empty());
+    }
 }
@@ -1,5 +1,61 @@
+use bevy::utils::Duration;
+use tracing::warn;
+
 pub mod native;
 
 #[cfg_attr(docsrs, doc(cfg(feature = "leafwing")))]
 #[cfg(feature = "leafwing")]
 pub mod leafwing;
+
+/// Number of ticks of inputs that an input message covers (before redundancy), so that the messages
+/// sent every `send_interval` contain the inputs of every tick.
+///
+/// The result is clamped between `min_message_ticks` (at least 1) and `max_message_ticks`.
+pub(crate) fn message_ticks(
+    min_message_ticks: u16,
+    max_message_ticks: u16,
+    send_interval: Duration,
+    tick_duration: Duration,
+) -> u16 {
+    let min = min_message_ticks.max(1);
+    let max = max_message_ticks.max(min);
+    if tick_duration.is_zero() {
+        return min;
+    }
+    let ticks = send_interval.as_nanos() / tick_duration.as_nanos() + 1;
+    ticks.clamp(min as u128, max as u128) as u16
+}
+
+/// Log a warning if the send interval of the input channel is inconsistent with the tick duration
+pub(crate) fn check_send_interval(
+    min_message_ticks: u16,
+    max_message_ticks: u16,
+    send_interval: Duration,
+    tick_duration: Duration,
+) {
+    let message_ticks = message_ticks(
+        min_message_ticks,
+        max_message_ticks,
+        send_interval,
+        tick_duration,
+    );
+    if tick_duration.is_zero() {
+        warn!("The tick duration is zero: input messages will cover {message_ticks} tick(s)");
+        return;
+    }
+    if !send_interval.is_zero() && send_interval < tick_duration {
+        warn!(
+            ?send_interval,
+            ?tick_duration,
+            "The input send interval is shorter than the tick duration: input messages will still cover at least {message_ticks} tick(s)"
+        );
+    }
+    let ticks = send_interval.as_nanos() / tick_duration.as_nanos() + 1;
+    if ticks > max_message_ticks.max(min_message_ticks) as u128 {
+        warn!(
+            ?send_interval,
+            ?tick_duration,
+            "The input send interval spans {ticks} ticks: input messages will only cover the last {message_ticks} tick(s)"
+        );
+    }
+}
@@ -45,12 +45,13 @@
 //! This module is kept for simplicity but might get removed in the future.
 use bevy::prelude::*;
 use bevy::reflect::Reflect;
-use bevy::utils::Duration;
-use tracing::{debug, error, trace};
+use bevy::utils::{Duration, HashMap};
+use tracing::{debug, error, trace, warn};
 
 use crate::client::config::ClientConfig;
 use crate::client::connection::ConnectionManager;
-use crate::client::events::InputEvent;
+use crate::client::events::{InputEvent, MessageEvent, RemoteInputEvent};
+use crate::client::input::{check_send_interval, message_ticks};
 use crate::client::prediction::plugin::is_in_rollback;
 use crate::client::prediction::rollback::Rollback;
 use crate::client::run_conditions::is_synced;
@@ -58,11 +59,14 @@ use crate::client::sync::SyncSet;
 use crate::connection::client::NetClient;
 use crate::connection::client::NetClientDispatch;
 use crate::inputs::native::input_buffer::InputBuffer;
-use crate::inputs::native::UserAction;
-use crate::prelude::{is_host_server, ChannelKind, ChannelRegistry, Tick, TickManager};
+use crate::inputs::native::{InputNack, RemoteInputMessage, UserAction};
+use crate::prelude::{is_host_server, ChannelKind, ChannelRegistry, ClientId, Tick, TickManager};
 use crate::shared::sets::{ClientMarker, InternalMainSet};
-use crate::shared::tick_manager::TickEvent;
-use crate::{channel::builder::InputChannel, prelude::client::ClientConnection};
+use crate::shared::tick_manager::{AppTickStepExt, TickEvent};
+use crate::{
+    channel::builder::{InputChannel, InputRecoveryChannel},
+    prelude::client::ClientConnection,
+};
 
 #[derive(Debug, Clone, Copy, Reflect)]
 pub struct InputConfig {
@@ -74,6 +78,43 @@ pub struct InputConfig {
     /// How often do we send input messages to the server?
     /// Duration::default() means that we will send input messages every frame.
     pub send_interval: Duration,
+    /// If the input buffer has no input for a tick, we can extrapolate it by assuming that the input
+    /// stayed the same as the input of the last buffered tick.
+    /// This is the maximum number of ticks for which we will extrapolate the last known input.
+    /// 0 means that we don't extrapolate and missing inputs are emitted as `None`.
+    ///
+    /// Ticks for which the user did not buffer any input are recorded as absent inputs, so a released key
+    /// is never extrapolated. This only applies to ticks that were never simulated with a buffered input,
+    /// for example ticks that were skipped by a tick snap and get replayed during a rollback.
+    ///
+    /// The server extrapolates the inputs that it didn't receive yet in the same way
+    /// (see [`ServerInputConfig`](crate::prelude::server::ServerInputConfig)).
+    ///
+    /// The inputs of the remote players (forwarded by the server if
+    /// [`ServerInputConfig::rebroadcast_inputs`](crate::prelude::server::ServerInputConfig::rebroadcast_inputs)
+    /// is enabled) are extrapolated in the same way when they didn't arrive yet, so that the predicted
+    /// entities that they control keep moving. They are emitted as [`RemoteInputEvent`]s.
+    pub max_extrapolation_ticks: u16,
+    /// If true, the client still sends an input message when all the inputs in the message are absent,
+    /// so that the server can tell apart an idle client from a client whose input messages are lost.
+    /// These heartbeat messages are only sent every `empty_input_heartbeat_ticks` ticks.
+    pub send_empty_input_heartbeat: bool,
+    /// Minimum number of ticks between two consecutive input messages when the client has no inputs
+    /// to send. Only used if `send_empty_input_heartbeat` is true.
+    /// Values above `i16::MAX` are treated as `i16::MAX`, the largest tick distance that can be measured.
+    pub empty_input_heartbeat_ticks: u16,
+    /// Minimum number of ticks of inputs that an input message covers (before redundancy).
+    /// Values below 1 are treated as 1.
+    ///
+    /// This only applies to the native inputs: the leafwing inputs use the bounds of their `LeafwingInputConfig`.
+    pub min_message_ticks: u16,
+    /// Maximum number of ticks of inputs that an input message covers (before redundancy).
+    ///
+    /// The number of ticks is computed from the send interval of the input channel and the tick duration,
+    /// so this prevents a misconfigured send interval from creating huge input messages.
+    ///
+    /// This only applies to the native inputs: the leafwing inputs use the bounds of their `LeafwingInputConfig`.
+    pub max_message_ticks: u16,
 }
 
 /// Resource that handles buffering and sending inputs to the server
@@ -83,12 +124,15 @@ pub struct InputConfig {
 #[derive(Debug, Resource)]
 pub struct InputManager<A> {
     pub(crate) input_buffer: InputBuffer<A>,
+    /// Tick at which we last sent an input message to the server
+    last_sent_tick: Option<Tick>,
 }
 
 impl<A> Default for InputManager<A> {
     fn default() -> Self {
         Self {
             input_buffer: InputBuffer::default(),
+            last_sent_tick: None,
         }
     }
 }
@@ -100,17 +144,87 @@ impl<A: UserAction> InputManager<A> {
         self.input_buffer.get(tick).cloned()
     }
 
+    /// Get the input for the given tick, falling back to the most recent input of the previous
+    /// `max_extrapolation_ticks` ticks if the input is missing
+    pub(crate) fn get_extrapolated_input(
+        &self,
+        tick: Tick,
+        max_extrapolation_ticks: u16,
+    ) -> Option<A> {
+        self.input_buffer
+            .get_extrapolated(tick, max_extrapolation_ticks)
+            .cloned()
+    }
+
     /// Buffer a user action for the given tick
     pub fn add_input(&mut self, input: A, tick: Tick) {
         self.input_buffer.set(tick, Some(input));
     }
 }
 
+/// Inputs of the remote clients, forwarded by the server if
+/// [`ServerInputConfig::rebroadcast_inputs`](crate::prelude::server::ServerInputConfig::rebroadcast_inputs) is enabled.
+///
+/// They are emitted every tick as [`RemoteInputEvent`]s.
+#[derive(Resource, Debug)]
+pub struct RemoteInputs<A> {
+    pub(crate) buffers: HashMap<ClientId, InputBuffer<A>>,
+}
+
+impl<A> Default for RemoteInputs<A> {
+    fn default() -> Self {
+        Self {
+            buffers: HashMap::default(),
+        }
+    }
+}
+
+impl<A: UserAction> RemoteInputs<A> {
+    /// Get the input of the remote client for the given tick, falling back to its most recent input
+    /// of the previous `max_extrapolation_ticks` ticks if the input didn't arrive yet
+    pub fn get_extrapolated_input(
+        &self,
+        client_id: ClientId,
+        tick: Tick,
+        max_extrapolation_ticks: u16,
+    ) -> Option<A> {
+        self.buffers
+            .get(&client_id)?
+            .get_extrapolated(tick, max_extrapolation_ticks)
+            .cloned()
+    }
+}
+
+/// Extension trait to buffer a native input before advancing a single [`App`] by one tick.
+///
+/// See [`AppTickStepExt`] for more details.
+pub trait AppInputStepExt: AppTickStepExt {
+    /// Buffer `input` in the client's [`InputManager`] for the next tick, then run that tick.
+    ///
+    /// Returns the tick that was simulated.
+    fn step_tick_with_input<A: UserAction>(&mut self, input: A) -> Tick;
+}
+
+impl AppInputStepExt for App {
+    fn step_tick_with_input<A: UserAction>(&mut self, input: A) -> Tick {
+        let next_tick = self.world().resource::<TickManager>().tick() + 1;
+        self.world_mut()
+            .resource_mut::<InputManager<A>>()
+            .add_input(input, next_tick);
+        self.step_tick()
+    }
+}
+
 impl Default for InputConfig {
     fn default() -> Self {
         InputConfig {
             packet_redundancy: 10,
             send_interval: Duration::default(),
+            max_extrapolation_ticks: 0,
+            send_empty_input_heartbeat: false,
+            empty_input_heartbeat_ticks: 10,
+            min_message_ticks: 1,
+            max_message_ticks: 64,
         }
     }
 }
@@ -148,8 +262,10 @@ impl<A: UserAction> Plugin for InputPlugin<A> {
         app.register_type::<InputConfig>();
         // RESOURCES
         app.init_resource::<InputManager<A>>();
+        app.init_resource::<RemoteInputs<A>>();
         // EVENT
         app.add_event::<InputEvent<A>>();
+        app.add_event::<RemoteInputEvent<A>>();
         // SETS
         app.configure_sets(
             FixedPreUpdate,
@@ -186,18 +302,31 @@ impl<A: UserAction> Plugin for InputPlugin<A> {
         );
         app.add_systems(
             FixedPreUpdate,
-            write_input_event::<A>
+            (write_input_event::<A>, write_remote_input_events::<A>)
                 .in_set(InputSystemSet::WriteInputEvent)
                 .run_if(not(is_host_server)),
         );
         app.add_systems(
             FixedPostUpdate,
-            clear_input_events::<A>.in_set(InputSystemSet::ClearInputEvent),
+            (
+                clear_input_events::<A>,
+                clear_remote_input_events::<A>.run_if(not(is_host_server)),
+            )
+                .in_set(InputSystemSet::ClearInputEvent),
+        );
+        app.add_systems(
+            PreUpdate,
+            receive_remote_input_messages::<A>
+                .after(InternalMainSet::<ClientMarker>::EmitEvents)
+                .run_if(not(is_host_server)),
         );
         app.observe(receive_tick_events::<A>);
         app.add_systems(
             PostUpdate,
-            (prepare_input_message::<A>.in_set(InputSystemSet::SendInputMessage),),
+            // resend the requested inputs before old inputs get removed from the buffer
+            (resend_nacked_inputs::<A>, prepare_input_message::<A>)
+                .chain()
+                .in_set(InputSystemSet::SendInputMessage),
         );
 
         // in case the framerate is faster than fixed-update interval, we also write/clear the events at frame limits
@@ -232,17 +361,95 @@ fn clear_input_events<A: UserAction>(mut input_events: EventReader<InputEvent<A>
 // Create a system that reads from the input buffer and returns the inputs of all clients for the current tick.
 // The only tricky part is that events are cleared every frame, but we want to clear every tick instead
 // Do it in this system because we want an input for every tick
+// During rollback, missing inputs are extrapolated again from the buffer, so any input that
+// was buffered in the meantime will be used instead of the extrapolated one.
 fn write_input_event<A: UserAction>(
+    config: Res<ClientConfig>,
     tick_manager: Res<TickManager>,
-    input_manager: Res<InputManager<A>>,
+    mut input_manager: ResMut<InputManager<A>>,
     mut client_input_events: EventWriter<InputEvent<A>>,
     rollback: Option<Res<Rollback>>,
+) {
+    let in_rollback = rollback.as_ref().is_some_and(|r| r.is_rollback());
+    let tick = rollback.map_or(tick_manager.tick(), |r| {
+        tick_manager.tick_or_rollback_tick(r.as_ref())
+    });
+    // the user did not buffer any input for the current tick: record that no input was pressed,
+    // so that the previous input (for example a released key) does not get extrapolated
+    if !in_rollback
+        && input_manager
+            .input_buffer
+            .end_tick()
+            .is_some_and(|end_tick| end_tick < tick)
+    {
+        input_manager.input_buffer.set(tick, None);
+    }
+    let input = input_manager.get_extrapolated_input(tick, config.input.max_extrapolation_ticks);
+    client_input_events.send(InputEvent::new(input, ()));
+}
+
+/// System that clears the remote input events, every tick
+fn clear_remote_input_events<A: UserAction>(mut input_events: EventReader<RemoteInputEvent<A>>) {
+    input_events.clear();
+}
+
+/// Emit the input of every remote client for the current tick (or the rollback tick).
+///
+/// The inputs that didn't arrive yet are extrapolated; during rollback, the inputs that arrived in the meantime
+/// are used instead of the extrapolated ones.
+fn write_remote_input_events<A: UserAction>(
+    config: Res<ClientConfig>,
+    tick_manager: Res<TickManager>,
+    remote_inputs: Res<RemoteInputs<A>>,
+    mut remote_input_events: EventWriter<RemoteInputEvent<A>>,
+    rollback: Option<Res<Rollback>>,
 ) {
     let tick = rollback.map_or(tick_manager.tick(), |r| {
         tick_manager.tick_or_rollback_tick(r.as_ref())
     });
-    let input = input_manager.get_input(tick);
-    client_input_events.send(InputEvent::new(input_manager.get_input(tick), ()));
+    for client_id in remote_inputs.buffers.keys() {
+        let input = remote_inputs.get_extrapolated_input(
+            *client_id,
+            tick,
+            config.input.max_extrapolation_ticks,
+        );
+        remote_input_events.send(RemoteInputEvent::new(input, *client_id));
+    }
+}
+
+/// Buffer the inputs of the remote clients forwarded by the server
+fn receive_remote_input_messages<A: UserAction>(
+    config: Res<ClientConfig>,
+    tick_manager: Res<TickManager>,
+    connection: Res<ConnectionManager>,
+    mut messages: ResMut<Events<MessageEvent<RemoteInputMessage<A>>>>,
+    mut remote_inputs: ResMut<RemoteInputs<A>>,
+) {
+    for event in messages.drain() {
+        let RemoteInputMessage { client_id, message } = event.message;
+        trace!(?client_id, end_tick = ?message.end_tick, "Received remote input message");
+        remote_inputs
+            .buffers
+            .entry(client_id)
+            .or_default()
+            .update_from_message(message);
+    }
+    // we never rollback to ticks before the interpolation tick
+    let interpolation_tick = connection.sync_manager.interpolation_tick(&tick_manager);
+    remote_inputs.buffers.retain(|_, buffer| {
+        let Some(end_tick) = buffer.end_tick() else {
+            return false;
+        };
+        // forget the clients whose inputs can not be extrapolated anymore
+        if (interpolation_tick - end_tick) as i32 > config.input.max_extrapolation_ticks as i32 {
+            return false;
+        }
+        // keep the last input, to extrapolate from it
+        if end_tick > interpolation_tick {
+            buffer.pop(interpolation_tick);
+        }
+        true
+    });
 }
 
 /// Receive an [`TickEvent`] signifying that the local tick has been updated,
@@ -272,6 +479,7 @@ fn prepare_input_message<A: UserAction>(
     mut input_manager: ResMut<InputManager<A>>,
     config: Res<ClientConfig>,
     tick_manager: Res<TickManager>,
+    mut checked_send_interval: Local<bool>,
 ) {
     let Some(mut connection) = connection else {
         return;
@@ -290,13 +498,25 @@ fn prepare_input_message<A: UserAction>(
         .unwrap()
         .settings
         .send_frequency;
-    let num_tick: u16 =
-        ((input_send_interval.as_nanos() / config.shared.tick.tick_duration.as_nanos()) + 1)
-            .try_into()
-            .unwrap();
+    let tick_duration = config.shared.tick.tick_duration;
+    if !*checked_send_interval {
+        check_send_interval(
+            config.input.min_message_ticks,
+            config.input.max_message_ticks,
+            input_send_interval,
+            tick_duration,
+        );
+        *checked_send_interval = true;
+    }
+    let num_tick = message_ticks(
+        config.input.min_message_ticks,
+        config.input.max_message_ticks,
+        input_send_interval,
+        tick_duration,
+    );
     let redundancy = config.input.packet_redundancy;
     // let redundancy = 3;
-    let message_len = redundancy * num_tick;
+    let message_len = redundancy.saturating_mul(num_tick);
     // TODO: we can either:
     //  - buffer an input message at every tick, and not require that much redundancy
     //  - buffer an input every frame; and require some redundancy (number of tick per frame)
@@ -305,8 +525,16 @@ fn prepare_input_message<A: UserAction>(
     let mut message = input_manager
         .input_buffer
         .create_message(tick_manager.tick(), message_len);
-    // all inputs are absent
-    if !message.is_empty() {
+    // if all inputs are absent, we only send the message as a periodic heartbeat
+    let send_heartbeat = config.input.send_empty_input_heartbeat
+        && input_manager.last_sent_tick.map_or(true, |last_sent_tick| {
+            current_tick - last_sent_tick
+                >= config
+                    .input
+                    .empty_input_heartbeat_ticks
+                    .min(i16::MAX as u16) as i16
+        });
+    if !message.is_empty() || send_heartbeat {
         // TODO: should we provide variants of each user-facing function, so that it pushes the error
         //  to the ConnectionEvents?
         debug!(
@@ -317,7 +545,8 @@ fn prepare_input_message<A: UserAction>(
             .send_message::<InputChannel, _>(&mut message)
             .unwrap_or_else(|err| {
                 error!("Error while sending input message: {:?}", err);
-            })
+            });
+        input_manager.last_sent_tick = Some(current_tick);
     }
     // NOTE: actually we keep the input values! because they might be needed when we rollback for client prediction
     // TODO: figure out when we can delete old inputs. Basically when the oldest prediction group tick has passed?
@@ -329,6 +558,35 @@ fn prepare_input_message<A: UserAction>(
     // .pop(current_tick - (message_len + 1));
 }
 
+/// Re-send the inputs that the server requested via an [`InputNack`], because it detected that
+/// they were missing
+fn resend_nacked_inputs<A: UserAction>(
+    connection: Option<ResMut<ConnectionManager>>,
+    input_manager: Res<InputManager<A>>,
+    mut nacks: EventReader<MessageEvent<InputNack<A>>>,
+) {
+    let Some(mut connection) = connection else {
+        return;
+    };
+    for event in nacks.read() {
+        let nack = event.message();
+        let num_ticks = (nack.end_tick - nack.start_tick + 1) as u16;
+        let mut message = input_manager
+            .input_buffer
+            .create_message(nack.end_tick, num_ticks);
+        debug!(
+            start_tick = ?nack.start_tick,
+            end_tick = ?nack.end_tick,
+            "re-sending inputs requested by the server"
+        );
+        connection
+            .send_message::<InputRecoveryChannel, _>(&mut message)
+            .unwrap_or_else(|err| {
+                error!("Error while re-sending input message: {:?}", err);
+            })
+    }
+}
+
 /// In host server mode, we don't buffer inputs (because there is no rollback) and we don't send
 /// inputs through the network, we just send directly to the server's InputEvents
 fn send_input_directly_to_client_events<A: UserAction>(
@@ -347,12 +605,27 @@ fn send_input_directly_to_client_events<A: UserAction>(
 
 #[cfg(test)]
 mod tests {
-    use crate::client::input::native::InputSystemSet;
-    use crate::prelude::client::InputManager;
-    use crate::prelude::{server, TickManager};
+    use crate::client::input::native::{AppInputStepExt, InputSystemSet};
+    use crate::client::input::{check_send_interval, message_ticks};
+    use crate::client::prediction::rollback::{run_rollback, Rollback};
+    use crate::prelude::client::{
+        ClientConfig, InputConfig, InputManager, InterpolationConfig, PredictionConfig,
+        RemoteInputs, SyncConfig,
+    };
+    use crate::prelude::ClientId;
+    use crate::prelude::{
+        client, server, AppTickStepExt, SharedConfig, Tick, TickConfig, TickManager,
+    };
+    use crate::server::input::native::InputSystemSet as ServerInputSystemSet;
+    use crate::shared::sets::{InternalMainSet, ServerMarker};
     use crate::tests::host_server_stepper::HostServerStepper;
-    use crate::tests::protocol::MyInput;
+    use crate::tests::log_buffer::LogBuffer;
+    use crate::tests::multi_stepper::{MultiBevyStepper, TEST_CLIENT_ID_1};
+    use crate::tests::protocol::{MyInput, ProtocolPlugin};
+    use crate::tests::stepper::BevyStepper;
     use bevy::prelude::*;
+    use bevy::state::app::StatesPlugin;
+    use bevy::utils::Duration;
 
     fn press_input(
         mut input_manager: ResMut<InputManager<MyInput>>,
@@ -390,4 +663,559 @@ mod tests {
         stepper.frame_step();
         assert!(stepper.server_app.world().resource::<Counter>().0 > 0);
     }
+
+    #[derive(Resource)]
+    struct Pressing(bool);
+
+    #[derive(Resource, Default)]
+    struct ReceivedInputs(Vec<Option<MyInput>>);
+
+    fn press_input_if_pressing(
+        pressing: Res<Pressing>,
+        mut input_manager: ResMut<InputManager<MyInput>>,
+        tick_manager: Res<TickManager>,
+    ) {
+        if pressing.0 {
+            input_manager.add_input(MyInput(2), tick_manager.tick());
+        }
+    }
+
+    fn record_input(
+        mut received: ResMut<ReceivedInputs>,
+        mut input: EventReader<client::InputEvent<MyInput>>,
+    ) {
+        for input in input.read() {
+            received.0.push(*input.input());
+        }
+    }
+
+    fn extrapolation_stepper() -> BevyStepper {
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..default()
+        };
+        let client_config = ClientConfig {
+            input: InputConfig {
+                max_extrapolation_ticks: 2,
+                ..default()
+            },
+            // the inputs older than the interpolation tick are removed from the buffer:
+            // keep them so that we can roll back
+            interpolation: client::InterpolationConfig {
+                delay: client::InterpolationDelay {
+                    min_delay: Duration::from_secs(10),
+                    send_interval_ratio: 0.0,
+                },
+                ..default()
+            },
+            ..default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, client_config, tick_duration);
+        stepper.client_app.insert_resource(Pressing(true));
+        stepper.client_app.init_resource::<ReceivedInputs>();
+        stepper.client_app.add_systems(
+            FixedPreUpdate,
+            press_input_if_pressing.in_set(InputSystemSet::BufferInputs),
+        );
+        stepper.client_app.add_systems(FixedUpdate, record_input);
+        stepper.init();
+        stepper
+    }
+
+    /// Check that a released input is not extrapolated: the ticks without any buffered input
+    /// are recorded as absent inputs
+    #[test]
+    fn test_input_release_not_extrapolated() {
+        let mut stepper = extrapolation_stepper();
+        stepper.frame_step();
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .resource::<ReceivedInputs>()
+                .0
+                .last(),
+            Some(&Some(MyInput(2)))
+        );
+
+        // stop providing inputs: the key was released
+        stepper.client_app.insert_resource(Pressing(false));
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<ReceivedInputs>()
+            .0
+            .clear();
+        for _ in 0..4 {
+            stepper.frame_step();
+        }
+        assert_eq!(
+            stepper.client_app.world().resource::<ReceivedInputs>().0,
+            vec![None; 4]
+        );
+    }
+
+    /// Check that the inputs replayed during a rollback are the buffered inputs, and that
+    /// the ticks that had no buffered input are extrapolated for up to `max_extrapolation_ticks` ticks
+    #[test]
+    fn test_input_extrapolation_rollback() {
+        let mut stepper = extrapolation_stepper();
+        for _ in 0..2 {
+            stepper.frame_step();
+        }
+        stepper.client_app.insert_resource(Pressing(false));
+        for _ in 0..3 {
+            stepper.frame_step();
+        }
+        let current_tick = stepper.client_tick();
+        let inputs = |stepper: &mut BevyStepper| {
+            let mut received = stepper
+                .client_app
+                .world_mut()
+                .resource_mut::<ReceivedInputs>();
+            let inputs = std::mem::take(&mut received.0);
+            inputs[inputs.len() - 5..].to_vec()
+        };
+        assert_eq!(
+            inputs(&mut stepper),
+            vec![Some(MyInput(2)), Some(MyInput(2)), None, None, None]
+        );
+
+        // rollback: the inputs are the same as during the initial simulation
+        let rollback_to = |stepper: &mut BevyStepper, tick: Tick| {
+            stepper
+                .client_app
+                .world()
+                .resource::<Rollback>()
+                .set_rollback_tick(tick);
+            run_rollback(stepper.client_app.world_mut());
+        };
+        rollback_to(&mut stepper, current_tick - 4);
+        assert_eq!(
+            inputs(&mut stepper),
+            vec![Some(MyInput(2)), Some(MyInput(2)), None, None, None]
+        );
+
+        // the last ticks were never buffered (for example because of a tick snap):
+        // during the rollback, the last input is extrapolated for 2 ticks
+        let mut input_manager = stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<InputManager<MyInput>>();
+        let len = input_manager.input_buffer.buffer.len();
+        input_manager.input_buffer.buffer.truncate(len - 3);
+        rollback_to(&mut stepper, current_tick - 4);
+        assert_eq!(
+            inputs(&mut stepper),
+            vec![
+                Some(MyInput(2)),
+                Some(MyInput(2)),
+                Some(MyInput(2)),
+                Some(MyInput(2)),
+                None
+            ]
+        );
+    }
+
+    fn press_tick_input(
+        mut input_manager: ResMut<InputManager<MyInput>>,
+        tick_manager: Res<TickManager>,
+    ) {
+        let tick = tick_manager.tick();
+        input_manager.add_input(MyInput(tick.0 as i16), tick);
+    }
+
+    #[derive(Resource)]
+    struct DropInputMessages(bool);
+
+    /// Simulate packet loss by dropping the input messages received by the server
+    fn drop_input_messages(
+        mut drop: ResMut<DropInputMessages>,
+        mut connection_manager: ResMut<server::ConnectionManager>,
+    ) {
+        if std::mem::take(&mut drop.0) {
+            for connection in connection_manager.connections.values_mut() {
+                connection.received_input_messages.clear();
+            }
+        }
+    }
+
+    #[derive(Resource, Default)]
+    struct ServerReceivedInputs(Vec<(Tick, Option<MyInput>)>);
+
+    fn record_server_input(
+        tick_manager: Res<TickManager>,
+        mut received: ResMut<ServerReceivedInputs>,
+        mut input: EventReader<server::InputEvent<MyInput>>,
+    ) {
+        for input in input.read() {
+            received.0.push((tick_manager.tick(), *input.input()));
+        }
+    }
+
+    /// Check that if an input message is lost (and is not covered by the redundancy), the server
+    /// requests the missing inputs and the client re-sends them
+    #[test]
+    fn test_input_nack() {
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..default()
+        };
+        let client_config = ClientConfig {
+            input: InputConfig {
+                // each input message only contains the input for the current tick
+                packet_redundancy: 1,
+                ..default()
+            },
+            ..default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, client_config, tick_duration);
+        // the client is only a couple of ticks ahead of the server in the tests (there is no latency),
+        // so the nack has to be sent as soon as the gap is detected to arrive in time
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<server::ServerConfig>()
+            .input
+            .nack_delay_ticks = 0;
+        stepper.client_app.add_systems(
+            FixedPreUpdate,
+            press_tick_input.in_set(InputSystemSet::BufferInputs),
+        );
+        stepper.server_app.insert_resource(DropInputMessages(false));
+        stepper.server_app.init_resource::<ServerReceivedInputs>();
+        stepper.server_app.add_systems(
+            PreUpdate,
+            drop_input_messages
+                .after(InternalMainSet::<ServerMarker>::Receive)
+                .before(crate::server::input::native::InputSystemSet::ReceiveInputMessage),
+        );
+        stepper
+            .server_app
+            .add_systems(FixedUpdate, record_server_input);
+        stepper.init();
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+
+        // drop one input message
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ServerReceivedInputs>()
+            .0
+            .clear();
+        stepper.server_app.insert_resource(DropInputMessages(true));
+        for _ in 0..20 {
+            stepper.frame_step();
+        }
+
+        // the server received the correct input for every tick, including the dropped one
+        let received = &stepper
+            .server_app
+            .world()
+            .resource::<ServerReceivedInputs>()
+            .0;
+        assert_eq!(received.len(), 20);
+        for (tick, input) in received {
+            assert_eq!(*input, Some(MyInput(tick.0 as i16)));
+        }
+    }
+
+    #[derive(Resource, Default)]
+    struct ServerInputMessageCount(usize);
+
+    fn count_input_messages(
+        mut count: ResMut<ServerInputMessageCount>,
+        connection_manager: Res<server::ConnectionManager>,
+    ) {
+        for connection in connection_manager.connections.values() {
+            count.0 += connection
+                .received_input_messages
+                .values()
+                .map(|messages| messages.len())
+                .sum::<usize>();
+        }
+    }
+
+    /// Check that if no inputs are pressed, the client only sends an empty input message
+    /// every `empty_input_heartbeat_ticks` ticks
+    #[test]
+    fn test_empty_input_heartbeat() {
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..default()
+        };
+        let client_config = ClientConfig {
+            input: InputConfig {
+                send_empty_input_heartbeat: true,
+                empty_input_heartbeat_ticks: 5,
+                ..default()
+            },
+            ..default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, client_config, tick_duration);
+        stepper
+            .server_app
+            .init_resource::<ServerInputMessageCount>();
+        stepper.server_app.add_systems(
+            PreUpdate,
+            count_input_messages
+                .after(InternalMainSet::<ServerMarker>::Receive)
+                .before(crate::server::input::native::InputSystemSet::ReceiveInputMessage),
+        );
+        stepper.init();
+
+        stepper
+            .server_app
+            .insert_resource(ServerInputMessageCount(0));
+        for _ in 0..20 {
+            stepper.frame_step();
+        }
+        assert_eq!(
+            stepper
+                .server_app
+                .world()
+                .resource::<ServerInputMessageCount>()
+                .0,
+            4
+        );
+
+        // without the heartbeat, no input messages are sent
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<ClientConfig>()
+            .input
+            .send_empty_input_heartbeat = false;
+        // receive the heartbeat that is still in flight
+        stepper.frame_step();
+        stepper
+            .server_app
+            .insert_resource(ServerInputMessageCount(0));
+        for _ in 0..20 {
+            stepper.frame_step();
+        }
+        assert_eq!(
+            stepper
+                .server_app
+                .world()
+                .resource::<ServerInputMessageCount>()
+                .0,
+            0
+        );
+    }
+
+    /// Check that with a send interval shorter than the tick duration, the input messages still cover
+    /// at least one tick, and that a warning is logged
+    #[test]
+    fn test_input_send_interval_shorter_than_tick() {
+        let config = InputConfig::default();
+        let (min, max) = (config.min_message_ticks, config.max_message_ticks);
+        let tick_duration = Duration::from_millis(16);
+        let send_interval = Duration::from_millis(5);
+        assert_eq!(message_ticks(min, max, send_interval, tick_duration), 1);
+        assert_eq!(message_ticks(min, max, send_interval, Duration::ZERO), 1);
+        // a very long send interval is clamped to the maximum
+        assert_eq!(
+            message_ticks(min, max, Duration::from_secs(10), tick_duration),
+            max
+        );
+
+        let buffer = LogBuffer::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::WARN)
+            .with_ansi(false)
+            .with_writer(buffer.clone())
+            .finish();
+        tracing::subscriber::with_default(subscriber, || {
+            check_send_interval(min, max, send_interval, tick_duration);
+        });
+        let logs = buffer.logs();
+        assert!(
+            logs.contains("WARN")
+                && logs.contains("input send interval is shorter than the tick duration"),
+            "no warning in:\n{logs}"
+        );
+    }
+
+    #[derive(Resource, Default)]
+    struct Position(i16);
+
+    fn move_player(
+        mut position: ResMut<Position>,
+        mut inputs: EventReader<client::InputEvent<MyInput>>,
+    ) {
+        for input in inputs.read() {
+            if let Some(MyInput(delta)) = input.input() {
+                position.0 += delta;
+            }
+        }
+    }
+
+    /// Check that we can drive a single app one tick at a time, with precise control over the inputs
+    #[test]
+    fn test_step_tick_with_input() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatesPlugin));
+        app.add_plugins((
+            client::ClientPlugins::new(ClientConfig::default()),
+            ProtocolPlugin,
+        ));
+        app.init_resource::<Position>();
+        app.add_systems(FixedUpdate, move_player);
+        app.finish();
+        app.cleanup();
+        let start_tick = app.world().resource::<TickManager>().tick();
+
+        assert_eq!(app.step_tick_with_input(MyInput(1)), start_tick + 1);
+        assert_eq!(app.world().resource::<Position>().0, 1);
+
+        // no input for this tick
+        assert_eq!(app.step_tick(), start_tick + 2);
+        assert_eq!(app.world().resource::<Position>().0, 1);
+
+        assert_eq!(app.step_tick_with_input(MyInput(3)), start_tick + 3);
+        assert_eq!(app.world().resource::<Position>().0, 4);
+        assert_eq!(app.step_tick_with_input(MyInput(-2)), start_tick + 4);
+        assert_eq!(app.world().resource::<Position>().0, 2);
+    }
+
+    #[derive(Resource, Default)]
+    struct ReceivedRemoteInputs(Vec<(Tick, ClientId, Option<MyInput>)>);
+
+    fn record_remote_inputs(
+        tick_manager: Res<TickManager>,
+        mut received: ResMut<ReceivedRemoteInputs>,
+        mut events: EventReader<client::RemoteInputEvent<MyInput>>,
+    ) {
+        for event in events.read() {
+            received
+                .0
+                .push((tick_manager.tick(), *event.context(), *event.input()));
+        }
+    }
+
+    #[derive(Resource, Default)]
+    struct WithholdInputs(bool);
+
+    /// Simulate the input messages of the clients getting lost before reaching the server
+    fn withhold_input_messages(
+        withhold: Res<WithholdInputs>,
+        mut connection_manager: ResMut<server::ConnectionManager>,
+    ) {
+        if withhold.0 {
+            for connection in connection_manager.connections.values_mut() {
+                connection.received_input_messages.clear();
+            }
+        }
+    }
+
+    /// Check that the inputs of a remote client are forwarded by the server, and that they
+    /// are extrapolated for up to `max_extrapolation_ticks` ticks while they are withheld
+    #[test]
+    fn test_remote_input_extrapolation() {
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..default()
+        };
+        let mut stepper = MultiBevyStepper::new(
+            shared_config,
+            SyncConfig::default(),
+            PredictionConfig::default(),
+            InterpolationConfig::default(),
+            tick_duration,
+        );
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<server::ServerConfig>()
+            .input
+            .rebroadcast_inputs = true;
+        stepper.server_app.init_resource::<WithholdInputs>();
+        stepper.server_app.add_systems(
+            PreUpdate,
+            withhold_input_messages
+                .after(InternalMainSet::<ServerMarker>::Receive)
+                .before(ServerInputSystemSet::ReceiveInputMessage),
+        );
+        stepper.client_app_1.add_systems(
+            FixedPreUpdate,
+            press_input.in_set(InputSystemSet::BufferInputs),
+        );
+        stepper
+            .client_app_2
+            .world_mut()
+            .resource_mut::<ClientConfig>()
+            .input
+            .max_extrapolation_ticks = 3;
+        stepper.client_app_2.init_resource::<ReceivedRemoteInputs>();
+        stepper
+            .client_app_2
+            .add_systems(FixedUpdate, record_remote_inputs);
+        stepper.init();
+        for _ in 0..20 {
+            stepper.frame_step();
+        }
+        let client_1 = ClientId::Netcode(TEST_CLIENT_ID_1);
+        let remote_end_tick = |stepper: &MultiBevyStepper| {
+            stepper
+                .client_app_2
+                .world()
+                .resource::<RemoteInputs<MyInput>>()
+                .buffers
+                .get(&client_1)
+                .and_then(|buffer| buffer.end_tick())
+        };
+        assert!(remote_end_tick(&stepper).is_some());
+        let received = std::mem::take(
+            &mut stepper
+                .client_app_2
+                .world_mut()
+                .resource_mut::<ReceivedRemoteInputs>()
+                .0,
+        );
+        let client_tick_2 = stepper
+            .client_app_2
+            .world()
+            .resource::<TickManager>()
+            .tick();
+        assert_eq!(
+            received.last(),
+            Some(&(client_tick_2, client_1, Some(MyInput(2))))
+        );
+
+        // withhold the inputs of client 1, and wait for the forwarded inputs in flight to arrive
+        stepper.server_app.insert_resource(WithholdInputs(true));
+        for _ in 0..2 {
+            stepper.frame_step();
+        }
+        let end_tick = remote_end_tick(&stepper).unwrap();
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        // the ticks after the last received input are extrapolated for 3 ticks, then the input is missing
+        let received: Vec<_> = stepper
+            .client_app_2
+            .world()
+            .resource::<ReceivedRemoteInputs>()
+            .0
+            .iter()
+            .filter(|(tick, _, _)| *tick > end_tick)
+            .cloned()
+            .collect();
+        assert!(received.len() > 3);
+        for (tick, client_id, input) in &received {
+            assert_eq!(*client_id, client_1);
+            if *tick - end_tick <= 3 {
+                assert_eq!(*input, Some(MyInput(2)), "tick: {tick:?}");
+            } else {
+                assert_eq!(*input, None, "tick: {tick:?}");
+            }
+        }
+    }
 }
@@ -1,42 +1,437 @@
-use bevy::prelude::{Commands, DespawnRecursiveExt, OnRemove, Query, ResMut, Trigger};
+use std::marker::PhantomData;
+
+use bevy::prelude::{
+    Commands, Component, DespawnRecursiveExt, Entity, OnRemove, Query, Res, ResMut, Time, Timer,
+    TimerMode, Trigger, With, World,
+};
+use bevy::utils::Duration;
 
 use crate::client::components::{Confirmed, SyncComponent};
+use crate::client::config::ClientConfig;
 use crate::client::interpolation::interpolate::InterpolateStatus;
 use crate::client::interpolation::interpolation_history::ConfirmedHistory;
 use crate::client::interpolation::resource::InterpolationManager;
+use crate::client::interpolation::Interpolated;
 
 /// Remove the component from interpolated entities when it gets removed from confirmed
+///
+/// If [`InterpolationConfig::despawn_grace_period`](crate::prelude::client::InterpolationConfig::despawn_grace_period)
+/// is set, the component is only removed after the grace period.
 pub(crate) fn removed_components<C: SyncComponent>(
     trigger: Trigger<OnRemove, C>,
+    config: Res<ClientConfig>,
     mut commands: Commands,
     query: Query<&Confirmed>,
 ) {
-    if let Ok(confirmed) = query.get(trigger.entity()) {
+    let confirmed_entity = trigger.entity();
+    let grace_period = config.interpolation.despawn_grace_period;
+    if let Ok(confirmed) = query.get(confirmed_entity) {
         if let Some(interpolated) = confirmed.interpolated {
-            if let Some(mut entity) = commands.get_entity(interpolated) {
-                entity.remove::<(C, ConfirmedHistory<C>, InterpolateStatus<C>)>();
-            }
+            commands.add(move |world: &mut World| {
+                // if the confirmed entity was despawned, the interpolated entity will be despawned
+                // (after the grace period) with its components
+                if world.get_entity(confirmed_entity).is_none() {
+                    return;
+                }
+                if let Some(mut entity) = world.get_entity_mut(interpolated) {
+                    if grace_period.is_zero() {
+                        entity.remove::<(C, ConfirmedHistory<C>, InterpolateStatus<C>)>();
+                    } else {
+                        entity.insert(InterpolatedRemoveTimer::<C>::new(grace_period));
+                    }
+                }
+            });
+        }
+    }
+}
+
+/// Component added on an interpolated entity when the component `C` was removed from its confirmed entity.
+///
+/// The interpolated entity keeps its last interpolated value of `C` until the timer finishes, and then `C` gets removed.
+/// If `C` is added back to the confirmed entity before that, the timer is cancelled.
+/// See [`InterpolationConfig::despawn_grace_period`](crate::prelude::client::InterpolationConfig::despawn_grace_period).
+#[derive(Component, Debug)]
+pub struct InterpolatedRemoveTimer<C> {
+    pub timer: Timer,
+    marker: PhantomData<C>,
+}
+
+impl<C> InterpolatedRemoveTimer<C> {
+    fn new(grace_period: Duration) -> Self {
+        Self {
+            timer: Timer::new(grace_period, TimerMode::Once),
+            marker: PhantomData,
         }
     }
 }
 
+/// Remove the interpolated components whose removal grace period has elapsed
+pub(crate) fn remove_interpolated_components_after_grace_period<C: SyncComponent>(
+    time: Res<Time>,
+    mut commands: Commands,
+    confirmed_query: Query<(), With<C>>,
+    mut query: Query<(Entity, &Interpolated, &mut InterpolatedRemoveTimer<C>)>,
+) {
+    for (entity, interpolated, mut remove_timer) in query.iter_mut() {
+        // the component was added back on the confirmed entity during the grace period
+        if confirmed_query.contains(interpolated.confirmed_entity) {
+            commands
+                .entity(entity)
+                .remove::<InterpolatedRemoveTimer<C>>();
+            continue;
+        }
+        if remove_timer.timer.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<(
+                C,
+                ConfirmedHistory<C>,
+                InterpolateStatus<C>,
+                InterpolatedRemoveTimer<C>,
+            )>();
+        }
+    }
+}
+
+/// Component added on an interpolated entity whose confirmed entity was despawned.
+///
+/// The interpolated entity keeps its last interpolated values until the timer finishes, and then gets despawned.
+/// If the entity is replicated again before that, the interpolated entity is reused and the timer is removed.
+/// See [`InterpolationConfig::despawn_grace_period`](crate::prelude::client::InterpolationConfig::despawn_grace_period).
+#[derive(Component, Debug)]
+pub struct InterpolatedDespawnTimer {
+    pub timer: Timer,
+    /// The remote entity that was replicated to the despawned confirmed entity
+    remote_entity: Option<Entity>,
+}
+
 /// Despawn interpolated entities when the confirmed entity gets despawned
 // TODO: we should despawn interpolated only when it reaches the latest confirmed snapshot?
 //  I suppose  we could add a DespawnedMarker, and the entity would get despawned as soon as it reaches the end of interpolation...
 //  not super priority but would be a nice to have
 pub(crate) fn despawn_interpolated(
     trigger: Trigger<OnRemove, Confirmed>,
+    config: Res<ClientConfig>,
     mut manager: ResMut<InterpolationManager>,
     mut commands: Commands,
 ) {
-    if let Some(interpolated) = manager
-        .interpolated_entity_map
-        .get_mut()
+    let entity_map = manager.interpolated_entity_map.get_mut();
+    let remote_entity = entity_map.confirmed_to_remote.remove(&trigger.entity());
+    if let Some(interpolated) = entity_map
         .confirmed_to_interpolated
         .remove(&trigger.entity())
     {
-        if let Some(entity_mut) = commands.get_entity(interpolated) {
-            entity_mut.despawn_recursive();
+        if let Some(mut entity_mut) = commands.get_entity(interpolated) {
+            let grace_period = config.interpolation.despawn_grace_period;
+            if grace_period.is_zero() {
+                entity_mut.despawn_recursive();
+            } else {
+                entity_mut.insert(InterpolatedDespawnTimer {
+                    timer: Timer::new(grace_period, TimerMode::Once),
+                    remote_entity,
+                });
+                if let Some(remote_entity) = remote_entity {
+                    entity_map
+                        .remote_to_despawning
+                        .insert(remote_entity, interpolated);
+                }
+            }
+        }
+    }
+}
+
+/// Despawn the interpolated entities whose despawn grace period has elapsed
+pub(crate) fn despawn_interpolated_after_grace_period(
+    time: Res<Time>,
+    mut manager: ResMut<InterpolationManager>,
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut InterpolatedDespawnTimer)>,
+) {
+    for (entity, mut despawn_timer) in query.iter_mut() {
+        if despawn_timer.timer.tick(time.delta()).finished() {
+            if let Some(remote_entity) = despawn_timer.remote_entity {
+                manager
+                    .interpolated_entity_map
+                    .get_mut()
+                    .remote_to_despawning
+                    .remove(&remote_entity);
+            }
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::default;
+
+    use super::*;
+    use crate::client::connection::ConnectionManager;
+    use crate::prelude::client::InterpolationConfig;
+    use crate::prelude::server::{RelevanceManager, Replicate, SyncTarget};
+    use crate::prelude::{ClientId, NetworkRelevanceMode, NetworkTarget, SharedConfig, TickConfig};
+    use crate::tests::protocol::ComponentSyncModeFull;
+    use crate::tests::stepper::{BevyStepper, TEST_CLIENT_ID};
+
+    /// Build a stepper where the interpolation grace period lasts 10 frames
+    fn grace_period_stepper() -> BevyStepper {
+        let frame_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(frame_duration),
+            ..default()
+        };
+        let client_config = ClientConfig {
+            interpolation: InterpolationConfig::default()
+                .with_despawn_grace_period(Duration::from_millis(100)),
+            ..default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, client_config, frame_duration);
+        stepper.init();
+        stepper
+    }
+
+    /// Spawn an interpolated entity on the server, and return the server, confirmed and interpolated entities
+    fn spawn_interpolated(
+        stepper: &mut BevyStepper,
+        relevance_mode: NetworkRelevanceMode,
+    ) -> (Entity, Entity, Entity) {
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((
+                Replicate {
+                    sync: SyncTarget {
+                        interpolation: NetworkTarget::All,
+                        ..default()
+                    },
+                    relevance_mode,
+                    ..default()
+                },
+                ComponentSyncModeFull(1.0),
+            ))
+            .id();
+        if relevance_mode == NetworkRelevanceMode::InterestManagement {
+            stepper
+                .server_app
+                .world_mut()
+                .resource_mut::<RelevanceManager>()
+                .gain_relevance(ClientId::Netcode(TEST_CLIENT_ID), server_entity);
+        }
+        for _ in 0..20 {
+            stepper.frame_step();
+        }
+        let confirmed = stepper
+            .client_app
+            .world()
+            .resource::<ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .expect("entity was not replicated to client");
+        let interpolated = stepper
+            .client_app
+            .world()
+            .get::<Confirmed>(confirmed)
+            .unwrap()
+            .interpolated
+            .unwrap();
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<ComponentSyncModeFull>(interpolated),
+            Some(&ComponentSyncModeFull(1.0))
+        );
+        (server_entity, confirmed, interpolated)
+    }
+
+    /// Check that the interpolated entity holds its last value during the grace period after its
+    /// confirmed entity was despawned, and is despawned afterwards
+    fn check_despawn_grace_period(
+        stepper: &mut BevyStepper,
+        confirmed: Entity,
+        interpolated: Entity,
+    ) {
+        stepper.frame_step();
+        stepper.frame_step();
+        assert!(stepper.client_app.world().get_entity(confirmed).is_none());
+
+        // the interpolated entity holds its last value during the grace period
+        for _ in 0..7 {
+            stepper.frame_step();
+            let entity = stepper
+                .client_app
+                .world()
+                .get_entity(interpolated)
+                .expect("interpolated entity was despawned during the grace period");
+            assert!(entity.contains::<InterpolatedDespawnTimer>());
+            assert_eq!(
+                entity.get::<ComponentSyncModeFull>(),
+                Some(&ComponentSyncModeFull(1.0))
+            );
+        }
+
+        // the interpolated entity is despawned after the grace period
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        assert!(stepper
+            .client_app
+            .world()
+            .get_entity(interpolated)
+            .is_none());
+    }
+
+    /// Check that the interpolated entity is kept with its last value during the grace period
+    /// after the server stopped replicating it
+    #[test]
+    fn test_despawn_grace_period() {
+        let mut stepper = grace_period_stepper();
+        let (server_entity, confirmed, interpolated) =
+            spawn_interpolated(&mut stepper, NetworkRelevanceMode::All);
+
+        // the server stops replicating the entity
+        stepper.server_app.world_mut().despawn(server_entity);
+        check_despawn_grace_period(&mut stepper, confirmed, interpolated);
+    }
+
+    /// Check that the interpolated entity is kept with its last value during the grace period
+    /// after the entity lost relevance for the client
+    #[test]
+    fn test_despawn_grace_period_relevance_loss() {
+        let mut stepper = grace_period_stepper();
+        let (server_entity, confirmed, interpolated) =
+            spawn_interpolated(&mut stepper, NetworkRelevanceMode::InterestManagement);
+
+        // the entity is not relevant to the client anymore
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<RelevanceManager>()
+            .lose_relevance(ClientId::Netcode(TEST_CLIENT_ID), server_entity);
+        check_despawn_grace_period(&mut stepper, confirmed, interpolated);
+        assert!(stepper
+            .server_app
+            .world()
+            .get_entity(server_entity)
+            .is_some());
+    }
+
+    /// Check that if the entity regains relevance during the grace period, the lingering
+    /// interpolated entity is reused instead of spawning a new one
+    #[test]
+    fn test_relevance_regained_during_grace_period() {
+        let mut stepper = grace_period_stepper();
+        let (server_entity, confirmed, interpolated) =
+            spawn_interpolated(&mut stepper, NetworkRelevanceMode::InterestManagement);
+
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<RelevanceManager>()
+            .lose_relevance(ClientId::Netcode(TEST_CLIENT_ID), server_entity);
+        for _ in 0..4 {
+            stepper.frame_step();
+        }
+        assert!(stepper.client_app.world().get_entity(confirmed).is_none());
+        assert!(stepper
+            .client_app
+            .world()
+            .entity(interpolated)
+            .contains::<InterpolatedDespawnTimer>());
+
+        // the entity regains relevance before the end of the grace period
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<RelevanceManager>()
+            .gain_relevance(ClientId::Netcode(TEST_CLIENT_ID), server_entity);
+        for _ in 0..4 {
+            stepper.frame_step();
+        }
+        let new_confirmed = stepper
+            .client_app
+            .world()
+            .resource::<ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .expect("entity was not replicated to client");
+        assert_ne!(new_confirmed, confirmed);
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<Confirmed>(new_confirmed)
+                .unwrap()
+                .interpolated,
+            Some(interpolated)
+        );
+        let entity = stepper.client_app.world().entity(interpolated);
+        assert!(!entity.contains::<InterpolatedDespawnTimer>());
+        assert_eq!(
+            entity.get::<Interpolated>().unwrap().confirmed_entity,
+            new_confirmed
+        );
+        assert_eq!(
+            stepper
+                .client_app
+                .world_mut()
+                .query::<&Interpolated>()
+                .iter(stepper.client_app.world())
+                .count(),
+            1
+        );
+
+        // the interpolated entity is not despawned when the initial grace period ends
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<ComponentSyncModeFull>(interpolated),
+            Some(&ComponentSyncModeFull(1.0))
+        );
+    }
+
+    /// Check that an interpolated component is kept with its last value during the grace period
+    /// after it was removed from the confirmed entity
+    #[test]
+    fn test_remove_component_grace_period() {
+        let mut stepper = grace_period_stepper();
+        let (server_entity, confirmed, interpolated) =
+            spawn_interpolated(&mut stepper, NetworkRelevanceMode::All);
+
+        stepper
+            .server_app
+            .world_mut()
+            .entity_mut(server_entity)
+            .remove::<ComponentSyncModeFull>();
+        stepper.frame_step();
+        stepper.frame_step();
+        assert!(stepper
+            .client_app
+            .world()
+            .get::<ComponentSyncModeFull>(confirmed)
+            .is_none());
+
+        // the interpolated component holds its last value during the grace period
+        for _ in 0..7 {
+            stepper.frame_step();
+            let entity = stepper.client_app.world().entity(interpolated);
+            assert!(entity.contains::<InterpolatedRemoveTimer<ComponentSyncModeFull>>());
+            assert_eq!(
+                entity.get::<ComponentSyncModeFull>(),
+                Some(&ComponentSyncModeFull(1.0))
+            );
+        }
+
+        // the interpolated component is removed after the grace period, but the entity is kept
+        for _ in 0..5 {
+            stepper.frame_step();
         }
+        let entity = stepper.client_app.world().entity(interpolated);
+        assert!(!entity.contains::<ComponentSyncModeFull>());
+        assert!(!entity.contains::<InterpolatedRemoveTimer<ComponentSyncModeFull>>());
     }
 }
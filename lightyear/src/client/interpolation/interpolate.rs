@@ -1,4 +1,4 @@
-use bevy::prelude::{Commands, Component, Entity, Query, Res, Without};
+use bevy::prelude::{Commands, Component, Entity, Query, Res, Time, Without};
 use tracing::{debug, trace};
 
 use crate::client::components::SyncComponent;
@@ -51,6 +51,7 @@ impl<C: Component> InterpolateStatus<C> {
 /// At the end of each frame, interpolate the components between the last 2 confirmed server states
 /// Invariant: start_tick <= current_interpolate_tick + overstep < end_tick
 pub(crate) fn update_interpolate_status<C: SyncComponent>(
+    component_registry: Res<ComponentRegistry>,
     config: Res<ClientConfig>,
     connection: Res<ConnectionManager>,
     tick_manager: Res<TickManager>,
@@ -75,6 +76,7 @@ pub(crate) fn update_interpolate_status<C: SyncComponent>(
     let current_interpolate_overstep = connection
         .sync_manager
         .interpolation_overstep(tick_manager.as_ref());
+    let dead_reckoning_samples = component_registry.dead_reckoning_samples::<C>();
     for (entity, component, mut status, mut history) in query.iter_mut() {
         let mut start = status.start.take();
         let mut end = status.end.take();
@@ -164,6 +166,13 @@ pub(crate) fn update_interpolate_status<C: SyncComponent>(
         // }
         // end = temp_end;
 
+        // keep the last start values, so that we can extrapolate from them when we don't have an end value
+        if let (Some(max_samples), Some((start_tick, start_value))) =
+            (dead_reckoning_samples, &start)
+        {
+            history.add_sample(*start_tick, start_value, max_samples);
+        }
+
         // If it's been too long since we received an update, reset the start tick to None
         // (so that we wait again until interpolation_tick is between two server updates)
         // otherwise the interpolation will seem weird because the start tick is very old
@@ -250,25 +259,75 @@ pub(crate) fn insert_interpolated_component<C: SyncComponent>(
     }
 }
 
+/// Critically-damped smoothing of a value towards a `target`.
+///
+/// The filter is made of two first-order low-pass filters in series with the same time constant, which
+/// is equivalent to a critically-damped spring: the output follows the target without overshooting, and
+/// its velocity changes continuously when the target changes direction.
+/// `state` contains the output of both stages, and `lerp` is the interpolation function of the component.
+pub(crate) fn smooth<C: Clone>(
+    state: &mut Option<(C, C)>,
+    target: &C,
+    delta: f32,
+    time_constant: f32,
+    lerp: impl Fn(&C, &C, f32) -> C,
+) -> C {
+    let (first, second) = state.get_or_insert_with(|| (target.clone(), target.clone()));
+    let alpha = if time_constant > 0.0 {
+        1.0 - (-delta / time_constant).exp()
+    } else {
+        1.0
+    };
+    *first = lerp(first, target, alpha);
+    *second = lerp(second, first, alpha);
+    second.clone()
+}
+
 /// Update the component value on the Interpolate entity
-pub(crate) fn interpolate<C: Component + Clone>(
+///
+/// If there is no end value to interpolate towards, the component is extrapolated with its
+/// [`DeadReckoning`](crate::prelude::DeadReckoning) model, if it has one.
+/// If the component has interpolation smoothing, the value is then smoothed with [`smooth`].
+pub(crate) fn interpolate<C: SyncComponent>(
     component_registry: Res<ComponentRegistry>,
-    mut query: Query<(&mut C, &InterpolateStatus<C>)>,
+    time: Res<Time>,
+    mut query: Query<(&mut C, &InterpolateStatus<C>, &mut ConfirmedHistory<C>)>,
 ) {
-    for (mut component, status) in query.iter_mut() {
+    let smoothing = component_registry.interpolation_smoothing::<C>();
+    for (mut component, status, mut history) in query.iter_mut() {
         debug!("checking if we do interpolation");
         // NOTE: it is possible that we reach start_tick when end_tick is not set
         if let Some((start_tick, start_value)) = &status.start {
-            if let Some((end_tick, end_value)) = &status.end {
+            let value = if let Some((end_tick, end_value)) = &status.end {
                 debug!(?start_tick, interpolate_tick=?status.current_tick, ?end_tick, "doing interpolation!");
                 assert!(status.current_tick < *end_tick);
                 if start_tick != end_tick {
                     let t = status.interpolation_fraction().unwrap();
-                    let value = component_registry.interpolate(start_value, end_value, t);
-                    *component = value;
+                    Some(component_registry.interpolate(start_value, end_value, t))
                 } else {
-                    *component = start_value.clone();
+                    Some(start_value.clone())
                 }
+            } else if !history.samples.is_empty() {
+                component_registry.extrapolate(
+                    &history.samples,
+                    status.current_tick,
+                    status.current_overstep,
+                )
+            } else {
+                None
+            };
+            if let Some(time_constant) = smoothing {
+                // if there is nothing to interpolate or extrapolate towards, converge to the last snapshot
+                let target = value.as_ref().unwrap_or(start_value);
+                *component = smooth(
+                    &mut history.smoothing,
+                    target,
+                    time.delta_seconds(),
+                    time_constant.as_secs_f32(),
+                    |start, end, t| component_registry.interpolate(start, end, t),
+                );
+            } else if let Some(value) = value {
+                *component = value;
             }
         }
     }
@@ -527,3 +586,81 @@ pub(crate) fn interpolate<C: Component + Clone>(
 //         Ok(())
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::{App, Update};
+    use bevy::utils::Duration;
+
+    use super::*;
+    use crate::client::components::ComponentSyncMode;
+    use crate::protocol::component::Linear;
+    use crate::tests::protocol::ComponentSyncModeFull;
+
+    /// Maximum change of velocity between two consecutive frames
+    fn max_acceleration(path: &[f32]) -> f32 {
+        path.windows(3)
+            .map(|w| (w[2] - 2.0 * w[1] + w[0]).abs())
+            .fold(0.0, f32::max)
+    }
+
+    /// Check that the smoothing removes the abrupt changes of direction of a zig-zag interpolation path
+    #[test]
+    fn test_smoothing_zig_zag() {
+        let delta = 1.0 / 60.0;
+        // snapshots alternate between 0.0 and 10.0, and we interpolate 10 frames between each snapshot
+        let snapshots = [0.0, 10.0, 0.0, 10.0, 0.0, 0.0, 0.0, 0.0];
+        let linear: Vec<f32> = snapshots
+            .windows(2)
+            .flat_map(|w| (0..10).map(move |i| f32::lerp(&w[0], &w[1], i as f32 / 10.0)))
+            .collect();
+        let mut state = None;
+        let smoothed: Vec<f32> = linear
+            .iter()
+            .map(|target| smooth(&mut state, target, delta, 0.03, f32::lerp))
+            .collect();
+
+        // the linear path changes velocity from +1.0 to -1.0 in a single frame
+        assert!((max_acceleration(&linear) - 2.0).abs() < 1e-3);
+        assert!(max_acceleration(&smoothed) < 1.0);
+        // the smoothed value reaches the target once it stops moving
+        assert!(smoothed.last().unwrap().abs() < 0.1);
+    }
+
+    /// Check that the interpolation system brings a smoothed entity that stopped moving
+    /// to the value of the last snapshot
+    #[test]
+    fn test_smoothing_stationary_entity() {
+        let mut registry = ComponentRegistry::default();
+        registry.set_interpolation_mode::<ComponentSyncModeFull>(ComponentSyncMode::Full);
+        registry.set_linear_interpolation::<ComponentSyncModeFull>();
+        registry.set_interpolation_smoothing::<ComponentSyncModeFull>(Duration::from_millis(30));
+        let mut app = App::new();
+        app.insert_resource(registry);
+        app.init_resource::<Time>();
+        app.add_systems(Update, interpolate::<ComponentSyncModeFull>);
+        // there is no end snapshot and no extrapolation
+        let entity = app
+            .world_mut()
+            .spawn((
+                ComponentSyncModeFull(0.0),
+                InterpolateStatus {
+                    start: Some((Tick(0), ComponentSyncModeFull(10.0))),
+                    end: None,
+                    current_tick: Tick(1),
+                    current_overstep: 0.0,
+                },
+                ConfirmedHistory::<ComponentSyncModeFull>::new(),
+            ))
+            .id();
+
+        for _ in 0..30 {
+            app.world_mut()
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_millis(16));
+            app.update();
+        }
+        let value = app.world().get::<ComponentSyncModeFull>(entity).unwrap().0;
+        assert!((value - 10.0).abs() < 0.1, "value: {value}");
+    }
+}
@@ -27,6 +27,12 @@ pub struct ConfirmedHistory<C: SyncComponent> {
 
     // We will only store the history for the ticks where the component got updated
     pub buffer: ReadyBuffer<Tick, C>,
+    /// Last values that were used as interpolation start, ordered from oldest to most recent.
+    /// Only stored if the component has a [`DeadReckoning`](crate::prelude::DeadReckoning) model
+    pub(crate) samples: Vec<(Tick, C)>,
+    /// State of the two stages of the smoothing filter.
+    /// Only stored if the component has interpolation smoothing enabled
+    pub(crate) smoothing: Option<(C, C)>,
 }
 
 impl<C: SyncComponent> Default for ConfirmedHistory<C> {
@@ -46,12 +52,16 @@ impl<C: SyncComponent> ConfirmedHistory<C> {
     pub fn new() -> Self {
         Self {
             buffer: ReadyBuffer::new(),
+            samples: Vec::new(),
+            smoothing: None,
         }
     }
 
     /// Reset the history for this component
     pub(crate) fn clear(&mut self) {
         self.buffer = ReadyBuffer::new();
+        self.samples.clear();
+        self.smoothing = None;
     }
 
     pub(crate) fn peek(&mut self) -> Option<(Tick, &C)> {
@@ -72,6 +82,21 @@ impl<C: SyncComponent> ConfirmedHistory<C> {
     pub(crate) fn pop_until_tick(&mut self, tick: Tick) -> Option<(Tick, C)> {
         self.buffer.pop_until(&tick)
     }
+
+    /// Record a new interpolation start value, keeping at most `max_samples` values
+    pub(crate) fn add_sample(&mut self, tick: Tick, value: &C, max_samples: usize) {
+        if self
+            .samples
+            .last()
+            .is_some_and(|(last_tick, _)| *last_tick >= tick)
+        {
+            return;
+        }
+        self.samples.push((tick, value.clone()));
+        if self.samples.len() > max_samples {
+            self.samples.drain(..self.samples.len() - max_samples);
+        }
+    }
 }
 
 // TODO: maybe add the component history on the Confirmed entity instead of Interpolated? would make more sense maybe
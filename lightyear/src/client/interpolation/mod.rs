@@ -3,9 +3,11 @@ use std::ops::{Add, Mul};
 
 use bevy::prelude::{Component, Entity, Reflect};
 
+pub use despawn::{InterpolatedDespawnTimer, InterpolatedRemoveTimer};
 pub use interpolate::InterpolateStatus;
 pub use interpolation_history::ConfirmedHistory;
 pub use plugin::{add_interpolation_systems, add_prepare_interpolation_systems};
+pub use spawn::EntityInterpolated;
 pub use visual_interpolation::{VisualInterpolateStatus, VisualInterpolationPlugin};
 
 use crate::client::components::LerpFn;
@@ -14,7 +16,7 @@ mod despawn;
 pub mod interpolate;
 pub mod interpolation_history;
 pub mod plugin;
-mod resource;
+pub(crate) mod resource;
 mod spawn;
 pub mod visual_interpolation;
 
@@ -2,12 +2,15 @@ use bevy::prelude::*;
 use bevy::utils::Duration;
 
 use crate::client::components::{ComponentSyncMode, SyncComponent};
-use crate::client::interpolation::despawn::{despawn_interpolated, removed_components};
+use crate::client::interpolation::despawn::{
+    despawn_interpolated, despawn_interpolated_after_grace_period,
+    remove_interpolated_components_after_grace_period, removed_components,
+};
 use crate::client::interpolation::interpolate::{
     insert_interpolated_component, interpolate, update_interpolate_status,
 };
 use crate::client::interpolation::resource::InterpolationManager;
-use crate::client::interpolation::spawn::spawn_interpolated_entity;
+use crate::client::interpolation::spawn::{spawn_interpolated_entity, EntityInterpolated};
 use crate::client::interpolation::Interpolated;
 use crate::client::run_conditions::is_synced;
 use crate::prelude::is_host_server;
@@ -62,6 +65,18 @@ impl InterpolationDelay {
 #[derive(Clone, Copy, Reflect)]
 pub struct InterpolationConfig {
     pub delay: InterpolationDelay,
+    /// How long an interpolated entity is kept (holding its last interpolated value) after its confirmed
+    /// entity was despawned, for example because the entity lost relevance for this client.
+    ///
+    /// This smooths out transient losses of relevance: the entity does not disappear abruptly.
+    /// The entity gets the [`InterpolatedDespawnTimer`](crate::prelude::client::InterpolatedDespawnTimer) component during the grace period.
+    /// If the entity is replicated again before the end of the grace period (for example because it regained
+    /// relevance), the lingering interpolated entity is reused instead of spawning a new one.
+    /// The same grace period applies when an interpolated component is removed from the confirmed entity: the
+    /// interpolated entity gets an [`InterpolatedRemoveTimer`](crate::prelude::client::InterpolatedRemoveTimer)
+    /// and keeps the last value of the component until the timer finishes.
+    /// Defaults to zero (the interpolated entity or component is removed immediately).
+    pub despawn_grace_period: Duration,
     // How long are we keeping the history of the confirmed entities so we can interpolate between them?
     // pub(crate) interpolation_buffer_size: Duration,
 }
@@ -71,6 +86,7 @@ impl Default for InterpolationConfig {
     fn default() -> Self {
         Self {
             delay: InterpolationDelay::default(),
+            despawn_grace_period: Duration::ZERO,
             // interpolation_buffer_size: Duration::from_millis(100),
         }
     }
@@ -81,6 +97,11 @@ impl InterpolationConfig {
         self.delay = delay;
         self
     }
+
+    pub fn with_despawn_grace_period(mut self, despawn_grace_period: Duration) -> Self {
+        self.despawn_grace_period = despawn_grace_period;
+        self
+    }
 }
 
 #[derive(Default)]
@@ -132,6 +153,11 @@ pub fn add_prepare_interpolation_systems<C: SyncComponent>(
         add_component_history::<C>.in_set(InterpolationSet::SpawnHistory),
     );
     app.observe(removed_components::<C>);
+    app.add_systems(
+        Update,
+        remove_interpolated_components_after_grace_period::<C>
+            .in_set(InterpolationSet::SpawnInterpolation),
+    );
     match interpolation_mode {
         ComponentSyncMode::Full => {
             app.add_systems(
@@ -178,6 +204,8 @@ impl Plugin for InterpolationPlugin {
 
         // RESOURCES
         app.init_resource::<InterpolationManager>();
+        // EVENTS
+        app.add_event::<EntityInterpolated>();
         // SETS
         app.configure_sets(
             Update,
@@ -197,7 +225,11 @@ impl Plugin for InterpolationPlugin {
         // SYSTEMS
         app.add_systems(
             Update,
-            spawn_interpolated_entity.in_set(InterpolationSet::SpawnInterpolation),
+            (
+                spawn_interpolated_entity,
+                despawn_interpolated_after_grace_period,
+            )
+                .in_set(InterpolationSet::SpawnInterpolation),
         );
         app.observe(despawn_interpolated);
     }
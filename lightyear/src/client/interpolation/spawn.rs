@@ -1,36 +1,97 @@
-use bevy::prelude::{Added, Commands, Entity, Query, Res, ResMut};
+use bevy::prelude::{
+    Added, Commands, DespawnRecursiveExt, DetectChanges, Entity, Event, EventWriter, Query, Res,
+    ResMut, World,
+};
 use tracing::trace;
 
 use crate::client::components::Confirmed;
 use crate::client::config::ClientConfig;
 use crate::client::connection::ConnectionManager;
+use crate::client::interpolation::despawn::InterpolatedDespawnTimer;
 use crate::client::interpolation::resource::InterpolationManager;
 use crate::client::interpolation::Interpolated;
+use crate::client::prediction::resource::PredictionManager;
 use crate::shared::replication::components::ShouldBeInterpolated;
 
+/// Event emitted on the client when a confirmed entity replicated from the server gets a corresponding
+/// [`Interpolated`] entity.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct EntityInterpolated {
+    /// The confirmed entity, replicated from the server
+    pub confirmed: Entity,
+    /// The interpolated entity
+    pub interpolated: Entity,
+}
+
 /// Spawn an interpolated entity for each confirmed entity that has the `ShouldBeInterpolated` component added
 pub(crate) fn spawn_interpolated_entity(
     config: Res<ClientConfig>,
     connection: Res<ConnectionManager>,
     mut manager: ResMut<InterpolationManager>,
     mut commands: Commands,
+    mut events: EventWriter<EntityInterpolated>,
     mut confirmed_entities: Query<(Entity, Option<&mut Confirmed>), Added<ShouldBeInterpolated>>,
 ) {
     for (confirmed_entity, confirmed) in confirmed_entities.iter_mut() {
-        let interpolated = commands.spawn(Interpolated { confirmed_entity }).id();
+        let entity_map = manager.interpolated_entity_map.get_mut();
+        let remote_entity = connection
+            .replication_receiver
+            .remote_entity_map
+            .get_remote(confirmed_entity);
+        // if the entity is replicated again while its previous interpolated entity is still in its
+        // despawn grace period, we reuse that interpolated entity
+        let lingering = remote_entity
+            .and_then(|remote| entity_map.remote_to_despawning.remove(&remote))
+            .filter(|interpolated| commands.get_entity(*interpolated).is_some());
+        let interpolated = if let Some(interpolated) = lingering {
+            trace!(
+                ?interpolated,
+                "Reusing interpolated entity in its despawn grace period"
+            );
+            commands
+                .entity(interpolated)
+                .remove::<InterpolatedDespawnTimer>()
+                .insert(Interpolated { confirmed_entity });
+            interpolated
+        } else {
+            commands.spawn(Interpolated { confirmed_entity }).id()
+        };
 
         // update the entity mapping
-        manager
-            .interpolated_entity_map
-            .get_mut()
+        entity_map
             .confirmed_to_interpolated
             .insert(confirmed_entity, interpolated);
+        if !config.interpolation.despawn_grace_period.is_zero() {
+            if let Some(remote_entity) = remote_entity {
+                entity_map
+                    .confirmed_to_remote
+                    .insert(confirmed_entity, remote_entity);
+            }
+        }
 
         // add Confirmed to the confirmed entity
         // safety: we know the entity exists
         let mut confirmed_entity_mut = commands.get_entity(confirmed_entity).unwrap();
         if let Some(mut confirmed) = confirmed {
             confirmed.interpolated = Some(interpolated);
+            // the entity was predicted until now (for example because we just lost control of it)
+            // (if `Confirmed` was just added, both copies are being spawned at the same time)
+            if !confirmed.is_added() {
+                if let Some(predicted) = confirmed.predicted.take() {
+                    commands.add(move |world: &mut World| {
+                        if let Some(mut manager) = world.get_resource_mut::<PredictionManager>() {
+                            manager
+                                .predicted_entity_map
+                                .get_mut()
+                                .confirmed_to_predicted
+                                .remove(&confirmed_entity);
+                        }
+                        if let Some(entity_mut) = world.get_entity_mut(predicted) {
+                            entity_mut.despawn_recursive();
+                        }
+                    });
+                }
+            }
         } else {
             // get the confirmed tick for the entity
             // if we don't have it, something has gone very wrong
@@ -53,5 +114,82 @@ pub(crate) fn spawn_interpolated_entity(
         {
             metrics::counter!("spawn_interpolated_entity").increment(1);
         }
+        events.send(EntityInterpolated {
+            confirmed: confirmed_entity,
+            interpolated,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::{default, EventReader, Resource, Update};
+
+    use super::*;
+    use crate::prelude::server::{Replicate, SyncTarget};
+    use crate::prelude::NetworkTarget;
+    use crate::tests::protocol::ComponentSyncModeFull;
+    use crate::tests::stepper::BevyStepper;
+
+    #[derive(Resource, Default)]
+    struct InterpolatedEvents(Vec<EntityInterpolated>);
+
+    fn collect_events(
+        mut events: EventReader<EntityInterpolated>,
+        mut collected: ResMut<InterpolatedEvents>,
+    ) {
+        collected.0.extend(events.read().copied());
+    }
+
+    /// Check that an event is emitted with the confirmed and interpolated entities when an interpolated entity is spawned
+    #[test]
+    fn test_entity_interpolated_event() {
+        let mut stepper = BevyStepper::default();
+        stepper.client_app.init_resource::<InterpolatedEvents>();
+        stepper.client_app.add_systems(Update, collect_events);
+
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((
+                Replicate {
+                    sync: SyncTarget {
+                        interpolation: NetworkTarget::All,
+                        ..default()
+                    },
+                    ..default()
+                },
+                ComponentSyncModeFull(1.0),
+            ))
+            .id();
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        let confirmed = stepper
+            .client_app
+            .world()
+            .resource::<ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .expect("entity was not replicated to client");
+        let interpolated = stepper
+            .client_app
+            .world()
+            .get::<Confirmed>(confirmed)
+            .unwrap()
+            .interpolated
+            .expect("interpolated entity was not spawned");
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .resource::<InterpolatedEvents>()
+                .0,
+            vec![EntityInterpolated {
+                confirmed,
+                interpolated
+            }]
+        );
     }
 }
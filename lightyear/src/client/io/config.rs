@@ -1,5 +1,7 @@
 use crate::client::io::transport::{ClientTransportBuilder, ClientTransportBuilderEnum};
 use crate::client::io::{Io, IoContext};
+#[cfg(all(feature = "steam", not(target_family = "wasm")))]
+use crate::connection::steam::{client::SocketConfig, steamworks_client::SteamworksClient};
 use crate::prelude::CompressionConfig;
 use crate::transport::config::SharedIoConfig;
 use crate::transport::dummy::DummyIo;
@@ -12,6 +14,8 @@ use crate::transport::middleware::compression::zstd::compression::ZstdCompressor
 use crate::transport::middleware::compression::zstd::decompression::ZstdDecompressor;
 use crate::transport::middleware::conditioner::LinkConditioner;
 use crate::transport::middleware::PacketReceiverWrapper;
+#[cfg(all(feature = "steam", not(target_family = "wasm")))]
+use crate::transport::steam::SteamClientSocketBuilder;
 #[cfg(not(target_family = "wasm"))]
 use crate::transport::udp::UdpSocketBuilder;
 #[cfg(feature = "websocket")]
@@ -21,7 +25,11 @@ use crate::transport::webtransport::client::WebTransportClientSocketBuilder;
 use crate::transport::{BoxedReceiver, Transport, LOCAL_SOCKET};
 use bevy::prelude::TypePath;
 use crossbeam_channel::{Receiver, Sender};
+#[cfg(all(feature = "steam", not(target_family = "wasm")))]
+use parking_lot::RwLock;
 use std::net::SocketAddr;
+#[cfg(all(feature = "steam", not(target_family = "wasm")))]
+use std::sync::Arc;
 
 /// Use this to configure the [`Transport`] that will be used to establish a connection with the
 /// server.
@@ -48,6 +56,18 @@ pub enum ClientTransport {
         recv: Receiver<Vec<u8>>,
         send: Sender<Vec<u8>>,
     },
+    /// Use a Steam socket as a transport, with netcode running on top of it.
+    ///
+    /// Steam only carries the bytes: the connection is authenticated and encrypted by netcode with
+    /// lightyear's connect tokens, instead of relying on Steam's authentication.
+    /// See [`steam`](crate::transport::steam) for how Steam peers are addressed.
+    #[cfg(all(feature = "steam", not(target_family = "wasm")))]
+    Steam {
+        /// The Steamworks client to use. If None, one will be created from the `app_id`
+        steamworks_client: Option<Arc<RwLock<SteamworksClient>>>,
+        app_id: u32,
+        socket_config: SocketConfig,
+    },
     /// Dummy transport if the connection handles its own io (for example steam sockets)
     Dummy,
 }
@@ -86,6 +106,16 @@ impl ClientTransport {
             ClientTransport::LocalChannel { recv, send } => {
                 ClientTransportBuilderEnum::LocalChannel(LocalChannelBuilder { recv, send })
             }
+            #[cfg(all(feature = "steam", not(target_family = "wasm")))]
+            ClientTransport::Steam {
+                steamworks_client,
+                app_id,
+                socket_config,
+            } => ClientTransportBuilderEnum::Steam(SteamClientSocketBuilder {
+                steamworks_client: steamworks_client
+                    .unwrap_or_else(|| Arc::new(RwLock::new(SteamworksClient::new(app_id)))),
+                socket_config,
+            }),
             ClientTransport::Dummy => ClientTransportBuilderEnum::Dummy(DummyIo),
         }
     }
@@ -117,6 +147,13 @@ impl SharedIoConfig<ClientTransport> {
         } else {
             Box::new(receiver)
         };
+        // the transform is applied to the final bytes sent on the wire, so it wraps the
+        // transport before the compression middleware
+        if let Some(transform) = self.transform {
+            use crate::transport::middleware::PacketSenderWrapper;
+            sender = Box::new(PacketSenderWrapper::wrap(transform.clone(), sender));
+            receiver = Box::new(PacketReceiverWrapper::wrap(transform, receiver));
+        }
         match self.compression {
             CompressionConfig::None => {}
             #[cfg(feature = "zstd")]
@@ -138,6 +175,10 @@ impl SharedIoConfig<ClientTransport> {
                 receiver = Box::new(decompressor.wrap(receiver));
             }
         }
+        // the whole middleware stack runs on the io threads
+        if let Some(threaded) = self.threaded {
+            (sender, receiver) = threaded.spawn(sender, receiver);
+        }
         Ok(BaseIo {
             local_addr,
             sender,
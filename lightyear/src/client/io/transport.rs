@@ -3,6 +3,8 @@ use crate::transport::dummy::DummyIo;
 use crate::transport::error::Error as TransportError;
 use crate::transport::io::IoState;
 use crate::transport::local::{LocalChannel, LocalChannelBuilder};
+#[cfg(all(feature = "steam", not(target_family = "wasm")))]
+use crate::transport::steam::{SteamClientSocket, SteamClientSocketBuilder};
 #[cfg(not(target_family = "wasm"))]
 use crate::transport::udp::{UdpSocket, UdpSocketBuilder};
 #[cfg(feature = "websocket")]
@@ -41,6 +43,8 @@ pub(crate) enum ClientTransportBuilderEnum {
     WebTransportClient(WebTransportClientSocketBuilder),
     #[cfg(feature = "websocket")]
     WebSocketClient(WebSocketClientSocketBuilder),
+    #[cfg(all(feature = "steam", not(target_family = "wasm")))]
+    Steam(SteamClientSocketBuilder),
     LocalChannel(LocalChannelBuilder),
     Dummy(DummyIo),
 }
@@ -54,6 +58,8 @@ pub(crate) enum ClientTransportEnum {
     WebTransportClient(WebTransportClientSocket),
     #[cfg(feature = "websocket")]
     WebSocketClient(WebSocketClientSocket),
+    #[cfg(all(feature = "steam", not(target_family = "wasm")))]
+    Steam(SteamClientSocket),
     LocalChannel(LocalChannel),
     Dummy(DummyIo),
 }
@@ -193,10 +193,19 @@ pub(crate) fn add_client_receive_message_from_server<M: Message>(app: &mut App)
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::channel::builder::{
+        Channel, ChannelDirection, DefaultOrderedReliableChannel, NotificationChannel,
+    };
+    use crate::client::config::ClientConfig;
+    use crate::client::error::ClientError;
+    use crate::prelude::{SharedConfig, TickConfig};
+    use crate::protocol::message::MessageError;
     use crate::serialize::writer::Writer;
     use crate::tests::host_server_stepper::HostServerStepper;
     use crate::tests::protocol::{Channel1, StringMessage};
-    use bevy::prelude::{EventReader, Resource, Update};
+    use crate::tests::stepper::BevyStepper;
+    use bevy::prelude::{default, EventReader, Resource, Update};
+    use bevy::utils::Duration;
 
     #[test]
     fn client_message_serde() {
@@ -250,4 +259,131 @@ mod tests {
         // verify that the server received the message
         assert_eq!(stepper.server_app.world().resource::<Counter>().0, 1);
     }
+
+    /// The `DefaultOrderedReliableChannel` is registered by default, so messages can be sent on it
+    /// without registering any channel
+    #[test]
+    fn client_send_message_on_default_channel() {
+        let mut stepper = BevyStepper::default();
+
+        stepper.server_app.init_resource::<Counter>();
+        stepper.server_app.add_systems(Update, count_messages);
+
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<crate::prelude::client::ConnectionManager>()
+            .send_message::<DefaultOrderedReliableChannel, StringMessage>(&mut StringMessage(
+                "a".to_string(),
+            ))
+            .unwrap();
+        stepper.frame_step();
+        stepper.frame_step();
+
+        assert_eq!(stepper.server_app.world().resource::<Counter>().0, 1);
+    }
+
+    /// A message sent for a future tick is only processed once the server reaches that tick
+    #[test]
+    fn client_send_message_at_tick() {
+        let mut stepper = BevyStepper::default();
+        stepper.server_app.init_resource::<Counter>();
+        stepper.server_app.add_systems(Update, count_messages);
+
+        let tick = stepper.server_tick() + 10;
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<crate::prelude::client::ConnectionManager>()
+            .send_message_at_tick::<Channel1, StringMessage>(
+                &mut StringMessage("a".to_string()),
+                NetworkTarget::None,
+                tick,
+            )
+            .unwrap();
+        for _ in 0..20 {
+            stepper.frame_step();
+            // the message is read at the start of the frame, before the tick is incremented
+            let expected = if stepper.server_tick() > tick { 1 } else { 0 };
+            assert_eq!(stepper.server_app.world().resource::<Counter>().0, expected);
+        }
+    }
+
+    /// Sending a message that requires the client to be synced returns an error before the sync is complete
+    #[test]
+    fn client_send_message_if_synced() {
+        let frame_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(frame_duration),
+            ..default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, ClientConfig::default(), frame_duration);
+        stepper.server_app.init_resource::<Counter>();
+        stepper.server_app.add_systems(Update, count_messages);
+        stepper.build();
+
+        let result = stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<crate::prelude::client::ConnectionManager>()
+            .send_message_if_synced::<Channel1, StringMessage>(&mut StringMessage("a".to_string()));
+        assert!(matches!(result, Err(ClientError::NotSynced)));
+
+        // connect and wait for the sync to complete
+        stepper.start();
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<crate::prelude::client::ConnectionManager>()
+            .send_message_if_synced::<Channel1, StringMessage>(&mut StringMessage("a".to_string()))
+            .unwrap();
+        stepper.frame_step();
+        stepper.frame_step();
+        assert_eq!(stepper.server_app.world().resource::<Counter>().0, 1);
+    }
+
+    /// The local client of a HostServer app never syncs, but it shares the server's tick so messages
+    /// that require the client to be synced can be sent right away
+    #[test]
+    fn client_send_message_if_synced_as_host_server_client() {
+        let mut stepper = HostServerStepper::default();
+        stepper.server_app.init_resource::<Counter>();
+        stepper.server_app.add_systems(Update, count_messages);
+
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<crate::prelude::client::ConnectionManager>()
+            .send_message_if_synced::<Channel1, StringMessage>(&mut StringMessage("a".to_string()))
+            .unwrap();
+        stepper.frame_step();
+        stepper.frame_step();
+
+        assert_eq!(stepper.server_app.world().resource::<Counter>().0, 1);
+    }
+
+    /// Sending a message on a `ServerToClient` channel from the client returns an error
+    #[test]
+    fn client_send_message_wrong_channel_direction() {
+        let mut stepper = BevyStepper::default();
+
+        let result = stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<crate::prelude::client::ConnectionManager>()
+            .send_message::<NotificationChannel, StringMessage>(&mut StringMessage(
+                "a".to_string(),
+            ));
+        let Err(ClientError::MessageProtocolError(MessageError::WrongChannelDirection {
+            channel,
+            direction,
+            sender,
+        })) = result
+        else {
+            panic!("expected a WrongChannelDirection error, got {result:?}");
+        };
+        assert_eq!(channel, NotificationChannel::name());
+        assert_eq!(direction, ChannelDirection::ServerToClient);
+        assert_eq!(sender, "client");
+    }
 }
@@ -7,9 +7,10 @@ use bevy::prelude::ResMut;
 use bevy::prelude::*;
 use tracing::{error, trace};
 
+use crate::channel::builder::InputRecoveryChannel;
 use crate::client::config::ClientConfig;
 use crate::client::connection::ConnectionManager;
-use crate::client::events::{ConnectEvent, DisconnectEvent};
+use crate::client::events::{ConnectEvent, DisconnectEvent, MessageEvent, SyncEvent};
 use crate::client::interpolation::Interpolated;
 use crate::client::io::ClientIoEvent;
 use crate::client::networking::utils::AppStateExt;
@@ -17,16 +18,22 @@ use crate::client::prediction::Predicted;
 use crate::client::replication::send::ReplicateToServer;
 use crate::client::run_conditions::is_disconnected;
 use crate::client::sync::SyncSet;
-use crate::connection::client::{ClientConnection, ConnectionState, DisconnectReason, NetClient};
+use crate::connection::client::{
+    ClientConnection, ConnectionError, ConnectionState, DisconnectReason, NetClient, NetConfig,
+};
 use crate::connection::server::IoConfig;
+use crate::packet::packet_builder::Payload;
 use crate::prelude::{
     is_host_server, ChannelRegistry, MainSet, MessageRegistry, TickManager, TimeManager,
 };
 use crate::protocol::component::ComponentRegistry;
+use crate::protocol::message;
 use crate::server::clients::ControlledEntities;
 use crate::shared::config::Mode;
+use crate::shared::input::{InputDelayCommand, ViewDelay};
 use crate::shared::replication::components::Replicated;
 use crate::shared::sets::{ClientMarker, InternalMainSet};
+use crate::shared::tick_manager::ServerWideTick;
 use crate::transport::io::IoState;
 
 #[derive(Default)]
@@ -78,9 +85,18 @@ impl Plugin for ClientNetworkingPlugin {
             )
             .add_systems(
                 PreUpdate,
-                (listen_io_state, (receive_packets, receive).chain())
+                (
+                    listen_io_state,
+                    (receive_packets, receive, handle_unknown_messages).chain(),
+                )
                     .in_set(InternalMainSet::<ClientMarker>::Receive),
             )
+            .add_systems(
+                PreUpdate,
+                (receive_input_delay_commands, receive_server_wide_tick)
+                    .after(InternalMainSet::<ClientMarker>::EmitEvents)
+                    .run_if(not(is_host_server.or_else(is_disconnected))),
+            )
             // TODO: make HostServer a computed state?
             .add_systems(
                 PostUpdate,
@@ -92,6 +108,11 @@ impl Plugin for ClientNetworkingPlugin {
                         .in_set(InternalMainSet::<ClientMarker>::Send),
                     // TODO: update virtual time with Time<Real> so we have more accurate time at Send time.
                     sync_update.in_set(SyncSet),
+                    // measure the view delay before the interpolation time is updated, so that it matches
+                    // the interpolation time that was used to display the entities during this frame
+                    send_view_delay
+                        .before(SyncSet)
+                        .run_if(not(is_host_server.or_else(is_disconnected))),
                 ),
             );
 
@@ -204,6 +225,12 @@ pub(crate) fn receive(world: &mut World) {
         .inspect_err(|e| error!("Error receiving packets: {}", e));
 }
 
+/// Pass the received messages with an unknown net id to the [`UnknownMessageFn`](crate::prelude::UnknownMessageFn)
+pub(crate) fn handle_unknown_messages(world: &mut World) {
+    let messages = std::mem::take(&mut world.resource_mut::<ConnectionManager>().unknown_messages);
+    message::handle_unknown_messages(world, messages);
+}
+
 pub(crate) fn send(
     mut netcode: ResMut<ClientConnection>,
     system_change_tick: SystemChangeTick,
@@ -213,19 +240,31 @@ pub(crate) fn send(
 ) {
     trace!("Send packets to server");
     // SEND_PACKETS: send buffered packets to io
+    let split_reliable = netcode.supports_reliable_send();
     let packet_bytes = connection
-        .send_packets(time_manager.as_ref(), tick_manager.as_ref())
+        .send_packets(time_manager.as_ref(), tick_manager.as_ref(), split_reliable)
         .unwrap();
-    for packet_byte in packet_bytes {
-        let _ = netcode.send(packet_byte.as_slice()).map_err(|e| {
-            error!("Error sending packet: {}", e);
-        });
-    }
+    send_packets(netcode.deref_mut(), packet_bytes);
 
     // no need to clear the connection, because we already std::mem::take it
     // client.connection.clear();
 }
 
+/// Send the packets to the transport. The packets that contain messages from a reliable channel are sent
+/// with [`NetClient::send_reliable`]
+fn send_packets(netclient: &mut impl NetClient, packets: Vec<(Payload, bool)>) {
+    for (packet_byte, reliable) in packets {
+        let result = if reliable {
+            netclient.send_reliable(packet_byte.as_slice())
+        } else {
+            netclient.send(packet_byte.as_slice())
+        };
+        let _ = result.map_err(|e| {
+            error!("Error sending packet: {}", e);
+        });
+    }
+}
+
 /// Send messages in host-server mode
 /// We cannot use the normal `send` function because there is no IO available
 pub(crate) fn send_host_server(
@@ -258,8 +297,17 @@ pub(crate) fn sync_update(
     mut time_manager: ResMut<TimeManager>,
     mut tick_manager: ResMut<TickManager>,
     mut virtual_time: ResMut<Time<Virtual>>,
+    mut sync_event_writer: EventWriter<SyncEvent>,
 ) {
     let connection = connection.into_inner();
+    // if pings are disabled, use the rtt measured by the transport
+    if !connection.ping_manager.is_enabled() {
+        if let Some(rtt) = netclient.rtt() {
+            let jitter = connection.ping_manager.jitter();
+            connection.ping_manager.set_external_stats(rtt, jitter);
+        }
+    }
+    let was_synced = connection.sync_manager.is_synced();
     // NOTE: this triggers change detection
     // Handle pongs, update RTT estimates, update client prediction time
     if let Some(tick_event) = connection.sync_manager.update(
@@ -272,6 +320,10 @@ pub(crate) fn sync_update(
     ) {
         commands.trigger(tick_event);
     }
+    if !was_synced && connection.sync_manager.is_synced() {
+        sync_event_writer.send(SyncEvent);
+        commands.trigger(SyncEvent);
+    }
 
     if connection.sync_manager.is_synced() {
         if let Some(tick_event) = connection.sync_manager.update_prediction_time(
@@ -286,6 +338,77 @@ pub(crate) fn sync_update(
     }
 }
 
+/// Apply the input delay set by the server with an [`InputDelayCommand`].
+///
+/// The prediction time is then re-synced in [`sync_update`] to account for the new input delay.
+fn receive_input_delay_commands(
+    mut messages: ResMut<Events<MessageEvent<InputDelayCommand>>>,
+    mut connection: ResMut<ConnectionManager>,
+) {
+    for message in messages.drain() {
+        debug!(
+            input_delay_ticks = ?message.message.input_delay_ticks,
+            "Received input delay from the server"
+        );
+        connection.sync_manager.server_input_delay_ticks = message.message.input_delay_ticks;
+    }
+}
+
+/// Use the [`ServerWideTick`] sent by the server when we connect to know its tick generation.
+///
+/// With pings enabled, the generation is already updated from every pong.
+fn receive_server_wide_tick(
+    mut messages: ResMut<Events<MessageEvent<ServerWideTick>>>,
+    mut connection: ResMut<ConnectionManager>,
+    mut tick_manager: ResMut<TickManager>,
+) {
+    for message in messages.drain() {
+        if connection.ping_manager.is_enabled() {
+            continue;
+        }
+        debug!(wide_tick = ?message.message.0, "Received the server wide tick");
+        connection
+            .sync_manager
+            .set_server_wide_tick(message.message.0, tick_manager.as_mut());
+    }
+}
+
+/// Minimum change of the interpolation delay (in ticks) before a new [`ViewDelay`] is sent to the server
+const VIEW_DELAY_THRESHOLD: f32 = 0.1;
+
+/// Send the [`ViewDelay`] of the client to the server when it changes, so that the server
+/// knows what the client was seeing when it sent its inputs.
+fn send_view_delay(
+    tick_manager: Res<TickManager>,
+    mut connection: ResMut<ConnectionManager>,
+    mut last_sent: Local<Option<ViewDelay>>,
+) {
+    if !connection.sync_manager.is_synced() {
+        return;
+    }
+    let interpolation_tick = connection.sync_manager.interpolation_tick(&tick_manager);
+    let interpolation_overstep = connection
+        .sync_manager
+        .interpolation_overstep(&tick_manager);
+    let mut view_delay = ViewDelay {
+        input_delay_ticks: connection.input_delay_ticks(tick_manager.config.tick_duration),
+        interpolation_delay_ticks: (tick_manager.tick() - interpolation_tick) as f32
+            - interpolation_overstep,
+    };
+    if last_sent.is_some_and(|last| {
+        last.input_delay_ticks == view_delay.input_delay_ticks
+            && (last.interpolation_delay_ticks - view_delay.interpolation_delay_ticks).abs()
+                < VIEW_DELAY_THRESHOLD
+    }) {
+        return;
+    }
+    if let Err(e) = connection.send_message::<InputRecoveryChannel, _>(&mut view_delay) {
+        error!("Could not send the view delay to the server: {e:?}");
+        return;
+    }
+    *last_sent = Some(view_delay);
+}
+
 /// Bevy [`State`] representing the networking state of the client.
 #[derive(States, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum NetworkingState {
@@ -377,6 +500,7 @@ fn on_connect_host_server(
     mut metadata: ResMut<HostServerMetadata>,
     mut server_manager: ResMut<crate::server::connection::ConnectionManager>,
     mut connect_event_writer: EventWriter<ConnectEvent>,
+    mut sync_event_writer: EventWriter<SyncEvent>,
 ) {
     // spawn an entity for the client
     let client_entity = commands.spawn(ControlledEntities::default()).id();
@@ -390,6 +514,9 @@ fn on_connect_host_server(
     connect_event_writer.send(ConnectEvent::new(netcode.id()));
     // also trigger the event
     commands.trigger(ConnectEvent::new(netcode.id()));
+    // the local client shares the server's tick, so it is synced as soon as it is connected
+    sync_event_writer.send(SyncEvent);
+    commands.trigger(SyncEvent);
 }
 
 /// System that runs when we enter the Disconnected state
@@ -445,13 +572,6 @@ fn on_disconnect_host_server(
 /// - we can take into account any changes to the client config
 fn rebuild_client_connection(world: &mut World) {
     let client_config = world.resource::<ClientConfig>().clone();
-    // if client_config.shared.mode == Mode::HostServer {
-    //     assert!(
-    //         matches!(client_config.net, NetConfig::Local { .. }),
-    //         "When running in HostServer mode, the client connection needs to be of type Local"
-    //     );
-    // }
-
     // insert a new connection manager (to reset sync, priority, message numbers, etc.)
     let connection_manager = ConnectionManager::new(
         world.resource::<ComponentRegistry>(),
@@ -481,6 +601,19 @@ fn connect(world: &mut World) {
     //     error!("The client is already started. The client can only start connecting when it is disconnected.");
     // }
 
+    // the host-client must use a Local connection so that its ClientId is distinct from the ids of remote clients
+    if let Err(e) = check_host_server_net_config(world.resource::<ClientConfig>()) {
+        error!("Refusing to connect the client: {}", e);
+        world
+            .resource_mut::<NextState<NetworkingState>>()
+            .set(NetworkingState::Disconnected);
+        // the OnEnter(Disconnected) systems don't emit a DisconnectEvent in HostServer mode
+        world.send_event(DisconnectEvent {
+            reason: Some(DisconnectReason::Refused(e)),
+        });
+        return;
+    }
+
     // Everytime we try to connect, we rebuild the net config because:
     // - we do not call update() while the client is disconnected, so the internal connection's time is wrong
     // - this allows us to take into account any changes to the client config (when building a
@@ -509,6 +642,14 @@ fn connect(world: &mut World) {
     }
 }
 
+/// Returns an error if the client runs in HostServer mode without a [`NetConfig::Local`] connection
+fn check_host_server_net_config(config: &ClientConfig) -> Result<(), ConnectionError> {
+    if config.shared.mode == Mode::HostServer && !matches!(config.net, NetConfig::Local { .. }) {
+        return Err(ConnectionError::HostServerNotLocal);
+    }
+    Ok(())
+}
+
 pub trait ClientCommands {
     /// Start the connection process
     fn connect_client(&mut self);
@@ -554,19 +695,96 @@ mod utils {
 #[cfg(test)]
 mod tests {
 
+    use std::net::SocketAddr;
     use std::time::Duration;
 
     use bevy::prelude::*;
 
+    use super::send_packets;
+    use crate::client::io::Io;
+    use crate::client::run_conditions::just_synced;
+    use crate::connection::client::{ConnectionError, ConnectionState, NetClient};
+    use crate::packet::packet_builder::RecvPayload;
     use crate::{
-        client::config::ClientConfig,
-        prelude::{client::ClientCommands, server::*, SharedConfig, TickConfig},
-        tests::host_server_stepper::HostServerStepper,
+        client::{config::ClientConfig, networking::NetworkingState},
+        connection::client::NetConfig,
+        prelude::{client::ClientCommands, server::*, ClientId, SharedConfig, TickConfig},
+        tests::host_server_stepper::{HostServerStepper, EXTERNAL_CLIENT_ID},
+        tests::stepper::{BevyStepper, TEST_CLIENT_ID},
     };
 
     #[derive(Resource, Default)]
     struct CheckCounter(usize);
 
+    /// Transport that records how each packet was sent
+    #[derive(Default)]
+    struct RecordingClient {
+        sent: Vec<(Vec<u8>, bool)>,
+    }
+
+    impl NetClient for RecordingClient {
+        fn connect(&mut self) -> Result<(), ConnectionError> {
+            Ok(())
+        }
+
+        fn disconnect(&mut self) -> Result<(), ConnectionError> {
+            Ok(())
+        }
+
+        fn state(&self) -> ConnectionState {
+            ConnectionState::Connected
+        }
+
+        fn try_update(&mut self, _: f64) -> Result<(), ConnectionError> {
+            Ok(())
+        }
+
+        fn recv(&mut self) -> Option<RecvPayload> {
+            None
+        }
+
+        fn send(&mut self, buf: &[u8]) -> Result<(), ConnectionError> {
+            self.sent.push((buf.to_vec(), false));
+            Ok(())
+        }
+
+        fn send_reliable(&mut self, buf: &[u8]) -> Result<(), ConnectionError> {
+            self.sent.push((buf.to_vec(), true));
+            Ok(())
+        }
+
+        fn id(&self) -> ClientId {
+            ClientId::Local(0)
+        }
+
+        fn local_addr(&self) -> SocketAddr {
+            SocketAddr::from(([127, 0, 0, 1], 0))
+        }
+
+        fn io(&self) -> Option<&Io> {
+            None
+        }
+
+        fn io_mut(&mut self) -> Option<&mut Io> {
+            None
+        }
+    }
+
+    /// Check that the packets with messages from reliable channels go through `send_reliable`,
+    /// which transports with their own reliability layer (like Steam) use to send them reliably
+    #[test]
+    fn test_send_packets_reliable() {
+        let mut netclient = RecordingClient::default();
+        send_packets(
+            &mut netclient,
+            vec![(vec![0], false), (vec![1], true), (vec![2], false)],
+        );
+        assert_eq!(
+            netclient.sent,
+            vec![(vec![0], false), (vec![1], true), (vec![2], false)]
+        );
+    }
+
     fn receive_connect_event(mut reader: EventReader<ConnectEvent>, mut res: ResMut<CheckCounter>) {
         for event in reader.read() {
             res.0 += 1;
@@ -621,4 +839,277 @@ mod tests {
         stepper.frame_step();
         assert_eq!(stepper.server_app.world().resource::<CheckCounter>().0, 2); // 2 because local client as well as external client disconnect
     }
+
+    /// Check that the id of the host-client does not collide with the id of a netcode client,
+    /// even if they use the same raw id
+    #[test]
+    fn test_host_server_local_client_id() {
+        let mut stepper = HostServerStepper::default_no_init();
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ClientConfig>()
+            .net = NetConfig::Local {
+            id: EXTERNAL_CLIENT_ID,
+        };
+        stepper.init();
+
+        let local_id = ClientId::Local(EXTERNAL_CLIENT_ID);
+        let external_id = ClientId::Netcode(EXTERNAL_CLIENT_ID);
+        assert_ne!(local_id, external_id);
+        let server_manager = stepper.server_app.world().resource::<ConnectionManager>();
+        assert_eq!(server_manager.connected_clients().count(), 2);
+        assert!(server_manager
+            .connection(local_id)
+            .unwrap()
+            .is_local_client());
+        assert!(!server_manager
+            .connection(external_id)
+            .unwrap()
+            .is_local_client());
+        assert_ne!(
+            server_manager.client_entity(local_id).unwrap(),
+            server_manager.client_entity(external_id).unwrap()
+        );
+    }
+
+    fn count_syncs(mut res: ResMut<CheckCounter>) {
+        res.0 += 1;
+    }
+
+    /// Check that the `just_synced` run condition runs once when the client becomes synced,
+    /// and again after a reconnection
+    #[test]
+    fn test_just_synced() {
+        let frame_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(frame_duration),
+            ..Default::default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, ClientConfig::default(), frame_duration);
+        stepper
+            .client_app
+            .init_resource::<CheckCounter>()
+            .add_systems(Update, count_syncs.run_if(just_synced));
+        stepper.init();
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        assert_eq!(stepper.client_app.world().resource::<CheckCounter>().0, 1);
+
+        // reconnect
+        stepper.stop();
+        stepper.start();
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        assert_eq!(stepper.client_app.world().resource::<CheckCounter>().0, 2);
+    }
+
+    /// The local client of a HostServer app doesn't sync, but a SyncEvent is still emitted once
+    /// when it connects
+    #[test]
+    fn test_just_synced_host_server() {
+        let mut stepper = HostServerStepper::default_no_init();
+        stepper
+            .server_app
+            .init_resource::<CheckCounter>()
+            .add_systems(Update, count_syncs.run_if(just_synced));
+        stepper.init();
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        assert_eq!(stepper.server_app.world().resource::<CheckCounter>().0, 1);
+    }
+
+    #[derive(Resource, Default)]
+    struct ClientDisconnects(Vec<Option<crate::connection::client::DisconnectReason>>);
+
+    fn receive_client_disconnect_event(
+        mut reader: ResMut<Events<crate::client::events::DisconnectEvent>>,
+        mut res: ResMut<ClientDisconnects>,
+    ) {
+        for event in reader.drain() {
+            res.0.push(event.reason);
+        }
+    }
+
+    /// Check that a disconnection triggered between two updates is reported exactly once,
+    /// during the next update of the server
+    #[test]
+    fn test_disconnect_reported_next_update() {
+        let mut stepper = BevyStepper::default();
+        stepper
+            .server_app
+            .init_resource::<CheckCounter>()
+            .add_systems(Update, receive_disconnect_event);
+
+        let client_id = ClientId::Netcode(TEST_CLIENT_ID);
+        let mut connections = stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ServerConnections>();
+        connections.disconnect(client_id).unwrap();
+        // the disconnection is deferred to the next update
+        assert!(connections.servers[0].new_disconnections().is_empty());
+
+        stepper.frame_step();
+        assert_eq!(stepper.server_app.world().resource::<CheckCounter>().0, 1);
+        assert!(stepper
+            .server_app
+            .world()
+            .resource::<ConnectionManager>()
+            .connection(client_id)
+            .is_err());
+        stepper.frame_step();
+        assert_eq!(stepper.server_app.world().resource::<CheckCounter>().0, 1);
+    }
+
+    /// Check that a simulated disconnect is handled immediately on the server, and that the
+    /// client only disconnects once it times out, as if the network had been cut
+    #[test]
+    fn test_simulate_disconnect() {
+        let mut stepper = BevyStepper::default();
+        stepper
+            .server_app
+            .init_resource::<CheckCounter>()
+            .add_systems(Update, receive_disconnect_event);
+        stepper
+            .client_app
+            .init_resource::<ClientDisconnects>()
+            .add_systems(Update, receive_client_disconnect_event);
+
+        let client_id = ClientId::Netcode(TEST_CLIENT_ID);
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ServerConnections>()
+            .simulate_disconnect(client_id)
+            .unwrap();
+        stepper.frame_step();
+        stepper.frame_step();
+
+        // the server handles the disconnection right away
+        assert_eq!(stepper.server_app.world().resource::<CheckCounter>().0, 1);
+        assert!(stepper
+            .server_app
+            .world()
+            .resource::<ConnectionManager>()
+            .connection(client_id)
+            .is_err());
+        // but the client has not been notified
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .resource::<State<NetworkingState>>()
+                .get(),
+            &NetworkingState::Connected
+        );
+        assert!(stepper
+            .client_app
+            .world()
+            .resource::<ClientDisconnects>()
+            .0
+            .is_empty());
+
+        // the client disconnects after the timeout
+        for _ in 0..400 {
+            stepper.frame_step();
+        }
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .resource::<State<NetworkingState>>()
+                .get(),
+            &NetworkingState::Disconnected
+        );
+        let disconnects = &stepper.client_app.world().resource::<ClientDisconnects>().0;
+        assert_eq!(disconnects.len(), 1);
+        assert!(matches!(
+            disconnects[0],
+            Some(crate::connection::client::DisconnectReason::Netcode(
+                crate::connection::netcode::ClientState::ConnectionTimedOut
+            ))
+        ));
+    }
+
+    /// Check that the reason sent by the server when kicking a client is available in the
+    /// client's `DisconnectEvent`
+    #[test]
+    fn test_kick_client() {
+        let mut stepper = BevyStepper::default();
+        stepper
+            .client_app
+            .init_resource::<ClientDisconnects>()
+            .add_systems(Update, receive_client_disconnect_event);
+
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ServerConnections>()
+            .kick(ClientId::Netcode(TEST_CLIENT_ID), "cheating")
+            .unwrap();
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .resource::<State<NetworkingState>>()
+                .get(),
+            &NetworkingState::Disconnected
+        );
+        let disconnects = &stepper.client_app.world().resource::<ClientDisconnects>().0;
+        assert_eq!(disconnects.len(), 1);
+        assert!(matches!(
+            &disconnects[0],
+            Some(crate::connection::client::DisconnectReason::Kicked(reason)) if reason == "cheating"
+        ));
+    }
+
+    /// Check that the host-client refuses to connect if its connection is not of type Local
+    #[test]
+    fn test_host_server_non_local_client_rejected() {
+        let mut stepper = HostServerStepper::default_no_init();
+        let external_net_config = stepper
+            .client_app
+            .world()
+            .resource::<ClientConfig>()
+            .net
+            .clone();
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ClientConfig>()
+            .net = external_net_config;
+        stepper
+            .server_app
+            .init_resource::<ClientDisconnects>()
+            .add_systems(Update, receive_client_disconnect_event);
+        stepper.init();
+
+        assert_eq!(
+            stepper
+                .server_app
+                .world()
+                .resource::<State<NetworkingState>>()
+                .get(),
+            &NetworkingState::Disconnected
+        );
+        let disconnects = &stepper.server_app.world().resource::<ClientDisconnects>().0;
+        assert_eq!(disconnects.len(), 1);
+        assert!(matches!(
+            disconnects[0],
+            Some(crate::connection::client::DisconnectReason::Refused(
+                ConnectionError::HostServerNotLocal
+            ))
+        ));
+        // the host-client did not connect to the server, only the external client did
+        let server_manager = stepper.server_app.world().resource::<ConnectionManager>();
+        assert_eq!(server_manager.connected_clients().count(), 1);
+    }
 }
@@ -7,6 +7,7 @@ use bevy::transform::TransformSystem;
 use std::time::Duration;
 
 use crate::client::components::{ComponentSyncMode, Confirmed, SyncComponent};
+use crate::client::config::ClientConfig;
 use crate::client::prediction::correction::{
     get_visually_corrected_state, restore_corrected_state,
 };
@@ -28,16 +29,23 @@ use crate::prelude::{client::is_synced, is_host_server, PreSpawnedPlayerObject};
 use crate::shared::sets::{ClientMarker, InternalMainSet};
 
 use super::pre_prediction::PrePredictionPlugin;
-use super::predicted_history::{add_component_history, apply_confirmed_update};
+use super::predicted_history::{
+    add_component_history, apply_confirmed_update, restore_confirmed_during_rollback,
+};
 use super::rollback::{
     check_rollback, increment_rollback_tick, prepare_rollback, prepare_rollback_non_networked,
-    prepare_rollback_prespawn, run_rollback, Rollback, RollbackState,
+    prepare_rollback_prespawn, run_rollback, LargePredictionError, Rollback, RollbackState,
 };
-use super::spawn::spawn_predicted_entity;
+use super::spawn::{interpolate_instead_of_predict, spawn_predicted_entity, EntityPredicted};
 
 /// Configuration to specify how the prediction plugin should behave
 #[derive(Debug, Clone, Copy, Reflect)]
 pub struct PredictionConfig {
+    /// If false, client-side prediction is disabled: the prediction and rollback systems don't run,
+    /// and entities that should be predicted are interpolated instead.
+    ///
+    /// This can be useful for debugging, to compare the behaviour with and without prediction.
+    pub enabled: bool,
     /// If true, we always rollback whenever we receive a server update, instead of checking
     /// ff the confirmed state matches the predicted state history
     pub always_rollback: bool,
@@ -88,6 +96,7 @@ impl Default for PredictionConfig {
     ///   (the rest will be covered by more input delay)
     fn default() -> Self {
         Self {
+            enabled: true,
             always_rollback: false,
             minimum_input_delay_ticks: 0,
             maximum_input_delay_before_prediction: 0,
@@ -98,6 +107,12 @@ impl Default for PredictionConfig {
 }
 
 impl PredictionConfig {
+    /// Enable or disable client-side prediction
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
     pub fn always_rollback(mut self, always_rollback: bool) -> Self {
         self.always_rollback = always_rollback;
         self
@@ -183,6 +198,11 @@ pub fn is_in_rollback(rollback: Option<Res<Rollback>>) -> bool {
     rollback.is_some_and(|rollback| rollback.is_rollback())
 }
 
+/// Returns true if client-side prediction is enabled in the [`PredictionConfig`]
+pub fn is_prediction_enabled(config: Res<ClientConfig>) -> bool {
+    config.prediction.enabled
+}
+
 /// Enable rollbacking a component even if the component is not networked
 pub fn add_non_networked_rollback_systems<C: Component + PartialEq + Clone>(app: &mut App) {
     app.observe(apply_component_removal_predicted::<C>);
@@ -250,8 +270,17 @@ pub fn add_prediction_systems<C: SyncComponent>(app: &mut App, prediction_mode:
                     restore_components_if_despawn_rolled_back::<C>
                         // .before(run_rollback::)
                         .in_set(PredictionSet::PrepareRollback),
+                    // the component is not predicted: re-simulate from the confirmed value
+                    restore_confirmed_during_rollback::<C>.in_set(PredictionSet::PrepareRollback),
                 ),
             );
+            app.add_systems(
+                FixedPostUpdate,
+                // discard any changes made to the component while re-simulating
+                restore_confirmed_during_rollback::<C>
+                    .run_if(is_in_rollback)
+                    .in_set(PredictionSet::UpdateHistory),
+            );
         }
         ComponentSyncMode::Once => {
             app.add_systems(
@@ -273,9 +302,14 @@ pub fn add_prediction_systems<C: SyncComponent>(app: &mut App, prediction_mode:
 impl Plugin for PredictionPlugin {
     fn build(&self, app: &mut App) {
         // we only run prediction:
-        // - if we're not in host-server mode
+        // - if we're not in host-server mode: the host runs the authoritative simulation, so it must never
+        //   roll back. Otherwise the re-simulated ticks would mark the components as changed, and the
+        //   transient values would be replicated to the remote clients
         // - after the client is synced
-        let should_prediction_run = not(is_host_server).and_then(is_synced);
+        // - if prediction is enabled
+        let should_prediction_run = not(is_host_server)
+            .and_then(is_synced)
+            .and_then(is_prediction_enabled);
 
         // REFLECTION
         app.register_type::<Predicted>()
@@ -290,6 +324,10 @@ impl Plugin for PredictionPlugin {
         app.init_resource::<PredictionManager>();
         app.insert_resource(Rollback::new(RollbackState::Default));
 
+        // EVENTS
+        app.add_event::<LargePredictionError>();
+        app.add_event::<EntityPredicted>();
+
         // PreUpdate systems:
         // 1. Receive confirmed entities, add Confirmed and Predicted components
         // 2. (in prediction_systems) add ComponentHistory
@@ -328,6 +366,12 @@ impl Plugin for PredictionPlugin {
                 run_rollback.in_set(PredictionSet::Rollback),
             ),
         );
+        app.add_systems(
+            PreUpdate,
+            interpolate_instead_of_predict
+                .after(InternalMainSet::<ClientMarker>::EmitEvents)
+                .run_if(not(is_host_server).and_then(not(is_prediction_enabled))),
+        );
         app.observe(despawn_confirmed);
 
         // FixedUpdate systems
@@ -378,10 +422,21 @@ impl Plugin for PredictionPlugin {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::client::connection::ConnectionManager;
+    use crate::client::events::ComponentUpdateEvent;
+    use crate::prelude::client::{InterpolationConfig, InterpolationDelay};
+    use crate::prelude::server::{Replicate, SyncTarget};
+    use crate::prelude::Replicating;
+    use crate::prelude::{NetworkTarget, SharedConfig, TickConfig};
+    use crate::tests::host_server_stepper::HostServerStepper;
+    use crate::tests::protocol::ComponentSyncModeFull;
+    use crate::tests::stepper::BevyStepper;
+    use bevy::prelude::{default, EventReader, FixedUpdate, Query, ResMut, Resource, Update, With};
 
     #[test]
     fn test_input_delay_config() {
         let config_1 = PredictionConfig {
+            enabled: true,
             always_rollback: false,
             minimum_input_delay_ticks: 2,
             maximum_input_delay_before_prediction: 3,
@@ -410,4 +465,206 @@ mod tests {
             12
         );
     }
+
+    #[derive(Resource, Default)]
+    struct RollbackCount(usize);
+
+    fn count_rollbacks(rollback: Res<Rollback>, mut count: ResMut<RollbackCount>) {
+        if rollback.is_rollback() {
+            count.0 += 1;
+        }
+    }
+
+    /// Check that when prediction is disabled, entities that should be predicted are
+    /// interpolated instead, and no rollback happens
+    #[test]
+    fn test_prediction_disabled() {
+        let frame_duration = Duration::from_millis(10);
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..default()
+        };
+        let client_config = ClientConfig {
+            prediction: PredictionConfig::default().enabled(false),
+            interpolation: InterpolationConfig::default().with_delay(InterpolationDelay {
+                min_delay: Duration::from_millis(50),
+                send_interval_ratio: 0.0,
+            }),
+            ..default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, client_config, frame_duration);
+        stepper.client_app.init_resource::<RollbackCount>();
+        stepper.client_app.add_systems(
+            PreUpdate,
+            count_rollbacks
+                .after(PredictionSet::CheckRollback)
+                .before(PredictionSet::Rollback),
+        );
+        stepper.init();
+
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((
+                Replicate {
+                    sync: SyncTarget {
+                        prediction: NetworkTarget::All,
+                        ..default()
+                    },
+                    ..default()
+                },
+                ComponentSyncModeFull(1.0),
+            ))
+            .id();
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        let confirmed = stepper
+            .client_app
+            .world()
+            .resource::<ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .expect("entity was not replicated to client");
+
+        // an interpolated entity was spawned instead of a predicted entity
+        let confirmed_component = stepper
+            .client_app
+            .world()
+            .get::<Confirmed>(confirmed)
+            .unwrap();
+        assert!(confirmed_component.predicted.is_none());
+        let interpolated = confirmed_component
+            .interpolated
+            .expect("interpolated entity was not spawned");
+        assert!(stepper
+            .client_app
+            .world_mut()
+            .query::<&Predicted>()
+            .iter(stepper.client_app.world())
+            .next()
+            .is_none());
+
+        // update the component on the server
+        stepper
+            .server_app
+            .world_mut()
+            .get_mut::<ComponentSyncModeFull>(server_entity)
+            .unwrap()
+            .0 = 2.0;
+        let mut frames_until_confirmed = None;
+        let mut frames_until_interpolated = None;
+        for i in 0..100 {
+            stepper.frame_step();
+            let world = stepper.client_app.world();
+            if frames_until_confirmed.is_none()
+                && world.get::<ComponentSyncModeFull>(confirmed)
+                    == Some(&ComponentSyncModeFull(2.0))
+            {
+                frames_until_confirmed = Some(i);
+            }
+            if world.get::<ComponentSyncModeFull>(interpolated) == Some(&ComponentSyncModeFull(2.0))
+            {
+                frames_until_interpolated = Some(i);
+                break;
+            }
+        }
+        // the controlled entity follows the confirmed state with the interpolation delay
+        let frames_until_confirmed =
+            frames_until_confirmed.expect("confirmed entity was not updated");
+        let frames_until_interpolated =
+            frames_until_interpolated.expect("interpolated entity was not updated");
+        // (the interpolation delay is 50ms, i.e. 5 frames)
+        assert!(frames_until_interpolated - frames_until_confirmed >= 5);
+        assert_eq!(stepper.client_app.world().resource::<RollbackCount>().0, 0);
+    }
+
+    #[derive(Resource, Default)]
+    struct ReceivedValues(Vec<f32>);
+
+    /// Check that in host-server mode, the host never rolls back (even with `always_rollback`), so
+    /// that the remote clients only receive the authoritative values of the host's simulation
+    #[test]
+    fn test_host_server_no_rollback_updates() {
+        let frame_duration = Duration::from_millis(10);
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..default()
+        };
+        let client_config = ClientConfig {
+            prediction: PredictionConfig {
+                always_rollback: true,
+                ..default()
+            },
+            ..default()
+        };
+        let mut stepper = HostServerStepper::new(shared_config, client_config, frame_duration);
+        stepper.server_app.init_resource::<RollbackCount>();
+        stepper.server_app.add_systems(
+            PreUpdate,
+            count_rollbacks
+                .after(PredictionSet::CheckRollback)
+                .before(PredictionSet::Rollback),
+        );
+        // the host simulation increments the component once per tick; every re-simulated tick
+        // would increment it again
+        stepper.server_app.add_systems(
+            FixedUpdate,
+            |mut query: Query<&mut ComponentSyncModeFull, With<Replicating>>| {
+                for mut component in query.iter_mut() {
+                    component.0 += 1.0;
+                }
+            },
+        );
+        stepper.client_app.init_resource::<ReceivedValues>();
+        stepper.client_app.add_systems(
+            Update,
+            |mut events: EventReader<ComponentUpdateEvent<ComponentSyncModeFull>>,
+             query: Query<&ComponentSyncModeFull>,
+             mut received: ResMut<ReceivedValues>| {
+                for event in events.read() {
+                    received.0.push(query.get(event.entity()).unwrap().0);
+                }
+            },
+        );
+        stepper.init();
+
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((
+                Replicate {
+                    sync: SyncTarget {
+                        prediction: NetworkTarget::All,
+                        ..default()
+                    },
+                    ..default()
+                },
+                ComponentSyncModeFull(0.0),
+            ))
+            .id();
+        let start_tick = stepper.server_tick();
+        for _ in 0..20 {
+            stepper.frame_step();
+        }
+
+        // the host simulation ran exactly once per tick
+        let host_value = stepper
+            .server_app
+            .world()
+            .get::<ComponentSyncModeFull>(server_entity)
+            .unwrap()
+            .0;
+        assert_eq!(host_value, (stepper.server_tick() - start_tick) as f32);
+        assert_eq!(stepper.server_app.world().resource::<RollbackCount>().0, 0);
+
+        // the remote client received increasing values, that were all produced by the authoritative simulation
+        let received = &stepper.client_app.world().resource::<ReceivedValues>().0;
+        assert!(!received.is_empty());
+        assert!(received.windows(2).all(|w| w[0] < w[1]));
+        assert!(received.iter().all(|value| *value <= host_value));
+    }
 }
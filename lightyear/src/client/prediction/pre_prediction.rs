@@ -5,6 +5,7 @@ use bevy::prelude::*;
 
 use crate::client::components::Confirmed;
 use crate::client::prediction::resource::PredictionManager;
+use crate::client::prediction::spawn::EntityPredicted;
 use crate::client::prediction::Predicted;
 use crate::client::replication::send::ReplicateToServer;
 use crate::prelude::client::is_synced;
@@ -103,6 +104,10 @@ impl PrePredictionPlugin {
                         confirmed_entity: Some(confirmed),
                     })
                     .remove::<ShouldBePredicted>();
+                world.send_event(EntityPredicted {
+                    confirmed,
+                    predicted,
+                });
             });
         } else {
             let predicted_entity = trigger.entity();
@@ -333,6 +333,37 @@ pub(crate) fn apply_confirmed_update<C: SyncComponent>(
     }
 }
 
+/// If ComponentSyncMode == Simple and the component was registered with
+/// [`keep_confirmed_during_rollback`](crate::prelude::ComponentRegistration::keep_confirmed_during_rollback),
+/// the component's value is authoritative on the remote and should not be re-computed during rollback.
+///
+/// We snap the predicted component back to the confirmed value at the start of the rollback and after every
+/// re-simulated tick, so that any modification made by the systems that run during rollback is discarded.
+pub(crate) fn restore_confirmed_during_rollback<C: SyncComponent>(
+    component_registry: Res<ComponentRegistry>,
+    manager: Res<PredictionManager>,
+    mut predicted_entities: Query<(&Predicted, &mut C), Without<Confirmed>>,
+    confirmed_entities: Query<&C, With<Confirmed>>,
+) {
+    if !component_registry.keep_confirmed_during_rollback::<C>() {
+        return;
+    }
+    for (predicted, mut predicted_component) in predicted_entities.iter_mut() {
+        let Some(confirmed_component) = predicted
+            .confirmed_entity
+            .and_then(|confirmed| confirmed_entities.get(confirmed).ok())
+        else {
+            continue;
+        };
+        // map any entities from confirmed to predicted
+        let mut component = confirmed_component.clone();
+        let _ = manager.map_entities(&mut component, component_registry.as_ref());
+        if *predicted_component != component {
+            *predicted_component = component;
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
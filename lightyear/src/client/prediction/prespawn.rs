@@ -9,6 +9,7 @@ use crate::client::connection::ConnectionManager;
 use crate::client::events::ComponentInsertEvent;
 use crate::client::prediction::resource::PredictionManager;
 use crate::client::prediction::rollback::Rollback;
+use crate::client::prediction::spawn::EntityPredicted;
 use crate::client::prediction::Predicted;
 use crate::prelude::client::PredictionSet;
 use crate::prelude::{ComponentRegistry, Replicated, ShouldBePredicted, TickManager};
@@ -182,6 +183,7 @@ impl PreSpawnedPlayerObjectPlugin {
         mut manager: ResMut<PredictionManager>,
         // TODO: replace with Query<&PreSpawnedPlayerObject, Added<Replicating>> ?
         mut events: EventReader<ComponentInsertEvent<PreSpawnedPlayerObject>>,
+        mut predicted_events: EventWriter<EntityPredicted>,
         query: Query<&PreSpawnedPlayerObject>,
     ) {
         for event in events.read() {
@@ -253,6 +255,10 @@ impl PreSpawnedPlayerObjectPlugin {
                 "Added/Spawned the Predicted entity: {:?} for the confirmed entity: {:?}",
                 predicted_entity, confirmed_entity
             );
+            predicted_events.send(EntityPredicted {
+                confirmed: confirmed_entity,
+                predicted: predicted_entity,
+            });
 
             // 3. re-add the remaining entities in the map
             if !client_entity_list.is_empty() {
@@ -5,8 +5,8 @@ use bevy::app::FixedMain;
 use bevy::ecs::entity::EntityHashSet;
 use bevy::ecs::reflect::ReflectResource;
 use bevy::prelude::{
-    Commands, Component, DespawnRecursiveExt, DetectChanges, Entity, Query, Ref, Res, ResMut,
-    Resource, With, Without, World,
+    Commands, Component, DespawnRecursiveExt, DetectChanges, Entity, Event, EventWriter, Query,
+    Ref, Res, ResMut, Resource, With, Without, World,
 };
 use bevy::reflect::Reflect;
 use parking_lot::RwLock;
@@ -96,6 +96,23 @@ impl Rollback {
     }
 }
 
+/// Event emitted when the value of a predicted component differs from the confirmed value by more than
+/// the threshold registered with [`add_prediction_error`](crate::prelude::ComponentRegistration::add_prediction_error).
+///
+/// Small prediction errors are routinely fixed by rollbacks, but large errors usually indicate
+/// a desync or a non-deterministic simulation.
+#[derive(Event, Debug, Clone, PartialEq)]
+pub struct LargePredictionError {
+    /// The predicted entity
+    pub entity: Entity,
+    /// The tick at which the predicted value was compared with the confirmed value
+    pub tick: Tick,
+    /// The magnitude of the error, computed by the prediction error function of the component
+    pub magnitude: f32,
+    /// The name of the component
+    pub component: &'static str,
+}
+
 /// Check if we need to do a rollback.
 /// We do this separately from `prepare_rollback` because even if component A is the same between predicted and confirmed,
 /// if component B is different we do a rollback for all components
@@ -111,6 +128,7 @@ pub(crate) fn check_rollback<C: SyncComponent>(
     // We use Option<> because the predicted component could have been removed while it still exists in Confirmed
     confirmed_query: Query<(Entity, Option<&C>, Ref<Confirmed>)>,
     rollback: Res<Rollback>,
+    mut large_errors: EventWriter<LargePredictionError>,
 ) {
     // TODO: can just enable bevy spans?
     let _span = trace_span!("client rollback check");
@@ -171,10 +189,31 @@ pub(crate) fn check_rollback<C: SyncComponent>(
             continue;
         }
 
+        // (the history is cleared anyway if we do a rollback)
+        let history_value = predicted_history.pop_until_tick(tick);
+        if let (Some(ComponentState::Updated(history_value)), Some(c)) =
+            (&history_value, confirmed_component)
+        {
+            if let Some(magnitude) = component_registry.large_prediction_error(history_value, c) {
+                debug!(
+                    ?p,
+                    ?tick,
+                    ?magnitude,
+                    "Large prediction error for component {:?}",
+                    kind
+                );
+                large_errors.send(LargePredictionError {
+                    entity: p,
+                    tick,
+                    magnitude,
+                    component: kind,
+                });
+            }
+        }
+
         // 3.a We are still not sure if we should do rollback. Compare history against confirmed
         // We rollback if there's no history (newly added predicted entity, or if there is a mismatch)
         if !rollback.is_rollback() {
-            let history_value = predicted_history.pop_until_tick(tick);
             let predicted_exist = history_value.is_some();
             let confirmed_exist = confirmed_component.is_some();
             let should_rollback = match confirmed_component {
@@ -597,9 +636,11 @@ mod unit_tests {
     use super::test_utils::*;
     use super::*;
 
+    use crate::prelude::AppComponentExt;
     use crate::tests::protocol::ComponentSyncModeFull;
     use crate::tests::stepper::BevyStepper;
     use bevy::ecs::system::RunSystemOnce;
+    use bevy::prelude::Events;
 
     // TODO: check that if A is updated but B is not, and A and B are in the same replication group,
     //  then we need to check the rollback for B as well!
@@ -748,6 +789,97 @@ mod unit_tests {
             .resource::<Rollback>()
             .is_rollback());
     }
+
+    /// Check that a [`LargePredictionError`] is emitted only when the difference between the predicted
+    /// and confirmed values is above the threshold
+    #[test]
+    fn test_large_prediction_error() {
+        let mut stepper = BevyStepper::default();
+        stepper
+            .client_app
+            .add_prediction_error_fn::<ComponentSyncModeFull>(
+                |predicted, confirmed| (predicted.0 - confirmed.0).abs(),
+                5.0,
+            );
+
+        // add predicted/confirmed entities
+        let confirmed = stepper
+            .client_app
+            .world_mut()
+            .spawn(Confirmed::default())
+            .id();
+        let predicted = stepper
+            .client_app
+            .world_mut()
+            .spawn(Predicted {
+                confirmed_entity: Some(confirmed),
+            })
+            .id();
+        stepper
+            .client_app
+            .world_mut()
+            .entity_mut(confirmed)
+            .get_mut::<Confirmed>()
+            .unwrap()
+            .predicted = Some(predicted);
+        stepper
+            .client_app
+            .world_mut()
+            .entity_mut(confirmed)
+            .insert(ComponentSyncModeFull(1.0));
+        stepper.frame_step();
+        let tick = stepper.client_tick();
+
+        let check_error = |stepper: &mut BevyStepper, confirmed_value: f32| {
+            stepper
+                .client_app
+                .world()
+                .resource::<Rollback>()
+                .set_non_rollback();
+            stepper
+                .client_app
+                .world_mut()
+                .entity_mut(predicted)
+                .get_mut::<PredictionHistory<ComponentSyncModeFull>>()
+                .unwrap()
+                .add_update(tick, ComponentSyncModeFull(1.0));
+            stepper
+                .client_app
+                .world_mut()
+                .entity_mut(confirmed)
+                .insert(ComponentSyncModeFull(confirmed_value));
+            received_confirmed_update(stepper, confirmed, tick);
+            stepper
+                .client_app
+                .world_mut()
+                .run_system_once(check_rollback::<ComponentSyncModeFull>);
+            stepper
+                .client_app
+                .world_mut()
+                .resource_mut::<Events<LargePredictionError>>()
+                .drain()
+                .collect::<Vec<_>>()
+        };
+
+        // small prediction errors only cause a rollback
+        assert!(check_error(&mut stepper, 2.0).is_empty());
+        assert!(stepper
+            .client_app
+            .world()
+            .resource::<Rollback>()
+            .is_rollback());
+
+        // large prediction errors emit an event
+        assert_eq!(
+            check_error(&mut stepper, 10.0),
+            vec![LargePredictionError {
+                entity: predicted,
+                tick,
+                magnitude: 9.0,
+                component: std::any::type_name::<ComponentSyncModeFull>(),
+            }]
+        );
+    }
 }
 
 /// More general integration tests for rollback
@@ -756,6 +888,7 @@ mod integration_tests {
     use super::test_utils::*;
 
     use crate::prelude::client::*;
+    use crate::prelude::ComponentRegistry;
     use crate::tests::protocol::*;
     use crate::tests::stepper::BevyStepper;
     use bevy::prelude::*;
@@ -1010,4 +1143,89 @@ mod integration_tests {
             .unwrap()
             .0 = 4.0;
     }
+
+    /// Trigger a rollback while a system re-computes the ComponentSyncMode::Simple component during re-simulation,
+    /// and return the value of the predicted component after the rollback
+    fn simple_component_after_rollback(keep_confirmed: bool) -> ComponentSyncModeSimple {
+        fn recompute_simple_component(
+            mut query: Query<&mut ComponentSyncModeSimple, With<Predicted>>,
+        ) {
+            for mut component in query.iter_mut() {
+                component.0 += 100.0;
+            }
+        }
+
+        let (mut stepper, confirmed, predicted) = setup();
+        if keep_confirmed {
+            stepper
+                .client_app
+                .world_mut()
+                .resource_mut::<ComponentRegistry>()
+                .set_keep_confirmed_during_rollback::<ComponentSyncModeSimple>();
+        }
+        stepper.client_app.add_systems(
+            FixedUpdate,
+            recompute_simple_component.run_if(is_in_rollback),
+        );
+        stepper
+            .client_app
+            .world_mut()
+            .entity_mut(confirmed)
+            .insert((ComponentSyncModeFull(0.0), ComponentSyncModeSimple(5.0)));
+        stepper.frame_step();
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<ComponentSyncModeSimple>(predicted),
+            Some(&ComponentSyncModeSimple(5.0))
+        );
+
+        // create a rollback situation
+        let tick = stepper.client_tick();
+        stepper
+            .client_app
+            .world_mut()
+            .get_mut::<ComponentSyncModeFull>(confirmed)
+            .unwrap()
+            .0 = -10.0;
+        received_confirmed_update(&mut stepper, confirmed, tick - 2);
+        stepper.frame_step();
+
+        // check that rollback happened
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<ComponentSyncModeFull>(predicted)
+                .unwrap()
+                .0,
+            -7.0
+        );
+        stepper
+            .client_app
+            .world()
+            .get::<ComponentSyncModeSimple>(predicted)
+            .unwrap()
+            .clone()
+    }
+
+    /// Test that a ComponentSyncMode::Simple component registered with `keep_confirmed_during_rollback`
+    /// keeps the value received from the server during rollback
+    #[test]
+    fn test_simple_component_not_recomputed_during_rollback() {
+        assert_eq!(
+            simple_component_after_rollback(true),
+            ComponentSyncModeSimple(5.0)
+        );
+    }
+
+    /// By default, the systems that run during rollback can modify a ComponentSyncMode::Simple component
+    #[test]
+    fn test_simple_component_recomputed_during_rollback_by_default() {
+        assert_ne!(
+            simple_component_after_rollback(false),
+            ComponentSyncModeSimple(5.0)
+        );
+    }
 }
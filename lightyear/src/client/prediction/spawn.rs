@@ -1,12 +1,30 @@
 //! Logic to handle spawning Predicted entities
-use bevy::prelude::{Added, Commands, Entity, Query, Res, ResMut};
+use bevy::prelude::{
+    Added, Commands, DespawnRecursiveExt, DetectChanges, Entity, Event, EventWriter, Has, Query,
+    Res, ResMut, Without, World,
+};
 use tracing::debug;
 
 use crate::client::components::Confirmed;
 use crate::client::connection::ConnectionManager;
+use crate::client::interpolation::resource::InterpolationManager;
 use crate::client::prediction::resource::PredictionManager;
 use crate::client::prediction::Predicted;
-use crate::prelude::ShouldBePredicted;
+use crate::prelude::{PrePredicted, ShouldBePredicted};
+use crate::shared::replication::components::ShouldBeInterpolated;
+
+/// Event emitted on the client when a confirmed entity replicated from the server gets a corresponding
+/// [`Predicted`] entity.
+///
+/// This is also emitted when a pre-spawned or pre-predicted entity gets matched with the confirmed
+/// entity sent by the server.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct EntityPredicted {
+    /// The confirmed entity, replicated from the server
+    pub confirmed: Entity,
+    /// The predicted entity
+    pub predicted: Entity,
+}
 
 /// Spawn a predicted entity for each confirmed entity that has the `ShouldBePredicted` component added
 /// The `Confirmed` entity could already exist because we share the Confirmed component for prediction and interpolation.
@@ -16,6 +34,7 @@ pub(crate) fn spawn_predicted_entity(
     connection: Res<ConnectionManager>,
     mut manager: ResMut<PredictionManager>,
     mut commands: Commands,
+    mut events: EventWriter<EntityPredicted>,
 
     // TODO: instead of listening to the ComponentInsertEvent, should we just directly query on Added<ShouldBePredicted>?
     //  maybe listening to the event is more performant, since Added<ShouldBePredicted> queries all entities that have this component?
@@ -45,6 +64,10 @@ pub(crate) fn spawn_predicted_entity(
         {
             metrics::counter!("spawn_predicted_entity").increment(1);
         }
+        events.send(EntityPredicted {
+            confirmed: confirmed_entity,
+            predicted: predicted_entity,
+        });
 
         // update the predicted entity mapping
         manager
@@ -59,6 +82,25 @@ pub(crate) fn spawn_predicted_entity(
         confirmed_entity_mut.remove::<ShouldBePredicted>();
         if let Some(mut confirmed) = confirmed {
             confirmed.predicted = Some(predicted_entity);
+            // the entity was interpolated until now (for example because we just got control of it)
+            // (if `Confirmed` was just added, both copies are being spawned at the same time)
+            if !confirmed.is_added() {
+                if let Some(interpolated) = confirmed.interpolated.take() {
+                    commands.add(move |world: &mut World| {
+                        if let Some(mut manager) = world.get_resource_mut::<InterpolationManager>()
+                        {
+                            manager
+                                .interpolated_entity_map
+                                .get_mut()
+                                .confirmed_to_interpolated
+                                .remove(&confirmed_entity);
+                        }
+                        if let Some(entity_mut) = world.get_entity_mut(interpolated) {
+                            entity_mut.despawn_recursive();
+                        }
+                    });
+                }
+            }
         } else {
             // TODO: this is the same as the current tick no? or maybe not because we could have received updates before the spawn
             //  and they are applied simultaneously
@@ -76,3 +118,91 @@ pub(crate) fn spawn_predicted_entity(
         }
     }
 }
+
+/// When prediction is disabled, entities that should be predicted are interpolated instead,
+/// so that they follow the confirmed state with the interpolation delay.
+pub(crate) fn interpolate_instead_of_predict(
+    mut commands: Commands,
+    confirmed_entities: Query<
+        (Entity, Has<ShouldBeInterpolated>),
+        (Added<ShouldBePredicted>, Without<PrePredicted>),
+    >,
+) {
+    for (confirmed_entity, is_interpolated) in confirmed_entities.iter() {
+        debug!("Prediction is disabled, interpolating entity {confirmed_entity:?} instead");
+        let mut confirmed_entity_mut = commands.entity(confirmed_entity);
+        confirmed_entity_mut.remove::<ShouldBePredicted>();
+        if !is_interpolated {
+            confirmed_entity_mut.insert(ShouldBeInterpolated);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::{default, EventReader, ResMut, Resource, Update};
+
+    use super::*;
+    use crate::prelude::server::{Replicate, SyncTarget};
+    use crate::prelude::NetworkTarget;
+    use crate::tests::protocol::ComponentSyncModeFull;
+    use crate::tests::stepper::BevyStepper;
+
+    #[derive(Resource, Default)]
+    struct PredictedEvents(Vec<EntityPredicted>);
+
+    fn collect_events(
+        mut events: EventReader<EntityPredicted>,
+        mut collected: ResMut<PredictedEvents>,
+    ) {
+        collected.0.extend(events.read().copied());
+    }
+
+    /// Check that an event is emitted with the confirmed and predicted entities when a predicted entity is spawned
+    #[test]
+    fn test_entity_predicted_event() {
+        let mut stepper = BevyStepper::default();
+        stepper.client_app.init_resource::<PredictedEvents>();
+        stepper.client_app.add_systems(Update, collect_events);
+
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((
+                Replicate {
+                    sync: SyncTarget {
+                        prediction: NetworkTarget::All,
+                        ..default()
+                    },
+                    ..default()
+                },
+                ComponentSyncModeFull(1.0),
+            ))
+            .id();
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        let confirmed = stepper
+            .client_app
+            .world()
+            .resource::<ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .expect("entity was not replicated to client");
+        let predicted = stepper
+            .client_app
+            .world()
+            .get::<Confirmed>(confirmed)
+            .unwrap()
+            .predicted
+            .expect("predicted entity was not spawned");
+        assert_eq!(
+            stepper.client_app.world().resource::<PredictedEvents>().0,
+            vec![EntityPredicted {
+                confirmed,
+                predicted
+            }]
+        );
+    }
+}
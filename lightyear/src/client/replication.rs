@@ -80,7 +80,7 @@ pub(crate) mod send {
     use crate::prelude::{
         client::{is_connected, is_synced},
         is_host_server, ComponentRegistry, DisabledComponent, ReplicateHierarchy, Replicated,
-        ReplicationGroup, TargetEntity, Tick, TickManager, TimeManager,
+        ReplicationGroup, ReplicationGroupKey, TargetEntity, Tick, TickManager, TimeManager,
     };
     use crate::protocol::component::ComponentKind;
 
@@ -298,6 +298,22 @@ pub(crate) mod send {
                 // without receiving an action first (to populate the latest_tick)
                 let replication_is_changed = replication_target_ticks
                     .is_changed(system_ticks.last_run(), system_ticks.this_run());
+                // the group key changed: move the entity to its new group on the server, and send
+                // all its components again as part of the new group
+                let migrated = !is_replicated
+                    && entity_ref
+                        .get_change_ticks::<ReplicationGroupKey>()
+                        .is_some_and(|ticks| {
+                            ticks.is_changed(system_ticks.last_run(), system_ticks.this_run())
+                        })
+                    && sender
+                        .replication_sender
+                        .prepare_entity_group_migration(entity.id(), group_id);
+                if migrated {
+                    sender
+                        .replication_sender
+                        .update_base_priority(group_id, priority);
+                }
 
                 // TODO: do the entity mapping here!
 
@@ -345,7 +361,7 @@ pub(crate) mod send {
                         replicated_component.kind,
                         data,
                         component_ticks,
-                        replication_is_changed,
+                        replication_is_changed || migrated,
                         group_id,
                         replicated_component.delta_compression,
                         replicated_component.replicate_once,
@@ -564,9 +580,15 @@ pub(crate) mod send {
                             ),
                         )?;
                         let raw_data = writer.split();
-                        sender
-                            .replication_sender
-                            .prepare_component_update(entity, group_id, raw_data);
+                        if component_registry.is_latest_only(component_kind) {
+                            sender
+                                .replication_sender
+                                .prepare_latest_component_update(entity, group_id, raw_data);
+                        } else {
+                            sender
+                                .replication_sender
+                                .prepare_component_update(entity, group_id, raw_data);
+                        }
                     }
                 }
             }
@@ -615,8 +637,11 @@ pub(crate) mod send {
         use crate::client::replication::send::ReplicateToServer;
         use crate::prelude::client::Replicate;
         use crate::prelude::{
-            server, ClientId, DisabledComponent, ReplicateOnceComponent, Replicated, TargetEntity,
+            server, ClientId, DisabledComponent, ReplicateOnceComponent, Replicated,
+            ReplicationGroupKey, TargetEntity,
         };
+        use crate::shared::replication::components::ReplicationGroupId;
+        use crate::shared::replication::receive::ReplicationReceiver;
         use crate::tests::protocol::ComponentSyncModeFull;
         use crate::tests::stepper::{BevyStepper, TEST_CLIENT_ID};
 
@@ -950,6 +975,82 @@ pub(crate) mod send {
             )
         }
 
+        /// Check that changing the [`ReplicationGroupKey`] of an entity replicated by the client moves it
+        /// to the new group on the server, without despawning it
+        #[test]
+        fn test_replication_group_key_migration() {
+            let mut stepper = BevyStepper::default();
+
+            let client_entity = stepper
+                .client_app
+                .world_mut()
+                .spawn((
+                    Replicate::default(),
+                    ReplicationGroupKey(1),
+                    ComponentSyncModeFull(1.0),
+                ))
+                .id();
+            for _ in 0..10 {
+                stepper.frame_step();
+            }
+            fn receiver(stepper: &BevyStepper) -> &ReplicationReceiver {
+                &stepper
+                    .server_app
+                    .world()
+                    .resource::<server::ConnectionManager>()
+                    .connection(ClientId::Netcode(TEST_CLIENT_ID))
+                    .unwrap()
+                    .replication_receiver
+            }
+            let server_entity = receiver(&stepper)
+                .remote_entity_map
+                .get_local(client_entity)
+                .expect("entity was not replicated to server");
+            assert_eq!(
+                receiver(&stepper).get_replication_group_id(server_entity),
+                Some(ReplicationGroupId(1))
+            );
+
+            // move the entity to another group
+            stepper
+                .client_app
+                .world_mut()
+                .entity_mut(client_entity)
+                .insert(ReplicationGroupKey(2));
+            for _ in 0..10 {
+                stepper.frame_step();
+            }
+            assert_eq!(
+                receiver(&stepper)
+                    .remote_entity_map
+                    .get_local(client_entity),
+                Some(server_entity)
+            );
+            assert_eq!(
+                receiver(&stepper).get_replication_group_id(server_entity),
+                Some(ReplicationGroupId(2))
+            );
+
+            // updates are still received in the new group
+            stepper
+                .client_app
+                .world_mut()
+                .entity_mut(client_entity)
+                .insert(ComponentSyncModeFull(2.0));
+            for _ in 0..10 {
+                stepper.frame_step();
+            }
+            assert_eq!(
+                stepper
+                    .server_app
+                    .world()
+                    .entity(server_entity)
+                    .get::<ComponentSyncModeFull>()
+                    .expect("component missing"),
+                &ComponentSyncModeFull(2.0)
+            );
+        }
+
         // TODO: hard to test because we need to wait a few ticks on the server..
         //  maybe disable sync for tests?
         // #[test]
@@ -1,7 +1,8 @@
 //! Common client-related run conditions
 use crate::client::connection::ConnectionManager;
+use crate::client::events::SyncEvent;
 use crate::connection::client::{ClientConnection, ConnectionState, NetClient};
-use bevy::prelude::Res;
+use bevy::prelude::{EventReader, Res};
 
 /// Returns true if the client is connected
 ///
@@ -31,3 +32,14 @@ pub fn is_synced(
         // TODO: check if this correct; in host-server mode, the client is always synced
         connection.map_or(false, |c| c.sync_manager.is_synced())
 }
+
+/// Run condition that returns true on the frame after the client became synced with the server
+/// (i.e. when a [`SyncEvent`] was emitted).
+///
+/// Each system that uses this run condition runs once per sync, and again after a reconnection:
+/// ```rust,ignore
+/// app.add_systems(Update, spawn_player.run_if(just_synced));
+/// ```
+pub fn just_synced(mut sync_events: EventReader<SyncEvent>) -> bool {
+    sync_events.read().count() > 0
+}
@@ -10,7 +10,7 @@ use crate::packet::packet::PacketId;
 use crate::prelude::client::PredictionConfig;
 use crate::shared::ping::manager::PingManager;
 use crate::shared::tick_manager::TickManager;
-use crate::shared::tick_manager::{Tick, TickEvent};
+use crate::shared::tick_manager::{Tick, TickEvent, WideTick};
 use crate::shared::time_manager::{TimeManager, WrappedTime};
 use crate::utils::ready_buffer::ReadyBuffer;
 
@@ -18,6 +18,29 @@ use crate::utils::ready_buffer::ReadyBuffer;
 #[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone, Copy)]
 pub struct SyncSet;
 
+/// How the client keeps its tick in sync with the server's tick
+#[derive(Clone, Copy, Debug, Default, PartialEq, Reflect)]
+pub enum SyncMode {
+    /// The client runs ahead of the server (by RTT/2 plus some margin, minus the input delay) so that its inputs
+    /// for tick T arrive on the server before the server reaches tick T.
+    /// The client speeds up or slows down its time to stay at the ideal tick as the network conditions change.
+    #[default]
+    Adaptive,
+    /// The client hard-follows the tick that the server sends in every packet: the client tick is always the
+    /// estimated current server tick minus `delay_ticks`, and it snaps to it whenever it differs by more
+    /// than `tolerance_ticks`.
+    ///
+    /// There is no adaptive speed-up or slow-down, which makes it simpler and more predictable for LAN or
+    /// low-jitter scenarios. Note that the client does not run ahead of the server, so its inputs
+    /// arrive on the server after the server has simulated their tick.
+    FixedDelay {
+        delay_ticks: u16,
+        /// The client tick is only snapped if it is more than this number of ticks away from the
+        /// objective tick, so that small variations of the server tick estimate don't make it jitter
+        tolerance_ticks: u16,
+    },
+}
+
 /// Configuration for the sync manager, which is in charge of syncing the client's tick/time with the server's tick/time
 ///
 /// The sync manager runs only on the client and maintains two different times:
@@ -38,6 +61,20 @@ pub struct SyncConfig {
     pub tick_margin: u8,
     /// Number of pings to exchange with the server before finalizing the handshake
     pub handshake_pings: u8,
+    /// If set, the handshake is only finalized once the jitter computed from the recent pings is below
+    /// this threshold.
+    ///
+    /// On links with a lot of jitter, this lets the client wait for a more stable connection before
+    /// syncing, instead of syncing with a bad estimate and sending inputs that arrive too late on the server.
+    /// The jitter is computed over the pings of the last
+    /// [`stats_buffer_duration`](crate::prelude::PingConfig::stats_buffer_duration).
+    pub handshake_max_jitter: Option<Duration>,
+    /// Maximum time to wait for the jitter to go below [`handshake_max_jitter`](Self::handshake_max_jitter)
+    /// once enough pings have been exchanged.
+    ///
+    /// After this timeout, the handshake is finalized anyway so that the client doesn't stay unsynced
+    /// forever on a link that never becomes stable.
+    pub handshake_max_jitter_timeout: Duration,
     /// Error margin for upstream throttle (in multiple of ticks)
     pub error_margin: f32,
     /// If the error margin is too big, we snap the prediction/interpolation time to the objective value
@@ -45,6 +82,8 @@ pub struct SyncConfig {
     // TODO: instead of constant speedup_factor, the speedup should be linear w.r.t the offset
     /// By how much should we speed up the simulation to make ticks stay in sync with server?
     pub speedup_factor: f32,
+    /// How the client tick follows the server tick
+    pub mode: SyncMode,
 
     // Integration
     pub server_time_estimate_smoothing: f32,
@@ -56,9 +95,12 @@ impl Default for SyncConfig {
             jitter_multiple_margin: 3,
             tick_margin: 1,
             handshake_pings: 3,
+            handshake_max_jitter: None,
+            handshake_max_jitter_timeout: Duration::from_secs(5),
             error_margin: 0.5,
             max_error_margin: 5.0,
             speedup_factor: 1.05,
+            mode: SyncMode::Adaptive,
             // server_time_estimate_smoothing: 0.0,
             server_time_estimate_smoothing: 0.2,
         }
@@ -70,6 +112,23 @@ impl SyncConfig {
         self.speedup_factor = speedup_factor;
         self
     }
+
+    /// Only finalize the handshake once the jitter is below the given threshold
+    pub fn handshake_max_jitter(mut self, max_jitter: Duration) -> Self {
+        self.handshake_max_jitter = Some(max_jitter);
+        self
+    }
+
+    /// Finalize the handshake after waiting for this long for the jitter to go below the threshold
+    pub fn handshake_max_jitter_timeout(mut self, timeout: Duration) -> Self {
+        self.handshake_max_jitter_timeout = timeout;
+        self
+    }
+
+    pub fn mode(mut self, mode: SyncMode) -> Self {
+        self.mode = mode;
+        self
+    }
 }
 
 #[derive(Default)]
@@ -85,14 +144,32 @@ impl SentPacketStore {
     }
 }
 
+/// Statistics used by the [`SyncManager`] to sync the client's time with the server's time.
+///
+/// This is mostly useful to debug sync instabilities.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SyncStatsSnapshot {
+    /// Number of pong samples currently used to compute the statistics
+    pub sample_count: usize,
+    /// Current estimate of the round-trip time
+    pub rtt: Duration,
+    /// Current estimate of the jitter, derived from the variance of the round-trip delay samples
+    pub jitter: Duration,
+}
+
 /// In charge of syncing the client's tick/time with the server's tick/time
 /// right after the connection is established
 #[derive(Debug)]
 pub struct SyncManager {
     config: SyncConfig,
     prediction_config: PredictionConfig,
+    /// Input delay set by the server with an [`InputDelayCommand`](crate::shared::input::InputDelayCommand),
+    /// which overrides the input delay computed from the [`PredictionConfig`]
+    pub(crate) server_input_delay_ticks: Option<u16>,
     /// whether the handshake is finalized
     pub(crate) synced: bool,
+    /// How long we have been waiting for the jitter to go below the handshake threshold
+    handshake_jitter_wait: Duration,
 
     // time
     server_time_estimate: WrappedTime,
@@ -104,6 +181,9 @@ pub struct SyncManager {
     /// Tick of the server that we last received in any packet from the server.
     /// This is not updated every tick, but only when we receive a packet from the server.
     pub(crate) latest_received_server_tick: Option<Tick>,
+    /// Fraction of the tick elapsed on the server when it sent the latest received server tick
+    /// (0.0 if the server does not send the sub-tick fraction)
+    pub(crate) latest_received_server_sub_tick: f32,
     pub(crate) duration_since_latest_received_server_tick: Duration,
     pub(crate) new_latest_received_server_tick: bool,
     /// The 'generation' of the tick. Everytime the tick wraps around, the generation increases by 1
@@ -119,13 +199,16 @@ impl SyncManager {
         Self {
             config,
             prediction_config,
+            server_input_delay_ticks: None,
             synced: false,
+            handshake_jitter_wait: Duration::default(),
             // time
             server_time_estimate: WrappedTime::default(),
             interpolation_time: WrappedTime::default(),
             interpolation_speed_ratio: 1.0,
             // server tick
             latest_received_server_tick: None,
+            latest_received_server_sub_tick: 0.0,
             duration_since_latest_received_server_tick: Duration::default(),
             new_latest_received_server_tick: false,
             server_pong_generation: 0,
@@ -151,7 +234,7 @@ impl SyncManager {
         self.interpolation_time += time_manager.delta().mul_f32(self.interpolation_speed_ratio);
 
         // check if we are ready to finalize the handshake
-        if !self.synced && ping_manager.sync_stats.len() >= self.config.handshake_pings as usize {
+        if !self.synced && self.is_handshake_ready(ping_manager, time_manager.delta()) {
             self.synced = true;
             self.interpolation_time = self.interpolation_objective(
                 interpolation_delay,
@@ -162,7 +245,13 @@ impl SyncManager {
                 interpolation_tick = ?self.interpolation_tick(tick_manager),
                 "Client is synced!"
             );
-            return self.finalize(time_manager, tick_manager, ping_manager);
+            let tick_event = self.finalize(time_manager, tick_manager, ping_manager);
+            // the client tick is now close to the server tick: initialize the client's wide tick
+            // from the server's, so that both peers agree on the upper bits
+            if let Some(server_wide_tick) = self.server_wide_tick() {
+                tick_manager.sync_wide_tick(server_wide_tick);
+            }
+            return tick_event;
         }
 
         if self.synced {
@@ -175,6 +264,34 @@ impl SyncManager {
         self.synced
     }
 
+    /// Number of ticks of input delay to apply: the value set by the server if there is one,
+    /// otherwise the value computed from the [`PredictionConfig`] and the current RTT
+    pub(crate) fn input_delay_ticks(&self, rtt: Duration, tick_duration: Duration) -> u16 {
+        self.server_input_delay_ticks
+            .unwrap_or_else(|| self.prediction_config.input_delay_ticks(rtt, tick_duration))
+    }
+
+    /// Returns true if we have exchanged enough pings with the server, and the connection is stable enough
+    /// (or we have waited for too long for it to become stable), to finalize the handshake
+    ///
+    /// If pings are disabled, we only need to have received the RTT from an external source and a tick from the server.
+    fn is_handshake_ready(&mut self, ping_manager: &PingManager, delta: Duration) -> bool {
+        if !ping_manager.is_enabled() {
+            return ping_manager.has_stats() && self.latest_received_server_tick.is_some();
+        }
+        if ping_manager.sync_stats.len() < self.config.handshake_pings as usize {
+            return false;
+        }
+        let Some(max_jitter) = self.config.handshake_max_jitter else {
+            return true;
+        };
+        if ping_manager.jitter() <= max_jitter {
+            return true;
+        }
+        self.handshake_jitter_wait += delta;
+        self.handshake_jitter_wait >= self.config.handshake_max_jitter_timeout
+    }
+
     /// Compute the current client time; we will make sure that the client tick is ahead of the server tick
     /// Even if it is wrapped around.
     /// (i.e. if client tick is 1, and server tick is 65535, we act as if the client tick was 65537)
@@ -224,6 +341,30 @@ impl SyncManager {
         self.server_time_estimate
     }
 
+    /// Set the tick generation of the server from a [`WideTick`] sent by the server.
+    ///
+    /// If we are already synced, the client's wide tick is re-initialized from the server's.
+    pub(crate) fn set_server_wide_tick(
+        &mut self,
+        server_wide_tick: WideTick,
+        tick_manager: &mut TickManager,
+    ) {
+        self.server_pong_tick = server_wide_tick.tick();
+        self.server_pong_generation = (server_wide_tick.0 >> 16) as u16;
+        if self.synced {
+            if let Some(server_wide_tick) = self.server_wide_tick() {
+                tick_manager.sync_wide_tick(server_wide_tick);
+            }
+        }
+    }
+
+    /// The [`WideTick`] of the server corresponding to the latest received server tick
+    fn server_wide_tick(&self) -> Option<WideTick> {
+        self.latest_received_server_tick.map(|tick| {
+            WideTick(((self.server_latest_tick_generation() as u32) << 16) | tick.0 as u32)
+        })
+    }
+
     fn server_latest_tick_generation(&self) -> u16 {
         // check if the latest_server_tick has crossed a generation compared to the latest pong tick
         if self.latest_received_server_tick.unwrap().0 < self.server_pong_tick.0 {
@@ -244,7 +385,9 @@ impl SyncManager {
             self.latest_received_server_tick.unwrap(),
             self.server_latest_tick_generation(),
             tick_duration,
-        ) + self.duration_since_latest_received_server_tick;
+        ) + tick_duration
+            .mul_f32(self.latest_received_server_sub_tick)
+            + self.duration_since_latest_received_server_tick;
 
         // instead of just using the latest_received_server_tick, we apply some smoothing
         // (in case the latest server tick is wildly off-base)
@@ -417,15 +560,25 @@ impl SyncManager {
         tick_manager: &mut TickManager,
         ping_manager: &PingManager,
     ) -> Option<TickEvent> {
+        if let SyncMode::FixedDelay {
+            delay_ticks,
+            tolerance_ticks,
+        } = self.config.mode
+        {
+            return self.update_fixed_delay_tick(
+                delay_ticks,
+                tolerance_ticks,
+                time_manager,
+                tick_manager,
+            );
+        }
         let rtt = ping_manager.rtt();
         let jitter = ping_manager.jitter();
         // current client time
         let current_prediction_time = self.current_prediction_time(tick_manager, time_manager);
 
         // client ideal time
-        let input_delay_ticks = self
-            .prediction_config
-            .input_delay_ticks(rtt, tick_manager.config.tick_duration);
+        let input_delay_ticks = self.input_delay_ticks(rtt, tick_manager.config.tick_duration);
         let client_ideal_time = self.client_ideal_time(
             rtt,
             tick_manager.config.tick_duration,
@@ -502,6 +655,38 @@ impl SyncManager {
         None
     }
 
+    /// Tick that the client should be at with [`SyncMode::FixedDelay`]: the current server tick (estimated
+    /// from the latest tick received from the server) minus the fixed delay
+    fn fixed_delay_tick(&self, delay_ticks: u16, tick_duration: Duration) -> Tick {
+        // number of ticks that the server simulated since it sent the latest tick we received
+        let elapsed_ticks = (self.duration_since_latest_received_server_tick.as_nanos()
+            / tick_duration.as_nanos()) as i16;
+        (self.latest_received_server_tick.unwrap_or(Tick(0)) + elapsed_ticks) - delay_ticks
+    }
+
+    /// With [`SyncMode::FixedDelay`], snap the client tick to the server tick minus the fixed delay
+    /// if it drifted by more than `tolerance_ticks`
+    fn update_fixed_delay_tick(
+        &mut self,
+        delay_ticks: u16,
+        tolerance_ticks: u16,
+        time_manager: &mut TimeManager,
+        tick_manager: &mut TickManager,
+    ) -> Option<TickEvent> {
+        time_manager.sync_relative_speed = 1.0;
+        let objective_tick = self.fixed_delay_tick(delay_ticks, tick_manager.config.tick_duration);
+        if (objective_tick - tick_manager.tick()).unsigned_abs() <= tolerance_ticks {
+            return None;
+        }
+        debug!(
+            client_tick = ?tick_manager.tick(),
+            ?objective_tick,
+            latest_received_server_tick = ?self.latest_received_server_tick,
+            "Snapping the client tick to the server tick minus the fixed delay"
+        );
+        Some(tick_manager.set_tick_to(objective_tick))
+    }
+
     // Update internal time using offset so that times are synced.
     // This happens when a necessary # of handshake pongs have been recorded
     // Compute the final RTT/offset and set the client tick accordingly
@@ -516,11 +701,14 @@ impl SyncManager {
         let jitter = ping_manager.jitter();
         // recompute the server time estimate (using the rtt we just computed)
         self.update_server_time_estimate(tick_duration, rtt);
+        if let SyncMode::FixedDelay { delay_ticks, .. } = self.config.mode {
+            return Some(
+                tick_manager.set_tick_to(self.fixed_delay_tick(delay_ticks, tick_duration)),
+            );
+        }
 
         // Compute how many ticks the client must be compared to server
-        let input_delay_ticks = self
-            .prediction_config
-            .input_delay_ticks(rtt, tick_manager.config.tick_duration);
+        let input_delay_ticks = self.input_delay_ticks(rtt, tick_manager.config.tick_duration);
         let client_ideal_time =
             self.client_ideal_time(rtt, tick_duration, jitter, input_delay_ticks);
 
@@ -562,11 +750,15 @@ mod tests {
     use bevy::utils::Duration;
 
     use crate::client::input::native::InputManager;
+    use crate::packet::header::SubTickFraction;
     use crate::prelude::server::Replicate;
+    use crate::prelude::server::ServerCommands;
     use crate::prelude::*;
     use crate::server::events::InputEvent;
+    use crate::shared::ping::manager::SyncStats;
+    use crate::tests::multi_stepper::{MultiBevyStepper, TEST_CLIENT_ID_1, TEST_CLIENT_ID_2};
     use crate::tests::protocol::*;
-    use crate::tests::stepper::BevyStepper;
+    use crate::tests::stepper::{BevyStepper, TEST_CLIENT_ID};
 
     use super::*;
 
@@ -653,4 +845,510 @@ mod tests {
             &ComponentSyncModeFull(1.0)
         );
     }
+
+    /// Receive one pong per frame with the given round-trip delays, and return the index of the
+    /// frame where the handshake was finalized
+    fn frames_until_synced(config: SyncConfig, rtts: &[Duration]) -> Option<usize> {
+        let mut sync_manager = SyncManager::new(config, PredictionConfig::default());
+        sync_manager.latest_received_server_tick = Some(Tick(0));
+        let mut ping_manager = PingManager::new(PingConfig {
+            ping_interval: Duration::default(),
+            stats_buffer_duration: Duration::from_secs(1),
+            ..Default::default()
+        });
+        let mut time_manager = TimeManager::default();
+        let mut tick_manager = TickManager::from_config(TickConfig::new(Duration::from_millis(10)));
+        for (frame, rtt) in rtts.iter().enumerate() {
+            time_manager.update(Duration::from_millis(100));
+            ping_manager.update(&time_manager);
+            ping_manager.sync_stats.push(
+                time_manager.current_time(),
+                SyncStats {
+                    round_trip_delay: *rtt,
+                },
+            );
+            ping_manager.compute_stats();
+            sync_manager.update(
+                &mut time_manager,
+                &mut tick_manager,
+                &ping_manager,
+                &InterpolationDelay::default(),
+                Duration::from_millis(100),
+            );
+            if sync_manager.is_synced() {
+                return Some(frame);
+            }
+        }
+        None
+    }
+
+    /// Check that on a jittery link, the handshake is only finalized once the jitter is below
+    /// the configured threshold
+    #[test]
+    fn test_sync_handshake_max_jitter() {
+        // the rtt alternates between 50ms and 150ms for 2 seconds, and then becomes stable
+        let rtts = (0..40)
+            .map(|i| {
+                if i >= 20 {
+                    Duration::from_millis(100)
+                } else if i % 2 == 0 {
+                    Duration::from_millis(50)
+                } else {
+                    Duration::from_millis(150)
+                }
+            })
+            .collect::<Vec<_>>();
+
+        // by default, we sync as soon as we have received enough pongs
+        assert_eq!(frames_until_synced(SyncConfig::default(), &rtts), Some(2));
+
+        // with a jitter threshold, we wait until the jittery pongs are out of the stats buffer
+        let synced_frame = frames_until_synced(
+            SyncConfig::default().handshake_max_jitter(Duration::from_millis(5)),
+            &rtts,
+        )
+        .expect("the client should sync once the link is stable");
+        assert!(synced_frame >= 20 + 9, "synced at frame {synced_frame}");
+    }
+
+    /// Check that the handshake is finalized after the timeout if the link never becomes stable
+    #[test]
+    fn test_sync_handshake_max_jitter_timeout() {
+        // the rtt alternates between 50ms and 150ms forever
+        let rtts = (0..40)
+            .map(|i| Duration::from_millis(if i % 2 == 0 { 50 } else { 150 }))
+            .collect::<Vec<_>>();
+        let config = SyncConfig::default().handshake_max_jitter(Duration::from_millis(5));
+        assert_eq!(frames_until_synced(config, &rtts), None);
+
+        // we have enough pings at frame 2, and each frame lasts 100ms
+        assert_eq!(
+            frames_until_synced(
+                config.handshake_max_jitter_timeout(Duration::from_secs(1)),
+                &rtts
+            ),
+            Some(2 + 9)
+        );
+    }
+
+    /// With jittery send times, including the sub-tick fraction in the packets lets the client estimate
+    /// the time at which the server sent the packet much more precisely than with the tick only
+    #[test]
+    fn test_sub_tick_fraction_server_time_estimate() {
+        let tick_duration = Duration::from_millis(16);
+        let max_error = |use_sub_tick: bool| {
+            let mut sync_manager =
+                SyncManager::new(SyncConfig::default(), PredictionConfig::default());
+            let mut max_error = Duration::ZERO;
+            // the server sends packets at irregular intervals that are not aligned with the ticks
+            let mut send_time = Duration::ZERO;
+            for i in 0..50u64 {
+                send_time += Duration::from_millis(10 + (i * 7) % 9);
+                let tick = Tick((send_time.as_nanos() / tick_duration.as_nanos()) as u16);
+                let overstep = (send_time - tick_duration * tick.0 as u32).as_secs_f32()
+                    / tick_duration.as_secs_f32();
+                sync_manager.latest_received_server_tick = Some(tick);
+                // the fraction is quantized to 1 byte in the packet header
+                sync_manager.latest_received_server_sub_tick = if use_sub_tick {
+                    SubTickFraction::new(overstep).fraction()
+                } else {
+                    0.0
+                };
+                sync_manager.update_server_time_estimate(tick_duration, Duration::ZERO);
+                let estimate = sync_manager.server_time_estimate().elapsed;
+                let error = if estimate > send_time {
+                    estimate - send_time
+                } else {
+                    send_time - estimate
+                };
+                max_error = max_error.max(error);
+            }
+            max_error
+        };
+        let error_without_sub_tick = max_error(false);
+        let error_with_sub_tick = max_error(true);
+        assert!(error_without_sub_tick > tick_duration / 2);
+        // the precision is limited by the quantization of the fraction to 1 byte
+        assert!(
+            error_with_sub_tick <= tick_duration / 255,
+            "{error_with_sub_tick:?}"
+        );
+    }
+    /// Check that the server can set the input delay of each client, regardless of their latency
+    #[test]
+    fn test_server_input_delay_command() {
+        let frame_duration = Duration::from_millis(10);
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..default()
+        };
+        let mut stepper = MultiBevyStepper::new(
+            shared_config,
+            SyncConfig::default().speedup_factor(1.0),
+            client::PredictionConfig::default(),
+            client::InterpolationConfig::default(),
+            frame_duration,
+        );
+        // the second client has a much higher latency than the first one
+        if let client::NetConfig::Netcode { io, .. } = &mut stepper
+            .client_app_2
+            .world_mut()
+            .resource_mut::<client::ClientConfig>()
+            .net
+        {
+            io.conditioner = Some(LinkConditionerConfig {
+                incoming_latency: Duration::from_millis(40),
+                incoming_jitter: Duration::ZERO,
+                incoming_loss: 0.0,
+            });
+        }
+        stepper.init();
+        for _ in 0..20 {
+            stepper.frame_step();
+        }
+        let input_delay_ticks = |app: &App| {
+            app.world()
+                .resource::<client::ConnectionManager>()
+                .input_delay_ticks(tick_duration)
+        };
+        assert_eq!(input_delay_ticks(&stepper.client_app_1), 0);
+        assert_eq!(input_delay_ticks(&stepper.client_app_2), 0);
+
+        // the low-latency client gets more input delay than the high-latency client
+        let mut commands = stepper.server_app.world_mut().commands();
+        commands.set_input_delay(ClientId::Netcode(TEST_CLIENT_ID_1), Some(6));
+        commands.set_input_delay(ClientId::Netcode(TEST_CLIENT_ID_2), Some(2));
+        stepper.server_app.world_mut().flush();
+        for _ in 0..20 {
+            stepper.frame_step();
+        }
+        assert_eq!(input_delay_ticks(&stepper.client_app_1), 6);
+        assert_eq!(input_delay_ticks(&stepper.client_app_2), 2);
+        assert!(stepper
+            .client_app_1
+            .world()
+            .resource::<client::ConnectionManager>()
+            .is_synced());
+        assert!(stepper
+            .client_app_2
+            .world()
+            .resource::<client::ConnectionManager>()
+            .is_synced());
+
+        // the clients can compute their own input delay again
+        let mut commands = stepper.server_app.world_mut().commands();
+        commands.set_input_delay(ClientId::Netcode(TEST_CLIENT_ID_1), None);
+        stepper.server_app.world_mut().flush();
+        for _ in 0..20 {
+            stepper.frame_step();
+        }
+        assert_eq!(input_delay_ticks(&stepper.client_app_1), 0);
+        assert_eq!(input_delay_ticks(&stepper.client_app_2), 2);
+    }
+
+    /// Check that the sync statistics are collected from the pongs, and can be reset
+    #[test]
+    fn test_sync_stats_reset() {
+        let mut stepper = BevyStepper::default();
+        for _ in 0..50 {
+            stepper.frame_step();
+        }
+        let stats = stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .sync_stats();
+        assert!(stats.sample_count > 0);
+
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<client::ConnectionManager>()
+            .reset_sync_stats();
+        let stats = stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .sync_stats();
+        assert_eq!(stats.sample_count, 0);
+        assert_eq!(stats.rtt, Duration::from_millis(100));
+        assert_eq!(stats.jitter, Duration::ZERO);
+
+        // the statistics are collected again from the next pongs
+        for _ in 0..50 {
+            stepper.frame_step();
+        }
+        let stats = stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .sync_stats();
+        assert!(stats.sample_count > 0);
+        assert!(stats.rtt < Duration::from_millis(100));
+    }
+
+    /// Check that with the fixed-delay sync mode, the client tick follows the server tick minus the
+    /// fixed delay
+    #[test]
+    fn test_sync_mode_fixed_delay() {
+        let frame_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(frame_duration),
+            ..default()
+        };
+        let client_config = client::ClientConfig {
+            sync: SyncConfig::default().mode(SyncMode::FixedDelay {
+                delay_ticks: 3,
+                tolerance_ticks: 0,
+            }),
+            ..default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, client_config, frame_duration);
+        stepper.init();
+        for _ in 0..20 {
+            stepper.frame_step();
+        }
+        for _ in 0..10 {
+            stepper.frame_step();
+            assert_eq!(stepper.client_tick(), stepper.server_tick() - 3u16);
+        }
+    }
+
+    /// Check that with the fixed-delay sync mode, the client tick is only snapped when it is further than
+    /// the tolerance from the objective tick
+    #[test]
+    fn test_sync_mode_fixed_delay_tolerance() {
+        let mut sync_manager = SyncManager::new(
+            SyncConfig::default().mode(SyncMode::FixedDelay {
+                delay_ticks: 3,
+                tolerance_ticks: 2,
+            }),
+            PredictionConfig::default(),
+        );
+        sync_manager.latest_received_server_tick = Some(Tick(100));
+        let mut time_manager = TimeManager::new();
+        let mut tick_manager = TickManager::from_config(TickConfig::new(Duration::from_millis(10)));
+
+        // within the tolerance: the client tick is not modified
+        tick_manager.set_tick_to(Tick(96));
+        assert!(sync_manager
+            .update_fixed_delay_tick(3, 2, &mut time_manager, &mut tick_manager)
+            .is_none());
+        assert_eq!(tick_manager.tick(), Tick(96));
+        tick_manager.set_tick_to(Tick(99));
+        assert!(sync_manager
+            .update_fixed_delay_tick(3, 2, &mut time_manager, &mut tick_manager)
+            .is_none());
+        assert_eq!(tick_manager.tick(), Tick(99));
+
+        // outside of the tolerance: the client tick snaps to the objective tick
+        tick_manager.set_tick_to(Tick(94));
+        assert!(sync_manager
+            .update_fixed_delay_tick(3, 2, &mut time_manager, &mut tick_manager)
+            .is_some());
+        assert_eq!(tick_manager.tick(), Tick(97));
+    }
+
+    /// Check that the client's wide tick is initialized from the server's wide tick, even if the server
+    /// has already wrapped its tick several times
+    #[test]
+    fn test_sync_wide_tick() {
+        let mut sync_manager = SyncManager::new(SyncConfig::default(), PredictionConfig::default());
+        sync_manager.server_pong_generation = 3;
+        sync_manager.server_pong_tick = Tick(39_990);
+        sync_manager.latest_received_server_tick = Some(Tick(40_000));
+        let server_wide_tick = sync_manager.server_wide_tick().unwrap();
+        assert_eq!(server_wide_tick, WideTick(3 * 65_536 + 40_000));
+
+        // the client snaps from tick 0 to a tick close to the server tick
+        let mut tick_manager = TickManager::from_config(TickConfig::new(Duration::from_millis(10)));
+        tick_manager.set_tick_to(Tick(40_005));
+        tick_manager.sync_wide_tick(server_wide_tick);
+        assert_eq!(tick_manager.wide_tick(), server_wide_tick + 5);
+
+        // the server tick wrapped since the latest pong
+        sync_manager.latest_received_server_tick = Some(Tick(10));
+        assert_eq!(
+            sync_manager.server_wide_tick(),
+            Some(WideTick(4 * 65_536 + 10))
+        );
+    }
+
+    /// Check that the client's wide tick matches the server's wide tick once the client is synced
+    #[test]
+    fn test_sync_wide_tick_stepper() {
+        let frame_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(frame_duration),
+            ..default()
+        };
+        let mut stepper = BevyStepper::new(
+            shared_config,
+            client::ClientConfig::default(),
+            frame_duration,
+        );
+        stepper.build();
+        // the client's wide tick starts with upper bits that don't match the server's
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<TickManager>()
+            .sync_wide_tick(WideTick(7 * 65_536));
+        stepper.start();
+        let client_wide_tick = stepper
+            .client_app
+            .world()
+            .resource::<TickManager>()
+            .wide_tick();
+        let server_wide_tick = stepper
+            .server_app
+            .world()
+            .resource::<TickManager>()
+            .wide_tick();
+        assert_eq!(client_wide_tick.tick(), stepper.client_tick());
+        assert!((client_wide_tick - server_wide_tick).abs() < 100);
+    }
+
+    /// Check that with pings disabled, the client syncs with the server using the RTT provided externally
+    #[test]
+    fn test_sync_ping_disabled_external_rtt() {
+        let frame_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(frame_duration),
+            ..default()
+        };
+        let mut stepper = BevyStepper::new(
+            shared_config,
+            client::ClientConfig::default(),
+            frame_duration,
+        );
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<client::ClientConfig>()
+            .ping
+            .enabled = false;
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<server::ServerConfig>()
+            .ping
+            .enabled = false;
+        stepper.init();
+
+        // no RTT is available yet, so the client cannot sync
+        assert!(!stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .is_synced());
+
+        // the client also needs to receive packets from the server to know the server tick
+        stepper
+            .server_app
+            .world_mut()
+            .spawn((ComponentSyncModeFull(0.0), Replicate::default()));
+        let rtt = Duration::from_millis(40);
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<client::ConnectionManager>()
+            .set_rtt(rtt, Duration::default());
+        for _ in 0..50 {
+            stepper.frame_step();
+        }
+
+        let connection = stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>();
+        assert!(connection.is_synced());
+        assert_eq!(connection.rtt(), rtt);
+        // no pings were exchanged
+        assert_eq!(connection.ping_manager.pings_sent, 0);
+        assert_eq!(
+            stepper
+                .server_app
+                .world()
+                .resource::<server::ConnectionManager>()
+                .connection(ClientId::Netcode(TEST_CLIENT_ID))
+                .unwrap()
+                .ping_manager
+                .pings_sent,
+            0
+        );
+        // the client runs ahead of the server by the RTT plus one tick of margin
+        let ahead = stepper.client_tick() - stepper.server_tick();
+        assert!((4..=6).contains(&ahead), "client is {ahead} ticks ahead");
+    }
+
+    /// Check that with pings disabled, the client still gets the tick generation of the server,
+    /// even if the server's tick already wrapped around before the client connected
+    #[test]
+    fn test_sync_wide_tick_ping_disabled() {
+        let frame_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(frame_duration),
+            ..default()
+        };
+        let mut stepper = BevyStepper::new(
+            shared_config,
+            client::ClientConfig::default(),
+            frame_duration,
+        );
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<client::ClientConfig>()
+            .ping
+            .enabled = false;
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<server::ServerConfig>()
+            .ping
+            .enabled = false;
+        stepper.build();
+        // the server has been running for several generations of ticks
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<TickManager>()
+            .sync_wide_tick(WideTick(5 * 65_536));
+        stepper.start();
+        stepper
+            .server_app
+            .world_mut()
+            .spawn((ComponentSyncModeFull(0.0), Replicate::default()));
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<client::ConnectionManager>()
+            .set_rtt(Duration::from_millis(40), Duration::default());
+        for _ in 0..50 {
+            stepper.frame_step();
+        }
+
+        assert!(stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .is_synced());
+        let client_wide_tick = stepper
+            .client_app
+            .world()
+            .resource::<TickManager>()
+            .wide_tick();
+        let server_wide_tick = stepper
+            .server_app
+            .world()
+            .resource::<TickManager>()
+            .wide_tick();
+        assert_eq!(server_wide_tick.0 >> 16, 5);
+        assert!(
+            (client_wide_tick - server_wide_tick).abs() < 100,
+            "client: {client_wide_tick:?}, server: {server_wide_tick:?}"
+        );
+    }
 }
@@ -4,6 +4,7 @@ use std::str::FromStr;
 use std::sync::Arc;
 
 use bevy::prelude::{Reflect, Resource};
+use bevy::utils::Duration;
 use enum_dispatch::enum_dispatch;
 #[cfg(all(feature = "steam", not(target_family = "wasm")))]
 use parking_lot::RwLock;
@@ -57,9 +58,33 @@ pub trait NetClient: Send + Sync {
     /// Send a packet to the server
     fn send(&mut self, buf: &[u8]) -> Result<(), ConnectionError>;
 
+    /// Send a packet that contains messages from a reliable channel to the server.
+    ///
+    /// The reliable channels already retransmit their lost messages, so by default this is the same as [`send`](NetClient::send).
+    /// Transports that have their own reliability layer (like Steam) can override it to send the packet reliably.
+    fn send_reliable(&mut self, buf: &[u8]) -> Result<(), ConnectionError> {
+        self.send(buf)
+    }
+
+    /// Returns true if [`send_reliable`](NetClient::send_reliable) uses a reliability layer of the transport.
+    ///
+    /// In that case the messages of reliable and unreliable channels are sent in separate packets, so that
+    /// the unreliable messages are not delayed by the retransmissions of the transport.
+    fn supports_reliable_send(&self) -> bool {
+        false
+    }
+
     /// Get the id of the client
     fn id(&self) -> ClientId;
 
+    /// The round-trip time to the server, if the transport measures it.
+    ///
+    /// When pings are disabled with [`PingConfig::enabled`](crate::prelude::PingConfig::enabled), this is used
+    /// to sync the client with the server.
+    fn rtt(&self) -> Option<Duration> {
+        None
+    }
+
     /// Get the local address of the client
     fn local_addr(&self) -> SocketAddr;
 
@@ -87,10 +112,18 @@ pub struct ClientConnection {
 }
 
 /// Enumerates the possible reasons for a client to disconnect from the server
+///
+/// For example, a connection timeout is reported as `Netcode(ClientState::ConnectionTimedOut)`.
 #[derive(Debug)]
 pub enum DisconnectReason {
     Transport(crate::transport::error::Error),
     Netcode(super::netcode::ClientState),
+    /// The server closed the connection
+    ServerClosed,
+    /// The server kicked the client, with a message explaining why
+    Kicked(String),
+    /// The client refused to start connecting, for example because its config is invalid
+    Refused(ConnectionError),
     #[cfg(all(feature = "steam", not(target_family = "wasm")))]
     Steam(steamworks::networking_types::NetConnectionEnd),
 }
@@ -117,9 +150,13 @@ pub enum NetConfig {
         config: SteamConfig,
         conditioner: Option<LinkConditionerConfig>,
     },
-    Local {
-        id: u64,
-    },
+    /// Connection used by the client running in the same app as the server in HostServer mode.
+    ///
+    /// The `id` is used as a [`ClientId::Local`], which cannot collide with the ids of the
+    /// remote clients connecting via netcode or steam.
+    /// In HostServer mode, a client with any other connection refuses to connect, and emits a
+    /// `DisconnectEvent` with [`DisconnectReason::Refused`].
+    Local { id: u64 },
 }
 
 impl Default for NetConfig {
@@ -211,10 +248,22 @@ impl NetClient for ClientConnection {
         self.client.send(buf)
     }
 
+    fn send_reliable(&mut self, buf: &[u8]) -> Result<(), ConnectionError> {
+        self.client.send_reliable(buf)
+    }
+
+    fn supports_reliable_send(&self) -> bool {
+        self.client.supports_reliable_send()
+    }
+
     fn id(&self) -> ClientId {
         self.client.id()
     }
 
+    fn rtt(&self) -> Option<Duration> {
+        self.client.rtt()
+    }
+
     fn local_addr(&self) -> SocketAddr {
         self.client.local_addr()
     }
@@ -319,6 +368,8 @@ pub enum ConnectionError {
     NotFound,
     #[error("client is not connected")]
     NotConnected,
+    #[error("in HostServer mode the client connection must be of type NetConfig::Local")]
+    HostServerNotLocal,
     #[error(transparent)]
     Transport(#[from] crate::transport::error::Error),
     #[error("netcode error: {0}")]
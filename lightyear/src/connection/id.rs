@@ -6,6 +6,11 @@ use byteorder::{NetworkEndian, ReadBytesExt, WriteBytesExt};
 use serde::{Deserialize, Serialize};
 use std::fmt::Formatter;
 
+/// Unique identifier of a client.
+///
+/// Each variant is a separate id space: the host client in HostServer mode always uses
+/// [`ClientId::Local`], so it can never collide with a [`ClientId::Netcode`] or [`ClientId::Steam`]
+/// client, even if the inner `u64` values are the same.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Reflect)]
 pub enum ClientId {
     /// A client id that is unique between netcode connections
@@ -55,6 +60,8 @@ impl ToBytes for ClientId {
 impl ClientId {
     // TODO: add impl From<ClientId> for u64?
     /// Convert a ClientId to a u64 representation
+    ///
+    /// The kind of the id is not included, so ids from different variants can have the same bits.
     pub fn to_bits(&self) -> u64 {
         match self {
             ClientId::Netcode(x) => *x,
@@ -192,6 +192,8 @@ pub struct NetcodeClient<Ctx = ()> {
     replay_protection: ReplayProtection,
     should_disconnect: bool,
     should_disconnect_state: ClientState,
+    /// The disconnect packet received from the server, if the server closed the connection
+    server_disconnect: Option<DisconnectPacket>,
     packet_queue: VecDeque<RecvPayload>,
     buffer_pool: Pool<Vec<u8>>,
     cfg: ClientConfig<Ctx>,
@@ -227,6 +229,7 @@ impl<Ctx> NetcodeClient<Ctx> {
             replay_protection: ReplayProtection::new(),
             should_disconnect: false,
             should_disconnect_state: ClientState::Disconnected,
+            server_disconnect: None,
             packet_queue: VecDeque::new(),
             buffer_pool: Pool::new(10, || vec![0u8; MAX_PKT_BUF_SIZE]),
             cfg,
@@ -423,8 +426,9 @@ impl<Ctx> NetcodeClient<Ctx> {
                 // TODO: control the size/memory of the packet queue?
                 self.packet_queue.push_back(buf);
             }
-            (Packet::Disconnect(_), ClientState::Connected) => {
-                debug!("client received disconnect packet from server");
+            (Packet::Disconnect(pkt), ClientState::Connected) => {
+                debug!(reason = ?pkt.reason, "client received disconnect packet from server");
+                self.server_disconnect = Some(pkt);
                 self.should_disconnect = true;
                 self.should_disconnect_state = ClientState::Disconnected;
             }
@@ -523,6 +527,7 @@ impl<Ctx> NetcodeClient<Ctx> {
     /// This function does not perform any IO, it only readies the client to send/receive packets on the next call to [`update`](NetcodeClient::update). <br>
     pub fn connect(&mut self) {
         self.reset_connection();
+        self.server_disconnect = None;
         self.set_state(ClientState::SendingConnectionRequest);
         info!(
             "client connecting to server {} [{}/{}]",
@@ -690,6 +695,17 @@ pub(crate) mod connection {
                     ConnectionState::Connecting
                 }
                 ClientState::Connected => ConnectionState::Connected,
+                ClientState::Disconnected if self.client.server_disconnect.is_some() => {
+                    let reason = match &self.client.server_disconnect {
+                        Some(DisconnectPacket {
+                            reason: Some(reason),
+                        }) => DisconnectReason::Kicked(reason.clone()),
+                        _ => DisconnectReason::ServerClosed,
+                    };
+                    ConnectionState::Disconnected {
+                        reason: Some(reason),
+                    }
+                }
                 _ => ConnectionState::Disconnected {
                     reason: Some(DisconnectReason::Netcode(self.client.state)),
                 },
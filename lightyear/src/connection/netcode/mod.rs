@@ -124,7 +124,9 @@ loop {
 pub use client::{connection::Client, ClientConfig, ClientState, NetcodeClient};
 pub use crypto::{generate_key, try_generate_key, Key};
 pub use error::{Error, Result};
-pub use server::{connection::Server, Callback, ClientId, NetcodeServer, ServerConfig};
+pub use server::{
+    connection::Server, Callback, ClientId, NetcodeServer, ServerConfig, MAX_CLIENTS,
+};
 pub use token::{ConnectToken, ConnectTokenBuilder, InvalidTokenError};
 
 mod bytes;
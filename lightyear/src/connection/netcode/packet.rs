@@ -350,22 +350,59 @@ impl PayloadPacket<'_> {
     }
 }
 
-pub struct DisconnectPacket {}
+/// Packet sent to notify the other side of a disconnection.
+///
+/// Unlike the netcode.io standard, where the disconnect packet has no payload, this packet always
+/// starts with a tag byte (0: no reason, 1: a reason follows, prefixed by its length as a u8).
+/// A disconnect packet from a standard netcode.io implementation is therefore rejected as malformed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DisconnectPacket {
+    /// Optional message sent by the server to explain why the client was disconnected
+    pub reason: Option<String>,
+}
 
 impl DisconnectPacket {
     pub fn create() -> Packet<'static> {
-        Packet::Disconnect(Self {})
+        Packet::Disconnect(Self { reason: None })
+    }
+
+    pub fn create_with_reason(reason: Option<String>) -> Packet<'static> {
+        Packet::Disconnect(Self { reason })
     }
 }
 
 impl Bytes for DisconnectPacket {
     type Error = io::Error;
-    fn write_to(&self, _writer: &mut impl WriteBytesExt) -> Result<(), Self::Error> {
+    fn write_to(&self, writer: &mut impl WriteBytesExt) -> Result<(), Self::Error> {
+        let Some(reason) = &self.reason else {
+            writer.write_u8(0)?;
+            return Ok(());
+        };
+        writer.write_u8(1)?;
+        // the reason cannot exceed u8::MAX in size
+        if reason.len() > u8::MAX as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "disconnect reason too long",
+            ));
+        }
+        writer.write_u8(reason.len() as u8)?;
+        writer.write_all(reason.as_bytes())?;
         Ok(())
     }
 
-    fn read_from(_reader: &mut impl byteorder::ReadBytesExt) -> Result<Self, io::Error> {
-        Ok(Self {})
+    fn read_from(reader: &mut impl byteorder::ReadBytesExt) -> Result<Self, io::Error> {
+        if reader.read_u8()? == 0 {
+            return Ok(Self { reason: None });
+        }
+        let len = reader.read_u8()? as usize;
+        let mut string_buf = vec![0; len];
+        reader.read_exact(&mut string_buf)?;
+        let reason = String::from_utf8(string_buf)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "invalid disconnect reason"))?;
+        Ok(Self {
+            reason: Some(reason),
+        })
     }
 }
 
@@ -794,7 +831,9 @@ mod tests {
         let sequence = 0u64;
         let mut replay_protection = ReplayProtection::new();
 
-        let packet = Packet::Disconnect(DisconnectPacket {});
+        let packet = Packet::Disconnect(DisconnectPacket {
+            reason: Some(String::from("kicked")),
+        });
 
         let mut buf = [0u8; MAX_PKT_BUF_SIZE];
         let size = packet
@@ -811,9 +850,10 @@ mod tests {
         )
         .unwrap();
 
-        let Packet::Disconnect(_disconnect_pkt) = packet else {
+        let Packet::Disconnect(disconnect_pkt) = packet else {
             panic!("wrong packet type");
         };
+        assert_eq!(disconnect_pkt.reason, Some(String::from("kicked")));
     }
 
     #[test]
@@ -187,6 +187,22 @@ impl ConnectionCache {
         self.replay_protection.remove(&client_id);
         self.clients.remove(&client_id);
     }
+    /// Remove a connection that never completed the handshake
+    fn remove_pending(&mut self, client_id: ClientId) {
+        let Some(conn) = self.clients.get(&client_id) else {
+            return;
+        };
+        if conn.is_connected() {
+            return;
+        }
+        self.client_id_map.remove(&conn.addr);
+        self.replay_protection.remove(&client_id);
+        self.clients.remove(&client_id);
+    }
+    /// Number of connections that are still performing the handshake
+    fn num_pending(&self) -> usize {
+        self.clients.values().filter(|c| !c.is_connected()).count()
+    }
 
     fn ids(&self) -> Vec<ClientId> {
         self.clients.keys().cloned().collect()
@@ -220,6 +236,7 @@ pub type Callback<Ctx> = Box<dyn FnMut(ClientId, SocketAddr, &mut Ctx) + Send +
 ///
 /// * `num_disconnect_packets` - The number of redundant disconnect packets that will be sent to a client when the server is disconnecting it.
 /// * `keep_alive_send_rate` - The rate at which keep-alive packets will be sent to clients.
+/// * `max_pending_connections` - The maximum number of clients that can be performing the connection handshake at the same time.
 /// * `on_connect` - A callback that will be called when a client is connected to the server.
 /// * `on_disconnect` - A callback that will be called when a client is disconnected from the server.
 ///
@@ -244,6 +261,7 @@ pub struct ServerConfig<Ctx> {
     keep_alive_send_rate: f64,
     token_expire_secs: i32,
     client_timeout_secs: i32,
+    max_pending_connections: usize,
     connection_request_handler: Arc<dyn ConnectionRequestHandler>,
     server_addr: SocketAddr,
     context: Ctx,
@@ -258,6 +276,7 @@ impl Default for ServerConfig<()> {
             keep_alive_send_rate: PACKET_SEND_RATE_SEC,
             token_expire_secs: TOKEN_EXPIRE_SEC,
             client_timeout_secs: CLIENT_TIMEOUT_SECS,
+            max_pending_connections: MAX_CLIENTS,
             connection_request_handler: Arc::new(DefaultConnectionRequestHandler),
             server_addr: SocketAddr::from(([0, 0, 0, 0], 0)),
             context: (),
@@ -279,6 +298,7 @@ impl<Ctx> ServerConfig<Ctx> {
             keep_alive_send_rate: PACKET_SEND_RATE_SEC,
             token_expire_secs: TOKEN_EXPIRE_SEC,
             client_timeout_secs: CLIENT_TIMEOUT_SECS,
+            max_pending_connections: MAX_CLIENTS,
             connection_request_handler: Arc::new(DefaultConnectionRequestHandler),
             server_addr: SocketAddr::from(([0, 0, 0, 0], 0)),
             context: ctx,
@@ -304,6 +324,14 @@ impl<Ctx> ServerConfig<Ctx> {
         self.client_timeout_secs = client_timeout_secs;
         self
     }
+    /// Set the maximum number of clients that can be performing the connection handshake at the same time. <br>
+    /// Connection requests from new clients are ignored while this limit is reached; the clients keep
+    /// re-sending their requests so they will be accepted once a handshake completes or times out.
+    /// The default is [`MAX_CLIENTS`].
+    pub fn max_pending_connections(mut self, max_pending_connections: usize) -> Self {
+        self.max_pending_connections = max_pending_connections;
+        self
+    }
     /// Set the duration (in seconds) after which ConnectTokens generated by the server will expire
     /// The default is 30 seconds.
     pub fn token_expire_secs(mut self, expire_secs: i32) -> Self {
@@ -594,6 +622,13 @@ impl<Ctx> NetcodeServer<Ctx> {
             debug!("server ignored connection request. a client with this id is already connected");
             return Ok(());
         };
+        // clients that are already performing the handshake can re-send their request
+        if self.conn_cache.find_by_addr(&from_addr).is_none()
+            && self.conn_cache.num_pending() >= self.cfg.max_pending_connections
+        {
+            debug!("server ignored connection request. too many pending connections");
+            return Ok(());
+        };
         let entry = TokenEntry {
             time: self.time,
             addr: from_addr,
@@ -616,12 +651,11 @@ impl<Ctx> NetcodeServer<Ctx> {
             )?;
             return Ok(());
         };
-        if let Some(denied_reason) = self
-            .cfg
-            .connection_request_handler
-            .handle_request(crate::prelude::ClientId::Netcode(token.client_id))
-        {
-            debug!("server denied connection request. handle_connection_request_fn returned false");
+        if let Some(denied_reason) = self.cfg.connection_request_handler.handle_netcode_request(
+            crate::prelude::ClientId::Netcode(token.client_id),
+            &token.user_data,
+        ) {
+            debug!("server denied connection request: {denied_reason:?}");
             self.send_to_addr(
                 DeniedPacket::create(denied_reason),
                 from_addr,
@@ -713,6 +747,13 @@ impl<Ctx> NetcodeServer<Ctx> {
                 continue;
             };
             if !client.is_connected() {
+                // drop the handshakes that were not completed in time, to free the pending slots
+                if client.timeout.is_positive()
+                    && client.last_access_time + (client.timeout as f64) < self.time
+                {
+                    debug!("server timed out pending connection with client {id}");
+                    self.conn_cache.remove_pending(id);
+                }
                 continue;
             }
             let addr = client.addr;
@@ -944,6 +985,39 @@ impl<Ctx> NetcodeServer<Ctx> {
     ///
     /// The server will send a number of redundant disconnect packets to the client, and then remove its connection info.
     pub fn disconnect(&mut self, client_id: ClientId, io: &mut Io) -> Result<()> {
+        self.disconnect_inner(client_id, io, true, None)
+    }
+
+    /// Disconnects a client, and sends it a `reason` explaining why it was disconnected.
+    ///
+    /// The reason cannot be longer than 255 bytes.
+    pub fn kick(&mut self, client_id: ClientId, reason: String, io: &mut Io) -> Result<()> {
+        if reason.len() > u8::MAX as usize {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "the kick reason cannot be longer than 255 bytes",
+            )
+            .into());
+        }
+        self.disconnect_inner(client_id, io, true, Some(reason))
+    }
+
+    /// Disconnects a client without notifying it, as if the network link between the
+    /// server and the client had been cut.
+    ///
+    /// No disconnect packets are sent: the client will only notice the disconnection
+    /// once it times out.
+    pub fn disconnect_silently(&mut self, client_id: ClientId, io: &mut Io) -> Result<()> {
+        self.disconnect_inner(client_id, io, false, None)
+    }
+
+    fn disconnect_inner(
+        &mut self,
+        client_id: ClientId,
+        io: &mut Io,
+        notify: bool,
+        reason: Option<String>,
+    ) -> Result<()> {
         let Some(conn) = self.conn_cache.clients.get_mut(&client_id) else {
             return Ok(());
         };
@@ -953,12 +1027,21 @@ impl<Ctx> NetcodeServer<Ctx> {
         let addr = conn.addr;
         debug!("server disconnecting client {client_id}");
         self.on_disconnect(client_id, addr);
-        for _ in 0..self.cfg.num_disconnect_packets {
+        let num_disconnect_packets = if notify {
+            self.cfg.num_disconnect_packets
+        } else {
+            0
+        };
+        for _ in 0..num_disconnect_packets {
             // self.send_to_client(DisconnectPacket::create(), client_id, io)?;
 
             // we do not use ? here because we want to continue even if the send fails
             let _ = self
-                .send_to_client(DisconnectPacket::create(), client_id, io)
+                .send_to_client(
+                    DisconnectPacket::create_with_reason(reason.clone()),
+                    client_id,
+                    io,
+                )
                 .inspect_err(|e| {
                     error!("server failed to send disconnect packet: {e}");
                 });
@@ -1002,6 +1085,11 @@ impl<Ctx> NetcodeServer<Ctx> {
         self.conn_cache.clients.keys().copied()
     }
 
+    /// Gets the number of clients that are still performing the connection handshake.
+    pub fn num_pending_connections(&self) -> usize {
+        self.conn_cache.num_pending()
+    }
+
     /// Gets the number of connected clients.
     pub fn num_connected_clients(&self) -> usize {
         self.conn_cache
@@ -1038,6 +1126,10 @@ pub(crate) mod connection {
         pub(crate) server: NetcodeServer<NetcodeServerContext>,
         io_config: IoConfig,
         io: Option<Io>,
+        /// Disconnections that were triggered manually outside of [`try_update`](NetServer::try_update).
+        /// They are reported in the next update, otherwise they would be cleared before
+        /// the server gets a chance to handle them.
+        pending_disconnections: Vec<id::ClientId>,
     }
 
     impl NetServer for Server {
@@ -1071,12 +1163,42 @@ pub(crate) mod connection {
         }
 
         /// Disconnect a client from the server
-        /// (also adds the client_id to the list of newly disconnected clients)
+        /// (also adds the client_id to the list of newly disconnected clients of the next update)
         fn disconnect(&mut self, client_id: id::ClientId) -> Result<(), ConnectionError> {
             match client_id {
                 id::ClientId::Netcode(id) => {
                     if let Some(io) = self.io.as_mut() {
-                        self.server.disconnect(id, io)?
+                        let num_disconnections = self.server.cfg.context.disconnections.len();
+                        self.server.disconnect(id, io)?;
+                        self.defer_disconnections(num_disconnections);
+                    }
+                    Ok(())
+                }
+                _ => Err(ConnectionError::InvalidConnectionType),
+            }
+        }
+
+        fn kick(&mut self, client_id: id::ClientId, reason: String) -> Result<(), ConnectionError> {
+            match client_id {
+                id::ClientId::Netcode(id) => {
+                    if let Some(io) = self.io.as_mut() {
+                        let num_disconnections = self.server.cfg.context.disconnections.len();
+                        self.server.kick(id, reason, io)?;
+                        self.defer_disconnections(num_disconnections);
+                    }
+                    Ok(())
+                }
+                _ => Err(ConnectionError::InvalidConnectionType),
+            }
+        }
+
+        fn simulate_disconnect(&mut self, client_id: id::ClientId) -> Result<(), ConnectionError> {
+            match client_id {
+                id::ClientId::Netcode(id) => {
+                    if let Some(io) = self.io.as_mut() {
+                        let num_disconnections = self.server.cfg.context.disconnections.len();
+                        self.server.disconnect_silently(id, io)?;
+                        self.defer_disconnections(num_disconnections);
                     }
                     Ok(())
                 }
@@ -1096,6 +1218,11 @@ pub(crate) mod connection {
             // reset the new connections/disconnections
             self.server.cfg.context.connections.clear();
             self.server.cfg.context.disconnections.clear();
+            self.server
+                .cfg
+                .context
+                .disconnections
+                .append(&mut self.pending_disconnections);
 
             self.server.try_update(delta_ms, io)?;
             Ok(())
@@ -1155,6 +1282,7 @@ pub(crate) mod connection {
             cfg = cfg.keep_alive_send_rate(config.keep_alive_send_rate);
             cfg = cfg.num_disconnect_packets(config.num_disconnect_packets);
             cfg = cfg.client_timeout_secs(config.client_timeout_secs);
+            cfg = cfg.max_pending_connections(config.max_pending_connections);
             cfg.connection_request_handler = config.connection_request_handler;
             let server = NetcodeServer::with_config(config.protocol_id, config.private_key, cfg)
                 .expect("Could not create server netcode");
@@ -1163,9 +1291,17 @@ pub(crate) mod connection {
                 server,
                 io_config,
                 io: None,
+                pending_disconnections: vec![],
             }
         }
 
+        /// Move the disconnections that were added after index `from` to the list of pending
+        /// disconnections, so that they get reported during the next update
+        fn defer_disconnections(&mut self, from: usize) {
+            self.pending_disconnections
+                .extend(self.server.cfg.context.disconnections.drain(from..));
+        }
+
         /// Disconnect a client from the server
         /// (also adds the client_id to the list of newly disconnected clients)
         pub(crate) fn disconnect_by_addr(
@@ -1179,3 +1315,73 @@ pub(crate) mod connection {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::netcode::NETCODE_VERSION;
+
+    const PROTOCOL_ID: u64 = 0x1234;
+
+    struct NoopSender;
+
+    impl PacketSender for NoopSender {
+        fn send(&mut self, _: &[u8], _: &SocketAddr) -> crate::transport::error::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Create a connection request for a new client, with the token already decrypted
+    fn request_packet(server: &mut NetcodeServer, client_id: ClientId) -> RequestPacket {
+        let token = server
+            .token(client_id, SocketAddr::from(([127, 0, 0, 1], 5000)))
+            .timeout_seconds(5)
+            .generate()
+            .unwrap();
+        let mut packet = RequestPacket {
+            version_info: *NETCODE_VERSION,
+            protocol_id: PROTOCOL_ID,
+            expire_timestamp: token.expire_timestamp,
+            token_nonce: token.nonce,
+            token_data: Box::new(token.private_data),
+        };
+        packet.decrypt_token_data(server.private_key).unwrap();
+        packet
+    }
+
+    /// Check that the number of concurrent handshakes is capped by `max_pending_connections`
+    #[test]
+    fn test_max_pending_connections() {
+        let cfg = ServerConfig::default().max_pending_connections(2);
+        let mut server =
+            NetcodeServer::with_config(PROTOCOL_ID, crypto::generate_key(), cfg).unwrap();
+        let addr = |port| SocketAddr::from(([127, 0, 0, 1], port));
+
+        // flood the server with connection requests
+        for i in 0..5 {
+            let packet = request_packet(&mut server, i);
+            server
+                .process_connection_request(addr(6000 + i as u16), packet, &mut NoopSender)
+                .unwrap();
+        }
+        assert_eq!(server.num_pending_connections(), 2);
+
+        // a client that is already pending can re-send its request
+        let packet = request_packet(&mut server, 0);
+        server
+            .process_connection_request(addr(6000), packet, &mut NoopSender)
+            .unwrap();
+        assert_eq!(server.num_pending_connections(), 2);
+
+        // the handshakes that are not completed in time free their slot
+        server.time += 6.0;
+        server.conn_cache.update(6.0);
+        server.check_for_timeouts();
+        assert_eq!(server.num_pending_connections(), 0);
+        let packet = request_packet(&mut server, 3);
+        server
+            .process_connection_request(addr(6003), packet, &mut NoopSender)
+            .unwrap();
+        assert_eq!(server.num_pending_connections(), 1);
+    }
+}
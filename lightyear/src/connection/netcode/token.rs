@@ -4,6 +4,7 @@ use std::{
     net::{Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs},
 };
 
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use byteorder::{LittleEndian, WriteBytesExt};
 use chacha20poly1305::{aead::OsRng, AeadCore, XChaCha20Poly1305, XNonce};
 use thiserror::Error;
@@ -31,6 +32,10 @@ pub enum InvalidTokenError {
     InvalidTimestamp,
     #[error("invalid version")]
     InvalidVersion,
+    #[error("invalid token length (must be {CONNECT_TOKEN_BYTES} bytes): {0}")]
+    InvalidLength(usize),
+    #[error("invalid base64 encoding: {0}")]
+    Base64(#[from] base64::DecodeError),
     #[error("io error: {0}")]
     Io(#[from] io::Error),
 }
@@ -446,6 +451,25 @@ impl ConnectToken {
         let mut cursor = io::Cursor::new(bytes);
         Self::read_from(&mut cursor)
     }
+
+    /// Encodes the token as a base64 string (standard alphabet, with padding).
+    ///
+    /// This is useful to hand the token to a web client, for example in the response of an HTTPS request.
+    /// The client can decode it with [`ConnectToken::from_base64`].
+    pub fn to_base64(&self) -> Result<String, io::Error> {
+        Ok(BASE64.encode(self.clone().try_into_bytes()?))
+    }
+
+    /// Decodes a token from a base64 string created with [`ConnectToken::to_base64`].
+    ///
+    /// Returns an error if the string is not valid base64, or does not decode to a 2048-byte token.
+    pub fn from_base64(encoded: &str) -> Result<Self, InvalidTokenError> {
+        let bytes = BASE64.decode(encoded.trim())?;
+        if bytes.len() != CONNECT_TOKEN_BYTES {
+            return Err(InvalidTokenError::InvalidLength(bytes.len()));
+        }
+        Self::try_from_bytes(&bytes)
+    }
 }
 
 impl Bytes for ConnectToken {
@@ -715,4 +739,37 @@ mod tests {
                 assert_eq!(have, expected);
             });
     }
+
+    #[test]
+    fn connect_token_base64() {
+        let connect_token = ConnectToken::build("127.0.0.1:12345", 1, 4, [0x42; PRIVATE_KEY_BYTES])
+            .user_data([0x11; USER_DATA_BYTES])
+            .generate()
+            .unwrap();
+
+        let encoded = connect_token.to_base64().unwrap();
+        let decoded = ConnectToken::from_base64(&encoded).unwrap();
+        assert_eq!(
+            decoded.try_into_bytes().unwrap(),
+            connect_token.try_into_bytes().unwrap()
+        );
+    }
+
+    #[test]
+    fn connect_token_base64_malformed() {
+        assert!(matches!(
+            ConnectToken::from_base64("not a base64 token!"),
+            Err(InvalidTokenError::Base64(_))
+        ));
+        // valid base64, but the token is truncated
+        assert!(matches!(
+            ConnectToken::from_base64(&BASE64.encode([0; 100])),
+            Err(InvalidTokenError::InvalidLength(100))
+        ));
+        // the token has the correct length, but its content is invalid
+        assert!(matches!(
+            ConnectToken::from_base64(&BASE64.encode([0; CONNECT_TOKEN_BYTES])),
+            Err(InvalidTokenError::InvalidVersion)
+        ));
+    }
 }
@@ -1,5 +1,5 @@
 use bevy::prelude::Resource;
-use bevy::utils::HashMap;
+use bevy::utils::{Duration, HashMap};
 use enum_dispatch::enum_dispatch;
 #[cfg(all(feature = "steam", not(target_family = "wasm")))]
 use parking_lot::RwLock;
@@ -8,6 +8,7 @@ use std::fmt::Debug;
 use std::sync::Arc;
 
 use crate::connection::id::ClientId;
+use crate::connection::netcode::USER_DATA_BYTES;
 #[cfg(all(feature = "steam", not(target_family = "wasm")))]
 use crate::connection::steam::{server::SteamConfig, steamworks_client::SteamworksClient};
 use crate::packet::packet_builder::RecvPayload;
@@ -35,7 +36,25 @@ pub trait ConnectionRequestHandler: Debug + Send + Sync {
     /// Handle a connection request from a client.
     /// Returns None if the connection is accepted,
     /// Returns Some(reason) if the connection is denied.
-    fn handle_request(&self, client_id: ClientId) -> Option<DeniedReason>;
+    ///
+    /// By default, all connection requests are accepted.
+    fn handle_request(&self, client_id: ClientId) -> Option<DeniedReason> {
+        None
+    }
+
+    /// Handle a netcode connection request from a client, with access to the `user_data`
+    /// contained in the client's `ConnectToken`.
+    ///
+    /// The `user_data` is set by the backend when generating the `ConnectToken`, so it can be
+    /// trusted and used to carry information such as a region or a matchmaking ticket.
+    /// By default, the `user_data` is ignored and this calls [`handle_request`](ConnectionRequestHandler::handle_request).
+    fn handle_netcode_request(
+        &self,
+        client_id: ClientId,
+        user_data: &[u8; USER_DATA_BYTES],
+    ) -> Option<DeniedReason> {
+        self.handle_request(client_id)
+    }
 }
 
 /// By default, all connection requests are accepted by the server.
@@ -62,8 +81,42 @@ pub trait NetServer: Send + Sync {
     //  and we decide whether to accept it or not
     /// Disconnect a specific client
     /// Is also responsible for adding the client to the list of new disconnections.
+    ///
+    /// The disconnection can be reported by [`new_disconnections`](NetServer::new_disconnections) right away
+    /// or after the next [`try_update`](NetServer::try_update). The netcode server reports it after the next
+    /// update, so that a disconnection triggered between two updates is not cleared before the server handles it.
     fn disconnect(&mut self, client_id: ClientId) -> Result<(), ConnectionError>;
 
+    /// Disconnect a specific client, and send it a `reason` explaining why it was disconnected.
+    ///
+    /// The netcode server sends the reason in its disconnect packet, and the client receives a
+    /// [`DisconnectReason::Kicked`](crate::connection::client::DisconnectReason::Kicked).
+    /// The Steam server passes the reason as the debug string of the closed connection, which is not
+    /// reported to the client.
+    ///
+    /// By default the reason is not sent and this is the same as [`disconnect`](NetServer::disconnect).
+    fn kick(&mut self, client_id: ClientId, reason: String) -> Result<(), ConnectionError> {
+        self.disconnect(client_id)
+    }
+
+    /// Disconnect a specific client as if the network connection had been lost:
+    /// the client is not notified and will only notice the disconnection when it times out.
+    ///
+    /// This is useful to test reconnection or failover logic.
+    /// By default this returns [`ConnectionError::NotSupported`], for connections that cannot
+    /// drop a client without notifying it.
+    fn simulate_disconnect(&mut self, _client_id: ClientId) -> Result<(), ConnectionError> {
+        Err(ConnectionError::NotSupported)
+    }
+
+    /// The round-trip time to the client, if the transport measures it.
+    ///
+    /// When pings are disabled with [`PingConfig::enabled`](crate::prelude::PingConfig::enabled), this is used
+    /// as the RTT of the client's connection.
+    fn rtt(&self, _client_id: ClientId) -> Option<Duration> {
+        None
+    }
+
     /// Return the list of connected clients
     fn connected_client_ids(&self) -> Vec<ClientId>;
 
@@ -76,6 +129,22 @@ pub trait NetServer: Send + Sync {
     /// Send a packet to one of the connected clients
     fn send(&mut self, buf: &[u8], client_id: ClientId) -> Result<(), ConnectionError>;
 
+    /// Send a packet that contains messages from a reliable channel to one of the connected clients.
+    ///
+    /// The reliable channels already retransmit their lost messages, so by default this is the same as [`send`](NetServer::send).
+    /// Transports that have their own reliability layer (like Steam) can override it to send the packet reliably.
+    fn send_reliable(&mut self, buf: &[u8], client_id: ClientId) -> Result<(), ConnectionError> {
+        self.send(buf, client_id)
+    }
+
+    /// Returns true if [`send_reliable`](NetServer::send_reliable) uses a reliability layer of the transport.
+    ///
+    /// In that case the messages of reliable and unreliable channels are sent in separate packets, so that
+    /// the unreliable messages are not delayed by the retransmissions of the transport.
+    fn supports_reliable_send(&self) -> bool {
+        false
+    }
+
     fn new_connections(&self) -> Vec<ClientId>;
 
     fn new_disconnections(&self) -> Vec<ClientId>;
@@ -214,6 +283,8 @@ impl ServerConnections {
     }
 
     /// Disconnect a specific client
+    ///
+    /// The [`DisconnectEvent`](crate::server::events::DisconnectEvent) is emitted during the next update of the server.
     pub fn disconnect(&mut self, client_id: ClientId) -> Result<(), ConnectionError> {
         self.client_server_map.get(&client_id).map_or(
             Err(ConnectionError::ConnectionNotFound),
@@ -227,6 +298,38 @@ impl ServerConnections {
         )
     }
 
+    /// Disconnect a specific client, and send it a `reason` explaining why it was disconnected.
+    ///
+    /// With netcode, the client will receive a [`DisconnectReason::Kicked`](crate::connection::client::DisconnectReason::Kicked)
+    /// in its `DisconnectEvent`. See [`NetServer::kick`] for the other connections.
+    pub fn kick(
+        &mut self,
+        client_id: ClientId,
+        reason: impl Into<String>,
+    ) -> Result<(), ConnectionError> {
+        self.client_server_map
+            .get(&client_id)
+            .map_or(Err(ConnectionError::ConnectionNotFound), |&server_idx| {
+                self.servers[server_idx].kick(client_id, reason.into())
+            })
+    }
+
+    /// Disconnect a specific client as if the network connection had been lost.
+    ///
+    /// The server handles the disconnection immediately, but the client is not notified:
+    /// it will only be disconnected once it stops hearing from the server and times out.
+    /// This is useful to test how your app handles unexpected disconnections.
+    ///
+    /// Returns [`ConnectionError::NotSupported`] if the connection cannot drop the client without
+    /// notifying it (e.g. Steam sockets, which always notify the peer when a connection is closed).
+    pub fn simulate_disconnect(&mut self, client_id: ClientId) -> Result<(), ConnectionError> {
+        self.client_server_map
+            .get(&client_id)
+            .map_or(Err(ConnectionError::ConnectionNotFound), |&server_idx| {
+                self.servers[server_idx].simulate_disconnect(client_id)
+            })
+    }
+
     /// Returns true if the server is currently listening for client packets
     pub(crate) fn is_listening(&self) -> bool {
         self.is_listening
@@ -242,6 +345,8 @@ pub enum ConnectionError {
     ConnectionNotFound,
     #[error("the connection type for this client is invalid")]
     InvalidConnectionType,
+    #[error("this operation is not supported by the connection")]
+    NotSupported,
     #[error(transparent)]
     Transport(#[from] crate::transport::error::Error),
     #[error("netcode error: {0}")]
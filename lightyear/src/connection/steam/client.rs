@@ -8,6 +8,7 @@ use parking_lot::RwLock;
 use std::collections::VecDeque;
 use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
 use std::sync::Arc;
+use std::time::Duration;
 use steamworks::networking_sockets::{InvalidHandle, NetConnection};
 use steamworks::networking_types::{
     NetConnectionEnd, NetConnectionInfo, NetworkingConnectionState, NetworkingIdentity, SendFlags,
@@ -34,6 +35,40 @@ impl Default for SteamConfig {
     }
 }
 
+impl SteamConfig {
+    /// Connect directly to the Steam user `host` that is hosting a server on the given `virtual_port`,
+    /// via the Steam relay network (see [`SocketConfig::P2P`]).
+    ///
+    /// The host must run a server with a P2P socket on the same virtual port.
+    ///
+    /// ```rust,ignore
+    /// use lightyear::prelude::client::*;
+    /// use steamworks::SteamId;
+    ///
+    /// // the SteamID of the host, for example the owner of the lobby that the client joined
+    /// let host = SteamId::from_raw(76561197960287930);
+    /// let net_config = NetConfig::Steam {
+    ///     steamworks_client: None,
+    ///     config: SteamConfig::p2p(host, 4000),
+    ///     conditioner: None,
+    /// };
+    /// let client_config = ClientConfig {
+    ///     net: net_config,
+    ///     ..default()
+    /// };
+    /// // then connect with `commands.connect_client()`
+    /// ```
+    pub fn p2p(host: SteamId, virtual_port: i32) -> Self {
+        Self {
+            socket_config: SocketConfig::P2P {
+                virtual_port,
+                steam_id: host.raw(),
+            },
+            ..Default::default()
+        }
+    }
+}
+
 /// Steam socket configuration for clients
 #[derive(Debug, Clone)]
 pub enum SocketConfig {
@@ -41,7 +76,14 @@ pub enum SocketConfig {
     Ip { server_addr: SocketAddr },
     /// Connect to another Steam user hosting a server. Suitable for
     /// peer-to-peer games.
-    P2P { virtual_port: i32, steam_id: u64 },
+    ///
+    /// The connection goes through the Steam relay network, whose access is initialized
+    /// when the client connects.
+    P2P {
+        virtual_port: i32,
+        /// SteamID of the host
+        steam_id: u64,
+    },
 }
 
 impl Default for SocketConfig {
@@ -121,17 +163,21 @@ impl NetClient for Client {
                 virtual_port,
                 steam_id,
             } => {
-                self.connection = Some(
-                    self.steamworks_client
-                        .try_read()
-                        .expect("could not get steamworks client")
-                        .get_client()
-                        .networking_sockets()
-                        .connect_p2p(
-                            NetworkingIdentity::new_steam_id(SteamId::from_raw(steam_id)),
-                            virtual_port,
-                            vec![],
-                        )?,
+                let client = self
+                    .steamworks_client
+                    .try_read()
+                    .expect("could not get steamworks client")
+                    .get_client();
+                // P2P connections go through the relay network, so make sure that we have access to it
+                client.networking_utils().init_relay_network_access();
+                self.connection = Some(client.networking_sockets().connect_p2p(
+                    NetworkingIdentity::new_steam_id(SteamId::from_raw(steam_id)),
+                    virtual_port,
+                    vec![],
+                )?);
+                info!(
+                    "Opened steam P2P connection to host {} on virtual port {}",
+                    steam_id, virtual_port
                 );
             }
         }
@@ -212,6 +258,21 @@ impl NetClient for Client {
         Ok(())
     }
 
+    /// Packets that contain messages from a reliable channel are sent with Steam's reliable send flag
+    /// (without Nagle's algorithm, to avoid adding latency), so that they are not dropped by the
+    /// Steam networking layer
+    fn send_reliable(&mut self, buf: &[u8]) -> Result<(), ConnectionError> {
+        self.connection
+            .as_ref()
+            .ok_or(ConnectionError::NotConnected)?
+            .send_message(buf, SendFlags::RELIABLE_NO_NAGLE)?;
+        Ok(())
+    }
+
+    fn supports_reliable_send(&self) -> bool {
+        true
+    }
+
     fn id(&self) -> ClientId {
         ClientId::Steam(
             self.steamworks_client
@@ -224,6 +285,21 @@ impl NetClient for Client {
         )
     }
 
+    /// The ping of the connection measured by Steam
+    fn rtt(&self) -> Option<Duration> {
+        let connection = self.connection.as_ref()?;
+        let (info, _) = self
+            .steamworks_client
+            .try_read()
+            .expect("could not get steamworks client")
+            .get_client()
+            .networking_sockets()
+            .get_realtime_connection_status(connection, 0)
+            .ok()?;
+        // the ping is negative if it is not known yet
+        u64::try_from(info.ping()).ok().map(Duration::from_millis)
+    }
+
     fn local_addr(&self) -> SocketAddr {
         LOCAL_SOCKET
     }
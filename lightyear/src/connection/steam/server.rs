@@ -6,11 +6,12 @@ use crate::connection::server::{
 use crate::packet::packet_builder::RecvPayload;
 use crate::prelude::LinkConditionerConfig;
 use crate::server::io::Io;
-use bevy::utils::HashMap;
+use bevy::utils::{HashMap, HashSet};
 use parking_lot::RwLock;
 use std::collections::VecDeque;
 use std::net::{Ipv4Addr, SocketAddr};
 use std::sync::Arc;
+use std::time::Duration;
 use steamworks::networking_sockets::{ListenSocket, NetConnection};
 use steamworks::networking_types::{ListenSocketEvent, NetConnectionEnd, SendFlags};
 use steamworks::{ClientManager, ServerMode, SteamError};
@@ -23,6 +24,9 @@ pub struct SteamConfig {
     pub app_id: u32,
     pub socket_config: SocketConfig,
     pub max_clients: usize,
+    /// Maximum number of connections that can be accepted but not yet established at the same time.
+    /// Connection attempts beyond this limit are rejected.
+    pub max_pending_connections: usize,
     /// A closure that will be used to accept or reject incoming connections
     pub connection_request_handler: Arc<dyn ConnectionRequestHandler>,
     // pub mode: ServerMode,
@@ -37,6 +41,7 @@ impl Default for SteamConfig {
             app_id: 480,
             socket_config: Default::default(),
             max_clients: 16,
+            max_pending_connections: 16,
             connection_request_handler: Arc::new(DefaultConnectionRequestHandler),
             // mode: ServerMode::NoAuthentication,
             version: "1.0".to_string(),
@@ -74,6 +79,8 @@ pub struct Server {
     config: SteamConfig,
     listen_socket: Option<ListenSocket<ClientManager>>,
     connections: HashMap<ClientId, NetConnection<ClientManager>>,
+    /// Connections that were accepted but are not established yet
+    pending_connections: HashSet<ClientId>,
     packet_queue: VecDeque<(RecvPayload, ClientId)>,
     new_connections: Vec<ClientId>,
     new_disconnections: Vec<ClientId>,
@@ -110,6 +117,7 @@ impl Server {
             config,
             listen_socket: None,
             connections: HashMap::new(),
+            pending_connections: HashSet::new(),
             packet_queue: VecDeque::new(),
             new_connections: Vec::new(),
             new_disconnections: Vec::new(),
@@ -141,11 +149,15 @@ impl NetServer for Server {
                 info!("Steam socket started on {:?}", server_addr);
             }
             SocketConfig::P2P { virtual_port } => {
+                let client = self
+                    .steamworks_client
+                    .try_read()
+                    .expect("could not get steamworks client")
+                    .get_client();
+                // the clients connect via the relay network, so make sure that we have access to it
+                client.networking_utils().init_relay_network_access();
                 self.listen_socket = Some(
-                    self.steamworks_client
-                        .try_read()
-                        .expect("could not get steamworks client")
-                        .get_client()
+                    client
                         .networking_sockets()
                         .create_listen_socket_p2p(virtual_port, vec![])?,
                 );
@@ -181,6 +193,40 @@ impl NetServer for Server {
         }
     }
 
+    /// Close the connection with the `reason` as the debug string of the connection end.
+    ///
+    /// Steam only reports the end reason to the client (as a [`DisconnectReason::Steam`](crate::connection::client::DisconnectReason::Steam)),
+    /// the debug string is only visible in the Steam logs and the connection info.
+    fn kick(&mut self, client_id: ClientId, reason: String) -> Result<(), ConnectionError> {
+        match client_id {
+            ClientId::Steam(_) => {
+                if let Some(connection) = self.connections.remove(&client_id) {
+                    // the debug string cannot contain nul bytes
+                    let reason = reason.replace('\0', "");
+                    let _ = connection.close(NetConnectionEnd::AppGeneric, Some(&reason), true);
+                    self.new_disconnections.push(client_id);
+                }
+                Ok(())
+            }
+            _ => Err(ConnectionError::InvalidConnectionType),
+        }
+    }
+
+    /// The ping of the connection measured by Steam
+    fn rtt(&self, client_id: ClientId) -> Option<Duration> {
+        let connection = self.connections.get(&client_id)?;
+        let (info, _) = self
+            .steamworks_client
+            .try_read()
+            .expect("could not get steamworks client")
+            .get_client()
+            .networking_sockets()
+            .get_realtime_connection_status(connection, 0)
+            .ok()?;
+        // the ping is negative if it is not known yet
+        u64::try_from(info.ping()).ok().map(Duration::from_millis)
+    }
+
     fn connected_client_ids(&self) -> Vec<ClientId> {
         self.connections.keys().cloned().collect()
     }
@@ -206,6 +252,7 @@ impl NetServer for Server {
                     if let Some(steam_id) = event.remote().steam_id() {
                         let client_id = ClientId::Steam(steam_id.raw());
                         info!("Client with id: {:?} connected!", client_id);
+                        self.pending_connections.remove(&client_id);
                         self.new_connections.push(client_id);
                         self.connections.insert(client_id, event.take_connection());
                     } else {
@@ -220,6 +267,7 @@ impl NetServer for Server {
                             client_id,
                             event.end_reason()
                         );
+                        self.pending_connections.remove(&client_id);
                         if let Some(connection) = self.connections.remove(&client_id) {
                             let _ = connection.close(NetConnectionEnd::AppGeneric, None, true);
                             self.new_disconnections.push(client_id);
@@ -233,6 +281,13 @@ impl NetServer for Server {
                         event.reject(NetConnectionEnd::AppGeneric, Some("Too many clients"));
                         continue;
                     }
+                    if self.pending_connections.len() >= self.config.max_pending_connections {
+                        event.reject(
+                            NetConnectionEnd::AppGeneric,
+                            Some("Too many pending connections"),
+                        );
+                        continue;
+                    }
                     let Some(steam_id) = event.remote().steam_id() else {
                         event.reject(NetConnectionEnd::AppGeneric, Some("Invalid steam id"));
                         continue;
@@ -243,12 +298,18 @@ impl NetServer for Server {
                         .connection_request_handler
                         .handle_request(ClientId::Steam(steam_id.raw()))
                     {
-                        event.reject(NetConnectionEnd::AppGeneric, Some("{denied_reason:?}"));
+                        event.reject(
+                            NetConnectionEnd::AppGeneric,
+                            Some(format!("{denied_reason:?}").as_str()),
+                        );
                         continue;
                     } else {
                         if let Err(e) = event.accept() {
                             error!("Failed to accept connection from {steam_id:?}: {e}");
+                            continue;
                         }
+                        self.pending_connections
+                            .insert(ClientId::Steam(steam_id.raw()));
                         info!("Accepted connection from client {:?}", steam_id);
                     }
                 }
@@ -288,6 +349,21 @@ impl NetServer for Server {
         Ok(())
     }
 
+    /// Packets that contain messages from a reliable channel are sent with Steam's reliable send flag
+    /// (without Nagle's algorithm, to avoid adding latency), so that they are not dropped by the
+    /// Steam networking layer
+    fn send_reliable(&mut self, buf: &[u8], client_id: ClientId) -> Result<(), ConnectionError> {
+        let Some(connection) = self.connections.get_mut(&client_id) else {
+            return Err(ConnectionError::ConnectionNotFound);
+        };
+        connection.send_message(buf, SendFlags::RELIABLE_NO_NAGLE)?;
+        Ok(())
+    }
+
+    fn supports_reliable_send(&self) -> bool {
+        true
+    }
+
     fn new_connections(&self) -> Vec<ClientId> {
         self.new_connections.clone()
     }
@@ -36,12 +36,50 @@ pub enum ActionDiff<A> {
         /// The new value of the axis
         axis_pair: Vec2,
     },
+    /// The value of the action changed, quantized to 8 bits (see [`quantize_axis`])
+    QuantizedAxisChanged {
+        /// The value of the action
+        action: A,
+        /// The new quantized value of the action
+        value: i8,
+    },
+    /// The axis pair of the action changed, quantized to 8 bits per axis (see [`quantize_axis`])
+    QuantizedAxisPairChanged {
+        /// The value of the action
+        action: A,
+        /// The new quantized value of the axis
+        axis_pair: [i8; 2],
+    },
+}
+
+/// Quantize an axis value in the range [-1.0, 1.0] to 8 bits.
+///
+/// Values outside of the range are clamped. The value is restored by [`dequantize_axis`] with a
+/// precision of `1.0 / 254.0` (half of a quantization step of `1.0 / 127.0`); changes smaller than a
+/// quantization step are not networked, which acts as a small dead-zone.
+pub(crate) fn quantize_axis(value: f32) -> i8 {
+    (value.clamp(-1.0, 1.0) * i8::MAX as f32).round() as i8
+}
+
+/// Restore an axis value that was quantized with [`quantize_axis`]
+pub(crate) fn dequantize_axis(value: i8) -> f32 {
+    (value as f32 / i8::MAX as f32).max(-1.0)
+}
+
+fn quantize_axis_pair(axis_pair: Vec2) -> [i8; 2] {
+    [quantize_axis(axis_pair.x), quantize_axis(axis_pair.y)]
 }
 
 impl<A: LeafwingUserAction> ActionDiff<A> {
     /// Creates a list of `ActionDiff` from the difference between two `ActionState`
     /// Used to have a smaller serialized size when sending inputs over the network
-    pub(crate) fn create(before: &ActionState<A>, after: &ActionState<A>) -> Vec<Self> {
+    ///
+    /// If `quantize` is true, the axis values are quantized to 8 bits (see [`quantize_axis`])
+    pub(crate) fn create(
+        before: &ActionState<A>,
+        after: &ActionState<A>,
+        quantize: bool,
+    ) -> Vec<Self> {
         let mut diffs = vec![];
         for (action, action_data_after) in after.all_action_data() {
             // no need to network disabled actions. Or should we network the default value?
@@ -74,7 +112,15 @@ impl<A: LeafwingUserAction> ActionDiff<A> {
                             ActionKindData::Axis(axis_data_before) => axis_data_before,
                             _ => unreachable!(),
                         };
-                        if axis_data_after.value != axis_data_before.value {
+                        if quantize {
+                            let value = quantize_axis(axis_data_after.value);
+                            if value != quantize_axis(axis_data_before.value) {
+                                diffs.push(ActionDiff::QuantizedAxisChanged {
+                                    action: action.clone(),
+                                    value,
+                                });
+                            }
+                        } else if axis_data_after.value != axis_data_before.value {
                             diffs.push(ActionDiff::AxisChanged {
                                 action: action.clone(),
                                 value: axis_data_after.value,
@@ -86,7 +132,15 @@ impl<A: LeafwingUserAction> ActionDiff<A> {
                             ActionKindData::DualAxis(dual_axis_before) => dual_axis_before,
                             _ => unreachable!(),
                         };
-                        if dual_axis_after.pair != dual_axis_before.pair {
+                        if quantize {
+                            let axis_pair = quantize_axis_pair(dual_axis_after.pair);
+                            if axis_pair != quantize_axis_pair(dual_axis_before.pair) {
+                                diffs.push(ActionDiff::QuantizedAxisPairChanged {
+                                    action: action.clone(),
+                                    axis_pair,
+                                });
+                            }
+                        } else if dual_axis_after.pair != dual_axis_before.pair {
                             diffs.push(ActionDiff::AxisPairChanged {
                                 action: action.clone(),
                                 axis_pair: dual_axis_after.pair,
@@ -108,16 +162,30 @@ impl<A: LeafwingUserAction> ActionDiff<A> {
                         }
                     }
                     ActionKindData::Axis(axis) => {
-                        diffs.push(ActionDiff::AxisChanged {
-                            action: action.clone(),
-                            value: axis.value,
-                        });
+                        if quantize {
+                            diffs.push(ActionDiff::QuantizedAxisChanged {
+                                action: action.clone(),
+                                value: quantize_axis(axis.value),
+                            });
+                        } else {
+                            diffs.push(ActionDiff::AxisChanged {
+                                action: action.clone(),
+                                value: axis.value,
+                            });
+                        }
                     }
                     ActionKindData::DualAxis(dual_axis) => {
-                        diffs.push(ActionDiff::AxisPairChanged {
-                            action: action.clone(),
-                            axis_pair: dual_axis.pair,
-                        });
+                        if quantize {
+                            diffs.push(ActionDiff::QuantizedAxisPairChanged {
+                                action: action.clone(),
+                                axis_pair: quantize_axis_pair(dual_axis.pair),
+                            });
+                        } else {
+                            diffs.push(ActionDiff::AxisPairChanged {
+                                action: action.clone(),
+                                axis_pair: dual_axis.pair,
+                            });
+                        }
                     }
                 }
             }
@@ -142,14 +210,23 @@ impl<A: LeafwingUserAction> ActionDiff<A> {
             ActionDiff::AxisPairChanged { action, axis_pair } => {
                 action_state.set_axis_pair(&action, axis_pair);
             }
+            ActionDiff::QuantizedAxisChanged { action, value } => {
+                action_state.axis_data_mut_or_default(&action).value = dequantize_axis(value);
+            }
+            ActionDiff::QuantizedAxisPairChanged {
+                action,
+                axis_pair: [x, y],
+            } => {
+                action_state
+                    .set_axis_pair(&action, Vec2::new(dequantize_axis(x), dequantize_axis(y)));
+            }
         };
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::prelude::{Deserialize, Serialize};
-    use bevy::prelude::Reflect;
+    use super::*;
     use leafwing_input_manager::Actionlike;
 
     #[derive(
@@ -157,6 +234,47 @@ mod tests {
     )]
     enum Action {
         Jump,
+        #[actionlike(DualAxis)]
+        Move,
+    }
+
+    /// Check that the quantized axis diffs restore the axis pair within the quantization precision,
+    /// and are smaller when serialized
+    #[test]
+    fn test_quantized_axis_pair_diff() {
+        let before = ActionState::<Action>::default();
+        let mut after = before.clone();
+        let axis_pair = Vec2::new(0.3, -0.77);
+        after.set_axis_pair(&Action::Move, axis_pair);
+
+        let diffs = ActionDiff::create(&before, &after, false);
+        let quantized_diffs = ActionDiff::create(&before, &after, true);
+        assert_eq!(
+            quantized_diffs,
+            vec![ActionDiff::QuantizedAxisPairChanged {
+                action: Action::Move,
+                axis_pair: [quantize_axis(0.3), quantize_axis(-0.77)],
+            }]
+        );
+
+        let mut action_state = before.clone();
+        for diff in quantized_diffs.clone() {
+            diff.apply(&mut action_state);
+        }
+        let restored = action_state.axis_pair(&Action::Move);
+        assert!((restored - axis_pair).abs().max_element() <= 1.0 / 254.0);
+
+        let config = bincode::config::standard();
+        let size = bincode::serde::encode_to_vec(&diffs, config).unwrap().len();
+        let quantized_size = bincode::serde::encode_to_vec(&quantized_diffs, config)
+            .unwrap()
+            .len();
+        assert!(quantized_size < size);
+
+        // changes smaller than the quantization step are not sent
+        let mut small_change = after.clone();
+        small_change.set_axis_pair(&Action::Move, axis_pair + Vec2::splat(0.001));
+        assert!(ActionDiff::create(&after, &small_change, true).is_empty());
     }
 
     // fn test_diff() {
@@ -165,7 +283,7 @@ mod tests {
     //     action_state.action_data_mut(&Action::Jump).unwrap().value = 0.5;
     //     let mut action_state2 = action_state.clone();
     //     action_state2.action_data_mut(&Action::Jump).unwrap().value = 0.75;
-    //     let diff = ActionDiff::create(&action_state, &action_state2);
+    //     let diff = ActionDiff::create(&action_state, &action_state2, false);
     //     assert_eq!(diff.len(), 1);
     //     let mut action_state3 = action_state.clone();
     //     diff[0].apply(&mut action_state3);
@@ -262,6 +262,92 @@ impl<T: LeafwingUserAction> InputBuffer<T> {
     }
 }
 
+/// Reads the ticks of an [`InputBuffer`] that haven't been read yet.
+///
+/// This lets you react to inputs in the `Update` schedule instead of `FixedUpdate`, while still knowing
+/// the tick that each input is associated with. The reader remembers the last tick that it read, so each
+/// tick is returned exactly once, even if there are multiple frames per tick or multiple ticks per frame.
+///
+/// The reader also remembers the last [`ActionState`] that it read, so that it can detect presses and releases
+/// even if the previous tick was already removed from the buffer. The first tick that is read is only used
+/// as a baseline: a key that is already held when the reader starts (for example on the first tick after
+/// the client is synced) is not reported as just pressed.
+///
+/// You can add it as a component on the entity that holds the [`InputBuffer`], or keep it in a `Local`.
+#[derive(Component, Debug)]
+pub struct InputBufferReader<A: LeafwingUserAction> {
+    last_read: Option<(Tick, ActionState<A>)>,
+}
+
+impl<A: LeafwingUserAction> Default for InputBufferReader<A> {
+    fn default() -> Self {
+        Self { last_read: None }
+    }
+}
+
+/// The [`ActionState`] of a tick that was returned by an [`InputBufferReader`]
+#[derive(Debug)]
+pub struct BufferedInput<'a, A: LeafwingUserAction> {
+    /// The tick that the input is associated with
+    pub tick: Tick,
+    /// The [`ActionState`] at this tick
+    pub action_state: &'a ActionState<A>,
+    /// The [`ActionState`] at the previous tick, or the last [`ActionState`] read by the reader
+    /// if the previous tick is not in the buffer anymore
+    pub previous: Option<ActionState<A>>,
+}
+
+impl<A: LeafwingUserAction> BufferedInput<'_, A> {
+    /// Returns true if the action is pressed at this tick, but wasn't pressed at the previous tick.
+    ///
+    /// Contrary to [`ActionState::just_pressed`], this only depends on the ticks and not on the frames.
+    /// Returns false if there is no previous input to compare with.
+    pub fn just_pressed(&self, action: &A) -> bool {
+        self.previous
+            .as_ref()
+            .is_some_and(|previous| self.action_state.pressed(action) && !previous.pressed(action))
+    }
+
+    /// Returns true if the action is not pressed at this tick, but was pressed at the previous tick.
+    ///
+    /// Returns false if there is no previous input to compare with.
+    pub fn just_released(&self, action: &A) -> bool {
+        self.previous
+            .as_ref()
+            .is_some_and(|previous| !self.action_state.pressed(action) && previous.pressed(action))
+    }
+}
+
+impl<A: LeafwingUserAction> InputBufferReader<A> {
+    /// Return the inputs of the ticks that were added to the buffer since the last read, oldest first
+    pub fn read<'a>(&mut self, buffer: &'a InputBuffer<A>) -> Vec<BufferedInput<'a, A>> {
+        let (Some(start_tick), Some(end_tick)) = (buffer.start_tick, buffer.end_tick()) else {
+            return vec![];
+        };
+        let first_tick = match &self.last_read {
+            Some((last_read_tick, _)) if *last_read_tick >= end_tick => return vec![],
+            Some((last_read_tick, _)) if *last_read_tick >= start_tick => *last_read_tick + 1,
+            _ => start_tick,
+        };
+        let mut previous = self.last_read.take().map(|(_, action_state)| action_state);
+        let inputs: Vec<_> = (0..=(end_tick - first_tick))
+            .filter_map(|i| {
+                let tick = first_tick + i;
+                let action_state = buffer.get(tick)?;
+                let input = BufferedInput {
+                    tick,
+                    action_state,
+                    previous: buffer.get(tick - 1).cloned().or_else(|| previous.take()),
+                };
+                previous = Some(action_state.clone());
+                Some(input)
+            })
+            .collect();
+        self.last_read = previous.map(|action_state| (end_tick, action_state));
+        inputs
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use bevy::prelude::Reflect;
@@ -310,4 +396,24 @@ mod tests {
         assert_eq!(input_buffer.start_tick, Some(Tick(8)));
         assert_eq!(input_buffer.buffer.len(), 0);
     }
+
+    /// Every input message starts with the absolute `ActionState` of its first tick, so if the message
+    /// that contained the `Pressed` diff is lost, the next message still restores the pressed state
+    #[test]
+    fn test_update_from_message_recovers_lost_press() {
+        let mut input_buffer = InputBuffer::<Action>::default();
+        let mut pressed = ActionState::default();
+        pressed.press(&Action::Jump);
+
+        // the message for ticks 6..=10, which contained the press at tick 8, was lost.
+        // The next message starts at tick 10, when the key is still held
+        input_buffer.update_from_message(Tick(14), &pressed, &vec![vec![]; 4]);
+
+        assert_eq!(input_buffer.start_tick, Some(Tick(10)));
+        for tick in 10..=14 {
+            assert!(input_buffer
+                .get(Tick(tick))
+                .is_some_and(|action_state| action_state.pressed(&Action::Jump)));
+        }
+    }
 }
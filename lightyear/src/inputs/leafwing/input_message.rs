@@ -27,11 +27,12 @@ impl<A: LeafwingUserAction> std::fmt::Display for InputMessage<A> {
         if self.diffs.is_empty() {
             return write!(f, "EmptyInputMessage");
         }
-        let start_tick = self.end_tick - Tick(self.diffs[0].2.len() as u16);
         let buffer_str = self
             .diffs
             .iter()
             .map(|(entity, start_value, diffs_per_entity)| {
+                // each target can have a different start tick
+                let start_tick = self.end_tick - Tick(diffs_per_entity.len() as u16);
                 let mut str = format!("Entity: {:?}\n", entity);
                 let _ = writeln!(
                     &mut str,
@@ -92,11 +93,14 @@ impl<A: LeafwingUserAction> InputMessage<A> {
     ///
     /// If we don't have a starting `ActionState` from the `input_buffer`, we start from the first tick for which
     /// we have an `ActionState`.
+    ///
+    /// If `quantize_axis` is true, the axis values of the diffs are quantized to 8 bits.
     pub(crate) fn add_inputs(
         &mut self,
         num_ticks: u16,
         input_target: InputTarget,
         input_buffer: &InputBuffer<A>,
+        quantize_axis: bool,
     ) {
         let mut inputs = Vec::new();
         // find the first tick for which we have an `ActionState` buffered
@@ -124,6 +128,7 @@ impl<A: LeafwingUserAction> InputMessage<A> {
                 input_buffer
                     .get(tick)
                     .unwrap_or(&ActionState::<A>::default()),
+                quantize_axis,
             );
             inputs.push(diffs);
             tick += 1;
@@ -155,7 +160,7 @@ mod tests {
     fn test_generate_input_message_no_start_input() {
         let input_buffer = InputBuffer::default();
         let mut input_message = InputMessage::<Action>::new(Tick(10));
-        input_message.add_inputs(5, InputTarget::Global, &input_buffer);
+        input_message.add_inputs(5, InputTarget::Global, &input_buffer, false);
         assert_eq!(
             input_message,
             InputMessage {
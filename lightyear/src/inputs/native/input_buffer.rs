@@ -1,9 +1,11 @@
 use std::collections::VecDeque;
 use std::fmt::Debug;
+use std::marker::PhantomData;
 
 use bevy::prelude::{Reflect, Resource};
 use serde::{Deserialize, Serialize};
 
+use crate::prelude::ClientId;
 use crate::shared::tick_manager::Tick;
 
 use super::UserAction;
@@ -33,7 +35,45 @@ pub struct InputMessage<T> {
     pub(crate) inputs: Vec<InputData<T>>,
 }
 
+/// Message sent by the server to ask the client to re-send its inputs for a range of ticks,
+/// when the server detects a gap in the input messages it received (for example because of packet loss)
+///
+/// This is only used by the native input plugin. The leafwing input messages start with the full
+/// `ActionState` of their first tick, so the server catches up with the next message it receives,
+/// and requesting the missing ticks is out of scope there.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct InputNack<T> {
+    pub(crate) start_tick: Tick,
+    pub(crate) end_tick: Tick,
+    #[serde(skip)]
+    _marker: PhantomData<T>,
+}
+
+impl<T> InputNack<T> {
+    pub(crate) fn new(start_tick: Tick, end_tick: Tick) -> Self {
+        Self {
+            start_tick,
+            end_tick,
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// Input message of a client, forwarded by the server to the other clients so that they can predict
+/// the entities controlled by that client
+/// (see [`ServerInputConfig::rebroadcast_inputs`](crate::prelude::server::ServerInputConfig::rebroadcast_inputs))
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct RemoteInputMessage<T> {
+    pub(crate) client_id: ClientId,
+    pub(crate) message: InputMessage<T>,
+}
+
 impl<T: UserAction> InputMessage<T> {
+    /// First tick included in the message
+    pub(crate) fn start_tick(&self) -> Tick {
+        self.end_tick - (self.inputs.len() as u16).saturating_sub(1)
+    }
+
     pub fn is_empty(&self) -> bool {
         if self.inputs.len() == 0 {
             return true;
@@ -103,6 +143,32 @@ impl<T: UserAction> InputBuffer<T> {
             .as_ref()
     }
 
+    /// Last tick for which the buffer contains a value (which can be an absent input)
+    pub(crate) fn end_tick(&self) -> Option<Tick> {
+        let start_tick = self.start_tick?;
+        if self.buffer.is_empty() {
+            return None;
+        }
+        Some(start_tick + (self.buffer.len() as i16 - 1))
+    }
+
+    /// Get the input for the given tick.
+    ///
+    /// If the tick is after the last tick of the buffer (the input was not received yet), assume
+    /// that the input stayed unchanged for up to `max_ticks` ticks and return the input of the last tick.
+    /// Ticks inside the buffer are never extrapolated: an absent input means that no input was pressed
+    /// (for example because a key was released).
+    pub(crate) fn get_extrapolated(&self, tick: Tick, max_ticks: u16) -> Option<&T> {
+        let end_tick = self.end_tick()?;
+        if tick <= end_tick {
+            return self.get(tick);
+        }
+        if (tick - end_tick) as i32 > max_ticks as i32 {
+            return None;
+        }
+        self.get(end_tick)
+    }
+
     pub(crate) fn set(&mut self, tick: Tick, value: Option<T>) {
         let Some(start_tick) = self.start_tick else {
             // initialize the buffer
@@ -209,6 +275,26 @@ mod tests {
         assert_eq!(input_buffer.buffer.len(), 0);
     }
 
+    #[test]
+    fn test_get_extrapolated() {
+        let mut input_buffer = InputBuffer::default();
+        assert_eq!(input_buffer.get_extrapolated(Tick(4), 2), None);
+
+        input_buffer.set(Tick(4), Some(0));
+        input_buffer.set(Tick(6), Some(1));
+
+        // ticks inside the buffer are not extrapolated
+        assert_eq!(input_buffer.get_extrapolated(Tick(5), 2), None);
+        // ticks after the end of the buffer use the last input, up to max_ticks
+        assert_eq!(input_buffer.get_extrapolated(Tick(7), 2), Some(&1));
+        assert_eq!(input_buffer.get_extrapolated(Tick(8), 2), Some(&1));
+        assert_eq!(input_buffer.get_extrapolated(Tick(9), 2), None);
+
+        // the input was released: it is not extrapolated
+        input_buffer.set(Tick(7), None);
+        assert_eq!(input_buffer.get_extrapolated(Tick(8), 2), None);
+    }
+
     #[test]
     fn test_create_message() {
         let mut input_buffer = InputBuffer::default();
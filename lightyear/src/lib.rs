@@ -186,8 +186,9 @@ pub mod prelude {
 
     pub use crate::channel::builder::{
         Channel, ChannelBuilder, ChannelContainer, ChannelDirection, ChannelMode, ChannelSettings,
-        InputChannel, ReliableSettings,
+        DefaultOrderedReliableChannel, InputChannel, ReliableSettings, WrongDirectionPolicy,
     };
+    pub use crate::channel::stats::ChannelStats;
     pub use crate::client::prediction::prespawn::PreSpawnedPlayerObject;
     pub use crate::connection::id::ClientId;
     pub use crate::connection::netcode::{generate_key, ConnectToken, Key};
@@ -196,25 +197,42 @@ pub mod prelude {
     pub use crate::inputs::native::UserAction;
     pub use crate::packet::error::PacketError;
     pub use crate::packet::message::Message;
-    pub use crate::protocol::channel::{AppChannelExt, ChannelKind, ChannelRegistry};
-    pub use crate::protocol::component::{AppComponentExt, ComponentRegistry, Linear};
-    pub use crate::protocol::message::{AppMessageExt, MessageRegistry};
+    pub use crate::protocol::channel::{AppChannelExt, ChannelError, ChannelKind, ChannelRegistry};
+    pub use crate::protocol::component::{
+        AppComponentExt, ComponentRegistry, DeadReckoning, ExtrapolateFn, Linear,
+    };
+    pub use crate::protocol::message::{
+        AppMessageExt, MessageRegistry, UnknownMessage, UnknownMessageFn,
+    };
     pub use crate::protocol::serialize::AppSerializeExt;
+    pub use crate::protocol::size::{ProtocolTypeCategory, SerializedSize, SerializedSizeReport};
+    pub use crate::shared::chat::{
+        ChatChannel, ChatCommands, ChatMessage, ChatMessageChannel, ChatModerationFn, ChatPlugin,
+    };
     pub use crate::shared::config::{Mode, SharedConfig};
     #[cfg(feature = "leafwing")]
     pub use crate::shared::input::leafwing::LeafwingInputPlugin;
     pub use crate::shared::input::native::InputPlugin;
+    pub use crate::shared::input::{InputDelayCommand, ViewDelay};
+    pub use crate::shared::notification::{ServerNotification, Severity};
     pub use crate::shared::ping::manager::PingConfig;
     pub use crate::shared::plugin::{NetworkIdentity, SharedPlugin};
     pub use crate::shared::replication::authority::HasAuthority;
     pub use crate::shared::replication::components::{
-        DeltaCompression, DisabledComponent, NetworkRelevanceMode, OverrideTargetComponent,
-        PrePredicted, ReplicateHierarchy, ReplicateOnceComponent, Replicated, Replicating,
-        ReplicationGroup, ReplicationTarget, ShouldBePredicted, TargetEntity,
+        AtomicHierarchy, DeltaCompression, DisabledComponent, NetworkRelevanceMode,
+        OverrideTargetComponent, PrePredicted, ReplicateHierarchy, ReplicateOnceComponent,
+        Replicated, Replicating, ReplicationGroup, ReplicationGroupKey, ReplicationTarget,
+        ShouldBePredicted, TargetEntity,
     };
+    pub use crate::shared::replication::delta::DeltaCompressionStats;
     pub use crate::shared::replication::entity_map::RemoteEntityMap;
-    pub use crate::shared::replication::hierarchy::ParentSync;
+    pub use crate::shared::replication::error::{ReplicationErrors, ReplicationSkipReason};
+    pub use crate::shared::replication::hierarchy::{
+        LocalTransformAdapter, LocalTransformPlugin, ParentSync,
+    };
     pub use crate::shared::replication::network_target::NetworkTarget;
+    pub use crate::shared::replication::plugin::DuplicateSpawnPolicy;
+    pub use crate::shared::replication::plugin::EarlyUpdatesPolicy;
     pub use crate::shared::replication::plugin::ReplicationConfig;
     pub use crate::shared::replication::plugin::SendUpdatesMode;
     pub use crate::shared::replication::resources::{
@@ -222,11 +240,18 @@ pub mod prelude {
     };
     pub use crate::shared::run_conditions::*;
     pub use crate::shared::sets::{FixedUpdateSet, MainSet};
+    pub use crate::shared::simulation_lag::{SimulationLagPlugin, SimulationLagging};
+    pub use crate::shared::stream::{
+        StreamChannel, StreamFailed, StreamFailureReason, StreamId, StreamPlugin, StreamProgress,
+        StreamSender, StreamSinkFn,
+    };
     pub use crate::shared::tick_manager::TickManager;
-    pub use crate::shared::tick_manager::{Tick, TickConfig};
+    pub use crate::shared::tick_manager::{AppTickStepExt, Tick, TickConfig, WideTick};
     pub use crate::shared::time_manager::TimeManager;
     pub use crate::transport::middleware::compression::CompressionConfig;
     pub use crate::transport::middleware::conditioner::LinkConditionerConfig;
+    pub use crate::transport::middleware::threaded::ThreadedIoConfig;
+    pub use crate::transport::middleware::transform::{PacketTransform, PacketTransformConfig};
 
     mod rename {
         pub use crate::client::events::ComponentInsertEvent as ClientComponentInsertEvent;
@@ -236,6 +261,7 @@ pub mod prelude {
         pub use crate::client::events::DisconnectEvent as ClientDisconnectEvent;
         pub use crate::client::events::EntityDespawnEvent as ClientEntityDespawnEvent;
         pub use crate::client::events::EntitySpawnEvent as ClientEntitySpawnEvent;
+        pub use crate::client::events::GroupAckEvent as ClientGroupAckEvent;
         pub use crate::client::events::MessageEvent as ClientMessageEvent;
 
         pub use crate::client::connection::ConnectionManager as ClientConnectionManager;
@@ -247,6 +273,7 @@ pub mod prelude {
         pub use crate::server::events::DisconnectEvent as ServerDisconnectEvent;
         pub use crate::server::events::EntityDespawnEvent as ServerEntityDespawnEvent;
         pub use crate::server::events::EntitySpawnEvent as ServerEntitySpawnEvent;
+        pub use crate::server::events::GroupAckEvent as ServerGroupAckEvent;
         pub use crate::server::events::MessageEvent as ServerMessageEvent;
 
         pub use crate::server::connection::ConnectionManager as ServerConnectionManager;
@@ -259,20 +286,25 @@ pub mod prelude {
         };
         pub use crate::client::config::{ClientConfig, NetcodeConfig, PacketConfig};
         pub use crate::client::connection::ConnectionManager;
+        pub use crate::client::diagnostics::{NetworkDebugState, ReplicationGroupDebugState};
         pub use crate::client::error::ClientError;
         pub use crate::client::events::{
-            ComponentInsertEvent, ComponentRemoveEvent, ComponentUpdateEvent, ConnectEvent,
-            DisconnectEvent, EntityDespawnEvent, EntitySpawnEvent, InputEvent, MessageEvent,
+            ComponentInsertEvent, ComponentRemoveEvent, ComponentStateTransition,
+            ComponentUpdateEvent, ConnectEvent, DisconnectEvent, EntityDespawnEvent,
+            EntitySpawnEvent, GroupAckEvent, InputEvent, MessageEvent, RemoteInputEvent, SyncEvent,
         };
         #[cfg(feature = "leafwing")]
         pub use crate::client::input::leafwing::LeafwingInputConfig;
-        pub use crate::client::input::native::{InputConfig, InputManager};
+        pub use crate::client::input::native::{
+            AppInputStepExt, InputConfig, InputManager, RemoteInputs,
+        };
         pub use crate::client::interpolation::interpolation_history::ConfirmedHistory;
         pub use crate::client::interpolation::plugin::{
             InterpolationConfig, InterpolationDelay, InterpolationSet,
         };
         pub use crate::client::interpolation::{
-            InterpolateStatus, Interpolated, VisualInterpolateStatus, VisualInterpolationPlugin,
+            EntityInterpolated, InterpolateStatus, Interpolated, InterpolatedDespawnTimer,
+            InterpolatedRemoveTimer, VisualInterpolateStatus, VisualInterpolationPlugin,
         };
         pub use crate::client::io::config::ClientTransport;
         pub use crate::client::io::Io;
@@ -282,17 +314,24 @@ pub mod prelude {
         pub use crate::client::prediction::despawn::PredictionDespawnCommandsExt;
         pub use crate::client::prediction::plugin::is_in_rollback;
         pub use crate::client::prediction::plugin::{PredictionConfig, PredictionSet};
-        pub use crate::client::prediction::rollback::{Rollback, RollbackState};
+        pub use crate::client::prediction::rollback::{
+            LargePredictionError, Rollback, RollbackState,
+        };
+        pub use crate::client::prediction::spawn::EntityPredicted;
         pub use crate::client::prediction::Predicted;
         pub use crate::client::replication::commands::DespawnReplicationCommandExt;
         pub use crate::client::replication::send::Replicate;
-        pub use crate::client::run_conditions::{is_connected, is_disconnected, is_synced};
-        pub use crate::client::sync::SyncConfig;
+        pub use crate::client::run_conditions::{
+            is_connected, is_disconnected, is_synced, just_synced,
+        };
+        pub use crate::client::sync::{SyncConfig, SyncMode, SyncStatsSnapshot};
         pub use crate::connection::client::{
             Authentication, ClientConnection, IoConfig, NetClient, NetConfig,
         };
         #[cfg(all(feature = "steam", not(target_family = "wasm")))]
         pub use crate::connection::steam::client::{SocketConfig, SteamConfig};
+        #[cfg(feature = "leafwing")]
+        pub use crate::inputs::leafwing::input_buffer::{BufferedInput, InputBufferReader};
     }
     pub mod server {
         #[cfg(all(feature = "webtransport", not(target_family = "wasm")))]
@@ -306,24 +345,40 @@ pub mod prelude {
         pub use crate::server::clients::ControlledEntities;
         pub use crate::server::config::{NetcodeConfig, PacketConfig, ServerConfig};
         pub use crate::server::connection::ConnectionManager;
+        pub use crate::server::diagnostics::{
+            UnreplicatedComponents, UnreplicatedComponentsPlugin,
+        };
         pub use crate::server::error::ServerError;
         pub use crate::server::events::{
             ComponentInsertEvent, ComponentRemoveEvent, ComponentUpdateEvent, ConnectEvent,
-            DisconnectEvent, EntityDespawnEvent, EntitySpawnEvent, InputEvent, MessageEvent,
+            DisconnectEvent, EntityDespawnEvent, EntitySpawnEvent, GroupAckEvent, InputEvent,
+            MessageEvent,
         };
+        #[cfg(feature = "leafwing")]
+        pub use crate::server::input::leafwing::GlobalActions;
+        pub use crate::server::input::{InputTooFarAheadEvent, ServerInputConfig};
         pub use crate::server::io::config::ServerTransport;
         pub use crate::server::io::Io;
+        pub use crate::server::message::MessageRateLimitedEvent;
         pub use crate::server::networking::{NetworkingState, ServerCommands};
         pub use crate::server::plugin::ServerPlugins;
         pub use crate::server::relevance::immediate::RelevanceManager;
         pub use crate::server::relevance::room::{RoomId, RoomManager};
+        pub use crate::server::relevance::spatial::{
+            SpatialInterest, SpatialInterestConfig, SpatialInterestPlugin,
+        };
         pub use crate::server::replication::commands::AuthorityCommandExt;
+        pub use crate::server::replication::commands::ControlCommandExt;
         pub use crate::server::replication::commands::DespawnReplicationCommandExt;
         pub use crate::server::replication::{
-            send::{ControlledBy, Lifetime, Replicate, ServerFilter, SyncTarget},
+            send::{
+                ControlledBy, Lifetime, Replicate, ReplicationPaused, ReplicationWorldId,
+                ServerFilter, SyncTarget,
+            },
             ReplicationSet, ServerReplicationSet,
         };
         pub use crate::server::run_conditions::{is_started, is_stopped};
+        pub use crate::server::snapshot::WorldSnapshotExt;
         pub use crate::shared::replication::authority::AuthorityPeer;
     }
 
@@ -1,4 +1,4 @@
-use bevy::utils::HashMap;
+use bevy::utils::{Duration, HashMap};
 use byteorder::NetworkEndian;
 use byteorder::ReadBytesExt;
 use ringbuffer::{ConstGenericRingBuffer, RingBuffer};
@@ -30,22 +30,54 @@ pub(crate) struct PacketHeader {
     ack_bitfield: u32,
     /// Current tick
     pub(crate) tick: Tick,
+    /// Optional time elapsed since the start of the tick when the packet was sent, as a fraction of the tick duration.
+    ///
+    /// It is quantized to a single byte (see [`SubTickFraction`]).
+    pub(crate) sub_tick: Option<SubTickFraction>,
+}
+
+/// Flag set on the packet type byte when the header contains a [`SubTickFraction`]
+const SUB_TICK_FLAG: u8 = 0x80;
+
+/// Fraction of the tick duration that elapsed on the sender since the start of the tick (the overstep),
+/// quantized to a single byte.
+///
+/// Including it costs 1 extra byte per packet, and gives a precision of 1/255 of a tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct SubTickFraction(u8);
+
+impl SubTickFraction {
+    pub(crate) fn new(fraction: f32) -> Self {
+        Self((fraction.clamp(0.0, 1.0) * u8::MAX as f32).round() as u8)
+    }
+
+    pub(crate) fn fraction(&self) -> f32 {
+        self.0 as f32 / u8::MAX as f32
+    }
 }
 
 impl ToBytes for PacketHeader {
     fn len(&self) -> usize {
-        11
+        11 + self.sub_tick.map_or(0, |_| 1)
     }
 
     fn to_bytes<T: byteorder::WriteBytesExt>(
         &self,
         buffer: &mut T,
     ) -> Result<(), SerializationError> {
-        buffer.write_u8(self.packet_type as u8)?;
+        let flags = if self.sub_tick.is_some() {
+            SUB_TICK_FLAG
+        } else {
+            0
+        };
+        buffer.write_u8(self.packet_type as u8 | flags)?;
         buffer.write_u16::<NetworkEndian>(self.packet_id.0)?;
         buffer.write_u16::<NetworkEndian>(self.last_ack_packet_id.0)?;
         buffer.write_u32::<NetworkEndian>(self.ack_bitfield)?;
         buffer.write_u16::<NetworkEndian>(self.tick.0)?;
+        if let Some(sub_tick) = self.sub_tick {
+            buffer.write_u8(sub_tick.0)?;
+        }
         Ok(())
     }
 
@@ -58,12 +90,18 @@ impl ToBytes for PacketHeader {
         let last_ack_packet_id = buffer.read_u16::<NetworkEndian>()?;
         let ack_bitfield = buffer.read_u32::<NetworkEndian>()?;
         let tick = buffer.read_u16::<NetworkEndian>()?;
+        let sub_tick = if packet_type & SUB_TICK_FLAG != 0 {
+            Some(SubTickFraction(buffer.read_u8()?))
+        } else {
+            None
+        };
         Ok(Self {
-            packet_type: PacketType::try_from(packet_type)?,
+            packet_type: PacketType::try_from(packet_type & !SUB_TICK_FLAG)?,
             packet_id: PacketId(packet_id),
             last_ack_packet_id: PacketId(last_ack_packet_id),
             ack_bitfield,
             tick: Tick(tick),
+            sub_tick,
         })
     }
 }
@@ -122,6 +160,13 @@ pub struct PacketHeaderManager {
     /// The default is 1.5; i.e. after 1.5 times the round trip time, we consider a packet lost if
     /// we haven't received an ACK for it.
     nack_rtt_multiple: f32,
+    /// Time at which we sent the oldest of our packets that the remote hasn't acked yet.
+    ///
+    /// Packets that are considered lost still count until the remote acks one of our packets again,
+    /// so that a remote that stopped acking is detected.
+    oldest_unacked_packet_time: Option<WrappedTime>,
+    /// Last time we sent a packet
+    last_send_time: Option<WrappedTime>,
 }
 
 impl PacketHeaderManager {
@@ -138,9 +183,29 @@ impl PacketHeaderManager {
             // ack_notification_receiver,
             current_time: WrappedTime::default(),
             nack_rtt_multiple,
+            oldest_unacked_packet_time: None,
+            last_send_time: None,
         }
     }
 
+    /// How long the oldest of our packets that the remote hasn't acked yet has been in flight.
+    ///
+    /// Returns `None` if all our packets have been acked.
+    pub(crate) fn oldest_unacked_packet_age(&self) -> Option<Duration> {
+        self.oldest_unacked_packet_time
+            .map(|time_sent| self.elapsed_since(time_sent))
+    }
+
+    /// How long it's been since we last sent a packet
+    pub(crate) fn time_since_last_send(&self) -> Option<Duration> {
+        self.last_send_time
+            .map(|last_send_time| self.elapsed_since(last_send_time))
+    }
+
+    fn elapsed_since(&self, time: WrappedTime) -> Duration {
+        (self.current_time - time).to_std().unwrap_or_default()
+    }
+
     /// Internal bookkeeping.
     /// Returns a list of packets that are considered NACKed (i.e. acknowledged as losts)
     pub(crate) fn update(
@@ -215,6 +280,10 @@ impl PacketHeaderManager {
                 }
             }
         }
+        if !newly_acked_packets.is_empty() {
+            // the remote is acking again: only the packets that are still in flight count
+            self.oldest_unacked_packet_time = self.sent_packets_not_acked.values().min().copied();
+        }
         newly_acked_packets
     }
 
@@ -237,27 +306,44 @@ impl PacketHeaderManager {
 
     /// Prepare the header of the next packet to send
     pub(crate) fn prepare_send_packet_header(&mut self, packet_type: PacketType) -> PacketHeader {
+        let outgoing_header = self.next_header(packet_type);
+        // keep track of when we sent the packet (so that if we don't get an ack after a certain amount of time we can consider it lost)
+        self.sent_packets_not_acked
+            .insert(self.next_packet_id, self.current_time);
+        self.oldest_unacked_packet_time
+            .get_or_insert(self.current_time);
+        self.increment_next_packet_id();
+        outgoing_header
+    }
+
+    /// Prepare the header of a packet that only contains acks for the packets we received.
+    ///
+    /// We don't expect the remote to ack this packet, so it is not tracked.
+    pub(crate) fn prepare_ack_packet_header(&mut self) -> PacketHeader {
+        let outgoing_header = self.next_header(PacketType::Data);
+        self.increment_next_packet_id();
+        outgoing_header
+    }
+
+    fn next_header(&mut self, packet_type: PacketType) -> PacketHeader {
         // if we didn't have a last packet id, start with the maximum value
         // (so that receiving 0 counts as an update)
         let last_ack_packet_id = match self.recv_buffer.last_recv_packet_id {
             Some(id) => id,
             None => PacketId(u16::MAX),
         };
-        let outgoing_header = PacketHeader {
+        // we build the header only when we actually send the packet, so computing the stats here is valid
+        self.stats_manager.sent_packet();
+        self.last_send_time = Some(self.current_time);
+        PacketHeader {
             packet_type,
             packet_id: self.next_packet_id,
             last_ack_packet_id,
             ack_bitfield: self.recv_buffer.get_bitfield(),
             // TODO: we send the tick, later. Seems a bit dangerous...
             tick: Tick(0),
-        };
-        // we build the header only when we actually send the packet, so computing the stats here is valid
-        self.stats_manager.sent_packet();
-        // keep track of when we sent the packet (so that if we don't get an ack after a certain amount of time we can consider it lost)
-        self.sent_packets_not_acked
-            .insert(self.next_packet_id, self.current_time);
-        self.increment_next_packet_id();
-        outgoing_header
+            sub_tick: None,
+        }
     }
 }
 
@@ -412,14 +498,40 @@ mod tests {
             last_ack_packet_id: PacketId(13),
             ack_bitfield: 3,
             tick: Tick(6),
+            sub_tick: None,
+        };
+        let mut writer = Vec::new();
+        header.to_bytes(&mut writer)?;
+        assert_eq!(writer.len(), header.len());
+
+        let mut reader = writer.into();
+        let read_header = PacketHeader::from_bytes(&mut reader)?;
+        assert_eq!(header, read_header);
+        Ok(())
+    }
+
+    /// The sub-tick fraction is written in one extra byte, and round-trips with a precision of 1/255 tick
+    #[test]
+    fn test_serde_header_sub_tick() -> Result<(), SerializationError> {
+        let header = PacketHeader {
+            packet_type: PacketType::DataFragment,
+            packet_id: PacketId(27),
+            last_ack_packet_id: PacketId(13),
+            ack_bitfield: 3,
+            tick: Tick(6),
+            sub_tick: Some(SubTickFraction::new(0.3)),
         };
         let mut writer = Vec::new();
         header.to_bytes(&mut writer)?;
+        assert_eq!(writer.len(), 12);
         assert_eq!(writer.len(), header.len());
 
         let mut reader = writer.into();
         let read_header = PacketHeader::from_bytes(&mut reader)?;
         assert_eq!(header, read_header);
+        assert_eq!(read_header.get_packet_type(), PacketType::DataFragment);
+        let fraction = read_header.sub_tick.unwrap().fraction();
+        assert!((fraction - 0.3).abs() <= 0.5 / 255.0, "{fraction}");
         Ok(())
     }
 }
@@ -1,22 +1,25 @@
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 
+use bevy::utils::Duration;
 use bytes::Bytes;
 use crossbeam_channel::{Receiver, Sender};
-use tracing::trace;
 #[cfg(feature = "trace")]
 use tracing::{instrument, Level};
+use tracing::{trace, trace_span};
 
-use crate::channel::builder::ChannelContainer;
+use crate::channel::builder::{ChannelContainer, ChannelMode};
 use crate::channel::receivers::ChannelReceive;
-use crate::channel::senders::ChannelSend;
+use crate::channel::senders::{ChannelSend, ChannelSender};
 #[cfg(feature = "trace")]
 use crate::channel::stats::send::ChannelSendStats;
+use crate::channel::stats::ChannelStats;
+use crate::connection::netcode::MAX_PACKET_SIZE;
 use crate::packet::error::PacketError;
-use crate::packet::header::PacketHeader;
+use crate::packet::header::{PacketHeader, SubTickFraction};
 use crate::packet::message::{
     FragmentData, MessageAck, MessageId, ReceiveMessage, SendMessage, SingleData,
 };
-use crate::packet::packet::PacketId;
+use crate::packet::packet::{fragment_size, PacketId, MIN_MTU};
 use crate::packet::packet_builder::{PacketBuilder, Payload, RecvPayload};
 use crate::packet::packet_type::PacketType;
 use crate::packet::priority_manager::{PriorityConfig, PriorityManager};
@@ -43,7 +46,7 @@ pub const DEFAULT_MESSAGE_PRIORITY: f32 = 1.0;
 pub struct MessageManager {
     /// Handles sending/receiving packets (including acks)
     packet_manager: PacketBuilder,
-    priority_manager: PriorityManager,
+    pub(crate) priority_manager: PriorityManager,
     pub(crate) channels: HashMap<ChannelKind, ChannelContainer>,
     pub(crate) channel_registry: ChannelRegistry,
     // TODO: can use Vec<ChannelKind, Vec<MessageId>> to be more efficient?
@@ -51,6 +54,24 @@ pub struct MessageManager {
     /// reliable senders can stop trying to send a message that has already been received
     packet_to_message_ack_map: HashMap<PacketId, Vec<(ChannelKind, MessageAck)>>,
     nack_senders: Vec<Sender<MessageId>>,
+    /// Copy of the last packets sent/received, only present if packet capture is enabled
+    packet_capture: Option<PacketCapture>,
+    /// If true, the sub-tick fraction is included in the header of the packets we send
+    send_sub_tick_fraction: bool,
+    /// Sub-tick fraction included in the header of the last packet received, if the remote sent it
+    last_received_sub_tick_fraction: Option<f32>,
+    /// True if we received messages since the last time we sent a packet (and therefore haven't acked them yet)
+    received_messages_to_ack: bool,
+    /// If set, when we have received messages but have nothing to send, we send a packet containing only the acks
+    /// at most once per interval
+    ack_packet_interval: Option<Duration>,
+}
+
+/// The raw bytes of the most recent packets sent and received on a connection, for debugging
+#[derive(Debug, Default)]
+struct PacketCapture {
+    last_sent: Option<Payload>,
+    last_received: Option<RecvPayload>,
 }
 
 impl MessageManager {
@@ -61,19 +82,94 @@ impl MessageManager {
     ) -> Self {
         Self {
             packet_manager: PacketBuilder::new(nack_rtt_multiple),
-            priority_manager: PriorityManager::new(priority_config),
+            priority_manager: PriorityManager::new(priority_config, channel_registry),
             channels: channel_registry.channels(),
             channel_registry: channel_registry.clone(),
             packet_to_message_ack_map: HashMap::new(),
             nack_senders: vec![],
+            packet_capture: None,
+            send_sub_tick_fraction: false,
+            last_received_sub_tick_fraction: None,
+            received_messages_to_ack: false,
+            ack_packet_interval: None,
+        }
+    }
+
+    /// Set the maximum number of bytes of the packets we send.
+    ///
+    /// Messages that don't fit in a packet are split into fragments; the remote must use the
+    /// same value so that it can reassemble them.
+    pub(crate) fn set_mtu(&mut self, mtu: usize) {
+        debug_assert!((MIN_MTU..=MAX_PACKET_SIZE).contains(&mtu));
+        self.packet_manager.mtu = mtu;
+        for channel in self.channels.values_mut() {
+            channel.sender.set_fragment_size(fragment_size(mtu));
+            channel.receiver.set_fragment_size(fragment_size(mtu));
         }
     }
 
+    /// Include the sub-tick fraction in the header of the packets we send.
+    ///
+    /// This costs 1 extra byte per packet.
+    pub(crate) fn enable_sub_tick_fraction(&mut self) {
+        self.send_sub_tick_fraction = true;
+    }
+
+    /// Send a packet containing only the acks at most once per `interval` when we have nothing else to send,
+    /// so that the remote knows that its packets arrived even if we are idle
+    pub(crate) fn enable_ack_packets(&mut self, interval: Duration) {
+        self.ack_packet_interval = Some(interval);
+    }
+
+    /// Set the overstep (fraction of the tick duration elapsed since the start of the current tick)
+    /// that will be written in the header of the next packets, if the sub-tick fraction is enabled
+    pub(crate) fn set_overstep(&mut self, overstep: f32) {
+        if self.send_sub_tick_fraction {
+            self.packet_manager.sub_tick = Some(SubTickFraction::new(overstep));
+        }
+    }
+
+    /// The sub-tick fraction sent by the remote in the last packet we received, if it was included
+    pub(crate) fn last_received_sub_tick_fraction(&self) -> Option<f32> {
+        self.last_received_sub_tick_fraction
+    }
+
+    /// Keep a copy of the last packet sent and of the last packet received.
+    ///
+    /// This is useful to debug protocol mismatches, but adds a copy for every packet.
+    pub(crate) fn enable_packet_capture(&mut self) {
+        self.packet_capture
+            .get_or_insert_with(PacketCapture::default);
+    }
+
+    /// The raw bytes of the last packet sent, if packet capture is enabled
+    pub fn last_sent_packet(&self) -> Option<&[u8]> {
+        self.packet_capture
+            .as_ref()
+            .and_then(|capture| capture.last_sent.as_deref())
+    }
+
+    /// The raw bytes of the last packet received, if packet capture is enabled
+    pub fn last_received_packet(&self) -> Option<&[u8]> {
+        self.packet_capture
+            .as_ref()
+            .and_then(|capture| capture.last_received.as_deref())
+    }
+
     pub(crate) fn get_replication_update_send_receiver(&mut self) -> Receiver<MessageId> {
         self.priority_manager
             .subscribe_replication_update_sent_messages()
     }
 
+    /// How long the oldest of our packets that the remote hasn't acked yet has been in flight.
+    ///
+    /// Returns `None` if the remote acked all our packets.
+    pub(crate) fn oldest_unacked_packet_age(&self) -> Option<Duration> {
+        self.packet_manager
+            .header_manager
+            .oldest_unacked_packet_age()
+    }
+
     /// Update bookkeeping
     #[cfg_attr(feature = "trace", instrument(level = Level::INFO, skip_all))]
     pub fn update(
@@ -112,6 +208,7 @@ impl MessageManager {
                 .update(time_manager, ping_manager, tick_manager);
             channel.receiver.update(time_manager, tick_manager);
         }
+        self.priority_manager.update(time_manager.delta());
     }
 
     /// Buffer a message to be sent on this connection
@@ -142,19 +239,45 @@ impl MessageManager {
         channel_kind: ChannelKind,
         priority: f32,
     ) -> Result<Option<MessageId>, PacketError> {
+        let channel_name = self
+            .channel_registry
+            .name(&channel_kind)
+            .unwrap_or("unknown");
+        let _span = trace_span!("buffer_send", channel = channel_name).entered();
         let channel = self
             .channels
             .get_mut(&channel_kind)
             .ok_or(PacketError::ChannelNotFound)?;
-        Ok(channel.sender.buffer_send(message, priority)?)
+        let message_id = channel.sender.buffer_send(message, priority)?;
+        trace!(?message_id, "buffered message");
+        Ok(message_id)
     }
 
     /// Prepare buckets from the internal send buffers, and return the bytes to send
     // TODO: maybe pass TickManager instead of Tick? Find a more elegant way to pass extra data that might not be used?
     //  (ticks are not purely necessary without client prediction)
     //  maybe be generic over a Context ?
-    #[cfg_attr(feature = "trace", instrument(level = Level::INFO, skip_all))]
     pub fn send_packets(&mut self, current_tick: Tick) -> Result<Vec<Payload>, PacketError> {
+        Ok(self
+            .send_packets_with_reliability(current_tick, false)?
+            .into_iter()
+            .map(|(payload, _)| payload)
+            .collect())
+    }
+
+    /// Prepare buckets from the internal send buffers, and return the bytes to send.
+    ///
+    /// Each payload is returned with a flag that is true if the packet contains messages from a
+    /// reliable channel, so that transports that provide their own reliability layer can send it reliably.
+    /// If `split_reliable` is true (the transport has its own reliability layer), the messages of reliable
+    /// and unreliable channels are written in separate packets, so that the unreliable messages are never
+    /// delayed by the retransmissions of the transport. Otherwise they share the same packets.
+    #[cfg_attr(feature = "trace", instrument(level = Level::INFO, skip_all))]
+    pub fn send_packets_with_reliability(
+        &mut self,
+        current_tick: Tick,
+        split_reliable: bool,
+    ) -> Result<Vec<(Payload, bool)>, PacketError> {
         // Step 1. Get the list of packets to send from all channels
         // for each channel, prepare packets using the buffered messages that are ready to be sent
         // TODO: iterate through the channels in order of channel priority? (with accumulation)
@@ -175,8 +298,9 @@ impl MessageManager {
         }
         // return early if there are no messages to send
         if !has_data_to_send {
-            return Ok(vec![]);
+            return self.send_ack_packet(current_tick);
         }
+        self.received_messages_to_ack = false;
 
         // priority manager: get the list of messages we can send according to the rate limiter
         //  (the other messages are stored in an internal buffer)
@@ -184,6 +308,17 @@ impl MessageManager {
             .priority_manager
             .priority_filter(data_to_send, &self.channel_registry, current_tick);
 
+        for (channel_id, data) in &single_data {
+            self.get_channel_mut(*channel_id)?
+                .stats
+                .add_sent(data.len(), data.iter().map(|d| d.bytes.len()).sum());
+        }
+        for (channel_id, data) in &fragment_data {
+            self.get_channel_mut(*channel_id)?
+                .stats
+                .add_sent(data.len(), data.iter().map(|d| d.bytes.len()).sum());
+        }
+
         #[cfg(feature = "trace")]
         {
             // NOTE: we don't know the actual exact amount of bytes sent (because we don't take into account the ids, etc.),
@@ -216,13 +351,70 @@ impl MessageManager {
             }
         }
 
-        let packets =
-            self.packet_manager
-                .build_packets(current_tick, single_data, fragment_data)?;
+        // split the messages of the reliable and unreliable channels (if the transport has its own reliability layer)
+        let is_reliable = |channel_id: &NetId| -> Result<bool, PacketError> {
+            let channel_kind = self
+                .channel_registry
+                .get_kind_from_net_id(*channel_id)
+                .ok_or(PacketError::ChannelNotFound)?;
+            Ok(self
+                .channels
+                .get(channel_kind)
+                .ok_or(PacketError::ChannelNotFound)?
+                .setting
+                .mode
+                .is_reliable())
+        };
+        let mut batches = vec![];
+        if split_reliable {
+            let (mut reliable_single_data, mut unreliable_single_data) = (vec![], vec![]);
+            for data in single_data {
+                if is_reliable(&data.0)? {
+                    reliable_single_data.push(data);
+                } else {
+                    unreliable_single_data.push(data);
+                }
+            }
+            let (mut reliable_fragment_data, mut unreliable_fragment_data) = (vec![], vec![]);
+            for data in fragment_data {
+                if is_reliable(&data.0)? {
+                    reliable_fragment_data.push(data);
+                } else {
+                    unreliable_fragment_data.push(data);
+                }
+            }
+            batches.push((false, unreliable_single_data, unreliable_fragment_data));
+            batches.push((true, reliable_single_data, reliable_fragment_data));
+        } else {
+            let mut reliable = false;
+            for channel_id in single_data
+                .iter()
+                .map(|(id, _)| id)
+                .chain(fragment_data.iter().map(|(id, _)| id))
+            {
+                reliable |= is_reliable(channel_id)?;
+            }
+            batches.push((reliable, single_data, fragment_data));
+        }
+        let mut packets = vec![];
+        for (reliable, single_data, fragment_data) in batches {
+            if single_data.is_empty() && fragment_data.is_empty() {
+                continue;
+            }
+            packets.extend(
+                self.packet_manager
+                    .build_packets(current_tick, single_data, fragment_data)?
+                    .into_iter()
+                    .map(|packet| (packet, reliable)),
+            );
+        }
 
         let mut bytes = Vec::new();
-        for mut packet in packets {
-            trace!(packet_id = ?packet.packet_id, num_messages = ?packet.num_messages(), "sending packet");
+        for (mut packet, reliable) in packets {
+            // the messages in the packet can be correlated with the receiver side via the packet id
+            // and the message ids
+            let _span = trace_span!("send_packet", packet_id = ?packet.packet_id).entered();
+            trace!(num_messages = ?packet.num_messages(), "sending packet");
             // TODO: should we update this to include fragment info as well?
             // Step 2. Update the packet_to_message_id_map (only for channels that care about acks)
             std::mem::take(&mut packet.message_acks)
@@ -232,6 +424,12 @@ impl MessageManager {
                         .channel_registry
                         .get_kind_from_net_id(channel_id)
                         .ok_or(PacketError::ChannelNotFound)?;
+                    trace!(
+                        channel = self.channel_registry.name(channel_kind).unwrap_or("unknown"),
+                        message_id = ?message_ack.message_id,
+                        fragment_id = ?message_ack.fragment_id,
+                        "sending message"
+                    );
                     let channel = self
                         .channels
                         .get(channel_kind)
@@ -252,12 +450,12 @@ impl MessageManager {
                 })?;
 
             // Step 3. Get the packets to send over the network
-            bytes.push(packet.payload);
+            bytes.push((packet.payload, reliable));
         }
 
         // adjust the real amount of bytes that we sent through the limiter (to account for the actual packet size)
         if self.priority_manager.config.enabled {
-            let total_bytes_sent = bytes.iter().map(|b| b.len() as u32).sum::<u32>();
+            let total_bytes_sent = bytes.iter().map(|(b, _)| b.len() as u32).sum::<u32>();
             if let Ok(remaining_bytes_to_add) =
                 (total_bytes_sent - num_bytes_added_to_limiter).try_into()
             {
@@ -268,20 +466,51 @@ impl MessageManager {
             }
         }
 
+        if let Some(capture) = self.packet_capture.as_mut() {
+            if let Some((last, _)) = bytes.last() {
+                capture.last_sent = Some(last.clone());
+            }
+        }
         Ok(bytes)
     }
 
+    /// If we received messages that we haven't acked for a while because we had nothing to send,
+    /// send a packet that only contains the acks, so that the remote knows that its packets arrived.
+    fn send_ack_packet(&mut self, current_tick: Tick) -> Result<Vec<(Payload, bool)>, PacketError> {
+        let Some(ack_packet_interval) = self.ack_packet_interval else {
+            return Ok(vec![]);
+        };
+        if !self.received_messages_to_ack
+            || self
+                .packet_manager
+                .header_manager
+                .time_since_last_send()
+                .is_some_and(|elapsed| elapsed < ack_packet_interval)
+        {
+            return Ok(vec![]);
+        }
+        self.received_messages_to_ack = false;
+        let packet = self.packet_manager.build_ack_packet(current_tick)?;
+        trace!(packet_id = ?packet.packet_id, "sending ack packet");
+        Ok(vec![(packet.payload, false)])
+    }
+
     /// Process packet received over the network as raw bytes
     /// Update the acks, and put the messages from the packets in internal buffers
     /// Returns the tick of the packet
     #[cfg_attr(feature = "trace", instrument(level = Level::INFO, skip_all))]
     pub fn recv_packet(&mut self, packet: RecvPayload) -> Result<Tick, PacketError> {
         trace!(?packet, "Received packet");
+        if let Some(capture) = self.packet_capture.as_mut() {
+            capture.last_received = Some(packet.clone());
+        }
         let mut cursor = Reader::from(packet);
 
         // Step 1. Parse the packet
         let header = PacketHeader::from_bytes(&mut cursor)?;
         let tick = header.tick;
+        self.last_received_sub_tick_fraction = header.sub_tick.map(|sub_tick| sub_tick.fraction());
+        let _span = trace_span!("recv_packet", packet_id = ?header.packet_id, ?tick).entered();
 
         // TODO: if it's fragmented, put it in a buffer? while we wait for all the parts to be ready?
         //  maybe the channel can handle the fragmentation?
@@ -319,6 +548,11 @@ impl MessageManager {
             }
         }
 
+        // packets that only contain acks don't need to be acked
+        if header.get_packet_type() == PacketType::DataFragment || cursor.has_remaining() {
+            self.received_messages_to_ack = true;
+        }
+
         // Step 4. Parse the payload into messages, put them in the internal buffers for each channel
         // we read directly from the packet and don't create intermediary datastructures to avoid allocations
         // TODO: maybe do this in a helper function?
@@ -326,12 +560,18 @@ impl MessageManager {
             // read the fragment data
             let channel_id = ChannelId::from_bytes(&mut cursor)?;
             let fragment_data = FragmentData::from_bytes(&mut cursor)?;
-            self.get_channel_mut(channel_id)?
-                .receiver
-                .buffer_recv(ReceiveMessage {
-                    data: fragment_data.into(),
-                    remote_sent_tick: tick,
-                })?;
+            trace!(
+                channel = self.channel_name(channel_id),
+                message_id = ?fragment_data.message_id,
+                fragment_id = ?fragment_data.fragment_id,
+                "received message"
+            );
+            let channel = self.get_channel_mut(channel_id)?;
+            channel.stats.add_received(fragment_data.bytes.len());
+            channel.receiver.buffer_recv(ReceiveMessage {
+                data: fragment_data.into(),
+                remote_sent_tick: tick,
+            })?;
         }
         // read single message data
         while cursor.has_remaining() {
@@ -339,12 +579,17 @@ impl MessageManager {
             let num_messages = cursor.read_varint()?;
             for i in 0..num_messages {
                 let single_data = SingleData::from_bytes(&mut cursor)?;
-                self.get_channel_mut(channel_id)?
-                    .receiver
-                    .buffer_recv(ReceiveMessage {
-                        data: single_data.into(),
-                        remote_sent_tick: tick,
-                    })?;
+                trace!(
+                    channel = self.channel_name(channel_id),
+                    message_id = ?single_data.id,
+                    "received message"
+                );
+                let channel = self.get_channel_mut(channel_id)?;
+                channel.stats.add_received(single_data.bytes.len());
+                channel.receiver.buffer_recv(ReceiveMessage {
+                    data: single_data.into(),
+                    remote_sent_tick: tick,
+                })?;
             }
         }
         // trace!(
@@ -375,9 +620,12 @@ impl MessageManager {
     pub(crate) fn read_messages(
         &mut self,
     ) -> impl Iterator<Item = (ChannelKind, (Tick, bytes::Bytes))> + Captures<&()> {
+        let channel_registry = &self.channel_registry;
         self.channels
             .iter_mut()
             .flat_map(move |(channel_kind, channel)| {
+                let channel_name = channel_registry.name(channel_kind).unwrap_or("unknown");
+                let _span = trace_span!("read_messages", channel = channel_name).entered();
                 // TODO: this is broken, we need to call a read_message in a while loop !
                 channel.receiver.read_message().map(move |(tick, bytes)| {
                     trace!(?channel_kind, "reading message: {:?}", bytes);
@@ -401,6 +649,14 @@ impl MessageManager {
         map
     }
 
+    /// Name of the channel, used in the logs
+    fn channel_name(&self, channel_id: ChannelId) -> &str {
+        self.channel_registry
+            .get_kind_from_net_id(channel_id)
+            .and_then(|kind| self.channel_registry.name(kind))
+            .unwrap_or("unknown")
+    }
+
     pub fn get_channel_mut(
         &mut self,
         channel_id: ChannelId,
@@ -414,6 +670,79 @@ impl MessageManager {
             .ok_or(PacketError::ChannelNotFound)
     }
 
+    /// Returns the number of messages sent on the channel that are still waiting for an ack.
+    ///
+    /// For reliable channels, these are the messages that have been sent at least once and
+    /// will keep being re-sent until they are acked. For other channels that watch acks, these
+    /// are the messages contained in packets that haven't been acked or lost yet.
+    ///
+    /// Returns 0 if the channel does not exist or does not track acks.
+    pub fn unacked_count(&self, channel_kind: ChannelKind) -> usize {
+        let Some(channel) = self.channels.get(&channel_kind) else {
+            return 0;
+        };
+        if let ChannelSender::Reliable(sender) = &channel.sender {
+            return sender.num_unacked_sent_messages();
+        }
+        self.packet_to_message_ack_map
+            .values()
+            .flatten()
+            .filter(|(kind, _)| *kind == channel_kind)
+            .map(|(_, message_ack)| message_ack.message_id)
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
+    /// Returns the number of messages buffered on a reliable channel that haven't been acked yet,
+    /// whether they have already been sent or are still waiting to be sent (for example because of
+    /// the bandwidth cap).
+    ///
+    /// Returns 0 if the channel does not exist or is not reliable.
+    pub(crate) fn pending_reliable_count(&self, channel_kind: ChannelKind) -> usize {
+        match self
+            .channels
+            .get(&channel_kind)
+            .map(|channel| &channel.sender)
+        {
+            Some(ChannelSender::Reliable(sender)) => sender.num_unacked_messages(),
+            _ => 0,
+        }
+    }
+
+    /// Cancel a reliable message that hasn't been acked yet, so that it won't be sent or re-sent anymore.
+    ///
+    /// This is best-effort: if the message was already sent, it might still be received by the remote peer.
+    /// Returns true if the message was still waiting for an ack and has been cancelled.
+    ///
+    /// Returns false if the channel is not reliable, or if it is ordered: the remote peer waits for every
+    /// message on an ordered channel, so cancelling one would block all the following messages.
+    pub fn cancel_message(&mut self, channel_kind: ChannelKind, message_id: MessageId) -> bool {
+        let Some(channel) = self.channels.get_mut(&channel_kind) else {
+            return false;
+        };
+        if matches!(channel.setting.mode, ChannelMode::OrderedReliable(_)) {
+            return false;
+        }
+        match &mut channel.sender {
+            ChannelSender::Reliable(sender) => sender.cancel_message(message_id),
+            _ => false,
+        }
+    }
+
+    /// Get the [`ChannelStats`] of a given channel
+    pub fn channel_stats<C: crate::prelude::Channel>(&self) -> Option<&ChannelStats> {
+        self.channels
+            .get(&ChannelKind::of::<C>())
+            .map(|channel| &channel.stats)
+    }
+
+    /// Iterate through the [`ChannelStats`] of all the channels
+    pub fn all_channel_stats(&self) -> impl Iterator<Item = (&ChannelKind, &ChannelStats)> {
+        self.channels
+            .iter()
+            .map(|(channel_kind, channel)| (channel_kind, &channel.stats))
+    }
+
     /// Get the ChannelSendStats of a given channel
     #[cfg(feature = "trace")]
     pub fn channel_send_stats<C: crate::prelude::Channel>(&self) -> Option<&ChannelSendStats> {
@@ -436,6 +765,7 @@ mod tests {
     use crate::packet::priority_manager::PriorityConfig;
     use crate::prelude::*;
 
+    use crate::tests::log_buffer::LogBuffer;
     use crate::tests::protocol::*;
 
     use super::*;
@@ -635,6 +965,33 @@ mod tests {
         Ok(())
     }
 
+    /// Check that a custom MTU is used to split messages into fragments and to reassemble them
+    #[test]
+    fn test_message_manager_custom_mtu() -> Result<(), PacketError> {
+        let (mut client_message_manager, mut server_message_manager) = setup();
+        const MTU: usize = 200;
+        client_message_manager.set_mtu(MTU);
+        server_message_manager.set_mtu(MTU);
+
+        // a message slightly bigger than the maximum packet size must be split in 2 fragments
+        let message = Bytes::from((0..MTU + 10).map(|i| i as u8).collect::<Vec<_>>());
+        let channel_kind = ChannelKind::of::<Channel1>();
+        client_message_manager.buffer_send(message.clone(), channel_kind)?;
+        let payloads = client_message_manager.send_packets(Tick(0))?;
+        assert_eq!(payloads.len(), 2);
+        for payload in &payloads {
+            assert!(payload.len() <= MTU);
+        }
+
+        for payload in payloads {
+            server_message_manager.recv_packet(payload.into())?;
+        }
+        let it = server_message_manager.read_messages();
+        let data = MessageManager::collect_messages(it);
+        assert_eq!(data.get(&channel_kind).unwrap(), &vec![(Tick(0), message)]);
+        Ok(())
+    }
+
     #[test]
     fn test_notify_ack() -> Result<(), PacketError> {
         let (mut client_message_manager, mut server_message_manager) = setup();
@@ -682,4 +1039,260 @@ mod tests {
         assert_eq!(update_acks_tracker.try_recv().unwrap(), message_id);
         Ok(())
     }
+
+    #[test]
+    fn test_unacked_count() -> Result<(), PacketError> {
+        let mut channel_registry = ChannelRegistry::default();
+        channel_registry.add_channel::<Channel1>(ChannelSettings {
+            mode: ChannelMode::OrderedReliable(ReliableSettings::default()),
+            ..default()
+        });
+        channel_registry.add_channel::<Channel2>(ChannelSettings {
+            mode: ChannelMode::UnorderedUnreliableWithAcks,
+            ..default()
+        });
+        let mut client_message_manager =
+            MessageManager::new(&channel_registry, 1.5, PriorityConfig::default());
+        let mut server_message_manager =
+            MessageManager::new(&channel_registry, 1.5, PriorityConfig::default());
+
+        // buffered messages that haven't been sent yet are not in-flight
+        for i in 0..3 {
+            client_message_manager.buffer_send(vec![i].into(), Channel1::kind())?;
+        }
+        client_message_manager.buffer_send(vec![3].into(), Channel2::kind())?;
+        assert_eq!(client_message_manager.unacked_count(Channel1::kind()), 0);
+        assert_eq!(client_message_manager.unacked_count(Channel2::kind()), 0);
+
+        // send the messages without receiving any acks
+        let payloads = client_message_manager.send_packets(Tick(0))?;
+        assert_eq!(client_message_manager.unacked_count(Channel1::kind()), 3);
+        assert_eq!(client_message_manager.unacked_count(Channel2::kind()), 1);
+
+        // the server receives the messages and sends back a packet that contains the acks
+        for payload in payloads {
+            server_message_manager.recv_packet(payload.into())?;
+        }
+        server_message_manager.buffer_send(vec![4].into(), Channel2::kind())?;
+        for payload in server_message_manager.send_packets(Tick(0))? {
+            client_message_manager.recv_packet(payload.into())?;
+        }
+        assert_eq!(client_message_manager.unacked_count(Channel1::kind()), 0);
+        assert_eq!(client_message_manager.unacked_count(Channel2::kind()), 0);
+        Ok(())
+    }
+
+    /// Check that a reliable message that is cancelled before being sent is never sent
+    #[test]
+    fn test_cancel_message() -> Result<(), PacketError> {
+        let mut channel_registry = ChannelRegistry::default();
+        channel_registry.add_channel::<Channel1>(ChannelSettings {
+            mode: ChannelMode::UnorderedReliable(ReliableSettings::default()),
+            ..default()
+        });
+        channel_registry.add_channel::<Channel2>(ChannelSettings {
+            mode: ChannelMode::OrderedReliable(ReliableSettings::default()),
+            ..default()
+        });
+        let mut client_message_manager =
+            MessageManager::new(&channel_registry, 1.5, PriorityConfig::default());
+        let mut server_message_manager =
+            MessageManager::new(&channel_registry, 1.5, PriorityConfig::default());
+
+        let cancelled = client_message_manager
+            .buffer_send(vec![0].into(), Channel1::kind())?
+            .unwrap();
+        client_message_manager.buffer_send(vec![1].into(), Channel1::kind())?;
+        assert!(client_message_manager.cancel_message(Channel1::kind(), cancelled));
+        // the message cannot be cancelled twice
+        assert!(!client_message_manager.cancel_message(Channel1::kind(), cancelled));
+
+        // messages on ordered channels cannot be cancelled
+        let ordered = client_message_manager
+            .buffer_send(vec![2].into(), Channel2::kind())?
+            .unwrap();
+        assert!(!client_message_manager.cancel_message(Channel2::kind(), ordered));
+
+        for payload in client_message_manager.send_packets(Tick(0))? {
+            server_message_manager.recv_packet(payload.into())?;
+        }
+        let messages = server_message_manager.read_messages().collect::<Vec<_>>();
+        assert_eq!(messages.len(), 2);
+        assert!(messages.contains(&(Channel1::kind(), (Tick(0), vec![1].into()))));
+        assert!(messages.contains(&(Channel2::kind(), (Tick(0), vec![2].into()))));
+        Ok(())
+    }
+
+    /// Check that the packets that contain messages from a reliable channel are flagged as reliable,
+    /// so that they can be sent reliably by transports that support it, and that the unreliable messages
+    /// are only sent in separate packets if the transport has its own reliability layer
+    #[test]
+    fn test_send_packets_reliability() -> Result<(), PacketError> {
+        let mut channel_registry = ChannelRegistry::default();
+        channel_registry.add_channel::<Channel1>(ChannelSettings {
+            mode: ChannelMode::UnorderedUnreliableWithAcks,
+            ..default()
+        });
+        channel_registry.add_channel::<Channel2>(ChannelSettings {
+            mode: ChannelMode::OrderedReliable(ReliableSettings::default()),
+            ..default()
+        });
+        let mut message_manager =
+            MessageManager::new(&channel_registry, 1.5, PriorityConfig::default());
+
+        message_manager.buffer_send(vec![0].into(), Channel1::kind())?;
+        let payloads = message_manager.send_packets_with_reliability(Tick(0), true)?;
+        assert_eq!(payloads.len(), 1);
+        assert!(!payloads[0].1);
+
+        message_manager.buffer_send(vec![1].into(), Channel2::kind())?;
+        let payloads = message_manager.send_packets_with_reliability(Tick(1), true)?;
+        assert_eq!(payloads.len(), 1);
+        assert!(payloads[0].1);
+
+        // with a transport that has its own reliability layer, reliable and unreliable messages
+        // are never sent in the same packet
+        message_manager.buffer_send(vec![2].into(), Channel1::kind())?;
+        message_manager.buffer_send(vec![3].into(), Channel2::kind())?;
+        let payloads = message_manager.send_packets_with_reliability(Tick(2), true)?;
+        assert_eq!(payloads.len(), 2);
+        assert!(!payloads[0].1);
+        assert!(payloads[1].1);
+
+        // otherwise they share the same packet
+        message_manager.buffer_send(vec![4].into(), Channel1::kind())?;
+        message_manager.buffer_send(vec![5].into(), Channel2::kind())?;
+        let payloads = message_manager.send_packets_with_reliability(Tick(3), false)?;
+        assert_eq!(payloads.len(), 1);
+        assert!(payloads[0].1);
+        Ok(())
+    }
+
+    /// Check that the always-on channel stats count the messages and bytes sent and received
+    #[test]
+    fn test_channel_stats() -> Result<(), PacketError> {
+        let (mut client_message_manager, mut server_message_manager) = setup();
+        assert_eq!(
+            client_message_manager.channel_stats::<Channel1>(),
+            Some(&ChannelStats::default())
+        );
+
+        client_message_manager.buffer_send(vec![0; 10].into(), Channel1::kind())?;
+        client_message_manager.buffer_send(vec![1; 20].into(), Channel1::kind())?;
+        for payload in client_message_manager.send_packets(Tick(0))? {
+            server_message_manager.recv_packet(payload.into())?;
+        }
+
+        let sent = client_message_manager.channel_stats::<Channel1>().unwrap();
+        assert_eq!(sent.messages_sent(), 2);
+        assert_eq!(sent.bytes_sent(), 30);
+        let received = server_message_manager.channel_stats::<Channel1>().unwrap();
+        assert_eq!(received.messages_received(), 2);
+        assert_eq!(received.bytes_received(), 30);
+        // the other channels are unaffected
+        assert!(client_message_manager
+            .all_channel_stats()
+            .filter(|(kind, _)| **kind != Channel1::kind())
+            .all(|(_, stats)| stats.bytes_sent() == 0));
+        Ok(())
+    }
+
+    /// Check that the raw bytes of the last sent/received packets are captured when enabled
+    #[test]
+    fn test_packet_capture() -> Result<(), PacketError> {
+        let (mut client_message_manager, mut server_message_manager) = setup();
+        let channel_kind_1 = ChannelKind::of::<Channel1>();
+
+        // packet capture is disabled by default
+        client_message_manager.buffer_send(vec![0].into(), channel_kind_1)?;
+        let payloads = client_message_manager.send_packets(Tick(0))?;
+        assert!(!payloads.is_empty());
+        assert_eq!(client_message_manager.last_sent_packet(), None);
+
+        client_message_manager.enable_packet_capture();
+        server_message_manager.enable_packet_capture();
+        assert_eq!(server_message_manager.last_received_packet(), None);
+        client_message_manager.buffer_send(vec![1, 2].into(), channel_kind_1)?;
+        let payloads = client_message_manager.send_packets(Tick(1))?;
+        assert_eq!(payloads.len(), 1);
+        assert_eq!(
+            client_message_manager.last_sent_packet(),
+            Some(payloads[0].as_slice())
+        );
+
+        server_message_manager.recv_packet(payloads[0].clone().into())?;
+        assert_eq!(
+            server_message_manager.last_received_packet(),
+            Some(payloads[0].as_slice())
+        );
+        assert_eq!(server_message_manager.last_sent_packet(), None);
+        Ok(())
+    }
+
+    /// Check that a single message can be followed in the logs from `buffer_send` to `read_messages`,
+    /// using the message id and the packet id
+    #[test]
+    fn test_message_correlation_spans() -> Result<(), PacketError> {
+        let mut channel_registry = ChannelRegistry::default();
+        channel_registry.add_channel::<Channel1>(ChannelSettings {
+            mode: ChannelMode::OrderedReliable(ReliableSettings::default()),
+            ..default()
+        });
+        let mut client_message_manager =
+            MessageManager::new(&channel_registry, 1.5, PriorityConfig::default());
+        let mut server_message_manager =
+            MessageManager::new(&channel_registry, 1.5, PriorityConfig::default());
+
+        let buffer = LogBuffer::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::TRACE)
+            .with_ansi(false)
+            .with_writer(buffer.clone())
+            .finish();
+        tracing::subscriber::with_default(subscriber, || {
+            client_message_manager.buffer_send(vec![0].into(), Channel1::kind())?;
+            let message_id = client_message_manager
+                .buffer_send(vec![1].into(), Channel1::kind())?
+                .unwrap();
+            assert_eq!(message_id, MessageId(1));
+            for payload in client_message_manager.send_packets(Tick(3))? {
+                server_message_manager.recv_packet(payload.into())?;
+            }
+            // `read_messages` only reads one message per channel
+            for _ in 0..2 {
+                assert_eq!(server_message_manager.read_messages().count(), 1);
+            }
+            Ok::<(), PacketError>(())
+        })?;
+
+        let logs = buffer.logs();
+        let find_line = |patterns: &[&str]| {
+            assert!(
+                logs.lines()
+                    .any(|line| patterns.iter().all(|pattern| line.contains(pattern))),
+                "no log line contains {patterns:?} in:\n{logs}"
+            );
+        };
+        find_line(&[
+            "buffer_send{channel=",
+            "buffered message",
+            "message_id=Some(MessageId(1))",
+        ]);
+        find_line(&[
+            "send_packet{packet_id=PacketId(0)}",
+            "sending message",
+            "message_id=MessageId(1)",
+        ]);
+        find_line(&[
+            "recv_packet{packet_id=PacketId(0) tick=Tick(3)}",
+            "received message",
+            "message_id=Some(MessageId(1))",
+        ]);
+        find_line(&[
+            "read_messages{channel=",
+            "read message",
+            "message_id=MessageId(1)",
+        ]);
+        Ok(())
+    }
 }
@@ -21,16 +21,26 @@ cfg_if::cfg_if!(
 // Internal id that we assign to each packet sent over the network
 wrapping_id!(PacketId);
 
-/// Number of bytes to write the header
-const HEADER_BYTES: usize = 11;
+/// Maximum number of bytes to write the header (including the optional sub-tick fraction)
+const HEADER_BYTES: usize = 12;
 
-/// The maximum number of bytes for a message before it is fragmented
-/// MAX_PACKET_SIZE - HEADER_BYTES - 1 (channel_net_id) - 6 (message_id/fragment_id/num_fragments) - 2 (num bytes in fragment)
+/// Minimum supported MTU, so that fragments can still carry a meaningful amount of data
+pub(crate) const MIN_MTU: usize = 128;
+
+/// The maximum number of bytes for a message before it is fragmented, for a given maximum packet size
+/// mtu - HEADER_BYTES - 1 (channel_net_id) - 6 (message_id/fragment_id/num_fragments) - 2 (num bytes in fragment)
 #[cfg(feature = "big_messages")]
-pub(crate) const FRAGMENT_SIZE: usize = MAX_PACKET_SIZE - HEADER_BYTES - 9;
+pub(crate) const fn fragment_size(mtu: usize) -> usize {
+    mtu.saturating_sub(HEADER_BYTES + 9)
+}
 
 #[cfg(not(feature = "big_messages"))]
-pub(crate) const FRAGMENT_SIZE: usize = MAX_PACKET_SIZE - HEADER_BYTES - 7;
+pub(crate) const fn fragment_size(mtu: usize) -> usize {
+    mtu.saturating_sub(HEADER_BYTES + 7)
+}
+
+/// The maximum number of bytes for a message before it is fragmented, with the default MTU
+pub(crate) const FRAGMENT_SIZE: usize = fragment_size(MAX_PACKET_SIZE);
 
 /// Data structure that will help us write the packet
 #[derive(Debug)]
@@ -41,12 +51,14 @@ pub(crate) struct Packet {
     pub(crate) packet_id: PacketId,
     // How many bytes we know we are going to have to write in the packet, but haven't written yet
     pub(crate) prewritten_size: usize,
+    /// Maximum number of bytes in the packet
+    pub(crate) mtu: usize,
 }
 
 impl Packet {
     /// Check that we can still fit some data in the buffer
     pub(crate) fn can_fit(&self, size: usize) -> bool {
-        self.payload.len() + size + self.prewritten_size <= MAX_PACKET_SIZE
+        self.payload.len() + size + self.prewritten_size <= self.mtu
     }
 
     /// Check if we can write a channel_id + the number of messages in the packet.
@@ -6,9 +6,9 @@ use std::collections::VecDeque;
 #[cfg(feature = "trace")]
 use tracing::{instrument, Level};
 
-use crate::packet::header::PacketHeaderManager;
+use crate::packet::header::{PacketHeaderManager, SubTickFraction};
 use crate::packet::message::{FragmentData, MessageAck, SingleData};
-use crate::packet::packet::{Packet, FRAGMENT_SIZE};
+use crate::packet::packet::{fragment_size, Packet};
 use crate::packet::packet_type::PacketType;
 use crate::prelude::Tick;
 use crate::protocol::channel::ChannelId;
@@ -31,6 +31,10 @@ pub type RecvPayload = Bytes;
 pub(crate) struct PacketBuilder {
     pub(crate) header_manager: PacketHeaderManager,
     current_packet: Option<Packet>,
+    /// Sub-tick fraction to write in the header of the packets, if enabled
+    pub(crate) sub_tick: Option<SubTickFraction>,
+    /// Maximum number of bytes in a packet
+    pub(crate) mtu: usize,
     // Pre-allocated buffer to encode/decode without allocation.
     // TODO: should this be associated with Packet?
     // cursor: Vec<u8>,
@@ -45,6 +49,8 @@ impl PacketBuilder {
         Self {
             header_manager: PacketHeaderManager::new(nack_rtt_multiple),
             current_packet: None,
+            sub_tick: None,
+            mtu: MAX_PACKET_SIZE,
             // cursor: Vec::with_capacity(PACKET_BUFFER_CAPACITY),
             // acks: Vec::new(),
 
@@ -57,7 +63,7 @@ impl PacketBuilder {
 
     // TODO: get the vec from a pool of preallocated buffers
     fn get_new_buffer(&self) -> Payload {
-        Vec::with_capacity(MAX_PACKET_SIZE)
+        Vec::with_capacity(self.mtu)
     }
 
     /// Start building new packet, we start with an empty packet
@@ -74,16 +80,38 @@ impl PacketBuilder {
             .prepare_send_packet_header(PacketType::Data);
         // set the tick at which the packet will be sent
         header.tick = current_tick;
+        header.sub_tick = self.sub_tick;
         header.to_bytes(&mut cursor)?;
         self.current_packet = Some(Packet {
             payload: cursor,
             message_acks: vec![],
             packet_id: header.packet_id,
             prewritten_size: 0,
+            mtu: self.mtu,
         });
         Ok(())
     }
 
+    /// Build a packet without any messages, that only acks the packets we received from the remote
+    pub(crate) fn build_ack_packet(
+        &mut self,
+        current_tick: Tick,
+    ) -> Result<Packet, SerializationError> {
+        let mut cursor = self.get_new_buffer();
+        let mut header = self.header_manager.prepare_ack_packet_header();
+        header.tick = current_tick;
+        header.sub_tick = self.sub_tick;
+        header.to_bytes(&mut cursor)?;
+        cursor.shrink_to_fit();
+        Ok(Packet {
+            payload: cursor,
+            message_acks: vec![],
+            packet_id: header.packet_id,
+            prewritten_size: 0,
+            mtu: self.mtu,
+        })
+    }
+
     pub(crate) fn build_new_fragment_packet(
         &mut self,
         channel_id: NetId,
@@ -97,6 +125,7 @@ impl PacketBuilder {
             .prepare_send_packet_header(PacketType::DataFragment);
         // set the tick at which the packet will be sent
         header.tick = current_tick;
+        header.sub_tick = self.sub_tick;
         header.to_bytes(&mut cursor)?;
         channel_id.to_bytes(&mut cursor)?;
         fragment_data.to_bytes(&mut cursor)?;
@@ -112,6 +141,7 @@ impl PacketBuilder {
             )],
             packet_id: header.packet_id,
             prewritten_size: 0,
+            mtu: self.mtu,
         });
         Ok(())
 
@@ -172,7 +202,7 @@ impl PacketBuilder {
         // try to fill the packet with fragment messages first
         for (channel_id, mut fragment_messages) in fragment_data.into_iter() {
             while let Some(fragment_data) = fragment_messages.pop_front() {
-                debug_assert!(fragment_data.bytes.len() <= FRAGMENT_SIZE);
+                debug_assert!(fragment_data.bytes.len() <= fragment_size(self.mtu));
                 self.build_new_fragment_packet(channel_id, &fragment_data, current_tick)?;
                 if !fragment_data.is_last_fragment() {
                     // big fragment, write packet immediately
@@ -426,6 +456,7 @@ mod tests {
 
     use crate::channel::senders::fragment_sender::FragmentSender;
     use crate::packet::message::MessageId;
+    use crate::packet::packet::FRAGMENT_SIZE;
     use crate::prelude::*;
 
     use super::*;
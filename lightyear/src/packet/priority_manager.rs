@@ -1,8 +1,10 @@
 use bevy::utils::HashMap;
 use std::collections::VecDeque;
 use std::num::NonZeroU32;
+use std::time::Duration;
 
 use crossbeam_channel::{Receiver, Sender};
+use governor::clock::{Clock, DefaultClock, Reference};
 use governor::{DefaultDirectRateLimiter, Quota};
 use nonzero_ext::*;
 use tracing::{debug, error, trace};
@@ -11,7 +13,7 @@ use tracing::{instrument, Level};
 
 use crate::packet::message::{FragmentData, MessageData, MessageId, SendMessage, SingleData};
 use crate::prelude::{ChannelRegistry, Tick};
-use crate::protocol::channel::ChannelId;
+use crate::protocol::channel::{ChannelId, ChannelKind};
 use crate::protocol::registry::NetId;
 
 const BYPASS_QUOTA_PRIORITY: f32 = 100000.0;
@@ -65,6 +67,12 @@ pub(crate) struct PriorityManager {
     pub(crate) config: PriorityConfig,
     // TODO: can I do without this limiter?
     pub(crate) limiter: DefaultDirectRateLimiter,
+    /// Clock of the rate limiter, also used to replenish the channel budgets
+    clock: DefaultClock,
+    /// Share of the bandwidth quota reserved for some channels (between 0.0 and 1.0)
+    channel_budget_shares: HashMap<ChannelKind, f32>,
+    /// Bandwidth budgets of the channels that have a share of the bandwidth quota
+    channel_budgets: HashMap<ChannelKind, ChannelBudget>,
     // // Internal buffer of data that we want to send
     // // Reuse allocation across frames
     // data_to_send: BTreeMap<ChannelId, (VecDeque<SendMessage>, VecDeque<SendMessage>)>,
@@ -72,16 +80,106 @@ pub(crate) struct PriorityManager {
     // buffered_data: Vec<BufferedMessage>,
     /// List of senders to notify when a replication update message is actually sent (included in packet)
     replication_update_senders: Vec<Sender<MessageId>>,
+    /// Temporary bandwidth quota that replaces the configured quota
+    boost: Option<BandwidthBoost>,
+}
+
+/// A temporary change of the bandwidth quota, that is reverted when it expires
+#[derive(Debug)]
+struct BandwidthBoost {
+    /// The temporary quota. If None, the bandwidth is not limited during the boost
+    quota: Option<Quota>,
+    /// How long the boost is still active. If None, the boost is active until it is ended manually
+    remaining: Option<Duration>,
 }
 
 impl PriorityManager {
-    pub(crate) fn new(config: PriorityConfig) -> Self {
-        Self {
+    pub(crate) fn new(config: PriorityConfig, channel_registry: &ChannelRegistry) -> Self {
+        let mut manager = Self {
             config: config.clone(),
             limiter: DefaultDirectRateLimiter::direct(config.bandwidth_quota),
+            clock: DefaultClock::default(),
+            channel_budget_shares: channel_registry
+                .budget_shares()
+                .iter()
+                .map(|(kind, share)| (*kind, *share))
+                .collect(),
+            channel_budgets: HashMap::default(),
             // data_to_send: BTreeMap::new(),
             // buffered_data: Vec::new(),
             replication_update_senders: Vec::new(),
+            boost: None,
+        };
+        manager.set_limiters(config.bandwidth_quota);
+        manager
+    }
+
+    /// Reset the global rate limiter and the per-channel budgets to use the given quota
+    fn set_limiters(&mut self, quota: Quota) {
+        self.limiter = DefaultDirectRateLimiter::direct_with_clock(quota, &self.clock);
+        let now = self.clock.now();
+        self.channel_budgets = self
+            .channel_budget_shares
+            .iter()
+            .map(|(kind, share)| (*kind, ChannelBudget::new(quota, *share, now)))
+            .collect();
+    }
+
+    /// Change the bandwidth quota. If a boost is active, the new quota is used once the boost ends.
+    pub(crate) fn set_bandwidth_quota(&mut self, quota: Quota) {
+        self.config.bandwidth_quota = quota;
+        if self.boost.is_none() {
+            self.set_limiters(quota);
+        }
+    }
+
+    /// Temporarily replace the bandwidth quota (or remove the bandwidth cap if `quota` is None).
+    ///
+    /// The configured quota is restored after `duration`, or when [`end_bandwidth_boost`](Self::end_bandwidth_boost)
+    /// is called. This has no effect if the bandwidth cap is disabled.
+    pub(crate) fn boost_bandwidth(&mut self, quota: Option<Quota>, duration: Option<Duration>) {
+        if let Some(quota) = quota {
+            self.set_limiters(quota);
+        }
+        self.boost = Some(BandwidthBoost {
+            quota,
+            remaining: duration,
+        });
+    }
+
+    /// End the current bandwidth boost, and restore the configured quota
+    ///
+    /// The restored limiters start empty, so that the bytes sent during the boost are not
+    /// immediately followed by a full burst of the configured quota.
+    pub(crate) fn end_bandwidth_boost(&mut self) {
+        if self.boost.take().is_some() {
+            self.set_limiters(self.config.bandwidth_quota);
+            let _ = self
+                .limiter
+                .check_n(self.config.bandwidth_quota.burst_size());
+            for budget in self.channel_budgets.values_mut() {
+                budget.available = 0.0;
+            }
+        }
+    }
+
+    /// Returns true if a bandwidth boost is currently active
+    pub(crate) fn is_bandwidth_boosted(&self) -> bool {
+        self.boost.is_some()
+    }
+
+    /// Advance the timer of the bandwidth boost (ending it if it expired)
+    pub(crate) fn update(&mut self, delta: Duration) {
+        let Some(remaining) = self
+            .boost
+            .as_mut()
+            .and_then(|boost| boost.remaining.as_mut())
+        else {
+            return;
+        };
+        *remaining = remaining.saturating_sub(delta);
+        if remaining.is_zero() {
+            self.end_bandwidth_boost();
         }
     }
 
@@ -93,6 +191,30 @@ impl PriorityManager {
         receiver
     }
 
+    /// Returns true if the message fits in the bandwidth quota
+    fn check_quota(&self, message_bytes: u32, priority: f32) -> bool {
+        let nonzero_message_bytes = NonZeroU32::try_from(message_bytes).unwrap();
+        let Ok(result) = self.limiter.check_n(nonzero_message_bytes) else {
+            error!("the bandwidth does not have enough capacity for a message of this size!");
+            return false;
+        };
+        // above BYPASS_QUOTA_PRIORITY, we still send the message
+        priority >= BYPASS_QUOTA_PRIORITY || result.is_ok()
+    }
+
+    /// Returns the kind of the channel if it has a share of the bandwidth quota.
+    /// Channels without a share are not limited.
+    fn budgeted_channel(
+        &self,
+        channel_net_id: ChannelId,
+        channel_registry: &ChannelRegistry,
+    ) -> Option<ChannelKind> {
+        channel_registry
+            .get_kind_from_net_id(channel_net_id)
+            .filter(|kind| self.channel_budgets.contains_key(*kind))
+            .copied()
+    }
+
     // TODO: maybe accumulate the used_bytes in the priority_manager instead of returning here?
     /// Filter the messages by priority and bandwidth quota
     /// Returns the list of messages that we can send, along with the amount of bytes we used
@@ -182,23 +304,70 @@ impl PriorityManager {
         let mut single_data: HashMap<ChannelId, VecDeque<SingleData>> = HashMap::new();
         let mut fragment_data: HashMap<ChannelId, VecDeque<FragmentData>> = HashMap::new();
         let mut bytes_used = 0;
+        // during a boost without quota, all the messages are sent
+        let unlimited = self
+            .boost
+            .as_ref()
+            .is_some_and(|boost| boost.quota.is_none());
+        let mut accepted_messages = vec![];
+        // messages that exceeded the bandwidth share of their channel
+        let mut deferred_messages = vec![];
+        let mut quota_reached = false;
+        // the budgets are replenished with the clock of the global rate limiter, so that both stay consistent
+        let now = self.clock.now();
+        for budget in self.channel_budgets.values_mut() {
+            budget.replenish(now);
+        }
         while let Some(buffered_message) = all_messages.pop() {
             // we don't use the exact size of the message, but the size of the bytes
             // we will adjust for this later
             let message_bytes = buffered_message.data.len() as u32;
-            let nonzero_message_bytes = NonZeroU32::try_from(message_bytes).unwrap();
-            let Ok(result) = self.limiter.check_n(nonzero_message_bytes) else {
-                error!("the bandwidth does not have enough capacity for a message of this size!");
-                break;
-            };
-
-            // above BYPASS_QUOTA_PRIORITY, we still send the message
-            if buffered_message.priority < BYPASS_QUOTA_PRIORITY {
-                let Ok(()) = result else {
+            if !unlimited {
+                let channel_kind = (buffered_message.priority < BYPASS_QUOTA_PRIORITY)
+                    .then(|| {
+                        self.budgeted_channel(buffered_message.channel_net_id, channel_registry)
+                    })
+                    .flatten();
+                if channel_kind
+                    .is_some_and(|kind| !self.channel_budgets[&kind].has_capacity(message_bytes))
+                {
+                    deferred_messages.push(buffered_message);
+                    continue;
+                }
+                if !self.check_quota(message_bytes, buffered_message.priority) {
                     debug!("Bandwidth quota reached, no more messages can be sent this tick");
+                    quota_reached = true;
                     break;
-                };
+                }
+                // only consume the share of the channel once the message also fits in the global quota
+                if let Some(kind) = channel_kind {
+                    self.channel_budgets
+                        .get_mut(&kind)
+                        .unwrap()
+                        .consume(message_bytes);
+                }
             }
+            accepted_messages.push(buffered_message);
+        }
+        // the bandwidth left unused by the other channels can be used by the channels that
+        // exceeded their share (the deferred messages are already sorted by priority)
+        let mut deferred_messages = deferred_messages.into_iter();
+        if !quota_reached {
+            for buffered_message in deferred_messages.by_ref() {
+                if !self.check_quota(
+                    buffered_message.data.len() as u32,
+                    buffered_message.priority,
+                ) {
+                    debug!("Bandwidth quota reached, no more messages can be sent this tick");
+                    break;
+                }
+                accepted_messages.push(buffered_message);
+            }
+        }
+        let num_messages_discarded = all_messages.len() + deferred_messages.len();
+
+        for buffered_message in accepted_messages {
+            let message_bytes = buffered_message.data.len() as u32;
             trace!(channel=?buffered_message.channel_net_id, "Sending message with priority {:?}", buffered_message.priority);
 
             // keep track of the bytes we added to the rate limiter
@@ -248,7 +417,7 @@ impl PriorityManager {
         debug!(
             bytes_sent = ?bytes_used,
             ?num_messages_sent,
-            num_messages_discarded = ?all_messages.len(),
+            ?num_messages_discarded,
             "priority filter done.");
 
         (
@@ -258,3 +427,256 @@ impl PriorityManager {
         )
     }
 }
+
+/// Number of bytes that a channel with a share of the bandwidth quota can still send.
+///
+/// Unlike the global rate limiter, the budget can be checked without being consumed, so that it is only
+/// consumed for the messages that also fit in the global quota.
+#[derive(Debug)]
+struct ChannelBudget {
+    /// Maximum number of bytes that the budget can hold
+    capacity: f32,
+    /// Number of bytes added to the budget every second
+    bytes_per_second: f32,
+    /// Number of bytes currently available
+    available: f32,
+    /// Last time the budget was replenished, according to the clock of the rate limiter
+    last_replenished: <DefaultClock as Clock>::Instant,
+}
+
+impl ChannelBudget {
+    /// Create the budget of a channel that has a `share` of the total bandwidth `quota`
+    fn new(quota: Quota, share: f32, now: <DefaultClock as Clock>::Instant) -> Self {
+        let share = share.clamp(0.0, 1.0);
+        let capacity = quota.burst_size().get() as f32 * share;
+        Self {
+            capacity,
+            bytes_per_second: share / quota.replenish_interval().as_secs_f32(),
+            available: capacity,
+            last_replenished: now,
+        }
+    }
+
+    fn replenish(&mut self, now: <DefaultClock as Clock>::Instant) {
+        let elapsed = Duration::from(now.duration_since(self.last_replenished));
+        self.last_replenished = now;
+        self.available =
+            (self.available + self.bytes_per_second * elapsed.as_secs_f32()).min(self.capacity);
+    }
+
+    fn has_capacity(&self, bytes: u32) -> bool {
+        self.available >= bytes as f32
+    }
+
+    fn consume(&mut self, bytes: u32) {
+        self.available -= bytes as f32;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::default;
+    use lightyear_macros::ChannelInternal;
+
+    use crate::packet::message::SingleData;
+    use crate::prelude::{ChannelMode, ChannelSettings};
+    use crate::serialize::ToBytes;
+
+    use super::*;
+
+    #[derive(ChannelInternal)]
+    struct ReplicationChannel;
+
+    #[derive(ChannelInternal)]
+    struct ChatChannel;
+
+    #[derive(ChannelInternal)]
+    struct VoiceChannel;
+
+    /// Check that under a tight bandwidth cap, each channel gets approximately its share of the
+    /// bandwidth, even if one of the channels has a much higher priority
+    #[test]
+    fn test_channel_budget_shares() {
+        let mut channel_registry = ChannelRegistry::default();
+        let settings = |priority| ChannelSettings {
+            mode: ChannelMode::UnorderedUnreliable,
+            priority,
+            ..default()
+        };
+        channel_registry.add_channel::<ReplicationChannel>(settings(1.0));
+        // the chat channel would starve the other channels without budget shares
+        channel_registry.add_channel::<ChatChannel>(settings(10.0));
+        channel_registry.add_channel::<VoiceChannel>(settings(1.0));
+        channel_registry.set_budget_share::<ReplicationChannel>(0.7);
+        channel_registry.set_budget_share::<ChatChannel>(0.1);
+        channel_registry.set_budget_share::<VoiceChannel>(0.2);
+
+        let shares = [
+            (ChannelKind::of::<ReplicationChannel>(), 0.7),
+            (ChannelKind::of::<ChatChannel>(), 0.1),
+            (ChannelKind::of::<VoiceChannel>(), 0.2),
+        ];
+        let mut manager = PriorityManager::new(
+            PriorityConfig {
+                bandwidth_quota: Quota::per_second(nonzero!(10000u32)),
+                enabled: true,
+            },
+            &channel_registry,
+        );
+
+        // every channel wants to send much more than the bandwidth cap
+        let data = shares
+            .iter()
+            .map(|(kind, _)| {
+                let net_id = *channel_registry.get_net_from_kind(kind).unwrap();
+                let messages = (0..200)
+                    .map(|_| SendMessage {
+                        data: MessageData::Single(SingleData::new(None, vec![0; 100].into())),
+                        priority: 1.0,
+                    })
+                    .collect();
+                (net_id, (messages, VecDeque::new()))
+            })
+            .collect();
+        let (single_data, _, bytes_used) =
+            manager.priority_filter(data, &channel_registry, Tick(0));
+
+        for (kind, share) in shares {
+            let net_id = *channel_registry.get_net_from_kind(&kind).unwrap();
+            let channel_bytes: usize = single_data
+                .iter()
+                .filter(|(id, _)| *id == net_id)
+                .flat_map(|(_, messages)| messages.iter().map(|m| m.len()))
+                .sum();
+            let actual_share = channel_bytes as f32 / bytes_used as f32;
+            assert!(
+                (actual_share - share).abs() < 0.03,
+                "channel got {actual_share} of the bandwidth instead of {share}"
+            );
+        }
+    }
+
+    /// Check that the share of a channel is not consumed by a message that is dropped
+    /// because the global bandwidth quota is reached
+    #[test]
+    fn test_channel_budget_not_consumed_when_quota_reached() {
+        let mut channel_registry = ChannelRegistry::default();
+        let settings = |priority| ChannelSettings {
+            mode: ChannelMode::UnorderedUnreliable,
+            priority,
+            ..default()
+        };
+        channel_registry.add_channel::<ReplicationChannel>(settings(1.0));
+        channel_registry.add_channel::<ChatChannel>(settings(10.0));
+        channel_registry.set_budget_share::<ChatChannel>(0.5);
+        let mut manager = PriorityManager::new(
+            PriorityConfig {
+                bandwidth_quota: Quota::per_minute(nonzero!(1000u32)),
+                enabled: true,
+            },
+            &channel_registry,
+        );
+        let messages = |channel_kind, num_messages| {
+            let net_id = *channel_registry.get_net_from_kind(&channel_kind).unwrap();
+            let messages = (0..num_messages)
+                .map(|_| SendMessage {
+                    data: MessageData::Single(SingleData::new(None, vec![0; 100].into())),
+                    priority: 1.0,
+                })
+                .collect();
+            vec![(net_id, (messages, VecDeque::new()))]
+        };
+
+        // use up the global quota
+        manager.priority_filter(
+            messages(ChannelKind::of::<ReplicationChannel>(), 10),
+            &channel_registry,
+            Tick(0),
+        );
+        // the chat message fits in the share of the channel, but not in the global quota
+        let (single_data, _, bytes_used) = manager.priority_filter(
+            messages(ChannelKind::of::<ChatChannel>(), 1),
+            &channel_registry,
+            Tick(0),
+        );
+        assert!(single_data.is_empty());
+        assert_eq!(bytes_used, 0);
+        assert_eq!(
+            manager.channel_budgets[&ChannelKind::of::<ChatChannel>()].available,
+            500.0
+        );
+    }
+
+    /// Check that the configured quota is not available as a full burst right after a bandwidth boost ends
+    #[test]
+    fn test_end_bandwidth_boost_starts_empty() {
+        let mut channel_registry = ChannelRegistry::default();
+        channel_registry.add_channel::<ChatChannel>(ChannelSettings {
+            mode: ChannelMode::UnorderedUnreliable,
+            ..default()
+        });
+        channel_registry.set_budget_share::<ChatChannel>(0.5);
+        let mut manager = PriorityManager::new(
+            PriorityConfig {
+                bandwidth_quota: Quota::per_minute(nonzero!(1000u32)),
+                enabled: true,
+            },
+            &channel_registry,
+        );
+        assert!(manager.check_quota(100, 1.0));
+
+        manager.boost_bandwidth(None, None);
+        manager.end_bandwidth_boost();
+        assert!(!manager.is_bandwidth_boosted());
+        assert!(!manager.check_quota(100, 1.0));
+        assert_eq!(
+            manager.channel_budgets[&ChannelKind::of::<ChatChannel>()].available,
+            0.0
+        );
+    }
+
+    /// Check that the channel budgets are replenished with the clock of the global rate limiter,
+    /// not with the frame delta
+    #[test]
+    fn test_channel_budget_uses_limiter_clock() {
+        let mut channel_registry = ChannelRegistry::default();
+        channel_registry.add_channel::<ChatChannel>(ChannelSettings {
+            mode: ChannelMode::UnorderedUnreliable,
+            ..default()
+        });
+        channel_registry.set_budget_share::<ChatChannel>(0.5);
+        let mut manager = PriorityManager::new(
+            PriorityConfig {
+                bandwidth_quota: Quota::per_minute(nonzero!(1000u32)),
+                enabled: true,
+            },
+            &channel_registry,
+        );
+        let net_id = *channel_registry
+            .get_net_from_kind(&ChannelKind::of::<ChatChannel>())
+            .unwrap();
+        let messages = || {
+            let messages = (0..5)
+                .map(|_| SendMessage {
+                    data: MessageData::Single(SingleData::new(None, vec![0; 100].into())),
+                    priority: 1.0,
+                })
+                .collect();
+            vec![(net_id, (messages, VecDeque::new()))]
+        };
+
+        // the chat channel uses up its share, and the rest of the global quota
+        manager.priority_filter(messages(), &channel_registry, Tick(0));
+        manager.priority_filter(messages(), &channel_registry, Tick(0));
+        assert!(manager.channel_budgets[&ChannelKind::of::<ChatChannel>()].available < 100.0);
+
+        // a large frame delta does not replenish the share of the channel, since the global
+        // rate limiter is not replenished either
+        manager.update(Duration::from_secs(60));
+        let (single_data, _, bytes_used) =
+            manager.priority_filter(messages(), &channel_registry, Tick(0));
+        assert!(single_data.is_empty());
+        assert_eq!(bytes_used, 0);
+        assert!(manager.channel_budgets[&ChannelKind::of::<ChatChannel>()].available < 100.0);
+    }
+}
@@ -2,15 +2,18 @@ use bevy::app::App;
 use bevy::prelude::{Resource, TypePath};
 use bevy::utils::Duration;
 use std::any::TypeId;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use tracing::warn;
 
 use crate::channel::builder::{
-    AuthorityChannel, Channel, ChannelBuilder, ChannelSettings, PongChannel,
+    AuthorityChannel, Channel, ChannelBuilder, ChannelSettings, DefaultOrderedReliableChannel,
+    InputRecoveryChannel, NotificationChannel, PongChannel, SnapshotChannel,
 };
 use crate::channel::builder::{
     ChannelContainer, EntityActionsChannel, EntityUpdatesChannel, InputChannel, PingChannel,
 };
-use crate::prelude::{ChannelMode, ReliableSettings};
+use crate::prelude::{ChannelDirection, ChannelMode, ReliableSettings};
+use crate::protocol::message::MessageError;
 use crate::protocol::registry::{NetId, TypeKind, TypeMapper};
 
 // TODO: derive Reflect once we reach bevy 0.14
@@ -20,6 +23,9 @@ pub struct ChannelKind(TypeId);
 
 pub type ChannelId = NetId;
 
+/// Tolerance used when checking that the bandwidth shares of the channels don't add up to more than 1.0
+const BUDGET_SHARE_TOLERANCE: f32 = 1e-4;
+
 impl ChannelKind {
     pub fn of<C: Channel>() -> Self {
         Self(TypeId::of::<C>())
@@ -57,13 +63,28 @@ impl From<TypeId> for ChannelKind {
 /// # }
 /// ```
 ///
+/// Plugins that build on top of lightyear can add their own channels in the same way, even if they
+/// are added to the [`App`] before the lightyear plugins: lightyear's default channels will be added to the same registry.
+/// Registering a channel that is already registered is ignored; use [`try_add_channel`](AppChannelExt::try_add_channel)
+/// to get an error instead.
+///
+/// ### Bandwidth shares
 ///
+/// When the bandwidth cap is enabled, a share of the bandwidth quota can be reserved for a channel with
+/// [`set_channel_budget_share`](AppChannelExt::set_channel_budget_share), so that a chatty channel cannot
+/// starve the other channels.
 #[derive(Resource, Default, Clone, Debug, PartialEq, TypePath)]
 pub struct ChannelRegistry {
     // we only store the ChannelBuilder because we might want to create multiple instances of the same channel
     pub(in crate::protocol) builder_map: HashMap<ChannelKind, ChannelBuilder>,
     pub(in crate::protocol) kind_map: TypeMapper<ChannelKind>,
     pub(in crate::protocol) name_map: HashMap<ChannelKind, String>,
+    /// Channels registered by lightyear or by its plugins to send their own messages.
+    /// They are not taken into account when checking that the user's channels can send
+    /// every message (see [`MessageRegistry::check`](crate::protocol::message::MessageRegistry::check))
+    internal_channels: HashSet<ChannelKind>,
+    /// Share of the bandwidth quota reserved for some channels (between 0.0 and 1.0)
+    budget_shares: HashMap<ChannelKind, f32>,
     built: bool,
 }
 
@@ -73,10 +94,27 @@ impl ChannelRegistry {
             builder_map: HashMap::new(),
             kind_map: TypeMapper::new(),
             name_map: HashMap::new(),
+            internal_channels: HashSet::new(),
+            budget_shares: HashMap::new(),
             built: false,
         };
-        registry.add_channel::<EntityUpdatesChannel>(ChannelSettings {
+        registry.add_default_channels(input_send_interval);
+        registry
+    }
+
+    /// Add the channels used internally by lightyear
+    ///
+    /// The default channels always get the first net ids, even if other channels were registered before them
+    /// (for example by a plugin added before the lightyear plugins), so that their net ids don't depend on
+    /// the order in which the plugins are added.
+    ///
+    /// Panics if one of these channels was already registered.
+    pub(crate) fn add_default_channels(&mut self, input_send_interval: Duration) {
+        // the channels that were already registered get their net ids again after the default channels
+        let previous_kind_map = std::mem::take(&mut self.kind_map);
+        self.add_default_channel::<EntityUpdatesChannel>(ChannelSettings {
             mode: ChannelMode::UnorderedUnreliableWithAcks,
+            direction: ChannelDirection::Bidirectional,
             // we do not send the send_frequency to `replication_interval` here
             // because we want to make sure that the entity updates for tick T
             // are sent on tick T, so we will set the `replication_interval`
@@ -84,8 +122,9 @@ impl ChannelRegistry {
             send_frequency: Duration::default(),
             priority: 1.0,
         });
-        registry.add_channel::<EntityActionsChannel>(ChannelSettings {
+        self.add_default_channel::<EntityActionsChannel>(ChannelSettings {
             mode: ChannelMode::UnorderedReliable(ReliableSettings::default()),
+            direction: ChannelDirection::Bidirectional,
             // we do not send the send_frequency to `replication_interval` here
             // because we want to make sure that the entity updates for tick T
             // are sent on tick T, so we will set the `replication_interval`
@@ -94,31 +133,79 @@ impl ChannelRegistry {
             // we want to send the entity actions as soon as possible
             priority: 10.0,
         });
-        registry.add_channel::<PingChannel>(ChannelSettings {
+        self.add_default_channel::<SnapshotChannel>(ChannelSettings {
+            mode: ChannelMode::UnorderedReliable(ReliableSettings::default()),
+            direction: ChannelDirection::Bidirectional,
+            send_frequency: Duration::default(),
+            // the snapshot contains entity actions, so it has the same priority
+            priority: 10.0,
+        });
+        self.add_default_channel::<PingChannel>(ChannelSettings {
             mode: ChannelMode::SequencedUnreliable,
+            direction: ChannelDirection::Bidirectional,
             send_frequency: Duration::default(),
             // we always want to include the ping in the packet
             priority: f32::INFINITY,
         });
-        registry.add_channel::<PongChannel>(ChannelSettings {
+        self.add_default_channel::<PongChannel>(ChannelSettings {
             mode: ChannelMode::SequencedUnreliable,
+            direction: ChannelDirection::Bidirectional,
             send_frequency: Duration::default(),
             // we always want to include the pong in the packet
             priority: f32::INFINITY,
         });
-        registry.add_channel::<InputChannel>(ChannelSettings {
+        self.add_default_channel::<InputChannel>(ChannelSettings {
             mode: ChannelMode::UnorderedUnreliable,
+            direction: ChannelDirection::Bidirectional,
             send_frequency: input_send_interval,
             // we always want to include the inputs in the packet
             priority: f32::INFINITY,
         });
-        registry.add_channel::<AuthorityChannel>(ChannelSettings {
+        self.add_default_channel::<InputRecoveryChannel>(ChannelSettings {
+            mode: ChannelMode::UnorderedReliable(ReliableSettings::default()),
+            direction: ChannelDirection::Bidirectional,
+            send_frequency: Duration::default(),
+            // the recovered inputs are only useful if they arrive before the server reaches their tick
+            priority: f32::INFINITY,
+        });
+        self.add_default_channel::<AuthorityChannel>(ChannelSettings {
             mode: ChannelMode::OrderedReliable(ReliableSettings::default()),
+            direction: ChannelDirection::Bidirectional,
             send_frequency: Duration::default(),
             // we want to send the authority transfers as soon as possible
             priority: 10.0,
         });
-        registry
+        self.add_default_channel::<NotificationChannel>(ChannelSettings {
+            mode: ChannelMode::OrderedReliable(ReliableSettings::default()),
+            direction: ChannelDirection::ServerToClient,
+            send_frequency: Duration::default(),
+            priority: 1.0,
+        });
+        self.add_default_channel::<DefaultOrderedReliableChannel>(ChannelSettings {
+            mode: ChannelMode::OrderedReliable(ReliableSettings::default()),
+            direction: ChannelDirection::Bidirectional,
+            send_frequency: Duration::default(),
+            priority: 1.0,
+        });
+        self.internal_channels.extend([
+            ChannelKind::of::<EntityUpdatesChannel>(),
+            ChannelKind::of::<EntityActionsChannel>(),
+            ChannelKind::of::<SnapshotChannel>(),
+            ChannelKind::of::<PingChannel>(),
+            ChannelKind::of::<PongChannel>(),
+            ChannelKind::of::<InputChannel>(),
+            ChannelKind::of::<InputRecoveryChannel>(),
+            ChannelKind::of::<AuthorityChannel>(),
+            ChannelKind::of::<NotificationChannel>(),
+            // the general-purpose channel is bidirectional, so it would hide any misconfigured user channel
+            ChannelKind::of::<DefaultOrderedReliableChannel>(),
+        ]);
+        // keep the relative registration order of the other channels
+        (0..previous_kind_map.next_net_id)
+            .filter_map(|net_id| previous_kind_map.kind(net_id))
+            .for_each(|kind| {
+                self.kind_map.add_kind(*kind);
+            });
     }
 
     /// Returns true if the net_id corresponds to a channel that is used for replication
@@ -136,6 +223,84 @@ impl ChannelRegistry {
         })
     }
 
+    /// Mark a channel as internal: it is only used to send the messages of lightyear or of one of its plugins
+    pub(crate) fn set_internal_channel<C: Channel>(&mut self) {
+        self.internal_channels.insert(ChannelKind::of::<C>());
+    }
+
+    /// Returns true if the channel is registered by default by lightyear (see [`Self::add_default_channels`])
+    /// or by one of its plugins, to send their own messages
+    pub(crate) fn is_internal_channel(&self, kind: &ChannelKind) -> bool {
+        self.internal_channels.contains(kind)
+    }
+
+    /// Check that the [`ChannelDirection`] of the channel allows sending messages from this peer
+    pub(crate) fn check_send_direction(
+        &self,
+        kind: &ChannelKind,
+        is_server: bool,
+    ) -> Result<(), MessageError> {
+        let builder = self
+            .get_builder_from_kind(kind)
+            .ok_or(MessageError::NotRegistered)?;
+        let direction = builder.settings.direction;
+        let allowed = if is_server {
+            direction.server_can_send()
+        } else {
+            direction.client_can_send()
+        };
+        if allowed {
+            return Ok(());
+        }
+        Err(MessageError::WrongChannelDirection {
+            channel: self.name(kind).unwrap_or_default().to_string(),
+            direction,
+            sender: if is_server { "server" } else { "client" },
+        })
+    }
+
+    /// Reserve a share (between 0.0 and 1.0) of the bandwidth quota for the channel `C`.
+    ///
+    /// Panics if the share is invalid, or if the shares of all the channels add up to more than 1.0.
+    pub fn set_budget_share<C: Channel>(&mut self, share: f32) {
+        if let Err(e) = self.try_set_budget_share::<C>(share) {
+            panic!("{e}");
+        }
+    }
+
+    /// Reserve a share (between 0.0 and 1.0) of the bandwidth quota for the channel `C`, or return an
+    /// error if the share is invalid or if the shares of all the channels add up to more than 1.0.
+    ///
+    /// When the bandwidth cap is reached, a channel with a share can only use its share of the
+    /// bandwidth. Any bandwidth left unused by the other channels can still be used.
+    pub fn try_set_budget_share<C: Channel>(&mut self, share: f32) -> Result<(), ChannelError> {
+        let kind = ChannelKind::of::<C>();
+        if !(0.0..=1.0).contains(&share) {
+            return Err(ChannelError::InvalidBudgetShare {
+                channel: C::name().to_string(),
+                share,
+            });
+        }
+        let total = share
+            + self
+                .budget_shares
+                .iter()
+                .filter(|(k, _)| **k != kind)
+                .map(|(_, share)| share)
+                .sum::<f32>();
+        // allow for some floating-point rounding errors
+        if total > 1.0 + BUDGET_SHARE_TOLERANCE {
+            return Err(ChannelError::BudgetSharesExceeded(total));
+        }
+        self.budget_shares.insert(kind, share);
+        Ok(())
+    }
+
+    /// Shares of the bandwidth quota reserved for each channel
+    pub(crate) fn budget_shares(&self) -> &HashMap<ChannelKind, f32> {
+        &self.budget_shares
+    }
+
     /// Build all the channels in the registry
     pub fn channels(&self) -> HashMap<ChannelKind, ChannelContainer> {
         let mut channels = HashMap::new();
@@ -150,11 +315,41 @@ impl ChannelRegistry {
     }
 
     /// Register a new type
+    ///
+    /// If the channel is already registered (which includes lightyear's default channels such as the
+    /// [`InputChannel`]), the existing registration is kept and a warning is logged.
+    /// Use [`try_add_channel`](Self::try_add_channel) to get an error instead.
     pub fn add_channel<C: Channel>(&mut self, settings: ChannelSettings) {
+        if let Err(e) = self.try_add_channel::<C>(settings) {
+            warn!("{e}, ignoring the new settings");
+        }
+    }
+
+    /// Register one of the default channels, which must not be registered yet
+    fn add_default_channel<C: Channel>(&mut self, settings: ChannelSettings) {
+        if let Err(e) = self.try_add_channel::<C>(settings) {
+            panic!("{e}");
+        }
+    }
+
+    /// Register a new type, or return an error if the channel is already registered
+    pub fn try_add_channel<C: Channel>(
+        &mut self,
+        settings: ChannelSettings,
+    ) -> Result<ChannelKind, ChannelError> {
+        if self.is_registered::<C>() {
+            return Err(ChannelError::AlreadyRegistered(C::name().to_string()));
+        }
         let kind = self.kind_map.add::<C>();
         self.builder_map.insert(kind, C::get_builder(settings));
         let name = C::name();
         self.name_map.insert(kind, name.to_string());
+        Ok(kind)
+    }
+
+    /// Returns true if the channel is already registered
+    pub fn is_registered<C: Channel>(&self) -> bool {
+        self.builder_map.contains_key(&ChannelKind::of::<C>())
     }
 
     /// get the registered object for a given type
@@ -185,24 +380,77 @@ impl ChannelRegistry {
     }
 }
 
-/// Add a message to the list of messages that can be sent
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum ChannelError {
+    #[error("channel {0} is already registered in the protocol")]
+    AlreadyRegistered(String),
+    #[error("the bandwidth share of channel {channel} must be between 0.0 and 1.0, got {share}")]
+    InvalidBudgetShare { channel: String, share: f32 },
+    #[error("the bandwidth shares of the channels add up to {0}, which is more than 1.0")]
+    BudgetSharesExceeded(f32),
+}
+
+/// Add a channel to the list of channels that can be used to send messages
+///
+/// Channels can be added before or after the lightyear plugins are added to the [`App`], which
+/// lets third-party plugins (for example a voice chat plugin) contribute their own channels to the
+/// protocol alongside lightyear's default channels.
+/// Lightyear's default channels always have the same network ids; the other channels are identified on the
+/// network by their registration order, so the client and the server must add the same channels (and plugins)
+/// in the same order.
 pub trait AppChannelExt {
+    /// Register a new channel.
+    ///
+    /// If the channel is already registered (which includes lightyear's default channels), the existing
+    /// registration is kept and a warning is logged.
+    /// Use [`try_add_channel`](AppChannelExt::try_add_channel) to get an error instead.
     fn add_channel<C: Channel>(&mut self, settings: ChannelSettings);
+
+    /// Register a new channel, or return an error if the channel is already registered.
+    fn try_add_channel<C: Channel>(
+        &mut self,
+        settings: ChannelSettings,
+    ) -> Result<ChannelKind, ChannelError>;
+
+    /// Reserve a share (between 0.0 and 1.0) of the bandwidth quota for the channel `C`.
+    ///
+    /// Panics if the share is invalid, or if the shares of all the channels add up to more than 1.0.
+    fn set_channel_budget_share<C: Channel>(&mut self, share: f32);
 }
 
 impl AppChannelExt for App {
     fn add_channel<C: Channel>(&mut self, settings: ChannelSettings) {
-        let mut registry = self.world_mut().resource_mut::<ChannelRegistry>();
-        registry.add_channel::<C>(settings);
+        self.world_mut()
+            .get_resource_or_insert_with(ChannelRegistry::default)
+            .add_channel::<C>(settings);
+    }
+
+    fn try_add_channel<C: Channel>(
+        &mut self,
+        settings: ChannelSettings,
+    ) -> Result<ChannelKind, ChannelError> {
+        let mut registry = self
+            .world_mut()
+            .get_resource_or_insert_with(ChannelRegistry::default);
+        registry.try_add_channel::<C>(settings)
+    }
+
+    fn set_channel_budget_share<C: Channel>(&mut self, share: f32) {
+        let mut registry = self
+            .world_mut()
+            .get_resource_or_insert_with(ChannelRegistry::default);
+        registry.set_budget_share::<C>(share);
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use bevy::prelude::{default, TypePath};
+    use bevy::prelude::{default, Plugin, TypePath};
     use lightyear_macros::ChannelInternal;
 
     use crate::channel::builder::{ChannelMode, ChannelSettings};
+    use crate::prelude::SharedConfig;
+    use crate::shared::plugin::SharedPlugin;
 
     use super::*;
 
@@ -227,4 +475,92 @@ mod tests {
             ChannelMode::UnorderedUnreliable
         );
     }
+
+    #[derive(ChannelInternal, TypePath)]
+    pub struct VoiceChannel;
+
+    struct VoicePlugin;
+
+    impl Plugin for VoicePlugin {
+        fn build(&self, app: &mut App) {
+            app.add_channel::<VoiceChannel>(ChannelSettings {
+                mode: ChannelMode::SequencedUnreliable,
+                ..default()
+            });
+        }
+    }
+
+    /// Check that a plugin added before the lightyear plugins can contribute its own channels
+    /// to the protocol, alongside the default channels
+    #[test]
+    fn test_plugin_channel() {
+        let mut app = App::new();
+        app.add_plugins(VoicePlugin);
+        app.add_plugins(SharedPlugin {
+            config: SharedConfig::default(),
+        });
+        let registry = app.world().resource::<ChannelRegistry>();
+        assert!(registry.is_registered::<VoiceChannel>());
+        // the default channels keep the same net ids, and the plugin channel is registered after them
+        let default_registry = ChannelRegistry::new(Duration::default());
+        assert_eq!(
+            registry.get_net_from_kind(&ChannelKind::of::<EntityUpdatesChannel>()),
+            default_registry.get_net_from_kind(&ChannelKind::of::<EntityUpdatesChannel>())
+        );
+        assert_eq!(
+            registry.get_net_from_kind(&ChannelKind::of::<DefaultOrderedReliableChannel>()),
+            default_registry.get_net_from_kind(&ChannelKind::of::<DefaultOrderedReliableChannel>())
+        );
+        assert_eq!(
+            registry.get_net_from_kind(&ChannelKind::of::<VoiceChannel>()),
+            Some(&(default_registry.len() as ChannelId))
+        );
+        assert!(registry.is_registered::<EntityUpdatesChannel>());
+        assert!(registry.is_registered::<InputChannel>());
+        assert!(registry.is_registered::<DefaultOrderedReliableChannel>());
+        assert_eq!(
+            registry
+                .get_builder_from_kind(&ChannelKind::of::<VoiceChannel>())
+                .unwrap()
+                .settings
+                .mode,
+            ChannelMode::SequencedUnreliable
+        );
+
+        // channels cannot be registered twice
+        assert_eq!(
+            app.try_add_channel::<InputChannel>(ChannelSettings::default()),
+            Err(ChannelError::AlreadyRegistered(
+                InputChannel::name().to_string()
+            ))
+        );
+        // add_channel keeps the existing registration
+        let registry = app.world().resource::<ChannelRegistry>().clone();
+        app.add_channel::<VoiceChannel>(ChannelSettings::default());
+        app.add_channel::<InputChannel>(ChannelSettings::default());
+        assert_eq!(app.world().resource::<ChannelRegistry>(), &registry);
+    }
+
+    /// Check that the bandwidth shares of the channels are validated
+    #[test]
+    fn test_budget_shares() {
+        let mut registry = ChannelRegistry::default();
+        registry.set_budget_share::<MyChannel>(0.7);
+        registry.set_budget_share::<VoiceChannel>(0.3);
+        assert_eq!(
+            registry.try_set_budget_share::<InputChannel>(1.5),
+            Err(ChannelError::InvalidBudgetShare {
+                channel: InputChannel::name().to_string(),
+                share: 1.5,
+            })
+        );
+        assert!(matches!(
+            registry.try_set_budget_share::<InputChannel>(0.1),
+            Err(ChannelError::BudgetSharesExceeded(_))
+        ));
+        // updating the share of a channel replaces its previous share
+        registry.set_budget_share::<MyChannel>(0.6);
+        registry.set_budget_share::<InputChannel>(0.1);
+        assert_eq!(registry.budget_shares().len(), 3);
+    }
 }
@@ -7,14 +7,15 @@ use std::ops::{Add, Mul};
 
 use bevy::prelude::{App, Component, EntityWorldMut, Mut, Resource, TypePath, World};
 use bevy::ptr::Ptr;
-use bevy::utils::HashMap;
+use bevy::utils::{Duration, HashMap};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 
-use tracing::{debug, error, trace};
+use tracing::{debug, error, trace, warn};
 
 use crate::client::components::ComponentSyncMode;
 use crate::client::config::ClientConfig;
+use crate::client::events::ComponentStateTransition;
 use crate::client::interpolation::{add_interpolation_systems, add_prepare_interpolation_systems};
 use crate::client::prediction::plugin::{
     add_non_networked_rollback_systems, add_prediction_systems,
@@ -143,6 +144,11 @@ pub struct ComponentRegistry {
     prediction_map: HashMap<ComponentKind, PredictionMetadata>,
     serialize_fns_map: HashMap<ComponentKind, ErasedSerializeFns>,
     delta_fns_map: HashMap<ComponentKind, ErasedDeltaFns>,
+    /// Priority used to order the component inserts when an entity is spawned.
+    /// Components with a higher priority are inserted first. (defaults to 0)
+    apply_priority_map: HashMap<ComponentKind, i32>,
+    /// Direction in which each component is replicated
+    directions: HashMap<ComponentKind, ChannelDirection>,
     pub(crate) kind_map: TypeMapper<ComponentKind>,
 }
 
@@ -153,6 +159,14 @@ pub struct ReplicationMetadata {
     pub replicate_once_id: ComponentId,
     pub override_target_id: ComponentId,
     pub disabled_id: ComponentId,
+    /// If true, the component is only replicated to the client(s) that control the entity
+    pub owner_only: bool,
+    /// If true, only the most recent update of the component is kept if multiple updates are
+    /// buffered before being sent
+    pub latest_only: bool,
+    /// If true, a [`ComponentStateTransition`] event is emitted when a replication update changes the value
+    /// of the component (including the updates received with delta compression)
+    pub state_transition_events: bool,
     pub write: RawWriteFn,
     pub remove: Option<RawRemoveFn>,
 }
@@ -165,6 +179,12 @@ pub struct PredictionMetadata {
     /// to determine if a rollback is needed. Returns true if we should do a rollback.
     /// Will default to a PartialEq::ne implementation, but can be overriden.
     pub should_rollback: unsafe fn(),
+    /// Function used to measure the error between the predicted component and the confirmed component,
+    /// along with the threshold above which a [`LargePredictionError`](crate::prelude::client::LargePredictionError) is emitted.
+    pub prediction_error: Option<(unsafe fn(), f32)>,
+    /// If true, a [`ComponentSyncMode::Simple`] component keeps the confirmed value during rollback instead of
+    /// being re-computed by the systems that run while re-simulating.
+    pub keep_confirmed_during_rollback: bool,
 }
 
 impl PredictionMetadata {
@@ -173,6 +193,8 @@ impl PredictionMetadata {
         Self {
             prediction_mode: mode,
             correction: None,
+            prediction_error: None,
+            keep_confirmed_during_rollback: false,
             should_rollback: unsafe {
                 std::mem::transmute::<for<'a, 'b> fn(&'a C, &'b C) -> bool, unsafe fn()>(
                     should_rollback,
@@ -187,6 +209,10 @@ pub struct InterpolationMetadata {
     pub interpolation_mode: ComponentSyncMode,
     pub interpolation: Option<unsafe fn()>,
     pub custom_interpolation: bool,
+    /// Number of samples and type-erased [`ExtrapolateFn`] of the [`DeadReckoning`] model, if any
+    pub dead_reckoning: Option<(usize, unsafe fn())>,
+    /// Time constant of the smoothing applied on top of the interpolation, if any
+    pub smoothing: Option<Duration>,
 }
 
 type RawRemoveFn = fn(&ComponentRegistry, &mut EntityWorldMut);
@@ -208,6 +234,9 @@ pub type LerpFn<C> = fn(start: &C, other: &C, t: f32) -> C;
 /// Defaults to PartialEq::ne
 type ShouldRollbackFn<C> = fn(this: &C, that: &C) -> bool;
 
+/// Function that returns the magnitude of the error between the client's predicted value and the server's value
+pub type PredictionErrorFn<C> = fn(predicted: &C, confirmed: &C) -> f32;
+
 pub trait Linear {
     fn lerp(start: &Self, other: &Self, t: f32) -> Self;
 }
@@ -222,6 +251,78 @@ where
     }
 }
 
+/// Function used to extrapolate the value of a component at `tick` (plus a fraction `overstep` of a tick),
+/// from the last values received from the remote. The `samples` are ordered from oldest to most recent,
+/// and there is always at least one sample.
+pub type ExtrapolateFn<C> = fn(samples: &[(Tick, C)], tick: Tick, overstep: f32) -> C;
+
+/// Dead-reckoning model used to extrapolate a component on the client when no newer update is available
+/// to interpolate towards (for example because of packet loss, or because the interpolation delay is too small).
+///
+/// By default the component keeps its last received value.
+pub struct DeadReckoning<C> {
+    /// Number of received values that the model needs to extrapolate
+    samples: usize,
+    extrapolate: ExtrapolateFn<C>,
+}
+
+impl<C: Clone> DeadReckoning<C> {
+    /// Keep the last received value
+    pub fn hold() -> Self {
+        Self::custom(1, Self::hold_fn)
+    }
+
+    /// Use a custom extrapolation function, which will be given up to `samples` received values
+    pub fn custom(samples: usize, extrapolate: ExtrapolateFn<C>) -> Self {
+        Self {
+            samples: samples.max(1),
+            extrapolate,
+        }
+    }
+
+    fn hold_fn(samples: &[(Tick, C)], _: Tick, _: f32) -> C {
+        samples.last().unwrap().1.clone()
+    }
+}
+
+impl<C: Linear + Clone> DeadReckoning<C> {
+    /// Extrapolate with a constant velocity, computed from the last two received values
+    pub fn linear() -> Self {
+        Self::custom(2, Self::linear_fn)
+    }
+
+    /// Extrapolate with a constant acceleration, by fitting a parabola through the last three received values
+    pub fn quadratic() -> Self {
+        Self::custom(3, Self::quadratic_fn)
+    }
+
+    fn linear_fn(samples: &[(Tick, C)], tick: Tick, overstep: f32) -> C {
+        match samples {
+            [.., (start_tick, start), (end_tick, end)] if start_tick != end_tick => {
+                let t = ((tick - *start_tick) as f32 + overstep) / (*end_tick - *start_tick) as f32;
+                C::lerp(start, end, t)
+            }
+            _ => Self::hold_fn(samples, tick, overstep),
+        }
+    }
+
+    fn quadratic_fn(samples: &[(Tick, C)], tick: Tick, overstep: f32) -> C {
+        match samples {
+            [.., (tick_0, p0), (tick_1, p1), (tick_2, p2)]
+                if tick_0 != tick_1 && tick_1 != tick_2 =>
+            {
+                // Neville's algorithm: each step is a linear interpolation between two lower-degree polynomials
+                let d = |sample_tick: &Tick| (tick - *sample_tick) as f32 + overstep;
+                let (d0, d1, d2) = (d(tick_0), d(tick_1), d(tick_2));
+                let p01 = C::lerp(p0, p1, d0 / (d0 - d1));
+                let p12 = C::lerp(p1, p2, d1 / (d1 - d2));
+                C::lerp(&p01, &p12, d0 / (d0 - d2))
+            }
+            _ => Self::linear_fn(samples, tick, overstep),
+        }
+    }
+}
+
 impl ComponentRegistry {
     pub fn net_id<C: 'static>(&self) -> ComponentNetId {
         self.kind_map
@@ -242,6 +343,25 @@ impl ComponentRegistry {
         self.kind_map.net_id(&ComponentKind::of::<C>()).is_some()
     }
 
+    /// Direction in which the component is replicated
+    pub fn direction(&self, kind: ComponentKind) -> Option<ChannelDirection> {
+        self.directions.get(&kind).copied()
+    }
+
+    /// Keep track of the direction in which a component is replicated.
+    ///
+    /// If the component is registered multiple times with different directions, it is replicated in both directions.
+    fn add_direction<C: 'static>(&mut self, direction: ChannelDirection) {
+        self.directions
+            .entry(ComponentKind::of::<C>())
+            .and_modify(|existing| {
+                if *existing != direction {
+                    *existing = ChannelDirection::Bidirectional;
+                }
+            })
+            .or_insert(direction);
+    }
+
     /// Check that the protocol is correct:
     /// - emits warnings for every component that has prediction/interpolation metadata but wasn't registered
     pub fn check(&self) {
@@ -445,6 +565,41 @@ mod prediction {
             };
         }
 
+        pub(crate) fn set_prediction_error<C: Component + PartialEq>(
+            &mut self,
+            prediction_error: PredictionErrorFn<C>,
+            threshold: f32,
+        ) {
+            let kind = ComponentKind::of::<C>();
+            self.prediction_map
+                .entry(kind)
+                .or_insert_with(|| PredictionMetadata::default_from::<C>(ComponentSyncMode::Full))
+                .prediction_error = Some((
+                unsafe {
+                    std::mem::transmute::<for<'a, 'b> fn(&'a C, &'b C) -> f32, unsafe fn()>(
+                        prediction_error,
+                    )
+                },
+                threshold,
+            ));
+        }
+
+        pub(crate) fn set_keep_confirmed_during_rollback<C: Component + PartialEq>(&mut self) {
+            let kind = ComponentKind::of::<C>();
+            self.prediction_map
+                .entry(kind)
+                .or_insert_with(|| PredictionMetadata::default_from::<C>(ComponentSyncMode::Simple))
+                .keep_confirmed_during_rollback = true;
+        }
+
+        /// Returns true if the component should keep its confirmed value during rollback
+        pub(crate) fn keep_confirmed_during_rollback<C: Component>(&self) -> bool {
+            let kind = ComponentKind::of::<C>();
+            self.prediction_map
+                .get(&kind)
+                .is_some_and(|metadata| metadata.keep_confirmed_during_rollback)
+        }
+
         pub(crate) fn set_linear_correction<C: Component + Linear + PartialEq>(&mut self) {
             self.set_correction(<C as Linear>::lerp);
         }
@@ -489,6 +644,24 @@ mod prediction {
             should_rollback_fn(this, that)
         }
 
+        /// Returns the magnitude of the error between the predicted and confirmed values, if it is
+        /// above the threshold registered for the component
+        pub(crate) fn large_prediction_error<C: Component>(
+            &self,
+            predicted: &C,
+            confirmed: &C,
+        ) -> Option<f32> {
+            let kind = ComponentKind::of::<C>();
+            let (prediction_error, threshold) = self
+                .prediction_map
+                .get(&kind)
+                .and_then(|metadata| metadata.prediction_error)?;
+            let prediction_error_fn: PredictionErrorFn<C> =
+                unsafe { std::mem::transmute(prediction_error) };
+            let magnitude = prediction_error_fn(predicted, confirmed);
+            (magnitude > threshold).then_some(magnitude)
+        }
+
         pub(crate) fn correct<C: Component>(&self, predicted: &C, corrected: &C, t: f32) -> C {
             let kind = ComponentKind::of::<C>();
             let prediction_metadata = self
@@ -514,6 +687,8 @@ mod interpolation {
                     interpolation_mode: mode,
                     interpolation: None,
                     custom_interpolation: false,
+                    dead_reckoning: None,
+                    smoothing: None,
                 })
                 .interpolation_mode = mode;
         }
@@ -530,6 +705,8 @@ mod interpolation {
                     interpolation_mode: ComponentSyncMode::Full,
                     interpolation: None,
                     custom_interpolation: false,
+                    dead_reckoning: None,
+                    smoothing: None,
                 })
                 .interpolation = Some(unsafe {
                 std::mem::transmute::<for<'a, 'b> fn(&'a C, &'b C, f32) -> C, unsafe fn()>(
@@ -545,6 +722,14 @@ mod interpolation {
                     metadata.interpolation_mode
                 })
         }
+        /// Returns true if an interpolation function is registered for the component
+        pub(crate) fn has_interpolation<C: Component>(&self) -> bool {
+            let kind = ComponentKind::of::<C>();
+            self.interpolation_map
+                .get(&kind)
+                .is_some_and(|metadata| metadata.interpolation.is_some())
+        }
+
         pub(crate) fn interpolate<C: Component>(&self, start: &C, end: &C, t: f32) -> C {
             let kind = ComponentKind::of::<C>();
             let interpolation_metadata = self
@@ -555,6 +740,75 @@ mod interpolation {
                 unsafe { std::mem::transmute(interpolation_metadata.interpolation.unwrap()) };
             interpolation_fn(start, end, t)
         }
+
+        pub(crate) fn set_dead_reckoning<C: Component>(&mut self, model: DeadReckoning<C>) {
+            let kind = ComponentKind::of::<C>();
+            self.interpolation_map
+                .entry(kind)
+                .or_insert_with(|| InterpolationMetadata {
+                    interpolation_mode: ComponentSyncMode::Full,
+                    interpolation: None,
+                    custom_interpolation: false,
+                    dead_reckoning: None,
+                    smoothing: None,
+                })
+                .dead_reckoning = Some((model.samples, unsafe {
+                std::mem::transmute::<for<'a> fn(&'a [(Tick, C)], Tick, f32) -> C, unsafe fn()>(
+                    model.extrapolate,
+                )
+            }));
+        }
+
+        pub(crate) fn set_interpolation_smoothing<C: Component>(
+            &mut self,
+            time_constant: Duration,
+        ) {
+            let kind = ComponentKind::of::<C>();
+            self.interpolation_map
+                .entry(kind)
+                .or_insert_with(|| InterpolationMetadata {
+                    interpolation_mode: ComponentSyncMode::Full,
+                    interpolation: None,
+                    custom_interpolation: false,
+                    dead_reckoning: None,
+                    smoothing: None,
+                })
+                .smoothing = Some(time_constant);
+        }
+
+        /// Time constant of the smoothing applied to the interpolated component,
+        /// or None if the component is interpolated linearly
+        pub(crate) fn interpolation_smoothing<C: Component>(&self) -> Option<Duration> {
+            let kind = ComponentKind::of::<C>();
+            self.interpolation_map
+                .get(&kind)
+                .and_then(|metadata| metadata.smoothing)
+        }
+
+        /// Number of received values that the [`DeadReckoning`] model of the component needs,
+        /// or None if the component has no dead-reckoning model
+        pub(crate) fn dead_reckoning_samples<C: Component>(&self) -> Option<usize> {
+            let kind = ComponentKind::of::<C>();
+            self.interpolation_map
+                .get(&kind)
+                .and_then(|metadata| metadata.dead_reckoning)
+                .map(|(samples, _)| samples)
+        }
+
+        /// Extrapolate the component with its [`DeadReckoning`] model.
+        ///
+        /// Returns None if the component has no dead-reckoning model.
+        pub(crate) fn extrapolate<C: Component>(
+            &self,
+            samples: &[(Tick, C)],
+            tick: Tick,
+            overstep: f32,
+        ) -> Option<C> {
+            let kind = ComponentKind::of::<C>();
+            let (_, extrapolate) = self.interpolation_map.get(&kind)?.dead_reckoning?;
+            let extrapolate_fn: ExtrapolateFn<C> = unsafe { std::mem::transmute(extrapolate) };
+            Some(extrapolate_fn(samples, tick, overstep))
+        }
     }
 }
 
@@ -580,12 +834,82 @@ mod replication {
                     replicate_once_id: world.init_component::<ReplicateOnceComponent<C>>(),
                     override_target_id: world.init_component::<OverrideTargetComponent<C>>(),
                     disabled_id: world.init_component::<DisabledComponent<C>>(),
+                    owner_only: false,
+                    latest_only: false,
+                    state_transition_events: false,
                     write,
                     remove: Some(remove),
                 },
             );
         }
 
+        pub(crate) fn set_apply_priority<C: Component>(&mut self, priority: i32) {
+            let kind = ComponentKind::of::<C>();
+            self.apply_priority_map.insert(kind, priority);
+        }
+
+        /// Sort the serialized component inserts so that components with a higher apply priority are inserted first.
+        ///
+        /// The sort is stable, so components with the same priority keep the order in which they were received.
+        pub(crate) fn sort_by_apply_priority(&self, inserts: &mut [bytes::Bytes]) {
+            if self.apply_priority_map.is_empty() {
+                return;
+            }
+            inserts.sort_by_cached_key(|component| {
+                let priority = NetId::from_bytes(&mut Reader::from(component.clone()))
+                    .ok()
+                    .and_then(|net_id| self.kind_map.kind(net_id))
+                    .and_then(|kind| self.apply_priority_map.get(kind))
+                    .copied()
+                    .unwrap_or_default();
+                std::cmp::Reverse(priority)
+            });
+        }
+
+        pub(crate) fn set_owner_only<C: Component>(&mut self) {
+            let kind = ComponentKind::of::<C>();
+            self.replication_map
+                .get_mut(&kind)
+                .expect("the component is not part of the protocol")
+                .owner_only = true;
+        }
+
+        /// Emit [`ComponentStateTransition`] events for the component.
+        ///
+        /// If the component uses delta compression, the updates are written by [`write_delta`](Self::write_delta),
+        /// which checks `state_transition_events` to emit the events, so the order of registration doesn't matter.
+        pub(crate) fn set_state_transition_events<C: Component + PartialEq + Clone>(&mut self) {
+            let kind = ComponentKind::of::<C>();
+            let metadata = self
+                .replication_map
+                .get_mut(&kind)
+                .expect("the component is not part of the protocol");
+            metadata.state_transition_events = true;
+            metadata.write = Self::write_with_transition::<C>;
+        }
+
+        /// Returns true if [`ComponentStateTransition`] events are emitted for the component
+        pub(crate) fn has_state_transition_events<C: Component>(&self) -> bool {
+            self.replication_map
+                .get(&ComponentKind::of::<C>())
+                .is_some_and(|metadata| metadata.state_transition_events)
+        }
+
+        pub(crate) fn set_latest_only<C: Component>(&mut self) {
+            let kind = ComponentKind::of::<C>();
+            self.replication_map
+                .get_mut(&kind)
+                .expect("the component is not part of the protocol")
+                .latest_only = true;
+        }
+
+        /// Returns true if only the most recent update of the component should be sent
+        pub(crate) fn is_latest_only(&self, kind: ComponentKind) -> bool {
+            self.replication_map
+                .get(&kind)
+                .is_some_and(|metadata| metadata.latest_only)
+        }
+
         /// SAFETY: the ReadWordBuffer must contain bytes corresponding to the correct component type
         pub(crate) fn raw_write(
             &self,
@@ -641,6 +965,39 @@ mod replication {
             Ok(())
         }
 
+        /// Same as [`write`](Self::write), but also emits a [`ComponentStateTransition`] event when the
+        /// value of an existing component changes
+        pub(crate) fn write_with_transition<C: Component + PartialEq + Clone>(
+            &self,
+            reader: &mut Reader,
+            net_id: ComponentNetId,
+            tick: Tick,
+            entity_world_mut: &mut EntityWorldMut,
+            entity_map: &mut ReceiveEntityMap,
+            events: &mut ConnectionEvents,
+        ) -> Result<(), ComponentError> {
+            trace!("Writing component {} to entity", std::any::type_name::<C>());
+            let component = self.raw_deserialize::<C>(reader, net_id, entity_map)?;
+            let entity = entity_world_mut.id();
+            if let Some(mut c) = entity_world_mut.get_mut::<C>() {
+                if c.as_ref() != &component {
+                    events.push_update_component(entity, net_id, tick);
+                    let old = std::mem::replace(c.as_mut(), component.clone());
+                    entity_world_mut.world_scope(|world| {
+                        world.send_event(ComponentStateTransition {
+                            entity,
+                            old,
+                            new: component,
+                        });
+                    });
+                }
+            } else {
+                events.push_insert_component(entity, net_id, tick);
+                entity_world_mut.insert(component);
+            }
+            Ok(())
+        }
+
         pub(crate) fn raw_remove(
             &self,
             net_id: ComponentNetId,
@@ -697,6 +1054,9 @@ mod delta {
                     replicate_once_id: ComponentId::new(0),
                     override_target_id: ComponentId::new(0),
                     disabled_id: ComponentId::new(0),
+                    owner_only: false,
+                    latest_only: false,
+                    state_transition_events: false,
                     write,
                     remove: None,
                 },
@@ -821,8 +1181,9 @@ mod delta {
                             std::any::type_name::<C>())
                         ));
                     };
-                    *c = new_value;
+                    let old = std::mem::replace(c.as_mut(), new_value);
                     events.push_update_component(entity, net_id, tick);
+                    self.send_state_transition(entity_world_mut, old);
                 }
                 DeltaType::FromBase => {
                     let mut new_value = C::base_value();
@@ -831,8 +1192,9 @@ mod delta {
                     if let Some(mut c) = entity_world_mut.get_mut::<C>() {
                         // only apply the update if the component is different, to not trigger change detection
                         if c.as_ref() != &new_value {
-                            *c = new_value;
+                            let old = std::mem::replace(c.as_mut(), new_value);
                             events.push_update_component(entity, net_id, tick);
+                            self.send_state_transition(entity_world_mut, old);
                         }
                     } else {
                         entity_world_mut.insert(new_value);
@@ -854,6 +1216,27 @@ mod delta {
             }
             Ok(())
         }
+
+        /// Emit a [`ComponentStateTransition`] event from `old` to the current value of the component,
+        /// if the component has state transition events and its value changed
+        fn send_state_transition<C: Component + PartialEq + Clone>(
+            &self,
+            entity_world_mut: &mut EntityWorldMut,
+            old: C,
+        ) {
+            if !self.has_state_transition_events::<C>() {
+                return;
+            }
+            let entity = entity_world_mut.id();
+            let Some(new) = entity_world_mut.get::<C>().cloned() else {
+                return;
+            };
+            if old != new {
+                entity_world_mut.world_scope(|world| {
+                    world.send_event(ComponentStateTransition { entity, old, new });
+                });
+            }
+        }
     }
 }
 
@@ -929,6 +1312,16 @@ pub trait AppComponentExt {
     ///  equality check. For example, you might want to add a threshold for floating point numbers)
     fn add_should_rollback_fn<C: SyncComponent>(&mut self, should_rollback: ShouldRollbackFn<C>);
 
+    /// Add a function that measures the error between the predicted and confirmed values of the component.
+    ///
+    /// A [`LargePredictionError`](crate::prelude::client::LargePredictionError) event is emitted when the error
+    /// is above `threshold` during a rollback check.
+    fn add_prediction_error_fn<C: SyncComponent>(
+        &mut self,
+        prediction_error: PredictionErrorFn<C>,
+        threshold: f32,
+    );
+
     /// Register helper systems to perform interpolation for the component; but the user has to define the interpolation logic
     /// themselves (the interpolation_fn will not be used)
     fn add_custom_interpolation<C: SyncComponent>(&mut self, interpolation_mode: ComponentSyncMode);
@@ -943,10 +1336,20 @@ pub trait AppComponentExt {
     /// Add a `Interpolation` behaviour to this component.
     fn add_interpolation_fn<C: SyncComponent>(&mut self, interpolation_fn: LerpFn<C>);
 
+    /// Add a [`DeadReckoning`] model used to extrapolate the interpolated component when no newer update
+    /// is available to interpolate towards.
+    fn add_dead_reckoning<C: SyncComponent>(&mut self, model: DeadReckoning<C>);
+
+    /// Smooth the interpolated component with a critically-damped spring towards the interpolation target.
+    fn add_interpolation_smoothing<C: SyncComponent>(&mut self, time_constant: Duration);
+
     /// Enable delta compression when serializing this component
     fn add_delta_compression<C: Component + PartialEq + Diffable>(&mut self)
     where
         C::Delta: Serialize + DeserializeOwned;
+
+    /// Emit a [`ComponentStateTransition`] event on the client whenever a replication update changes the value of the component.
+    fn add_state_transition_events<C: Component + PartialEq + Clone>(&mut self);
 }
 
 pub struct ComponentRegistration<'a, C> {
@@ -1006,6 +1409,38 @@ impl<C> ComponentRegistration<'_, C> {
         self
     }
 
+    /// Keep the value received from the remote during rollback, instead of letting the systems that run while
+    /// re-simulating re-compute it.
+    ///
+    /// This is useful for values that only the remote can compute (for example the result of a server-side random roll).
+    /// It only applies to components predicted with [`ComponentSyncMode::Simple`].
+    pub fn keep_confirmed_during_rollback(self) -> Self
+    where
+        C: SyncComponent,
+    {
+        let mut registry = self.app.world_mut().resource_mut::<ComponentRegistry>();
+        registry.set_keep_confirmed_during_rollback::<C>();
+        self
+    }
+
+    /// Add a function that measures the error between the predicted and confirmed values of the component.
+    ///
+    /// A [`LargePredictionError`](crate::prelude::client::LargePredictionError) event is emitted when the error
+    /// is above `threshold` during a rollback check. This can be used to detect desyncs, as opposed to the
+    /// small corrections that are expected during prediction.
+    pub fn add_prediction_error(
+        self,
+        prediction_error: PredictionErrorFn<C>,
+        threshold: f32,
+    ) -> Self
+    where
+        C: SyncComponent,
+    {
+        self.app
+            .add_prediction_error_fn::<C>(prediction_error, threshold);
+        self
+    }
+
     /// Enable interpolation systems for this component.
     /// You can specify the interpolation [`ComponentSyncMode`]
     pub fn add_interpolation(self, interpolation_mode: ComponentSyncMode) -> Self
@@ -1044,6 +1479,29 @@ impl<C> ComponentRegistration<'_, C> {
         self
     }
 
+    /// Add a [`DeadReckoning`] model used to extrapolate the interpolated component when no newer update
+    /// is available to interpolate towards (instead of keeping the last received value).
+    pub fn dead_reckoning(self, model: DeadReckoning<C>) -> Self
+    where
+        C: SyncComponent,
+    {
+        self.app.add_dead_reckoning::<C>(model);
+        self
+    }
+
+    /// Smooth the interpolated component with a critically-damped spring towards the interpolation target,
+    /// instead of following the piecewise-linear interpolation path exactly.
+    ///
+    /// This removes the abrupt changes of direction at the snapshot boundaries, at the cost of adding a
+    /// delay of about `2 * time_constant` to the displayed value.
+    pub fn add_interpolation_smoothing(self, time_constant: Duration) -> Self
+    where
+        C: SyncComponent,
+    {
+        self.app.add_interpolation_smoothing::<C>(time_constant);
+        self
+    }
+
     /// Enable delta compression when serializing this component
     pub fn add_delta_compression(self) -> Self
     where
@@ -1053,6 +1511,65 @@ impl<C> ComponentRegistration<'_, C> {
         self.app.add_delta_compression::<C>();
         self
     }
+
+    /// Emit a [`ComponentStateTransition`] event on the client, with the old and new values, whenever a
+    /// replication update changes the value of this component.
+    ///
+    /// This is useful for components that represent a state machine (e.g. `PlayerState::{Idle, Running}`).
+    /// No event is emitted when the component is first inserted.
+    ///
+    /// The protocol must be registered after the `ClientPlugins` are added, otherwise this has no effect.
+    /// In HostServer mode, no event is emitted for the local client since it shares the server's entities.
+    pub fn add_state_transition_events(self) -> Self
+    where
+        C: Component + PartialEq + Clone,
+    {
+        self.app.add_state_transition_events::<C>();
+        self
+    }
+
+    /// Set the priority used to order the component inserts when an entity is spawned with multiple components.
+    ///
+    /// Components with a higher priority are inserted first (the default priority is 0).
+    /// This is useful if an observer or hook of a component expects another component to already be present.
+    pub fn set_apply_priority(self, priority: i32) -> Self
+    where
+        C: Component,
+    {
+        let mut registry = self.app.world_mut().resource_mut::<ComponentRegistry>();
+        registry.set_apply_priority::<C>(priority);
+        self
+    }
+
+    /// Only replicate this component to the client(s) that control the entity (as specified by
+    /// [`ControlledBy`](crate::prelude::server::ControlledBy)). The other clients that receive the
+    /// entity will not receive this component.
+    ///
+    /// When the [`ControlledBy`](crate::prelude::server::ControlledBy) component changes, the component is
+    /// inserted on the new owners and removed from the clients that lost the control of the entity.
+    ///
+    /// This is only used for server to client replication.
+    pub fn owner_only(self) -> Self
+    where
+        C: Component,
+    {
+        let mut registry = self.app.world_mut().resource_mut::<ComponentRegistry>();
+        registry.set_owner_only::<C>();
+        self
+    }
+
+    /// Only the most recent value of this component matters: if multiple updates for the component
+    /// are buffered before they get sent, only the latest one is kept instead of sending all of them.
+    ///
+    /// This is useful for components where stale values are useless (for example a cursor position).
+    pub fn latest_only(self) -> Self
+    where
+        C: Component,
+    {
+        let mut registry = self.app.world_mut().resource_mut::<ComponentRegistry>();
+        registry.set_latest_only::<C>();
+        self
+    }
 }
 
 impl AppComponentExt for App {
@@ -1066,6 +1583,7 @@ impl AppComponentExt for App {
                     registry.register_component::<C>();
                 }
                 registry.set_replication_fns::<C>(world);
+                registry.add_direction::<C>(direction);
                 debug!("register component {}", std::any::type_name::<C>());
             });
         register_component_send::<C>(self, direction);
@@ -1086,6 +1604,7 @@ impl AppComponentExt for App {
                     registry.register_component_custom_serde::<C>(serialize_fns);
                 }
                 registry.set_replication_fns::<C>(world);
+                registry.add_direction::<C>(direction);
                 debug!("register component {}", std::any::type_name::<C>());
             });
         register_component_send::<C>(self, direction);
@@ -1131,6 +1650,15 @@ impl AppComponentExt for App {
         registry.set_should_rollback::<C>(rollback_check);
     }
 
+    fn add_prediction_error_fn<C: SyncComponent>(
+        &mut self,
+        prediction_error: PredictionErrorFn<C>,
+        threshold: f32,
+    ) {
+        let mut registry = self.world_mut().resource_mut::<ComponentRegistry>();
+        registry.set_prediction_error::<C>(prediction_error, threshold);
+    }
+
     fn add_custom_interpolation<C: SyncComponent>(
         &mut self,
         interpolation_mode: ComponentSyncMode,
@@ -1175,6 +1703,16 @@ impl AppComponentExt for App {
         registry.set_interpolation::<C>(interpolation_fn);
     }
 
+    fn add_dead_reckoning<C: SyncComponent>(&mut self, model: DeadReckoning<C>) {
+        let mut registry = self.world_mut().resource_mut::<ComponentRegistry>();
+        registry.set_dead_reckoning::<C>(model);
+    }
+
+    fn add_interpolation_smoothing<C: SyncComponent>(&mut self, time_constant: Duration) {
+        let mut registry = self.world_mut().resource_mut::<ComponentRegistry>();
+        registry.set_interpolation_smoothing::<C>(time_constant);
+    }
+
     fn add_delta_compression<C: Component + PartialEq + Diffable>(&mut self)
     where
         C::Delta: Serialize + DeserializeOwned,
@@ -1182,6 +1720,22 @@ impl AppComponentExt for App {
         let mut registry = self.world_mut().resource_mut::<ComponentRegistry>();
         registry.set_delta_compression::<C>();
     }
+
+    fn add_state_transition_events<C: Component + PartialEq + Clone>(&mut self) {
+        let is_client = self.world().get_resource::<ClientConfig>().is_some();
+        let is_server = self.world().get_resource::<ServerConfig>().is_some();
+        if !is_client && !is_server {
+            warn!(
+                "add_state_transition_events::<{}> has no effect because the ClientPlugins were not added before the protocol was registered",
+                std::any::type_name::<C>()
+            );
+        }
+        if is_client {
+            self.add_event::<ComponentStateTransition<C>>();
+            let mut registry = self.world_mut().resource_mut::<ComponentRegistry>();
+            registry.set_state_transition_events::<C>();
+        }
+    }
 }
 
 /// [`ComponentKind`] is an internal wrapper around the type of the component
@@ -1229,4 +1783,33 @@ mod tests {
             .unwrap();
         assert_eq!(component, read);
     }
+
+    /// Check that the dead-reckoning models extrapolate a linearly-moving component differently
+    #[test]
+    fn test_dead_reckoning_extrapolation() {
+        // the entity moves by 1.0 per tick, and we received updates every 5 ticks
+        let samples: Vec<_> = [0, 5, 10]
+            .into_iter()
+            .map(|tick| (Tick(tick), ComponentSyncModeFull(tick as f32)))
+            .collect();
+        let extrapolate = |model: DeadReckoning<ComponentSyncModeFull>| {
+            let mut registry = ComponentRegistry::default();
+            registry.set_dead_reckoning(model);
+            registry.extrapolate(&samples, Tick(13), 0.5).unwrap().0
+        };
+
+        // without a model, there is nothing to extrapolate
+        assert!(ComponentRegistry::default()
+            .extrapolate(&samples, Tick(13), 0.5)
+            .is_none());
+        assert_eq!(extrapolate(DeadReckoning::hold()), 10.0);
+        assert_eq!(extrapolate(DeadReckoning::linear()), 13.5);
+        assert_eq!(extrapolate(DeadReckoning::quadratic()), 13.5);
+        assert_eq!(
+            extrapolate(DeadReckoning::custom(1, |samples, _, _| {
+                ComponentSyncModeFull(-samples.last().unwrap().1 .0)
+            })),
+            -10.0
+        );
+    }
 }
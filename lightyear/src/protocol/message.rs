@@ -1,19 +1,23 @@
 use bevy::ecs::entity::MapEntities;
 use std::any::TypeId;
 use std::fmt::Debug;
+use std::num::NonZeroU32;
 
 use crate::client::config::ClientConfig;
 use crate::client::message::add_client_receive_message_from_server;
 use crate::prelude::{client, server};
-use bevy::prelude::{App, Resource, TypePath};
+use bevy::prelude::{App, Resource, TypePath, World};
 use bevy::utils::HashMap;
+use bytes::Bytes;
+use governor::Quota;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
-use tracing::{debug, error};
+use tracing::{debug, warn};
 
 use crate::packet::message::Message;
 use crate::prelude::server::ServerConfig;
-use crate::prelude::ChannelDirection;
+use crate::prelude::{ChannelDirection, ClientId};
+use crate::protocol::channel::ChannelRegistry;
 use crate::protocol::registry::{NetId, TypeKind, TypeMapper};
 use crate::protocol::serialize::{ErasedSerializeFns, SerializeFns};
 use crate::serialize::reader::Reader;
@@ -29,10 +33,23 @@ pub enum MessageError {
     NotRegistered,
     #[error("missing serialization functions for message")]
     MissingSerializationFns,
+    #[error("the {sender} cannot send messages on channel {channel} (direction: {direction:?})")]
+    WrongChannelDirection {
+        channel: String,
+        direction: ChannelDirection,
+        sender: &'static str,
+    },
+    #[error("no channel can send these messages in their registered direction: {0:?}")]
+    MissingChannelDirection(Vec<(&'static str, ChannelDirection)>),
     #[error(transparent)]
     Serialization(#[from] crate::serialize::SerializationError),
 }
 
+/// Reserved [`NetId`] written before messages that must only be processed once the receiver
+/// reaches a given tick. It is followed by the [`Tick`](crate::prelude::Tick), and then by the
+/// serialized message (including its own [`NetId`]).
+pub(crate) const SCHEDULED_MESSAGE_NET_ID: NetId = NetId::MAX;
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub(crate) enum MessageType {
     /// This is a message for a [`LeafwingUserAction`](crate::inputs::leafwing::LeafwingUserAction)
@@ -99,9 +116,52 @@ pub(crate) enum MessageType {
 pub struct MessageRegistry {
     typed_map: HashMap<MessageKind, MessageType>,
     serialize_fns_map: HashMap<MessageKind, ErasedSerializeFns>,
+    /// The direction in which each user message can be sent
+    directions: HashMap<MessageKind, ChannelDirection>,
+    /// The maximum rate at which each client can send a given message to the server
+    rate_limits: HashMap<MessageKind, Quota>,
     pub(crate) kind_map: TypeMapper<MessageKind>,
 }
 
+/// Message received from the remote peer whose network id is not registered in the [`MessageRegistry`],
+/// for example because the remote peer is running a different version of the protocol.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnknownMessage {
+    /// The client that sent the message, or `None` if the message was sent by the server
+    pub sender: Option<ClientId>,
+    /// The network id of the message
+    pub net_id: u16,
+    /// The raw bytes of the message, as serialized by the remote peer (starting with the network id)
+    pub bytes: Bytes,
+}
+
+/// Function called for every received [`UnknownMessage`].
+///
+/// Messages are framed individually, so an unknown message is skipped without affecting the other
+/// messages of the packet. Without a handler, the unknown messages are logged and dropped.
+pub type UnknownMessageFn = fn(world: &mut World, message: UnknownMessage);
+
+/// Stores the [`UnknownMessageFn`] set with [`AppMessageExt::set_unknown_message_handler`]
+#[derive(Resource)]
+pub(crate) struct UnknownMessageHandler(pub(crate) UnknownMessageFn);
+
+/// Pass the received [`UnknownMessage`]s to the [`UnknownMessageFn`], or log and drop them if there is no handler
+pub(crate) fn handle_unknown_messages(world: &mut World, messages: Vec<UnknownMessage>) {
+    let handler = world
+        .get_resource::<UnknownMessageHandler>()
+        .map(|handler| handler.0);
+    for message in messages {
+        match handler {
+            Some(handler) => handler(world, message),
+            None => warn!(
+                sender = ?message.sender,
+                net_id = ?message.net_id,
+                "Dropping message with an unknown net id"
+            ),
+        }
+    }
+}
+
 fn register_message_send<M: Message>(app: &mut App, direction: ChannelDirection) {
     let is_client = app.world().get_resource::<ClientConfig>().is_some();
     let is_server = app.world().get_resource::<ServerConfig>().is_some();
@@ -290,6 +350,18 @@ pub trait AppMessageExt {
         direction: ChannelDirection,
         serialize_fns: SerializeFns<R>,
     );
+
+    /// Set the handler called for the received messages whose network id is not registered
+    /// (see [`UnknownMessageFn`])
+    fn set_unknown_message_handler(&mut self, handler: UnknownMessageFn);
+
+    /// Limit the number of messages of type `M` that each client can send to the server per second.
+    ///
+    /// The messages that exceed the rate are dropped by the server before they are emitted as a
+    /// [`MessageEvent`](crate::server::events::MessageEvent), and a
+    /// [`MessageRateLimitedEvent`](crate::prelude::server::MessageRateLimitedEvent) is emitted instead.
+    /// This can be used to protect the server against clients spamming a message (for example chat messages).
+    fn register_message_rate_limit<M: Message>(&mut self, max_per_second: NonZeroU32);
 }
 
 impl AppMessageExt for App {
@@ -297,6 +369,9 @@ impl AppMessageExt for App {
         &mut self,
         direction: ChannelDirection,
     ) -> MessageRegistration<'_, M> {
+        self.world_mut()
+            .resource_mut::<MessageRegistry>()
+            .add_direction::<M>(direction);
         self.register_message_internal(direction, MessageType::Normal)
     }
 
@@ -305,6 +380,9 @@ impl AppMessageExt for App {
         direction: ChannelDirection,
         serialize_fns: SerializeFns<M>,
     ) -> MessageRegistration<'_, M> {
+        self.world_mut()
+            .resource_mut::<MessageRegistry>()
+            .add_direction::<M>(direction);
         self.register_message_internal_custom_serde(direction, MessageType::Normal, serialize_fns)
     }
 
@@ -328,25 +406,104 @@ impl AppMessageExt for App {
         self.register_message::<DespawnResource<R>>(direction);
         register_resource_send::<R>(self, direction)
     }
+
+    fn set_unknown_message_handler(&mut self, handler: UnknownMessageFn) {
+        self.insert_resource(UnknownMessageHandler(handler));
+    }
+
+    fn register_message_rate_limit<M: Message>(&mut self, max_per_second: NonZeroU32) {
+        self.world_mut()
+            .resource_mut::<MessageRegistry>()
+            .rate_limits
+            .insert(MessageKind::of::<M>(), Quota::per_second(max_per_second));
+    }
 }
 
 impl MessageRegistry {
-    pub(crate) fn message_type(&self, net_id: NetId) -> MessageType {
-        let kind = self.kind_map.kind(net_id).unwrap();
-        self.typed_map
-            .get(kind)
-            .map_or(MessageType::Normal, |message_type| *message_type)
+    /// Returns the [`MessageType`] of the message, or `None` if the net id is not registered
+    pub(crate) fn message_type(&self, net_id: NetId) -> Option<MessageType> {
+        let kind = self.kind_map.kind(net_id)?;
+        Some(
+            self.typed_map
+                .get(kind)
+                .map_or(MessageType::Normal, |message_type| *message_type),
+        )
+    }
+
+    /// Returns the maximum rate at which a client can send the message to the server, if any
+    pub(crate) fn rate_limit(&self, kind: &MessageKind) -> Option<Quota> {
+        self.rate_limits.get(kind).copied()
     }
 
     pub fn is_registered<M: 'static>(&self) -> bool {
         self.kind_map.net_id(&MessageKind::of::<M>()).is_some()
     }
 
+    /// Keep track of the direction in which a user message can be sent.
+    ///
+    /// If the message is registered multiple times with different directions, it can be sent in both directions.
+    fn add_direction<M: 'static>(&mut self, direction: ChannelDirection) {
+        self.directions
+            .entry(MessageKind::of::<M>())
+            .and_modify(|existing| {
+                if *existing != direction {
+                    *existing = ChannelDirection::Bidirectional;
+                }
+            })
+            .or_insert(direction);
+    }
+
+    /// Check that the protocol is correct:
+    /// - every message can be sent in its registered direction on at least one of the channels
+    ///   of the [`ChannelRegistry`] that are not reserved for internal messages.
+    ///
+    /// The channels registered by default (including the [`DefaultOrderedReliableChannel`](crate::prelude::DefaultOrderedReliableChannel))
+    /// are not taken into account, except if the user didn't register any channel: in that case the messages
+    /// can only be sent on the bidirectional [`DefaultOrderedReliableChannel`](crate::prelude::DefaultOrderedReliableChannel).
+    ///
+    /// Since the [`DefaultOrderedReliableChannel`](crate::prelude::DefaultOrderedReliableChannel) can always be used,
+    /// an error is only reported as a warning when the app is built.
+    pub fn check(&self, channel_registry: &ChannelRegistry) -> Result<(), MessageError> {
+        let channel_directions = channel_registry
+            .builder_map
+            .iter()
+            .filter(|(kind, _)| !channel_registry.is_internal_channel(kind))
+            .map(|(_, builder)| builder.settings.direction)
+            .collect::<Vec<_>>();
+        if channel_directions.is_empty() {
+            return Ok(());
+        }
+        let client_can_send = channel_directions.iter().any(|d| d.client_can_send());
+        let server_can_send = channel_directions.iter().any(|d| d.server_can_send());
+        let mut missing = self
+            .directions
+            .iter()
+            .filter(|(_, direction)| {
+                let needs_client = direction.client_can_send();
+                let needs_server = direction.server_can_send();
+                (needs_client && !client_can_send) || (needs_server && !server_can_send)
+            })
+            .map(|(kind, direction)| {
+                let name = self
+                    .serialize_fns_map
+                    .get(kind)
+                    .map_or("unknown", |fns| fns.type_name);
+                (name, *direction)
+            })
+            .collect::<Vec<_>>();
+        if missing.is_empty() {
+            return Ok(());
+        }
+        missing.sort_by_key(|(name, _)| *name);
+        Err(MessageError::MissingChannelDirection(missing))
+    }
+
     pub(crate) fn add_message<M: Message + Serialize + DeserializeOwned>(
         &mut self,
         message_type: MessageType,
     ) {
         let message_kind = self.kind_map.add::<M>();
+        self.check_reserved_net_id(message_kind);
         self.serialize_fns_map
             .insert(message_kind, ErasedSerializeFns::new::<M>());
         self.typed_map.insert(message_kind, message_type);
@@ -358,6 +515,7 @@ impl MessageRegistry {
         serialize_fns: SerializeFns<M>,
     ) {
         let message_kind = self.kind_map.add::<M>();
+        self.check_reserved_net_id(message_kind);
         self.serialize_fns_map.insert(
             message_kind,
             ErasedSerializeFns::new_custom_serde::<M>(serialize_fns),
@@ -365,6 +523,15 @@ impl MessageRegistry {
         self.typed_map.insert(message_kind, message_type);
     }
 
+    /// Panics if the message was assigned the [`SCHEDULED_MESSAGE_NET_ID`], which is reserved
+    fn check_reserved_net_id(&self, kind: MessageKind) {
+        assert_ne!(
+            self.kind_map.net_id(&kind),
+            Some(&SCHEDULED_MESSAGE_NET_ID),
+            "too many messages registered: the net id {SCHEDULED_MESSAGE_NET_ID} is reserved for scheduled messages"
+        );
+    }
+
     pub(crate) fn try_add_map_entities<M: Clone + MapEntities + 'static>(&mut self) {
         let kind = MessageKind::of::<M>();
         if let Some(erased_fns) = self.serialize_fns_map.get_mut(&kind) {
@@ -451,10 +618,14 @@ impl From<TypeId> for MessageKind {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::prelude::{ChannelKind, ChannelMode, ChannelSettings};
     use crate::tests::protocol::{
-        deserialize_resource2, serialize_resource2, ComponentMapEntities, Resource1, Resource2,
+        deserialize_resource2, serialize_resource2, Channel1, Channel2, ComponentMapEntities,
+        Resource1, Resource2, StringMessage,
     };
-    use bevy::prelude::Entity;
+    use crate::tests::stepper::BevyStepper;
+    use bevy::prelude::{default, Entity};
+    use bevy::utils::Duration;
 
     #[test]
     fn test_serde() {
@@ -473,6 +644,20 @@ mod tests {
         assert_eq!(message, read);
     }
 
+    /// Regular messages are not prefixed with any scheduling header: only the net id is written
+    #[test]
+    fn test_serialized_len() {
+        let mut registry = MessageRegistry::default();
+        registry.add_message::<Resource1>(MessageType::Normal);
+
+        let mut writer = Writer::default();
+        registry
+            .serialize(&Resource1(1.0), &mut writer, None)
+            .unwrap();
+        // 1 byte for the net id, 4 bytes for the f32
+        assert_eq!(writer.to_bytes().len(), 5);
+    }
+
     #[test]
     fn test_serde_map() {
         let mut registry = MessageRegistry::default();
@@ -518,4 +703,81 @@ mod tests {
             .unwrap();
         assert_eq!(message, read);
     }
+
+    /// Check that the protocol validation fails if a message cannot be sent in its registered direction
+    #[test]
+    fn test_check_channel_directions() {
+        let mut registry = MessageRegistry::default();
+        registry.add_message::<StringMessage>(MessageType::Normal);
+        registry.add_direction::<StringMessage>(ChannelDirection::ClientToServer);
+        registry.add_message::<Resource1>(MessageType::Normal);
+        registry.add_direction::<Resource1>(ChannelDirection::ServerToClient);
+
+        // the default channels (including the bidirectional DefaultOrderedReliableChannel) are not
+        // taken into account once the user registers their own channels
+        let mut channel_registry = ChannelRegistry::new(Duration::default());
+        assert!(registry.check(&channel_registry).is_ok());
+        channel_registry.add_channel::<Channel1>(ChannelSettings {
+            mode: ChannelMode::UnorderedUnreliable,
+            direction: ChannelDirection::ServerToClient,
+            ..default()
+        });
+        let Err(MessageError::MissingChannelDirection(missing)) = registry.check(&channel_registry)
+        else {
+            panic!("expected the protocol check to fail");
+        };
+        assert_eq!(
+            missing,
+            vec![(
+                std::any::type_name::<StringMessage>(),
+                ChannelDirection::ClientToServer
+            )]
+        );
+        assert!(registry
+            .check(&channel_registry)
+            .unwrap_err()
+            .to_string()
+            .contains("StringMessage"));
+
+        // a channel that allows the client to send fixes the protocol
+        channel_registry.add_channel::<Channel2>(ChannelSettings {
+            mode: ChannelMode::UnorderedUnreliable,
+            direction: ChannelDirection::ClientToServer,
+            ..default()
+        });
+        assert!(registry.check(&channel_registry).is_ok());
+    }
+
+    /// Check the protocol validation on the full test protocol, where a message registered as
+    /// bidirectional cannot be sent by the client
+    #[test]
+    fn test_check_channel_directions_test_protocol() {
+        let stepper = BevyStepper::default();
+        let world = stepper.client_app.world();
+        let registry = world.resource::<MessageRegistry>();
+        let mut channel_registry = world.resource::<ChannelRegistry>().clone();
+        assert!(registry.check(&channel_registry).is_ok());
+
+        // the user channels can only be used by the server
+        for kind in [ChannelKind::of::<Channel1>(), ChannelKind::of::<Channel2>()] {
+            channel_registry
+                .builder_map
+                .get_mut(&kind)
+                .unwrap()
+                .settings
+                .direction = ChannelDirection::ServerToClient;
+        }
+        let Err(MessageError::MissingChannelDirection(missing)) = registry.check(&channel_registry)
+        else {
+            panic!("expected the protocol check to fail");
+        };
+        assert!(missing.contains(&(
+            std::any::type_name::<StringMessage>(),
+            ChannelDirection::Bidirectional
+        )));
+        // the internal messages are sent on the internal channels
+        assert!(missing
+            .iter()
+            .all(|(name, _)| name.contains("lightyear::tests::protocol::")));
+    }
 }
@@ -23,6 +23,8 @@ pub(crate) mod delta;
 /// Provides a mapping from a type to a unique identifier that can be serialized
 pub(crate) mod registry;
 pub(crate) mod serialize;
+/// Measure the serialized size of the registered components and messages
+pub(crate) mod size;
 
 /// Data that can be used in an Event
 /// Same as `Event`, but we implement it automatically for all compatible types
@@ -58,11 +58,17 @@ impl<K: TypeKind> TypeMapper<K> {
         if self.kind_map.contains_key(&kind) {
             panic!("Type {:?} already registered", std::any::type_name::<T>());
         }
+        self.add_kind(kind);
+        kind
+    }
+
+    /// Register a new kind, and return its net id
+    pub(crate) fn add_kind(&mut self, kind: K) -> NetId {
         let net_id = self.next_net_id;
         self.kind_map.insert(kind, net_id);
         self.id_map.insert(net_id, kind);
         self.next_net_id += 1;
-        kind
+        net_id
     }
 
     pub fn kind(&self, net_id: NetId) -> Option<&K> {
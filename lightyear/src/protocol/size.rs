@@ -0,0 +1,200 @@
+//! Measure the serialized size of the types registered in the protocol
+//!
+//! This can be useful to budget the bandwidth used by each component or message.
+//! The values are serialized using the same functions as the ones used to send them over the network,
+//! so the reported sizes include the [`NetId`](crate::protocol::registry::NetId) prefix.
+//!
+//! ```rust
+//! use bevy::prelude::*;
+//! use lightyear::prelude::*;
+//!
+//! fn log_protocol_sizes(components: Res<ComponentRegistry>, messages: Res<MessageRegistry>) {
+//!     let mut report = SerializedSizeReport::default();
+//!     // report.add_component(&components, MyComponent::default()).unwrap();
+//!     // report.add_message(&messages, &MyMessage::default()).unwrap();
+//!     report.log();
+//! }
+//! ```
+use bevy::prelude::Component;
+use tracing::info;
+
+use crate::prelude::{ComponentRegistry, Message, MessageRegistry};
+use crate::protocol::component::ComponentError;
+use crate::protocol::message::MessageError;
+use crate::serialize::writer::Writer;
+
+/// Whether the type is a component or a message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProtocolTypeCategory {
+    Component,
+    Message,
+}
+
+/// Serialized size statistics for a single registered type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SerializedSize {
+    /// Name of the type
+    pub name: &'static str,
+    pub category: ProtocolTypeCategory,
+    /// Number of samples that were serialized for this type
+    pub num_samples: usize,
+    /// Smallest serialized size (in bytes) among all the samples
+    pub min_bytes: usize,
+    /// Largest serialized size (in bytes) among all the samples (worst-case)
+    pub max_bytes: usize,
+    /// Sum of the serialized sizes of all the samples, used to compute the average
+    total_bytes: usize,
+}
+
+impl SerializedSize {
+    /// Average serialized size (in bytes) of the samples
+    pub fn average_bytes(&self) -> f32 {
+        self.total_bytes as f32 / self.num_samples as f32
+    }
+}
+
+/// Report of the serialized size of registered components and messages, computed from sample values.
+///
+/// Multiple samples can be provided for the same type to get the typical and worst-case sizes.
+#[derive(Debug, Default, Clone)]
+pub struct SerializedSizeReport {
+    entries: Vec<SerializedSize>,
+}
+
+impl SerializedSizeReport {
+    /// Serialize the `sample` component using the [`ComponentRegistry`] and record its size.
+    ///
+    /// Returns the number of bytes of the serialized sample.
+    pub fn add_component<C: Component>(
+        &mut self,
+        registry: &ComponentRegistry,
+        mut sample: C,
+    ) -> Result<usize, ComponentError> {
+        let mut writer = Writer::default();
+        registry.serialize(&mut sample, &mut writer, None)?;
+        let num_bytes = writer.to_bytes().len();
+        self.record(
+            std::any::type_name::<C>(),
+            ProtocolTypeCategory::Component,
+            num_bytes,
+        );
+        Ok(num_bytes)
+    }
+
+    /// Serialize the `sample` message using the [`MessageRegistry`] and record its size.
+    ///
+    /// Returns the number of bytes of the serialized sample.
+    pub fn add_message<M: Message>(
+        &mut self,
+        registry: &MessageRegistry,
+        sample: &M,
+    ) -> Result<usize, MessageError> {
+        let mut writer = Writer::default();
+        registry.serialize(sample, &mut writer, None)?;
+        let num_bytes = writer.to_bytes().len();
+        self.record(
+            std::any::type_name::<M>(),
+            ProtocolTypeCategory::Message,
+            num_bytes,
+        );
+        Ok(num_bytes)
+    }
+
+    /// Get the size statistics for the type `T`
+    pub fn get<T: 'static>(&self) -> Option<&SerializedSize> {
+        let name = std::any::type_name::<T>();
+        self.entries.iter().find(|entry| entry.name == name)
+    }
+
+    /// Iterate through the size statistics of all the types in the report
+    pub fn iter(&self) -> impl Iterator<Item = &SerializedSize> {
+        self.entries.iter()
+    }
+
+    /// Log the size statistics of all the types in the report
+    pub fn log(&self) {
+        for entry in self.entries.iter() {
+            info!(
+                name = entry.name,
+                category = ?entry.category,
+                samples = entry.num_samples,
+                min_bytes = entry.min_bytes,
+                max_bytes = entry.max_bytes,
+                average_bytes = entry.average_bytes(),
+                "Serialized size"
+            );
+        }
+    }
+
+    fn record(&mut self, name: &'static str, category: ProtocolTypeCategory, num_bytes: usize) {
+        match self.entries.iter_mut().find(|entry| entry.name == name) {
+            Some(entry) => {
+                entry.num_samples += 1;
+                entry.min_bytes = entry.min_bytes.min(num_bytes);
+                entry.max_bytes = entry.max_bytes.max(num_bytes);
+                entry.total_bytes += num_bytes;
+            }
+            None => self.entries.push(SerializedSize {
+                name,
+                category,
+                num_samples: 1,
+                min_bytes: num_bytes,
+                max_bytes: num_bytes,
+                total_bytes: num_bytes,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::message::MessageType;
+    use crate::tests::protocol::*;
+
+    #[test]
+    fn test_serialized_size_report() {
+        let mut component_registry = ComponentRegistry::default();
+        component_registry.register_component::<ComponentSyncModeFull>();
+        let mut message_registry = MessageRegistry::default();
+        message_registry.add_message::<StringMessage>(MessageType::Normal);
+
+        let mut report = SerializedSizeReport::default();
+        let component_size = report
+            .add_component(&component_registry, ComponentSyncModeFull(1.0))
+            .unwrap();
+        let short_size = report
+            .add_message(&message_registry, &StringMessage("a".to_string()))
+            .unwrap();
+        let long_size = report
+            .add_message(&message_registry, &StringMessage("abcdef".to_string()))
+            .unwrap();
+
+        // check that the sizes match a manual serialization
+        let mut writer = Writer::default();
+        component_registry
+            .serialize(&mut ComponentSyncModeFull(1.0), &mut writer, None)
+            .unwrap();
+        assert_eq!(component_size, writer.to_bytes().len());
+        let mut writer = Writer::default();
+        message_registry
+            .serialize(&StringMessage("abcdef".to_string()), &mut writer, None)
+            .unwrap();
+        assert_eq!(long_size, writer.to_bytes().len());
+
+        let component_entry = report.get::<ComponentSyncModeFull>().unwrap();
+        assert_eq!(component_entry.category, ProtocolTypeCategory::Component);
+        assert_eq!(component_entry.num_samples, 1);
+        assert_eq!(component_entry.max_bytes, component_size);
+
+        let message_entry = report.get::<StringMessage>().unwrap();
+        assert_eq!(message_entry.category, ProtocolTypeCategory::Message);
+        assert_eq!(message_entry.num_samples, 2);
+        assert_eq!(message_entry.min_bytes, short_size);
+        assert_eq!(message_entry.max_bytes, long_size);
+        assert_eq!(
+            message_entry.average_bytes(),
+            (short_size + long_size) as f32 / 2.0
+        );
+    }
+}
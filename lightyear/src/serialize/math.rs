@@ -0,0 +1,202 @@
+//! [`ToBytes`] implementations for common bevy math types, that can be used in custom serialization
+//! functions (see [`AppSerializeExt`](crate::prelude::AppSerializeExt))
+//!
+//! The floats are written in full precision. Rotations can be written with the lossy [`CompactQuat`]
+//! encoding, which uses 4 bytes instead of 16.
+use std::f32::consts::FRAC_1_SQRT_2;
+
+use bevy::math::{Quat, Vec2, Vec3};
+use byteorder::{NetworkEndian, ReadBytesExt, WriteBytesExt};
+
+use crate::serialize::reader::Reader;
+use crate::serialize::{SerializationError, ToBytes};
+
+impl ToBytes for Vec2 {
+    fn len(&self) -> usize {
+        8
+    }
+
+    fn to_bytes<T: WriteBytesExt>(&self, buffer: &mut T) -> Result<(), SerializationError> {
+        buffer.write_f32::<NetworkEndian>(self.x)?;
+        buffer.write_f32::<NetworkEndian>(self.y)?;
+        Ok(())
+    }
+
+    fn from_bytes(buffer: &mut Reader) -> Result<Self, SerializationError>
+    where
+        Self: Sized,
+    {
+        Ok(Vec2::new(
+            buffer.read_f32::<NetworkEndian>()?,
+            buffer.read_f32::<NetworkEndian>()?,
+        ))
+    }
+}
+
+impl ToBytes for Vec3 {
+    fn len(&self) -> usize {
+        12
+    }
+
+    fn to_bytes<T: WriteBytesExt>(&self, buffer: &mut T) -> Result<(), SerializationError> {
+        buffer.write_f32::<NetworkEndian>(self.x)?;
+        buffer.write_f32::<NetworkEndian>(self.y)?;
+        buffer.write_f32::<NetworkEndian>(self.z)?;
+        Ok(())
+    }
+
+    fn from_bytes(buffer: &mut Reader) -> Result<Self, SerializationError>
+    where
+        Self: Sized,
+    {
+        Ok(Vec3::new(
+            buffer.read_f32::<NetworkEndian>()?,
+            buffer.read_f32::<NetworkEndian>()?,
+            buffer.read_f32::<NetworkEndian>()?,
+        ))
+    }
+}
+
+impl ToBytes for Quat {
+    fn len(&self) -> usize {
+        16
+    }
+
+    fn to_bytes<T: WriteBytesExt>(&self, buffer: &mut T) -> Result<(), SerializationError> {
+        for value in self.to_array() {
+            buffer.write_f32::<NetworkEndian>(value)?;
+        }
+        Ok(())
+    }
+
+    fn from_bytes(buffer: &mut Reader) -> Result<Self, SerializationError>
+    where
+        Self: Sized,
+    {
+        Ok(Quat::from_xyzw(
+            buffer.read_f32::<NetworkEndian>()?,
+            buffer.read_f32::<NetworkEndian>()?,
+            buffer.read_f32::<NetworkEndian>()?,
+            buffer.read_f32::<NetworkEndian>()?,
+        ))
+    }
+}
+
+/// Number of bits used to encode each of the three smallest components of a [`CompactQuat`]
+const COMPACT_QUAT_BITS: u32 = 10;
+const COMPACT_QUAT_MAX: u32 = (1 << COMPACT_QUAT_BITS) - 1;
+
+/// Lossy encoding of a unit [`Quat`] in 4 bytes, using the 'smallest three' method.
+///
+/// We only write the index of the largest component (2 bits) and the three other components
+/// (10 bits each): the largest component can be recomputed because the quaternion is normalized.
+/// Each of the three smaller components is in the range `[-1/sqrt(2), 1/sqrt(2)]`, so the precision
+/// per component is about `0.0014`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CompactQuat(pub Quat);
+
+impl ToBytes for CompactQuat {
+    fn len(&self) -> usize {
+        4
+    }
+
+    fn to_bytes<T: WriteBytesExt>(&self, buffer: &mut T) -> Result<(), SerializationError> {
+        let mut components = self.0.normalize().to_array();
+        let (largest, _) = components
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.abs().total_cmp(&b.abs()))
+            .unwrap();
+        // q and -q represent the same rotation, so we can make sure that the largest component is positive
+        if components[largest] < 0.0 {
+            components.iter_mut().for_each(|c| *c = -*c);
+        }
+        let mut packed = largest as u32;
+        for (i, component) in components.into_iter().enumerate() {
+            if i == largest {
+                continue;
+            }
+            let normalized = (component / FRAC_1_SQRT_2).clamp(-1.0, 1.0) * 0.5 + 0.5;
+            let quantized = (normalized * COMPACT_QUAT_MAX as f32).round() as u32;
+            packed = (packed << COMPACT_QUAT_BITS) | quantized;
+        }
+        buffer.write_u32::<NetworkEndian>(packed)?;
+        Ok(())
+    }
+
+    fn from_bytes(buffer: &mut Reader) -> Result<Self, SerializationError>
+    where
+        Self: Sized,
+    {
+        let packed = buffer.read_u32::<NetworkEndian>()?;
+        let largest = (packed >> (3 * COMPACT_QUAT_BITS)) as usize;
+        let mut components = [0.0; 4];
+        let mut sum_squares = 0.0;
+        let mut shift = 3 * COMPACT_QUAT_BITS;
+        for (i, component) in components.iter_mut().enumerate() {
+            if i == largest {
+                continue;
+            }
+            shift -= COMPACT_QUAT_BITS;
+            let quantized = (packed >> shift) & COMPACT_QUAT_MAX;
+            *component = ((quantized as f32 / COMPACT_QUAT_MAX as f32) * 2.0 - 1.0) * FRAC_1_SQRT_2;
+            sum_squares += *component * *component;
+        }
+        components[largest] = (1.0 - sum_squares).max(0.0).sqrt();
+        Ok(CompactQuat(Quat::from_array(components).normalize()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::{Entity, EulerRot};
+
+    use super::*;
+    use crate::serialize::writer::Writer;
+
+    fn round_trip<M: ToBytes>(value: &M) -> (M, usize) {
+        let mut writer = Writer::default();
+        value.to_bytes(&mut writer).unwrap();
+        let bytes = writer.to_bytes();
+        assert_eq!(bytes.len(), value.len());
+        let mut reader = Reader::from(bytes.clone());
+        (M::from_bytes(&mut reader).unwrap(), bytes.len())
+    }
+
+    #[test]
+    fn test_serialize_vec() {
+        let vec2 = Vec2::new(1.5, -2.25);
+        assert_eq!(round_trip(&vec2).0, vec2);
+        let vec3 = Vec3::new(1.5, -2.25, 1000.125);
+        assert_eq!(round_trip(&vec3).0, vec3);
+    }
+
+    #[test]
+    fn test_serialize_quat() {
+        let quat = Quat::from_euler(EulerRot::XYZ, 0.3, -1.2, 2.5);
+        assert_eq!(round_trip(&quat).0, quat);
+    }
+
+    #[test]
+    fn test_serialize_entity() {
+        let entity = Entity::from_raw(1234);
+        assert_eq!(round_trip(&entity).0, entity);
+    }
+
+    /// Check that the compact quaternion encoding uses 4 bytes, and restores the rotation with a small error
+    #[test]
+    fn test_serialize_compact_quat() {
+        for quat in [
+            Quat::IDENTITY,
+            Quat::from_euler(EulerRot::XYZ, 0.3, -1.2, 2.5),
+            Quat::from_euler(EulerRot::YXZ, -3.0, 0.7, -0.1),
+            // the largest component is negative
+            -Quat::from_rotation_z(0.5),
+        ] {
+            let (CompactQuat(read), size) = round_trip(&CompactQuat(quat));
+            assert_eq!(size, 4);
+            // q and -q are the same rotation
+            assert!(read.dot(quat).abs() > 0.9999, "{quat:?} != {read:?}");
+        }
+    }
+}
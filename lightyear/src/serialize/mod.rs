@@ -7,6 +7,7 @@ use bytes::Bytes;
 use hashbrown::HashMap;
 use std::hash::{BuildHasher, Hash};
 
+pub mod math;
 pub mod reader;
 pub(crate) mod varint;
 pub mod writer;
@@ -132,7 +133,8 @@ bevy::utils::all_tuples!(impl_tuple_query_data, 1, 8, P);
 
 impl<M: ToBytes> ToBytes for Vec<M> {
     fn len(&self) -> usize {
-        varint_len(self.len() as u64) + self.iter().map(ToBytes::len).sum::<usize>()
+        // the number of items is written as a u64
+        8 + self.iter().map(ToBytes::len).sum::<usize>()
     }
 
     fn to_bytes<T: WriteBytesExt>(&self, buffer: &mut T) -> Result<(), SerializationError> {
@@ -157,7 +159,8 @@ impl<M: ToBytes> ToBytes for Vec<M> {
 
 impl<K: ToBytes + Eq + Hash, V: ToBytes, S: Default + BuildHasher> ToBytes for HashMap<K, V, S> {
     fn len(&self) -> usize {
-        varint_len(self.len() as u64) + self.iter().map(|(k, v)| k.len() + v.len()).sum::<usize>()
+        // the number of entries is written as a u64
+        8 + self.iter().map(|(k, v)| k.len() + v.len()).sum::<usize>()
     }
 
     fn to_bytes<T: WriteBytesExt>(&self, buffer: &mut T) -> Result<(), SerializationError> {
@@ -189,6 +192,7 @@ impl<K: ToBytes + Eq + Hash, V: ToBytes, S: Default + BuildHasher> ToBytes for H
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::protocol::registry::NetId;
     use crate::serialize::writer::Writer;
 
     #[test]
@@ -201,4 +205,18 @@ mod tests {
         let read = Bytes::from_bytes(&mut reader).unwrap();
         assert_eq!(a, read);
     }
+
+    #[test]
+    fn test_serialize_hashmap_len() {
+        let map: HashMap<NetId, Bytes> =
+            HashMap::from_iter([(1, vec![7; 10].into()), (300, vec![8; 200].into())]);
+        let mut writer = Writer::with_capacity(5);
+        map.to_bytes(&mut writer).unwrap();
+        let bytes = writer.to_bytes();
+        assert_eq!(ToBytes::len(&map), bytes.len());
+
+        let mut reader = Reader::from(bytes);
+        let read = HashMap::<NetId, Bytes>::from_bytes(&mut reader).unwrap();
+        assert_eq!(map, read);
+    }
 }
@@ -47,7 +47,7 @@ pub trait VarIntWriteExt: WriteBytesExt {
                 self.write_u32::<NetworkEndian>(val)?;
             }
             8 => {
-                let val = value | 0xc0_0000_0000_0000;
+                let val = value | 0xc000_0000_0000_0000;
                 self.write_u64::<NetworkEndian>(val)?;
             }
             _ => return Err(std::io::Error::other("value is too large for varint").into()),
@@ -145,4 +145,19 @@ mod tests {
         let read_val = reader.read_varint().unwrap();
         assert_eq!(val, read_val);
     }
+
+    #[test]
+    fn test_varint_len_8() {
+        let mut writer = vec![];
+
+        let val = 1_073_741_824;
+        writer.write_varint(val).unwrap();
+        assert_eq!(writer.len(), 8);
+        // the first 2 bits are the tag of the 8-byte encoding
+        assert_eq!(writer[0] >> 6, 0b11);
+
+        let mut reader = Cursor::new(writer);
+        let read_val = reader.read_varint().unwrap();
+        assert_eq!(val, read_val);
+    }
 }
@@ -1,14 +1,17 @@
 //! Defines server-specific configuration options
 use bevy::prelude::Resource;
+use bevy::utils::Duration;
 use governor::Quota;
 use nonzero_ext::nonzero;
 use std::sync::Arc;
 
-use crate::connection::netcode::{Key, PRIVATE_KEY_BYTES};
+use crate::channel::builder::WrongDirectionPolicy;
+use crate::connection::netcode::{Key, MAX_CLIENTS, PRIVATE_KEY_BYTES};
 use crate::connection::server::{
     ConnectionRequestHandler, DefaultConnectionRequestHandler, NetConfig,
 };
 use crate::prelude::ReplicationConfig;
+use crate::server::input::ServerInputConfig;
 use crate::shared::config::SharedConfig;
 use crate::shared::ping::manager::PingConfig;
 
@@ -20,6 +23,9 @@ pub struct NetcodeConfig {
     /// This is valid for tokens generated by the server.
     /// The default is 3 seconds. A negative value means no timeout.
     pub client_timeout_secs: i32,
+    /// Maximum number of clients that can be performing the connection handshake at the same time.
+    /// Connection requests from other clients are ignored until a handshake completes or times out.
+    pub max_pending_connections: usize,
     pub protocol_id: u64,
     pub private_key: Key,
     /// A closure that will be used to accept or reject incoming connections
@@ -32,6 +38,7 @@ impl Default for NetcodeConfig {
             num_disconnect_packets: 10,
             keep_alive_send_rate: 1.0 / 10.0,
             client_timeout_secs: 3,
+            max_pending_connections: MAX_CLIENTS,
             protocol_id: 0,
             private_key: [0; PRIVATE_KEY_BYTES],
             connection_request_handler: Arc::new(DefaultConnectionRequestHandler),
@@ -53,6 +60,11 @@ impl NetcodeConfig {
         self.client_timeout_secs = client_timeout_secs;
         self
     }
+
+    pub fn with_max_pending_connections(mut self, max_pending_connections: usize) -> Self {
+        self.max_pending_connections = max_pending_connections;
+        self
+    }
 }
 
 /// Configuration related to sending packets
@@ -67,6 +79,28 @@ pub struct PacketConfig {
     pub per_client_send_bandwidth_cap: Quota,
     /// If false, there is no bandwidth cap and all messages are sent as soon as possible
     pub bandwidth_cap_enabled: bool,
+    /// What to do when trying to send a message on a channel whose [`ChannelDirection`](crate::prelude::ChannelDirection)
+    /// does not allow it
+    pub wrong_direction_policy: WrongDirectionPolicy,
+    /// If true, keep a copy of the raw bytes of the last packet sent and received on each connection,
+    /// which can be inspected via the `ConnectionManager` to debug protocol mismatches.
+    ///
+    /// This is disabled by default because it copies every packet.
+    pub capture_packets: bool,
+    /// If true, include in the header of every packet the time elapsed since the start of the current tick
+    /// (as a fraction of the tick duration), so that the receiver can estimate more precisely when the
+    /// packet was sent.
+    ///
+    /// This costs 1 extra byte per packet. It is useful on the server, so that the clients can place the
+    /// received updates more accurately on their interpolation timeline.
+    pub send_sub_tick_fraction: bool,
+    /// If set, a packet that only contains the acks is sent at most once per interval when there is nothing
+    /// else to send, so that the remote still receives acks while this peer is idle.
+    ///
+    /// Enable it if the remote uses a [`backpressure_timeout`](crate::prelude::ReplicationConfig::backpressure_timeout),
+    /// otherwise the remote pauses its replication updates while this peer is idle.
+    /// Disabled by default.
+    pub ack_packet_interval: Option<Duration>,
 }
 
 impl Default for PacketConfig {
@@ -76,6 +110,10 @@ impl Default for PacketConfig {
             // 56 KB/s bandwidth cap
             per_client_send_bandwidth_cap: Quota::per_second(nonzero!(56000u32)),
             bandwidth_cap_enabled: false,
+            wrong_direction_policy: WrongDirectionPolicy::default(),
+            capture_packets: false,
+            send_sub_tick_fraction: false,
+            ack_packet_interval: None,
         }
     }
 }
@@ -96,6 +134,21 @@ impl PacketConfig {
         self.bandwidth_cap_enabled = true;
         self
     }
+
+    pub fn enable_packet_capture(mut self) -> Self {
+        self.capture_packets = true;
+        self
+    }
+
+    pub fn enable_sub_tick_fraction(mut self) -> Self {
+        self.send_sub_tick_fraction = true;
+        self
+    }
+
+    pub fn with_ack_packet_interval(mut self, interval: Duration) -> Self {
+        self.ack_packet_interval = Some(interval);
+        self
+    }
 }
 
 /// Configuration for the server plugin.
@@ -113,12 +166,16 @@ pub struct ServerConfig {
     pub packet: PacketConfig,
     pub replication: ReplicationConfig,
     pub ping: PingConfig,
+    pub input: ServerInputConfig,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::client::config::ClientConfig;
     use crate::client::networking::NetworkingState;
+    use crate::connection::client::Authentication;
+    use crate::connection::netcode::{ConnectToken, USER_DATA_BYTES};
     use crate::connection::server::DeniedReason;
     use crate::prelude::ClientId;
 
@@ -171,4 +228,82 @@ mod tests {
             &NetworkingState::Disconnected
         );
     }
+
+    /// Rejects clients whose `ConnectToken` user data marks them as banned
+    #[derive(Debug, Clone)]
+    struct BanListConnectionRequestHandler;
+
+    impl ConnectionRequestHandler for BanListConnectionRequestHandler {
+        fn handle_netcode_request(
+            &self,
+            client_id: ClientId,
+            user_data: &[u8; USER_DATA_BYTES],
+        ) -> Option<DeniedReason> {
+            (user_data[0] == 1).then_some(DeniedReason::Banned)
+        }
+    }
+
+    #[test]
+    fn test_connection_request_handler_user_data() {
+        let mut stepper = BevyStepper::default();
+        stepper.stop();
+        for netconfig in &mut stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ServerConfig>()
+            .net
+        {
+            netconfig.set_connection_request_handler(Arc::new(BanListConnectionRequestHandler));
+        }
+
+        // the default token has empty user data: the client is accepted
+        stepper.start();
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .resource::<State<NetworkingState>>()
+                .get(),
+            &NetworkingState::Connected
+        );
+        stepper.stop();
+
+        // use a token whose user data marks the client as banned
+        let mut client_config = stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<ClientConfig>();
+        let crate::connection::client::NetConfig::Netcode { auth, .. } = &mut client_config.net
+        else {
+            unreachable!()
+        };
+        let Authentication::Manual {
+            server_addr,
+            client_id,
+            private_key,
+            protocol_id,
+        } = *auth
+        else {
+            unreachable!()
+        };
+        let mut user_data = [0; USER_DATA_BYTES];
+        user_data[0] = 1;
+        *auth = Authentication::Token(
+            ConnectToken::build(server_addr, protocol_id, client_id, private_key)
+                .user_data(user_data)
+                .generate()
+                .unwrap(),
+        );
+
+        // the client never reaches the connected state
+        stepper.start();
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .resource::<State<NetworkingState>>()
+                .get(),
+            &NetworkingState::Disconnected
+        );
+    }
 }
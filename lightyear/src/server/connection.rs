@@ -3,19 +3,25 @@ use bevy::ecs::component::Tick as BevyTick;
 use bevy::ecs::entity::{EntityHash, MapEntities};
 use bevy::prelude::{Component, Entity, Resource, World};
 use bevy::ptr::Ptr;
-use bevy::utils::{Duration, HashMap};
+use bevy::utils::{Duration, HashMap, HashSet};
 use bytes::Bytes;
+use governor::clock::{Clock, FakeRelativeClock};
+use governor::middleware::NoOpMiddleware;
+use governor::state::{InMemoryState, NotKeyed};
+use governor::{Quota, RateLimiter};
 use hashbrown::hash_map::Entry;
-use tracing::{debug, info, info_span, trace, trace_span};
+use tracing::{debug, error, info, info_span, trace, trace_span};
 #[cfg(feature = "trace")]
 use tracing::{instrument, Level};
 
 use crate::channel::builder::{
-    EntityActionsChannel, EntityUpdatesChannel, PingChannel, PongChannel,
+    EntityActionsChannel, EntityUpdatesChannel, PingChannel, PongChannel, SnapshotChannel,
+    WrongDirectionPolicy,
 };
 
 use crate::channel::receivers::ChannelReceive;
 use crate::channel::senders::ChannelSend;
+use crate::channel::stats::ChannelStats;
 use crate::client::message::ClientMessage;
 use crate::connection::id::ClientId;
 use crate::connection::netcode::MAX_PACKET_SIZE;
@@ -30,7 +36,9 @@ use crate::protocol::channel::ChannelRegistry;
 use crate::protocol::component::{
     ComponentError, ComponentKind, ComponentNetId, ComponentRegistry,
 };
-use crate::protocol::message::{MessageError, MessageRegistry, MessageType};
+use crate::protocol::message::{
+    MessageError, MessageRegistry, MessageType, UnknownMessage, SCHEDULED_MESSAGE_NET_ID,
+};
 use crate::protocol::registry::NetId;
 use crate::serialize::reader::Reader;
 use crate::serialize::writer::Writer;
@@ -39,12 +47,14 @@ use crate::server::config::PacketConfig;
 use crate::server::error::ServerError;
 use crate::server::events::{ConnectEvent, ServerEvents};
 use crate::server::relevance::error::RelevanceError;
+use crate::server::replication::send::ReplicationWorldId;
 use crate::shared::events::connection::ConnectionEvents;
+use crate::shared::input::ViewDelay;
 use crate::shared::message::MessageSend;
 use crate::shared::ping::manager::{PingConfig, PingManager};
 use crate::shared::ping::message::{Ping, Pong};
 use crate::shared::replication::components::ReplicationGroupId;
-use crate::shared::replication::delta::DeltaManager;
+use crate::shared::replication::delta::{DeltaCompressionStats, DeltaManager};
 use crate::shared::replication::network_target::NetworkTarget;
 use crate::shared::replication::receive::ReplicationReceiver;
 use crate::shared::replication::send::ReplicationSender;
@@ -69,13 +79,56 @@ pub struct ConnectionManager {
     // (we want to keep track of them because we need to replicate the entire world state to them)
     pub(crate) new_clients: Vec<ClientId>,
     pub(crate) writer: Writer,
+    /// Components that have been replicated at least once since the server started
+    pub(crate) replicated_components: HashSet<ComponentKind>,
 
     // CONFIG
     replication_config: ReplicationConfig,
     packet_config: PacketConfig,
     ping_config: PingConfig,
+    /// Maximum number of bytes in a packet
+    mtu: usize,
+    /// Clock used by the message rate limiters
+    pub(crate) rate_limit_clock: RateLimitClock,
+}
+
+/// Clock used by the message rate limiters.
+///
+/// It measures the wall-clock time by default, but a manually-advanced clock can be injected
+/// instead (for example so that the tests don't depend on the wall-clock time).
+#[derive(Debug, Clone)]
+pub(crate) enum RateLimitClock {
+    /// Measures the time elapsed since the given instant
+    Real(bevy::utils::Instant),
+    /// Only advances when [`FakeRelativeClock::advance`] is called
+    Manual(FakeRelativeClock),
+}
+
+impl Default for RateLimitClock {
+    fn default() -> Self {
+        Self::Real(bevy::utils::Instant::now())
+    }
+}
+
+impl Clock for RateLimitClock {
+    type Instant = Duration;
+
+    fn now(&self) -> Self::Instant {
+        match self {
+            RateLimitClock::Real(start) => start.elapsed(),
+            RateLimitClock::Manual(clock) => clock.now().into(),
+        }
+    }
 }
 
+/// Rate limiter used to limit the number of messages of a given type that a client can send
+pub(crate) type MessageRateLimiter = RateLimiter<
+    NotKeyed,
+    InMemoryState,
+    RateLimitClock,
+    NoOpMiddleware<<RateLimitClock as Clock>::Instant>,
+>;
+
 // This is useful in cases where we need to temporarily store a fake ConnectionManager
 impl Default for ConnectionManager {
     fn default() -> Self {
@@ -85,6 +138,7 @@ impl Default for ConnectionManager {
             ReplicationConfig::default(),
             PacketConfig::default(),
             PingConfig::default(),
+            MAX_PACKET_SIZE,
         )
     }
 }
@@ -96,6 +150,7 @@ impl ConnectionManager {
         replication_config: ReplicationConfig,
         packet_config: PacketConfig,
         ping_config: PingConfig,
+        mtu: usize,
     ) -> Self {
         Self {
             connections: HashMap::default(),
@@ -105,9 +160,12 @@ impl ConnectionManager {
             delta_manager: DeltaManager::default(),
             new_clients: vec![],
             writer: Writer::with_capacity(MAX_PACKET_SIZE),
+            replicated_components: HashSet::default(),
             replication_config,
             packet_config,
             ping_config,
+            mtu,
+            rate_limit_clock: RateLimitClock::default(),
         }
     }
 
@@ -143,7 +201,21 @@ impl ConnectionManager {
         message: &mut M,
         target: NetworkTarget,
     ) -> Result<(), ServerError> {
-        self.erased_send_message_to_target(message, ChannelKind::of::<C>(), target)
+        self.erased_send_message_to_target(message, ChannelKind::of::<C>(), target, None)
+    }
+
+    /// Queues up a message to be sent to all clients matching the specific [`NetworkTarget`].
+    ///
+    /// The clients will hold the message until their own tick reaches `tick`, and only then emit the
+    /// [`MessageEvent`](crate::client::events::MessageEvent). If the message arrives after `tick`,
+    /// it is processed immediately.
+    pub fn send_message_at_tick<C: Channel, M: Message>(
+        &mut self,
+        message: &mut M,
+        target: NetworkTarget,
+        tick: Tick,
+    ) -> Result<(), ServerError> {
+        self.erased_send_message_to_target(message, ChannelKind::of::<C>(), target, Some(tick))
     }
 
     /// Send a message to all clients in a room
@@ -188,6 +260,252 @@ impl ConnectionManager {
         Ok(())
     }
 
+    /// Update the priority of the `ReplicationGroup` of an entity, for all the clients that replicate it.
+    ///
+    /// This can be called every frame, for example to give a higher priority to the entities that are
+    /// close to the camera. The new priority is used the next time the priority of the group is accumulated;
+    /// the priority that the group already accumulated is kept until the group is sent.
+    /// Note that the priority is shared by all the entities of the group.
+    pub fn set_replication_priority(&mut self, entity: Entity, priority: f32) {
+        for connection in self.connections.values_mut() {
+            if let Some(group_id) = connection.replication_sender.entity_group_id(entity) {
+                connection
+                    .replication_sender
+                    .update_base_priority(group_id, priority);
+            }
+        }
+    }
+
+    /// Update the importance of a `ReplicationGroup` for a given client.
+    ///
+    /// The importance is specific to each client (for example it can be computed from the distance
+    /// between the entity and the entity controlled by the client) and multiplies the priority of the group.
+    /// When the bandwidth is limited, the groups that are the most important for a client are sent first.
+    pub fn update_importance(
+        &mut self,
+        replication_group_id: ReplicationGroupId,
+        client_id: ClientId,
+        importance: f32,
+    ) -> Result<(), ServerError> {
+        trace!(
+            ?client_id,
+            ?replication_group_id,
+            "Set importance to {:?}",
+            importance
+        );
+        self.connection_mut(client_id)?
+            .replication_sender
+            .update_importance(replication_group_id, importance);
+        Ok(())
+    }
+
+    /// Force a full resync of the replicated state of a single client, for example if the state of
+    /// the client is detected as corrupted.
+    ///
+    /// All the entities (and resources) replicated to the client are sent again with all their replicated
+    /// components, as if the client just connected, and the acknowledgement state of the replication groups
+    /// is reset so that the following updates are not computed from a state that was previously acked by the client.
+    /// The other clients are not affected.
+    ///
+    /// The client should use the default [`DuplicateSpawnPolicy::Resync`](crate::prelude::DuplicateSpawnPolicy)
+    /// so that the components contained in the re-sent spawns are applied to the existing entities.
+    pub fn resync_client(&mut self, client_id: ClientId) -> Result<(), ServerError> {
+        info!(?client_id, "Resyncing client");
+        self.connection_mut(client_id)?
+            .replication_sender
+            .reset_acks();
+        if !self.new_clients.contains(&client_id) {
+            self.new_clients.push(client_id);
+        }
+        Ok(())
+    }
+
+    /// Change the bandwidth quota used to send messages to a given client.
+    ///
+    /// This only has an effect if the bandwidth cap is enabled in the [`PacketConfig`].
+    pub fn set_bandwidth_cap(
+        &mut self,
+        client_id: ClientId,
+        quota: Quota,
+    ) -> Result<(), ServerError> {
+        self.connection_mut(client_id)?
+            .message_manager
+            .priority_manager
+            .set_bandwidth_quota(quota);
+        Ok(())
+    }
+
+    /// Temporarily raise the bandwidth cap of a given client, for example to quickly send the initial
+    /// state of the world while the client is loading.
+    ///
+    /// If `quota` is None, the bandwidth cap is removed for the duration of the boost.
+    /// The configured bandwidth cap is restored after `duration`, or when [`end_bandwidth_boost`](Self::end_bandwidth_boost)
+    /// is called (for example once the client notifies the server that it finished loading) if `duration` is None.
+    ///
+    /// This only has an effect if the bandwidth cap is enabled in the [`PacketConfig`].
+    pub fn boost_bandwidth_cap(
+        &mut self,
+        client_id: ClientId,
+        quota: Option<Quota>,
+        duration: Option<Duration>,
+    ) -> Result<(), ServerError> {
+        debug!(?client_id, ?quota, ?duration, "Boost bandwidth cap");
+        self.connection_mut(client_id)?
+            .message_manager
+            .priority_manager
+            .boost_bandwidth(quota, duration);
+        Ok(())
+    }
+
+    /// Restore the configured bandwidth cap of a client whose cap was raised with [`boost_bandwidth_cap`](Self::boost_bandwidth_cap)
+    pub fn end_bandwidth_boost(&mut self, client_id: ClientId) -> Result<(), ServerError> {
+        self.connection_mut(client_id)?
+            .message_manager
+            .priority_manager
+            .end_bandwidth_boost();
+        Ok(())
+    }
+
+    /// Returns true if the bandwidth cap of the client is currently raised by [`boost_bandwidth_cap`](Self::boost_bandwidth_cap)
+    pub fn is_bandwidth_boosted(&self, client_id: ClientId) -> Result<bool, ServerError> {
+        Ok(self
+            .connection(client_id)?
+            .message_manager
+            .priority_manager
+            .is_bandwidth_boosted())
+    }
+
+    /// Returns true if the replication updates to the client are paused because it stopped acking packets.
+    ///
+    /// See [`ReplicationConfig::backpressure_timeout`](crate::prelude::ReplicationConfig::backpressure_timeout)
+    pub fn is_replication_paused(&self, client_id: ClientId) -> Result<bool, ServerError> {
+        Ok(self
+            .connection(client_id)?
+            .replication_sender
+            .updates_paused)
+    }
+
+    /// Returns the entities that currently belong to a `ReplicationGroup` replicated to a given client.
+    ///
+    /// This can be useful for debugging, or to apply some logic to all the entities of a group.
+    pub fn group_entities(
+        &self,
+        replication_group_id: ReplicationGroupId,
+        client_id: ClientId,
+    ) -> Result<Vec<Entity>, ServerError> {
+        Ok(self
+            .connection(client_id)?
+            .replication_sender
+            .group_entities(replication_group_id))
+    }
+
+    /// Returns the latest tick at which the client acknowledged the replication group of the entity.
+    ///
+    /// Every replication update for the entity's group sent up to this tick has been received by the client,
+    /// so this can be used to wait until the client has received some state before acting on it.
+    /// Returns `None` if the entity is not replicated to the client, or if nothing was acknowledged yet.
+    pub fn replication_group_acked_tick(
+        &self,
+        entity: Entity,
+        client_id: ClientId,
+    ) -> Result<Option<Tick>, ServerError> {
+        let replication_sender = &self.connection(client_id)?.replication_sender;
+        Ok(replication_sender
+            .entity_group_id(entity)
+            .and_then(|group_id| replication_sender.group_acked_tick(group_id)))
+    }
+
+    /// Assign the client to a [`ReplicationWorldId`].
+    ///
+    /// The client will only receive the entities that belong to the same world.
+    /// The world should be assigned as soon as the client connects: entities that were already
+    /// replicated to the client are not despawned if the client moves to a different world.
+    pub fn set_client_world(
+        &mut self,
+        client_id: ClientId,
+        world_id: ReplicationWorldId,
+    ) -> Result<(), ServerError> {
+        self.connection_mut(client_id)?.world_id = world_id;
+        Ok(())
+    }
+
+    /// Return the estimated number of ticks that the client's simulation is ahead of the server's
+    /// simulation (negative if the client is behind).
+    ///
+    /// The offset is computed from the tick of the latest packet received from the client and
+    /// the RTT measured by the pings. Returns `None` if the client doesn't exist or if no packet has been received yet.
+    pub fn client_tick_offset(&self, client_id: ClientId) -> Option<i16> {
+        self.connection(client_id).ok()?.client_tick_offset
+    }
+
+    /// Return the time at which the client saw the interpolated entities when it sent the inputs that
+    /// the server applies at `tick`: `tick - input_delay - interpolation_delay`.
+    ///
+    /// The time is returned as a tick and an overstep (fraction of a tick in `[0, 1)`) after that tick.
+    /// The delays are the ones sent by the client in its latest [`ViewDelay`]; until one is received
+    /// (or for the local client in host-server mode), the client is assumed to see the current tick.
+    /// Returns `None` if the client doesn't exist.
+    pub fn client_view_tick(&self, client_id: ClientId, tick: Tick) -> Option<(Tick, f32)> {
+        let Some(view_delay) = self.connection(client_id).ok()?.view_delay else {
+            return Some((tick, 0.0));
+        };
+        let delay_ticks =
+            view_delay.input_delay_ticks as f32 + view_delay.interpolation_delay_ticks.max(0.0);
+        let ticks_back = delay_ticks.ceil();
+        Some((tick - ticks_back as u16, ticks_back - delay_ticks))
+    }
+
+    /// Return the latest estimate of the round-trip time to the client.
+    ///
+    /// Returns `Duration::ZERO` until the first pong is received from the client.
+    pub fn rtt(&self, client_id: ClientId) -> Result<Duration, ServerError> {
+        self.connection(client_id).map(|c| c.rtt())
+    }
+
+    /// Return the latest estimate of the jitter of the connection to the client.
+    ///
+    /// Returns `Duration::ZERO` until the first pong is received from the client.
+    pub fn jitter(&self, client_id: ClientId) -> Result<Duration, ServerError> {
+        self.connection(client_id).map(|c| c.jitter())
+    }
+
+    /// Return how many delta-compressed updates of the component `C` were sent to the client
+    /// as incremental diffs vs full diffs from the base value.
+    ///
+    /// See [`DeltaCompressionStats`] for how to use it to detect ineffective delta compression.
+    pub fn delta_compression_stats<C: Component>(
+        &self,
+        client_id: ClientId,
+    ) -> Result<DeltaCompressionStats, ServerError> {
+        self.connection(client_id).map(|c| {
+            c.replication_sender
+                .delta_compression_stats
+                .get(&ComponentKind::of::<C>())
+                .copied()
+                .unwrap_or_default()
+        })
+    }
+
+    /// Return the [`ReplicationWorldId`] that the client is assigned to
+    pub fn client_world(&self, client_id: ClientId) -> Result<ReplicationWorldId, ServerError> {
+        self.connection(client_id).map(|c| c.world_id)
+    }
+
+    /// Return the clients that are assigned to the given [`ReplicationWorldId`],
+    /// or `None` if all the connected clients are in that world (so that no filtering is needed)
+    pub(crate) fn world_target(&self, world_id: ReplicationWorldId) -> Option<NetworkTarget> {
+        if self.connections.values().all(|c| c.world_id == world_id) {
+            return None;
+        }
+        Some(NetworkTarget::Only(
+            self.connections
+                .iter()
+                .filter(|(_, c)| c.world_id == world_id)
+                .map(|(client_id, _)| *client_id)
+                .collect(),
+        ))
+    }
+
     /// Find the list of connected clients that match the provided [`NetworkTarget`]
     pub(crate) fn connected_targets(
         &self,
@@ -247,6 +565,48 @@ impl ConnectionManager {
             .ok_or(ServerError::ClientIdNotFound(client_id))
     }
 
+    /// The raw bytes of the last packet sent to a client.
+    ///
+    /// Only available if [`PacketConfig::capture_packets`] is enabled.
+    pub fn last_sent_packet(&self, client_id: ClientId) -> Result<Option<&[u8]>, ServerError> {
+        Ok(self
+            .connection(client_id)?
+            .message_manager
+            .last_sent_packet())
+    }
+
+    /// The raw bytes of the last packet received from a client.
+    ///
+    /// Only available if [`PacketConfig::capture_packets`] is enabled.
+    pub fn last_received_packet(&self, client_id: ClientId) -> Result<Option<&[u8]>, ServerError> {
+        Ok(self
+            .connection(client_id)?
+            .message_manager
+            .last_received_packet())
+    }
+
+    /// The [`ChannelStats`] (number of messages and bytes sent/received) of a given channel for a client
+    pub fn channel_stats<C: Channel>(
+        &self,
+        client_id: ClientId,
+    ) -> Result<Option<&ChannelStats>, ServerError> {
+        Ok(self
+            .connection(client_id)?
+            .message_manager
+            .channel_stats::<C>())
+    }
+
+    /// Iterate through the [`ChannelStats`] of all the channels for a client
+    pub fn all_channel_stats(
+        &self,
+        client_id: ClientId,
+    ) -> Result<impl Iterator<Item = (&ChannelKind, &ChannelStats)>, ServerError> {
+        Ok(self
+            .connection(client_id)?
+            .message_manager
+            .all_channel_stats())
+    }
+
     pub(crate) fn update(
         &mut self,
         world_tick: BevyTick,
@@ -272,6 +632,7 @@ impl ConnectionManager {
                 self.replication_config,
                 self.packet_config,
                 self.ping_config,
+                self.mtu,
             );
             self.events.add_connect_event(ConnectEvent {
                 client_id,
@@ -330,11 +691,16 @@ impl ConnectionManager {
         message: &M,
         channel: ChannelKind,
         target: NetworkTarget,
+        tick: Option<Tick>,
     ) -> Result<(), ServerError> {
         self.connections
             .iter_mut()
             .filter(|(id, _)| target.targets(id))
             .try_for_each(|(_, c)| {
+                if let Some(tick) = tick {
+                    SCHEDULED_MESSAGE_NET_ID.to_bytes(&mut self.writer)?;
+                    tick.to_bytes(&mut self.writer)?;
+                }
                 self.message_registry.serialize(
                     message,
                     &mut self.writer,
@@ -357,15 +723,34 @@ impl ConnectionManager {
     /// - If the message is not `MapEntities`, we can serialize it once and reuse the same bytes
     ///   for all `Connections`.
     /// - If it is `MapEntities`, we need to map it in each connection.
+    ///
+    /// If a `tick` is provided, the receiver will only process the message once it reaches that tick.
     pub(crate) fn erased_send_message_to_target<M: Message>(
         &mut self,
         message: &M,
         channel_kind: ChannelKind,
         target: NetworkTarget,
+        tick: Option<Tick>,
     ) -> Result<(), ServerError> {
+        if let Err(e) = self
+            .channel_registry
+            .check_send_direction(&channel_kind, true)
+        {
+            match self.packet_config.wrong_direction_policy {
+                WrongDirectionPolicy::Error => return Err(e.into()),
+                WrongDirectionPolicy::Drop => {
+                    error!("Dropping message: {e}");
+                    return Ok(());
+                }
+            }
+        }
         if self.message_registry.is_map_entities::<M>() {
-            self.buffer_map_entities_message(message, channel_kind, target)?;
+            self.buffer_map_entities_message(message, channel_kind, target, tick)?;
         } else {
+            if let Some(tick) = tick {
+                SCHEDULED_MESSAGE_NET_ID.to_bytes(&mut self.writer)?;
+                tick.to_bytes(&mut self.writer)?;
+            }
             self.message_registry
                 .serialize(message, &mut self.writer, None)?;
             let message_bytes = self.writer.split();
@@ -467,6 +852,10 @@ pub struct Connection {
     // TODO: maybe don't do any replication until connection is synced?
     /// Used to transfer raw bytes to a system that can convert the bytes to the actual type
     pub(crate) received_messages: HashMap<NetId, Vec<(Bytes, NetworkTarget, ChannelKind)>>,
+    /// Rate limiters for the messages that have a rate limit (see [`AppMessageExt::register_message_rate_limit`](crate::prelude::AppMessageExt::register_message_rate_limit))
+    pub(crate) message_rate_limiters: HashMap<NetId, MessageRateLimiter>,
+    /// Received messages with an unknown net id, that will be passed to the [`UnknownMessageFn`](crate::prelude::UnknownMessageFn)
+    pub(crate) unknown_messages: Vec<UnknownMessage>,
     pub(crate) received_input_messages: HashMap<NetId, Vec<(Bytes, NetworkTarget, ChannelKind)>>,
     #[cfg(feature = "leafwing")]
     pub(crate) received_leafwing_input_messages:
@@ -474,10 +863,18 @@ pub struct Connection {
     writer: Writer,
     // messages that we have received that need to be rebroadcasted to other clients
     pub(crate) messages_to_rebroadcast: Vec<(Bytes, NetworkTarget, ChannelKind)>,
+    /// Messages that were sent for a specific tick, and that are held until the server reaches that tick
+    scheduled_messages: Vec<(Tick, Bytes, NetworkTarget, ChannelKind)>,
     /// True if this connection corresponds to a local client when running in host-server mode
     is_local_client: bool,
     /// Messages to send to the local client (we don't buffer them in the MessageManager because there is no io)
     pub(crate) local_messages_to_send: Vec<Bytes>,
+    /// The logical world that the client is assigned to. The client only receives the entities of that world.
+    pub(crate) world_id: ReplicationWorldId,
+    /// Estimated number of ticks that the client's simulation is ahead of the server's simulation
+    client_tick_offset: Option<i16>,
+    /// Latest [`ViewDelay`] sent by the client
+    pub(crate) view_delay: Option<ViewDelay>,
 }
 
 impl Connection {
@@ -488,6 +885,7 @@ impl Connection {
         replication_config: ReplicationConfig,
         packet_config: PacketConfig,
         ping_config: PingConfig,
+        mtu: usize,
     ) -> Self {
         let bandwidth_cap_enabled = packet_config.bandwidth_cap_enabled;
         // create the message manager and the channels
@@ -496,6 +894,16 @@ impl Connection {
             packet_config.nack_rtt_multiple,
             packet_config.into(),
         );
+        message_manager.set_mtu(mtu);
+        if packet_config.capture_packets {
+            message_manager.enable_packet_capture();
+        }
+        if packet_config.send_sub_tick_fraction {
+            message_manager.enable_sub_tick_fraction();
+        }
+        if let Some(interval) = packet_config.ack_packet_interval {
+            message_manager.enable_ack_packets(interval);
+        }
         // get notified about acks/nacks for replication-update messages
         let entity_updates_sender = &mut message_manager
             .channels
@@ -514,7 +922,10 @@ impl Connection {
             replication_config,
             bandwidth_cap_enabled,
         );
-        let replication_receiver = ReplicationReceiver::new();
+        let replication_receiver = ReplicationReceiver::new()
+            .with_duplicate_spawn_policy(replication_config.duplicate_spawn_policy)
+            .with_max_spawns_per_frame(replication_config.max_spawns_per_frame)
+            .with_early_updates_policy(replication_config.early_updates_policy);
         Self {
             client_id,
             entity,
@@ -524,13 +935,19 @@ impl Connection {
             ping_manager: PingManager::new(ping_config),
             events: ConnectionEvents::default(),
             received_messages: HashMap::default(),
+            message_rate_limiters: HashMap::default(),
+            unknown_messages: vec![],
             received_input_messages: HashMap::default(),
             #[cfg(feature = "leafwing")]
             received_leafwing_input_messages: HashMap::default(),
             writer: Writer::with_capacity(MAX_PACKET_SIZE),
             messages_to_rebroadcast: vec![],
+            scheduled_messages: vec![],
             is_local_client: false,
             local_messages_to_send: vec![],
+            world_id: ReplicationWorldId::default(),
+            client_tick_offset: None,
+            view_delay: None,
         }
     }
 
@@ -544,16 +961,29 @@ impl Connection {
         self.is_local_client
     }
 
-    /// Return the latest estimate of rtt
+    /// Return the latest estimate of rtt, or `Duration::ZERO` if no pong was received yet
     pub fn rtt(&self) -> Duration {
+        if !self.ping_manager.has_stats() {
+            return Duration::ZERO;
+        }
         self.ping_manager.rtt()
     }
 
-    /// Return the latest estimate of jitter
+    /// Return the latest estimate of jitter, or `Duration::ZERO` if no pong was received yet
     pub fn jitter(&self) -> Duration {
+        if !self.ping_manager.has_stats() {
+            return Duration::ZERO;
+        }
         self.ping_manager.jitter()
     }
 
+    /// Provide the RTT and jitter of the connection from an external source, when pings are disabled
+    /// with [`PingConfig::enabled`](crate::prelude::PingConfig::enabled) and the transport does not
+    /// report the RTT itself (see [`NetServer::rtt`](crate::connection::server::NetServer::rtt))
+    pub fn set_rtt(&mut self, rtt: Duration, jitter: Duration) {
+        self.ping_manager.set_external_stats(rtt, jitter);
+    }
+
     pub(crate) fn update(
         &mut self,
         world_tick: BevyTick,
@@ -628,11 +1058,15 @@ impl Connection {
     }
 
     /// Send packets that are ready to be sent
+    ///
+    /// `split_reliable` should be true if the transport has its own reliability layer
+    /// (see [`NetServer::supports_reliable_send`](crate::connection::server::NetServer::supports_reliable_send))
     pub fn send_packets(
         &mut self,
         time_manager: &TimeManager,
         tick_manager: &TickManager,
-    ) -> Result<Vec<Payload>, ServerError> {
+        split_reliable: bool,
+    ) -> Result<Vec<(Payload, bool)>, ServerError> {
         // update the ping manager with the actual send time
         // TODO: issues here: we would like to send the ping/pong messages immediately, otherwise the recorded current time is incorrect
         //   - can give infinity priority to this channel?
@@ -656,7 +1090,10 @@ impl Connection {
                 self.send_pong(pong)?;
                 Ok::<(), ServerError>(())
             })?;
-        let payloads = self.message_manager.send_packets(tick_manager.tick())?;
+        self.message_manager.set_overstep(time_manager.overstep());
+        let payloads = self
+            .message_manager
+            .send_packets_with_reliability(tick_manager.tick(), split_reliable)?;
 
         // update the replication sender about which messages were actually sent, and accumulate priority
         self.replication_sender.recv_send_notification();
@@ -676,14 +1113,13 @@ impl Connection {
             .channels
             .iter_mut()
             .try_for_each(|(channel_kind, channel)| {
+                let channel_name = self
+                    .message_manager
+                    .channel_registry
+                    .name(channel_kind)
+                    .unwrap_or("unknown");
+                let _span_channel = trace_span!("read_messages", channel = channel_name).entered();
                 while let Some((tick, single_data)) = channel.receiver.read_message() {
-                    // let channel_name = self
-                    //     .message_manager
-                    //     .channel_registry
-                    //     .name(&channel_kind)
-                    //     .unwrap_or("unknown");
-                    // let _span_channel = trace_span!("channel", channel = channel_name).entered();
-
                     trace!(?channel_kind, ?tick, ?single_data, "received message");
                     let mut reader = Reader::from(single_data);
                     // TODO: get const type ids
@@ -704,6 +1140,10 @@ impl Connection {
                         trace!(?tick, ?actions, "received replication actions message");
                         // buffer the replication message
                         self.replication_receiver.recv_actions(actions, tick);
+                    } else if channel_kind == &ChannelKind::of::<SnapshotChannel>() {
+                        trace!(?tick, "received replication snapshot");
+                        self.replication_receiver
+                            .recv_snapshot(&reader.consume(), tick)?;
                     } else if channel_kind == &ChannelKind::of::<EntityUpdatesChannel>() {
                         let updates = EntityUpdatesMessage::from_bytes(&mut reader)?;
                         trace!(?tick, ?updates, "received replication updates message");
@@ -720,6 +1160,17 @@ impl Connection {
 
                         let mut reader = Reader::from(message);
                         let net_id = NetId::from_bytes(&mut reader)?;
+                        if net_id == SCHEDULED_MESSAGE_NET_ID {
+                            let tick = Tick::from_bytes(&mut reader)?;
+                            let remaining = reader.remaining();
+                            self.scheduled_messages.push((
+                                tick,
+                                reader.split_len(remaining),
+                                target,
+                                *channel_kind,
+                            ));
+                            continue;
+                        }
                         // we are also sending target and channel kind so the message can be
                         // rebroadcasted to other clients after we have converted the entities from the
                         // client World to the server World
@@ -729,19 +1180,24 @@ impl Connection {
                         // TODO: avoid clone with Arc<[u8]>?
                         let data = (reader.consume(), target, *channel_kind);
                         match message_registry.message_type(net_id) {
+                            None => self.unknown_messages.push(UnknownMessage {
+                                sender: Some(self.client_id),
+                                net_id,
+                                bytes: data.0,
+                            }),
                             #[cfg(feature = "leafwing")]
-                            MessageType::LeafwingInput => self
+                            Some(MessageType::LeafwingInput) => self
                                 .received_leafwing_input_messages
                                 .entry(net_id)
                                 .or_default()
                                 .push(data),
-                            MessageType::NativeInput => {
+                            Some(MessageType::NativeInput) => {
                                 self.received_input_messages
                                     .entry(net_id)
                                     .or_default()
                                     .push(data);
                             }
-                            MessageType::Normal => {
+                            Some(MessageType::Normal) => {
                                 self.received_messages.entry(net_id).or_default().push(data);
                             }
                         }
@@ -749,6 +1205,7 @@ impl Connection {
                 }
                 Ok::<(), SerializationError>(())
             })?;
+        self.release_scheduled_messages(tick_manager.tick(), message_registry)?;
 
         // Check if we have any replication messages we can apply to the World (and emit events)
         self.replication_receiver.apply_world(
@@ -780,6 +1237,13 @@ impl Connection {
 
         let mut reader = Reader::from(message);
         let net_id = NetId::from_bytes(&mut reader)?;
+        if net_id == SCHEDULED_MESSAGE_NET_ID {
+            let tick = Tick::from_bytes(&mut reader)?;
+            let remaining = reader.remaining();
+            self.scheduled_messages
+                .push((tick, reader.split_len(remaining), target, channel_kind));
+            return Ok(());
+        }
         // we are also sending target and channel kind so the message can be
         // rebroadcasted to other clients after we have converted the entities from the
         // client World to the server World
@@ -788,24 +1252,58 @@ impl Connection {
         //  or it matters for input messages?
         // TODO: avoid clone with Arc<[u8]>?
         let data = (reader.consume(), target, channel_kind);
+        self.push_received_message(net_id, data, message_registry);
+        Ok(())
+    }
+
+    /// Buffer the scheduled messages whose tick has been reached, so that they are read
+    /// like the other received messages
+    fn release_scheduled_messages(
+        &mut self,
+        tick: Tick,
+        message_registry: &MessageRegistry,
+    ) -> Result<(), SerializationError> {
+        let (ready, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.scheduled_messages)
+            .into_iter()
+            .partition(|(message_tick, ..)| *message_tick <= tick);
+        self.scheduled_messages = pending;
+        for (_, message, target, channel_kind) in ready {
+            let mut reader = Reader::from(message);
+            let net_id = NetId::from_bytes(&mut reader)?;
+            let data = (reader.consume(), target, channel_kind);
+            self.push_received_message(net_id, data, message_registry);
+        }
+        Ok(())
+    }
+
+    fn push_received_message(
+        &mut self,
+        net_id: NetId,
+        data: (Bytes, NetworkTarget, ChannelKind),
+        message_registry: &MessageRegistry,
+    ) {
         match message_registry.message_type(net_id) {
+            None => self.unknown_messages.push(UnknownMessage {
+                sender: Some(self.client_id),
+                net_id,
+                bytes: data.0,
+            }),
             #[cfg(feature = "leafwing")]
-            MessageType::LeafwingInput => self
+            Some(MessageType::LeafwingInput) => self
                 .received_leafwing_input_messages
                 .entry(net_id)
                 .or_default()
                 .push(data),
-            MessageType::NativeInput => {
+            Some(MessageType::NativeInput) => {
                 self.received_input_messages
                     .entry(net_id)
                     .or_default()
                     .push(data);
             }
-            MessageType::Normal => {
+            Some(MessageType::Normal) => {
                 self.received_messages.entry(net_id).or_default().push(data);
             }
         }
-        Ok(())
     }
 
     pub fn recv_packet(
@@ -817,6 +1315,12 @@ impl Connection {
     ) -> Result<(), ServerError> {
         // receive the packets, buffer them, update any sender that were waiting for their sent messages to be acked
         let tick = self.message_manager.recv_packet(packet)?;
+        // the packet was sent by the client half a RTT ago, so the client's current tick is further ahead
+        let latency_ticks = (self.ping_manager.rtt().as_secs_f32()
+            / 2.0
+            / tick_manager.config.tick_duration.as_secs_f32())
+        .round() as i16;
+        self.client_tick_offset = Some((tick - tick_manager.tick()).saturating_add(latency_ticks));
         // notify the replication sender that some sent messages were received
         self.replication_sender
             .recv_update_acks(component_registry, delta_manager);
@@ -913,6 +1417,8 @@ impl ConnectionManager {
         //         client_entity: None,
         //     }));
 
+        self.replicated_components.insert(kind);
+
         // same thing for PreSpawnedPlayerObject: that component should only be replicated to prediction_target
         let mut actual_target = target;
         let should_be_predicted_kind = ComponentKind::of::<ShouldBePredicted>();
@@ -1093,7 +1599,11 @@ impl ConnectionManager {
                         .replication_receiver
                         .remote_entity_map
                         .to_remote(entity);
-                    connection.replication_sender.prepare_component_update(entity, group_id, raw_data);
+                    if registry.is_latest_only(kind) {
+                        connection.replication_sender.prepare_latest_component_update(entity, group_id, raw_data);
+                    } else {
+                        connection.replication_sender.prepare_component_update(entity, group_id, raw_data);
+                    }
                 }
             }
             Ok::<(), ServerError>(())
@@ -1136,7 +1646,7 @@ impl MessageSend for ConnectionManager {
         channel_kind: ChannelKind,
         target: NetworkTarget,
     ) -> Result<(), ServerError> {
-        self.erased_send_message_to_target(message, channel_kind, target)
+        self.erased_send_message_to_target(message, channel_kind, target, None)
     }
 }
 
@@ -1177,3 +1687,466 @@ impl ReplicationSend for ConnectionManager {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::App;
+    use bevy::utils::Duration;
+
+    use governor::RateLimiter;
+    use nonzero_ext::nonzero;
+
+    use crate::prelude::server::{NetConfig, Replicate, ServerConfig};
+    use crate::prelude::{
+        client, LinkConditionerConfig, ReplicateOnceComponent, SharedConfig, TickConfig,
+    };
+    use crate::tests::multi_stepper::{MultiBevyStepper, TEST_CLIENT_ID_1};
+    use crate::tests::protocol::{ComponentSyncModeFull, ComponentSyncModeSimple};
+    use crate::tests::stepper::{BevyStepper, TEST_CLIENT_ID};
+
+    use super::*;
+
+    /// Check that the rtt reported by the client and the server matches the latency of the link
+    #[test]
+    fn test_rtt() {
+        let frame_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(frame_duration),
+            ..Default::default()
+        };
+        let mut client_config = client::ClientConfig::default();
+        if let client::NetConfig::Netcode { io, .. } = &mut client_config.net {
+            // the conditioner is applied to both the client and the server
+            io.conditioner = Some(LinkConditionerConfig {
+                incoming_latency: Duration::from_millis(40),
+                incoming_jitter: Duration::default(),
+                incoming_loss: 0.0,
+            });
+        }
+        let mut stepper = BevyStepper::new(shared_config, client_config, frame_duration);
+
+        // no pong received yet
+        let connection = Connection::new(
+            ClientId::Netcode(TEST_CLIENT_ID),
+            Entity::PLACEHOLDER,
+            stepper.server_app.world().resource::<ChannelRegistry>(),
+            ReplicationConfig::default(),
+            PacketConfig::default(),
+            PingConfig::default(),
+            MAX_PACKET_SIZE,
+        );
+        assert_eq!(connection.rtt(), Duration::ZERO);
+        assert_eq!(connection.jitter(), Duration::ZERO);
+
+        stepper.init();
+        for _ in 0..300 {
+            stepper.frame_step();
+        }
+        let client_rtt = stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .rtt();
+        let server_rtt = stepper
+            .server_app
+            .world()
+            .resource::<ConnectionManager>()
+            .rtt(ClientId::Netcode(TEST_CLIENT_ID))
+            .unwrap();
+        for rtt in [client_rtt, server_rtt] {
+            assert!(
+                rtt >= Duration::from_millis(70) && rtt <= Duration::from_millis(100),
+                "rtt: {:?}",
+                rtt
+            );
+        }
+    }
+
+    /// Check that the tick offset of the client estimated by the server matches how far ahead
+    /// the client's simulation actually is
+    #[test]
+    fn test_client_tick_offset() {
+        let mut stepper = BevyStepper::default();
+        stepper.stop();
+        #[allow(irrefutable_let_patterns)]
+        if let NetConfig::Netcode { io, .. } = stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ServerConfig>()
+            .net
+            .first_mut()
+            .unwrap()
+        {
+            io.conditioner = Some(LinkConditionerConfig {
+                incoming_latency: Duration::from_millis(50),
+                incoming_jitter: Duration::default(),
+                incoming_loss: 0.0,
+            })
+        }
+        stepper.start();
+        for _ in 0..50 {
+            stepper.frame_step();
+        }
+
+        let manager = stepper.server_app.world().resource::<ConnectionManager>();
+        let offset = manager
+            .client_tick_offset(ClientId::Netcode(TEST_CLIENT_ID))
+            .expect("no tick offset for the client");
+        let actual_offset = stepper.client_tick() - stepper.server_tick();
+        let rtt = manager
+            .connection(ClientId::Netcode(TEST_CLIENT_ID))
+            .unwrap()
+            .ping_manager
+            .rtt();
+        let latency_ticks = (rtt / 2).as_millis() as i16 / stepper.tick_duration.as_millis() as i16;
+        // the client should be ahead of the server by at least the one-way latency
+        assert!(offset >= latency_ticks);
+        assert!((offset - actual_offset).abs() <= 1);
+    }
+
+    /// Check that with a tight bandwidth cap, the groups that are the most important for a client
+    /// are sent before the other ones
+    #[test]
+    fn test_update_importance() {
+        let mut stepper = BevyStepper::default();
+        stepper.stop();
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ServerConfig>()
+            .packet = PacketConfig::default().enable_bandwidth_cap();
+        stepper.start();
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+
+        let near = stepper
+            .server_app
+            .world_mut()
+            .spawn((Replicate::default(), ComponentSyncModeFull(0.0)))
+            .id();
+        let far = stepper
+            .server_app
+            .world_mut()
+            .spawn((Replicate::default(), ComponentSyncModeFull(0.0)))
+            .id();
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        let client_entity = |stepper: &BevyStepper, entity: Entity| {
+            stepper
+                .client_app
+                .world()
+                .resource::<crate::prelude::client::ConnectionManager>()
+                .replication_receiver
+                .remote_entity_map
+                .get_local(entity)
+                .expect("entity was not replicated to client")
+        };
+        let client_near = client_entity(&stepper, near);
+        let client_far = client_entity(&stepper, far);
+
+        // the near entity is more important for the client
+        let client_id = ClientId::Netcode(TEST_CLIENT_ID);
+        let mut manager = stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ConnectionManager>();
+        manager
+            .update_importance(ReplicationGroupId(near.to_bits()), client_id, 10.0)
+            .unwrap();
+        manager
+            .update_importance(ReplicationGroupId(far.to_bits()), client_id, 1.0)
+            .unwrap();
+        // only leave enough bandwidth for a single entity update (the quota doesn't refill during the test)
+        manager
+            .connection_mut(client_id)
+            .unwrap()
+            .message_manager
+            .priority_manager
+            .limiter =
+            RateLimiter::direct(Quota::per_hour(nonzero!(1u32)).allow_burst(nonzero!(80u32)));
+
+        for entity in [near, far] {
+            stepper
+                .server_app
+                .world_mut()
+                .entity_mut(entity)
+                .insert(ComponentSyncModeFull(1.0));
+        }
+        stepper.frame_step();
+        stepper.frame_step();
+
+        // only the update of the near entity was sent
+        let client_world = stepper.client_app.world();
+        assert_eq!(
+            client_world.get::<ComponentSyncModeFull>(client_near),
+            Some(&ComponentSyncModeFull(1.0))
+        );
+        assert_eq!(
+            client_world.get::<ComponentSyncModeFull>(client_far),
+            Some(&ComponentSyncModeFull(0.0))
+        );
+
+        // the far entity gets updated once there is enough bandwidth again
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ConnectionManager>()
+            .connection_mut(client_id)
+            .unwrap()
+            .message_manager
+            .priority_manager
+            .limiter = RateLimiter::direct(Quota::per_second(nonzero!(56000u32)));
+        stepper.frame_step();
+        stepper.frame_step();
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<ComponentSyncModeFull>(client_far),
+            Some(&ComponentSyncModeFull(1.0))
+        );
+    }
+
+    /// Number of entities replicated to the client after a few frames, when the server spawns a lot of
+    /// entities with a tight bandwidth cap
+    fn replicated_entities_with_cap(boost: bool) -> usize {
+        let mut stepper = BevyStepper::default();
+        stepper.stop();
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ServerConfig>()
+            .packet = PacketConfig::default().enable_bandwidth_cap();
+        stepper.start();
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        let client_id = ClientId::Netcode(TEST_CLIENT_ID);
+        let mut manager = stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ConnectionManager>();
+        // the quota doesn't refill during the test
+        manager
+            .set_bandwidth_cap(
+                client_id,
+                Quota::per_hour(nonzero!(1u32)).allow_burst(nonzero!(200u32)),
+            )
+            .unwrap();
+        if boost {
+            manager
+                .boost_bandwidth_cap(client_id, None, Some(Duration::from_millis(100)))
+                .unwrap();
+        }
+
+        let entities = (0..50)
+            .map(|i| {
+                stepper
+                    .server_app
+                    .world_mut()
+                    .spawn((Replicate::default(), ComponentSyncModeFull(i as f32)))
+                    .id()
+            })
+            .collect::<Vec<_>>();
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        let manager = stepper
+            .client_app
+            .world()
+            .resource::<crate::prelude::client::ConnectionManager>();
+        let replicated = entities
+            .iter()
+            .filter(|entity| {
+                manager
+                    .replication_receiver
+                    .remote_entity_map
+                    .get_local(**entity)
+                    .is_some()
+            })
+            .count();
+
+        if boost {
+            // the boost expires and the configured cap is restored
+            for _ in 0..10 {
+                stepper.frame_step();
+            }
+            assert!(!stepper
+                .server_app
+                .world()
+                .resource::<ConnectionManager>()
+                .is_bandwidth_boosted(client_id)
+                .unwrap());
+        }
+        replicated
+    }
+
+    /// Check that raising the bandwidth cap of a client lets the initial state of the world
+    /// be sent faster than with the steady cap
+    #[test]
+    fn test_boost_bandwidth_cap() {
+        let steady = replicated_entities_with_cap(false);
+        let boosted = replicated_entities_with_cap(true);
+        assert!(steady < 50);
+        assert_eq!(boosted, 50);
+    }
+
+    /// Check that when the bandwidth is limited, the updates of the entity with the highest
+    /// replication priority are sent first
+    #[test]
+    fn test_set_replication_priority() {
+        let mut stepper = BevyStepper::default();
+        stepper.stop();
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ServerConfig>()
+            .packet = PacketConfig::default().enable_bandwidth_cap();
+        stepper.start();
+        let low = stepper
+            .server_app
+            .world_mut()
+            .spawn((Replicate::default(), ComponentSyncModeFull(0.0)))
+            .id();
+        let high = stepper
+            .server_app
+            .world_mut()
+            .spawn((Replicate::default(), ComponentSyncModeFull(0.0)))
+            .id();
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+
+        let mut manager = stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ConnectionManager>();
+        manager.set_replication_priority(low, 1.0);
+        manager.set_replication_priority(high, 10.0);
+        // the quota doesn't refill during the test, and only fits one update
+        manager
+            .set_bandwidth_cap(
+                ClientId::Netcode(TEST_CLIENT_ID),
+                Quota::per_hour(nonzero!(1u32)).allow_burst(nonzero!(80u32)),
+            )
+            .unwrap();
+        for entity in [low, high] {
+            stepper
+                .server_app
+                .world_mut()
+                .get_mut::<ComponentSyncModeFull>(entity)
+                .unwrap()
+                .0 = 1.0;
+        }
+        stepper.frame_step();
+        stepper.frame_step();
+
+        let client_value = |entity| {
+            let client_entity = stepper
+                .client_app
+                .world()
+                .resource::<crate::prelude::client::ConnectionManager>()
+                .replication_receiver
+                .remote_entity_map
+                .get_local(entity)
+                .unwrap();
+            stepper
+                .client_app
+                .world()
+                .get::<ComponentSyncModeFull>(client_entity)
+                .unwrap()
+                .0
+        };
+        assert_eq!(client_value(high), 1.0);
+        assert_eq!(client_value(low), 0.0);
+    }
+
+    /// Check that resyncing a client re-sends the full replicated state to that client only
+    #[test]
+    fn test_resync_client() {
+        let mut stepper = MultiBevyStepper::default();
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((
+                Replicate::default(),
+                ComponentSyncModeFull(1.0),
+                // components that are replicated once are only sent again with the full state
+                ComponentSyncModeSimple(1.0),
+                ReplicateOnceComponent::<ComponentSyncModeSimple>::default(),
+            ))
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+
+        let client_entity = |app: &App| {
+            app.world()
+                .resource::<crate::prelude::client::ConnectionManager>()
+                .replication_receiver
+                .remote_entity_map
+                .get_local(server_entity)
+                .expect("entity was not replicated to client")
+        };
+        let client_entity_1 = client_entity(&stepper.client_app_1);
+        let client_entity_2 = client_entity(&stepper.client_app_2);
+        // the state of both clients gets corrupted
+        for (app, entity) in [
+            (&mut stepper.client_app_1, client_entity_1),
+            (&mut stepper.client_app_2, client_entity_2),
+        ] {
+            app.world_mut()
+                .entity_mut(entity)
+                .remove::<(ComponentSyncModeFull, ComponentSyncModeSimple)>();
+        }
+
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ConnectionManager>()
+            .resync_client(ClientId::Netcode(TEST_CLIENT_ID_1))
+            .unwrap();
+        stepper.frame_step();
+        stepper.frame_step();
+
+        // only the resynced client receives the full state again
+        let client_world_1 = stepper.client_app_1.world();
+        assert_eq!(
+            client_world_1.get::<ComponentSyncModeFull>(client_entity_1),
+            Some(&ComponentSyncModeFull(1.0))
+        );
+        assert_eq!(
+            client_world_1.get::<ComponentSyncModeSimple>(client_entity_1),
+            Some(&ComponentSyncModeSimple(1.0))
+        );
+        let client_world_2 = stepper.client_app_2.world();
+        assert!(client_world_2
+            .get::<ComponentSyncModeFull>(client_entity_2)
+            .is_none());
+        assert!(client_world_2
+            .get::<ComponentSyncModeSimple>(client_entity_2)
+            .is_none());
+        // the entity was not spawned again
+        assert_eq!(client_entity(&stepper.client_app_1), client_entity_1);
+
+        // both clients keep receiving updates
+        stepper
+            .server_app
+            .world_mut()
+            .get_mut::<ComponentSyncModeFull>(server_entity)
+            .unwrap()
+            .0 = 2.0;
+        stepper.frame_step();
+        stepper.frame_step();
+        for (app, entity) in [
+            (&stepper.client_app_1, client_entity_1),
+            (&stepper.client_app_2, client_entity_2),
+        ] {
+            assert_eq!(
+                app.world().get::<ComponentSyncModeFull>(entity),
+                Some(&ComponentSyncModeFull(2.0))
+            );
+        }
+    }
+}
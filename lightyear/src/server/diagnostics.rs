@@ -0,0 +1,145 @@
+//! Debug diagnostics to detect misconfigured replication on the server
+//!
+//! The [`UnreplicatedComponentsPlugin`] keeps track of the components that are registered in the
+//! [`ComponentRegistry`] and can be replicated by the server, but that were never sent to any client since
+//! the server started. This is usually a sign that the component was never added to a replicated entity, or
+//! that it was registered with the wrong [`ChannelDirection`](crate::prelude::ChannelDirection).
+use bevy::prelude::*;
+use bevy::utils::Duration;
+
+use crate::prelude::server::is_started;
+use crate::prelude::{
+    ComponentRegistry, ParentSync, PrePredicted, PreSpawnedPlayerObject, ShouldBePredicted,
+};
+use crate::protocol::component::ComponentKind;
+use crate::server::connection::ConnectionManager;
+use crate::shared::replication::components::{Controlled, ShouldBeInterpolated};
+
+/// Plugin that reports the registered components that were never replicated by the server
+pub struct UnreplicatedComponentsPlugin {
+    /// How long to wait after the server started before logging a warning for the components
+    /// that were never replicated
+    pub warn_after: Duration,
+}
+
+impl Default for UnreplicatedComponentsPlugin {
+    fn default() -> Self {
+        Self {
+            warn_after: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Names of the registered components that were never replicated by the server since it started.
+///
+/// This is updated every frame by the [`UnreplicatedComponentsPlugin`].
+#[derive(Resource, Debug, Default, Clone, PartialEq)]
+pub struct UnreplicatedComponents(pub Vec<&'static str>);
+
+#[derive(Resource, Debug)]
+struct UnreplicatedComponentsTimer {
+    timer: Timer,
+    warned: bool,
+}
+
+impl Plugin for UnreplicatedComponentsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<UnreplicatedComponents>();
+        app.insert_resource(UnreplicatedComponentsTimer {
+            timer: Timer::new(self.warn_after, TimerMode::Once),
+            warned: false,
+        });
+        app.add_systems(Last, update_unreplicated_components.run_if(is_started));
+    }
+}
+
+/// Components that are registered by lightyear, and that are only replicated in some situations
+fn is_internal_component(kind: &ComponentKind) -> bool {
+    [
+        ComponentKind::of::<PreSpawnedPlayerObject>(),
+        ComponentKind::of::<PrePredicted>(),
+        ComponentKind::of::<ShouldBePredicted>(),
+        ComponentKind::of::<ShouldBeInterpolated>(),
+        ComponentKind::of::<ParentSync>(),
+        ComponentKind::of::<Controlled>(),
+    ]
+    .contains(kind)
+}
+
+fn update_unreplicated_components(
+    time: Res<Time<Real>>,
+    registry: Res<ComponentRegistry>,
+    connection_manager: Res<ConnectionManager>,
+    mut timer: ResMut<UnreplicatedComponentsTimer>,
+    mut unreplicated: ResMut<UnreplicatedComponents>,
+) {
+    let mut components = registry
+        .kind_map
+        .kind_map
+        .keys()
+        .filter(|kind| {
+            !is_internal_component(kind)
+                && registry
+                    .direction(**kind)
+                    .is_some_and(|direction| direction.server_can_send())
+                && !connection_manager.replicated_components.contains(*kind)
+        })
+        .map(|kind| registry.name(*kind))
+        .collect::<Vec<_>>();
+    components.sort_unstable();
+    unreplicated.0 = components;
+
+    timer.timer.tick(time.delta());
+    if timer.timer.finished() && !timer.warned {
+        timer.warned = true;
+        if !unreplicated.0.is_empty() {
+            warn!(
+                components = ?unreplicated.0,
+                "These components are registered for replication but were never replicated by the server. \
+                Did you forget to add them to a replicated entity, or register them with the wrong direction?"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::client::ClientConfig;
+    use crate::prelude::server::Replicate;
+    use crate::prelude::{SharedConfig, TickConfig};
+    use crate::tests::protocol::{ComponentSyncModeFull, ComponentSyncModeOnce};
+    use crate::tests::stepper::BevyStepper;
+
+    /// Check that the components that are registered but never replicated are reported
+    #[test]
+    fn test_unreplicated_components() {
+        let frame_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(frame_duration),
+            ..default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, ClientConfig::default(), frame_duration);
+        stepper
+            .server_app
+            .add_plugins(UnreplicatedComponentsPlugin {
+                warn_after: Duration::from_millis(20),
+            });
+        stepper.init();
+        stepper
+            .server_app
+            .world_mut()
+            .spawn((Replicate::default(), ComponentSyncModeFull(1.0)));
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        let unreplicated = &stepper
+            .server_app
+            .world()
+            .resource::<UnreplicatedComponents>()
+            .0;
+        // the component was never added to a replicated entity
+        assert!(unreplicated.contains(&std::any::type_name::<ComponentSyncModeOnce>()));
+        assert!(!unreplicated.contains(&std::any::type_name::<ComponentSyncModeFull>()));
+    }
+}
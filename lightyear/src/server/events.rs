@@ -26,13 +26,15 @@ impl Plugin for ServerEventsPlugin {
             // EVENTS
             .add_event::<ConnectEvent>()
             .add_event::<DisconnectEvent>()
+            .add_event::<GroupAckEvent>()
             // PLUGIN
             .add_plugins(EventsPlugin::<ConnectionManager>::default())
             // SYSTEMS
             .add_systems(
                 PreUpdate,
                 // TODO: check if this should be between Receive and EmitEvents
-                emit_connect_events.in_set(InternalMainSet::<ServerMarker>::EmitEvents),
+                (emit_connect_events, emit_group_ack_events)
+                    .in_set(InternalMainSet::<ServerMarker>::EmitEvents),
             );
     }
 }
@@ -73,6 +75,21 @@ fn emit_connect_events(
     }
 }
 
+/// Emit a [`GroupAckEvent`] for every replication update message that was acked by a client
+fn emit_group_ack_events(
+    mut commands: Commands,
+    mut group_ack_events: EventWriter<GroupAckEvent>,
+    mut connection_manager: ResMut<ConnectionManager>,
+) {
+    for (client_id, connection) in connection_manager.connections.iter_mut() {
+        for (group_id, tick) in connection.replication_sender.acked_groups.drain(..) {
+            let event = GroupAckEvent::new(group_id, tick, *client_id);
+            group_ack_events.send(event);
+            commands.trigger(event);
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct ServerEvents {
     pub connections: Vec<ConnectEvent>,
@@ -279,16 +296,22 @@ pub type ComponentInsertEvent<C> =
 pub type ComponentRemoveEvent<C> =
     crate::shared::events::components::ComponentRemoveEvent<C, ClientId>;
 
+/// Bevy [`Event`] emitted on the server on the frame where a client acked a replication update message
+pub type GroupAckEvent = crate::shared::events::components::GroupAckEvent<ClientId>;
+
 /// Bevy [`Event`] emitted on the server on the frame where a (non-replication) message is received
 pub type MessageEvent<M> = crate::shared::events::components::MessageEvent<M, ClientId>;
 
 #[cfg(test)]
 mod tests {
+    use crate::prelude::server::Replicate;
     use crate::prelude::Tick;
     use crate::protocol::channel::ChannelKind;
+    use crate::shared::replication::components::ReplicationGroupId;
     use crate::tests::protocol::{
         Channel1, Channel2, ComponentSyncModeFull, ComponentSyncModeOnce, StringMessage,
     };
+    use crate::tests::stepper::{BevyStepper, TEST_CLIENT_ID};
 
     use super::*;
 
@@ -333,4 +356,52 @@ mod tests {
         assert!(data.contains(&(entity_1, client_1)));
         assert!(data.contains(&(entity_2, client_2)));
     }
+
+    #[derive(Resource, Default)]
+    struct GroupAcks(Vec<GroupAckEvent>);
+
+    /// Check that an observer is triggered when a client acks an update message for a replication group
+    #[test]
+    fn test_group_ack_event() {
+        let mut stepper = BevyStepper::default();
+        stepper.server_app.init_resource::<GroupAcks>().observe(
+            |trigger: Trigger<GroupAckEvent>, mut acks: ResMut<GroupAcks>| {
+                acks.0.push(*trigger.event());
+            },
+        );
+
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((ComponentSyncModeFull(0.0), Replicate::default()))
+            .id();
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<GroupAcks>()
+            .0
+            .clear();
+
+        // send an update for the replication group
+        stepper
+            .server_app
+            .world_mut()
+            .entity_mut(server_entity)
+            .insert(ComponentSyncModeFull(1.0));
+        stepper.frame_step();
+        let update_tick = stepper.server_tick();
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+
+        let acks = &stepper.server_app.world().resource::<GroupAcks>().0;
+        assert!(acks.contains(&GroupAckEvent::new(
+            ReplicationGroupId(server_entity.to_bits()),
+            update_tick,
+            ClientId::Netcode(TEST_CLIENT_ID),
+        )));
+    }
 }
@@ -4,30 +4,76 @@ use std::ops::DerefMut;
 use crate::inputs::leafwing::input_buffer::InputBuffer;
 use crate::inputs::leafwing::input_message::InputTarget;
 use bevy::prelude::*;
+use bevy::utils::HashMap;
 use leafwing_input_manager::prelude::*;
 
 use crate::inputs::leafwing::LeafwingUserAction;
-use crate::prelude::server::MessageEvent;
-use crate::prelude::{server::is_started, InputMessage, MessageRegistry, Mode, TickManager};
+use crate::prelude::server::{ControlledBy, DisconnectEvent, MessageEvent};
+use crate::prelude::{
+    server::is_started, ClientId, InputMessage, MessageRegistry, Mode, OverrideTargetComponent,
+    ReplicationTarget, TickManager,
+};
 use crate::protocol::message::MessageKind;
 use crate::serialize::reader::Reader;
 use crate::server::config::ServerConfig;
 use crate::server::connection::ConnectionManager;
+use crate::server::input::InputTooFarAheadEvent;
 use crate::shared::replication::network_target::NetworkTarget;
 use crate::shared::sets::{InternalMainSet, ServerMarker};
 
 pub struct LeafwingInputPlugin<A> {
+    /// If true, the [`ActionState`] of the entities is replicated to the clients that don't control them
+    replicate_action_state: bool,
     marker: std::marker::PhantomData<A>,
 }
 
 impl<A> Default for LeafwingInputPlugin<A> {
     fn default() -> Self {
         Self {
+            replicate_action_state: false,
             marker: std::marker::PhantomData,
         }
     }
 }
 
+impl<A> LeafwingInputPlugin<A> {
+    pub(crate) fn new(replicate_action_state: bool) -> Self {
+        Self {
+            replicate_action_state,
+            marker: std::marker::PhantomData,
+        }
+    }
+}
+
+/// The global inputs of each client, i.e. the inputs that are stored in a [`Resource`] on the client
+/// instead of being attached to an [`Entity`]
+#[derive(Resource, Debug)]
+pub struct GlobalActions<A: LeafwingUserAction> {
+    input_buffers: HashMap<ClientId, InputBuffer<A>>,
+    action_states: HashMap<ClientId, ActionState<A>>,
+}
+
+impl<A: LeafwingUserAction> Default for GlobalActions<A> {
+    fn default() -> Self {
+        Self {
+            input_buffers: HashMap::default(),
+            action_states: HashMap::default(),
+        }
+    }
+}
+
+impl<A: LeafwingUserAction> GlobalActions<A> {
+    /// The global [`ActionState`] of the client for the current tick
+    pub fn action_state(&self, client_id: ClientId) -> Option<&ActionState<A>> {
+        self.action_states.get(&client_id)
+    }
+
+    /// The [`InputBuffer`] containing the global inputs received from the client
+    pub fn input_buffer(&self, client_id: ClientId) -> Option<&InputBuffer<A>> {
+        self.input_buffers.get(&client_id)
+    }
+}
+
 #[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone, Copy)]
 pub enum InputSystemSet {
     /// Add the ActionDiffBuffers to new entities that have an [`ActionState`]
@@ -41,8 +87,9 @@ pub enum InputSystemSet {
 impl<A: LeafwingUserAction> Plugin for LeafwingInputPlugin<A> {
     fn build(&self, app: &mut App) {
         // RESOURCES
-        // app.init_resource::<GlobalActions<A>>();
-        // TODO: (global action states) add a resource tracking the action-state of all clients
+        app.init_resource::<GlobalActions<A>>();
+        // EVENTS
+        app.add_event::<InputTooFarAheadEvent>();
         // SETS
         app.configure_sets(
             PreUpdate,
@@ -69,6 +116,13 @@ impl<A: LeafwingUserAction> Plugin for LeafwingInputPlugin<A> {
             FixedPreUpdate,
             update_action_state::<A>.in_set(InputSystemSet::Update),
         );
+        app.observe(handle_client_disconnect::<A>);
+        if self.replicate_action_state {
+            app.add_systems(
+                PreUpdate,
+                set_action_state_replication_target::<A>.in_set(InputSystemSet::AddBuffers),
+            );
+        }
 
         // TODO: register this in Plugin::finish by checking if the client plugin is already registered?
         if app.world().resource::<ServerConfig>().shared.mode != Mode::HostServer {
@@ -90,14 +144,81 @@ fn add_action_diff_buffer<A: LeafwingUserAction>(
     }
 }
 
+/// Marker component indicating that the [`OverrideTargetComponent`] for the [`ActionState`]
+/// was inserted by lightyear (and not by the user), so that we can keep it up-to-date
+#[derive(Component)]
+struct SpectatorActionStateTarget<A>(std::marker::PhantomData<A>);
+
+/// Replicate the [`ActionState`] only to the clients that don't control the entity (i.e. the spectators),
+/// since the controlling clients already have their own inputs.
+///
+/// If the user already added their own [`OverrideTargetComponent`] for the [`ActionState`], it is left untouched.
+fn set_action_state_replication_target<A: LeafwingUserAction>(
+    mut commands: Commands,
+    query: Query<
+        (
+            Entity,
+            &ReplicationTarget,
+            Option<&ControlledBy>,
+            Has<OverrideTargetComponent<ActionState<A>>>,
+            Has<SpectatorActionStateTarget<A>>,
+        ),
+        (
+            With<ActionState<A>>,
+            Or<(
+                Added<ActionState<A>>,
+                Changed<ReplicationTarget>,
+                Changed<ControlledBy>,
+            )>,
+        ),
+    >,
+) {
+    for (entity, replication_target, controlled_by, has_override, is_spectator_target) in
+        query.iter()
+    {
+        if has_override && !is_spectator_target {
+            continue;
+        }
+        let mut target = replication_target.target.clone();
+        if let Some(controlled_by) = controlled_by {
+            target.exclude(&controlled_by.target);
+        }
+        commands.entity(entity).insert((
+            OverrideTargetComponent::<ActionState<A>>::new(target),
+            SpectatorActionStateTarget::<A>(std::marker::PhantomData),
+        ));
+    }
+}
+
+/// Remove the global inputs of the client if the client disconnects
+fn handle_client_disconnect<A: LeafwingUserAction>(
+    trigger: Trigger<DisconnectEvent>,
+    mut global_actions: ResMut<GlobalActions<A>>,
+) {
+    global_actions
+        .input_buffers
+        .remove(&trigger.event().client_id);
+    global_actions
+        .action_states
+        .remove(&trigger.event().client_id);
+}
+
 /// Read the input messages from the server events to update the InputBuffers
+///
+/// The global inputs of each client are stored in the [`GlobalActions`] resource.
+/// Inputs for an entity that is controlled by other clients (see [`ControlledBy`]) are ignored,
+/// so that a client cannot keep driving an entity after its control was transferred to another client.
 fn receive_input_message<A: LeafwingUserAction>(
     message_registry: Res<MessageRegistry>,
+    tick_manager: Res<TickManager>,
+    config: Res<ServerConfig>,
     mut connection_manager: ResMut<ConnectionManager>,
     // TODO: currently we do not handle entities that are controlled by multiple clients
-    mut query: Query<Option<&mut InputBuffer<A>>>,
+    mut query: Query<(Option<&mut InputBuffer<A>>, Option<&ControlledBy>)>,
+    mut global_actions: ResMut<GlobalActions<A>>,
     mut commands: Commands,
     mut events: EventWriter<MessageEvent<InputMessage<A>>>,
+    mut too_far_ahead_events: EventWriter<InputTooFarAheadEvent>,
 ) {
     let kind = MessageKind::of::<InputMessage<A>>();
     let Some(net) = message_registry.kind_map.net_id(&kind).copied() else {
@@ -122,6 +243,22 @@ fn receive_input_message<A: LeafwingUserAction>(
                 ) {
                     Ok(message) => {
                         debug!(?client_id, action = ?A::short_type_path(), ?message.end_tick, ?message.diffs, "received input message");
+                        if config
+                            .input
+                            .is_too_far_ahead(message.end_tick, tick_manager.tick())
+                        {
+                            warn!(
+                                ?client_id,
+                                end_tick = ?message.end_tick,
+                                server_tick = ?tick_manager.tick(),
+                                "Dropping input message for ticks too far in the future"
+                            );
+                            too_far_ahead_events.send(InputTooFarAheadEvent {
+                                client_id: *client_id,
+                                end_tick: message.end_tick,
+                            });
+                            continue;
+                        }
                         // TODO: UPDATE THIS
                         for (target, start, diffs) in &message.diffs {
                             match target {
@@ -134,8 +271,17 @@ fn receive_input_message<A: LeafwingUserAction>(
                                     // TODO Don't update input buffer if inputs arrived too late?
                                     debug!("received input for entity: {:?}", entity);
 
-                                    if let Ok(buffer) = query.get_mut(*entity) {
-                                        if let Some(mut buffer) = buffer {
+                                    if let Ok((buffer, controlled_by)) = query.get_mut(*entity) {
+                                        if controlled_by.is_some_and(|controlled_by| {
+                                            controlled_by.target != NetworkTarget::None
+                                                && !controlled_by.targets(client_id)
+                                        }) {
+                                            debug!(
+                                                ?client_id,
+                                                ?entity,
+                                                "ignoring input for an entity controlled by another client"
+                                            );
+                                        } else if let Some(mut buffer) = buffer {
                                             debug!(
                                                 ?target,
                                                 "Update InputBuffer: {} using InputMessage: {}",
@@ -159,11 +305,12 @@ fn receive_input_message<A: LeafwingUserAction>(
                                     }
                                 }
                                 InputTarget::Global => {
-                                    // TODO: handle global diffs for each client! How? create one entity per client?
-                                    //  or have a resource containing the global ActionState for each client?
-                                    // if let Some(ref mut buffer) = global {
-                                    //     buffer.update_from_message(message.end_tick, std::mem::take(&mut message.global_diffs))
-                                    // }
+                                    debug!(?client_id, "received global input");
+                                    global_actions
+                                        .input_buffers
+                                        .entry(*client_id)
+                                        .or_default()
+                                        .update_from_message(message.end_tick, start, diffs);
                                 }
                             }
                         }
@@ -207,8 +354,7 @@ fn receive_input_message<A: LeafwingUserAction>(
 /// Read the InputState for the current tick from the buffer, and use them to update the ActionState
 fn update_action_state<A: LeafwingUserAction>(
     tick_manager: Res<TickManager>,
-    // global_input_buffer: Res<InputBuffer<A>>,
-    // global_action_state: Option<ResMut<ActionState<A>>>,
+    mut global_actions: ResMut<GlobalActions<A>>,
     mut action_state_query: Query<(Entity, &mut ActionState<A>, &mut InputBuffer<A>)>,
 ) {
     let tick = tick_manager.tick();
@@ -226,6 +372,17 @@ fn update_action_state<A: LeafwingUserAction>(
             input_buffer.pop(tick - 1);
         }
     }
+
+    let global_actions = global_actions.as_mut();
+    for (client_id, input_buffer) in global_actions.input_buffers.iter_mut() {
+        if let Some(action) = input_buffer.get(tick) {
+            global_actions
+                .action_states
+                .insert(*client_id, action.clone());
+            trace!(?tick, ?client_id, pressed = ?action.get_pressed(), "global action state after update");
+            input_buffer.pop(tick - 1);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -234,10 +391,11 @@ mod tests {
     use crate::inputs::leafwing::input_buffer::InputBuffer;
     use leafwing_input_manager::prelude::ActionState;
 
-    use crate::prelude::client;
+    use crate::prelude::client::{self, LeafwingInputConfig};
     use crate::prelude::server::*;
+    use crate::prelude::{ClientId, SharedConfig, TickConfig};
     use crate::tests::protocol::*;
-    use crate::tests::stepper::BevyStepper;
+    use crate::tests::stepper::{BevyStepper, TEST_CLIENT_ID};
 
     #[test]
     fn test_leafwing_inputs() {
@@ -341,4 +499,337 @@ mod tests {
             .unwrap()
             .released(&LeafwingInput1::Jump));
     }
+
+    /// Check that the inputs of a client are ignored for an entity controlled by another client,
+    /// and applied once the control is transferred to the client
+    #[test]
+    fn test_leafwing_inputs_controlled_by_other_client() {
+        let mut stepper = BevyStepper::default();
+        let other_client = ClientId::Netcode(TEST_CLIENT_ID + 1);
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((
+                ActionState::<LeafwingInput1>::default(),
+                Replicate {
+                    controlled_by: ControlledBy {
+                        target: NetworkTarget::Single(other_client),
+                        ..default()
+                    },
+                    ..default()
+                },
+            ))
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+        let client_entity = stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .unwrap();
+        stepper
+            .client_app
+            .world_mut()
+            .entity_mut(client_entity)
+            .insert(InputMap::<LeafwingInput1>::new([(
+                LeafwingInput1::Jump,
+                KeyCode::KeyA,
+            )]));
+        stepper.frame_step();
+
+        let pressed_on_server = |stepper: &BevyStepper, tick| {
+            stepper
+                .server_app
+                .world()
+                .entity(server_entity)
+                .get::<InputBuffer<LeafwingInput1>>()
+                .unwrap()
+                .get(tick)
+                .is_some_and(|action_state| action_state.pressed(&LeafwingInput1::Jump))
+        };
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::KeyA);
+        stepper.frame_step();
+        assert!(!pressed_on_server(&stepper, stepper.client_tick()));
+
+        // transfer the control of the entity to our client
+        stepper
+            .server_app
+            .world_mut()
+            .commands()
+            .entity(server_entity)
+            .transfer_control(ClientId::Netcode(TEST_CLIENT_ID));
+        stepper.server_app.world_mut().flush();
+        stepper.frame_step();
+        assert!(pressed_on_server(&stepper, stepper.client_tick()));
+    }
+
+    /// Check that in local co-op, the inputs of each locally-controlled entity (each with its own
+    /// InputMap) are sent separately and applied to the correct entity on the server
+    #[test]
+    fn test_leafwing_inputs_multiple_local_entities() {
+        let mut stepper = BevyStepper::default();
+        let server_entity_1 = stepper
+            .server_app
+            .world_mut()
+            .spawn((
+                ActionState::<LeafwingInput1>::default(),
+                Replicate::default(),
+            ))
+            .id();
+        let server_entity_2 = stepper
+            .server_app
+            .world_mut()
+            .spawn((
+                ActionState::<LeafwingInput1>::default(),
+                Replicate::default(),
+            ))
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+
+        // each player has its own InputMap
+        let client_entity = |stepper: &BevyStepper, server_entity| {
+            stepper
+                .client_app
+                .world()
+                .resource::<client::ConnectionManager>()
+                .replication_receiver
+                .remote_entity_map
+                .get_local(server_entity)
+                .unwrap()
+        };
+        let client_entity_1 = client_entity(&stepper, server_entity_1);
+        let client_entity_2 = client_entity(&stepper, server_entity_2);
+        stepper
+            .client_app
+            .world_mut()
+            .entity_mut(client_entity_1)
+            .insert(InputMap::<LeafwingInput1>::new([(
+                LeafwingInput1::Jump,
+                KeyCode::KeyA,
+            )]));
+        stepper
+            .client_app
+            .world_mut()
+            .entity_mut(client_entity_2)
+            .insert(InputMap::<LeafwingInput1>::new([(
+                LeafwingInput1::Dash,
+                KeyCode::KeyB,
+            )]));
+        stepper.frame_step();
+
+        // both players press their action on the same tick
+        let mut keys = stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>();
+        keys.press(KeyCode::KeyA);
+        keys.press(KeyCode::KeyB);
+        stepper.frame_step();
+        let client_tick = stepper.client_tick();
+        stepper.frame_step();
+
+        let server_action_state = |stepper: &BevyStepper, server_entity| {
+            stepper
+                .server_app
+                .world()
+                .entity(server_entity)
+                .get::<InputBuffer<LeafwingInput1>>()
+                .unwrap()
+                .get(client_tick)
+                .unwrap()
+                .clone()
+        };
+        let action_state_1 = server_action_state(&stepper, server_entity_1);
+        assert!(action_state_1.pressed(&LeafwingInput1::Jump));
+        assert!(!action_state_1.pressed(&LeafwingInput1::Dash));
+        let action_state_2 = server_action_state(&stepper, server_entity_2);
+        assert!(action_state_2.pressed(&LeafwingInput1::Dash));
+        assert!(!action_state_2.pressed(&LeafwingInput1::Jump));
+    }
+
+    /// Check that the global inputs (stored in a resource on the client) are applied to the
+    /// client's global ActionState on the server
+    #[test]
+    fn test_leafwing_global_inputs() {
+        let mut stepper = BevyStepper::default();
+        stepper
+            .client_app
+            .insert_resource(InputMap::<LeafwingInput1>::new([(
+                LeafwingInput1::Jump,
+                KeyCode::KeyA,
+            )]))
+            .init_resource::<ActionState<LeafwingInput1>>();
+        stepper.frame_step();
+
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::KeyA);
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+
+        let client_id = ClientId::Netcode(TEST_CLIENT_ID);
+        assert!(stepper
+            .server_app
+            .world()
+            .resource::<GlobalActions<LeafwingInput1>>()
+            .action_state(client_id)
+            .expect("the server did not receive the global inputs")
+            .pressed(&LeafwingInput1::Jump));
+
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .release(KeyCode::KeyA);
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        assert!(stepper
+            .server_app
+            .world()
+            .resource::<GlobalActions<LeafwingInput1>>()
+            .action_state(client_id)
+            .unwrap()
+            .released(&LeafwingInput1::Jump));
+    }
+
+    #[derive(
+        serde::Serialize,
+        serde::Deserialize,
+        Debug,
+        PartialEq,
+        Eq,
+        Clone,
+        Copy,
+        Hash,
+        Reflect,
+        Actionlike,
+    )]
+    enum SpectatorAction {
+        Fire,
+    }
+
+    /// Check that the server's ActionState is replicated to the clients that don't control the entity
+    #[test]
+    fn test_replicate_action_state_to_spectators() {
+        let frame_duration = bevy::utils::Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(frame_duration),
+            ..default()
+        };
+        let mut stepper = BevyStepper::new(
+            shared_config,
+            client::ClientConfig::default(),
+            frame_duration,
+        );
+        let config =
+            LeafwingInputConfig::<SpectatorAction>::default().with_replicate_action_state(true);
+        stepper
+            .client_app
+            .add_plugins(crate::prelude::LeafwingInputPlugin::<SpectatorAction> { config });
+        stepper
+            .server_app
+            .add_plugins(crate::prelude::LeafwingInputPlugin::<SpectatorAction> { config });
+        stepper.init();
+
+        // the client is a spectator of this entity
+        let spectated_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((
+                ActionState::<SpectatorAction>::default(),
+                Replicate::default(),
+            ))
+            .id();
+        // the client controls this entity
+        let controlled_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((
+                ActionState::<SpectatorAction>::default(),
+                Replicate {
+                    controlled_by: ControlledBy {
+                        target: NetworkTarget::Single(ClientId::Netcode(TEST_CLIENT_ID)),
+                        ..default()
+                    },
+                    ..default()
+                },
+            ))
+            .id();
+        // the user provided their own replication target for the ActionState
+        let overridden_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((
+                ActionState::<SpectatorAction>::default(),
+                Replicate::default(),
+                OverrideTargetComponent::<ActionState<SpectatorAction>>::new(NetworkTarget::None),
+            ))
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+
+        for entity in [spectated_entity, controlled_entity, overridden_entity] {
+            stepper
+                .server_app
+                .world_mut()
+                .get_mut::<ActionState<SpectatorAction>>(entity)
+                .unwrap()
+                .press(&SpectatorAction::Fire);
+        }
+        stepper.frame_step();
+        stepper.frame_step();
+
+        let client_entity = |stepper: &BevyStepper, server_entity| {
+            stepper
+                .client_app
+                .world()
+                .resource::<client::ConnectionManager>()
+                .replication_receiver
+                .remote_entity_map
+                .get_local(server_entity)
+                .unwrap()
+        };
+        let spectated_client_entity = client_entity(&stepper, spectated_entity);
+        assert!(stepper
+            .client_app
+            .world()
+            .get::<ActionState<SpectatorAction>>(spectated_client_entity)
+            .expect("the ActionState was not replicated to the spectator")
+            .pressed(&SpectatorAction::Fire));
+        // the controlling client does not receive the server's ActionState
+        let controlled_client_entity = client_entity(&stepper, controlled_entity);
+        assert!(stepper
+            .client_app
+            .world()
+            .get::<ActionState<SpectatorAction>>(controlled_client_entity)
+            .is_none());
+        // the user-provided target is not overwritten
+        assert_eq!(
+            stepper
+                .server_app
+                .world()
+                .get::<OverrideTargetComponent<ActionState<SpectatorAction>>>(overridden_entity)
+                .unwrap()
+                .target,
+            NetworkTarget::None
+        );
+        let overridden_client_entity = client_entity(&stepper, overridden_entity);
+        assert!(stepper
+            .client_app
+            .world()
+            .get::<ActionState<SpectatorAction>>(overridden_client_entity)
+            .is_none());
+    }
 }
@@ -1,5 +1,92 @@
+use bevy::prelude::{Event, Reflect};
+
+use crate::prelude::{ClientId, Tick};
+
 pub mod native;
 
 #[cfg_attr(docsrs, doc(cfg(feature = "leafwing")))]
 #[cfg(feature = "leafwing")]
 pub mod leafwing;
+
+/// Configuration of how the server handles the inputs received from the clients
+#[derive(Debug, Clone, Copy, Reflect)]
+pub struct ServerInputConfig {
+    /// Maximum number of ticks ahead of the server tick for which we buffer the inputs of a client.
+    ///
+    /// The clients run ahead of the server, so their inputs usually arrive a few ticks in advance.
+    /// Input messages that contain inputs beyond this window are dropped (and an [`InputTooFarAheadEvent`]
+    /// is emitted), so that a misbehaving client cannot make the server buffer inputs indefinitely.
+    pub max_future_ticks: u16,
+    /// Maximum number of ticks for which the server extrapolates the input of a client whose inputs
+    /// didn't arrive yet, by assuming that the input stayed the same as the last received input.
+    ///
+    /// Absent inputs received from the client (for example because a key was released) are never extrapolated.
+    /// After this number of ticks, missing inputs are emitted as `None`.
+    /// The default is 0: missing inputs are emitted as `None` right away.
+    ///
+    /// Extrapolating the input that the client keeps pressing means that the server simulation matches
+    /// the client's prediction, so the client does not need to rollback once the late inputs arrive.
+    pub max_extrapolation_ticks: u16,
+    /// Number of ticks that the server waits for the missing inputs of a client before requesting them
+    /// with an [`InputNack`](crate::inputs::native::InputNack).
+    ///
+    /// Input messages can arrive out of order, so a gap in the received ticks is often filled by a message
+    /// that was only delayed. A nack is only sent for the ticks that are still missing after this delay.
+    /// This only applies to the native inputs: the leafwing inputs do not use nacks.
+    pub nack_delay_ticks: u16,
+    /// If true, the server forwards the native inputs received from each client to the other clients,
+    /// which emit them as [`RemoteInputEvent`](crate::prelude::client::RemoteInputEvent)s so that they can
+    /// predict the entities controlled by remote players.
+    ///
+    /// This is disabled by default because it multiplies the input traffic by the number of clients.
+    pub rebroadcast_inputs: bool,
+}
+
+impl Default for ServerInputConfig {
+    fn default() -> Self {
+        Self {
+            max_future_ticks: 256,
+            max_extrapolation_ticks: 0,
+            nack_delay_ticks: 1,
+            rebroadcast_inputs: false,
+        }
+    }
+}
+
+impl ServerInputConfig {
+    pub fn with_max_future_ticks(mut self, max_future_ticks: u16) -> Self {
+        self.max_future_ticks = max_future_ticks;
+        self
+    }
+
+    pub fn with_max_extrapolation_ticks(mut self, max_extrapolation_ticks: u16) -> Self {
+        self.max_extrapolation_ticks = max_extrapolation_ticks;
+        self
+    }
+
+    pub fn with_nack_delay_ticks(mut self, nack_delay_ticks: u16) -> Self {
+        self.nack_delay_ticks = nack_delay_ticks;
+        self
+    }
+
+    pub fn with_rebroadcast_inputs(mut self, rebroadcast_inputs: bool) -> Self {
+        self.rebroadcast_inputs = rebroadcast_inputs;
+        self
+    }
+
+    /// Returns true if an input message ending at `end_tick` is too far ahead of the server `tick`
+    pub(crate) fn is_too_far_ahead(&self, end_tick: Tick, tick: Tick) -> bool {
+        (end_tick - tick) as i32 > self.max_future_ticks as i32
+    }
+}
+
+/// Event emitted when the server drops an input message from a client because it contains inputs
+/// for ticks that are too far in the future (see [`ServerInputConfig::max_future_ticks`]).
+///
+/// This usually means that the client is misbehaving; you can use this event to kick it.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct InputTooFarAheadEvent {
+    pub client_id: ClientId,
+    /// Last tick of the dropped input message
+    pub end_tick: Tick,
+}
@@ -2,14 +2,19 @@
 use bevy::prelude::*;
 use bevy::utils::HashMap;
 
+use crate::channel::builder::{InputChannel, InputRecoveryChannel};
 use crate::inputs::native::input_buffer::InputBuffer;
-use crate::inputs::native::InputMessage;
+use crate::inputs::native::{InputMessage, InputNack, RemoteInputMessage};
 use crate::prelude::server::DisconnectEvent;
-use crate::prelude::{server::is_started, ClientId, MessageRegistry, TickManager, UserAction};
+use crate::prelude::{
+    server::is_started, ClientId, MessageRegistry, Tick, TickManager, UserAction,
+};
 use crate::protocol::message::MessageKind;
 use crate::serialize::reader::Reader;
+use crate::server::config::ServerConfig;
 use crate::server::connection::ConnectionManager;
 use crate::server::events::InputEvent;
+use crate::server::input::InputTooFarAheadEvent;
 use crate::shared::replication::network_target::NetworkTarget;
 use crate::shared::sets::{InternalMainSet, ServerMarker};
 
@@ -19,15 +24,51 @@ pub struct InputPlugin<A> {
 
 #[derive(Resource, Debug)]
 pub struct InputBuffers<A> {
-    /// The first element stores the last input we have received from the client.
-    /// In case we are missing the client input for a tick, we will fallback to using this.
+    /// The first element stores the input of the last received tick that was simulated.
+    /// In case we are missing the client input for a tick, we extrapolate it from this input.
     buffers: HashMap<ClientId, (Option<A>, InputBuffer<A>)>,
+    /// Most recent tick for which we received an input message from each client.
+    /// Used to detect gaps in the received inputs.
+    last_received_ticks: HashMap<ClientId, Tick>,
+    /// Ranges of ticks for which we are still missing the inputs of each client.
+    /// They are only requested with an [`InputNack`] if they are not filled by a late message
+    /// within [`ServerInputConfig::nack_delay_ticks`](crate::server::input::ServerInputConfig::nack_delay_ticks).
+    missing_ticks: HashMap<ClientId, Vec<MissingTicks>>,
+}
+
+/// Range of ticks for which the inputs of a client are missing
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct MissingTicks {
+    start_tick: Tick,
+    end_tick: Tick,
+    /// Server tick at which the gap was detected
+    detected_tick: Tick,
+}
+
+impl MissingTicks {
+    /// Remove the ticks `start_tick..=end_tick` from the missing range, which can split it in two
+    fn remove(self, start_tick: Tick, end_tick: Tick) -> impl Iterator<Item = Self> {
+        if end_tick < self.start_tick || start_tick > self.end_tick {
+            return [Some(self), None].into_iter().flatten();
+        }
+        let before = (self.start_tick < start_tick).then_some(Self {
+            end_tick: start_tick - 1,
+            ..self
+        });
+        let after = (end_tick < self.end_tick).then_some(Self {
+            start_tick: end_tick + 1,
+            ..self
+        });
+        [before, after].into_iter().flatten()
+    }
 }
 
 impl<A> Default for InputBuffers<A> {
     fn default() -> Self {
         Self {
             buffers: HashMap::default(),
+            last_received_ticks: HashMap::default(),
+            missing_ticks: HashMap::default(),
         }
     }
 }
@@ -56,6 +97,7 @@ impl<A: UserAction> Plugin for InputPlugin<A> {
         app.init_resource::<InputBuffers<A>>();
         // EVENTS
         app.add_event::<InputEvent<A>>();
+        app.add_event::<InputTooFarAheadEvent>();
         // SETS
         app.configure_sets(
             PreUpdate,
@@ -94,14 +136,35 @@ fn handle_client_disconnect<A: UserAction>(
     mut input_buffers: ResMut<InputBuffers<A>>,
 ) {
     input_buffers.buffers.remove(&trigger.event().client_id);
+    input_buffers
+        .last_received_ticks
+        .remove(&trigger.event().client_id);
+    input_buffers
+        .missing_ticks
+        .remove(&trigger.event().client_id);
 }
 
 /// Read the message received from the client and emit the MessageEvent event
+///
+/// If there is a gap between the ticks of the input messages received from a client (for example because
+/// the packets got lost and the redundancy was not enough to cover it), we send an [`InputNack`] to the client
+/// to request the missing inputs, unless the gap gets filled by a late message within
+/// [`ServerInputConfig::nack_delay_ticks`](crate::server::input::ServerInputConfig::nack_delay_ticks).
+///
+/// Input messages that arrive out of order are still used to update the input buffer: only the ticks that
+/// the server has already simulated are ignored.
+///
+/// Input messages for ticks too far in the future (see [`ServerInputConfig`](crate::server::input::ServerInputConfig))
+/// are dropped, and an [`InputTooFarAheadEvent`] is emitted.
 fn receive_input_message<A: UserAction>(
     message_registry: Res<MessageRegistry>,
+    tick_manager: Res<TickManager>,
+    config: Res<ServerConfig>,
     mut connection_manager: ResMut<ConnectionManager>,
     mut input_buffers: ResMut<InputBuffers<A>>,
+    mut too_far_ahead_events: EventWriter<InputTooFarAheadEvent>,
 ) {
+    let tick = tick_manager.tick();
     let kind = MessageKind::of::<InputMessage<A>>();
     let Some(net) = message_registry.kind_map.net_id(&kind).copied() else {
         error!(
@@ -110,6 +173,7 @@ fn receive_input_message<A: UserAction>(
         );
         return;
     };
+    let mut remote_input_messages = Vec::new();
     for (client_id, connection) in connection_manager.connections.iter_mut() {
         if let Some(message_list) = connection.received_input_messages.remove(&net) {
             for (message_bytes, target, channel_kind) in message_list {
@@ -123,6 +187,66 @@ fn receive_input_message<A: UserAction>(
                 ) {
                     Ok(message) => {
                         debug!("Received input message: {:?}", message);
+                        let start_tick = message.start_tick();
+                        let end_tick = message.end_tick;
+                        if config.input.is_too_far_ahead(end_tick, tick_manager.tick()) {
+                            warn!(
+                                ?client_id,
+                                ?end_tick,
+                                server_tick = ?tick_manager.tick(),
+                                "Dropping input message for ticks too far in the future"
+                            );
+                            too_far_ahead_events.send(InputTooFarAheadEvent {
+                                client_id: *client_id,
+                                end_tick,
+                            });
+                            continue;
+                        }
+                        let missing_ticks =
+                            input_buffers.missing_ticks.entry(*client_id).or_default();
+                        // this message might fill a gap, if it arrived out of order
+                        *missing_ticks = missing_ticks
+                            .drain(..)
+                            .flat_map(|missing| missing.remove(start_tick, end_tick))
+                            .collect();
+                        if let Some(last_tick) =
+                            input_buffers.last_received_ticks.get(client_id).copied()
+                        {
+                            // inputs for ticks that the server already simulated are useless
+                            let gap_start = std::cmp::max(last_tick + 1, tick + 1);
+                            if gap_start < start_tick {
+                                debug!(
+                                    ?client_id,
+                                    ?gap_start,
+                                    gap_end = ?(start_tick - 1),
+                                    "Missing inputs from client"
+                                );
+                                input_buffers
+                                    .missing_ticks
+                                    .entry(*client_id)
+                                    .or_default()
+                                    .push(MissingTicks {
+                                        start_tick: gap_start,
+                                        end_tick: start_tick - 1,
+                                        detected_tick: tick,
+                                    });
+                            }
+                        }
+                        if input_buffers
+                            .last_received_ticks
+                            .get(client_id)
+                            .map_or(true, |last_tick| end_tick > *last_tick)
+                        {
+                            input_buffers
+                                .last_received_ticks
+                                .insert(*client_id, end_tick);
+                        }
+                        if config.input.rebroadcast_inputs {
+                            remote_input_messages.push(RemoteInputMessage {
+                                client_id: *client_id,
+                                message: message.clone(),
+                            });
+                        }
                         input_buffers
                             .buffers
                             .entry(*client_id)
@@ -146,43 +270,98 @@ fn receive_input_message<A: UserAction>(
             }
         }
     }
+    // forward the inputs to the other clients, so that they can predict the entities of remote players
+    for mut remote_input_message in remote_input_messages {
+        let target = NetworkTarget::AllExceptSingle(remote_input_message.client_id);
+        connection_manager
+            .send_message_to_target::<InputChannel, _>(&mut remote_input_message, target)
+            .unwrap_or_else(|err| {
+                error!("Error while forwarding input message: {:?}", err);
+            });
+    }
+    // request the inputs that are still missing after the nack delay
+    let mut nacks = Vec::new();
+    for (client_id, missing_ticks) in input_buffers.missing_ticks.iter_mut() {
+        missing_ticks.retain(|missing| {
+            // inputs for ticks that the server already simulated are useless
+            let start_tick = std::cmp::max(missing.start_tick, tick + 1);
+            if start_tick > missing.end_tick {
+                return false;
+            }
+            if tick - missing.detected_tick < config.input.nack_delay_ticks as i16 {
+                return true;
+            }
+            debug!(
+                ?client_id,
+                ?start_tick,
+                end_tick = ?missing.end_tick,
+                "Missing inputs from client, sending InputNack"
+            );
+            nacks.push((
+                *client_id,
+                InputNack::<A>::new(start_tick, missing.end_tick),
+            ));
+            false
+        });
+    }
+    for (client_id, mut nack) in nacks {
+        connection_manager
+            .send_message::<InputRecoveryChannel, _>(client_id, &mut nack)
+            .unwrap_or_else(|err| {
+                error!("Error while sending input nack: {:?}", err);
+            });
+    }
 }
 
 // Create a system that reads from the input buffer and returns the inputs of all clients for the current tick.
 // The only tricky part is that events are cleared every frame, but we want to clear every tick instead
 // Do it in this system because we want an input for every tick
 fn write_input_event<A: UserAction>(
+    config: Res<ServerConfig>,
     tick_manager: Res<TickManager>,
     mut input_buffers: ResMut<InputBuffers<A>>,
     mut input_events: EventWriter<InputEvent<A>>,
 ) {
     let tick = tick_manager.tick();
-    input_buffers
-        .buffers
+    let InputBuffers {
+        buffers,
+        last_received_ticks,
+        ..
+    } = input_buffers.as_mut();
+    buffers
         .iter_mut()
-        .for_each(move |(client_id, (last_input, input_buffer))| {
+        .for_each(|(client_id, (last_input, input_buffer))| {
             debug!(?input_buffer, ?tick, ?client_id, "input buffer for client");
             let received_input = input_buffer.pop(tick);
-            let fallback = received_input.is_none();
-
-            // NOTE: if there is no input for this tick, we should use the last input that we have
-            //  as a best-effort fallback.
-            let input = match received_input {
-                None => last_input.clone(),
-                Some(i) => {
-                    *last_input = Some(i.clone());
-                    Some(i)
+            let last_received_tick = last_received_ticks.get(client_id).copied();
+            let input = match last_received_tick {
+                // the client sent the input for this tick (an absent input means that nothing was pressed)
+                Some(last_received_tick) if tick <= last_received_tick => {
+                    *last_input = received_input.clone();
+                    received_input
+                }
+                // NOTE: if there is no input for this tick, we use the last input that we have
+                //  as a best-effort fallback.
+                _ => {
+                    let extrapolated = last_received_tick.is_some_and(|last_received_tick| {
+                        (tick - last_received_tick) as i32
+                            <= config.input.max_extrapolation_ticks as i32
+                    });
+                    let input = if extrapolated {
+                        last_input.clone()
+                    } else {
+                        None
+                    };
+                    // TODO: do not log this while clients are syncing..
+                    debug!(
+                        ?client_id,
+                        ?tick,
+                        fallback_input = ?&input,
+                        "Missed client input!"
+                    );
+                    input
                 }
             };
-            if fallback {
-                // TODO: do not log this while clients are syncing..
-                debug!(
-                ?client_id,
-                ?tick,
-                fallback_input = ?&input,
-                "Missed client input!"
-                )
-            }
             // TODO: We should also let the user know that it needs to send inputs a bit earlier so that
             //  we have more of a buffer. Send a SyncMessage to tell the user to speed up?
             //  See Overwatch GDC video
@@ -195,3 +374,593 @@ fn write_input_event<A: UserAction>(
 fn clear_input_events<A: UserAction>(mut input_events: EventReader<InputEvent<A>>) {
     input_events.clear();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channel::builder::InputChannel;
+    use crate::client::connection::ConnectionManager as ClientConnectionManager;
+    use crate::client::events::{
+        InputEvent as ClientInputEvent, MessageEvent as ClientMessageEvent,
+    };
+    use crate::client::input::native::InputSystemSet as ClientInputSystemSet;
+    use crate::inputs::native::input_buffer::InputData;
+    use crate::prelude::client::{ClientConfig, InputConfig, InputManager};
+    use crate::prelude::client::{Confirmed, Predicted};
+    use crate::prelude::server::{Replicate, SyncTarget};
+    use crate::prelude::{SharedConfig, TickConfig};
+    use crate::protocol::channel::ChannelKind;
+    use crate::protocol::registry::NetId;
+    use crate::tests::protocol::{ComponentSyncModeFull, MyInput};
+    use crate::tests::stepper::{BevyStepper, TEST_CLIENT_ID};
+    use bevy::utils::Duration;
+    use bytes::Bytes;
+
+    fn press_tick_input(
+        mut input_manager: ResMut<InputManager<MyInput>>,
+        tick_manager: Res<TickManager>,
+    ) {
+        let tick = tick_manager.tick();
+        input_manager.add_input(MyInput(tick.0 as i16), tick);
+    }
+
+    type ReceivedMessages = HashMap<NetId, Vec<(Bytes, NetworkTarget, ChannelKind)>>;
+
+    #[derive(Resource, Default)]
+    struct DelayInputMessages {
+        delay: bool,
+        /// Number of extra frames during which the messages are held back
+        extra_frames: u8,
+        held: Option<ReceivedMessages>,
+    }
+
+    /// Hold back the input messages received during one frame, and deliver them after
+    /// the input messages received during the next frame (or `extra_frames` frames later)
+    fn delay_input_messages(
+        mut delay: ResMut<DelayInputMessages>,
+        mut connection_manager: ResMut<ConnectionManager>,
+    ) {
+        let Some(connection) = connection_manager
+            .connections
+            .get_mut(&ClientId::Netcode(TEST_CLIENT_ID))
+        else {
+            return;
+        };
+        if std::mem::take(&mut delay.delay) {
+            delay.held = Some(std::mem::take(&mut connection.received_input_messages));
+        } else if delay.held.is_some() && delay.extra_frames > 0 {
+            delay.extra_frames -= 1;
+        } else if let Some(held) = delay.held.take() {
+            for (net, messages) in held {
+                connection
+                    .received_input_messages
+                    .entry(net)
+                    .or_default()
+                    .extend(messages);
+            }
+        }
+    }
+
+    #[derive(Resource, Default)]
+    struct ReceivedNacks(Vec<InputNack<MyInput>>);
+
+    fn collect_nacks(
+        mut events: EventReader<ClientMessageEvent<InputNack<MyInput>>>,
+        mut nacks: ResMut<ReceivedNacks>,
+    ) {
+        nacks
+            .0
+            .extend(events.read().map(|event| event.message().clone()));
+    }
+
+    /// Check that an input message that arrives after a more recent input message is still
+    /// applied for the ticks that the server has not simulated yet
+    #[test]
+    fn test_late_input_message() {
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..default()
+        };
+        let client_config = ClientConfig {
+            input: InputConfig {
+                // each input message only contains the input for the current tick
+                packet_redundancy: 1,
+                ..default()
+            },
+            ..default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, client_config, tick_duration);
+        stepper.client_app.add_systems(
+            FixedPreUpdate,
+            press_tick_input.in_set(ClientInputSystemSet::BufferInputs),
+        );
+        stepper.server_app.init_resource::<DelayInputMessages>();
+        stepper.server_app.add_systems(
+            PreUpdate,
+            delay_input_messages
+                .after(InternalMainSet::<ServerMarker>::Receive)
+                .before(InputSystemSet::ReceiveInputMessage),
+        );
+        stepper.init();
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+
+        // the input message sent during this frame is delivered after the one sent during the next frame
+        let late_tick = stepper.client_tick() + 1;
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<DelayInputMessages>()
+            .delay = true;
+        stepper.frame_step();
+        assert!(stepper
+            .server_app
+            .world()
+            .resource::<DelayInputMessages>()
+            .held
+            .is_some());
+        stepper.frame_step();
+
+        // the server has not reached the tick of the late message yet, so its input is applied
+        assert!(stepper.server_tick() < late_tick);
+        let input_buffers = stepper
+            .server_app
+            .world()
+            .resource::<InputBuffers<MyInput>>();
+        let buffer = &input_buffers
+            .buffers
+            .get(&ClientId::Netcode(TEST_CLIENT_ID))
+            .unwrap()
+            .1;
+        assert_eq!(buffer.get(late_tick), Some(&MyInput(late_tick.0 as i16)));
+        assert_eq!(
+            buffer.get(late_tick + 1),
+            Some(&MyInput((late_tick + 1).0 as i16))
+        );
+    }
+
+    /// Check that the server waits for `nack_delay_ticks` before requesting missing inputs, so that
+    /// an input message that arrives out of order does not trigger an [`InputNack`]
+    #[test]
+    fn test_out_of_order_input_message_not_nacked() {
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..default()
+        };
+        let client_config = ClientConfig {
+            input: InputConfig {
+                // each input message only contains the input for the current tick
+                packet_redundancy: 1,
+                ..default()
+            },
+            ..default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, client_config, tick_duration);
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ServerConfig>()
+            .input
+            .nack_delay_ticks = 2;
+        stepper.client_app.add_systems(
+            FixedPreUpdate,
+            press_tick_input.in_set(ClientInputSystemSet::BufferInputs),
+        );
+        stepper.server_app.init_resource::<DelayInputMessages>();
+        stepper.server_app.add_systems(
+            PreUpdate,
+            delay_input_messages
+                .after(InternalMainSet::<ServerMarker>::Receive)
+                .before(InputSystemSet::ReceiveInputMessage),
+        );
+        stepper.client_app.init_resource::<ReceivedNacks>();
+        stepper.client_app.add_systems(Update, collect_nacks);
+        stepper.init();
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+
+        // the input message sent during this frame arrives one frame after the next message
+        stepper
+            .server_app
+            .world_mut()
+            .insert_resource(DelayInputMessages {
+                delay: true,
+                extra_frames: 1,
+                held: None,
+            });
+        stepper.frame_step();
+        stepper.frame_step();
+        let missing_ticks = |stepper: &BevyStepper| {
+            stepper
+                .server_app
+                .world()
+                .resource::<InputBuffers<MyInput>>()
+                .missing_ticks
+                .get(&ClientId::Netcode(TEST_CLIENT_ID))
+                .unwrap()
+                .len()
+        };
+        assert_eq!(missing_ticks(&stepper), 1);
+        stepper.frame_step();
+
+        // the gap was filled by the late message, so the server does not request the inputs
+        assert_eq!(missing_ticks(&stepper), 0);
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        assert_eq!(
+            stepper.client_app.world().resource::<ReceivedNacks>().0,
+            vec![]
+        );
+    }
+
+    #[test]
+    fn test_remove_missing_ticks() {
+        let missing = MissingTicks {
+            start_tick: Tick(10),
+            end_tick: Tick(20),
+            detected_tick: Tick(5),
+        };
+        let remove = |start, end| {
+            missing
+                .remove(Tick(start), Tick(end))
+                .map(|m| (m.start_tick.0, m.end_tick.0))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(remove(0, 9), vec![(10, 20)]);
+        assert_eq!(remove(21, 30), vec![(10, 20)]);
+        assert_eq!(remove(5, 12), vec![(13, 20)]);
+        assert_eq!(remove(18, 25), vec![(10, 17)]);
+        assert_eq!(remove(12, 15), vec![(10, 11), (16, 20)]);
+        assert_eq!(remove(10, 20), vec![]);
+    }
+
+    #[derive(Resource, Default)]
+    struct TooFarAheadEvents(Vec<InputTooFarAheadEvent>);
+
+    fn collect_too_far_ahead_events(
+        mut events: EventReader<InputTooFarAheadEvent>,
+        mut collected: ResMut<TooFarAheadEvents>,
+    ) {
+        collected.0.extend(events.read().copied());
+    }
+
+    /// Check that the inputs for ticks too far in the future are dropped and the client is flagged,
+    /// while the regular inputs are still buffered
+    #[test]
+    fn test_input_too_far_ahead() {
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, ClientConfig::default(), tick_duration);
+        stepper.client_app.add_systems(
+            FixedPreUpdate,
+            press_tick_input.in_set(ClientInputSystemSet::BufferInputs),
+        );
+        stepper.server_app.init_resource::<TooFarAheadEvents>();
+        stepper
+            .server_app
+            .add_systems(Update, collect_too_far_ahead_events);
+        stepper.init();
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+
+        let far_tick = stepper.client_tick() + 10000;
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<ClientConnectionManager>()
+            .send_message::<InputChannel, _>(&mut InputMessage::<MyInput> {
+                end_tick: far_tick,
+                inputs: vec![InputData::Input(MyInput(1))],
+            })
+            .unwrap();
+        stepper.frame_step();
+        stepper.frame_step();
+
+        assert_eq!(
+            stepper.server_app.world().resource::<TooFarAheadEvents>().0,
+            vec![InputTooFarAheadEvent {
+                client_id: ClientId::Netcode(TEST_CLIENT_ID),
+                end_tick: far_tick,
+            }]
+        );
+        let input_buffers = stepper
+            .server_app
+            .world()
+            .resource::<InputBuffers<MyInput>>();
+        let buffer = &input_buffers
+            .buffers
+            .get(&ClientId::Netcode(TEST_CLIENT_ID))
+            .unwrap()
+            .1;
+        assert_eq!(buffer.get(far_tick), None);
+        // the regular inputs are still received
+        let client_tick = stepper.client_tick();
+        assert_eq!(
+            buffer.get(client_tick),
+            Some(&MyInput(client_tick.0 as i16))
+        );
+    }
+
+    #[derive(Resource)]
+    struct Pressing(bool);
+
+    fn press_input_if_pressing(
+        pressing: Res<Pressing>,
+        mut input_manager: ResMut<InputManager<MyInput>>,
+        tick_manager: Res<TickManager>,
+    ) {
+        if pressing.0 {
+            input_manager.add_input(MyInput(2), tick_manager.tick());
+        }
+    }
+
+    #[derive(Resource, Default)]
+    struct DropInputMessages(bool);
+
+    /// Simulate the input messages getting lost or delayed
+    fn drop_input_messages(
+        drop: Res<DropInputMessages>,
+        mut connection_manager: ResMut<ConnectionManager>,
+    ) {
+        if drop.0 {
+            for connection in connection_manager.connections.values_mut() {
+                connection.received_input_messages.clear();
+            }
+        }
+    }
+
+    #[derive(Resource, Default)]
+    struct ServerInputs(Vec<(Tick, Option<MyInput>)>);
+
+    fn record_server_inputs(
+        tick_manager: Res<TickManager>,
+        mut inputs: ResMut<ServerInputs>,
+        mut events: EventReader<InputEvent<MyInput>>,
+    ) {
+        for event in events.read() {
+            inputs.0.push((tick_manager.tick(), *event.input()));
+        }
+    }
+
+    fn extrapolation_stepper() -> BevyStepper {
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, ClientConfig::default(), tick_duration);
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ServerConfig>()
+            .input
+            .max_extrapolation_ticks = 2;
+        stepper.client_app.insert_resource(Pressing(true));
+        stepper.client_app.add_systems(
+            FixedPreUpdate,
+            press_input_if_pressing.in_set(ClientInputSystemSet::BufferInputs),
+        );
+        stepper.server_app.init_resource::<DropInputMessages>();
+        stepper.server_app.init_resource::<ServerInputs>();
+        stepper.server_app.add_systems(
+            PreUpdate,
+            drop_input_messages
+                .after(InternalMainSet::<ServerMarker>::Receive)
+                .before(InputSystemSet::ReceiveInputMessage),
+        );
+        stepper
+            .server_app
+            .add_systems(FixedUpdate, record_server_inputs);
+        stepper.init();
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        stepper
+    }
+
+    fn last_received_tick(stepper: &BevyStepper) -> Tick {
+        *stepper
+            .server_app
+            .world()
+            .resource::<InputBuffers<MyInput>>()
+            .last_received_ticks
+            .get(&ClientId::Netcode(TEST_CLIENT_ID))
+            .unwrap()
+    }
+
+    /// Check that the server extrapolates the last received input of a client for up to
+    /// `max_extrapolation_ticks` ticks when the inputs of the client are missing
+    #[test]
+    fn test_input_extrapolation() {
+        let mut stepper = extrapolation_stepper();
+        stepper.server_app.insert_resource(DropInputMessages(true));
+        let last_received_tick = last_received_tick(&stepper);
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ServerInputs>()
+            .0
+            .clear();
+        for _ in 0..20 {
+            stepper.frame_step();
+        }
+        assert!(stepper.server_tick() > last_received_tick + 2);
+        for (tick, input) in &stepper.server_app.world().resource::<ServerInputs>().0 {
+            if *tick <= last_received_tick + 2 {
+                assert_eq!(input, &Some(MyInput(2)), "tick {tick:?}");
+            } else {
+                assert_eq!(input, &None, "tick {tick:?}");
+            }
+        }
+    }
+
+    /// Check that an input that the client released is not extrapolated by the server
+    #[test]
+    fn test_input_release_not_extrapolated() {
+        let mut stepper = extrapolation_stepper();
+        // release the input: the input messages still contain the pressed inputs of the previous ticks
+        stepper.client_app.insert_resource(Pressing(false));
+        let release_tick = stepper.client_tick() + 1;
+        stepper.frame_step();
+        stepper.server_app.insert_resource(DropInputMessages(true));
+        assert!(last_received_tick(&stepper) >= release_tick);
+        for _ in 0..20 {
+            stepper.frame_step();
+        }
+        let inputs = &stepper.server_app.world().resource::<ServerInputs>().0;
+        assert!(inputs.iter().any(|(tick, _)| *tick >= release_tick + 2));
+        for (tick, input) in inputs {
+            if *tick < release_tick {
+                assert_eq!(input, &Some(MyInput(2)), "tick {tick:?}");
+            } else {
+                assert_eq!(input, &None, "tick {tick:?}");
+            }
+        }
+    }
+
+    #[derive(Resource, Default)]
+    struct PressedTicks(u32);
+
+    fn count_pressed_ticks(pressing: Res<Pressing>, mut pressed_ticks: ResMut<PressedTicks>) {
+        if pressing.0 {
+            pressed_ticks.0 += 1;
+        }
+    }
+
+    /// Move the client's entity by one unit for each tick where the input is pressed
+    fn server_move(
+        mut events: EventReader<InputEvent<MyInput>>,
+        mut query: Query<&mut ComponentSyncModeFull>,
+    ) {
+        for event in events.read() {
+            if event.input().is_some() {
+                for mut component in query.iter_mut() {
+                    component.0 += 1.0;
+                }
+            }
+        }
+    }
+
+    fn client_move(
+        mut events: EventReader<ClientInputEvent<MyInput>>,
+        mut query: Query<&mut ComponentSyncModeFull, With<Predicted>>,
+    ) {
+        for event in events.read() {
+            if event.input().is_some() {
+                for mut component in query.iter_mut() {
+                    component.0 += 1.0;
+                }
+            }
+        }
+    }
+
+    /// Check that a predicted entity driven by the client's inputs keeps moving on the server while the
+    /// inputs of the client are missing, so that the prediction stays correct once the inputs arrive
+    #[test]
+    fn test_input_extrapolation_predicted_entity() {
+        let mut stepper = extrapolation_stepper();
+        // release the input that was pressed while the stepper was initialized
+        stepper.client_app.insert_resource(Pressing(false));
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ServerConfig>()
+            .input
+            .max_extrapolation_ticks = 10;
+        stepper.client_app.init_resource::<PressedTicks>();
+        stepper.client_app.add_systems(
+            FixedPreUpdate,
+            count_pressed_ticks.in_set(ClientInputSystemSet::BufferInputs),
+        );
+        stepper.client_app.add_systems(FixedUpdate, client_move);
+        stepper.server_app.add_systems(FixedUpdate, server_move);
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((
+                ComponentSyncModeFull(0.0),
+                Replicate {
+                    sync: SyncTarget {
+                        prediction: NetworkTarget::All,
+                        ..default()
+                    },
+                    ..default()
+                },
+            ))
+            .id();
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        let confirmed = stepper
+            .client_app
+            .world()
+            .resource::<ClientConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .expect("entity was not replicated to client");
+        let predicted = stepper
+            .client_app
+            .world()
+            .get::<Confirmed>(confirmed)
+            .unwrap()
+            .predicted
+            .expect("entity is not predicted");
+
+        stepper.client_app.insert_resource(Pressing(true));
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+
+        // the input messages of the client are lost: the server keeps moving the entity
+        stepper.server_app.insert_resource(DropInputMessages(true));
+        let server_value = |stepper: &BevyStepper| {
+            stepper
+                .server_app
+                .world()
+                .get::<ComponentSyncModeFull>(server_entity)
+                .unwrap()
+                .0
+        };
+        let last_received_tick = last_received_tick(&stepper);
+        for _ in 0..8 {
+            let value = server_value(&stepper);
+            stepper.frame_step();
+            assert_eq!(server_value(&stepper), value + 1.0);
+        }
+        assert!(stepper.server_tick() > last_received_tick);
+
+        // the inputs arrive again, then the client releases the input
+        stepper.server_app.insert_resource(DropInputMessages(false));
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        stepper.client_app.insert_resource(Pressing(false));
+        for _ in 0..20 {
+            stepper.frame_step();
+        }
+
+        // the server did not lose any of the pressed ticks, and the prediction matches the server
+        let pressed_ticks = stepper.client_app.world().resource::<PressedTicks>().0 as f32;
+        assert_eq!(server_value(&stepper), pressed_ticks);
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<ComponentSyncModeFull>(predicted),
+            Some(&ComponentSyncModeFull(pressed_ticks))
+        );
+    }
+}
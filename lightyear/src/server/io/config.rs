@@ -1,4 +1,6 @@
 use super::*;
+#[cfg(all(feature = "steam", not(target_family = "wasm")))]
+use crate::connection::steam::{server::SocketConfig, steamworks_client::SteamworksClient};
 use crate::prelude::CompressionConfig;
 use crate::server::io::transport::{ServerTransportBuilder, ServerTransportBuilderEnum};
 use crate::transport::channels::Channels;
@@ -11,6 +13,8 @@ use crate::transport::middleware::compression::zstd::compression::ZstdCompressor
 use crate::transport::middleware::compression::zstd::decompression::ZstdDecompressor;
 use crate::transport::middleware::conditioner::LinkConditioner;
 use crate::transport::middleware::PacketReceiverWrapper;
+#[cfg(all(feature = "steam", not(target_family = "wasm")))]
+use crate::transport::steam::SteamServerSocketBuilder;
 use crate::transport::udp::UdpSocketBuilder;
 #[cfg(all(feature = "websocket", not(target_family = "wasm")))]
 use crate::transport::websocket::server::WebSocketServerSocketBuilder;
@@ -19,7 +23,11 @@ use crate::transport::webtransport::server::WebTransportServerSocketBuilder;
 use crate::transport::BoxedReceiver;
 use crate::transport::Transport;
 use bevy::prelude::TypePath;
+#[cfg(all(feature = "steam", not(target_family = "wasm")))]
+use parking_lot::RwLock;
 use std::net::IpAddr;
+#[cfg(all(feature = "steam", not(target_family = "wasm")))]
+use std::sync::Arc;
 #[cfg(all(feature = "webtransport", not(target_family = "wasm")))]
 use wtransport::Identity;
 
@@ -46,6 +54,18 @@ pub enum ServerTransport {
             Sender<Vec<u8>>,
         )>,
     },
+    /// Use a Steam socket as a transport, with netcode running on top of it.
+    ///
+    /// Steam only carries the bytes: the connection is authenticated and encrypted by netcode with
+    /// lightyear's connect tokens, instead of relying on Steam's authentication.
+    /// See [`steam`](crate::transport::steam) for how Steam peers are addressed.
+    #[cfg(all(feature = "steam", not(target_family = "wasm")))]
+    Steam {
+        /// The Steamworks client to use. If None, one will be created from the `app_id`
+        steamworks_client: Option<Arc<RwLock<SteamworksClient>>>,
+        app_id: u32,
+        socket_config: SocketConfig,
+    },
     /// Dummy transport if the connection handles its own io (for example steam sockets)
     Dummy,
 }
@@ -75,6 +95,16 @@ impl Clone for ServerTransport {
             ServerTransport::Channels { channels: __self_0 } => ServerTransport::Channels {
                 channels: Clone::clone(__self_0),
             },
+            #[cfg(all(feature = "steam", not(target_family = "wasm")))]
+            ServerTransport::Steam {
+                steamworks_client,
+                app_id,
+                socket_config,
+            } => ServerTransport::Steam {
+                steamworks_client: steamworks_client.clone(),
+                app_id: *app_id,
+                socket_config: socket_config.clone(),
+            },
             ServerTransport::Dummy => ServerTransport::Dummy,
         }
     }
@@ -103,6 +133,16 @@ impl ServerTransport {
             ServerTransport::Channels { channels } => {
                 ServerTransportBuilderEnum::Channels(Channels::new(channels))
             }
+            #[cfg(all(feature = "steam", not(target_family = "wasm")))]
+            ServerTransport::Steam {
+                steamworks_client,
+                app_id,
+                socket_config,
+            } => ServerTransportBuilderEnum::Steam(SteamServerSocketBuilder {
+                steamworks_client: steamworks_client
+                    .unwrap_or_else(|| Arc::new(RwLock::new(SteamworksClient::new(app_id)))),
+                socket_config,
+            }),
             ServerTransport::Dummy => ServerTransportBuilderEnum::Dummy(DummyIo),
         }
     }
@@ -127,6 +167,13 @@ impl SharedIoConfig<ServerTransport> {
         } else {
             Box::new(receiver)
         };
+        // the transform is applied to the final bytes sent on the wire, so it wraps the
+        // transport before the compression middleware
+        if let Some(transform) = self.transform {
+            use crate::transport::middleware::PacketSenderWrapper;
+            sender = Box::new(PacketSenderWrapper::wrap(transform.clone(), sender));
+            receiver = Box::new(PacketReceiverWrapper::wrap(transform, receiver));
+        }
         match self.compression {
             CompressionConfig::None => {}
             #[cfg(feature = "zstd")]
@@ -148,6 +195,10 @@ impl SharedIoConfig<ServerTransport> {
                 receiver = Box::new(decompressor.wrap(receiver));
             }
         }
+        // the whole middleware stack runs on the io threads
+        if let Some(threaded) = self.threaded {
+            (sender, receiver) = threaded.spawn(sender, receiver);
+        }
         Ok(BaseIo {
             local_addr,
             sender,
@@ -3,6 +3,8 @@ use crate::transport::channels::Channels;
 use crate::transport::dummy::DummyIo;
 use crate::transport::error::Result;
 use crate::transport::io::IoState;
+#[cfg(all(feature = "steam", not(target_family = "wasm")))]
+use crate::transport::steam::{SteamServerSocket, SteamServerSocketBuilder};
 use crate::transport::udp::{UdpSocket, UdpSocketBuilder};
 #[cfg(all(feature = "websocket", not(target_family = "wasm")))]
 use crate::transport::websocket::server::{WebSocketServerSocket, WebSocketServerSocketBuilder};
@@ -32,6 +34,8 @@ pub(crate) enum ServerTransportBuilderEnum {
     WebTransportServer(WebTransportServerSocketBuilder),
     #[cfg(all(feature = "websocket", not(target_family = "wasm")))]
     WebSocketServer(WebSocketServerSocketBuilder),
+    #[cfg(all(feature = "steam", not(target_family = "wasm")))]
+    Steam(SteamServerSocketBuilder),
     Channels(Channels),
     Dummy(DummyIo),
 }
@@ -44,6 +48,8 @@ pub(crate) enum ServerTransportEnum {
     WebTransportServer(WebTransportServerSocket),
     #[cfg(all(feature = "websocket", not(target_family = "wasm")))]
     WebSocketServer(WebSocketServerSocket),
+    #[cfg(all(feature = "steam", not(target_family = "wasm")))]
+    Steam(SteamServerSocket),
     Channels(Channels),
     Dummy(DummyIo),
 }
@@ -0,0 +1,284 @@
+//! Rewind the replicated entities to what a client was seeing when it sent its inputs
+//!
+//! The client displays the remote entities in the past (with an interpolation delay), and its inputs
+//! are applied on the server with an input delay. So when the server applies at tick `T` an input of
+//! a client (for example to check if a shot hit its target), the client was seeing the other
+//! entities as they were on the server at tick `T - input_delay - interpolation_delay`.
+//!
+//! The client sends these delays to the server in a [`ViewDelay`] message.
+//! The [`LagCompensationPlugin<C>`] keeps a bounded history of the component `C` for every replicated
+//! entity, and the [`LagCompensation<C>`] system param returns the value of the component that a client
+//! was seeing when it sent the inputs applied at a given tick.
+//!
+//! [`ViewDelay`]: crate::shared::input::ViewDelay
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+use crate::prelude::{ClientId, ComponentRegistry, TickManager};
+use crate::server::connection::ConnectionManager;
+use crate::shared::replication::components::ReplicationTarget;
+use crate::shared::tick_manager::Tick;
+
+/// Default number of ticks of history kept for each entity
+pub const DEFAULT_HISTORY_TICKS: u16 = 64;
+
+/// Plugin that records the history of the component `C` on the server for every replicated entity,
+/// so that it can be rewound with [`LagCompensation<C>`]
+pub struct LagCompensationPlugin<C> {
+    /// Number of ticks of history kept for each entity.
+    ///
+    /// It must be larger than the `input_delay + interpolation_delay` of the clients,
+    /// otherwise the oldest recorded value is returned.
+    pub history_ticks: u16,
+    _marker: PhantomData<C>,
+}
+
+impl<C> LagCompensationPlugin<C> {
+    pub fn new(history_ticks: u16) -> Self {
+        Self {
+            history_ticks,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<C> Default for LagCompensationPlugin<C> {
+    fn default() -> Self {
+        Self::new(DEFAULT_HISTORY_TICKS)
+    }
+}
+
+impl<C: Component + Clone> Plugin for LagCompensationPlugin<C> {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(LagCompensationConfig::<C> {
+            history_ticks: self.history_ticks,
+            _marker: PhantomData,
+        });
+        app.add_systems(FixedPostUpdate, record_history::<C>);
+    }
+}
+
+#[derive(Resource)]
+struct LagCompensationConfig<C> {
+    history_ticks: u16,
+    _marker: PhantomData<C>,
+}
+
+/// Values of the component `C` on the server during the last ticks
+#[derive(Component, Debug)]
+pub struct LagCompensationHistory<C> {
+    buffer: VecDeque<(Tick, C)>,
+}
+
+impl<C> Default for LagCompensationHistory<C> {
+    fn default() -> Self {
+        Self {
+            buffer: VecDeque::new(),
+        }
+    }
+}
+
+impl<C> LagCompensationHistory<C> {
+    /// Record the value of the component at `tick`, and drop the values older than `history_ticks`
+    fn add(&mut self, tick: Tick, value: C, history_ticks: u16) {
+        self.buffer.push_back((tick, value));
+        while self
+            .buffer
+            .front()
+            .is_some_and(|(oldest, _)| tick - *oldest > history_ticks as i16)
+        {
+            self.buffer.pop_front();
+        }
+    }
+
+    /// Return the latest value recorded at or before `tick`, and the next recorded value.
+    ///
+    /// If `tick` is older than the history, the oldest value is returned.
+    fn samples(&self, tick: Tick) -> Option<(&(Tick, C), Option<&(Tick, C)>)> {
+        let index = self
+            .buffer
+            .iter()
+            .rposition(|(recorded, _)| *recorded <= tick)
+            .unwrap_or(0);
+        Some((self.buffer.get(index)?, self.buffer.get(index + 1)))
+    }
+}
+
+/// Record the value of the component at the end of each tick, which is the value that gets replicated
+fn record_history<C: Component + Clone>(
+    mut commands: Commands,
+    config: Res<LagCompensationConfig<C>>,
+    tick_manager: Res<TickManager>,
+    mut query: Query<(Entity, &C, Option<&mut LagCompensationHistory<C>>), With<ReplicationTarget>>,
+) {
+    let tick = tick_manager.tick();
+    for (entity, component, history) in query.iter_mut() {
+        match history {
+            Some(mut history) => history.add(tick, component.clone(), config.history_ticks),
+            None => {
+                let mut history = LagCompensationHistory::default();
+                history.add(tick, component.clone(), config.history_ticks);
+                commands.entity(entity).insert(history);
+            }
+        }
+    }
+}
+
+/// [`SystemParam`] to get the value of the component `C` that a client was seeing when it sent
+/// the inputs that the server applies at a given tick.
+///
+/// Requires the [`LagCompensationPlugin<C>`].
+#[derive(SystemParam)]
+pub struct LagCompensation<'w, 's, C: Component> {
+    connection_manager: Res<'w, ConnectionManager>,
+    component_registry: Res<'w, ComponentRegistry>,
+    query: Query<'w, 's, &'static LagCompensationHistory<C>>,
+}
+
+impl<'w, 's, C: Component + Clone> LagCompensation<'w, 's, C> {
+    /// Return the value of the component `C` of `entity` that `client_id` was seeing when it sent the inputs
+    /// that the server applies at `tick`.
+    ///
+    /// The value is interpolated between the two recorded ticks if the component has an interpolation function.
+    /// Returns `None` if the client doesn't exist or if no history was recorded for the entity.
+    pub fn rewind(&self, client_id: ClientId, entity: Entity, tick: Tick) -> Option<C> {
+        let (view_tick, overstep) = self.connection_manager.client_view_tick(client_id, tick)?;
+        let history = self.query.get(entity).ok()?;
+        let ((start_tick, start), end) = history.samples(view_tick)?;
+        match end {
+            Some((end_tick, end))
+                if *start_tick <= view_tick && self.component_registry.has_interpolation::<C>() =>
+            {
+                let t = ((view_tick - *start_tick) as f32 + overstep)
+                    / (*end_tick - *start_tick) as f32;
+                Some(
+                    self.component_registry
+                        .interpolate(start, end, t.clamp(0.0, 1.0)),
+                )
+            }
+            _ => Some(start.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::utils::Duration;
+
+    use super::*;
+    use crate::client::interpolation::plugin::{InterpolationConfig, InterpolationDelay};
+    use crate::prelude::client::{ClientConfig, Confirmed};
+    use crate::prelude::server::{Replicate, SyncTarget};
+    use crate::prelude::{client, NetworkTarget, SharedConfig, TickConfig};
+    use crate::tests::protocol::ComponentSyncModeFull;
+    use crate::tests::stepper::{BevyStepper, TEST_CLIENT_ID};
+
+    fn increment(mut query: Query<&mut ComponentSyncModeFull, With<ReplicationTarget>>) {
+        for mut component in query.iter_mut() {
+            component.0 += 1.0;
+        }
+    }
+
+    /// Check that the rewound value on the server matches the value displayed by the client
+    /// for an interpolated entity
+    #[test]
+    fn test_rewind_to_client_view() {
+        let frame_duration = Duration::from_millis(10);
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..Default::default()
+        };
+        let client_config = ClientConfig {
+            interpolation: InterpolationConfig::default().with_delay(
+                InterpolationDelay::default()
+                    .with_min_delay(Duration::from_millis(50))
+                    .with_send_interval_ratio(0.0),
+            ),
+            ..default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, client_config, frame_duration);
+        stepper
+            .server_app
+            .add_plugins(LagCompensationPlugin::<ComponentSyncModeFull>::default())
+            .add_systems(FixedUpdate, increment);
+        stepper.init();
+
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((
+                ComponentSyncModeFull(0.0),
+                Replicate {
+                    sync: SyncTarget {
+                        interpolation: NetworkTarget::All,
+                        ..default()
+                    },
+                    ..default()
+                },
+            ))
+            .id();
+        for _ in 0..50 {
+            stepper.frame_step();
+        }
+        let confirmed_entity = stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .expect("entity was not replicated to client");
+        let interpolated_entity = stepper
+            .client_app
+            .world()
+            .get::<Confirmed>(confirmed_entity)
+            .unwrap()
+            .interpolated
+            .expect("entity is not interpolated");
+
+        // value displayed by the client at its current tick
+        let client_tick = stepper.client_tick();
+        let displayed = stepper
+            .client_app
+            .world()
+            .get::<ComponentSyncModeFull>(interpolated_entity)
+            .unwrap()
+            .0;
+        // the client is displaying the entity in the past
+        let server_value = stepper
+            .server_app
+            .world()
+            .get::<ComponentSyncModeFull>(server_entity)
+            .unwrap()
+            .0;
+        assert!(server_value - displayed > 4.0);
+
+        // the server reaches the client tick later
+        while stepper.server_tick() < client_tick {
+            stepper.frame_step();
+        }
+        let rewound = stepper
+            .server_app
+            .world_mut()
+            .run_system_once(
+                move |lag_compensation: LagCompensation<ComponentSyncModeFull>| {
+                    lag_compensation.rewind(
+                        ClientId::Netcode(TEST_CLIENT_ID),
+                        server_entity,
+                        client_tick,
+                    )
+                },
+            )
+            .expect("could not rewind the entity")
+            .0;
+        assert!(
+            (rewound - displayed).abs() < 0.2,
+            "rewound: {rewound}, displayed: {displayed}"
+        );
+    }
+}
@@ -1,21 +1,33 @@
 use std::ops::DerefMut;
 
-use crate::prelude::{server::is_started, Message};
+use crate::prelude::{server::is_started, ClientId, Message};
 use crate::protocol::message::{MessageKind, MessageRegistry};
 use crate::serialize::reader::Reader;
-use crate::server::connection::ConnectionManager;
+use crate::server::connection::{ConnectionManager, MessageRateLimiter};
 use crate::server::events::MessageEvent;
 use crate::shared::replication::network_target::NetworkTarget;
 use crate::shared::sets::{InternalMainSet, ServerMarker};
 use bevy::app::{App, PreUpdate};
-use bevy::prelude::{EventWriter, IntoSystemConfigs, Res, ResMut};
-use tracing::{error, trace};
+use bevy::prelude::{Event, EventWriter, IntoSystemConfigs, Res, ResMut};
+use tracing::{debug, error, trace};
+
+/// Event emitted when the server drops a message from a client because the client exceeded the rate limit
+/// of the message (see [`AppMessageExt::register_message_rate_limit`](crate::prelude::AppMessageExt::register_message_rate_limit))
+///
+/// This usually means that the client is misbehaving; you can use this event to kick it.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct MessageRateLimitedEvent {
+    pub client_id: ClientId,
+    /// Type name of the dropped message
+    pub message: &'static str,
+}
 
 /// Read the messages received from the clients and emit the MessageEvent event
 fn read_message<M: Message>(
     message_registry: Res<MessageRegistry>,
     mut connection_manager: ResMut<ConnectionManager>,
     mut event: EventWriter<MessageEvent<M>>,
+    mut rate_limited_events: EventWriter<MessageRateLimitedEvent>,
 ) {
     let kind = MessageKind::of::<M>();
     let Some(net) = message_registry.kind_map.net_id(&kind).copied() else {
@@ -25,11 +37,37 @@ fn read_message<M: Message>(
         );
         return;
     };
+    let rate_limit = message_registry.rate_limit(&kind);
     // re-borrow to allow split borrows
     let connection_manager = connection_manager.deref_mut();
     for (client_id, connection) in connection_manager.connections.iter_mut() {
         if let Some(message_list) = connection.received_messages.remove(&net) {
             for (message_bytes, target, channel_kind) in message_list {
+                if let Some(quota) = rate_limit {
+                    let rate_limiter =
+                        connection
+                            .message_rate_limiters
+                            .entry(net)
+                            .or_insert_with(|| {
+                                MessageRateLimiter::direct_with_clock(
+                                    quota,
+                                    &connection_manager.rate_limit_clock,
+                                )
+                            });
+                    if rate_limiter.check().is_err() {
+                        // the drops are reported with the MessageRateLimitedEvent
+                        debug!(
+                            ?client_id,
+                            "Dropping message {} that exceeds the rate limit",
+                            std::any::type_name::<M>()
+                        );
+                        rate_limited_events.send(MessageRateLimitedEvent {
+                            client_id: *client_id,
+                            message: std::any::type_name::<M>(),
+                        });
+                        continue;
+                    }
+                }
                 let mut reader = Reader::from(message_bytes);
                 match message_registry.deserialize::<M>(
                     &mut reader,
@@ -66,6 +104,7 @@ fn read_message<M: Message>(
 /// Register a message that can be sent from client to server
 pub(crate) fn add_server_receive_message_from_client<M: Message>(app: &mut App) {
     app.add_event::<MessageEvent<M>>();
+    app.add_event::<MessageRateLimitedEvent>();
     app.add_systems(
         PreUpdate,
         read_message::<M>
@@ -180,11 +219,24 @@ pub(crate) fn add_server_receive_message_from_client<M: Message>(app: &mut App)
 
 #[cfg(test)]
 mod tests {
-    use crate::prelude::NetworkTarget;
+    use std::io::Write;
+
+    use super::MessageRateLimitedEvent;
+    use crate::prelude::server::ConnectionManager;
+    use crate::prelude::{AppMessageExt, ClientId, NetworkTarget, UnknownMessage};
+    use crate::protocol::channel::ChannelKind;
+    use crate::serialize::writer::Writer;
+    use crate::serialize::ToBytes;
+    use crate::server::connection::RateLimitClock;
     use crate::tests::host_server_stepper::HostServerStepper;
+    use crate::tests::multi_stepper::{MultiBevyStepper, TEST_CLIENT_ID_1, TEST_CLIENT_ID_2};
     use crate::tests::protocol::{Channel1, StringMessage};
-    use bevy::app::Update;
-    use bevy::prelude::{EventReader, ResMut, Resource};
+    use crate::tests::stepper::{BevyStepper, TEST_CLIENT_ID};
+    use bevy::app::{App, Update};
+    use bevy::prelude::{EventReader, ResMut, Resource, World};
+    use bevy::utils::Duration;
+    use governor::clock::FakeRelativeClock;
+    use nonzero_ext::nonzero;
 
     #[derive(Resource, Default)]
     struct Counter(usize);
@@ -234,4 +286,190 @@ mod tests {
         // verify that the other client received the message
         assert_eq!(stepper.client_app.world().resource::<Counter>().0, 1);
     }
+    /// A message sent for a future tick is only processed once the client reaches that tick
+    #[test]
+    fn server_send_message_at_tick() {
+        let mut stepper = BevyStepper::default();
+        stepper.client_app.init_resource::<Counter>();
+        stepper.client_app.add_systems(Update, count_messages);
+
+        let tick = stepper.client_tick() + 10;
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<crate::prelude::server::ConnectionManager>()
+            .send_message_at_tick::<Channel1, StringMessage>(
+                &mut StringMessage("a".to_string()),
+                NetworkTarget::All,
+                tick,
+            )
+            .unwrap();
+        for _ in 0..20 {
+            stepper.frame_step();
+            // the message is read at the start of the frame, before the tick is incremented
+            let expected = if stepper.client_tick() > tick { 1 } else { 0 };
+            assert_eq!(
+                stepper.client_app.world().resource::<Counter>().0,
+                expected,
+                "client tick: {:?}",
+                stepper.client_tick()
+            );
+        }
+    }
+
+    #[derive(Resource, Default)]
+    struct ReceivedMessages {
+        messages: Vec<StringMessage>,
+        unknown: Vec<UnknownMessage>,
+    }
+
+    fn receive_server_messages(
+        mut received: ResMut<ReceivedMessages>,
+        mut events: EventReader<crate::server::events::MessageEvent<StringMessage>>,
+    ) {
+        received
+            .messages
+            .extend(events.read().map(|event| event.message().clone()));
+    }
+
+    fn receive_unknown_message(world: &mut World, message: UnknownMessage) {
+        world
+            .resource_mut::<ReceivedMessages>()
+            .unknown
+            .push(message);
+    }
+
+    /// A message with a net id that is not registered on the server is skipped and passed to the
+    /// unknown message handler, without affecting the other messages of the packet
+    #[test]
+    fn server_receive_unknown_message() {
+        let mut stepper = BevyStepper::default();
+        stepper.server_app.init_resource::<ReceivedMessages>();
+        stepper
+            .server_app
+            .add_systems(Update, receive_server_messages);
+        stepper
+            .server_app
+            .set_unknown_message_handler(receive_unknown_message);
+
+        // write a message with an unregistered net id, as a client with a more recent protocol would
+        let mut writer = Writer::default();
+        1000u16.to_bytes(&mut writer).unwrap();
+        writer.write_all(&[1, 2, 3]).unwrap();
+        let unknown_message = writer.split();
+        NetworkTarget::None.to_bytes(&mut writer).unwrap();
+        writer.write_all(&unknown_message).unwrap();
+        let mut connection_manager = stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<crate::prelude::client::ConnectionManager>();
+        connection_manager
+            .messages_to_send
+            .push((writer.to_bytes(), ChannelKind::of::<Channel1>()));
+        connection_manager
+            .send_message::<Channel1, StringMessage>(&mut StringMessage("a".to_string()))
+            .unwrap();
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+
+        let received = stepper.server_app.world().resource::<ReceivedMessages>();
+        assert_eq!(received.messages, vec![StringMessage("a".to_string())]);
+        assert_eq!(
+            received.unknown,
+            vec![UnknownMessage {
+                sender: Some(ClientId::Netcode(TEST_CLIENT_ID)),
+                net_id: 1000,
+                bytes: unknown_message,
+            }]
+        );
+    }
+
+    #[derive(Resource, Default)]
+    struct RateLimitedMessages {
+        received: Vec<ClientId>,
+        dropped: Vec<ClientId>,
+    }
+
+    fn receive_rate_limited_messages(
+        mut messages: ResMut<RateLimitedMessages>,
+        mut events: EventReader<crate::server::events::MessageEvent<StringMessage>>,
+        mut rate_limited_events: EventReader<MessageRateLimitedEvent>,
+    ) {
+        messages
+            .received
+            .extend(events.read().map(|event| *event.context()));
+        messages
+            .dropped
+            .extend(rate_limited_events.read().map(|event| event.client_id));
+    }
+
+    /// Check that the messages that exceed the rate limit of a client are dropped,
+    /// without affecting the other clients, and that the quota is replenished over time
+    #[test]
+    fn server_message_rate_limit() {
+        let mut stepper = MultiBevyStepper::default();
+        stepper
+            .server_app
+            .register_message_rate_limit::<StringMessage>(nonzero!(3u32));
+        stepper.server_app.init_resource::<RateLimitedMessages>();
+        stepper
+            .server_app
+            .add_systems(Update, receive_rate_limited_messages);
+        // use a manual clock so that the quota is only replenished when the clock advances
+        let clock = FakeRelativeClock::default();
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ConnectionManager>()
+            .rate_limit_clock = RateLimitClock::Manual(clock.clone());
+
+        let send_messages = |client_app: &mut App, num_messages: usize| {
+            let mut connection_manager = client_app
+                .world_mut()
+                .resource_mut::<crate::prelude::client::ConnectionManager>(
+            );
+            for _ in 0..num_messages {
+                connection_manager
+                    .send_message::<Channel1, StringMessage>(&mut StringMessage("a".to_string()))
+                    .unwrap();
+            }
+        };
+        // client 1 spams the message, client 2 stays below the limit
+        send_messages(&mut stepper.client_app_1, 10);
+        send_messages(&mut stepper.client_app_2, 2);
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+
+        let client_1 = ClientId::Netcode(TEST_CLIENT_ID_1);
+        let client_2 = ClientId::Netcode(TEST_CLIENT_ID_2);
+        let count = |stepper: &MultiBevyStepper| {
+            let messages = stepper.server_app.world().resource::<RateLimitedMessages>();
+            let count = |clients: &Vec<ClientId>, client_id| {
+                clients.iter().filter(|id| **id == client_id).count()
+            };
+            [
+                count(&messages.received, client_1),
+                count(&messages.dropped, client_1),
+                count(&messages.received, client_2),
+                count(&messages.dropped, client_2),
+            ]
+        };
+        assert_eq!(count(&stepper), [3, 7, 2, 0]);
+
+        // the quota is not replenished while the clock doesn't advance
+        send_messages(&mut stepper.client_app_1, 3);
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        assert_eq!(count(&stepper), [3, 10, 2, 0]);
+
+        clock.advance(Duration::from_secs(1));
+        send_messages(&mut stepper.client_app_1, 3);
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        assert_eq!(count(&stepper), [6, 10, 2, 0]);
+    }
 }
@@ -7,6 +7,8 @@ pub mod config;
 
 pub mod connection;
 
+pub mod diagnostics;
+
 pub mod error;
 
 pub mod events;
@@ -15,6 +17,8 @@ pub mod input;
 
 pub(crate) mod io;
 
+pub mod lag_compensation;
+
 pub mod plugin;
 
 pub(crate) mod message;
@@ -25,3 +29,4 @@ pub(crate) mod networking;
 pub mod relevance;
 pub mod replication;
 pub mod run_conditions;
+pub mod snapshot;
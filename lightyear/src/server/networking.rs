@@ -1,17 +1,24 @@
 //! Defines the server bevy systems and run conditions
+use crate::channel::builder::NotificationChannel;
 use crate::connection::server::{IoConfig, NetServer, ServerConnection, ServerConnections};
 use crate::prelude::{
-    is_host_server, server::is_started, ChannelRegistry, MainSet, MessageRegistry, TickManager,
-    TimeManager,
+    is_host_server, server::is_started, ChannelRegistry, ClientId, MainSet, MessageRegistry,
+    TickManager, TimeManager,
 };
 use crate::protocol::component::ComponentRegistry;
+use crate::protocol::message;
 use crate::serialize::reader::Reader;
 use crate::server::clients::ControlledEntities;
 use crate::server::config::ServerConfig;
 use crate::server::connection::ConnectionManager;
 use crate::server::error::ServerError;
+use crate::server::events::MessageEvent;
 use crate::server::io::ServerIoEvent;
+use crate::shared::input::{InputDelayCommand, ViewDelay};
+use crate::shared::notification::{ServerNotification, Severity};
+use crate::shared::replication::network_target::NetworkTarget;
 use crate::shared::sets::{InternalMainSet, ServerMarker};
+use crate::shared::tick_manager::ServerWideTick;
 use async_channel::TryRecvError;
 use bevy::ecs::system::{RunSystemOnce, SystemChangeTick};
 use bevy::prelude::*;
@@ -51,10 +58,16 @@ impl Plugin for ServerNetworkingPlugin {
             // SYSTEMS //
             .add_systems(
                 PreUpdate,
-                (receive_packets, receive)
+                (receive_packets, receive, handle_unknown_messages)
                     .chain()
                     .in_set(InternalMainSet::<ServerMarker>::Receive),
             )
+            .add_systems(
+                PreUpdate,
+                receive_view_delays
+                    .after(InternalMainSet::<ServerMarker>::EmitEvents)
+                    .run_if(is_started),
+            )
             .add_systems(
                 PostUpdate,
                 (send, send_host_server.run_if(is_host_server))
@@ -136,6 +149,14 @@ pub(crate) fn receive_packets(
                 .spawn((ControlledEntities::default(), Name::new("Client")))
                 .id();
             connection_manager.add(client_id, client_entity);
+            // the client needs the upper bits of our tick to compute the WideTick, which are otherwise
+            // only sent in the pongs
+            let _ = connection_manager
+                .send_message::<NotificationChannel, _>(
+                    client_id,
+                    &mut ServerWideTick(tick_manager.wide_tick()),
+                )
+                .inspect_err(|e| error!("Error sending the server wide tick: {:?}", e));
         }
         // handle disconnections
 
@@ -163,6 +184,23 @@ pub(crate) fn receive_packets(
         }
     }
 
+    // if pings are disabled, use the rtt measured by the transport
+    for (client_id, connection) in connection_manager.connections.iter_mut() {
+        if connection.ping_manager.is_enabled() {
+            continue;
+        }
+        let Some(rtt) = netservers
+            .client_server_map
+            .get(client_id)
+            .and_then(|idx| netservers.servers.get(*idx))
+            .and_then(|netserver| netserver.rtt(*client_id))
+        else {
+            continue;
+        };
+        let jitter = connection.ping_manager.jitter();
+        connection.ping_manager.set_external_stats(rtt, jitter);
+    }
+
     // update connections
     connection_manager.update(
         system_change_tick.this_run(),
@@ -236,6 +274,17 @@ pub(crate) fn receive(
         });
 }
 
+/// Pass the received messages with an unknown net id to the [`UnknownMessageFn`](crate::prelude::UnknownMessageFn)
+pub(crate) fn handle_unknown_messages(world: &mut World) {
+    let messages: Vec<_> = world
+        .resource_mut::<ConnectionManager>()
+        .connections
+        .values_mut()
+        .flat_map(|connection| std::mem::take(&mut connection.unknown_messages))
+        .collect();
+    message::handle_unknown_messages(world, messages);
+}
+
 // or do additional send stuff here
 pub(crate) fn send(
     change_tick: SystemChangeTick,
@@ -262,8 +311,15 @@ pub(crate) fn send(
                 .servers
                 .get_mut(netserver_idx)
                 .ok_or(ServerError::ServerConnectionNotFound)?;
-            for packet_byte in connection.send_packets(&time_manager, &tick_manager)? {
-                netserver.send(packet_byte.as_slice(), *client_id)?;
+            let split_reliable = netserver.supports_reliable_send();
+            for (packet_byte, reliable) in
+                connection.send_packets(&time_manager, &tick_manager, split_reliable)?
+            {
+                if reliable {
+                    netserver.send_reliable(packet_byte.as_slice(), *client_id)?;
+                } else {
+                    netserver.send(packet_byte.as_slice(), *client_id)?;
+                }
             }
             Ok(())
         })
@@ -277,6 +333,7 @@ pub(crate) fn send(
 pub(crate) fn send_host_server(
     mut connection_manager: ResMut<ConnectionManager>,
     mut client_manager: ResMut<crate::client::connection::ConnectionManager>,
+    tick_manager: Res<TickManager>,
 ) {
     let _ = connection_manager
         .connections
@@ -289,6 +346,10 @@ pub(crate) fn send_host_server(
                 .try_for_each(|message| client_manager.receive_message(Reader::from(message)))
         })
         .inspect_err(|e| error!("Error sending messages to local client: {:?}", e));
+    // the local client shares the server's tick
+    let _ = client_manager
+        .release_scheduled_messages(tick_manager.tick())
+        .inspect_err(|e| error!("Error reading scheduled messages on local client: {:?}", e));
 }
 
 /// Bevy [`State`] representing the networking state of the server.
@@ -303,6 +364,20 @@ pub enum NetworkingState {
     Started,
 }
 
+/// Store the [`ViewDelay`] sent by each client, to be used for lag compensation
+fn receive_view_delays(
+    mut messages: ResMut<Events<MessageEvent<ViewDelay>>>,
+    mut connection_manager: ResMut<ConnectionManager>,
+) {
+    for message in messages.drain() {
+        let client_id = *message.context();
+        trace!(?client_id, view_delay = ?message.message, "Received view delay");
+        if let Ok(connection) = connection_manager.connection_mut(client_id) {
+            connection.view_delay = Some(message.message);
+        }
+    }
+}
+
 /// This runs only when we restart the server.
 ///
 /// We rebuild the [`ServerConnections`] by using the latest [`ServerConfig`].
@@ -320,6 +395,7 @@ fn rebuild_server_connections(world: &mut World) {
         server_config.replication,
         server_config.packet,
         server_config.ping,
+        server_config.shared.mtu,
     );
     // // make sure the previous replication metadata is ported over to the new manager
     // if let Some(mut previous_manager) = world.get_resource_mut::<ConnectionManager>() {
@@ -362,6 +438,18 @@ pub trait ServerCommands {
     fn start_server(&mut self);
 
     fn stop_server(&mut self);
+
+    /// Reliably send a [`ServerNotification`] to all connected clients.
+    ///
+    /// The clients will receive it as a [`ServerNotification`] event.
+    fn broadcast_notification(&mut self, text: String, severity: Severity);
+
+    /// Reliably set the number of ticks of input delay that a client must use, instead of the input delay
+    /// that the client computes from its [`PredictionConfig`](crate::client::prediction::plugin::PredictionConfig).
+    ///
+    /// This can be used to equalize the effective input delay across clients in competitive games.
+    /// Use `None` to let the client compute its own input delay again.
+    fn set_input_delay(&mut self, client_id: ClientId, input_delay_ticks: Option<u16>);
 }
 
 impl ServerCommands for Commands<'_, '_> {
@@ -372,4 +460,28 @@ impl ServerCommands for Commands<'_, '_> {
     fn stop_server(&mut self) {
         self.insert_resource(NextState::Pending(NetworkingState::Stopped));
     }
+
+    fn broadcast_notification(&mut self, text: String, severity: Severity) {
+        self.add(move |world: &mut World| {
+            let _ = world
+                .resource_mut::<ConnectionManager>()
+                .send_message_to_target::<NotificationChannel, _>(
+                    &mut ServerNotification { text, severity },
+                    NetworkTarget::All,
+                )
+                .inspect_err(|e| error!("Error broadcasting server notification: {:?}", e));
+        });
+    }
+
+    fn set_input_delay(&mut self, client_id: ClientId, input_delay_ticks: Option<u16>) {
+        self.add(move |world: &mut World| {
+            let _ = world
+                .resource_mut::<ConnectionManager>()
+                .send_message::<NotificationChannel, _>(
+                    client_id,
+                    &mut InputDelayCommand { input_delay_ticks },
+                )
+                .inspect_err(|e| error!("Error sending input delay command: {:?}", e));
+        });
+    }
 }
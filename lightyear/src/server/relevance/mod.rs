@@ -2,3 +2,4 @@ pub mod immediate;
 
 pub mod error;
 pub mod room;
+pub mod spatial;
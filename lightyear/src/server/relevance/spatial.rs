@@ -0,0 +1,270 @@
+/*! Spatial network relevance module, where entities are relevant to a client if they are close to the entities it controls
+
+# Spatial Interest
+
+For open-world games, it is common to only replicate to a client the entities that are close to it.
+The [`SpatialInterestPlugin`] computes the network relevance of every entity that has the [`SpatialInterest`]
+component: the entity is relevant to a client if it is within `radius` units of any entity controlled
+by that client (see [`ControlledBy`](crate::prelude::server::ControlledBy)).
+
+The position of an entity is extracted from a component `C` via a user-provided function, so that
+you can use `Transform` or your own position component.
+
+The relevance is recomputed every send_interval. Entities that enter the radius of a client will be spawned
+on that client, and entities that leave it will be despawned.
+
+```rust
+use bevy::prelude::*;
+use lightyear::prelude::*;
+use lightyear::prelude::server::*;
+
+fn setup(app: &mut App) {
+    app.add_plugins(SpatialInterestPlugin::<Transform>::new(100.0, |t| t.translation));
+}
+
+fn spawn_entity(mut commands: Commands) {
+    commands.spawn((
+        Transform::default(),
+        SpatialInterest,
+        Replicate {
+            // spatial interest relies on interest management
+            relevance_mode: NetworkRelevanceMode::InterestManagement,
+            ..default()
+        },
+    ));
+}
+```
+
+## Implementation
+
+Under the hood, the [`SpatialInterestPlugin`] uses the same functions as in the immediate-mode [`RelevanceManager`].
+The relevance of the entities with [`SpatialInterest`] is fully managed by the plugin, so you should not
+also update it manually or via rooms.
+*/
+use bevy::prelude::*;
+
+use crate::prelude::server::is_started;
+use crate::server::relevance::immediate::{NetworkRelevanceSet, RelevanceManager};
+use crate::shared::sets::{InternalReplicationSet, ServerMarker};
+
+/// Marker component for entities whose network relevance is computed from their distance
+/// to the entities controlled by each client.
+///
+/// The entity must also use [`NetworkRelevanceMode::InterestManagement`](crate::prelude::NetworkRelevanceMode::InterestManagement).
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct SpatialInterest;
+
+/// Resource that holds the configuration of the [`SpatialInterestPlugin`]
+///
+/// It can be modified at runtime to update the interest radius.
+#[derive(Resource)]
+pub struct SpatialInterestConfig<C> {
+    /// An entity is relevant to a client if it is within this distance of any entity controlled by the client
+    pub radius: f32,
+    /// Function used to extract the position of an entity from the component `C`
+    pub position: fn(&C) -> Vec3,
+}
+
+impl<C> Clone for SpatialInterestConfig<C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C> Copy for SpatialInterestConfig<C> {}
+
+/// Plugin that computes the network relevance of [`SpatialInterest`] entities, using the component `C`
+/// to get the position of entities
+pub struct SpatialInterestPlugin<C> {
+    config: SpatialInterestConfig<C>,
+}
+
+impl<C> SpatialInterestPlugin<C> {
+    /// Create a new plugin with the given interest radius and position-extraction function
+    pub fn new(radius: f32, position: fn(&C) -> Vec3) -> Self {
+        Self {
+            config: SpatialInterestConfig { radius, position },
+        }
+    }
+}
+
+/// System sets related to the spatial interest
+#[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone, Copy)]
+pub enum SpatialInterestSet {
+    /// Compute the relevance of entities based on their position
+    UpdateRelevance,
+}
+
+impl<C: Component> Plugin for SpatialInterestPlugin<C> {
+    fn build(&self, app: &mut App) {
+        // REFLECTION
+        app.register_type::<SpatialInterest>();
+        // RESOURCES
+        app.insert_resource(self.config);
+        // SETS
+        app.configure_sets(
+            PostUpdate,
+            (
+                // the spatial relevance must be computed before the relevance events are processed
+                SpatialInterestSet::UpdateRelevance
+                    .before(NetworkRelevanceSet::UpdateRelevance)
+                    .run_if(is_started),
+                // the spatial systems can run every send_interval
+                SpatialInterestSet::UpdateRelevance
+                    .in_set(InternalReplicationSet::<ServerMarker>::SendMessages),
+            ),
+        );
+        // SYSTEMS
+        app.add_systems(
+            PostUpdate,
+            systems::update_spatial_relevance::<C>.in_set(SpatialInterestSet::UpdateRelevance),
+        );
+    }
+}
+
+pub(super) mod systems {
+    use super::*;
+    use crate::server::clients::ControlledEntities;
+    use crate::server::connection::ConnectionManager;
+    use crate::server::relevance::immediate::CachedNetworkRelevance;
+
+    /// For each client, update the relevance of every [`SpatialInterest`] entity depending on whether
+    /// it is within the interest radius of one of the client's controlled entities.
+    ///
+    /// Relevance events are only emitted when the relevance of the entity changes for the client.
+    pub(super) fn update_spatial_relevance<C: Component>(
+        config: Res<SpatialInterestConfig<C>>,
+        connection_manager: Res<ConnectionManager>,
+        clients: Query<&ControlledEntities>,
+        positions: Query<&C>,
+        entities: Query<(Entity, &C, Option<&CachedNetworkRelevance>), With<SpatialInterest>>,
+        mut relevance_manager: ResMut<RelevanceManager>,
+    ) {
+        let radius_squared = config.radius * config.radius;
+        for client_id in connection_manager.connected_clients() {
+            let Some(controlled_entities) = connection_manager
+                .client_entity(client_id)
+                .ok()
+                .and_then(|client_entity| clients.get(client_entity).ok())
+            else {
+                continue;
+            };
+            let viewers: Vec<Vec3> = controlled_entities
+                .keys()
+                .filter_map(|entity| positions.get(*entity).ok())
+                .map(config.position)
+                .collect();
+            for (entity, component, cached_relevance) in entities.iter() {
+                let position = (config.position)(component);
+                let in_range = viewers
+                    .iter()
+                    .any(|viewer| viewer.distance_squared(position) <= radius_squared);
+                let relevant = cached_relevance
+                    .is_some_and(|cached| cached.clients_cache.contains_key(&client_id));
+                if in_range && !relevant {
+                    relevance_manager.gain_relevance(client_id, entity);
+                } else if !in_range && relevant {
+                    relevance_manager.lose_relevance(client_id, entity);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::server::{ControlledBy, Replicate};
+    use crate::prelude::{client, ClientId, NetworkRelevanceMode, NetworkTarget};
+    use crate::tests::protocol::ComponentSyncModeFull;
+    use crate::tests::stepper::{BevyStepper, TEST_CLIENT_ID};
+
+    /// An entity is spawned on the client when it enters the interest radius of the client's
+    /// controlled entity, and despawned when it leaves it
+    #[test]
+    fn test_spatial_interest_enter_leave_radius() {
+        let mut stepper = BevyStepper::default();
+        // the stepper is already finished, so we build the plugin directly
+        SpatialInterestPlugin::<ComponentSyncModeFull>::new(10.0, |c| Vec3::new(c.0, 0.0, 0.0))
+            .build(&mut stepper.server_app);
+
+        // the entity controlled by the client, around which the client has interest
+        stepper.server_app.world_mut().spawn((
+            ComponentSyncModeFull(0.0),
+            Replicate {
+                controlled_by: ControlledBy {
+                    target: NetworkTarget::Single(ClientId::Netcode(TEST_CLIENT_ID)),
+                    ..default()
+                },
+                ..default()
+            },
+        ));
+        // an entity outside of the interest radius
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((
+                ComponentSyncModeFull(100.0),
+                SpatialInterest,
+                Replicate {
+                    relevance_mode: NetworkRelevanceMode::InterestManagement,
+                    ..default()
+                },
+            ))
+            .id();
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        assert!(stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .is_none());
+
+        // the entity enters the interest radius: it gets spawned on the client
+        stepper
+            .server_app
+            .world_mut()
+            .get_mut::<ComponentSyncModeFull>(server_entity)
+            .unwrap()
+            .0 = 5.0;
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        let client_entity = stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .expect("entity was not spawned on the client");
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<ComponentSyncModeFull>(client_entity),
+            Some(&ComponentSyncModeFull(5.0))
+        );
+
+        // the entity leaves the interest radius: it gets despawned on the client
+        stepper
+            .server_app
+            .world_mut()
+            .get_mut::<ComponentSyncModeFull>(server_entity)
+            .unwrap()
+            .0 = 50.0;
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        assert!(stepper
+            .client_app
+            .world()
+            .get_entity(client_entity)
+            .is_none());
+    }
+}
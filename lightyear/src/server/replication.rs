@@ -54,7 +54,7 @@ pub(crate) mod send {
     use crate::prelude::{
         is_host_server, ClientId, ComponentRegistry, DisabledComponent, NetworkRelevanceMode,
         OverrideTargetComponent, ReplicateHierarchy, Replicated, ReplicationGroup,
-        ShouldBePredicted, TargetEntity, Tick, TickManager, TimeManager,
+        ReplicationGroupKey, ShouldBePredicted, TargetEntity, Tick, TickManager, TimeManager,
     };
     use crate::protocol::component::ComponentKind;
     use crate::server::error::ServerError;
@@ -70,9 +70,10 @@ pub(crate) mod send {
     };
     use crate::shared::replication::network_target::NetworkTarget;
     use crate::shared::replication::ReplicationSend;
-    use bevy::ecs::component::ComponentTicks;
+    use bevy::ecs::component::{ComponentId, ComponentTicks};
     use bevy::ecs::system::SystemChangeTick;
     use bevy::ptr::Ptr;
+    use bevy::utils::HashMap;
 
     #[derive(Default)]
     pub struct ServerReplicationSendPlugin {
@@ -90,6 +91,8 @@ pub(crate) mod send {
             app
                 // REFLECTION
                 .register_type::<Replicate>()
+                .register_type::<ReplicationPaused>()
+                .register_type::<ReplicationWorldId>()
                 // PLUGIN
                 .add_plugins(ReplicationSendPlugin::<ConnectionManager>::new(
                     self.tick_interval,
@@ -123,6 +126,7 @@ pub(crate) mod send {
                         .in_set(InternalReplicationSet::<ServerMarker>::BufferComponentUpdates),
                     (
                         handle_replication_target_update,
+                        handle_controlled_by_cache_update,
                         buffer_replication_messages,
                     )
                         .in_set(InternalReplicationSet::<ServerMarker>::AfterBuffer),
@@ -137,6 +141,7 @@ pub(crate) mod send {
             );
 
             app.observe(replicate_entity_local_despawn);
+            app.observe(resume_paused_replication);
             app.observe(add_has_authority_component);
             app.observe(handle_pre_predicted);
         }
@@ -180,6 +185,26 @@ pub(crate) mod send {
         }
     }
 
+    /// Marker component that pauses sending replication updates for an entity.
+    ///
+    /// While this component is present, the entity stays spawned on the clients but none of its
+    /// component inserts/updates are sent. When the component is removed, replication resumes
+    /// and the current state of all replicated components is sent, so that the clients receive
+    /// every change that happened while the entity was paused.
+    #[derive(Component, Clone, Copy, Debug, Default, PartialEq, Reflect)]
+    #[reflect(Component)]
+    pub struct ReplicationPaused;
+
+    /// Identifies the logical world that an entity belongs to.
+    ///
+    /// A single server can run multiple logical worlds (for example a hub and several game instances).
+    /// Entities are only replicated to the clients that are assigned to the same world
+    /// (see [`ConnectionManager::set_client_world`]).
+    /// Entities without this component, and clients that were not assigned to a world, belong to the default world.
+    #[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Reflect)]
+    #[reflect(Component)]
+    pub struct ReplicationWorldId(pub u32);
+
     #[derive(Clone, Copy, Debug, Default, PartialEq, Reflect)]
     pub enum Lifetime {
         #[default]
@@ -268,12 +293,14 @@ pub(crate) mod send {
             Ref<ReplicationTarget>,
             &SyncTarget,
             Option<Ref<ControlledBy>>,
+            Has<Controlled>,
             Option<&PrePredicted>,
         )>,
         connection: Res<ClientConnection>,
     ) {
         let local_client = connection.id();
-        for (entity, replication_target, sync_target, controlled_by, pre_predicted) in query.iter()
+        for (entity, replication_target, sync_target, controlled_by, controlled, pre_predicted) in
+            query.iter()
         {
             // also insert [`Controlled`] on the entity if it's controlled by the local client
             if let Some(controlled_by) = controlled_by {
@@ -283,6 +310,11 @@ pub(crate) mod send {
                         // NOTE: do not replicate this Controlled to other clients, or they will
                         // think they control this entity
                         .insert((Controlled, DisabledComponent::<Controlled>::default()));
+                } else if controlled_by.is_changed() && controlled {
+                    // the local client lost the control of the entity (e.g. with `transfer_control`)
+                    commands
+                        .entity(entity)
+                        .remove::<(Controlled, DisabledComponent<Controlled>)>();
                 }
             }
             if (replication_target.is_changed()) && replication_target.target.targets(&local_client)
@@ -351,6 +383,32 @@ pub(crate) mod send {
         }
     }
 
+    /// Keep a cached version of the [`ControlledBy`] component so that when it gets updated
+    /// we know which clients lost the control of the entity.
+    ///
+    /// This needs to run after we compute the diff, so after the `replicate` system runs
+    pub(crate) fn handle_controlled_by_cache_update(
+        mut commands: Commands,
+        mut query: Query<
+            (Entity, &ControlledBy, Option<&mut Cached<ControlledBy>>),
+            Changed<ControlledBy>,
+        >,
+        removed: Query<Entity, (With<Cached<ControlledBy>>, Without<ControlledBy>)>,
+    ) {
+        for (entity, controlled_by, cached) in query.iter_mut() {
+            if let Some(mut cached) = cached {
+                cached.value = controlled_by.clone();
+            } else {
+                commands.entity(entity).insert(Cached {
+                    value: controlled_by.clone(),
+                });
+            }
+        }
+        for entity in removed.iter() {
+            commands.entity(entity).remove::<Cached<ControlledBy>>();
+        }
+    }
+
     /// Add HasAuthority component to a newly replicated entity if the server has
     /// authority over it
     fn add_has_authority_component(
@@ -385,6 +443,9 @@ pub(crate) mod send {
 
         let mut sender = std::mem::take(&mut *set.p1());
         let world = set.p0();
+        // cache the clients that are assigned to each world
+        let mut world_targets: HashMap<ReplicationWorldId, Option<NetworkTarget>> =
+            HashMap::default();
 
         // 2. go through all the archetypes that should be replicated
         for replicated_archetype in replicated_archetypes.archetypes.iter() {
@@ -416,6 +477,16 @@ pub(crate) mod send {
                     g.group_id(Some(entity.id()))
                 });
                 let priority = group.map_or(1.0, |g| g.priority());
+                // the group key changed: move the entity to its new group on the clients that replicate it
+                let migrated_target = if entity_ref
+                    .get_change_ticks::<ReplicationGroupKey>()
+                    .is_some_and(|ticks| {
+                        ticks.is_changed(system_ticks.last_run(), system_ticks.this_run())
+                    }) {
+                    replicate_entity_group_migration(entity.id(), group_id, priority, &mut sender)
+                } else {
+                    NetworkTarget::None
+                };
                 let cached_replication_target = entity_ref.get::<Cached<ReplicationTarget>>();
                 let visibility = entity_ref.get::<CachedNetworkRelevance>();
                 let sync_target = entity_ref.get::<SyncTarget>();
@@ -427,6 +498,23 @@ pub(crate) mod send {
                 // because the archetype is in replicated_archetypes
                 let replication_target =
                     unsafe { entity_ref.get::<ReplicationTarget>().unwrap_unchecked() };
+                // only replicate to the clients that are in the same world as the entity
+                let world_id = entity_ref
+                    .get::<ReplicationWorldId>()
+                    .copied()
+                    .unwrap_or_default();
+                let world_target = world_targets
+                    .entry(world_id)
+                    .or_insert_with(|| sender.world_target(world_id))
+                    .as_ref();
+                let world_replication_target = world_target.map(|world_target| {
+                    let mut replication_target = replication_target.clone();
+                    replication_target.target.intersection(world_target);
+                    replication_target
+                });
+                let replication_target = world_replication_target
+                    .as_ref()
+                    .unwrap_or(replication_target);
                 let replication_target_ticks = unsafe {
                     entity_ref
                         .get_change_ticks::<ReplicationTarget>()
@@ -454,6 +542,7 @@ pub(crate) mod send {
                     cached_replication_target,
                     authority_peer,
                     visibility,
+                    world_target,
                     &mut sender,
                 );
 
@@ -475,11 +564,51 @@ pub(crate) mod send {
                     &system_ticks,
                 );
 
-                // If the group is not set to send, skip sending updates for this entity
-                if group.is_some_and(|g| !g.should_send) {
+                // If the group is not set to send or the entity is paused, skip sending updates for this entity
+                if group.is_some_and(|g| !g.should_send)
+                    || entity_ref.contains::<ReplicationPaused>()
+                {
                     continue;
                 }
 
+                // the clients that gained or lost the control of the entity since the last send:
+                // the owner-only components have to be inserted or removed on them
+                let cached_controlled_by = entity_ref.get::<Cached<ControlledBy>>();
+                let owner_change =
+                    (entity_ref
+                        .get_change_ticks::<ControlledBy>()
+                        .is_some_and(|ticks| {
+                            ticks.is_changed(system_ticks.last_run(), system_ticks.this_run())
+                        })
+                        || (controlled_by.is_none() && cached_controlled_by.is_some()))
+                    .then(|| {
+                        let new_owners =
+                            controlled_by.map_or(NetworkTarget::None, |c| c.target.clone());
+                        let previous_owners = cached_controlled_by
+                            .map_or(NetworkTarget::None, |c| c.value.target.clone());
+                        let mut gained = new_owners.clone();
+                        gained.exclude(&previous_owners);
+                        let mut lost = previous_owners;
+                        lost.exclude(&new_owners);
+                        // only the clients that currently replicate the entity are affected
+                        if let Some(visibility) = visibility {
+                            let relevant = NetworkTarget::from(
+                                visibility
+                                    .clients_cache
+                                    .iter()
+                                    .filter(|(_, relevance)| **relevance != ClientRelevance::Lost)
+                                    .map(|(client_id, _)| *client_id)
+                                    .collect::<Vec<_>>(),
+                            );
+                            gained.intersection(&relevant);
+                            lost.intersection(&relevant);
+                        }
+                        if let Some(AuthorityPeer::Client(c)) = authority_peer {
+                            lost.exclude(&NetworkTarget::Single(*c));
+                        }
+                        (gained, lost)
+                    });
+
                 // d. all components that were added or changed
                 for replicated_component in replicated_archetype.components.iter() {
                     let (data, component_ticks) = unsafe {
@@ -498,6 +627,45 @@ pub(crate) mod send {
                             // the OverrideTarget<C> component has the same memory layout as NetworkTarget
                             .map(|ptr| unsafe { ptr.deref::<NetworkTarget>() })
                     });
+                    // owner-only components are only replicated to the clients that control the entity
+                    let owner_target;
+                    let mut owner_gained_target = None;
+                    let override_target = if replicated_component.owner_only {
+                        let base_target = override_target.unwrap_or(&replication_target.target);
+                        if let Some((gained, lost)) = &owner_change {
+                            // remove the component from the clients that lost the control of the entity
+                            let mut lost_target = lost.clone();
+                            lost_target.intersection(base_target);
+                            if let Some(net_id) = component_registry
+                                .kind_map
+                                .net_id(&replicated_component.kind)
+                                .filter(|_| !lost_target.is_empty())
+                            {
+                                let _ = sender
+                                    .prepare_component_remove(
+                                        entity.id(),
+                                        *net_id,
+                                        group.unwrap_or(&ReplicationGroup::default()),
+                                        lost_target,
+                                    )
+                                    .inspect_err(|e| {
+                                        error!("error sending component remove: {:?}", e);
+                                    });
+                            }
+                            // and insert it on the clients that gained the control of the entity
+                            let mut gained_target = migrated_target.clone();
+                            gained_target.union(gained);
+                            owner_gained_target = Some(gained_target);
+                        }
+                        let mut target = base_target.clone();
+                        target.intersection(
+                            controlled_by.map_or(&NetworkTarget::None, |c| &c.target),
+                        );
+                        owner_target = target;
+                        Some(&owner_target)
+                    } else {
+                        override_target
+                    };
 
                     replicate_component_updates(
                         tick_manager.tick(),
@@ -514,6 +682,7 @@ pub(crate) mod send {
                         replicated_component.delta_compression,
                         replicated_component.replicate_once,
                         override_target,
+                        owner_gained_target.as_ref().unwrap_or(&migrated_target),
                         &system_ticks,
                         &mut sender,
                     );
@@ -526,6 +695,71 @@ pub(crate) mod send {
         *set.p1() = sender;
     }
 
+    /// When an entity stops being paused, mark all its replicated components as changed so that
+    /// every change that happened during the pause gets sent to the clients
+    pub(crate) fn resume_paused_replication(
+        trigger: Trigger<OnRemove, ReplicationPaused>,
+        mut commands: Commands,
+    ) {
+        let entity = trigger.entity();
+        commands.add(move |world: &mut World| {
+            let Some(entity_ref) = world.get_entity(entity) else {
+                return;
+            };
+            let component_registry = world.resource::<ComponentRegistry>();
+            let replicated_components: Vec<ComponentId> = entity_ref
+                .archetype()
+                .components()
+                .filter(|id| {
+                    world
+                        .components()
+                        .get_info(*id)
+                        .and_then(|info| info.type_id())
+                        .is_some_and(|type_id| {
+                            component_registry
+                                .replication_map
+                                .contains_key(&ComponentKind(type_id))
+                        })
+                })
+                .collect();
+            let mut entity_mut = world.entity_mut(entity);
+            for component_id in replicated_components {
+                if let Some(mut component) = entity_mut.get_mut_by_id(component_id) {
+                    component.set_changed();
+                }
+            }
+        });
+    }
+
+    /// Move an entity whose [`ReplicationGroupKey`] changed to its new replication group, on all the clients
+    /// that already replicate it.
+    ///
+    /// Returns the clients for which the entity was migrated: all the replicated components of the entity
+    /// have to be sent again to them as part of the new group.
+    pub(crate) fn replicate_entity_group_migration(
+        entity: Entity,
+        group_id: ReplicationGroupId,
+        priority: f32,
+        sender: &mut ConnectionManager,
+    ) -> NetworkTarget {
+        let clients = sender
+            .connections
+            .iter_mut()
+            .filter_map(|(client_id, connection)| {
+                connection
+                    .replication_sender
+                    .prepare_entity_group_migration(entity, group_id)
+                    .then(|| {
+                        connection
+                            .replication_sender
+                            .update_base_priority(group_id, priority);
+                        *client_id
+                    })
+            })
+            .collect::<Vec<_>>();
+        NetworkTarget::from(clients)
+    }
+
     /// Send entity spawn replication messages to clients
     /// Also handles:
     /// - newly_connected_clients should receive the entity spawn message even if the entity was not just spawned
@@ -567,8 +801,11 @@ pub(crate) mod send {
                                 }
                                 ClientRelevance::Lost => {}
                                 ClientRelevance::Maintained => {
-                                    // only try to replicate if the replicate component was just added
-                                    if replication_target.is_added() {
+                                    // only try to replicate if the replicate component was just added,
+                                    // or if the client is being resynced
+                                    if replication_target.is_added()
+                                        || sender.new_clients.contains(client_id)
+                                    {
                                         trace!(
                                             ?entity,
                                             ?client_id,
@@ -703,6 +940,7 @@ pub(crate) mod send {
                 &ReplicationGroup,
                 &ReplicationTarget,
                 Option<&CachedNetworkRelevance>,
+                Option<&ReplicationWorldId>,
             ),
             With<Replicating>,
         >,
@@ -711,7 +949,9 @@ pub(crate) mod send {
         mut sender: ResMut<ConnectionManager>,
     ) {
         let entity = trigger.entity();
-        if let Ok((replication_group, network_target, cached_relevance)) = query.get(entity) {
+        if let Ok((replication_group, network_target, cached_relevance, world_id)) =
+            query.get(entity)
+        {
             trace!(?entity, "Replicate entity despawn");
             // only send the despawn to clients who were in the target of the entity
             let mut target = network_target.clone().target;
@@ -722,6 +962,10 @@ pub(crate) mod send {
                     network_relevance.clients_cache.keys().copied().collect(),
                 ))
             }
+            // only send the despawn to clients that are in the same world as the entity
+            if let Some(world_target) = sender.world_target(world_id.copied().unwrap_or_default()) {
+                target.intersection(&world_target);
+            }
             trace!(?entity, ?target, "send entity despawn");
             let _ = sender
                 .prepare_entity_despawn(entity, replication_group.group_id(Some(entity)), target)
@@ -742,6 +986,7 @@ pub(crate) mod send {
         cached_replication_target: Option<&Cached<ReplicationTarget>>,
         authority_peer: Option<&AuthorityPeer>,
         visibility: Option<&CachedNetworkRelevance>,
+        world_target: Option<&NetworkTarget>,
         sender: &mut ConnectionManager,
     ) {
         // 1. send despawn for clients that lost visibility
@@ -779,6 +1024,10 @@ pub(crate) mod send {
         if let Some(AuthorityPeer::Client(c)) = authority_peer {
             target.exclude(&NetworkTarget::Single(*c));
         }
+        // 4. clients in other worlds never received the entity
+        if let Some(world_target) = world_target {
+            target.intersection(world_target);
+        }
 
         if !target.is_empty() {
             let _ = sender
@@ -816,6 +1065,7 @@ pub(crate) mod send {
         delta_compression: bool,
         replicate_once: bool,
         override_target: Option<&NetworkTarget>,
+        migrated_target: &NetworkTarget,
         system_ticks: &SystemChangeTick,
         sender: &mut ConnectionManager,
     ) {
@@ -848,6 +1098,7 @@ pub(crate) mod send {
                                             system_ticks.last_run(),
                                             system_ticks.this_run(),
                                         ) || force_insert
+                                            || sender.new_clients.contains(client_id)
                                         {
                                             insert_clients.push(*client_id);
                                         } else {
@@ -884,15 +1135,14 @@ pub(crate) mod send {
                     {
                         trace!("component is added or replication_target is added");
                         insert_target.union(target);
-                    } else {
+                    } else if replicate_once {
                         // do not send updates for these components, only inserts/removes
-                        if replicate_once {
-                            trace!(?entity,
-                                "not replicating updates for {:?} because it is marked as replicate_once",
-                                "COMPONENT_KIND"
-                            );
-                            return;
-                        }
+                        // (but we still need to insert them for newly connected clients)
+                        trace!(?entity,
+                            "not replicating updates for {:?} because it is marked as replicate_once",
+                            "COMPONENT_KIND"
+                        );
+                    } else {
                         // otherwise send an update for all components that changed since the
                         // last update we have ack-ed
                         update_target.union(target);
@@ -910,6 +1160,12 @@ pub(crate) mod send {
                     (insert_target, update_target)
                 }
             };
+        // the entity moved to a different replication group: send all the components again in the new group
+        if !migrated_target.is_empty() {
+            let mut migrated_target = migrated_target.clone();
+            migrated_target.intersection(target);
+            insert_target.union(&migrated_target);
+        }
 
         // we don't send messages to the client that has authority
         if let Some(AuthorityPeer::Client(c)) = authority_peer {
@@ -971,6 +1227,7 @@ pub(crate) mod send {
                 Option<&CachedNetworkRelevance>,
                 Has<DisabledComponent<C>>,
                 Option<&OverrideTargetComponent<C>>,
+                Option<&ReplicationWorldId>,
             ),
             With<Replicating>,
         >,
@@ -986,6 +1243,7 @@ pub(crate) mod send {
                 visibility,
                 disabled,
                 override_target,
+                world_id,
             )) = query.get(entity)
             {
                 // do not replicate components that are disabled
@@ -1023,6 +1281,11 @@ pub(crate) mod send {
                 if let Some(AuthorityPeer::Client(c)) = authority_peer {
                     target.exclude(&NetworkTarget::Single(*c));
                 }
+                if let Some(world_target) =
+                    sender.world_target(world_id.copied().unwrap_or_default())
+                {
+                    target.intersection(&world_target);
+                }
                 if target.is_empty() {
                     return;
                 }
@@ -1216,6 +1479,101 @@ pub(crate) mod send {
                 .is_none());
         }
 
+        /// Check that entities are only replicated to the clients that are in the same world
+        #[test]
+        fn test_entity_spawn_world() {
+            let mut stepper = MultiBevyStepper::default();
+            stepper
+                .server_app
+                .world_mut()
+                .resource_mut::<ConnectionManager>()
+                .set_client_world(ClientId::Netcode(TEST_CLIENT_ID_2), ReplicationWorldId(1))
+                .unwrap();
+
+            // spawn one entity in the default world and one entity in world 1
+            let server_entity_default = stepper
+                .server_app
+                .world_mut()
+                .spawn(Replicate::default())
+                .id();
+            let server_entity_1 = stepper
+                .server_app
+                .world_mut()
+                .spawn((Replicate::default(), ReplicationWorldId(1)))
+                .id();
+            stepper.frame_step();
+            stepper.frame_step();
+
+            let remote_entity_map_1 = &stepper
+                .client_app_1
+                .world()
+                .resource::<client::ConnectionManager>()
+                .replication_receiver
+                .remote_entity_map;
+            assert!(remote_entity_map_1
+                .get_local(server_entity_default)
+                .is_some());
+            assert!(remote_entity_map_1.get_local(server_entity_1).is_none());
+            let remote_entity_map_2 = &stepper
+                .client_app_2
+                .world()
+                .resource::<client::ConnectionManager>()
+                .replication_receiver
+                .remote_entity_map;
+            assert!(remote_entity_map_2
+                .get_local(server_entity_default)
+                .is_none());
+            assert!(remote_entity_map_2.get_local(server_entity_1).is_some());
+        }
+
+        /// Check that bucketed replication groups reduce the number of group channels
+        #[test]
+        fn test_entity_spawn_bucketed_groups() {
+            let mut stepper = BevyStepper::default();
+
+            let num_entities = 100;
+            let num_buckets = 4;
+            let server_entities: Vec<Entity> = (0..num_entities)
+                .map(|i| {
+                    stepper
+                        .server_app
+                        .world_mut()
+                        .spawn(Replicate {
+                            group: ReplicationGroup::new_bucket(i, num_buckets),
+                            ..default()
+                        })
+                        .id()
+                })
+                .collect();
+            stepper.frame_step();
+            stepper.frame_step();
+
+            // check that all the entities were replicated
+            for server_entity in server_entities {
+                assert!(stepper
+                    .client_app
+                    .world()
+                    .resource::<client::ConnectionManager>()
+                    .replication_receiver
+                    .remote_entity_map
+                    .get_local(server_entity)
+                    .is_some());
+            }
+            // check that the entities were bucketed into a small number of groups
+            assert_eq!(
+                stepper
+                    .server_app
+                    .world()
+                    .resource::<ConnectionManager>()
+                    .connection(ClientId::Netcode(TEST_CLIENT_ID))
+                    .unwrap()
+                    .replication_sender
+                    .group_channels
+                    .len(),
+                num_buckets as usize
+            );
+        }
+
         #[test]
         fn test_entity_spawn_preexisting_target() {
             let mut stepper = BevyStepper::default();
@@ -1973,6 +2331,146 @@ pub(crate) mod send {
             );
         }
 
+        /// Check that a [`ComponentStateTransition`](crate::client::events::ComponentStateTransition) event is emitted
+        /// on the client, with the old and new values, every time the state component is updated
+        #[test]
+        fn test_state_transition_events() {
+            use crate::client::events::ComponentStateTransition;
+            use bevy::prelude::Events;
+
+            let mut stepper = BevyStepper::default();
+            let mut transitions = vec![];
+            let mut step = |stepper: &mut BevyStepper| {
+                stepper.frame_step();
+                stepper.frame_step();
+                transitions.extend(
+                    stepper
+                        .client_app
+                        .world_mut()
+                        .resource_mut::<Events<ComponentStateTransition<ComponentStateMachine>>>()
+                        .drain(),
+                );
+            };
+
+            let server_entity = stepper
+                .server_app
+                .world_mut()
+                .spawn((Replicate::default(), ComponentStateMachine::Idle))
+                .id();
+            step(&mut stepper);
+            let client_entity = stepper
+                .client_app
+                .world()
+                .resource::<client::ConnectionManager>()
+                .replication_receiver
+                .remote_entity_map
+                .get_local(server_entity)
+                .expect("entity was not replicated to client");
+
+            for state in [
+                ComponentStateMachine::Running,
+                ComponentStateMachine::Jumping,
+                ComponentStateMachine::Idle,
+            ] {
+                stepper
+                    .server_app
+                    .world_mut()
+                    .entity_mut(server_entity)
+                    .insert(state);
+                step(&mut stepper);
+            }
+
+            assert_eq!(
+                stepper
+                    .client_app
+                    .world()
+                    .entity(client_entity)
+                    .get::<ComponentStateMachine>(),
+                Some(&ComponentStateMachine::Idle)
+            );
+            let transition = |old, new| ComponentStateTransition {
+                entity: client_entity,
+                old,
+                new,
+            };
+            assert_eq!(
+                transitions,
+                vec![
+                    transition(ComponentStateMachine::Idle, ComponentStateMachine::Running),
+                    transition(
+                        ComponentStateMachine::Running,
+                        ComponentStateMachine::Jumping
+                    ),
+                    transition(ComponentStateMachine::Jumping, ComponentStateMachine::Idle),
+                ]
+            );
+        }
+
+        /// Check that [`ComponentStateTransition`](crate::client::events::ComponentStateTransition) events are also
+        /// emitted for components that are replicated with delta compression
+        #[test]
+        fn test_state_transition_events_delta() {
+            use crate::client::events::ComponentStateTransition;
+            use bevy::prelude::Events;
+
+            let mut stepper = BevyStepper::default();
+            let mut transitions = vec![];
+            let mut step = |stepper: &mut BevyStepper| {
+                stepper.frame_step();
+                stepper.frame_step();
+                transitions.extend(
+                    stepper
+                        .client_app
+                        .world_mut()
+                        .resource_mut::<Events<ComponentStateTransition<ComponentDeltaCompression2>>>()
+                        .drain(),
+                );
+            };
+
+            let server_entity = stepper
+                .server_app
+                .world_mut()
+                .spawn((
+                    Replicate::default(),
+                    ComponentDeltaCompression2(HashSet::from([1])),
+                    DeltaCompression::<ComponentDeltaCompression2>::default(),
+                ))
+                .id();
+            step(&mut stepper);
+            let client_entity = stepper
+                .client_app
+                .world()
+                .resource::<client::ConnectionManager>()
+                .replication_receiver
+                .remote_entity_map
+                .get_local(server_entity)
+                .expect("entity was not replicated to client");
+
+            stepper
+                .server_app
+                .world_mut()
+                .entity_mut(server_entity)
+                .insert(ComponentDeltaCompression2(HashSet::from([1, 2])));
+            step(&mut stepper);
+
+            assert_eq!(
+                stepper
+                    .client_app
+                    .world()
+                    .entity(client_entity)
+                    .get::<ComponentDeltaCompression2>(),
+                Some(&ComponentDeltaCompression2(HashSet::from([1, 2])))
+            );
+            assert_eq!(
+                transitions,
+                vec![ComponentStateTransition {
+                    entity: client_entity,
+                    old: ComponentDeltaCompression2(HashSet::from([1])),
+                    new: ComponentDeltaCompression2(HashSet::from([1, 2])),
+                }]
+            );
+        }
+
         /// Test that replicating updates works even if the update happens after tick wrapping
         #[test]
         fn test_component_update_after_tick_wrap() {
@@ -2722,6 +3220,66 @@ pub(crate) mod send {
                 .contains_key(&insert_tick));
         }
 
+        /// Check that we count how many delta-compressed updates are computed from an acked value
+        /// vs from the base value, for each client
+        #[test]
+        fn test_delta_compression_stats() {
+            let mut stepper = MultiBevyStepper::default();
+            let server_entity = stepper
+                .server_app
+                .world_mut()
+                .spawn((
+                    Replicate::default(),
+                    ComponentDeltaCompression(vec![1]),
+                    DeltaCompression::<ComponentDeltaCompression>::default(),
+                ))
+                .id();
+            stepper.frame_step();
+            stepper.frame_step();
+
+            let stats = |stepper: &MultiBevyStepper, client_id| {
+                stepper
+                    .server_app
+                    .world()
+                    .resource::<ConnectionManager>()
+                    .delta_compression_stats::<ComponentDeltaCompression>(ClientId::Netcode(
+                        client_id,
+                    ))
+                    .unwrap()
+            };
+            // client 1 stops acking the updates (its packets don't reach the server), and the server
+            // doesn't have any acked value to diff from
+            stepper
+                .server_app
+                .world_mut()
+                .resource_mut::<ConnectionManager>()
+                .connection_mut(ClientId::Netcode(TEST_CLIENT_ID_1))
+                .unwrap()
+                .replication_sender
+                .reset_acks();
+            for i in 2..12 {
+                stepper
+                    .server_app
+                    .world_mut()
+                    .entity_mut(server_entity)
+                    .get_mut::<ComponentDeltaCompression>()
+                    .unwrap()
+                    .0
+                    .push(i);
+                stepper.advance_time(stepper.frame_duration);
+                stepper.client_app_2.update();
+                stepper.server_app.update();
+            }
+
+            // (an update is not sent on the frames where the server does not run a tick)
+            let lossy = stats(&stepper, TEST_CLIENT_ID_1);
+            assert!(lossy.full_diffs >= 8);
+            assert_eq!(lossy.incremental_diffs, 0);
+            let healthy = stats(&stepper, TEST_CLIENT_ID_2);
+            assert_eq!(healthy.full_diffs, 0);
+            assert_eq!(healthy.incremental_diffs, lossy.full_diffs);
+        }
+
         /// Check that updates are not sent if the `ReplicationTarget` component gets removed.
         /// Check that updates are resumed when the `ReplicationTarget` component gets re-added.
         #[test]
@@ -2832,18 +3390,14 @@ pub(crate) mod send {
         }
 
         #[test]
-        fn test_component_update_replicate_once() {
+        fn test_component_update_paused() {
             let mut stepper = BevyStepper::default();
 
             // spawn an entity on server
             let server_entity = stepper
                 .server_app
                 .world_mut()
-                .spawn((
-                    Replicate::default(),
-                    ComponentSyncModeFull(1.0),
-                    ReplicateOnceComponent::<ComponentSyncModeFull>::default(),
-                ))
+                .spawn((Replicate::default(), ComponentSyncModeFull(1.0)))
                 .id();
             stepper.frame_step();
             stepper.frame_step();
@@ -2855,7 +3409,252 @@ pub(crate) mod send {
                 .remote_entity_map
                 .get_local(server_entity)
                 .expect("entity was not replicated to client");
-            // check that the component was replicated
+
+            // pause replication and update the component multiple times
+            stepper
+                .server_app
+                .world_mut()
+                .entity_mut(server_entity)
+                .insert(ReplicationPaused);
+            stepper.frame_step();
+            stepper
+                .server_app
+                .world_mut()
+                .get_mut::<ComponentSyncModeFull>(server_entity)
+                .unwrap()
+                .0 = 2.0;
+            stepper.frame_step();
+            stepper
+                .server_app
+                .world_mut()
+                .get_mut::<ComponentSyncModeFull>(server_entity)
+                .unwrap()
+                .0 = 3.0;
+            stepper.frame_step();
+            stepper.frame_step();
+
+            // check that the entity is still present but the component was not updated
+            assert_eq!(
+                stepper
+                    .client_app
+                    .world()
+                    .entity(client_entity)
+                    .get::<ComponentSyncModeFull>()
+                    .expect("component missing"),
+                &ComponentSyncModeFull(1.0)
+            );
+
+            // resume replication
+            stepper
+                .server_app
+                .world_mut()
+                .entity_mut(server_entity)
+                .remove::<ReplicationPaused>();
+            stepper.frame_step();
+            stepper.frame_step();
+
+            // check that the client received the accumulated change
+            assert_eq!(
+                stepper
+                    .client_app
+                    .world()
+                    .entity(client_entity)
+                    .get::<ComponentSyncModeFull>()
+                    .expect("component missing"),
+                &ComponentSyncModeFull(3.0)
+            );
+        }
+
+        /// Check that owner-only components are only replicated to the client that controls the entity
+        #[test]
+        fn test_component_owner_only() {
+            let mut stepper = MultiBevyStepper::default();
+
+            // spawn an entity on server, controlled by client 1 but replicated to both clients
+            let server_entity = stepper
+                .server_app
+                .world_mut()
+                .spawn((
+                    Replicate {
+                        controlled_by: ControlledBy {
+                            target: NetworkTarget::Single(ClientId::Netcode(TEST_CLIENT_ID_1)),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    ComponentOwnerOnly(1.0),
+                ))
+                .id();
+            stepper.frame_step();
+            stepper.frame_step();
+            let client_entity_1 = stepper
+                .client_app_1
+                .world()
+                .resource::<client::ConnectionManager>()
+                .replication_receiver
+                .remote_entity_map
+                .get_local(server_entity)
+                .expect("entity was not replicated to client 1");
+            let client_entity_2 = stepper
+                .client_app_2
+                .world()
+                .resource::<client::ConnectionManager>()
+                .replication_receiver
+                .remote_entity_map
+                .get_local(server_entity)
+                .expect("entity was not replicated to client 2");
+
+            // check that the component was only replicated to the owning client
+            assert_eq!(
+                stepper
+                    .client_app_1
+                    .world()
+                    .get::<ComponentOwnerOnly>(client_entity_1)
+                    .expect("component missing"),
+                &ComponentOwnerOnly(1.0)
+            );
+            assert!(stepper
+                .client_app_2
+                .world()
+                .get::<ComponentOwnerOnly>(client_entity_2)
+                .is_none());
+
+            // update the component
+            stepper
+                .server_app
+                .world_mut()
+                .get_mut::<ComponentOwnerOnly>(server_entity)
+                .unwrap()
+                .0 = 2.0;
+            stepper.frame_step();
+            stepper.frame_step();
+
+            // check that the update was only replicated to the owning client
+            assert_eq!(
+                stepper
+                    .client_app_1
+                    .world()
+                    .get::<ComponentOwnerOnly>(client_entity_1)
+                    .expect("component missing"),
+                &ComponentOwnerOnly(2.0)
+            );
+            assert!(stepper
+                .client_app_2
+                .world()
+                .get::<ComponentOwnerOnly>(client_entity_2)
+                .is_none());
+        }
+
+        /// Check that owner-only components are moved to the new owner when the control of the entity changes
+        #[test]
+        fn test_component_owner_only_transfer() {
+            let mut stepper = MultiBevyStepper::default();
+
+            let client_id_1 = ClientId::Netcode(TEST_CLIENT_ID_1);
+            let client_id_2 = ClientId::Netcode(TEST_CLIENT_ID_2);
+            let server_entity = stepper
+                .server_app
+                .world_mut()
+                .spawn((
+                    Replicate {
+                        controlled_by: ControlledBy {
+                            target: NetworkTarget::Single(client_id_1),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    ComponentOwnerOnly(1.0),
+                ))
+                .id();
+            stepper.frame_step();
+            stepper.frame_step();
+            let client_entity = |client_app: &App| {
+                client_app
+                    .world()
+                    .resource::<client::ConnectionManager>()
+                    .replication_receiver
+                    .remote_entity_map
+                    .get_local(server_entity)
+                    .expect("entity was not replicated to the client")
+            };
+            let client_entity_1 = client_entity(&stepper.client_app_1);
+            let client_entity_2 = client_entity(&stepper.client_app_2);
+            assert!(stepper
+                .client_app_1
+                .world()
+                .get::<ComponentOwnerOnly>(client_entity_1)
+                .is_some());
+            assert!(stepper
+                .client_app_2
+                .world()
+                .get::<ComponentOwnerOnly>(client_entity_2)
+                .is_none());
+
+            // transfer the control of the entity to client 2
+            stepper
+                .server_app
+                .world_mut()
+                .get_mut::<ControlledBy>(server_entity)
+                .unwrap()
+                .target = NetworkTarget::Single(client_id_2);
+            stepper.frame_step();
+            stepper.frame_step();
+
+            // the component is removed from the previous owner and inserted on the new owner
+            assert!(stepper
+                .client_app_1
+                .world()
+                .get::<ComponentOwnerOnly>(client_entity_1)
+                .is_none());
+            assert_eq!(
+                stepper
+                    .client_app_2
+                    .world()
+                    .get::<ComponentOwnerOnly>(client_entity_2)
+                    .expect("component missing"),
+                &ComponentOwnerOnly(1.0)
+            );
+
+            // the entity is not controlled by anyone anymore
+            stepper
+                .server_app
+                .world_mut()
+                .entity_mut(server_entity)
+                .remove::<ControlledBy>();
+            stepper.frame_step();
+            stepper.frame_step();
+            assert!(stepper
+                .client_app_2
+                .world()
+                .get::<ComponentOwnerOnly>(client_entity_2)
+                .is_none());
+        }
+
+        #[test]
+        fn test_component_update_replicate_once() {
+            let mut stepper = BevyStepper::default();
+
+            // spawn an entity on server
+            let server_entity = stepper
+                .server_app
+                .world_mut()
+                .spawn((
+                    Replicate::default(),
+                    ComponentSyncModeFull(1.0),
+                    ReplicateOnceComponent::<ComponentSyncModeFull>::default(),
+                ))
+                .id();
+            stepper.frame_step();
+            stepper.frame_step();
+            let client_entity = stepper
+                .client_app
+                .world()
+                .resource::<client::ConnectionManager>()
+                .replication_receiver
+                .remote_entity_map
+                .get_local(server_entity)
+                .expect("entity was not replicated to client");
+            // check that the component was replicated
             assert_eq!(
                 stepper
                     .client_app
@@ -3087,15 +3886,106 @@ pub(crate) mod send {
                 1
             );
         }
+
+        /// Check that changing the [`ReplicationGroupKey`] of an entity moves it to the new group on the client,
+        /// without despawning it
+        #[test]
+        fn test_replication_group_key_migration() {
+            let mut stepper = BevyStepper::default();
+
+            let server_entity = stepper
+                .server_app
+                .world_mut()
+                .spawn((
+                    Replicate::default(),
+                    ReplicationGroupKey(1),
+                    ComponentSyncModeFull(1.0),
+                ))
+                .id();
+            stepper.frame_step();
+            stepper.frame_step();
+            let client_entity = stepper
+                .client_app
+                .world()
+                .resource::<client::ConnectionManager>()
+                .replication_receiver
+                .remote_entity_map
+                .get_local(server_entity)
+                .expect("entity was not replicated to client");
+            assert_eq!(
+                stepper
+                    .client_app
+                    .world()
+                    .resource::<client::ConnectionManager>()
+                    .replication_receiver
+                    .get_replication_group_id(client_entity),
+                Some(ReplicationGroupId(1))
+            );
+
+            // move the entity to another group
+            stepper
+                .server_app
+                .world_mut()
+                .entity_mut(server_entity)
+                .insert(ReplicationGroupKey(2));
+            stepper.frame_step();
+            stepper.frame_step();
+            assert_eq!(
+                stepper
+                    .client_app
+                    .world()
+                    .resource::<client::ConnectionManager>()
+                    .replication_receiver
+                    .remote_entity_map
+                    .get_local(server_entity),
+                Some(client_entity)
+            );
+            assert_eq!(
+                stepper
+                    .client_app
+                    .world()
+                    .resource::<client::ConnectionManager>()
+                    .replication_receiver
+                    .get_replication_group_id(client_entity),
+                Some(ReplicationGroupId(2))
+            );
+
+            // updates are still received in the new group
+            stepper
+                .server_app
+                .world_mut()
+                .entity_mut(server_entity)
+                .insert(ComponentSyncModeFull(2.0));
+            stepper.frame_step();
+            stepper.frame_step();
+            assert_eq!(
+                stepper
+                    .client_app
+                    .world()
+                    .entity(client_entity)
+                    .get::<ComponentSyncModeFull>()
+                    .expect("component missing"),
+                &ComponentSyncModeFull(2.0)
+            );
+        }
     }
 }
 
 pub(crate) mod commands {
     use crate::channel::builder::AuthorityChannel;
-    use crate::prelude::{Replicating, ServerConnectionManager};
+    use crate::prelude::server::{ControlledBy, SyncTarget};
+    use crate::prelude::{
+        ClientId, ComponentRegistry, NetworkTarget, Replicating, ReplicationGroup,
+        ServerConnectionManager,
+    };
+    use crate::server::clients::ControlledEntities;
     use crate::shared::replication::authority::{AuthorityChange, AuthorityPeer, HasAuthority};
+    use crate::shared::replication::components::{
+        Controlled, ShouldBeInterpolated, ShouldBePredicted,
+    };
     use bevy::ecs::system::EntityCommands;
-    use bevy::prelude::{Entity, World};
+    use bevy::prelude::{Entity, Mut, World};
+    use tracing::error;
 
     pub trait AuthorityCommandExt {
         /// This command is used to transfer the authority of an entity to a different peer.
@@ -3219,6 +4109,158 @@ pub(crate) mod commands {
         }
     }
 
+    pub trait ControlCommandExt {
+        /// Transfer the control of the entity (i.e. which client provides the inputs for it) to the client `new_owner`.
+        ///
+        /// The [`ControlledBy`] target of the entity is replaced with `new_owner` (the [`Lifetime`](crate::prelude::server::Lifetime)
+        /// is kept), the [`ControlledEntities`] of the clients are updated, and the [`Controlled`] marker is removed
+        /// from the entity on the previous owners' clients and inserted on the new owner's client.
+        ///
+        /// The entity stays replicated to all the clients, only the control changes.
+        /// Inputs are not tied to an entity on the server: inputs from the previous owner that were still
+        /// in flight are received as usual, so the server should use [`ControlledEntities`] to decide which
+        /// entities an input applies to. They then stop affecting the entity as soon as the transfer is applied.
+        /// The previous owner's client keeps predicting the entity with its own inputs until it receives
+        /// the removal of [`Controlled`], which will be corrected by the server's state.
+        /// In HostServer mode, [`Controlled`] is also updated on the entity of the local client.
+        ///
+        /// If the previous owners were predicting the entity (for example with `prediction: NetworkTarget::Single(owner)`),
+        /// the [`SyncTarget`] is updated so that the new owner predicts the entity instead, and the previous owners
+        /// interpolate it if the other clients were interpolating it. The clients replace their predicted or
+        /// interpolated entity accordingly.
+        fn transfer_control(&mut self, new_owner: ClientId);
+    }
+
+    impl ControlCommandExt for EntityCommands<'_> {
+        fn transfer_control(&mut self, new_owner: ClientId) {
+            self.add(move |entity: Entity, world: &mut World| {
+                let Some(mut entity_mut) = world.get_entity_mut(entity) else {
+                    return;
+                };
+                let group = entity_mut.get::<ReplicationGroup>().cloned();
+                let previous_sync = entity_mut.get::<SyncTarget>().cloned();
+                let mut controlled_by = entity_mut
+                    .get::<ControlledBy>()
+                    .cloned()
+                    .unwrap_or_default();
+                let previous_target =
+                    std::mem::replace(&mut controlled_by.target, NetworkTarget::Single(new_owner));
+                // the ControlledEntities of the new owner are updated when the ControlledBy component changes
+                entity_mut.insert(controlled_by);
+
+                // the previous owners lose control of the entity
+                let mut lost_target = previous_target.clone();
+                lost_target.exclude(&NetworkTarget::Single(new_owner));
+
+                // if the owners were predicting the entity, the new owner predicts it instead
+                let sync = previous_sync.as_ref().map(|previous_sync| {
+                    let mut sync = previous_sync.clone();
+                    let mut predicting_owners = previous_target.clone();
+                    predicting_owners.intersection(&sync.prediction);
+                    if !predicting_owners.is_empty() && !sync.prediction.targets(&new_owner) {
+                        sync.prediction.exclude(&lost_target);
+                        sync.prediction.union(&NetworkTarget::Single(new_owner));
+                        // the other clients were interpolating the entity: the previous owners now do too
+                        if sync.interpolation.targets(&new_owner) {
+                            sync.interpolation
+                                .exclude(&NetworkTarget::Single(new_owner));
+                            sync.interpolation.union(&predicting_owners);
+                        }
+                        entity_mut.insert(sync.clone());
+                    }
+                    sync
+                });
+                let client_entities: Vec<Entity> = {
+                    let manager = world.resource::<ServerConnectionManager>();
+                    manager
+                        .connected_targets(lost_target.clone())
+                        .filter_map(|client_id| manager.client_entity(client_id).ok())
+                        .collect()
+                };
+                for client_entity in client_entities {
+                    if let Some(mut controlled_entities) =
+                        world.get_mut::<ControlledEntities>(client_entity)
+                    {
+                        controlled_entities.remove(&entity);
+                    }
+                }
+
+                // update the Controlled marker on the clients
+                let Some(group) = group else {
+                    return;
+                };
+                world.resource_scope(|world, mut manager: Mut<ServerConnectionManager>| {
+                    let component_registry = world.resource::<ComponentRegistry>();
+                    let Some(net_id) = component_registry.get_net_id::<Controlled>() else {
+                        return;
+                    };
+                    if let Err(e) =
+                        manager.prepare_component_remove(entity, net_id, &group, lost_target)
+                    {
+                        error!(
+                            ?entity,
+                            "could not remove Controlled from the previous owners: {e:?}"
+                        );
+                    }
+                    let group_id = group.group_id(Some(entity));
+                    if !previous_target.targets(&new_owner) {
+                        if let Err(e) = manager.prepare_typed_component_insert(
+                            entity,
+                            group_id,
+                            new_owner,
+                            component_registry,
+                            &mut Controlled,
+                        ) {
+                            error!(
+                                ?entity,
+                                "could not insert Controlled for the new owner: {e:?}"
+                            );
+                        }
+                    }
+                    // the clients that start predicting (or interpolating) the entity replace their
+                    // interpolated (or predicted) entity
+                    let (Some(previous_sync), Some(sync)) = (previous_sync, sync) else {
+                        return;
+                    };
+                    let mut new_prediction = sync.prediction;
+                    new_prediction.exclude(&previous_sync.prediction);
+                    let mut new_interpolation = sync.interpolation;
+                    new_interpolation.exclude(&previous_sync.interpolation);
+                    let result = manager
+                        .connected_targets(new_prediction)
+                        .try_for_each(|client_id| {
+                            manager.prepare_typed_component_insert(
+                                entity,
+                                group_id,
+                                client_id,
+                                component_registry,
+                                &mut ShouldBePredicted,
+                            )
+                        })
+                        .and_then(|_| {
+                            manager
+                                .connected_targets(new_interpolation)
+                                .try_for_each(|client_id| {
+                                    manager.prepare_typed_component_insert(
+                                        entity,
+                                        group_id,
+                                        client_id,
+                                        component_registry,
+                                        &mut ShouldBeInterpolated,
+                                    )
+                                })
+                        });
+                    if let Err(e) = result {
+                        error!(
+                            ?entity,
+                            "could not update the SyncTarget of the clients: {e:?}"
+                        );
+                    }
+                });
+            });
+        }
+    }
+
     fn despawn_without_replication(entity: Entity, world: &mut World) {
         // remove replicating separately so that when we despawn the entity and trigger the observer
         // the entity doesn't have replicating anymore
@@ -3240,14 +4282,165 @@ pub(crate) mod commands {
 
     #[cfg(test)]
     mod tests {
-        use bevy::prelude::With;
+        use bevy::prelude::{default, App, With};
 
-        use crate::prelude::server::Replicate;
+        use crate::prelude::client::{self, Confirmed, Interpolated, Predicted};
+        use crate::prelude::server::{ConnectionManager, Replicate};
+        use crate::tests::host_server_stepper::{
+            HostServerStepper, EXTERNAL_CLIENT_ID, LOCAL_CLIENT_ID,
+        };
+        use crate::tests::multi_stepper::{MultiBevyStepper, TEST_CLIENT_ID_1, TEST_CLIENT_ID_2};
         use crate::tests::protocol::*;
         use crate::tests::stepper::BevyStepper;
 
         use super::*;
 
+        /// Check that transferring the control of an entity updates the [`Controlled`] marker and the
+        /// [`ControlledEntities`] of the clients, and that the new owner starts predicting the entity
+        #[test]
+        fn test_transfer_control() {
+            let mut stepper = MultiBevyStepper::default();
+            let client_1 = ClientId::Netcode(TEST_CLIENT_ID_1);
+            let client_2 = ClientId::Netcode(TEST_CLIENT_ID_2);
+            let server_entity = stepper
+                .server_app
+                .world_mut()
+                .spawn((
+                    ComponentSyncModeFull(1.0),
+                    Replicate {
+                        sync: SyncTarget {
+                            prediction: NetworkTarget::Single(client_1),
+                            interpolation: NetworkTarget::AllExceptSingle(client_1),
+                        },
+                        controlled_by: ControlledBy {
+                            target: NetworkTarget::Single(client_1),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                ))
+                .id();
+            stepper.frame_step();
+            stepper.frame_step();
+
+            let controls = |stepper: &MultiBevyStepper, client_id| {
+                let client_entity = stepper
+                    .server_app
+                    .world()
+                    .resource::<ConnectionManager>()
+                    .client_entity(client_id)
+                    .unwrap();
+                stepper
+                    .server_app
+                    .world()
+                    .get::<ControlledEntities>(client_entity)
+                    .unwrap()
+                    .contains(&server_entity)
+            };
+            // returns (is controlled, is predicted, is interpolated)
+            let sync_state = |app: &App| {
+                let client_entity = app
+                    .world()
+                    .resource::<client::ConnectionManager>()
+                    .replication_receiver
+                    .remote_entity_map
+                    .get_local(server_entity)
+                    .expect("entity was not replicated to client");
+                let confirmed = app.world().entity(client_entity);
+                let is_controlled = confirmed.contains::<Controlled>();
+                let state = confirmed.get::<Confirmed>().unwrap();
+                let (predicted, interpolated) = (state.predicted, state.interpolated);
+                (
+                    is_controlled,
+                    predicted.is_some_and(|e| app.world().get::<Predicted>(e).is_some()),
+                    interpolated.is_some_and(|e| app.world().get::<Interpolated>(e).is_some()),
+                )
+            };
+            assert!(controls(&stepper, client_1));
+            assert!(!controls(&stepper, client_2));
+            assert_eq!(sync_state(&stepper.client_app_1), (true, true, false));
+            assert_eq!(sync_state(&stepper.client_app_2), (false, false, true));
+
+            stepper
+                .server_app
+                .world_mut()
+                .commands()
+                .entity(server_entity)
+                .transfer_control(client_2);
+            stepper.server_app.world_mut().flush();
+            let sync = stepper
+                .server_app
+                .world()
+                .get::<SyncTarget>(server_entity)
+                .unwrap();
+            assert_eq!(sync.prediction, NetworkTarget::Single(client_2));
+            assert!(sync.interpolation.targets(&client_1));
+            assert!(!sync.interpolation.targets(&client_2));
+            stepper.frame_step();
+            stepper.frame_step();
+
+            assert!(!controls(&stepper, client_1));
+            assert!(controls(&stepper, client_2));
+            // the previous owner now interpolates the entity, and the new owner predicts it
+            assert_eq!(sync_state(&stepper.client_app_1), (false, false, true));
+            assert_eq!(sync_state(&stepper.client_app_2), (true, true, false));
+            // the previous predicted/interpolated copies have been despawned
+            for app in [&mut stepper.client_app_1, &mut stepper.client_app_2] {
+                let predicted = app
+                    .world_mut()
+                    .query_filtered::<(), With<Predicted>>()
+                    .iter(app.world())
+                    .count();
+                let interpolated = app
+                    .world_mut()
+                    .query_filtered::<(), With<Interpolated>>()
+                    .iter(app.world())
+                    .count();
+                assert_eq!(predicted + interpolated, 1);
+            }
+        }
+
+        /// Check that in HostServer mode, the local client loses the [`Controlled`] marker
+        /// when the control of the entity is transferred to another client
+        #[test]
+        fn test_transfer_control_host_server() {
+            let mut stepper = HostServerStepper::default();
+            let server_entity = stepper
+                .server_app
+                .world_mut()
+                .spawn((
+                    ComponentSyncModeFull(1.0),
+                    Replicate {
+                        controlled_by: ControlledBy {
+                            target: NetworkTarget::Single(ClientId::Local(LOCAL_CLIENT_ID)),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                ))
+                .id();
+            stepper.frame_step();
+            assert!(stepper
+                .server_app
+                .world()
+                .get::<Controlled>(server_entity)
+                .is_some());
+
+            stepper
+                .server_app
+                .world_mut()
+                .commands()
+                .entity(server_entity)
+                .transfer_control(ClientId::Netcode(EXTERNAL_CLIENT_ID));
+            stepper.server_app.world_mut().flush();
+            stepper.frame_step();
+            assert!(stepper
+                .server_app
+                .world()
+                .get::<Controlled>(server_entity)
+                .is_none());
+        }
+
         #[test]
         fn test_despawn() {
             let mut stepper = BevyStepper::default();
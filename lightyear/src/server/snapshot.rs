@@ -0,0 +1,372 @@
+//! Save and restore the state of the replicated world
+//!
+//! A snapshot contains all the entities that are replicated by the server, along with:
+//! - all their components that are registered in the [`ComponentRegistry`] (serialized with the
+//!   same functions as the ones used for replication)
+//! - their [`ReplicationGroup`], [`ReplicationTarget`] and [`SyncTarget`]
+//!
+//! This can be used for save games, or to recover the server state after a crash.
+//!
+//! ```rust
+//! use bevy::prelude::*;
+//! use lightyear::prelude::server::*;
+//!
+//! fn save_and_reload(world: &mut World) {
+//!     let snapshot = world.serialize_world_snapshot().unwrap();
+//!     // ... despawn the replicated entities, restart the server, etc.
+//!     let _entity_map = world.load_world_snapshot(snapshot).unwrap();
+//! }
+//! ```
+use bevy::ecs::entity::EntityHashMap;
+use bevy::prelude::{Entity, Mut, World};
+use bevy::utils::HashMap;
+use byteorder::WriteBytesExt;
+use bytes::Bytes;
+
+use crate::prelude::{ComponentRegistry, ReplicationGroup, TickManager};
+use crate::protocol::component::ComponentKind;
+use crate::serialize::reader::Reader;
+use crate::serialize::writer::Writer;
+use crate::serialize::{SerializationError, ToBytes};
+use crate::server::error::ServerError;
+use crate::server::replication::send::{Replicate, ServerFilter, SyncTarget};
+use crate::shared::events::connection::ConnectionEvents;
+use crate::shared::replication::components::{ReplicationGroupId, ReplicationTarget};
+use crate::shared::replication::entity_map::{ReceiveEntityMap, SendEntityMap};
+use crate::shared::replication::network_target::NetworkTarget;
+
+/// The saved state of a single replicated entity
+#[derive(Debug, Clone, PartialEq)]
+struct EntitySnapshot {
+    entity: Entity,
+    group: ReplicationGroup,
+    target: NetworkTarget,
+    prediction: NetworkTarget,
+    interpolation: NetworkTarget,
+    /// The serialized components (including the net id prefix)
+    components: Vec<Bytes>,
+}
+
+impl ToBytes for EntitySnapshot {
+    fn len(&self) -> usize {
+        self.entity.len()
+            + self.group.len()
+            + self.target.len()
+            + self.prediction.len()
+            + self.interpolation.len()
+            + ToBytes::len(&self.components)
+    }
+
+    fn to_bytes<T: WriteBytesExt>(&self, buffer: &mut T) -> Result<(), SerializationError> {
+        self.entity.to_bytes(buffer)?;
+        self.group.to_bytes(buffer)?;
+        self.target.to_bytes(buffer)?;
+        self.prediction.to_bytes(buffer)?;
+        self.interpolation.to_bytes(buffer)?;
+        self.components.to_bytes(buffer)?;
+        Ok(())
+    }
+
+    fn from_bytes(buffer: &mut Reader) -> Result<Self, SerializationError>
+    where
+        Self: Sized,
+    {
+        Ok(Self {
+            entity: Entity::from_bytes(buffer)?,
+            group: ReplicationGroup::from_bytes(buffer)?,
+            target: NetworkTarget::from_bytes(buffer)?,
+            prediction: NetworkTarget::from_bytes(buffer)?,
+            interpolation: NetworkTarget::from_bytes(buffer)?,
+            components: Vec::from_bytes(buffer)?,
+        })
+    }
+}
+
+/// Extension trait to save and restore the replicated entities of the server [`World`]
+pub trait WorldSnapshotExt {
+    /// Serialize all the entities replicated by the server, and their replicated components
+    fn serialize_world_snapshot(&mut self) -> Result<Bytes, ServerError>;
+
+    /// Spawn the entities contained in a snapshot created by
+    /// [`serialize_world_snapshot`](WorldSnapshotExt::serialize_world_snapshot), and start replicating them.
+    ///
+    /// The entities are spawned with new ids: the entities referenced in the components and in the
+    /// replication groups are mapped to the new entities.
+    /// Returns the mapping from the entities in the snapshot to the newly spawned entities.
+    ///
+    /// If the snapshot cannot be loaded, the entities that were already spawned are despawned.
+    fn load_world_snapshot(
+        &mut self,
+        snapshot: Bytes,
+    ) -> Result<EntityHashMap<Entity>, ServerError>;
+}
+
+impl WorldSnapshotExt for World {
+    fn serialize_world_snapshot(&mut self) -> Result<Bytes, ServerError> {
+        let entities = self
+            .query_filtered::<(
+                Entity,
+                &ReplicationTarget,
+                Option<&ReplicationGroup>,
+                Option<&SyncTarget>,
+            ), ServerFilter>()
+            .iter(self)
+            .map(|(entity, target, group, sync)| {
+                (
+                    entity,
+                    target.target.clone(),
+                    group.cloned().unwrap_or_default(),
+                    sync.cloned().unwrap_or_default(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let registry = self.resource::<ComponentRegistry>();
+        let mut writer = Writer::default();
+        // the entities are saved without mapping, they are mapped when the snapshot is loaded
+        let mut send_entity_map = SendEntityMap::default();
+        let mut snapshots = Vec::with_capacity(entities.len());
+        for (entity, target, group, sync) in entities {
+            let entity_ref = self.entity(entity);
+            let archetype = entity_ref.archetype();
+            let mut components = vec![];
+            for component_id in archetype.components() {
+                let info = self.components().get_info(component_id).unwrap();
+                let Some(kind) = info.type_id().map(ComponentKind) else {
+                    continue;
+                };
+                let Some(replication_metadata) = registry.replication_map.get(&kind) else {
+                    continue;
+                };
+                // the component is not replicated for this entity
+                if archetype.contains(replication_metadata.disabled_id) {
+                    continue;
+                }
+                let component = entity_ref.get_by_id(component_id).unwrap();
+                registry.erased_serialize(
+                    component,
+                    &mut writer,
+                    kind,
+                    Some(&mut send_entity_map),
+                )?;
+                components.push(writer.split());
+            }
+            snapshots.push(EntitySnapshot {
+                entity,
+                group,
+                target,
+                prediction: sync.prediction,
+                interpolation: sync.interpolation,
+                components,
+            });
+        }
+        snapshots.to_bytes(&mut writer)?;
+        Ok(writer.split())
+    }
+
+    fn load_world_snapshot(
+        &mut self,
+        snapshot: Bytes,
+    ) -> Result<EntityHashMap<Entity>, ServerError> {
+        let mut reader = Reader::from(snapshot);
+        let snapshots = Vec::<EntitySnapshot>::from_bytes(&mut reader)?;
+        // spawn all the entities first, so that we can map the entities referenced in the components
+        let entity_map = snapshots
+            .iter()
+            .map(|snapshot| (snapshot.entity, self.spawn_empty().id()))
+            .collect::<EntityHashMap<Entity>>();
+        // the groups whose id is derived from an entity get a new id, so the other members of these
+        // groups must use the new id as well
+        let group_id_map = snapshots
+            .iter()
+            .filter(|snapshot| snapshot.group.is_from_entity())
+            .map(|snapshot| {
+                (
+                    ReplicationGroupId(snapshot.entity.to_bits()),
+                    ReplicationGroupId(entity_map[&snapshot.entity].to_bits()),
+                )
+            })
+            .collect::<HashMap<_, _>>();
+        let tick = self.resource::<TickManager>().tick();
+        let result = self.resource_scope(|world, registry: Mut<ComponentRegistry>| {
+            let mut receive_entity_map = ReceiveEntityMap(entity_map.clone());
+            // the events are not used: the components are replicated as newly inserted components
+            let mut events = ConnectionEvents::new();
+            for mut snapshot in snapshots {
+                let local_entity = *entity_map.get(&snapshot.entity).unwrap();
+                let mut entity_world_mut = world.entity_mut(local_entity);
+                for component in snapshot.components {
+                    registry.raw_write(
+                        &mut Reader::from(component),
+                        &mut entity_world_mut,
+                        tick,
+                        &mut receive_entity_map,
+                        &mut events,
+                    )?;
+                }
+                snapshot.group.map_group_id(&group_id_map);
+                entity_world_mut.insert(Replicate {
+                    target: ReplicationTarget {
+                        target: snapshot.target,
+                    },
+                    sync: SyncTarget {
+                        prediction: snapshot.prediction,
+                        interpolation: snapshot.interpolation,
+                    },
+                    group: snapshot.group,
+                    ..Default::default()
+                });
+            }
+            Ok::<(), ServerError>(())
+        });
+        if let Err(e) = result {
+            // don't leave partially loaded entities behind
+            for entity in entity_map.values() {
+                self.despawn(*entity);
+            }
+            return Err(e);
+        }
+        Ok(entity_map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::client;
+    use crate::tests::protocol::{ComponentMapEntities, ComponentSyncModeFull};
+    use crate::tests::stepper::BevyStepper;
+
+    /// Check that a snapshot of the replicated world can be restored, and that the restored entities
+    /// are replicated again
+    #[test]
+    fn test_world_snapshot() {
+        let mut stepper = BevyStepper::default();
+        let server_world = stepper.server_app.world_mut();
+        let entity_1 = server_world
+            .spawn((Replicate::default(), ComponentSyncModeFull(1.0)))
+            .id();
+        let entity_2 = server_world
+            .spawn((
+                Replicate {
+                    group: ReplicationGroup::new_id(entity_1.to_bits()),
+                    ..Default::default()
+                },
+                ComponentMapEntities(entity_1),
+            ))
+            .id();
+        // entities that are not replicated are not saved
+        server_world.spawn(ComponentSyncModeFull(3.0));
+        stepper.frame_step();
+        stepper.frame_step();
+
+        let snapshot = stepper
+            .server_app
+            .world_mut()
+            .serialize_world_snapshot()
+            .unwrap();
+
+        // clear the world
+        stepper.server_app.world_mut().despawn(entity_1);
+        stepper.server_app.world_mut().despawn(entity_2);
+        stepper.frame_step();
+        stepper.frame_step();
+
+        let entity_map = stepper
+            .server_app
+            .world_mut()
+            .load_world_snapshot(snapshot)
+            .unwrap();
+        assert_eq!(entity_map.len(), 2);
+        let new_entity_1 = *entity_map.get(&entity_1).unwrap();
+        let new_entity_2 = *entity_map.get(&entity_2).unwrap();
+        let server_world = stepper.server_app.world();
+        assert_eq!(
+            server_world.get::<ComponentSyncModeFull>(new_entity_1),
+            Some(&ComponentSyncModeFull(1.0))
+        );
+        // the entity references are mapped to the new entities
+        assert_eq!(
+            server_world.get::<ComponentMapEntities>(new_entity_2),
+            Some(&ComponentMapEntities(new_entity_1))
+        );
+        assert_eq!(
+            server_world
+                .get::<ReplicationGroup>(new_entity_2)
+                .unwrap()
+                .group_id(Some(new_entity_2)),
+            server_world
+                .get::<ReplicationGroup>(new_entity_1)
+                .unwrap()
+                .group_id(Some(new_entity_1)),
+        );
+
+        // the restored entities are replicated to the client
+        stepper.frame_step();
+        stepper.frame_step();
+        let client_entity_1 = stepper
+            .client_app
+            .world()
+            .resource::<client::ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(new_entity_1)
+            .expect("entity was not replicated to client");
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<ComponentSyncModeFull>(client_entity_1),
+            Some(&ComponentSyncModeFull(1.0))
+        );
+    }
+
+    #[test]
+    fn test_entity_snapshot_len() {
+        let snapshot = EntitySnapshot {
+            entity: Entity::from_raw(1),
+            group: ReplicationGroup::default(),
+            target: NetworkTarget::All,
+            prediction: NetworkTarget::None,
+            interpolation: NetworkTarget::None,
+            components: vec![
+                Bytes::from_static(&[1, 2, 3]),
+                Bytes::from_static(&[4; 200]),
+            ],
+        };
+        let mut writer = Writer::default();
+        snapshot.to_bytes(&mut writer).unwrap();
+        assert_eq!(snapshot.len(), writer.split().len());
+    }
+
+    /// Check that a snapshot that cannot be loaded doesn't leave any entity behind
+    #[test]
+    fn test_world_snapshot_load_error() {
+        let mut stepper = BevyStepper::default();
+        let server_world = stepper.server_app.world_mut();
+        let entity = server_world
+            .spawn((Replicate::default(), ComponentSyncModeFull(1.0)))
+            .id();
+        let snapshot = server_world.serialize_world_snapshot().unwrap();
+        let mut snapshots = Vec::<EntitySnapshot>::from_bytes(&mut Reader::from(snapshot)).unwrap();
+        // add an entity with a component that is not registered in the protocol
+        snapshots.push(EntitySnapshot {
+            entity: Entity::from_raw(1000),
+            components: vec![Bytes::from_static(&[200, 1, 2, 3])],
+            ..snapshots[0].clone()
+        });
+        let mut writer = Writer::default();
+        snapshots.to_bytes(&mut writer).unwrap();
+
+        server_world.despawn(entity);
+        let num_entities = server_world.entities().len();
+        assert!(server_world.load_world_snapshot(writer.split()).is_err());
+        assert_eq!(server_world.entities().len(), num_entities);
+        assert_eq!(
+            server_world
+                .query::<&ComponentSyncModeFull>()
+                .iter(server_world)
+                .count(),
+            0
+        );
+    }
+}
@@ -0,0 +1,311 @@
+//! Optional built-in text chat between clients
+//!
+//! The [`ChatPlugin`] must be added to the client and server apps (after the lightyear plugins, like
+//! your protocol), since it registers the [`ChatMessageChannel`] and the [`ChatMessage`] message.
+//!
+//! - clients send chat messages with [`ChatCommands::send_chat_message`]
+//! - the server sets the [`ChatMessage::sender`], runs the optional moderation hook, and relays the message
+//!   to the recipients. The sender also receives its own message, so that all clients see the messages in
+//!   the same order.
+//! - the relayed messages are emitted as a [`ChatMessage`] event on the clients
+use bevy::prelude::*;
+use bevy::utils::Duration;
+use lightyear_macros::ChannelInternal;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use crate::channel::builder::{ChannelMode, ChannelSettings, ReliableSettings};
+use crate::client::config::ClientConfig;
+use crate::client::connection::ConnectionManager as ClientConnectionManager;
+use crate::client::events::MessageEvent as ClientMessageEvent;
+use crate::connection::client::{ClientConnection, NetClient};
+use crate::prelude::{AppChannelExt, ChannelDirection, ChannelRegistry, ClientId, NetworkTarget};
+use crate::protocol::message::{AppMessageInternalExt, MessageType};
+use crate::server::config::ServerConfig;
+use crate::server::connection::ConnectionManager as ServerConnectionManager;
+use crate::server::events::MessageEvent as ServerMessageEvent;
+use crate::shared::sets::{ClientMarker, InternalMainSet, ServerMarker};
+
+#[derive(ChannelInternal)]
+/// Channel used to send the [`ChatMessage`]s
+/// This is an Ordered Reliable channel
+pub struct ChatMessageChannel;
+
+/// Who should receive a [`ChatMessage`]
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum ChatChannel {
+    /// All the connected clients
+    #[default]
+    All,
+    /// Only the specified client (and the sender)
+    Whisper(ClientId),
+}
+
+/// Chat message sent by a client and relayed by the server.
+///
+/// It is emitted as an [`Event`] on the clients.
+#[derive(Event, Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Reflect)]
+pub struct ChatMessage {
+    /// The client that sent the message.
+    ///
+    /// This is set by the server when relaying the message, so a client cannot impersonate another client.
+    pub sender: ClientId,
+    pub text: String,
+    pub channel: ChatChannel,
+}
+
+/// Function called by the server on every [`ChatMessage`] before it is relayed.
+///
+/// The message can be modified (for example to filter words); the message is dropped if the function returns false.
+pub type ChatModerationFn = fn(world: &World, message: &mut ChatMessage) -> bool;
+
+/// Plugin that adds the built-in chat
+#[derive(Default)]
+pub struct ChatPlugin {
+    /// Optional moderation hook, called by the server before relaying each message
+    pub moderation: Option<ChatModerationFn>,
+}
+
+impl ChatPlugin {
+    pub fn with_moderation(mut self, moderation: ChatModerationFn) -> Self {
+        self.moderation = Some(moderation);
+        self
+    }
+}
+
+#[derive(Resource)]
+struct ChatModeration(Option<ChatModerationFn>);
+
+impl Plugin for ChatPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<ChatChannel>()
+            .register_type::<ChatMessage>();
+        app.add_channel::<ChatMessageChannel>(ChannelSettings {
+            mode: ChannelMode::OrderedReliable(ReliableSettings::default()),
+            direction: ChannelDirection::Bidirectional,
+            send_frequency: Duration::default(),
+            priority: 1.0,
+        });
+        app.world_mut()
+            .resource_mut::<ChannelRegistry>()
+            .set_internal_channel::<ChatMessageChannel>();
+        app.register_message_internal::<ChatMessage>(
+            ChannelDirection::Bidirectional,
+            MessageType::Normal,
+        );
+
+        if app.world().get_resource::<ClientConfig>().is_some() {
+            app.add_event::<ChatMessage>();
+            app.add_systems(
+                PreUpdate,
+                emit_chat_messages.after(InternalMainSet::<ClientMarker>::EmitEvents),
+            );
+        }
+        if app.world().get_resource::<ServerConfig>().is_some() {
+            app.insert_resource(ChatModeration(self.moderation));
+            app.add_systems(
+                PreUpdate,
+                relay_chat_messages.after(InternalMainSet::<ServerMarker>::EmitEvents),
+            );
+        }
+    }
+}
+
+/// Extension trait to send chat messages from the client
+pub trait ChatCommands {
+    /// Send a [`ChatMessage`] to the server, which will relay it to the clients of the [`ChatChannel`]
+    fn send_chat_message(&mut self, text: String, channel: ChatChannel);
+}
+
+impl ChatCommands for Commands<'_, '_> {
+    fn send_chat_message(&mut self, text: String, channel: ChatChannel) {
+        self.add(move |world: &mut World| {
+            let Some(sender) = world
+                .get_resource::<ClientConnection>()
+                .map(|connection| connection.id())
+            else {
+                error!("Cannot send a chat message: the client is not connected");
+                return;
+            };
+            let _ = world
+                .resource_mut::<ClientConnectionManager>()
+                .send_message::<ChatMessageChannel, _>(&mut ChatMessage {
+                    sender,
+                    text,
+                    channel,
+                })
+                .inspect_err(|e| error!("Error sending chat message: {:?}", e));
+        });
+    }
+}
+
+/// Emit a [`ChatMessage`] event for every chat message relayed by the server
+fn emit_chat_messages(
+    mut messages: ResMut<Events<ClientMessageEvent<ChatMessage>>>,
+    mut chat_messages: EventWriter<ChatMessage>,
+) {
+    for message in messages.drain() {
+        chat_messages.send(message.message);
+    }
+}
+
+/// Clients that a chat message sent on `channel` by `sender` is relayed to
+fn relay_target(channel: ChatChannel, sender: ClientId) -> NetworkTarget {
+    match channel {
+        ChatChannel::All => NetworkTarget::All,
+        // the sender also receives its own whisper, but only once if it whispers to itself
+        ChatChannel::Whisper(recipient) if recipient == sender => NetworkTarget::Single(sender),
+        ChatChannel::Whisper(recipient) => NetworkTarget::Only(vec![recipient, sender]),
+    }
+}
+
+/// Moderate the chat messages received by the server and relay them to their recipients
+fn relay_chat_messages(world: &mut World) {
+    let messages: Vec<_> = world
+        .resource_mut::<Events<ServerMessageEvent<ChatMessage>>>()
+        .drain()
+        .collect();
+    let moderation = world.resource::<ChatModeration>().0;
+    for ServerMessageEvent {
+        mut message,
+        context: sender,
+    } in messages
+    {
+        message.sender = sender;
+        if moderation.is_some_and(|moderation| !moderation(world, &mut message)) {
+            continue;
+        }
+        let target = relay_target(message.channel, sender);
+        let _ = world
+            .resource_mut::<ServerConnectionManager>()
+            .send_message_to_target::<ChatMessageChannel, _>(&mut message, target)
+            .inspect_err(|e| error!("Error relaying chat message: {:?}", e));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::client::{InterpolationConfig, PredictionConfig, SyncConfig};
+    use crate::prelude::{SharedConfig, TickConfig};
+    use crate::tests::multi_stepper::{MultiBevyStepper, TEST_CLIENT_ID_1};
+
+    #[derive(Resource, Default)]
+    struct ReceivedChatMessages(Vec<ChatMessage>);
+
+    fn receive_chat_messages(
+        mut received: ResMut<ReceivedChatMessages>,
+        mut chat_messages: EventReader<ChatMessage>,
+    ) {
+        received.0.extend(chat_messages.read().cloned());
+    }
+
+    fn no_swearing(_: &World, message: &mut ChatMessage) -> bool {
+        !message.text.contains("heck")
+    }
+
+    fn setup() -> MultiBevyStepper {
+        let frame_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(frame_duration),
+            ..Default::default()
+        };
+        let mut stepper = MultiBevyStepper::new(
+            shared_config,
+            SyncConfig::default().speedup_factor(1.0),
+            PredictionConfig::default(),
+            InterpolationConfig::default(),
+            frame_duration,
+        );
+        stepper
+            .server_app
+            .add_plugins(ChatPlugin::default().with_moderation(no_swearing));
+        for client_app in [&mut stepper.client_app_1, &mut stepper.client_app_2] {
+            client_app.add_plugins(ChatPlugin::default());
+            client_app.init_resource::<ReceivedChatMessages>();
+            client_app.add_systems(Update, receive_chat_messages);
+        }
+        stepper.init();
+        stepper
+    }
+
+    /// Check that a chat message sent by a client is relayed to the other client with the correct sender
+    #[test]
+    fn test_chat_message_relay() {
+        let mut stepper = setup();
+        for text in ["heck", "hello"] {
+            stepper
+                .client_app_1
+                .world_mut()
+                .commands()
+                .send_chat_message(text.to_string(), ChatChannel::All);
+        }
+        stepper.client_app_1.world_mut().flush();
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+
+        let expected = vec![ChatMessage {
+            sender: ClientId::Netcode(TEST_CLIENT_ID_1),
+            text: "hello".to_string(),
+            channel: ChatChannel::All,
+        }];
+        // the moderated message was dropped
+        assert_eq!(
+            stepper
+                .client_app_2
+                .world()
+                .resource::<ReceivedChatMessages>()
+                .0,
+            expected
+        );
+        assert_eq!(
+            stepper
+                .client_app_1
+                .world()
+                .resource::<ReceivedChatMessages>()
+                .0,
+            expected
+        );
+    }
+
+    /// Check that a client that whispers to itself is targeted once, and receives its message once
+    #[test]
+    fn test_whisper_to_self() {
+        let sender = ClientId::Netcode(TEST_CLIENT_ID_1);
+        assert_eq!(
+            relay_target(ChatChannel::Whisper(sender), sender),
+            NetworkTarget::Single(sender)
+        );
+
+        let mut stepper = setup();
+        stepper
+            .client_app_1
+            .world_mut()
+            .commands()
+            .send_chat_message("hello".to_string(), ChatChannel::Whisper(sender));
+        stepper.client_app_1.world_mut().flush();
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+
+        assert_eq!(
+            stepper
+                .client_app_1
+                .world()
+                .resource::<ReceivedChatMessages>()
+                .0,
+            vec![ChatMessage {
+                sender,
+                text: "hello".to_string(),
+                channel: ChatChannel::Whisper(sender),
+            }]
+        );
+        assert!(stepper
+            .client_app_2
+            .world()
+            .resource::<ReceivedChatMessages>()
+            .0
+            .is_empty());
+    }
+}
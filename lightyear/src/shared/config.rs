@@ -2,6 +2,7 @@
 use bevy::reflect::Reflect;
 use bevy::utils::Duration;
 
+use crate::connection::netcode::MAX_PACKET_SIZE;
 use crate::shared::tick_manager::TickConfig;
 
 /// Configuration that has to be the same between the server and the client.
@@ -13,6 +14,15 @@ pub struct SharedConfig {
     /// configuration for the [`FixedUpdate`](bevy::prelude::FixedUpdate) schedule
     pub tick: TickConfig,
     pub mode: Mode,
+    /// Maximum number of bytes in a lightyear packet. Messages that don't fit in a single packet are
+    /// split into fragments.
+    ///
+    /// This is not the transport MTU: the netcode header (up to 9 bytes) and MAC (16 bytes) are added
+    /// on top of it, as well as the headers of the transport itself (for example 28 bytes for UDP over IPv4).
+    /// Lower this if the transport has a smaller effective MTU (for example some VPN tunnels).
+    ///
+    /// It must be between 128 and [`MAX_PACKET_SIZE`], and the client and server must use the same value.
+    pub mtu: usize,
 }
 
 // TODO: maybe the modes should just be
@@ -42,6 +52,7 @@ impl Default for SharedConfig {
             server_replication_send_interval: Duration::from_millis(0),
             tick: TickConfig::new(Duration::from_millis(16)),
             mode: Mode::default(),
+            mtu: MAX_PACKET_SIZE,
         }
     }
 }
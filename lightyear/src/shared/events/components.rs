@@ -5,6 +5,8 @@ use std::marker::PhantomData;
 use bevy::prelude::{Component, Entity, Event};
 
 use crate::packet::message::Message;
+use crate::prelude::Tick;
+use crate::shared::replication::components::ReplicationGroupId;
 
 /// This event is emitted whenever we receive a message from the remote
 #[derive(Event, Debug)]
@@ -90,6 +92,41 @@ impl<Ctx> EntityDespawnEvent<Ctx> {
     }
 }
 
+/// Event emitted whenever the remote acknowledges an update message that we sent for a
+/// [`ReplicationGroup`](crate::prelude::ReplicationGroup)
+///
+/// The event is also triggered, so you can react to it with an observer.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct GroupAckEvent<Ctx = ()> {
+    group_id: ReplicationGroupId,
+    tick: Tick,
+    context: Ctx,
+}
+
+impl<Ctx> GroupAckEvent<Ctx> {
+    pub fn new(group_id: ReplicationGroupId, tick: Tick, context: Ctx) -> Self {
+        Self {
+            group_id,
+            tick,
+            context,
+        }
+    }
+
+    /// The replication group whose update message was acked
+    pub fn group_id(&self) -> ReplicationGroupId {
+        self.group_id
+    }
+
+    /// The tick at which the acked update message was sent
+    pub fn tick(&self) -> Tick {
+        self.tick
+    }
+
+    pub fn context(&self) -> &Ctx {
+        &self.context
+    }
+}
+
 /// Event emitted whenever we update a component from the remote world
 #[derive(Event)]
 pub struct ComponentUpdateEvent<C: Component, Ctx = ()> {
@@ -43,8 +43,13 @@ impl<A: LeafwingUserAction> Plugin for LeafwingInputPlugin<A> {
         // - so that the server entity has an ActionState on the server when the ActionState is added on the client
         //   (we only replicate it once when ActionState is first added)
         // - we don't need to replicate from server->client because we will add ActionState on any entity
-        //   where the client adds an InputMap
-        app.register_component::<ActionState<A>>(ChannelDirection::ClientToServer);
+        //   where the client adds an InputMap, unless the server's ActionState is replicated to spectators
+        let direction = if self.config.replicate_action_state {
+            ChannelDirection::Bidirectional
+        } else {
+            ChannelDirection::ClientToServer
+        };
+        app.register_component::<ActionState<A>>(direction);
         let is_client = app.world().get_resource::<ClientConfig>().is_some();
         let is_server = app.world().get_resource::<ServerConfig>().is_some();
         if is_client {
@@ -53,7 +58,11 @@ impl<A: LeafwingUserAction> Plugin for LeafwingInputPlugin<A> {
             );
         }
         if is_server {
-            app.add_plugins(crate::server::input::leafwing::LeafwingInputPlugin::<A>::default());
+            app.add_plugins(
+                crate::server::input::leafwing::LeafwingInputPlugin::<A>::new(
+                    self.config.replicate_action_state,
+                ),
+            );
         }
     }
 }
@@ -1,4 +1,30 @@
+use bevy::prelude::Reflect;
+use serde::{Deserialize, Serialize};
+
 pub mod native;
 
 #[cfg(feature = "leafwing")]
 pub mod leafwing;
+
+/// Message sent by the server to override the input delay of a client
+/// (see [`ServerCommands::set_input_delay`](crate::server::networking::ServerCommands::set_input_delay))
+///
+/// This can be used in competitive games to equalize the effective input delay across clients.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub struct InputDelayCommand {
+    /// Number of ticks of input delay that the client must use.
+    /// If `None`, the client goes back to computing its input delay from its [`PredictionConfig`](crate::client::prediction::plugin::PredictionConfig)
+    pub input_delay_ticks: Option<u16>,
+}
+
+/// Message sent by the client to tell the server how far in the past the client sees the interpolated
+/// entities, so that the server can rewind them for lag compensation
+/// (see [`LagCompensation`](crate::server::lag_compensation::LagCompensation))
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Reflect)]
+pub struct ViewDelay {
+    /// Number of ticks of input delay that the client applies
+    pub input_delay_ticks: u16,
+    /// Number of ticks (including the fractional part) between the client's current tick and
+    /// the interpolation time used to display the interpolated entities
+    pub interpolation_delay_ticks: f32,
+}
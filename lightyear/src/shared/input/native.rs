@@ -3,9 +3,9 @@
 use bevy::app::{App, Plugin};
 
 use crate::client::config::ClientConfig;
-use crate::inputs::native::InputMessage;
-use crate::prelude::{MessageRegistry, UserAction};
-use crate::protocol::message::MessageType;
+use crate::inputs::native::{InputMessage, InputNack, RemoteInputMessage};
+use crate::prelude::{ChannelDirection, MessageRegistry, UserAction};
+use crate::protocol::message::{AppMessageInternalExt, MessageType};
 use crate::server::config::ServerConfig;
 
 pub struct InputPlugin<A> {
@@ -27,6 +27,14 @@ impl<A: UserAction> Plugin for InputPlugin<A> {
         app.world_mut()
             .resource_mut::<MessageRegistry>()
             .add_message::<InputMessage<A>>(MessageType::NativeInput);
+        app.register_message_internal::<InputNack<A>>(
+            ChannelDirection::ServerToClient,
+            MessageType::Normal,
+        );
+        app.register_message_internal::<RemoteInputMessage<A>>(
+            ChannelDirection::ServerToClient,
+            MessageType::Normal,
+        );
         let is_client = app.world().get_resource::<ClientConfig>().is_some();
         let is_server = app.world().get_resource::<ServerConfig>().is_some();
         if is_client {
@@ -1,11 +1,15 @@
 //! Shared code between the server and client.
 
+pub mod chat;
+
 pub mod config;
 
 pub mod events;
 
 pub mod log;
 
+pub mod notification;
+
 pub mod ping;
 
 pub mod plugin;
@@ -14,6 +18,10 @@ pub mod replication;
 
 pub mod sets;
 
+pub mod simulation_lag;
+
+pub mod stream;
+
 pub mod tick_manager;
 
 pub mod input;
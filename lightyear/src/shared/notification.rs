@@ -0,0 +1,72 @@
+//! Built-in notifications that the server can broadcast to all connected clients
+//!
+//! This can be used for announcements, shutdown warnings, etc.
+//! The server sends them via [`ServerCommands::broadcast_notification`](crate::server::networking::ServerCommands::broadcast_notification)
+//! and they are emitted as a [`ServerNotification`] event on the client.
+use bevy::prelude::{Event, Reflect};
+use serde::{Deserialize, Serialize};
+
+/// How important a [`ServerNotification`] is
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum Severity {
+    #[default]
+    Info,
+    Warning,
+    Error,
+}
+
+/// Notification broadcast by the server to all connected clients.
+///
+/// It is sent reliably and is emitted as an [`Event`] on the client.
+#[derive(Event, Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Reflect)]
+pub struct ServerNotification {
+    pub text: String,
+    pub severity: Severity,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::server::ServerCommands;
+    use crate::tests::multi_stepper::MultiBevyStepper;
+    use bevy::prelude::{EventReader, ResMut, Resource, Update};
+
+    #[derive(Resource, Default)]
+    struct ReceivedNotifications(Vec<ServerNotification>);
+
+    fn receive_notifications(
+        mut received: ResMut<ReceivedNotifications>,
+        mut notifications: EventReader<ServerNotification>,
+    ) {
+        received.0.extend(notifications.read().cloned());
+    }
+
+    #[test]
+    fn test_broadcast_notification() {
+        let mut stepper = MultiBevyStepper::default();
+        for client_app in [&mut stepper.client_app_1, &mut stepper.client_app_2] {
+            client_app.init_resource::<ReceivedNotifications>();
+            client_app.add_systems(Update, receive_notifications);
+        }
+
+        stepper
+            .server_app
+            .world_mut()
+            .commands()
+            .broadcast_notification("Server shutting down".to_string(), Severity::Warning);
+        stepper.server_app.world_mut().flush();
+        stepper.frame_step();
+        stepper.frame_step();
+
+        let expected = ServerNotification {
+            text: "Server shutting down".to_string(),
+            severity: Severity::Warning,
+        };
+        for client_app in [&stepper.client_app_1, &stepper.client_app_2] {
+            assert_eq!(
+                client_app.world().resource::<ReceivedNotifications>().0,
+                vec![expected.clone()]
+            );
+        }
+    }
+}
@@ -13,6 +13,19 @@ use crate::utils::ready_buffer::ReadyBuffer;
 /// to compute network statistics (RTT, jitter)
 #[derive(Clone, Copy, Debug, Reflect)]
 pub struct PingConfig {
+    /// If false, no pings are sent to the remote host.
+    ///
+    /// This is useful if the transport already measures the RTT (for example Steam), or if the RTT is not needed.
+    /// The RTT and jitter estimates must then be provided externally: they are read from the transport if it
+    /// reports them, or they can be set manually with `set_rtt` on the `ConnectionManager`.
+    /// Since the server ticks are then only received with the other server packets (replication, messages),
+    /// the client only syncs once it received a packet from the server.
+    /// Without pongs, the client gets the tick generation of the server from the
+    /// [`ServerWideTick`](crate::shared::tick_manager::ServerWideTick) message sent when it connects,
+    /// and then counts the wraps of the server ticks it receives.
+    /// Note that the [`PingChannel`](crate::channel::builder::PingChannel) stays registered, so that the protocol
+    /// is the same whether or not pings are enabled.
+    pub enabled: bool,
     /// The duration to wait before sending a ping message to the remote host,
     /// in order to estimate RTT time
     pub ping_interval: Duration,
@@ -24,6 +37,7 @@ pub struct PingConfig {
 impl Default for PingConfig {
     fn default() -> Self {
         PingConfig {
+            enabled: true,
             ping_interval: Duration::from_millis(100),
             stats_buffer_duration: Duration::from_secs(4),
         }
@@ -55,10 +69,12 @@ pub struct PingManager {
     pub(crate) pings_sent: u32,
     /// The number of pongs we have received
     pub(crate) pongs_recv: u32,
+    /// True if the stats were provided externally instead of being computed from pongs
+    external_stats: bool,
 }
 
 /// Connection stats aggregated over several [`SyncStats`]
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Reflect)]
 pub struct FinalStats {
     pub rtt: Duration,
     pub jitter: Duration,
@@ -96,6 +112,7 @@ impl PingManager {
             final_stats: FinalStats::default(),
             pings_sent: 0,
             pongs_recv: 0,
+            external_stats: false,
         }
     }
 
@@ -109,6 +126,36 @@ impl PingManager {
         self.final_stats.jitter
     }
 
+    /// Returns true if pings are sent to the remote to compute the stats
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Returns true if we received at least one pong, or if the stats were provided externally,
+    /// i.e. if the rtt and jitter estimates are based on actual measurements
+    pub(crate) fn has_stats(&self) -> bool {
+        self.pongs_recv > 0 || self.external_stats
+    }
+
+    /// Set the rtt and jitter estimates from an external source (for example the transport),
+    /// instead of computing them from the pongs
+    pub(crate) fn set_external_stats(&mut self, rtt: Duration, jitter: Duration) {
+        self.final_stats = FinalStats { rtt, jitter };
+        self.external_stats = true;
+    }
+
+    /// Return the number of pong samples currently used to compute the stats
+    pub fn sample_count(&self) -> usize {
+        self.sync_stats.len()
+    }
+
+    /// Discard the samples collected so far, and go back to the initial estimates of rtt and jitter
+    pub(crate) fn reset_stats(&mut self) {
+        self.sync_stats = SyncStatsBuffer::new();
+        self.final_stats = FinalStats::default();
+        self.external_stats = false;
+    }
+
     /// Update the ping manager after a delta update
     pub(crate) fn update(&mut self, time_manager: &TimeManager) {
         self.ping_timer.tick(time_manager.delta());
@@ -134,6 +181,9 @@ impl PingManager {
 
     /// Check if we are ready to send a ping to the remote
     pub(crate) fn maybe_prepare_ping(&mut self, time_manager: &TimeManager) -> Option<Ping> {
+        if !self.config.enabled {
+            return None;
+        }
         // TODO: should we have something to start sending a sync ping right away? (so we don't wait for initial timer)
         if self.ping_timer.elapsed() >= self.config.ping_interval {
             self.ping_timer.reset();
@@ -280,6 +330,7 @@ mod tests {
     #[test]
     fn test_send_pings() {
         let config = PingConfig {
+            enabled: true,
             ping_interval: Duration::from_millis(100),
             stats_buffer_duration: Duration::from_secs(4),
         };
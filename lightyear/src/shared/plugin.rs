@@ -1,20 +1,25 @@
 //! Bevy [`Plugin`] used by both the server and the client
 use crate::client::config::ClientConfig;
+use crate::connection::netcode::MAX_PACKET_SIZE;
 use crate::connection::server::ServerConnections;
 use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use bevy::utils::Duration;
 
+use crate::packet::packet::MIN_MTU;
 use crate::prelude::client::ComponentSyncMode;
 use crate::prelude::{
-    AppComponentExt, AppMessageExt, ChannelDirection, ChannelRegistry, ComponentRegistry,
-    LinkConditionerConfig, MessageRegistry, Mode, ParentSync, PingConfig, PrePredicted,
-    PreSpawnedPlayerObject, ShouldBePredicted, TickConfig,
+    AppComponentExt, ChannelDirection, ChannelRegistry, ComponentRegistry, LinkConditionerConfig,
+    MessageRegistry, Mode, ParentSync, PingConfig, PrePredicted, PreSpawnedPlayerObject,
+    ShouldBePredicted, TickConfig,
 };
+use crate::protocol::message::{AppMessageInternalExt, MessageType};
 use crate::shared::config::SharedConfig;
+use crate::shared::input::{InputDelayCommand, ViewDelay};
+use crate::shared::notification::{ServerNotification, Severity};
 use crate::shared::replication::authority::AuthorityChange;
 use crate::shared::replication::components::{Controlled, ShouldBeInterpolated};
-use crate::shared::tick_manager::TickManagerPlugin;
+use crate::shared::tick_manager::{ServerWideTick, TickManagerPlugin};
 use crate::shared::time_manager::TimePlugin;
 use crate::transport::io::{IoState, IoStats};
 use crate::transport::middleware::compression::CompressionConfig;
@@ -95,6 +100,11 @@ impl<'w, 's> NetworkIdentity<'w, 's> {
 
 impl Plugin for SharedPlugin {
     fn build(&self, app: &mut App) {
+        assert!(
+            (MIN_MTU..=MAX_PACKET_SIZE).contains(&self.config.mtu),
+            "SharedConfig::mtu must be between {MIN_MTU} and {MAX_PACKET_SIZE}, got {}",
+            self.config.mtu
+        );
         // REFLECTION
         app.register_type::<Mode>()
             .register_type::<SharedConfig>()
@@ -103,7 +113,12 @@ impl Plugin for SharedPlugin {
             .register_type::<IoStats>()
             .register_type::<IoState>()
             .register_type::<LinkConditionerConfig>()
-            .register_type::<CompressionConfig>();
+            .register_type::<CompressionConfig>()
+            .register_type::<Severity>()
+            .register_type::<ServerNotification>()
+            .register_type::<InputDelayCommand>()
+            .register_type::<ViewDelay>()
+            .register_type::<ServerWideTick>();
 
         // PLUGINS
         #[cfg(feature = "avian2d")]
@@ -121,7 +136,12 @@ impl Plugin for SharedPlugin {
                 // on the server (when rebroadcasting inputs), send inputs every frame
                 Duration::default()
             };
-        app.insert_resource(ChannelRegistry::new(input_send_interval));
+        // plugins added before the lightyear plugins might have already registered their own channels
+        if let Some(mut registry) = app.world_mut().get_resource_mut::<ChannelRegistry>() {
+            registry.add_default_channels(input_send_interval);
+        } else {
+            app.insert_resource(ChannelRegistry::new(input_send_interval));
+        }
         app.insert_resource(ComponentRegistry::default());
         app.insert_resource(MessageRegistry::default());
         // NOTE: this tick duration must be the same as any previous existing fixed timesteps
@@ -154,10 +174,38 @@ impl Plugin for SharedPlugin {
             .add_prediction(ComponentSyncMode::Once)
             .add_interpolation(ComponentSyncMode::Once);
 
-        app.register_message::<AuthorityChange>(ChannelDirection::ServerToClient)
-            .add_map_entities();
+        // internal messages are sent on internal channels, so they are not checked against the user channels
+        app.register_message_internal::<AuthorityChange>(
+            ChannelDirection::ServerToClient,
+            MessageType::Normal,
+        )
+        .add_map_entities();
+        app.register_message_internal::<ServerNotification>(
+            ChannelDirection::ServerToClient,
+            MessageType::Normal,
+        );
+        app.register_message_internal::<InputDelayCommand>(
+            ChannelDirection::ServerToClient,
+            MessageType::Normal,
+        );
+        app.register_message_internal::<ViewDelay>(
+            ChannelDirection::ClientToServer,
+            MessageType::Normal,
+        );
+        app.register_message_internal::<ServerWideTick>(
+            ChannelDirection::ServerToClient,
+            MessageType::Normal,
+        );
 
         // check that the protocol was built correctly
         app.world().resource::<ComponentRegistry>().check();
+        // the messages can still be sent on the bidirectional DefaultOrderedReliableChannel, so this is not an error
+        if let Err(e) = app
+            .world()
+            .resource::<MessageRegistry>()
+            .check(app.world().resource::<ChannelRegistry>())
+        {
+            warn!("{e}. These messages can only be sent on the DefaultOrderedReliableChannel");
+        }
     }
 }
@@ -94,6 +94,7 @@ pub(crate) struct ReplicatedArchetype {
 pub(crate) struct ReplicatedComponent {
     pub(crate) delta_compression: bool,
     pub(crate) replicate_once: bool,
+    pub(crate) owner_only: bool,
     pub(crate) override_target: Option<ComponentId>,
     pub(crate) id: ComponentId,
     pub(crate) kind: ComponentKind,
@@ -198,6 +199,7 @@ impl<C: Component> ReplicatedArchetypes<C> {
                     replicated_archetype.components.push(ReplicatedComponent {
                         delta_compression,
                         replicate_once,
+                        owner_only: replication_metadata.owner_only,
                         override_target,
                         id: component,
                         kind,
@@ -2,6 +2,7 @@
 use bevy::ecs::reflect::ReflectComponent;
 use bevy::prelude::{Component, Entity, Reflect};
 use bevy::time::{Timer, TimerMode};
+use bevy::utils::HashMap;
 use byteorder::{NetworkEndian, ReadBytesExt, WriteBytesExt};
 use serde::{Deserialize, Serialize};
 
@@ -91,14 +92,34 @@ pub struct ReplicateHierarchy {
     /// If false, you can still replicate hierarchies, but in a more fine-grained manner. You will have to add the `Replicate`
     /// and `ParentSync` components to the children yourself
     pub recursive: bool,
+    /// If true, despawning this entity also despawns all its descendants, so that the replicated descendants
+    /// are despawned on the remote peers right after this entity (even if they are in a different
+    /// [`ReplicationGroup`](crate::prelude::ReplicationGroup)).
+    ///
+    /// If false, despawning the entity with [`despawn`](bevy::ecs::world::EntityWorldMut::despawn)
+    /// leaves its descendants untouched.
+    pub despawn_descendants: bool,
 }
 
 impl Default for ReplicateHierarchy {
     fn default() -> Self {
-        Self { recursive: true }
+        Self {
+            recursive: true,
+            despawn_descendants: false,
+        }
     }
 }
 
+/// Marker component to add on the root of a hierarchy to replicate the entire hierarchy as a single unit.
+///
+/// All the descendants of the root are added to the root's [`ReplicationGroup`] when they start being replicated,
+/// even if they were spawned with their own `Replicate` bundle. The spawns and inserts for the whole subtree
+/// are then sent in a single message and applied in the same frame on the remote, so the hierarchy is
+/// never partially spawned.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct AtomicHierarchy;
+
 // TODO: do we need this? or do we just check if delta compression fn is present in the registry?
 /// If this component is present, the component will be replicated via delta-compression.
 ///
@@ -182,6 +203,14 @@ pub enum ReplicationGroupIdBuilder {
     // TODO: how can i generate one that doesn't conflict with an existing entity? maybe take u32 as input, and apply generation = u32::MAX - 1?
     //  or reserver some entities on the sender world?
     Group(u64),
+    // bucket the entity into one of `num_buckets` groups based on a user-provided key
+    // (for example the index of the spatial cell that the entity is in).
+    // The resulting group id is always lower than `u32::MAX`, so it cannot collide with the
+    // group id of an entity (entity generations start at 1, so the high bits of `Entity::to_bits` are never 0)
+    Bucket {
+        key: u64,
+        num_buckets: u32,
+    },
 }
 
 /// Component to specify the replication group of an entity
@@ -243,12 +272,37 @@ impl ReplicationGroup {
         }
     }
 
+    /// Put the entity in one of `num_buckets` shared groups, chosen according to the `key`.
+    ///
+    /// By default every entity is in its own replication group, which means that the sender keeps
+    /// track of one group channel per entity. With thousands of entities, bucketing many entities
+    /// together (for example by spatial cell, or by any other user key) reduces the number of groups
+    /// and the per-group bookkeeping.
+    ///
+    /// The tradeoff is that acks, priority and send frequency are tracked per group: with coarser
+    /// groups, a single change to an entity causes the group's updates to be sent together, and
+    /// priority accumulates for the whole bucket instead of for each entity.
+    ///
+    /// Note that the bucket ids are in the range `0..num_buckets`, so they can collide with group ids
+    /// that are manually specified via [`ReplicationGroup::new_id`].
+    pub const fn new_bucket(key: u64, num_buckets: u32) -> Self {
+        Self {
+            id_builder: ReplicationGroupIdBuilder::Bucket { key, num_buckets },
+            base_priority: 1.0,
+            send_frequency: None,
+            should_send: true,
+        }
+    }
+
     pub(crate) fn group_id(&self, entity: Option<Entity>) -> ReplicationGroupId {
         match self.id_builder {
             ReplicationGroupIdBuilder::FromEntity => {
                 ReplicationGroupId(entity.expect("need to provide an entity").to_bits())
             }
             ReplicationGroupIdBuilder::Group(id) => ReplicationGroupId(id),
+            ReplicationGroupIdBuilder::Bucket { key, num_buckets } => {
+                ReplicationGroupId(key % num_buckets.max(1) as u64)
+            }
         }
     }
 
@@ -266,6 +320,11 @@ impl ReplicationGroup {
         self
     }
 
+    /// Use the [`ReplicationGroupKey`] as the group id
+    pub(crate) fn set_key(&mut self, key: ReplicationGroupKey) {
+        self.id_builder = ReplicationGroupIdBuilder::Group(key.0);
+    }
+
     /// Sets the send frequency for this [`ReplicationGroup`]
     ///
     /// Any replication updates related to this group will only be buffered at the specified frequency.
@@ -280,6 +339,98 @@ impl ReplicationGroup {
     }
 }
 
+/// Component to put all the entities that share the same key in the same [`ReplicationGroup`].
+///
+/// The group id of the entity's [`ReplicationGroup`] is set to the key, and is updated whenever the key changes.
+/// When the key of an entity that is already replicated changes, the entity is migrated to the new group on the
+/// remote: the entity is not despawned, but it is now updated together with the other entities of its new group.
+///
+/// Note that the keys share the same space as the ids set with [`ReplicationGroup::new_id`].
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Hash, Reflect)]
+#[reflect(Component)]
+pub struct ReplicationGroupKey(pub u64);
+
+/// Only the group id, the priority and the send frequency are serialized.
+/// (the group is used to save the state of the replicated world, see [`WorldSnapshotExt`](crate::server::snapshot::WorldSnapshotExt))
+impl ToBytes for ReplicationGroup {
+    fn len(&self) -> usize {
+        let id_len = match self.id_builder {
+            ReplicationGroupIdBuilder::FromEntity => 1,
+            ReplicationGroupIdBuilder::Group(_) => 9,
+            ReplicationGroupIdBuilder::Bucket { .. } => 13,
+        };
+        id_len + 4 + 1 + self.send_frequency.as_ref().map_or(0, |_| 8)
+    }
+
+    fn to_bytes<T: WriteBytesExt>(&self, buffer: &mut T) -> Result<(), SerializationError> {
+        match self.id_builder {
+            ReplicationGroupIdBuilder::FromEntity => buffer.write_u8(0)?,
+            ReplicationGroupIdBuilder::Group(id) => {
+                buffer.write_u8(1)?;
+                buffer.write_u64::<NetworkEndian>(id)?;
+            }
+            ReplicationGroupIdBuilder::Bucket { key, num_buckets } => {
+                buffer.write_u8(2)?;
+                buffer.write_u64::<NetworkEndian>(key)?;
+                buffer.write_u32::<NetworkEndian>(num_buckets)?;
+            }
+        }
+        buffer.write_f32::<NetworkEndian>(self.base_priority)?;
+        match &self.send_frequency {
+            Some(timer) => {
+                buffer.write_u8(1)?;
+                buffer.write_u64::<NetworkEndian>(timer.duration().as_nanos() as u64)?;
+            }
+            None => buffer.write_u8(0)?,
+        }
+        Ok(())
+    }
+
+    fn from_bytes(buffer: &mut Reader) -> Result<Self, SerializationError>
+    where
+        Self: Sized,
+    {
+        let id_builder = match buffer.read_u8()? {
+            0 => ReplicationGroupIdBuilder::FromEntity,
+            1 => ReplicationGroupIdBuilder::Group(buffer.read_u64::<NetworkEndian>()?),
+            2 => ReplicationGroupIdBuilder::Bucket {
+                key: buffer.read_u64::<NetworkEndian>()?,
+                num_buckets: buffer.read_u32::<NetworkEndian>()?,
+            },
+            _ => return Err(SerializationError::InvalidValue),
+        };
+        let mut group = ReplicationGroup {
+            id_builder,
+            base_priority: buffer.read_f32::<NetworkEndian>()?,
+            ..Default::default()
+        };
+        if buffer.read_u8()? != 0 {
+            let nanos = buffer.read_u64::<NetworkEndian>()?;
+            group = group.set_send_frequency(bevy::utils::Duration::from_nanos(nanos));
+        }
+        Ok(group)
+    }
+}
+
+impl ReplicationGroup {
+    /// Returns true if the group id is the id of the entity the group is attached to
+    pub(crate) fn is_from_entity(&self) -> bool {
+        matches!(self.id_builder, ReplicationGroupIdBuilder::FromEntity)
+    }
+
+    /// If the group was given an explicit group id that is present in `group_id_map`, replace it with the mapped id
+    pub(crate) fn map_group_id(
+        &mut self,
+        group_id_map: &HashMap<ReplicationGroupId, ReplicationGroupId>,
+    ) {
+        if let ReplicationGroupIdBuilder::Group(id) = self.id_builder {
+            if let Some(mapped) = group_id_map.get(&ReplicationGroupId(id)) {
+                self.id_builder = ReplicationGroupIdBuilder::Group(mapped.0);
+            }
+        }
+    }
+}
+
 #[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, Reflect)]
 pub struct ReplicationGroupId(pub u64);
 
@@ -40,6 +40,57 @@ pub struct DeltaMessage<M> {
 /// - your component contains a hashmap, and your delta is `Add(key, value)` and `Remove(key)`
 /// - your component is a struct with multiple fields, and your delta only contains data for the fields that changed.
 ///   (to avoid sending the full struct every time over the network)
+///
+/// The `Delta` is what gets serialized on the wire, so implementing the trait manually lets a component
+/// define its own minimal diff. For example, for a `Transform`-like component where mostly the translation
+/// changes, only the fields that changed are sent:
+/// ```rust,ignore
+/// #[derive(Component, Serialize, Deserialize, Clone, PartialEq)]
+/// struct MyTransform {
+///     translation: Vec3,
+///     rotation: Quat,
+///     scale: Vec3,
+/// }
+///
+/// #[derive(Serialize, Deserialize, Clone, PartialEq)]
+/// struct MyTransformDelta {
+///     translation: Option<Vec3>,
+///     rotation: Option<Quat>,
+///     scale: Option<Vec3>,
+/// }
+///
+/// impl Diffable for MyTransform {
+///     type Delta = MyTransformDelta;
+///
+///     fn base_value() -> Self {
+///         Self { translation: Vec3::ZERO, rotation: Quat::IDENTITY, scale: Vec3::ONE }
+///     }
+///
+///     fn diff(&self, new: &Self) -> Self::Delta {
+///         MyTransformDelta {
+///             translation: (self.translation != new.translation).then_some(new.translation),
+///             rotation: (self.rotation != new.rotation).then_some(new.rotation),
+///             scale: (self.scale != new.scale).then_some(new.scale),
+///         }
+///     }
+///
+///     fn apply_diff(&mut self, delta: &Self::Delta) {
+///         if let Some(translation) = delta.translation {
+///             self.translation = translation;
+///         }
+///         if let Some(rotation) = delta.rotation {
+///             self.rotation = rotation;
+///         }
+///         if let Some(scale) = delta.scale {
+///             self.scale = scale;
+///         }
+///     }
+/// }
+///
+/// // register the component with delta compression enabled
+/// app.register_component::<MyTransform>(ChannelDirection::ServerToClient)
+///     .add_delta_compression();
+/// ```
 pub trait Diffable: Clone {
     // /// Set to true if the Deltas are idempotent (applying the same delta multiple times has no effect)
     // const IDEMPOTENT: bool;
@@ -57,6 +108,19 @@ pub trait Diffable: Clone {
     fn apply_diff(&mut self, delta: &Self::Delta);
 }
 
+/// Number of delta-compressed updates that were sent to a remote peer for a given component.
+///
+/// Delta compression only saves bandwidth if the updates can be computed from a value that the remote
+/// has acked. If `full_diffs` keeps increasing, the remote never acks the updates (for example because
+/// of packet loss) and every update is a diff against the [`Diffable::base_value`].
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeltaCompressionStats {
+    /// Number of updates that were computed from a value acked by the remote
+    pub incremental_diffs: u32,
+    /// Number of updates that were computed from the base value, because no acked value was available
+    pub full_diffs: u32,
+}
+
 /// Store a history of past delta-component values so we can apply diffs properly
 #[derive(Component, Debug)]
 pub struct DeltaComponentHistory<C> {
@@ -220,8 +284,98 @@ impl DeltaComponentStore {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::serialize::writer::Writer;
     use crate::tests::protocol::ComponentDeltaCompression;
 
+    /// Component where only the fields that changed are included in the diff
+    #[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq)]
+    struct FieldsComponent {
+        translation: [f32; 3],
+        rotation: [f32; 4],
+        scale: [f32; 3],
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+    struct FieldsDelta {
+        translation: Option<[f32; 3]>,
+        rotation: Option<[f32; 4]>,
+        scale: Option<[f32; 3]>,
+    }
+
+    impl Diffable for FieldsComponent {
+        type Delta = FieldsDelta;
+
+        fn base_value() -> Self {
+            Self {
+                translation: [0.0; 3],
+                rotation: [0.0, 0.0, 0.0, 1.0],
+                scale: [1.0; 3],
+            }
+        }
+
+        fn diff(&self, new: &Self) -> Self::Delta {
+            FieldsDelta {
+                translation: (self.translation != new.translation).then_some(new.translation),
+                rotation: (self.rotation != new.rotation).then_some(new.rotation),
+                scale: (self.scale != new.scale).then_some(new.scale),
+            }
+        }
+
+        fn apply_diff(&mut self, delta: &Self::Delta) {
+            if let Some(translation) = delta.translation {
+                self.translation = translation;
+            }
+            if let Some(rotation) = delta.rotation {
+                self.rotation = rotation;
+            }
+            if let Some(scale) = delta.scale {
+                self.scale = scale;
+            }
+        }
+    }
+
+    /// Check that a component can define its own diff, and that a diff that only
+    /// contains one of the fields is smaller than the full component
+    #[test]
+    fn test_manual_field_diff() {
+        let mut registry = ComponentRegistry::default();
+        registry.register_component::<FieldsComponent>();
+        registry.set_delta_compression::<FieldsComponent>();
+        let kind = ComponentKind::of::<FieldsComponent>();
+        let old = FieldsComponent {
+            translation: [1.0, 2.0, 3.0],
+            rotation: [0.0, 0.0, 0.0, 1.0],
+            scale: [2.0; 3],
+        };
+        let mut new = FieldsComponent {
+            translation: [4.0, 5.0, 6.0],
+            ..old.clone()
+        };
+
+        let mut writer = Writer::default();
+        registry.serialize(&mut new, &mut writer, None).unwrap();
+        let full = writer.split();
+        // SAFETY: the pointers correspond to components of type `FieldsComponent`
+        unsafe {
+            registry
+                .serialize_diff(
+                    Tick(0),
+                    Ptr::from(&old),
+                    Ptr::from(&new),
+                    &mut writer,
+                    kind,
+                    None,
+                )
+                .unwrap();
+        }
+        let diff = writer.split();
+        assert!(diff.len() < full.len());
+
+        let mut applied = old.clone();
+        applied.apply_diff(&old.diff(&new));
+        assert_eq!(applied, new);
+    }
+
     #[test]
     fn test_add_get_data() {
         let mut registry = ComponentRegistry::default();
@@ -64,6 +64,13 @@ pub struct InterpolatedEntityMap {
     // map from the confirmed entity to the interpolated entity
     // useful for despawning, as we won't have access to the Confirmed/Interpolated components anymore
     pub(crate) confirmed_to_interpolated: EntityMap,
+    // map from the confirmed entity to the remote entity that it replicates
+    // (only filled when a despawn grace period is set)
+    pub(crate) confirmed_to_remote: EntityMap,
+    // map from the remote entity to its interpolated entity, for interpolated entities that are
+    // in their despawn grace period. If the remote entity is replicated again during the grace
+    // period, the interpolated entity is reused
+    pub(crate) remote_to_despawning: EntityMap,
 }
 
 impl RemoteEntityMap {
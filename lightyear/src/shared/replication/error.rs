@@ -1,5 +1,8 @@
 //! Replication-related errors
 
+use bevy::ecs::entity::EntityHashMap;
+use bevy::prelude::{Entity, Reflect, ReflectResource, ResMut, Resource, World};
+
 use crate::serialize::SerializationError;
 
 pub type Result<T> = std::result::Result<T, ReplicationError>;
@@ -15,3 +18,88 @@ pub enum ReplicationError {
     #[error(transparent)]
     ComponentProtocolError(#[from] crate::protocol::component::ComponentError),
 }
+
+/// Reason why the replication of an entity was skipped
+#[derive(Debug, Clone, PartialEq, Reflect)]
+pub enum ReplicationSkipReason {
+    /// We received replication actions or updates for a remote entity that is not mapped to a local entity.
+    ///
+    /// This can happen for a few updates that were buffered before the entity got despawned
+    UnmappedEntity,
+    /// We received a despawn for a remote entity that is not mapped to a local entity
+    DespawnUnmappedEntity,
+    /// The remote asked to reuse a local entity for the spawn, but that entity does not exist
+    ReuseMissingEntity,
+    /// We received replication data from a peer that does not have authority over the entity
+    NoAuthority,
+    /// A replicated component could not be written to the entity
+    ComponentWrite(String),
+    /// The inputs of the entity could not be sent because the entity is not mapped to a server entity
+    UnmappedInputEntity,
+}
+
+/// Per-entity replication problems that happened during the current frame.
+///
+/// These are cases where the replication of an entity was skipped, that would otherwise only be visible
+/// in the logs. Remote entities that are not mapped to a local entity are stored separately from the
+/// local entities, since the two can have the same [`Entity`] value.
+///
+/// The errors are cleared at the start of every frame.
+#[derive(Resource, Debug, Default, Clone, PartialEq, Reflect)]
+#[reflect(Resource)]
+pub struct ReplicationErrors {
+    /// Errors for entities of the local [`World`]
+    pub local: EntityHashMap<ReplicationSkipReason>,
+    /// Errors for entities of the remote peer that are not mapped to a local entity
+    pub remote: EntityHashMap<ReplicationSkipReason>,
+}
+
+impl ReplicationErrors {
+    /// Get the reason why the replication of a local entity was skipped during this frame, if it was
+    pub fn get_local(&self, entity: Entity) -> Option<&ReplicationSkipReason> {
+        self.local.get(&entity)
+    }
+
+    /// Get the reason why the replication of a remote entity was skipped during this frame, if it was
+    pub fn get_remote(&self, entity: Entity) -> Option<&ReplicationSkipReason> {
+        self.remote.get(&entity)
+    }
+
+    pub(crate) fn record_local(&mut self, entity: Entity, reason: ReplicationSkipReason) {
+        self.local.insert(entity, reason);
+    }
+
+    pub(crate) fn record_remote(&mut self, entity: Entity, reason: ReplicationSkipReason) {
+        self.remote.insert(entity, reason);
+    }
+}
+
+/// Record a replication error for a local entity in the [`ReplicationErrors`] resource, if it exists
+pub(crate) fn record_local_replication_error(
+    world: &mut World,
+    entity: Entity,
+    reason: ReplicationSkipReason,
+) {
+    if let Some(mut errors) = world.get_resource_mut::<ReplicationErrors>() {
+        errors.record_local(entity, reason);
+    }
+}
+
+/// Record a replication error for a remote entity in the [`ReplicationErrors`] resource, if it exists
+pub(crate) fn record_remote_replication_error(
+    world: &mut World,
+    entity: Entity,
+    reason: ReplicationSkipReason,
+) {
+    if let Some(mut errors) = world.get_resource_mut::<ReplicationErrors>() {
+        errors.record_remote(entity, reason);
+    }
+}
+
+/// Clear the replication errors of the previous frame
+pub(crate) fn clear_replication_errors(mut errors: ResMut<ReplicationErrors>) {
+    if !errors.local.is_empty() || !errors.remote.is_empty() {
+        errors.local.clear();
+        errors.remote.clear();
+    }
+}
@@ -10,9 +10,11 @@ use crate::prelude::{
 };
 use crate::server::replication::send::SyncTarget;
 use crate::shared::replication::authority::{AuthorityPeer, HasAuthority};
-use crate::shared::replication::components::{ReplicateHierarchy, ReplicationTarget};
+use crate::shared::replication::components::{
+    AtomicHierarchy, DisabledComponent, ReplicateHierarchy, ReplicationTarget,
+};
 use crate::shared::replication::{ReplicationPeer, ReplicationSend};
-use crate::shared::sets::{InternalMainSet, InternalReplicationSet};
+use crate::shared::sets::{ClientMarker, InternalMainSet, InternalReplicationSet, ServerMarker};
 
 /// This component can be added to an entity to replicate the entity's hierarchy to the remote world.
 /// The `ParentSync` component will be updated automatically when the `Parent` component changes,
@@ -114,7 +116,10 @@ impl<R: ReplicationSend> HierarchySendPlugin<R> {
                         parent_group
                             .clone()
                             .set_id(parent_group.group_id(Some(parent_entity)).0),
-                        ReplicateHierarchy { recursive: true },
+                        ReplicateHierarchy {
+                            recursive: true,
+                            despawn_descendants: replicate_hierarchy.despawn_descendants,
+                        },
                         ParentSync(None),
                     ));
                     // On the client, we want to add the PrePredicted component to the children
@@ -159,6 +164,39 @@ impl<R: ReplicationSend> HierarchySendPlugin<R> {
         }
     }
 
+    /// If the root of a hierarchy has the [`AtomicHierarchy`] component, make sure that all the descendants
+    /// that start being replicated are part of the root's [`ReplicationGroup`], so that the whole subtree
+    /// is sent in a single message
+    fn group_atomic_hierarchy(
+        mut commands: Commands,
+        root_query: Query<(Entity, &ReplicationGroup), (With<AtomicHierarchy>, Without<Parent>)>,
+        children_query: Query<&Children>,
+        child_query: Query<(Option<&ReplicationGroup>, Ref<Replicating>)>,
+    ) {
+        for (root, root_group) in root_query.iter() {
+            let group_id = root_group.group_id(Some(root));
+            for child in children_query.iter_descendants(root) {
+                let Ok((child_group, replicating)) = child_query.get(child) else {
+                    continue;
+                };
+                // the replication group of an entity cannot be updated once it is being replicated
+                if !replicating.is_added() {
+                    continue;
+                }
+                if child_group.map_or(true, |group| group.group_id(Some(child)) != group_id) {
+                    trace!(
+                        ?child,
+                        ?root,
+                        "Adding child to the replication group of the atomic hierarchy"
+                    );
+                    commands
+                        .entity(child)
+                        .insert(root_group.clone().set_id(group_id.0));
+                }
+            }
+        }
+    }
+
     /// Update ParentSync if the hierarchy changed
     /// (run this in post-update before replicating, to account for any hierarchy changed initiated by the user)
     ///
@@ -178,6 +216,39 @@ impl<R: ReplicationSend> HierarchySendPlugin<R> {
         }
     }
 
+    /// If the [`ReplicateHierarchy`] of a despawned entity has `despawn_descendants` set, despawn
+    /// all its descendants, so that the replicated descendants are also despawned on the remote peers.
+    ///
+    /// The descendants are despawned after the entity, so their despawns are sent after the entity's despawn.
+    fn despawn_descendants(
+        // the ReplicateHierarchy component is removed when the entity is despawned
+        trigger: Trigger<OnRemove, ReplicateHierarchy>,
+        query: Query<(&ReplicateHierarchy, &Children)>,
+        mut commands: Commands,
+    ) {
+        let entity = trigger.entity();
+        let Ok((hierarchy, children)) = query.get(entity) else {
+            return;
+        };
+        if !hierarchy.despawn_descendants {
+            return;
+        }
+        let children = children.to_vec();
+        commands.add(move |world: &mut World| {
+            // the component was removed, but the entity was not despawned
+            if world.get_entity(entity).is_some() {
+                return;
+            }
+            for child in children {
+                // the children might have already been despawned with `despawn_recursive`
+                if let Some(child) = world.get_entity_mut(child) {
+                    trace!(child = ?child.id(), parent = ?entity, "Despawning descendant of despawned entity");
+                    child.despawn_recursive();
+                }
+            }
+        });
+    }
+
     /// Update ParentSync if the parent has been removed
     ///
     /// This only runs on the sending side
@@ -194,9 +265,14 @@ impl<R: ReplicationSend> HierarchySendPlugin<R> {
 impl<R: ReplicationSend> Plugin for HierarchySendPlugin<R> {
     fn build(&self, app: &mut App) {
         app.observe(Self::handle_parent_remove);
+        app.observe(Self::despawn_descendants);
         app.add_systems(
             PostUpdate,
-            (Self::propagate_replicate, Self::update_parent_sync)
+            (
+                Self::propagate_replicate,
+                Self::group_atomic_hierarchy,
+                Self::update_parent_sync,
+            )
                 .chain()
                 // we don't need to run these every frame, only every send_interval
                 .in_set(InternalReplicationSet::<R::SetMarker>::SendMessages)
@@ -265,18 +341,147 @@ impl<R: ReplicationPeer> Plugin for HierarchyReceivePlugin<R> {
     }
 }
 
+/// Adapter between a world-space transform component and a local-space transform component (relative to the parent),
+/// used by the [`LocalTransformPlugin`].
+///
+/// lightyear doesn't own any transform component, so you need to implement this trait for your own components.
+/// For example `Global` could be an avian `Position` and `Local` a `LocalPosition` component; both components need
+/// to be registered in the protocol.
+pub trait LocalTransformAdapter: Send + Sync + 'static {
+    /// The world-space transform, which is replicated for the roots of the hierarchies
+    type Global: Component + Clone + PartialEq;
+    /// The transform relative to the parent, which is replicated for the children instead of [`Self::Global`]
+    type Local: Component + PartialEq;
+
+    /// Compute the local transform of a child from its world-space transform and the parent's world-space transform
+    fn to_local(parent: &Self::Global, global: &Self::Global) -> Self::Local;
+
+    /// Compute the world-space transform of a child from its local transform and the parent's world-space transform
+    fn to_global(parent: &Self::Global, local: &Self::Local) -> Self::Global;
+}
+
+/// Opt-in mode to replicate the local transforms of the children of a hierarchy instead of their world-space transforms.
+///
+/// On the sending side, the children that are replicated with [`ParentSync`] replicate `A::Local` (computed from
+/// the parent's transform) and stop replicating `A::Global`; so when only the parent moves, only the parent's
+/// transform is sent.
+/// On the receiving side, the children's `A::Global` is reconstructed from the parent's `A::Global` and the
+/// replicated `A::Local`.
+///
+/// The plugin must be added to both the sending and the receiving apps.
+pub struct LocalTransformPlugin<A> {
+    _marker: std::marker::PhantomData<A>,
+}
+
+impl<A> Default for LocalTransformPlugin<A> {
+    fn default() -> Self {
+        Self {
+            _marker: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<A: LocalTransformAdapter> LocalTransformPlugin<A> {
+    /// Update the local transform of the replicated children from their world-space transform
+    ///
+    /// This only runs on the sending side
+    fn update_local_transforms(
+        mut commands: Commands,
+        parents: Query<&A::Global>,
+        mut children: Query<
+            (Entity, &Parent, &A::Global, Option<&mut A::Local>),
+            (With<ParentSync>, With<Replicating>),
+        >,
+    ) {
+        for (entity, parent, global, local) in children.iter_mut() {
+            let Ok(parent_global) = parents.get(parent.get()) else {
+                continue;
+            };
+            let new_local = A::to_local(parent_global, global);
+            match local {
+                // only update the component if the local transform changed, so that it isn't replicated
+                Some(mut local) => {
+                    local.set_if_neq(new_local);
+                }
+                None => {
+                    commands
+                        .entity(entity)
+                        .insert((new_local, DisabledComponent::<A::Global>::default()));
+                }
+            }
+        }
+    }
+
+    /// Reconstruct the world-space transform of the replicated children from their local transform,
+    /// starting from the roots of the hierarchies
+    ///
+    /// This only runs on the receiving side
+    fn update_global_transforms(
+        mut commands: Commands,
+        roots: Query<Entity, (With<A::Global>, Without<A::Local>, With<Children>)>,
+        children_query: Query<&Children>,
+        locals: Query<&A::Local, With<Replicated>>,
+        mut globals: Query<&mut A::Global>,
+    ) {
+        let mut stack: Vec<Entity> = roots.iter().collect();
+        while let Some(parent) = stack.pop() {
+            let Ok(parent_global) = globals.get(parent).cloned() else {
+                continue;
+            };
+            let Ok(children) = children_query.get(parent) else {
+                continue;
+            };
+            for &child in children {
+                let Ok(local) = locals.get(child) else {
+                    continue;
+                };
+                let global = A::to_global(&parent_global, local);
+                if let Ok(mut child_global) = globals.get_mut(child) {
+                    // only write the transform if it changed, to avoid triggering change detection
+                    child_global.set_if_neq(global);
+                    stack.push(child);
+                } else {
+                    // the descendants of the child will be updated on the next frame
+                    commands.entity(child).insert(global);
+                }
+            }
+        }
+    }
+}
+
+impl<A: LocalTransformAdapter> Plugin for LocalTransformPlugin<A> {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PreUpdate,
+            Self::update_global_transforms.after(MainSet::Receive),
+        );
+        app.add_systems(
+            PostUpdate,
+            Self::update_local_transforms
+                .before(InternalReplicationSet::<ServerMarker>::All)
+                .before(InternalReplicationSet::<ClientMarker>::All),
+        );
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::ops::Deref;
 
     use bevy::hierarchy::{BuildWorldChildren, Children, Parent};
-    use bevy::prelude::{default, Entity, With};
+    use bevy::prelude::{
+        default, Changed, Entity, EventReader, Query, ResMut, Resource, Update, With,
+    };
+
+    use bevy::utils::Duration;
 
     use crate::prelude::client;
     use crate::prelude::server::Replicate;
-    use crate::prelude::ReplicationGroup;
-    use crate::shared::replication::components::ReplicateHierarchy;
-    use crate::shared::replication::hierarchy::ParentSync;
+    use crate::prelude::{ReplicationGroup, SharedConfig, TickConfig};
+    use crate::shared::replication::components::{AtomicHierarchy, ReplicateHierarchy};
+    use crate::shared::replication::hierarchy::{
+        LocalTransformAdapter, LocalTransformPlugin, ParentSync,
+    };
     use crate::tests::protocol::*;
     use crate::tests::stepper::BevyStepper;
 
@@ -307,7 +512,10 @@ mod tests {
         let (mut stepper, grandparent, parent, child) = setup_hierarchy();
 
         let replicate = Replicate {
-            hierarchy: ReplicateHierarchy { recursive: false },
+            hierarchy: ReplicateHierarchy {
+                recursive: false,
+                ..default()
+            },
             // make sure that child and parent are replicated in the same group, so that both entities are spawned
             // before entity mapping is done
             group: ReplicationGroup::new_id(0),
@@ -513,4 +721,327 @@ mod tests {
             &ParentSync(Some(server_parent))
         );
     }
+
+    /// Check that a hierarchy marked with [`AtomicHierarchy`] is spawned entirely in a single receive step,
+    /// even if each entity of the hierarchy has its own `Replicate` bundle
+    #[test]
+    fn test_atomic_hierarchy() {
+        let frame_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(frame_duration),
+            ..default()
+        };
+        let mut client_config = client::ClientConfig::default();
+        // only apply one message containing spawns per frame, so that a hierarchy split
+        // across multiple replication groups would be spawned over multiple frames
+        client_config.replication.max_spawns_per_frame = Some(1);
+        let mut stepper = BevyStepper::new(shared_config, client_config, frame_duration);
+        stepper.init();
+
+        let replicate = Replicate {
+            hierarchy: ReplicateHierarchy {
+                recursive: false,
+                ..default()
+            },
+            ..default()
+        };
+        let server_world = stepper.server_app.world_mut();
+        let child = server_world
+            .spawn((
+                ComponentSyncModeOnce(0.0),
+                replicate.clone(),
+                ParentSync::default(),
+            ))
+            .id();
+        let parent = server_world
+            .spawn((
+                ComponentSyncModeSimple(0.0),
+                replicate.clone(),
+                ParentSync::default(),
+            ))
+            .add_child(child)
+            .id();
+        let grandparent = server_world
+            .spawn((ComponentSyncModeFull(0.0), replicate, AtomicHierarchy))
+            .add_child(parent)
+            .id();
+
+        let client_entities = |stepper: &mut BevyStepper| {
+            let client_world = stepper.client_app.world_mut();
+            (
+                client_world
+                    .query_filtered::<Entity, With<ComponentSyncModeFull>>()
+                    .get_single(client_world)
+                    .ok(),
+                client_world
+                    .query_filtered::<Entity, With<ComponentSyncModeSimple>>()
+                    .get_single(client_world)
+                    .ok(),
+                client_world
+                    .query_filtered::<Entity, With<ComponentSyncModeOnce>>()
+                    .get_single(client_world)
+                    .ok(),
+            )
+        };
+        // step until the hierarchy is received
+        let (client_grandparent, client_parent, client_child) = loop {
+            stepper.frame_step();
+            match client_entities(&mut stepper) {
+                (None, None, None) => continue,
+                entities => break entities,
+            }
+        };
+        // the whole hierarchy was spawned in the same frame
+        let client_grandparent = client_grandparent.expect("grandparent was not spawned");
+        let client_parent = client_parent.expect("parent was not spawned");
+        let client_child = client_child.expect("child was not spawned");
+        let client_world = stepper.client_app.world();
+        assert_eq!(
+            client_world.get::<Parent>(client_parent).unwrap().get(),
+            client_grandparent
+        );
+        assert_eq!(
+            client_world.get::<Parent>(client_child).unwrap().get(),
+            client_parent
+        );
+
+        // all the entities are part of the root's replication group
+        let server_world = stepper.server_app.world();
+        let group_id = server_world
+            .get::<ReplicationGroup>(grandparent)
+            .unwrap()
+            .group_id(Some(grandparent));
+        for entity in [parent, child] {
+            assert_eq!(
+                server_world
+                    .get::<ReplicationGroup>(entity)
+                    .unwrap()
+                    .group_id(Some(entity)),
+                group_id
+            );
+        }
+    }
+
+    /// Check that despawning the root of a hierarchy with `despawn_descendants` also despawns
+    /// its replicated descendants on the client, even if they are in different replication groups
+    #[test]
+    fn test_despawn_descendants() {
+        let (mut stepper, grandparent, parent, child) = setup_hierarchy();
+        let replicate = Replicate {
+            hierarchy: ReplicateHierarchy {
+                recursive: false,
+                ..default()
+            },
+            ..default()
+        };
+        for entity in [parent, child] {
+            stepper
+                .server_app
+                .world_mut()
+                .entity_mut(entity)
+                .insert((replicate.clone(), ParentSync::default()));
+        }
+        stepper
+            .server_app
+            .world_mut()
+            .entity_mut(grandparent)
+            .insert(Replicate {
+                hierarchy: ReplicateHierarchy {
+                    recursive: false,
+                    despawn_descendants: true,
+                },
+                ..default()
+            });
+        stepper.frame_step();
+        stepper.frame_step();
+
+        let client_world = stepper.client_app.world_mut();
+        let client_entities = [
+            client_world
+                .query_filtered::<Entity, With<ComponentSyncModeFull>>()
+                .get_single(client_world)
+                .unwrap(),
+            client_world
+                .query_filtered::<Entity, With<ComponentSyncModeSimple>>()
+                .get_single(client_world)
+                .unwrap(),
+            client_world
+                .query_filtered::<Entity, With<ComponentSyncModeOnce>>()
+                .get_single(client_world)
+                .unwrap(),
+        ];
+
+        // only despawn the root of the hierarchy
+        stepper.server_app.world_mut().despawn(grandparent);
+        stepper.frame_step();
+        stepper.frame_step();
+
+        // the descendants are despawned on the server and on the client
+        assert!(stepper.server_app.world().get_entity(parent).is_none());
+        assert!(stepper.server_app.world().get_entity(child).is_none());
+        for client_entity in client_entities {
+            assert!(stepper
+                .client_app
+                .world()
+                .get_entity(client_entity)
+                .is_none());
+        }
+    }
+
+    struct TestLocalTransform;
+
+    impl LocalTransformAdapter for TestLocalTransform {
+        type Global = ComponentSyncModeFull;
+        type Local = ComponentSyncModeSimple;
+
+        fn to_local(parent: &Self::Global, global: &Self::Global) -> Self::Local {
+            ComponentSyncModeSimple(global.0 - parent.0)
+        }
+
+        fn to_global(parent: &Self::Global, local: &Self::Local) -> Self::Global {
+            ComponentSyncModeFull(parent.0 + local.0)
+        }
+    }
+
+    #[derive(Resource, Default)]
+    struct ReceivedUpdates(Vec<Entity>);
+
+    #[derive(Resource, Default)]
+    struct ChangedGlobals(Vec<Entity>);
+
+    fn record_changed_globals(
+        mut changed: ResMut<ChangedGlobals>,
+        query: Query<Entity, Changed<ComponentSyncModeFull>>,
+    ) {
+        changed.0.extend(query.iter());
+    }
+
+    fn receive_updates(
+        mut received: ResMut<ReceivedUpdates>,
+        mut full_updates: EventReader<client::ComponentUpdateEvent<ComponentSyncModeFull>>,
+        mut simple_updates: EventReader<client::ComponentUpdateEvent<ComponentSyncModeSimple>>,
+    ) {
+        received
+            .0
+            .extend(full_updates.read().map(|event| event.entity()));
+        received
+            .0
+            .extend(simple_updates.read().map(|event| event.entity()));
+    }
+
+    /// With the local transform mode, moving the parent only replicates the parent's transform;
+    /// the children's world-space transforms are reconstructed on the receiver
+    #[test]
+    fn test_replicate_local_transforms() {
+        let frame_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(frame_duration),
+            ..default()
+        };
+        let mut stepper = BevyStepper::new(
+            shared_config,
+            client::ClientConfig::default(),
+            frame_duration,
+        );
+        stepper
+            .server_app
+            .add_plugins(LocalTransformPlugin::<TestLocalTransform>::default());
+        stepper
+            .client_app
+            .add_plugins(LocalTransformPlugin::<TestLocalTransform>::default());
+        stepper.client_app.init_resource::<ReceivedUpdates>();
+        stepper.client_app.init_resource::<ChangedGlobals>();
+        stepper
+            .client_app
+            .add_systems(Update, (receive_updates, record_changed_globals));
+        stepper.init();
+
+        let child = stepper
+            .server_app
+            .world_mut()
+            .spawn(ComponentSyncModeFull(1.0))
+            .id();
+        let parent = stepper
+            .server_app
+            .world_mut()
+            .spawn((Replicate::default(), ComponentSyncModeFull(0.0)))
+            .add_child(child)
+            .id();
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        let client_child = stepper
+            .client_app
+            .world_mut()
+            .query_filtered::<Entity, With<ParentSync>>()
+            .get_single(stepper.client_app.world())
+            .unwrap();
+        let client_parent = stepper
+            .client_app
+            .world()
+            .get::<Parent>(client_child)
+            .unwrap()
+            .get();
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<ComponentSyncModeSimple>(client_child),
+            Some(&ComponentSyncModeSimple(1.0))
+        );
+
+        // move the parent and its child together
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<ReceivedUpdates>()
+            .0
+            .clear();
+        stepper
+            .server_app
+            .world_mut()
+            .get_mut::<ComponentSyncModeFull>(parent)
+            .unwrap()
+            .0 = 5.0;
+        stepper
+            .server_app
+            .world_mut()
+            .get_mut::<ComponentSyncModeFull>(child)
+            .unwrap()
+            .0 = 6.0;
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+
+        // only the parent received an update
+        assert_eq!(
+            stepper.client_app.world().resource::<ReceivedUpdates>().0,
+            vec![client_parent]
+        );
+        // the child's world-space transform was reconstructed from the parent
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<ComponentSyncModeFull>(client_child),
+            Some(&ComponentSyncModeFull(6.0))
+        );
+
+        // the child's world-space transform is not written again if nothing moved
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<ChangedGlobals>()
+            .0
+            .clear();
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        assert!(!stepper
+            .client_app
+            .world()
+            .resource::<ChangedGlobals>()
+            .0
+            .contains(&client_child));
+    }
 }
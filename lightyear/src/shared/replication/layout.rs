@@ -0,0 +1,364 @@
+//! Compact encoding of the component net ids of a replication updates message.
+//!
+//! Every serialized component starts with its [`ComponentNetId`], so when many entities of a group
+//! have the same components, the same net ids are repeated for every entity.
+//! Instead, [`ComponentLayouts`] writes each distinct list of component net ids (a 'layout') once per message,
+//! and every entity only references its layout by index.
+use std::io::Write;
+
+use bevy::prelude::Entity;
+use byteorder::{ReadBytesExt, WriteBytesExt};
+use bytes::Bytes;
+use hashbrown::HashMap;
+
+use crate::prelude::Tick;
+use crate::protocol::component::ComponentNetId;
+use crate::serialize::reader::Reader;
+use crate::serialize::varint::{varint_len, VarIntReadExt, VarIntWriteExt};
+use crate::serialize::writer::Writer;
+use crate::serialize::{SerializationError, ToBytes};
+
+/// How the component data of an updates message is encoded
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub(crate) enum UpdatesEncoding {
+    /// Each component is written with its net id
+    Full = 0,
+    /// The net ids are written once per layout, see [`ComponentLayouts`]
+    Layouts = 1,
+}
+
+/// The `last_action_tick` of an updates message, together with the [`UpdatesEncoding`] of its updates.
+///
+/// Both are written in the tag byte of the `Option<Tick>`: with the [`UpdatesEncoding::Full`] encoding this is
+/// the same byte as a regular `Option<Tick>`, so the encoding only costs bytes if the updates are sent with
+/// [`compact_component_ids`](crate::prelude::ReplicationConfig::compact_component_ids).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct UpdatesHeader {
+    pub(crate) last_action_tick: Option<Tick>,
+    pub(crate) encoding: UpdatesEncoding,
+}
+
+impl UpdatesHeader {
+    const HAS_LAST_ACTION_TICK: u8 = 1;
+    const LAYOUTS: u8 = 1 << 1;
+}
+
+impl ToBytes for UpdatesHeader {
+    fn len(&self) -> usize {
+        self.last_action_tick.len()
+    }
+
+    fn to_bytes<T: WriteBytesExt>(&self, buffer: &mut T) -> Result<(), SerializationError> {
+        let mut tag = 0;
+        if self.last_action_tick.is_some() {
+            tag |= Self::HAS_LAST_ACTION_TICK;
+        }
+        if self.encoding == UpdatesEncoding::Layouts {
+            tag |= Self::LAYOUTS;
+        }
+        buffer.write_u8(tag)?;
+        if let Some(tick) = self.last_action_tick {
+            tick.to_bytes(buffer)?;
+        }
+        Ok(())
+    }
+
+    fn from_bytes(buffer: &mut Reader) -> Result<Self, SerializationError>
+    where
+        Self: Sized,
+    {
+        let tag = buffer.read_u8()?;
+        if tag & !(Self::HAS_LAST_ACTION_TICK | Self::LAYOUTS) != 0 {
+            return Err(SerializationError::InvalidValue);
+        }
+        let last_action_tick = if tag & Self::HAS_LAST_ACTION_TICK != 0 {
+            Some(Tick::from_bytes(buffer)?)
+        } else {
+            None
+        };
+        let encoding = if tag & Self::LAYOUTS != 0 {
+            UpdatesEncoding::Layouts
+        } else {
+            UpdatesEncoding::Full
+        };
+        Ok(Self {
+            last_action_tick,
+            encoding,
+        })
+    }
+}
+
+/// Component updates of the entities of a message, where the net ids of the components
+/// are written once per distinct layout instead of once per component
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct ComponentLayouts {
+    /// The distinct lists of component net ids
+    layouts: Vec<Vec<ComponentNetId>>,
+    /// For each entity, the index of its layout and the component data (without the net ids)
+    entities: Vec<(Entity, usize, Vec<Bytes>)>,
+}
+
+impl ComponentLayouts {
+    /// Build the layouts from the serialized components of each entity (net id followed by the component data)
+    pub(crate) fn new<'a>(
+        updates: impl IntoIterator<Item = (&'a Entity, &'a Vec<Bytes>)>,
+    ) -> Result<Self, SerializationError> {
+        let mut layouts = Self::default();
+        let mut layout_indices = HashMap::<Vec<ComponentNetId>, usize>::new();
+        for (entity, components) in updates {
+            let mut layout = Vec::with_capacity(components.len());
+            let mut data = Vec::with_capacity(components.len());
+            for component in components {
+                let mut reader = Reader::from(component.clone());
+                let net_id = ComponentNetId::from_bytes(&mut reader)?;
+                layout.push(net_id);
+                data.push(reader.split_len(reader.remaining()));
+            }
+            let index = *layout_indices.entry(layout).or_insert_with_key(|layout| {
+                layouts.layouts.push(layout.clone());
+                layouts.layouts.len() - 1
+            });
+            layouts.entities.push((*entity, index, data));
+        }
+        Ok(layouts)
+    }
+
+    /// Convert back to the serialized components of each entity (net id followed by the component data)
+    pub(crate) fn into_updates(self) -> Result<Vec<(Entity, Vec<Bytes>)>, SerializationError> {
+        self.entities
+            .into_iter()
+            .map(|(entity, index, data)| {
+                let layout = self
+                    .layouts
+                    .get(index)
+                    .filter(|layout| layout.len() == data.len())
+                    .ok_or(SerializationError::InvalidValue)?;
+                let components = layout
+                    .iter()
+                    .zip(data)
+                    .map(|(net_id, data)| {
+                        let mut writer = Writer::with_capacity(net_id.len() + data.len());
+                        net_id.to_bytes(&mut writer)?;
+                        writer.write_all(&data)?;
+                        Ok(writer.to_bytes())
+                    })
+                    .collect::<Result<Vec<_>, SerializationError>>()?;
+                Ok((entity, components))
+            })
+            .collect()
+    }
+}
+
+impl ToBytes for ComponentLayouts {
+    fn len(&self) -> usize {
+        varint_len(self.layouts.len() as u64)
+            + self
+                .layouts
+                .iter()
+                .map(|layout| {
+                    varint_len(layout.len() as u64) + layout.iter().map(ToBytes::len).sum::<usize>()
+                })
+                .sum::<usize>()
+            + varint_len(self.entities.len() as u64)
+            + self
+                .entities
+                .iter()
+                .map(|(entity, index, data)| {
+                    entity.len()
+                        + varint_len(*index as u64)
+                        + data.iter().map(ToBytes::len).sum::<usize>()
+                })
+                .sum::<usize>()
+    }
+
+    fn to_bytes<T: WriteBytesExt>(&self, buffer: &mut T) -> Result<(), SerializationError> {
+        buffer.write_varint(self.layouts.len() as u64)?;
+        for layout in &self.layouts {
+            buffer.write_varint(layout.len() as u64)?;
+            layout
+                .iter()
+                .try_for_each(|net_id| net_id.to_bytes(buffer))?;
+        }
+        buffer.write_varint(self.entities.len() as u64)?;
+        for (entity, index, data) in &self.entities {
+            entity.to_bytes(buffer)?;
+            buffer.write_varint(*index as u64)?;
+            // the number of components is given by the layout
+            data.iter().try_for_each(|data| data.to_bytes(buffer))?;
+        }
+        Ok(())
+    }
+
+    fn from_bytes(buffer: &mut Reader) -> Result<Self, SerializationError>
+    where
+        Self: Sized,
+    {
+        let num_layouts = buffer.read_varint()? as usize;
+        let mut layouts = Vec::with_capacity(num_layouts);
+        for _ in 0..num_layouts {
+            let num_components = buffer.read_varint()? as usize;
+            let layout = (0..num_components)
+                .map(|_| ComponentNetId::from_bytes(buffer))
+                .collect::<Result<Vec<_>, _>>()?;
+            layouts.push(layout);
+        }
+        let num_entities = buffer.read_varint()? as usize;
+        let mut entities = Vec::with_capacity(num_entities);
+        for _ in 0..num_entities {
+            let entity = Entity::from_bytes(buffer)?;
+            let index = buffer.read_varint()? as usize;
+            let num_components = layouts
+                .get(index)
+                .ok_or(SerializationError::InvalidValue)?
+                .len();
+            let data = (0..num_components)
+                .map(|_| Bytes::from_bytes(buffer))
+                .collect::<Result<Vec<_>, _>>()?;
+            entities.push((entity, index, data));
+        }
+        Ok(Self { layouts, entities })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::entity::EntityHash;
+    use bevy::prelude::default;
+    use bevy::utils::Duration;
+
+    use super::*;
+    use crate::prelude::server::{Replicate, ServerConfig};
+    use crate::prelude::{client, ReplicationGroup, SharedConfig, Tick, TickConfig};
+    use crate::shared::replication::components::ReplicationGroupId;
+    use crate::shared::replication::{EntityUpdatesMessage, SendEntityUpdatesMessage};
+    use crate::tests::protocol::{ComponentSyncModeFull, ComponentSyncModeFull2};
+    use crate::tests::stepper::BevyStepper;
+
+    fn serialize(message: &SendEntityUpdatesMessage) -> Bytes {
+        let mut writer = Writer::default();
+        message.to_bytes(&mut writer).unwrap();
+        writer.to_bytes()
+    }
+
+    /// Check that writing the component net ids once per layout makes the message smaller
+    /// for a group of entities with the same components, and that the message can be read back
+    #[test]
+    fn test_component_layouts_size() {
+        let mut updates = hashbrown::HashMap::<Entity, Vec<Bytes>, EntityHash>::default();
+        for i in 0..100 {
+            let components = (0..3_u16)
+                .map(|net_id| {
+                    let mut writer = Writer::default();
+                    net_id.to_bytes(&mut writer).unwrap();
+                    writer.write_all(&[i as u8; 4]).unwrap();
+                    writer.to_bytes()
+                })
+                .collect();
+            updates.insert(Entity::from_raw(i), components);
+        }
+        let mut message = SendEntityUpdatesMessage {
+            group_id: ReplicationGroupId(0),
+            last_action_tick: Some(Tick(2)),
+            updates,
+            layouts: None,
+        };
+        let full = serialize(&message);
+        // without compact component ids, the message doesn't contain any encoding tag
+        assert_eq!(
+            full.len(),
+            message.group_id.len()
+                + message.last_action_tick.len()
+                + ToBytes::len(&message.updates)
+        );
+        assert_eq!(full.len(), message.len());
+        message.layouts = Some(ComponentLayouts::new(&message.updates).unwrap());
+        let compact = serialize(&message);
+        // one net id per component is replaced by one layout index per entity
+        assert!(
+            full.len() >= compact.len() + 2 * 100,
+            "{} vs {}",
+            full.len(),
+            compact.len()
+        );
+
+        for bytes in [full, compact] {
+            let read = EntityUpdatesMessage::from_bytes(&mut Reader::from(bytes)).unwrap();
+            assert_eq!(read.group_id, message.group_id);
+            assert_eq!(read.last_action_tick, message.last_action_tick);
+            assert_eq!(read.updates.len(), message.updates.len());
+            for (entity, components) in read.updates {
+                assert_eq!(message.updates.get(&entity), Some(&components));
+            }
+        }
+    }
+
+    /// Check that the updates sent with compact component ids are applied by the client
+    #[test]
+    fn test_replicate_compact_component_ids() {
+        let frame_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(frame_duration),
+            ..default()
+        };
+        let mut stepper = BevyStepper::new(
+            shared_config,
+            client::ClientConfig::default(),
+            frame_duration,
+        );
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ServerConfig>()
+            .replication
+            .compact_component_ids = true;
+        stepper.init();
+
+        let server_entities = (0..20)
+            .map(|_| {
+                stepper
+                    .server_app
+                    .world_mut()
+                    .spawn((
+                        ComponentSyncModeFull(0.0),
+                        ComponentSyncModeFull2(0.0),
+                        Replicate {
+                            group: ReplicationGroup::new_id(1),
+                            ..default()
+                        },
+                    ))
+                    .id()
+            })
+            .collect::<Vec<_>>();
+        stepper.frame_step();
+        stepper.frame_step();
+
+        for (i, entity) in server_entities.iter().enumerate() {
+            let mut entity_mut = stepper.server_app.world_mut().entity_mut(*entity);
+            entity_mut.get_mut::<ComponentSyncModeFull>().unwrap().0 = i as f32;
+            entity_mut.get_mut::<ComponentSyncModeFull2>().unwrap().0 = -(i as f32);
+        }
+        stepper.frame_step();
+        stepper.frame_step();
+
+        for (i, entity) in server_entities.iter().enumerate() {
+            let client_entity = stepper
+                .client_app
+                .world()
+                .resource::<client::ConnectionManager>()
+                .replication_receiver
+                .remote_entity_map
+                .get_local(*entity)
+                .expect("entity was not replicated to client");
+            let client_entity = stepper.client_app.world().entity(client_entity);
+            assert_eq!(
+                client_entity.get::<ComponentSyncModeFull>(),
+                Some(&ComponentSyncModeFull(i as f32))
+            );
+            assert_eq!(
+                client_entity.get::<ComponentSyncModeFull2>(),
+                Some(&ComponentSyncModeFull2(-(i as f32)))
+            );
+        }
+    }
+}
@@ -22,6 +22,7 @@ use crate::shared::events::connection::{
     IterEntityDespawnEvent, IterEntitySpawnEvent,
 };
 use crate::shared::replication::components::ReplicationGroupId;
+use crate::shared::replication::layout::{ComponentLayouts, UpdatesEncoding, UpdatesHeader};
 
 pub mod components;
 
@@ -31,6 +32,7 @@ pub mod delta;
 pub mod entity_map;
 pub mod error;
 pub(crate) mod hierarchy;
+pub(crate) mod layout;
 pub mod network_target;
 pub(crate) mod plugin;
 pub(crate) mod prespawn;
@@ -45,7 +47,8 @@ pub(crate) mod systems;
 /// TODO: optimize for the case where generation == 1, which should be most cases
 impl ToBytes for Entity {
     fn len(&self) -> usize {
-        varint_len(self.index() as u64) + varint_len(self.generation() as u64)
+        // the generation is written as a u32
+        varint_len(self.index() as u64) + 4
     }
 
     fn to_bytes<T: WriteBytesExt>(&self, buffer: &mut T) -> Result<(), SerializationError> {
@@ -232,6 +235,9 @@ pub struct SendEntityUpdatesMessage {
     last_action_tick: Option<Tick>,
     /// Updates containing the full component data
     pub(crate) updates: HashMap<Entity, Vec<Bytes>, EntityHash>,
+    /// If present, the updates are written with the component net ids only written once per layout
+    /// (see [`ReplicationConfig::compact_component_ids`](crate::prelude::ReplicationConfig::compact_component_ids))
+    pub(crate) layouts: Option<ComponentLayouts>,
     // /// Updates containing diffs with a previous value
     // #[bitcode(with_serde)]
     // diff_updates: Vec<(Entity, Vec<RawData>)>,
@@ -239,13 +245,31 @@ pub struct SendEntityUpdatesMessage {
 
 impl ToBytes for SendEntityUpdatesMessage {
     fn len(&self) -> usize {
-        self.group_id.len() + self.last_action_tick.len() + self.updates.len()
+        self.group_id.len()
+            + self.last_action_tick.len()
+            + self
+                .layouts
+                .as_ref()
+                .map_or_else(|| ToBytes::len(&self.updates), ToBytes::len)
     }
 
     fn to_bytes<T: WriteBytesExt>(&self, buffer: &mut T) -> Result<(), SerializationError> {
         self.group_id.to_bytes(buffer)?;
-        self.last_action_tick.to_bytes(buffer)?;
-        self.updates.to_bytes(buffer)?;
+        let encoding = if self.layouts.is_some() {
+            UpdatesEncoding::Layouts
+        } else {
+            UpdatesEncoding::Full
+        };
+        UpdatesHeader {
+            last_action_tick: self.last_action_tick,
+            encoding,
+        }
+        .to_bytes(buffer)?;
+        if let Some(layouts) = &self.layouts {
+            layouts.to_bytes(buffer)?;
+        } else {
+            self.updates.to_bytes(buffer)?;
+        }
         Ok(())
     }
 
@@ -253,10 +277,23 @@ impl ToBytes for SendEntityUpdatesMessage {
     where
         Self: Sized,
     {
+        let group_id = ReplicationGroupId::from_bytes(buffer)?;
+        let UpdatesHeader {
+            last_action_tick,
+            encoding,
+        } = UpdatesHeader::from_bytes(buffer)?;
+        let updates = match encoding {
+            UpdatesEncoding::Full => HashMap::<Entity, Vec<Bytes>, EntityHash>::from_bytes(buffer)?,
+            UpdatesEncoding::Layouts => ComponentLayouts::from_bytes(buffer)?
+                .into_updates()?
+                .into_iter()
+                .collect(),
+        };
         Ok(Self {
-            group_id: ReplicationGroupId::from_bytes(buffer)?,
-            last_action_tick: Option::<Tick>::from_bytes(buffer)?,
-            updates: HashMap::<Entity, Vec<Bytes>, EntityHash>::from_bytes(buffer)?,
+            group_id,
+            last_action_tick,
+            updates,
+            layouts: None,
         })
     }
 }
@@ -278,7 +315,7 @@ pub struct EntityUpdatesMessage {
 
 impl ToBytes for EntityUpdatesMessage {
     fn len(&self) -> usize {
-        self.group_id.len() + self.last_action_tick.len() + self.updates.len()
+        self.group_id.len() + self.last_action_tick.len() + ToBytes::len(&self.updates)
     }
 
     fn to_bytes<T: WriteBytesExt>(&self, buffer: &mut T) -> Result<(), SerializationError> {
@@ -292,10 +329,20 @@ impl ToBytes for EntityUpdatesMessage {
     where
         Self: Sized,
     {
+        let group_id = ReplicationGroupId::from_bytes(buffer)?;
+        // the sender can write the net ids of the components once per layout
+        let UpdatesHeader {
+            last_action_tick,
+            encoding,
+        } = UpdatesHeader::from_bytes(buffer)?;
+        let updates = match encoding {
+            UpdatesEncoding::Full => Vec::<(Entity, Vec<Bytes>)>::from_bytes(buffer)?,
+            UpdatesEncoding::Layouts => ComponentLayouts::from_bytes(buffer)?.into_updates()?,
+        };
         Ok(Self {
-            group_id: ReplicationGroupId::from_bytes(buffer)?,
-            last_action_tick: Option::<Tick>::from_bytes(buffer)?,
-            updates: Vec::<(Entity, Vec<Bytes>)>::from_bytes(buffer)?,
+            group_id,
+            last_action_tick,
+            updates,
         })
     }
 }
@@ -12,7 +12,8 @@ pub enum NetworkTarget {
     #[default]
     /// Message sent to no client
     None,
-    /// Message sent to all clients except one
+    /// Message sent to all clients except one (for example to re-broadcast a client's message
+    /// to everyone but the sender)
     AllExceptSingle(ClientId),
     /// Message sent to all clients except for these
     AllExcept(Vec<ClientId>),
@@ -489,4 +490,28 @@ mod tests {
         target.union(&NetworkTarget::AllExcept(vec![client_0, client_2]));
         assert_eq!(target, NetworkTarget::AllExcept(vec![client_0, client_2]));
     }
+
+    /// Check that `AllExceptSingle` targets every client except the excluded one,
+    /// including after being restricted to the clients that an entity is visible to
+    #[test]
+    fn test_all_except_single() {
+        let client_0 = ClientId::Netcode(0);
+        let client_1 = ClientId::Netcode(1);
+        let client_2 = ClientId::Netcode(2);
+        let target = NetworkTarget::AllExceptSingle(client_0);
+        assert!(!target.targets(&client_0));
+        assert!(target.targets(&client_1));
+        assert_eq!(NetworkTarget::from_exclude([client_0]), target);
+
+        // only client 0 and client 1 can see the entity
+        let mut visible_target = target.clone();
+        visible_target.intersection(&NetworkTarget::Only(vec![client_0, client_1]));
+        assert!(!visible_target.targets(&client_0));
+        assert!(visible_target.targets(&client_1));
+        assert!(!visible_target.targets(&client_2));
+
+        let mut visible_target = NetworkTarget::Only(vec![client_0, client_1]);
+        visible_target.intersection(&target);
+        assert_eq!(visible_target, NetworkTarget::Single(client_1));
+    }
 }
@@ -8,6 +8,7 @@ use crate::shared::replication::resources::{
 use crate::shared::replication::systems;
 use crate::shared::replication::{ReplicationReceive, ReplicationSend};
 use crate::shared::sets::{InternalMainSet, InternalReplicationSet, MainSet};
+use crate::transport::middleware::compression::CompressionConfig;
 use bevy::prelude::*;
 use bevy::time::common_conditions::on_timer;
 use bevy::utils::Duration;
@@ -20,6 +21,54 @@ pub struct ReplicationConfig {
     ///
     /// Set to `Duration::default()` to send updates every frame.
     pub send_interval: Duration,
+    /// What to do when we receive a spawn for a remote entity that is already mapped to a local entity
+    pub duplicate_spawn_policy: DuplicateSpawnPolicy,
+    /// Maximum number of replicated entity spawns that are applied every frame.
+    ///
+    /// When a lot of entities are spawned at the same time (for example when joining a game in progress),
+    /// the extra spawns are kept in the buffer and applied during the next frames to avoid a frame spike.
+    /// A single message is always applied entirely, even if it contains more spawns than the limit.
+    ///
+    /// Set to `None` to apply all the spawns as soon as they are received.
+    pub max_spawns_per_frame: Option<usize>,
+    /// Pause the replication updates sent to a remote peer when the oldest of our packets that it hasn't acked
+    /// has been in flight for this long (for example because it is stalled or overwhelmed), instead of wasting
+    /// bandwidth on updates that will probably be lost.
+    ///
+    /// A remote that has nothing to send only acks the packets it receives if its `PacketConfig::ack_packet_interval`
+    /// is set, so it should be enabled on the remote, and the timeout should be larger than that interval plus the round-trip time.
+    ///
+    /// The entity actions are still sent reliably, and the updates resume as soon as the remote acks packets again.
+    /// On the server, this is evaluated separately for each client.
+    ///
+    /// Set to `None` to always send the updates.
+    pub backpressure_timeout: Option<Duration>,
+    /// What to do with the component updates that arrive before the entity actions (spawn, inserts, etc.)
+    /// that they depend on
+    pub early_updates_policy: EarlyUpdatesPolicy,
+    /// If true, the component net ids of an update message are written only once for each distinct
+    /// set of components (layout), and each entity references its layout by index, instead of writing
+    /// the net id of every component of every entity.
+    ///
+    /// This saves bytes for large replication groups where many entities have the same components.
+    /// Only the update messages are compacted; entity actions (spawns, inserts, removals) are sent as usual.
+    /// The receiver can decode both encodings, so this doesn't need to be enabled on the remote peer.
+    pub compact_component_ids: bool,
+    /// Compression used for the initial snapshot sent to a remote peer that just connected: the first
+    /// entity actions sent to it, which contain the spawns of all the existing entities when a client
+    /// joins a game in progress.
+    ///
+    /// The snapshot is large and only sent once, so it can be worth using a slower algorithm with a better
+    /// ratio than the per-packet [`SharedIoConfig::compression`](crate::transport::config::SharedIoConfig::compression).
+    /// The actions of all the replication groups are sent as a single payload on the [`SnapshotChannel`],
+    /// compressed before being fragmented into packets.
+    ///
+    /// With [`CompressionConfig::None`], the initial snapshot is sent like the other entity actions.
+    /// The receiver can decode the snapshot without enabling this setting, as long as the algorithm's
+    /// feature is enabled.
+    ///
+    /// [`SnapshotChannel`]: crate::channel::builder::SnapshotChannel
+    pub initial_snapshot_compression: CompressionConfig,
 }
 
 #[derive(Clone, Copy, Debug, Reflect)]
@@ -42,11 +91,49 @@ pub enum SendUpdatesMode {
     SinceLastSend,
 }
 
+/// How the receiver handles a spawn for a remote entity that is already mapped to a local entity.
+///
+/// This can happen if a spawn action gets retransmitted, or if the remote re-sends the spawn
+/// after a reconnection. In both cases we never spawn a duplicate local entity.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+pub enum DuplicateSpawnPolicy {
+    /// The duplicate spawn is a no-op: the spawn and any other actions for that entity contained
+    /// in the same message are discarded.
+    Ignore,
+    /// Keep the existing local entity, but re-apply the components contained in the
+    /// spawn message to force a resync of the entity.
+    #[default]
+    Resync,
+}
+
+/// How the receiver handles the component updates that arrive before the entity actions they depend on.
+///
+/// The updates are sent on an unreliable channel and the actions on a reliable channel, so an update for an
+/// entity can arrive before the action that spawns the entity. Every update contains the tick of the
+/// last action sent for its replication group, and is only applied once that action has been applied.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+pub enum EarlyUpdatesPolicy {
+    /// Keep the updates in a buffer until the actions they depend on arrive.
+    #[default]
+    Buffer,
+    /// Keep the updates in a buffer until the actions they depend on arrive, but drop the buffered updates
+    /// that are more than `max_ticks` older than the most recent update received for the same replication group.
+    ///
+    /// This bounds the number of updates kept in the buffer if the actions take a long time to arrive.
+    BufferWithTimeout { max_ticks: u16 },
+}
+
 impl Default for ReplicationConfig {
     fn default() -> Self {
         Self {
             send_updates_mode: SendUpdatesMode::SinceLastAck,
             send_interval: Duration::default(),
+            duplicate_spawn_policy: DuplicateSpawnPolicy::default(),
+            max_spawns_per_frame: None,
+            backpressure_timeout: None,
+            early_updates_policy: EarlyUpdatesPolicy::default(),
+            compact_component_ids: false,
+            initial_snapshot_compression: CompressionConfig::None,
         }
     }
 }
@@ -88,7 +175,7 @@ pub(crate) mod receive {
 
 pub(crate) mod send {
     use super::*;
-    use crate::prelude::{Replicating, ReplicationGroup, TimeManager};
+    use crate::prelude::{Replicating, ReplicationGroup, ReplicationGroupKey, TimeManager};
 
     pub(crate) struct ReplicationSendPlugin<R> {
         send_interval: Duration,
@@ -137,6 +224,18 @@ pub(crate) mod send {
             }
         }
 
+        /// Update the group id of the entities whose [`ReplicationGroupKey`] changed
+        fn update_replication_group_keys(
+            mut replication_groups: Query<
+                (&ReplicationGroupKey, &mut ReplicationGroup),
+                Changed<ReplicationGroupKey>,
+            >,
+        ) {
+            for (key, mut replication_group) in replication_groups.iter_mut() {
+                replication_group.set_key(*key);
+            }
+        }
+
         /// After we buffer updates, reset all the `should_send` to false
         /// for the replication groups that have a `send_frequency`
         fn update_replication_group_should_send(
@@ -230,7 +329,10 @@ pub(crate) mod send {
             app.add_systems(
                 PostUpdate,
                 (
-                    ReplicationSendPlugin::<R>::tick_replication_group_timers
+                    (
+                        ReplicationSendPlugin::<R>::update_replication_group_keys,
+                        ReplicationSendPlugin::<R>::tick_replication_group_timers,
+                    )
                         .in_set(InternalReplicationSet::<R::SetMarker>::BeforeBuffer),
                     ReplicationSendPlugin::<R>::update_replication_group_should_send
                         // note that this runs every send_interval
@@ -248,17 +350,21 @@ pub(crate) mod send {
 pub(crate) mod shared {
     use crate::client::replication::send::ReplicateToServer;
     use crate::prelude::{
-        NetworkRelevanceMode, PrePredicted, RemoteEntityMap, ReplicateHierarchy, Replicated,
-        ReplicationConfig, ReplicationGroup, ReplicationTarget, ShouldBePredicted, TargetEntity,
+        DuplicateSpawnPolicy, NetworkRelevanceMode, PrePredicted, RemoteEntityMap,
+        ReplicateHierarchy, Replicated, ReplicationConfig, ReplicationGroup, ReplicationGroupKey,
+        ReplicationTarget, ShouldBePredicted, TargetEntity,
     };
     use crate::shared::replication::authority::{AuthorityPeer, HasAuthority};
     use crate::shared::replication::components::{
-        Controlled, Replicating, ReplicationGroupId, ReplicationGroupIdBuilder,
+        AtomicHierarchy, Controlled, Replicating, ReplicationGroupId, ReplicationGroupIdBuilder,
         ShouldBeInterpolated,
     };
     use crate::shared::replication::entity_map::{InterpolatedEntityMap, PredictedEntityMap};
+    use crate::shared::replication::error::{
+        clear_replication_errors, ReplicationErrors, ReplicationSkipReason,
+    };
     use crate::shared::replication::network_target::NetworkTarget;
-    use bevy::prelude::{App, Plugin};
+    use bevy::prelude::{App, First, Plugin};
 
     pub(crate) struct SharedPlugin;
 
@@ -272,9 +378,12 @@ pub(crate) mod shared {
                 .register_type::<ReplicationTarget>()
                 .register_type::<ReplicateToServer>()
                 .register_type::<ReplicateHierarchy>()
+                .register_type::<AtomicHierarchy>()
                 .register_type::<ReplicationGroupIdBuilder>()
                 .register_type::<ReplicationGroup>()
+                .register_type::<ReplicationGroupKey>()
                 .register_type::<ReplicationConfig>()
+                .register_type::<DuplicateSpawnPolicy>()
                 .register_type::<ReplicationGroupId>()
                 .register_type::<NetworkRelevanceMode>()
                 .register_type::<NetworkTarget>()
@@ -285,7 +394,15 @@ pub(crate) mod shared {
                 .register_type::<PredictedEntityMap>()
                 .register_type::<HasAuthority>()
                 .register_type::<AuthorityPeer>()
-                .register_type::<InterpolatedEntityMap>();
+                .register_type::<InterpolatedEntityMap>()
+                .register_type::<ReplicationSkipReason>()
+                .register_type::<ReplicationErrors>();
+
+            // RESOURCES
+            app.init_resource::<ReplicationErrors>();
+
+            // SYSTEMS
+            app.add_systems(First, clear_replication_errors);
         }
     }
 }
@@ -8,9 +8,15 @@ use crate::prelude::client::Confirmed;
 use crate::prelude::{ClientConnectionManager, ClientId, ServerConnectionManager, Tick};
 use crate::protocol::component::ComponentRegistry;
 use crate::serialize::reader::Reader;
+use crate::serialize::{SerializationError, ToBytes};
 use crate::shared::events::connection::ConnectionEvents;
 use crate::shared::replication::authority::{AuthorityPeer, HasAuthority};
 use crate::shared::replication::components::{Replicated, ReplicationGroupId};
+use crate::shared::replication::error::{
+    record_local_replication_error, record_remote_replication_error, ReplicationSkipReason,
+};
+use crate::shared::replication::plugin::{DuplicateSpawnPolicy, EarlyUpdatesPolicy};
+use crate::transport::middleware::compression::decompress_payload;
 #[cfg(test)]
 use crate::utils::captures::Captures;
 use bevy::ecs::entity::EntityHash;
@@ -35,6 +41,15 @@ pub struct ReplicationReceiver {
     // BOTH
     /// Buffer to so that we have an ordered receiver per group
     pub(crate) group_channels: EntityHashMap<ReplicationGroupId, GroupChannel>,
+
+    /// How to handle a spawn for a remote entity that is already mapped
+    pub(crate) duplicate_spawn_policy: DuplicateSpawnPolicy,
+
+    /// Maximum number of entity spawns applied every frame
+    pub(crate) max_spawns_per_frame: Option<usize>,
+
+    /// How to handle the updates that arrive before the actions they depend on
+    pub(crate) early_updates_policy: EarlyUpdatesPolicy,
 }
 
 /// Get `ConnectionEvents` depending on whether we receive from a client or a server
@@ -66,9 +81,47 @@ impl ReplicationReceiver {
             remote_entity_to_group: Default::default(),
             // BOTH
             group_channels: Default::default(),
+            duplicate_spawn_policy: DuplicateSpawnPolicy::default(),
+            max_spawns_per_frame: None,
+            early_updates_policy: EarlyUpdatesPolicy::default(),
         }
     }
 
+    /// Set the policy used when receiving a spawn for a remote entity that is already mapped
+    pub(crate) fn with_duplicate_spawn_policy(mut self, policy: DuplicateSpawnPolicy) -> Self {
+        self.duplicate_spawn_policy = policy;
+        self
+    }
+
+    /// Set the maximum number of entity spawns applied every frame
+    pub(crate) fn with_max_spawns_per_frame(mut self, max_spawns_per_frame: Option<usize>) -> Self {
+        self.max_spawns_per_frame = max_spawns_per_frame;
+        self
+    }
+
+    /// Set the policy used for the updates that arrive before the actions they depend on
+    pub(crate) fn with_early_updates_policy(mut self, policy: EarlyUpdatesPolicy) -> Self {
+        self.early_updates_policy = policy;
+        self
+    }
+
+    /// Buffer all the [`EntityActionsMessage`]s contained in a received initial snapshot.
+    ///
+    /// See [`ReplicationConfig::initial_snapshot_compression`](crate::prelude::ReplicationConfig::initial_snapshot_compression)
+    pub(crate) fn recv_snapshot(
+        &mut self,
+        payload: &[u8],
+        remote_tick: Tick,
+    ) -> Result<(), SerializationError> {
+        let snapshot = decompress_payload(payload)?;
+        let messages = Vec::<EntityActionsMessage>::from_bytes(&mut Reader::from(snapshot))?;
+        trace!(num_groups = messages.len(), "Received initial snapshot");
+        for actions in messages {
+            self.recv_actions(actions, remote_tick);
+        }
+        Ok(())
+    }
+
     /// Buffer a received [`EntityActionsMessage`].
     ///
     /// The remote_tick is the tick at which the message was buffered and sent by the remote client.
@@ -141,6 +194,16 @@ impl ReplicationReceiver {
         //  which should be fairly quick, never more than 1-2 sec. (so a buffer of size 64 or 128 seems good). It might need more memory though?
         //  Benchmark.
         channel.buffered_updates.insert(updates, remote_tick);
+        // the updates that are still waiting for their actions after the timeout are dropped
+        if let EarlyUpdatesPolicy::BufferWithTimeout { max_ticks } = self.early_updates_policy {
+            let dropped = channel.buffered_updates.drop_older_than(max_ticks);
+            if dropped > 0 {
+                debug!(
+                    ?dropped,
+                    "dropped buffered updates that were waiting for their actions for too long"
+                );
+            }
+        }
 
         // TODO: include somewhere in the update message the m.last_ack_tick since when we compute changes?
         //  (if we want to do diff compression?)
@@ -516,10 +579,11 @@ impl ReplicationReceiver {
         // });
 
         trace!(?current_tick, ?self.group_channels, "applying replication actions messages");
+        let mut spawns_applied = 0;
         self.group_channels
             .iter_mut()
             .for_each(|(group_id, channel)| {
-                let Some((remote_tick, _)) = channel
+                let Some((remote_tick, message)) = channel
                     .actions_recv_message_buffer
                     .get(&channel.actions_pending_recv_message_id)
                 else {
@@ -533,6 +597,24 @@ impl ReplicationReceiver {
                     );
                     return;
                 }
+                // if we already applied too many spawns this frame, keep the message for the next frames.
+                // The later messages of the group will wait as well since the group's latest_tick is not updated
+                let num_spawns = message
+                    .actions
+                    .iter()
+                    .filter(|(_, actions)| actions.spawn == SpawnAction::Spawn)
+                    .count();
+                if let Some(max_spawns) = self.max_spawns_per_frame {
+                    if spawns_applied > 0 && spawns_applied + num_spawns > max_spawns {
+                        trace!(
+                            ?group_id,
+                            ?num_spawns,
+                            "spawn limit reached for this frame, delaying actions message"
+                        );
+                        return;
+                    }
+                }
+                spawns_applied += num_spawns;
 
                 // We have received the message we are waiting for
                 let (remote_tick, message) = channel
@@ -552,6 +634,7 @@ impl ReplicationReceiver {
                     message,
                     &mut self.remote_entity_map,
                     &mut self.remote_entity_to_group,
+                    self.duplicate_spawn_policy,
                     events,
                 );
             });
@@ -587,6 +670,7 @@ impl ReplicationReceiver {
                         message,
                         events,
                         &mut self.remote_entity_map,
+                        &self.remote_entity_to_group,
                     );
                 }
             })
@@ -598,7 +682,7 @@ impl ReplicationReceiver {
 pub struct GroupChannel {
     // entities
     // set of remote entities that are part of the same Replication Group
-    remote_entities: HashSet<Entity>,
+    pub(crate) remote_entities: HashSet<Entity>,
     // actions
     pub(crate) actions_pending_recv_message_id: MessageId,
     pub(crate) actions_recv_message_buffer: BTreeMap<MessageId, (Tick, EntityActionsMessage)>,
@@ -737,6 +821,20 @@ impl UpdatesBuffer {
     fn pop_oldest(&mut self) -> Option<(Tick, EntityUpdatesMessage)> {
         self.0.pop()
     }
+
+    /// Drop the messages that are more than `max_ticks` older than the most recent message in the buffer.
+    ///
+    /// Returns the number of dropped messages
+    fn drop_older_than(&mut self, max_ticks: u16) -> usize {
+        let Some((most_recent_tick, _)) = self.0.first() else {
+            return 0;
+        };
+        let min_tick = *most_recent_tick - max_ticks;
+        let len = self.len();
+        let idx = self.0.partition_point(|(tick, _)| *tick >= min_tick);
+        self.0.truncate(idx);
+        len - idx
+    }
 }
 
 /// Iterator that returns all the available [`EntityUpdatesMessage`] for the current [`GroupChannel`]
@@ -815,10 +913,13 @@ impl GroupChannel {
         message: EntityActionsMessage,
         remote_entity_map: &mut RemoteEntityMap,
         remote_entity_to_group: &mut EntityHashMap<Entity, ReplicationGroupId>,
+        duplicate_spawn_policy: DuplicateSpawnPolicy,
         events: &mut ConnectionEvents,
     ) {
         let group_id = message.group_id;
         debug!(?remote_tick, ?message, "Received replication actions");
+        // remote entities for which we received a duplicate spawn that should be ignored
+        let mut ignored_entities = vec![];
         // NOTE: order matters here, because some components can depend on other entities.
         // These components could even form a cycle, for example A.HasWeapon(B) and B.HasHolder(A)
         // Our solution is to first handle spawn for all entities separately.
@@ -828,16 +929,32 @@ impl GroupChannel {
             match actions.spawn {
                 SpawnAction::Spawn => {
                     // TODO: update this to local_entity_to_group??
-                    remote_entity_to_group.insert(*remote_entity, group_id);
+                    let previous_group_id = remote_entity_to_group.insert(*remote_entity, group_id);
                     // TODO ABOVE
 
                     if let Some(local_entity) = remote_entity_map.get_local(*remote_entity) {
                         if world.get_entity(local_entity).is_some() {
+                            // the entity moved to a different replication group: the remote sends a spawn
+                            // in the new group, along with all the replicated components of the entity
+                            if previous_group_id.is_some_and(|previous| previous != group_id) {
+                                debug!(
+                                    ?remote_entity,
+                                    ?local_entity,
+                                    ?previous_group_id,
+                                    ?group_id,
+                                    "Entity migrated to a different replication group"
+                                );
+                                continue;
+                            }
                             warn!(
                                 ?remote_entity,
                                 ?local_entity,
+                                ?duplicate_spawn_policy,
                                 "Received spawn for an entity that already exists"
                             );
+                            if duplicate_spawn_policy == DuplicateSpawnPolicy::Ignore {
+                                ignored_entities.push(*remote_entity);
+                            }
                             continue;
                         }
                         warn!("Received spawn for an entity that is already in our entity mapping! Not spawning");
@@ -878,6 +995,11 @@ impl GroupChannel {
                     let Some(mut entity_mut) = world.get_entity_mut(local_entity) else {
                         // TODO: ignore the entity in the next steps because it does not exist!
                         error!("Received ReuseEntity({local_entity:?}) but the entity does not exist in the world");
+                        record_local_replication_error(
+                            world,
+                            local_entity,
+                            ReplicationSkipReason::ReuseMissingEntity,
+                        );
                         continue;
                     };
                     entity_mut.insert(Replicated { from: remote });
@@ -890,6 +1012,20 @@ impl GroupChannel {
 
         for (entity, actions) in message.actions.into_iter() {
             debug!(remote_entity = ?entity, "Received entity actions");
+            if ignored_entities.contains(&entity) {
+                trace!(remote_entity = ?entity, "Ignoring actions for duplicate spawn");
+                continue;
+            }
+            // the entity already migrated to another group: these actions are stale, and the
+            // migration spawn re-sent all the components of the entity in the new group
+            if actions.spawn != SpawnAction::Spawn
+                && remote_entity_to_group
+                    .get(&entity)
+                    .is_some_and(|current| *current != group_id)
+            {
+                trace!(remote_entity = ?entity, ?group_id, "Ignoring stale actions from the entity's previous replication group");
+                continue;
+            }
 
             // despawn
             if actions.spawn == SpawnAction::Despawn {
@@ -903,7 +1039,12 @@ impl GroupChannel {
                     events.push_despawn(local_entity);
                     remote_entity_to_group.remove(&entity);
                 } else {
-                    error!("Received despawn for an entity that does not exist")
+                    error!("Received despawn for an entity that does not exist");
+                    record_remote_replication_error(
+                        world,
+                        entity,
+                        ReplicationSkipReason::DespawnUnmappedEntity,
+                    );
                 }
                 continue;
             }
@@ -911,12 +1052,24 @@ impl GroupChannel {
             // safety: we know by this point that the entity exists
             let Some(mut local_entity_mut) = remote_entity_map.get_by_remote(world, entity) else {
                 error!(?entity, "cannot find entity");
+                record_remote_replication_error(
+                    world,
+                    entity,
+                    ReplicationSkipReason::UnmappedEntity,
+                );
                 continue;
             };
+            let local_entity = local_entity_mut.id();
             if !Self::authority_check(&mut local_entity_mut, remote) {
                 trace!("Ignored a replication action received from peer {:?} that does not have authority over the entity: {:?}", remote, entity);
+                record_local_replication_error(
+                    world,
+                    local_entity,
+                    ReplicationSkipReason::NoAuthority,
+                );
                 continue;
             }
+            let mut write_error = None;
 
             // NOTE: 2 options
             //  - send the raw data to a separate typed system
@@ -925,7 +1078,9 @@ impl GroupChannel {
             // inserts
             // TODO: remove updates that are duplicate for the same component
             debug!(remote_entity = ?entity, "Received InsertComponent");
-            for component in actions.insert {
+            let mut inserts = actions.insert;
+            component_registry.sort_by_apply_priority(&mut inserts);
+            for component in inserts {
                 // TODO: reuse a single reader that reads through the entire message
                 let mut reader = Reader::from(component);
                 let _ = component_registry
@@ -937,7 +1092,8 @@ impl GroupChannel {
                         events,
                     )
                     .inspect_err(|e| {
-                        error!("could not write the component to the entity: {:?}", e)
+                        error!("could not write the component to the entity: {:?}", e);
+                        write_error = Some(e.to_string());
                     });
 
                 // TODO: special-case for pre-spawned entities: we receive them from a client, but then we
@@ -970,11 +1126,25 @@ impl GroupChannel {
                         events,
                     )
                     .inspect_err(|e| {
-                        error!("could not write the component to the entity: {:?}", e)
+                        error!("could not write the component to the entity: {:?}", e);
+                        write_error = Some(e.to_string());
                     });
             }
+            if let Some(error) = write_error {
+                record_local_replication_error(
+                    world,
+                    local_entity,
+                    ReplicationSkipReason::ComponentWrite(error),
+                );
+            }
         }
-        self.update_confirmed_tick(world, group_id, remote_tick, remote_entity_map);
+        self.update_confirmed_tick(
+            world,
+            group_id,
+            remote_tick,
+            remote_entity_map,
+            remote_entity_to_group,
+        );
     }
 
     // TODO: should we accept updates from the client that lost authority if they are from a
@@ -1004,6 +1174,7 @@ impl GroupChannel {
         message: EntityUpdatesMessage,
         events: &mut ConnectionEvents,
         remote_entity_map: &mut RemoteEntityMap,
+        remote_entity_to_group: &EntityHashMap<Entity, ReplicationGroupId>,
     ) {
         let group_id = message.group_id;
         debug!(?remote_tick, ?message, "Received replication updates");
@@ -1014,17 +1185,37 @@ impl GroupChannel {
         }
         for (entity, components) in message.updates.into_iter() {
             debug!(?components, remote_entity = ?entity, "Received UpdateComponent");
+            // the entity migrated to a different replication group, the updates from this group are outdated
+            if remote_entity_to_group
+                .get(&entity)
+                .is_some_and(|entity_group_id| *entity_group_id != group_id)
+            {
+                trace!(remote_entity = ?entity, ?group_id, "Ignoring update from a previous replication group");
+                continue;
+            }
             let Some(mut local_entity_mut) = remote_entity_map.get_by_remote(world, entity) else {
                 // we can get a few buffered updates after the entity has been despawned
                 // those are the updates that we received before the despawn action message, but with a tick
                 // later than the despawn action message
                 info!(remote_entity = ?entity, "update for entity that doesn't exist?");
+                record_remote_replication_error(
+                    world,
+                    entity,
+                    ReplicationSkipReason::UnmappedEntity,
+                );
                 continue;
             };
+            let local_entity = local_entity_mut.id();
             if !Self::authority_check(&mut local_entity_mut, remote) {
                 trace!("Ignored a replication update received from peer {:?} that does not have authority over the entity: {:?}", remote, entity);
+                record_local_replication_error(
+                    world,
+                    local_entity,
+                    ReplicationSkipReason::NoAuthority,
+                );
                 continue;
             };
+            let mut write_error = None;
             if !Self::authority_check(&mut local_entity_mut, remote) {
                 debug!("authority check failed for entity: {:?}", entity);
                 continue;
@@ -1040,11 +1231,25 @@ impl GroupChannel {
                         events,
                     )
                     .inspect_err(|e| {
-                        error!("could not write the component to the entity: {:?}", e)
+                        error!("could not write the component to the entity: {:?}", e);
+                        write_error = Some(e.to_string());
                     });
             }
+            if let Some(error) = write_error {
+                record_local_replication_error(
+                    world,
+                    local_entity,
+                    ReplicationSkipReason::ComponentWrite(error),
+                );
+            }
         }
-        self.update_confirmed_tick(world, group_id, remote_tick, remote_entity_map);
+        self.update_confirmed_tick(
+            world,
+            group_id,
+            remote_tick,
+            remote_entity_map,
+            remote_entity_to_group,
+        );
     }
 
     /// Update the Confirmed tick for all entities in the replication group
@@ -1059,6 +1264,7 @@ impl GroupChannel {
         group_id: ReplicationGroupId,
         remote_tick: Tick,
         remote_entity_map: &mut RemoteEntityMap,
+        remote_entity_to_group: &EntityHashMap<Entity, ReplicationGroupId>,
     ) {
         // TODO: maybe get the confirmed tick from the apply_world message directly?
         // // let confirmed_tick = self.group_channels.get(&group_id).unwrap().latest_tick;
@@ -1069,6 +1275,13 @@ impl GroupChannel {
         // }
 
         self.remote_entities.iter().for_each(|remote_entity| {
+            // skip the entities that migrated to a different replication group
+            if remote_entity_to_group
+                .get(remote_entity)
+                .is_some_and(|entity_group_id| *entity_group_id != group_id)
+            {
+                return;
+            }
             if let Some(mut local_entity_mut) =
                 remote_entity_map.get_by_remote(world, *remote_entity)
             {
@@ -1084,7 +1297,11 @@ impl GroupChannel {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::serialize::writer::Writer;
+    use crate::shared::replication::error::ReplicationErrors;
     use crate::shared::replication::EntityActions;
+    use crate::tests::protocol::{ComponentSyncModeFull, ComponentSyncModeOnce};
+    use bevy::prelude::{Has, OnAdd, Query, ResMut, Resource, Trigger};
 
     /// Test that the UpdatesIterator works correctly, when we want to iterate through
     /// the buffered updates we have received
@@ -1425,4 +1642,286 @@ mod tests {
             local_entity
         );
     }
+
+    /// Test that receiving a spawn for a remote entity that is already mapped does not
+    /// create a duplicate local entity, for each [`DuplicateSpawnPolicy`]
+    #[test]
+    fn test_recv_duplicate_spawn() {
+        for (policy, expected) in [
+            (DuplicateSpawnPolicy::Ignore, ComponentSyncModeFull(1.0)),
+            (DuplicateSpawnPolicy::Resync, ComponentSyncModeFull(2.0)),
+        ] {
+            let mut manager = ReplicationReceiver::new().with_duplicate_spawn_policy(policy);
+            let mut world = World::new();
+            let mut component_registry = ComponentRegistry::default();
+            component_registry.register_component::<ComponentSyncModeFull>();
+            component_registry.set_replication_fns::<ComponentSyncModeFull>(&mut world);
+            let mut events = ConnectionEvents::default();
+            let remote_entity = Entity::from_raw(1000);
+            let group_id = ReplicationGroupId(0);
+
+            let spawn_message = |sequence_id: MessageId, value: f32| {
+                let mut writer = Writer::default();
+                component_registry
+                    .serialize(&mut ComponentSyncModeFull(value), &mut writer, None)
+                    .unwrap();
+                EntityActionsMessage {
+                    group_id,
+                    sequence_id,
+                    actions: vec![(
+                        remote_entity,
+                        EntityActions {
+                            spawn: SpawnAction::Spawn,
+                            insert: vec![writer.to_bytes()],
+                            remove: Default::default(),
+                            updates: vec![],
+                        },
+                    )],
+                }
+            };
+            let first = spawn_message(MessageId(0), 1.0);
+            let duplicate = spawn_message(MessageId(1), 2.0);
+
+            manager.recv_actions(first, Tick(0));
+            manager.apply_world(&mut world, None, &component_registry, Tick(0), &mut events);
+            let local_entity = manager.remote_entity_map.get_local(remote_entity).unwrap();
+
+            // receive a duplicate spawn for the same remote entity
+            manager.recv_actions(duplicate, Tick(1));
+            manager.apply_world(&mut world, None, &component_registry, Tick(1), &mut events);
+
+            // check that no duplicate entity was spawned and that the mapping is unchanged
+            assert_eq!(world.entities().len(), 1);
+            assert_eq!(
+                manager.remote_entity_map.get_local(remote_entity).unwrap(),
+                local_entity
+            );
+            assert_eq!(
+                world.get::<ComponentSyncModeFull>(local_entity).unwrap(),
+                &expected
+            );
+        }
+    }
+
+    /// Test that components are inserted in the order of their apply priority when an entity is spawned
+    #[test]
+    fn test_recv_spawn_apply_priority() {
+        #[derive(Resource, Default)]
+        struct OnceWasPresent(Vec<bool>);
+
+        let mut manager = ReplicationReceiver::new();
+        let mut world = World::new();
+        world.init_resource::<OnceWasPresent>();
+        // the observer for ComponentSyncModeFull expects ComponentSyncModeOnce to already be present
+        world.observe(
+            |trigger: Trigger<OnAdd, ComponentSyncModeFull>,
+             query: Query<Has<ComponentSyncModeOnce>>,
+             mut present: ResMut<OnceWasPresent>| {
+                present.0.push(query.get(trigger.entity()).unwrap());
+            },
+        );
+        let mut component_registry = ComponentRegistry::default();
+        component_registry.register_component::<ComponentSyncModeFull>();
+        component_registry.set_replication_fns::<ComponentSyncModeFull>(&mut world);
+        component_registry.register_component::<ComponentSyncModeOnce>();
+        component_registry.set_replication_fns::<ComponentSyncModeOnce>(&mut world);
+        component_registry.set_apply_priority::<ComponentSyncModeOnce>(1);
+        let mut events = ConnectionEvents::default();
+
+        let mut writer = Writer::default();
+        component_registry
+            .serialize(&mut ComponentSyncModeFull(1.0), &mut writer, None)
+            .unwrap();
+        let full = writer.split();
+        component_registry
+            .serialize(&mut ComponentSyncModeOnce(1.0), &mut writer, None)
+            .unwrap();
+        let once = writer.split();
+        let remote_entity = Entity::from_raw(1000);
+        // the components are received in the wrong order
+        let message = EntityActionsMessage {
+            group_id: ReplicationGroupId(0),
+            sequence_id: MessageId(0),
+            actions: vec![(
+                remote_entity,
+                EntityActions {
+                    spawn: SpawnAction::Spawn,
+                    insert: vec![full, once],
+                    remove: Default::default(),
+                    updates: vec![],
+                },
+            )],
+        };
+        manager.recv_actions(message, Tick(0));
+        manager.apply_world(&mut world, None, &component_registry, Tick(0), &mut events);
+
+        let local_entity = manager.remote_entity_map.get_local(remote_entity).unwrap();
+        assert!(world.get::<ComponentSyncModeFull>(local_entity).is_some());
+        assert!(world.get::<ComponentSyncModeOnce>(local_entity).is_some());
+        assert_eq!(world.resource::<OnceWasPresent>().0, vec![true]);
+    }
+
+    /// Test that the number of entities spawned every frame is limited by `max_spawns_per_frame`,
+    /// and that the remaining spawns are applied during the next frames
+    #[test]
+    fn test_recv_max_spawns_per_frame() {
+        let mut manager = ReplicationReceiver::new().with_max_spawns_per_frame(Some(3));
+        let mut world = World::new();
+        let component_registry = ComponentRegistry::default();
+        let mut events = ConnectionEvents::default();
+
+        // receive a large batch of spawns, each in its own replication group
+        for i in 0..10 {
+            let message = EntityActionsMessage {
+                group_id: ReplicationGroupId(i),
+                sequence_id: MessageId(0),
+                actions: vec![(
+                    Entity::from_raw(1000 + i as u32),
+                    EntityActions {
+                        spawn: SpawnAction::Spawn,
+                        insert: vec![],
+                        remove: Default::default(),
+                        updates: vec![],
+                    },
+                )],
+            };
+            manager.recv_actions(message, Tick(0));
+        }
+
+        let mut spawned_per_frame = vec![];
+        for _ in 0..5 {
+            let before = world.entities().len();
+            manager.apply_world(&mut world, None, &component_registry, Tick(0), &mut events);
+            spawned_per_frame.push(world.entities().len() - before);
+        }
+        assert_eq!(spawned_per_frame, vec![3, 3, 3, 1, 0]);
+        assert_eq!(manager.remote_entity_map.remote_to_local.0.len(), 10);
+    }
+
+    /// Test that the actions received for a remote entity that is not mapped are recorded
+    /// in the [`ReplicationErrors`](crate::prelude::ReplicationErrors) resource
+    #[test]
+    fn test_recv_unmapped_entity_error() {
+        let mut manager = ReplicationReceiver::new();
+        let mut world = World::new();
+        world.init_resource::<ReplicationErrors>();
+        let mut component_registry = ComponentRegistry::default();
+        component_registry.register_component::<ComponentSyncModeFull>();
+        component_registry.set_replication_fns::<ComponentSyncModeFull>(&mut world);
+        let mut events = ConnectionEvents::default();
+        let remote_entity = Entity::from_raw(1000);
+
+        let mut writer = Writer::default();
+        component_registry
+            .serialize(&mut ComponentSyncModeFull(1.0), &mut writer, None)
+            .unwrap();
+        let message = EntityActionsMessage {
+            group_id: ReplicationGroupId(0),
+            sequence_id: MessageId(0),
+            actions: vec![(
+                remote_entity,
+                EntityActions {
+                    spawn: SpawnAction::None,
+                    insert: vec![],
+                    remove: Default::default(),
+                    updates: vec![writer.to_bytes()],
+                },
+            )],
+        };
+        manager.recv_actions(message, Tick(0));
+        manager.apply_world(&mut world, None, &component_registry, Tick(0), &mut events);
+
+        assert_eq!(
+            world
+                .resource::<ReplicationErrors>()
+                .get_remote(remote_entity),
+            Some(&ReplicationSkipReason::UnmappedEntity)
+        );
+        // the remote entity is not confused with a local entity that has the same id
+        assert!(world
+            .resource::<ReplicationErrors>()
+            .get_local(remote_entity)
+            .is_none());
+    }
+
+    /// Test that an update that arrives before the spawn of its entity is buffered and applied once
+    /// the spawn arrives, and that updates that wait for too long are dropped
+    #[test]
+    fn test_recv_update_before_spawn() {
+        let mut manager = ReplicationReceiver::new()
+            .with_early_updates_policy(EarlyUpdatesPolicy::BufferWithTimeout { max_ticks: 10 });
+        let mut world = World::new();
+        let mut component_registry = ComponentRegistry::default();
+        component_registry.register_component::<ComponentSyncModeFull>();
+        component_registry.set_replication_fns::<ComponentSyncModeFull>(&mut world);
+        let mut events = ConnectionEvents::default();
+        let group_id = ReplicationGroupId(0);
+        let remote_entity = Entity::from_raw(1000);
+
+        let mut writer = Writer::default();
+        component_registry
+            .serialize(&mut ComponentSyncModeFull(1.0), &mut writer, None)
+            .unwrap();
+        let update = writer.to_bytes();
+
+        // the update arrives before the spawn action
+        manager.recv_updates(
+            EntityUpdatesMessage {
+                group_id,
+                last_action_tick: Some(Tick(1)),
+                updates: vec![(remote_entity, vec![update.clone()])],
+            },
+            Tick(2),
+        );
+        manager.apply_world(&mut world, None, &component_registry, Tick(2), &mut events);
+        assert!(manager.remote_entity_map.get_local(remote_entity).is_none());
+
+        // the spawn arrives within the window: the buffered update is applied
+        manager.recv_actions(
+            EntityActionsMessage {
+                group_id,
+                sequence_id: MessageId(0),
+                actions: vec![(
+                    remote_entity,
+                    EntityActions {
+                        spawn: SpawnAction::Spawn,
+                        insert: vec![],
+                        remove: Default::default(),
+                        updates: vec![],
+                    },
+                )],
+            },
+            Tick(1),
+        );
+        manager.apply_world(&mut world, None, &component_registry, Tick(3), &mut events);
+        let local_entity = manager
+            .remote_entity_map
+            .get_local(remote_entity)
+            .expect("the entity should be spawned");
+        assert_eq!(
+            world.get::<ComponentSyncModeFull>(local_entity),
+            Some(&ComponentSyncModeFull(1.0))
+        );
+
+        // updates that wait for their action for longer than the timeout are dropped
+        for remote_tick in [Tick(5), Tick(10), Tick(20)] {
+            manager.recv_updates(
+                EntityUpdatesMessage {
+                    group_id,
+                    last_action_tick: Some(Tick(4)),
+                    updates: vec![(remote_entity, vec![update.clone()])],
+                },
+                remote_tick,
+            );
+        }
+        assert_eq!(
+            manager
+                .group_channels
+                .get(&group_id)
+                .unwrap()
+                .buffered_updates
+                .len(),
+            2
+        );
+    }
 }
@@ -1,7 +1,7 @@
 //! General struct handling replication
 use std::iter::Extend;
 
-use crate::channel::builder::{EntityActionsChannel, EntityUpdatesChannel};
+use crate::channel::builder::{EntityActionsChannel, EntityUpdatesChannel, SnapshotChannel};
 use bevy::ecs::component::Tick as BevyTick;
 use bevy::ecs::entity::EntityHash;
 use bevy::prelude::Entity;
@@ -9,7 +9,7 @@ use bevy::ptr::Ptr;
 use bevy::utils::{hashbrown, HashMap};
 use bytes::Bytes;
 use crossbeam_channel::Receiver;
-use tracing::{debug, error, trace};
+use tracing::{debug, error, info, trace};
 #[cfg(feature = "trace")]
 use tracing::{instrument, Level};
 
@@ -20,12 +20,16 @@ use crate::prelude::{
     ChannelKind, ComponentRegistry, PacketError, RemoteEntityMap, Tick, TimeManager,
 };
 use crate::protocol::component::{ComponentKind, ComponentNetId};
+use crate::protocol::registry::NetId;
+use crate::serialize::reader::Reader;
 use crate::serialize::writer::Writer;
 use crate::serialize::{SerializationError, ToBytes};
 use crate::shared::replication::components::ReplicationGroupId;
-use crate::shared::replication::delta::DeltaManager;
+use crate::shared::replication::delta::{DeltaCompressionStats, DeltaManager};
 use crate::shared::replication::error::ReplicationError;
+use crate::shared::replication::layout::ComponentLayouts;
 use crate::shared::replication::plugin::{ReplicationConfig, SendUpdatesMode};
+use crate::transport::middleware::compression::CompressionConfig;
 #[cfg(test)]
 use {
     super::{EntityActionsMessage, EntityUpdatesMessage},
@@ -65,6 +69,9 @@ pub(crate) struct ReplicationSender {
     pub group_with_updates: EntityHashSet<ReplicationGroupId>,
     /// Buffer to so that we have an ordered receiver per group
     pub group_channels: EntityHashMap<ReplicationGroupId, GroupChannel>,
+    /// Replication group of each entity that is spawned on the remote, so that we don't have to
+    /// search through every group channel
+    entity_to_group: EntityHashMap<Entity, ReplicationGroupId>,
 
     // PRIORITY
     /// Get notified whenever a message for a given ReplicationGroup was actually sent
@@ -73,8 +80,20 @@ pub(crate) struct ReplicationSender {
     /// We update the `send_tick` only when the message was actually sent.
     pub message_send_receiver: Receiver<MessageId>,
 
+    /// Replication groups (and the tick of the update message) for which an update message was acked
+    /// since the last time we emitted the corresponding events
+    pub(crate) acked_groups: Vec<(ReplicationGroupId, Tick)>,
+
     replication_config: ReplicationConfig,
     bandwidth_cap_enabled: bool,
+    /// True if the updates are paused because the remote stopped acking our packets.
+    /// See [`ReplicationConfig::backpressure_timeout`]
+    pub(crate) updates_paused: bool,
+    /// Number of incremental/full diffs sent for each delta-compressed component
+    pub(crate) delta_compression_stats: HashMap<ComponentKind, DeltaCompressionStats>,
+    /// True until the initial snapshot is sent to the remote.
+    /// See [`ReplicationConfig::initial_snapshot_compression`]
+    snapshot_pending: bool,
 }
 
 impl ReplicationSender {
@@ -94,10 +113,18 @@ impl ReplicationSender {
             group_with_updates: EntityHashSet::default(),
             // pending_unique_components: EntityHashMap::default(),
             group_channels: Default::default(),
+            entity_to_group: Default::default(),
             replication_config,
             // PRIORITY
             message_send_receiver,
+            acked_groups: Vec::new(),
             bandwidth_cap_enabled,
+            updates_paused: false,
+            delta_compression_stats: HashMap::default(),
+            snapshot_pending: !matches!(
+                replication_config.initial_snapshot_compression,
+                CompressionConfig::None
+            ),
         }
     }
 
@@ -247,6 +274,7 @@ impl ReplicationSender {
 
                     // update the acks for the delta manager
                     delta_manager.receive_ack(tick, group_id, component_registry);
+                    self.acked_groups.push((group_id, tick));
                 } else {
                     error!("Received an update message-id ack but the corresponding group channel does not exist");
                 }
@@ -297,6 +325,27 @@ impl ReplicationSender {
             .base_priority = priority;
     }
 
+    /// Update the importance of a given group for the remote peer.
+    ///
+    /// The importance multiplies the base priority of the group when accumulating priority,
+    /// so that more important groups are sent first when the bandwidth is limited.
+    pub(crate) fn update_importance(&mut self, group_id: ReplicationGroupId, importance: f32) {
+        self.group_channels.entry(group_id).or_default().importance = importance;
+    }
+
+    /// Forget which updates were sent and acked by the remote, so that the next updates are not
+    /// computed from a state that the remote previously acked.
+    ///
+    /// The message ids of the actions are kept, because the remote still expects them in order.
+    pub(crate) fn reset_acks(&mut self) {
+        self.updates_message_id_to_group_id.clear();
+        for channel in self.group_channels.values_mut() {
+            channel.send_tick = None;
+            channel.ack_bevy_tick = None;
+            channel.ack_tick = None;
+        }
+    }
+
     // TODO: how can I emit metrics here that contain the channel kind?
     //  use a OnceCell that gets set with the channel name mapping when the protocol is finalized?
     //  the other option is to have wrappers in Connection, but that's pretty ugly
@@ -306,13 +355,10 @@ impl ReplicationSender {
     // #[cfg_attr(feature = "trace", instrument(level = Level::INFO, skip_all))]
     pub(crate) fn prepare_entity_spawn(&mut self, entity: Entity, group_id: ReplicationGroupId) {
         self.group_with_actions.insert(group_id);
-        self.group_channels
-            .entry(group_id)
-            .or_default()
-            .pending_actions
-            .entry(entity)
-            .or_default()
-            .spawn = SpawnAction::Spawn;
+        let channel = self.group_channels.entry(group_id).or_default();
+        channel.entities.insert(entity);
+        channel.pending_actions.entry(entity).or_default().spawn = SpawnAction::Spawn;
+        self.entity_to_group.insert(entity, group_id);
     }
 
     /// Host wants to start replicating an entity, but instead of spawning a new entity, it wants to reuse an existing entity
@@ -325,25 +371,102 @@ impl ReplicationSender {
         remote_entity: Entity,
     ) {
         self.group_with_actions.insert(group_id);
-        self.group_channels
-            .entry(group_id)
-            .or_default()
+        let channel = self.group_channels.entry(group_id).or_default();
+        channel.entities.insert(local_entity);
+        channel
             .pending_actions
             .entry(local_entity)
             .or_default()
             .spawn = SpawnAction::Reuse(remote_entity);
+        self.entity_to_group.insert(local_entity, group_id);
     }
 
     #[cfg_attr(feature = "trace", instrument(level = Level::INFO, skip_all))]
     pub(crate) fn prepare_entity_despawn(&mut self, entity: Entity, group_id: ReplicationGroupId) {
         self.group_with_actions.insert(group_id);
+        let channel = self.group_channels.entry(group_id).or_default();
+        channel.entities.remove(&entity);
+        channel.pending_actions.entry(entity).or_default().spawn = SpawnAction::Despawn;
+        if self.entity_to_group.get(&entity) == Some(&group_id) {
+            self.entity_to_group.remove(&entity);
+        }
+    }
+
+    /// The entity moved to a different replication group: remove it from its previous group and
+    /// buffer a spawn in the new group, so that the remote moves the entity to the new group.
+    ///
+    /// Returns false if the entity was not replicated as part of a different group.
+    pub(crate) fn prepare_entity_group_migration(
+        &mut self,
+        entity: Entity,
+        group_id: ReplicationGroupId,
+    ) -> bool {
+        let Some(previous_group_id) = self
+            .entity_to_group
+            .get(&entity)
+            .copied()
+            .filter(|previous_group_id| *previous_group_id != group_id)
+        else {
+            return false;
+        };
+        let Some(previous_channel) = self.group_channels.get_mut(&previous_group_id) else {
+            return false;
+        };
+        previous_channel.entities.remove(&entity);
+        // the pending actions and updates are replaced by the spawn and the inserts in the new group
+        previous_channel.pending_actions.remove(&entity);
+        previous_channel.pending_updates.remove(&entity);
+        if previous_channel.pending_actions.is_empty() {
+            self.group_with_actions.remove(&previous_group_id);
+        }
+        if previous_channel.pending_updates.is_empty() {
+            self.group_with_updates.remove(&previous_group_id);
+        }
+        debug!(
+            ?entity,
+            ?previous_group_id,
+            ?group_id,
+            "Migrating entity to a different replication group"
+        );
+        self.prepare_entity_spawn(entity, group_id);
+        true
+    }
+
+    /// Returns the entities that currently belong to the replication group.
+    ///
+    /// This includes the entities that were spawned on the remote as part of the group (and not despawned since),
+    /// as well as the entities that have actions or updates pending for this group.
+    pub(crate) fn group_entities(&self, group_id: ReplicationGroupId) -> Vec<Entity> {
+        let Some(channel) = self.group_channels.get(&group_id) else {
+            return vec![];
+        };
+        let mut entities = channel.entities.clone();
+        entities.extend(
+            channel
+                .pending_actions
+                .iter()
+                .filter(|(_, actions)| actions.spawn != SpawnAction::Despawn)
+                .map(|(entity, _)| *entity),
+        );
+        entities.extend(channel.pending_updates.keys().copied());
+        entities.into_iter().collect()
+    }
+
+    /// Returns the replication group of an entity that was spawned on the remote
+    pub(crate) fn entity_group_id(&self, entity: Entity) -> Option<ReplicationGroupId> {
+        self.entity_to_group.get(&entity).copied()
+    }
+
+    /// Returns the latest tick of the replication group that the remote has acknowledged.
+    ///
+    /// All the update messages of the group sent up to that tick have been received by the remote.
+    /// Only update messages advance this tick: entity actions are sent on a reliable channel and are
+    /// not tracked here, so a group that only sent actions since its last acked update keeps its
+    /// previous acked tick (or `None`).
+    pub(crate) fn group_acked_tick(&self, group_id: ReplicationGroupId) -> Option<Tick> {
         self.group_channels
-            .entry(group_id)
-            .or_default()
-            .pending_actions
-            .entry(entity)
-            .or_default()
-            .spawn = SpawnAction::Despawn;
+            .get(&group_id)
+            .and_then(|channel| channel.ack_tick)
     }
 
     // we want to send all component inserts that happen together for the same entity in a single message
@@ -402,6 +525,33 @@ impl ReplicationSender {
             .push(raw_data);
     }
 
+    /// Buffer a component update, replacing any pending update for the same entity and component
+    /// so that only the most recent value gets sent
+    pub(crate) fn prepare_latest_component_update(
+        &mut self,
+        entity: Entity,
+        group_id: ReplicationGroupId,
+        raw_data: Bytes,
+    ) {
+        self.group_with_updates.insert(group_id);
+        let updates = self
+            .group_channels
+            .entry(group_id)
+            .or_default()
+            .pending_updates
+            .entry(entity)
+            .or_default();
+        let net_id = |data: &Bytes| NetId::from_bytes(&mut Reader::from(data.clone())).ok();
+        let new_net_id = net_id(&raw_data);
+        match updates
+            .iter_mut()
+            .find(|data| new_net_id.is_some() && net_id(data) == new_net_id)
+        {
+            Some(pending) => *pending = raw_data,
+            None => updates.push(raw_data),
+        }
+    }
+
     /// Create a component update.
     #[cfg_attr(feature = "trace", instrument(level = Level::INFO, skip_all))]
     #[allow(clippy::too_many_arguments)]
@@ -418,6 +568,7 @@ impl ReplicationSender {
         remote_entity_map: &mut RemoteEntityMap,
     ) -> Result<(), ReplicationError> {
         let group_channel = self.group_channels.entry(group_id).or_default();
+        let incremental = group_channel.ack_tick.is_some();
         // Get the latest acked tick for this entity/component
         let raw_data = group_channel
             .ack_tick
@@ -470,6 +621,12 @@ impl ReplicationSender {
         // use the network entity when serializing
         let entity = remote_entity_map.to_remote(entity);
         self.prepare_component_update(entity, group_id, raw_data);
+        let stats = self.delta_compression_stats.entry(kind).or_default();
+        if incremental {
+            stats.incremental_diffs += 1;
+        } else {
+            stats.full_diffs += 1;
+        }
         Ok(())
     }
 
@@ -539,16 +696,20 @@ impl ReplicationSender {
         let priority_multiplier = 1.0;
         self.group_channels.values_mut().for_each(|channel| {
             trace!(
-                "in accumulate priority: accumulated={:?} base={:?} multiplier={:?}, send_interval={:?}, time_manager_delta={:?}",
-                channel.accumulated_priority, channel.base_priority, priority_multiplier,
+                "in accumulate priority: accumulated={:?} base={:?} importance={:?} multiplier={:?}, send_interval={:?}, time_manager_delta={:?}",
+                channel.accumulated_priority, channel.base_priority, channel.importance, priority_multiplier,
                 self.replication_config.send_interval.as_nanos(),
                 time_manager.delta().as_nanos()
             );
-            channel.accumulated_priority += channel.base_priority * priority_multiplier;
+            channel.accumulated_priority +=
+                channel.base_priority * channel.importance * priority_multiplier;
         });
     }
 
     /// Prepare the [`EntityActionsMessage`](super::EntityActionsMessage) messages to send.
+    ///
+    /// The first actions sent to the remote are grouped in a single compressed snapshot if
+    /// [`ReplicationConfig::initial_snapshot_compression`] is enabled.
     #[cfg_attr(feature = "trace", instrument(level = Level::INFO, skip_all))]
     pub(crate) fn send_actions_messages(
         &mut self,
@@ -558,6 +719,11 @@ impl ReplicationSender {
         writer: &mut Writer,
         message_manager: &mut MessageManager,
     ) -> Result<(), PacketError> {
+        // the snapshot is only sent once there are actions, since the spawns for a newly connected
+        // remote are only buffered on the next replication send
+        let send_snapshot = self.snapshot_pending && !self.group_with_actions.is_empty();
+        let mut snapshot = Vec::new();
+        let mut snapshot_priority = 0.0_f32;
         self.group_with_actions.drain().try_for_each(|group_id| {
             // SAFETY: we know that the group_channel exists since group_with_actions contains the group_id
             let channel = self.group_channels.get_mut(&group_id).unwrap();
@@ -602,6 +768,11 @@ impl ReplicationSender {
                 actions,
             };
             trace!("final action messages to send: {:?}", message);
+            if send_snapshot {
+                snapshot_priority = snapshot_priority.max(priority);
+                snapshot.push(message);
+                return Ok(());
+            }
 
             // TODO: we had to put this here because of the borrow checker, but it's not ideal,
             //  the replication send should normally just an iterator of messages to send
@@ -626,7 +797,29 @@ impl ReplicationSender {
             channel.pending_actions.clear();
 
             Ok::<(), PacketError>(())
-        })
+        })?;
+        if send_snapshot {
+            self.snapshot_pending = false;
+            // compress all the messages together before they get fragmented
+            snapshot.to_bytes(writer)?;
+            let snapshot_bytes = writer.split();
+            let payload = self
+                .replication_config
+                .initial_snapshot_compression
+                .compress_payload(&snapshot_bytes)?;
+            debug!(
+                num_groups = snapshot.len(),
+                uncompressed_bytes = snapshot_bytes.len(),
+                compressed_bytes = payload.len(),
+                "Sending initial replication snapshot"
+            );
+            message_manager.buffer_send_with_priority(
+                Bytes::from(payload),
+                ChannelKind::of::<SnapshotChannel>(),
+                snapshot_priority,
+            )?;
+        }
+        Ok(())
     }
 
     /// Prepare the [`EntityUpdateMessage`] to send
@@ -667,11 +860,45 @@ impl ReplicationSender {
         writer: &mut Writer,
         message_manager: &mut MessageManager,
     ) -> Result<(), PacketError> {
+        // nothing to pause if there are no updates to send
+        if self.group_with_updates.is_empty() {
+            return Ok(());
+        }
+        // only pause if our packets are actually going unacked: a remote that has nothing to send
+        // still sends acks for the packets it receives
+        let paused = self
+            .replication_config
+            .backpressure_timeout
+            .zip(message_manager.oldest_unacked_packet_age())
+            .is_some_and(|(timeout, age)| age > timeout);
+        if paused != self.updates_paused {
+            self.updates_paused = paused;
+            if paused {
+                info!("Pausing the replication updates because the remote stopped acking packets");
+            } else {
+                info!("Resuming the replication updates");
+            }
+        }
+        if paused {
+            // drop the updates without sending them: the send_tick is not updated so they will be
+            // collected again when the updates resume
+            for group_id in self.group_with_updates.drain() {
+                if let Some(channel) = self.group_channels.get_mut(&group_id) {
+                    channel.pending_updates.clear();
+                }
+            }
+            return Ok(());
+        }
         self.group_with_updates.drain().try_for_each(|group_id| {
             let channel = self.group_channels.get_mut(&group_id).unwrap();
             let updates = std::mem::take(&mut channel.pending_updates);
             trace!(?group_id, "pending updates: {:?}", updates);
             let priority = channel.accumulated_priority;
+            let layouts = if self.replication_config.compact_component_ids {
+                Some(ComponentLayouts::new(&updates)?)
+            } else {
+                None
+            };
             let message = SendEntityUpdatesMessage {
                 group_id,
                 // TODO: as an optimization (to avoid 1 byte for the Option), we can use `last_action_tick = tick`
@@ -681,6 +908,7 @@ impl ReplicationSender {
                 //  updates without sending any action before that)
                 last_action_tick: channel.last_action_tick,
                 updates,
+                layouts,
             };
 
             // message.emit_send_logs("EntityUpdatesChannel");
@@ -737,6 +965,8 @@ pub struct GroupChannel {
     /// to collect new replication messages
     pub pending_actions: EntityHashMap<Entity, EntityActions>,
     pub pending_updates: EntityHashMap<Entity, Vec<Bytes>>,
+    /// Entities that were spawned on the remote as part of this group, and not despawned since
+    pub entities: EntityHashSet<Entity>,
     pub actions_next_send_message_id: MessageId,
 
     // TODO: maybe also keep track of which Tick this bevy-tick corresponds to? (will enable doing diff-compression)
@@ -766,6 +996,9 @@ pub struct GroupChannel {
     /// for this group because of the bandwidth cap, in which case it will be accumulated.
     pub accumulated_priority: f32,
     pub base_priority: f32,
+    /// How important this group is for the remote peer (for example based on the distance
+    /// to the entity controlled by the client). Multiplies the `base_priority`.
+    pub importance: f32,
 }
 
 impl Default for GroupChannel {
@@ -773,6 +1006,7 @@ impl Default for GroupChannel {
         Self {
             pending_updates: EntityHashMap::default(),
             pending_actions: EntityHashMap::default(),
+            entities: EntityHashSet::default(),
             actions_next_send_message_id: MessageId(0),
             send_tick: None,
             ack_bevy_tick: None,
@@ -780,17 +1014,21 @@ impl Default for GroupChannel {
             last_action_tick: None,
             accumulated_priority: 0.0,
             base_priority: 1.0,
+            importance: 1.0,
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::prelude::server::Replicate;
-    use crate::prelude::ClientId;
+    use bevy::utils::{Duration, HashSet};
+
+    use crate::prelude::client::ClientConfig;
+    use crate::prelude::server::{Replicate, ServerConfig};
+    use crate::prelude::{ClientId, Replicated, ReplicationGroup, SharedConfig, TickConfig};
     use crate::server::connection::ConnectionManager;
 
-    use crate::tests::protocol::ComponentSyncModeFull;
+    use crate::tests::protocol::{ComponentSyncModeFull, ComponentSyncModeOnce};
     use crate::tests::stepper::{BevyStepper, TEST_CLIENT_ID};
     use bevy::prelude::*;
 
@@ -917,6 +1155,237 @@ mod tests {
         assert_eq!(group_channel.ack_tick, Some(server_tick - 1));
     }
 
+    /// Check that the replication updates are paused while the client doesn't ack packets,
+    /// and that the entity actions are still queued
+    #[test]
+    fn test_backpressure_pauses_updates() {
+        let frame_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(frame_duration),
+            ..default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, ClientConfig::default(), frame_duration);
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ServerConfig>()
+            .replication
+            .backpressure_timeout = Some(Duration::from_millis(100));
+        stepper.init();
+        macro_rules! connection {
+            () => {
+                stepper
+                    .server_app
+                    .world()
+                    .resource::<ConnectionManager>()
+                    .connection(ClientId::Netcode(TEST_CLIENT_ID))
+                    .unwrap()
+            };
+        }
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((ComponentSyncModeFull(0.0), Replicate::default()))
+            .id();
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+
+        // the client stops acking: only the server is updated, and the component changes every frame
+        let mut sent_message_ids = HashSet::new();
+        let mut new_messages = vec![];
+        for i in 1..=40 {
+            stepper
+                .server_app
+                .world_mut()
+                .get_mut::<ComponentSyncModeFull>(server_entity)
+                .unwrap()
+                .0 = i as f32;
+            stepper.advance_time(frame_duration);
+            stepper.server_app.update();
+            let tracked = &connection!()
+                .replication_sender
+                .updates_message_id_to_group_id;
+            new_messages.push(
+                tracked
+                    .keys()
+                    .filter(|message_id| sent_message_ids.insert(**message_id))
+                    .count(),
+            );
+            if i == 20 {
+                // the entity actions are still sent reliably during the pause
+                stepper.server_app.world_mut().spawn(Replicate::default());
+            }
+        }
+        // updates are sent until the timeout, and then paused
+        assert!(new_messages[..5].iter().all(|count| *count == 1));
+        assert!(new_messages[15..].iter().all(|count| *count == 0));
+        assert!(stepper
+            .server_app
+            .world()
+            .resource::<ConnectionManager>()
+            .is_replication_paused(ClientId::Netcode(TEST_CLIENT_ID))
+            .unwrap());
+        // the lost update messages are not tracked anymore
+        assert!(connection!()
+            .replication_sender
+            .updates_message_id_to_group_id
+            .is_empty());
+        assert!(
+            connection!()
+                .message_manager
+                .unacked_count(ChannelKind::of::<EntityActionsChannel>())
+                > 0
+        );
+
+        // the client acks again: the updates resume
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        assert!(!stepper
+            .server_app
+            .world()
+            .resource::<ConnectionManager>()
+            .is_replication_paused(ClientId::Netcode(TEST_CLIENT_ID))
+            .unwrap());
+        let client_entity = stepper
+            .client_app
+            .world()
+            .resource::<crate::client::connection::ConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .unwrap();
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<ComponentSyncModeFull>(client_entity),
+            Some(&ComponentSyncModeFull(40.0))
+        );
+        assert_eq!(
+            stepper
+                .client_app
+                .world_mut()
+                .query::<&Replicated>()
+                .iter(stepper.client_app.world())
+                .count(),
+            2
+        );
+    }
+
+    /// A client that has nothing to send (pings disabled, no inputs) still acks the packets it receives
+    /// if its `ack_packet_interval` is set, so the replication updates are not paused
+    #[test]
+    fn test_backpressure_idle_client() {
+        let frame_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(frame_duration),
+            ..default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, ClientConfig::default(), frame_duration);
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<ClientConfig>()
+            .ping
+            .enabled = false;
+        // the idle client acks the packets it receives
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<ClientConfig>()
+            .packet
+            .ack_packet_interval = Some(Duration::from_millis(100));
+        let mut server_config = stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<ServerConfig>();
+        server_config.ping.enabled = false;
+        server_config.replication.backpressure_timeout = Some(Duration::from_millis(200));
+        stepper.init();
+        stepper
+            .client_app
+            .world_mut()
+            .resource_mut::<crate::client::connection::ConnectionManager>()
+            .set_rtt(Duration::from_millis(20), Duration::default());
+
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((ComponentSyncModeFull(0.0), Replicate::default()))
+            .id();
+        for i in 1..=100 {
+            stepper
+                .server_app
+                .world_mut()
+                .get_mut::<ComponentSyncModeFull>(server_entity)
+                .unwrap()
+                .0 = i as f32;
+            stepper.frame_step();
+            assert!(!stepper
+                .server_app
+                .world()
+                .resource::<ConnectionManager>()
+                .is_replication_paused(ClientId::Netcode(TEST_CLIENT_ID))
+                .unwrap());
+        }
+        let client_connection = stepper
+            .client_app
+            .world()
+            .resource::<crate::client::connection::ConnectionManager>();
+        assert_eq!(client_connection.ping_manager.pings_sent, 0);
+        let client_entity = client_connection
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .unwrap();
+        stepper.frame_step();
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .get::<ComponentSyncModeFull>(client_entity),
+            Some(&ComponentSyncModeFull(100.0))
+        );
+    }
+
+    /// Check that the replication group of each entity is tracked through spawns, migrations and despawns
+    #[test]
+    fn test_entity_group_id() {
+        let (_, rx_ack) = crossbeam_channel::unbounded();
+        let (_, rx_nack) = crossbeam_channel::unbounded();
+        let (_, rx_send) = crossbeam_channel::unbounded();
+        let mut sender = ReplicationSender::new(
+            rx_ack,
+            rx_nack,
+            rx_send,
+            ReplicationConfig::default(),
+            false,
+        );
+        let entity = Entity::from_raw(1);
+        let group_1 = ReplicationGroupId(1);
+        let group_2 = ReplicationGroupId(2);
+        assert_eq!(sender.entity_group_id(entity), None);
+
+        sender.prepare_entity_spawn(entity, group_1);
+        assert_eq!(sender.entity_group_id(entity), Some(group_1));
+
+        // migrating to the same group does nothing
+        assert!(!sender.prepare_entity_group_migration(entity, group_1));
+        assert!(sender.prepare_entity_group_migration(entity, group_2));
+        assert_eq!(sender.entity_group_id(entity), Some(group_2));
+        assert!(!sender
+            .group_channels
+            .get(&group_1)
+            .unwrap()
+            .entities
+            .contains(&entity));
+
+        sender.prepare_entity_despawn(entity, group_2);
+        assert_eq!(sender.entity_group_id(entity), None);
+    }
+
     #[test]
     fn test_send_tick_no_priority() {
         // create fake channels for receiving updates about acks and sends
@@ -1184,4 +1653,239 @@ mod tests {
             Some(Tick(2))
         );
     }
+
+    /// Test that for `latest_only` components, only the most recent pending update is sent
+    #[test]
+    fn test_latest_only_component_update() {
+        let (_, rx_ack) = crossbeam_channel::unbounded();
+        let (_, rx_nack) = crossbeam_channel::unbounded();
+        let (_, rx_send) = crossbeam_channel::unbounded();
+        let mut manager = ReplicationSender::new(
+            rx_ack,
+            rx_nack,
+            rx_send,
+            ReplicationConfig::default(),
+            false,
+        );
+        let mut registry = ComponentRegistry::default();
+        registry.register_component::<ComponentSyncModeFull>();
+        registry.register_component::<ComponentSyncModeOnce>();
+        let mut writer = Writer::default();
+        let mut serialize = |component: &mut dyn FnMut(&mut Writer)| {
+            component(&mut writer);
+            writer.split()
+        };
+
+        let entity = Entity::from_raw(0);
+        let group = ReplicationGroupId(0);
+        manager.group_channels.insert(
+            group,
+            GroupChannel {
+                last_action_tick: Some(Tick(0)),
+                ..Default::default()
+            },
+        );
+
+        // mutate the component several times before sending
+        let mut latest = Bytes::new();
+        for value in [1.0, 2.0, 3.0] {
+            latest = serialize(&mut |writer| {
+                registry
+                    .serialize(&mut ComponentSyncModeFull(value), writer, None)
+                    .unwrap()
+            });
+            manager.prepare_latest_component_update(entity, group, latest.clone());
+        }
+        // updates for other components are kept
+        let other = serialize(&mut |writer| {
+            registry
+                .serialize(&mut ComponentSyncModeOnce(1.0), writer, None)
+                .unwrap()
+        });
+        manager.prepare_latest_component_update(entity, group, other.clone());
+
+        let updates = manager
+            .updates_to_send(Tick(1), BevyTick::new(1))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            updates,
+            vec![(
+                EntityUpdatesMessage {
+                    group_id: group,
+                    last_action_tick: Some(Tick(0)),
+                    updates: vec![(entity, vec![latest, other])],
+                },
+                0.0
+            )]
+        );
+    }
+
+    /// Test that we can enumerate the entities that belong to a replication group
+    #[test]
+    fn test_group_entities() {
+        let mut stepper = BevyStepper::default();
+        let group = ReplicationGroup::new_id(1);
+        let entities = (0..3)
+            .map(|_| {
+                stepper
+                    .server_app
+                    .world_mut()
+                    .spawn(Replicate {
+                        group: group.clone(),
+                        ..default()
+                    })
+                    .id()
+            })
+            .collect::<HashSet<_>>();
+        // entity in a different group
+        stepper.server_app.world_mut().spawn(Replicate {
+            group: ReplicationGroup::new_id(2),
+            ..default()
+        });
+        stepper.frame_step();
+        stepper.frame_step();
+
+        let group_entities = |stepper: &BevyStepper| {
+            stepper
+                .server_app
+                .world()
+                .resource::<ConnectionManager>()
+                .group_entities(ReplicationGroupId(1), ClientId::Netcode(TEST_CLIENT_ID))
+                .unwrap()
+                .into_iter()
+                .collect::<HashSet<_>>()
+        };
+        assert_eq!(group_entities(&stepper), entities);
+
+        // despawned entities are removed from the group
+        let despawned = *entities.iter().next().unwrap();
+        stepper.server_app.world_mut().despawn(despawned);
+        stepper.frame_step();
+        let mut expected = entities.clone();
+        expected.remove(&despawned);
+        assert_eq!(group_entities(&stepper), expected);
+    }
+
+    /// Check that we can get the latest tick acked by the client for the replication group of an entity
+    #[test]
+    fn test_replication_group_acked_tick() {
+        let mut stepper = BevyStepper::default();
+        let server_entity = stepper
+            .server_app
+            .world_mut()
+            .spawn((ComponentSyncModeFull(1.0), Replicate::default()))
+            .id();
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+
+        // send an update
+        stepper
+            .server_app
+            .world_mut()
+            .entity_mut(server_entity)
+            .get_mut::<ComponentSyncModeFull>()
+            .unwrap()
+            .0 = 2.0;
+        stepper.frame_step();
+        let send_tick = stepper
+            .server_app
+            .world()
+            .resource::<ConnectionManager>()
+            .connection(ClientId::Netcode(TEST_CLIENT_ID))
+            .unwrap()
+            .replication_sender
+            .updates_message_id_to_group_id
+            .values()
+            .next()
+            .expect("the update should have been sent")
+            .tick;
+
+        // wait for the client to ack the update
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        let acked_tick = stepper
+            .server_app
+            .world()
+            .resource::<ConnectionManager>()
+            .replication_group_acked_tick(server_entity, ClientId::Netcode(TEST_CLIENT_ID))
+            .unwrap();
+        assert_eq!(acked_tick, Some(send_tick));
+
+        // entities that are not replicated have no acked tick
+        let other_entity = stepper.server_app.world_mut().spawn_empty().id();
+        assert_eq!(
+            stepper
+                .server_app
+                .world()
+                .resource::<ConnectionManager>()
+                .replication_group_acked_tick(other_entity, ClientId::Netcode(TEST_CLIENT_ID))
+                .unwrap(),
+            None
+        );
+    }
+
+    /// Check that the initial snapshot sent to a client that joins with existing entities
+    /// is smaller with a snapshot-specific compression than with the default compression
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_initial_snapshot_compression() {
+        const NUM_ENTITIES: usize = 200;
+        /// Bytes of entity actions sent to the client when it connects
+        fn snapshot_bytes(compression: CompressionConfig) -> usize {
+            let frame_duration = Duration::from_millis(10);
+            let shared_config = SharedConfig {
+                tick: TickConfig::new(frame_duration),
+                ..default()
+            };
+            let mut stepper =
+                BevyStepper::new(shared_config, ClientConfig::default(), frame_duration);
+            stepper
+                .server_app
+                .world_mut()
+                .resource_mut::<ServerConfig>()
+                .replication
+                .initial_snapshot_compression = compression;
+            // the entities exist before the client connects
+            for i in 0..NUM_ENTITIES {
+                stepper
+                    .server_app
+                    .world_mut()
+                    .spawn((ComponentSyncModeFull(i as f32), Replicate::default()));
+            }
+            stepper.init();
+            for _ in 0..10 {
+                stepper.frame_step();
+            }
+            let num_replicated = stepper
+                .client_app
+                .world_mut()
+                .query_filtered::<(), With<Replicated>>()
+                .iter(stepper.client_app.world())
+                .count();
+            assert_eq!(num_replicated, NUM_ENTITIES);
+            let message_manager = &stepper
+                .server_app
+                .world()
+                .resource::<ConnectionManager>()
+                .connection(ClientId::Netcode(TEST_CLIENT_ID))
+                .unwrap()
+                .message_manager;
+            [
+                message_manager.channel_stats::<EntityActionsChannel>(),
+                message_manager.channel_stats::<SnapshotChannel>(),
+            ]
+            .into_iter()
+            .map(|stats| stats.unwrap().bytes_sent())
+            .sum()
+        }
+
+        let default_bytes = snapshot_bytes(CompressionConfig::None);
+        let snapshot_bytes = snapshot_bytes(CompressionConfig::Zstd { level: 19 });
+        assert!(
+            snapshot_bytes < default_bytes,
+            "snapshot compression: {snapshot_bytes} bytes, default: {default_bytes} bytes"
+        );
+    }
 }
@@ -0,0 +1,245 @@
+//! Detect when the [`FixedUpdate`] schedule cannot keep up with the tick rate
+//!
+//! If the simulation systems are too slow, more and more [`FixedUpdate`] runs are needed every frame
+//! to catch up with the elapsed time. This makes the frames even slower, and usually shows up as
+//! networking issues (sync problems, input delays, rollbacks).
+//!
+//! The [`SimulationLagPlugin`] counts the number of ticks that run every frame and accumulates the ticks
+//! that exceed the per-frame budget into a backlog. A slow frame once in a while only creates a small
+//! backlog that is paid back by the following frames, but if the backlog keeps growing for several
+//! consecutive frames, the simulation is falling behind and a [`SimulationLagging`] event is emitted.
+//! Both the ticks per frame and the backlog are exposed as [`Diagnostic`]s.
+use bevy::app::RunFixedMainLoop;
+use bevy::diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic};
+use bevy::prelude::*;
+use bevy::time::run_fixed_main_schedule;
+
+use crate::client::prediction::plugin::is_in_rollback;
+use crate::client::prediction::rollback::Rollback;
+use crate::prelude::FixedUpdateSet;
+
+/// Plugin that detects when the [`FixedUpdate`] schedule is falling behind
+pub struct SimulationLagPlugin {
+    /// Number of [`FixedUpdate`] runs per frame that the simulation is expected to need.
+    ///
+    /// It is normal to run a few ticks per frame if the frame rate is lower than the tick rate,
+    /// so this should be set according to the lowest expected frame rate.
+    /// Every frame, the ticks above this budget are added to the backlog, and the ticks below it
+    /// are removed from the backlog.
+    pub max_ticks_per_frame: u32,
+    /// Number of consecutive frames during which the backlog grows after which a [`SimulationLagging`]
+    /// event is emitted
+    pub lagging_frames_threshold: u32,
+    /// Number of measurements kept in the history of the [`TICKS_PER_FRAME`](Self::TICKS_PER_FRAME)
+    /// and [`BACKLOG`](Self::BACKLOG) diagnostics
+    pub history_len: usize,
+}
+
+impl Default for SimulationLagPlugin {
+    fn default() -> Self {
+        Self {
+            max_ticks_per_frame: 2,
+            lagging_frames_threshold: 10,
+            history_len: 60,
+        }
+    }
+}
+
+/// Event emitted every frame while the simulation is lagging behind the tick rate
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SimulationLagging {
+    /// Number of [`FixedUpdate`] runs during this frame
+    pub ticks_this_frame: u32,
+    /// Number of [`FixedUpdate`] runs above [`SimulationLagPlugin::max_ticks_per_frame`] accumulated over
+    /// the previous frames
+    pub backlog: u32,
+    /// Number of consecutive frames during which the backlog has been growing
+    pub lagging_frames: u32,
+}
+
+#[derive(Resource, Debug, Clone, Copy)]
+struct SimulationLagState {
+    max_ticks_per_frame: u32,
+    lagging_frames_threshold: u32,
+    ticks_this_frame: u32,
+    backlog: u32,
+    lagging_frames: u32,
+}
+
+impl SimulationLagPlugin {
+    /// Number of [`FixedUpdate`] runs per frame
+    pub const TICKS_PER_FRAME: DiagnosticPath =
+        DiagnosticPath::const_new("simulation.ticks_per_frame");
+    /// Number of [`FixedUpdate`] runs above the per-frame budget accumulated over the previous frames
+    pub const BACKLOG: DiagnosticPath = DiagnosticPath::const_new("simulation.backlog");
+}
+
+impl Plugin for SimulationLagPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SimulationLagging>();
+        app.insert_resource(SimulationLagState {
+            max_ticks_per_frame: self.max_ticks_per_frame,
+            lagging_frames_threshold: self.lagging_frames_threshold,
+            ticks_this_frame: 0,
+            backlog: 0,
+            lagging_frames: 0,
+        });
+        app.register_diagnostic(
+            Diagnostic::new(Self::TICKS_PER_FRAME)
+                .with_suffix("")
+                .with_max_history_length(self.history_len),
+        );
+        app.register_diagnostic(
+            Diagnostic::new(Self::BACKLOG)
+                .with_suffix("")
+                .with_max_history_length(self.history_len),
+        );
+        app.add_systems(
+            FixedFirst,
+            count_ticks
+                .after(FixedUpdateSet::TickUpdate)
+                // ticks that are re-simulated during rollback don't count
+                .run_if(not(resource_exists::<Rollback>).or_else(not(is_in_rollback))),
+        );
+        app.add_systems(
+            RunFixedMainLoop,
+            detect_simulation_lag.after(run_fixed_main_schedule),
+        );
+    }
+}
+
+fn count_ticks(mut state: ResMut<SimulationLagState>) {
+    state.ticks_this_frame += 1;
+}
+
+/// Update the backlog with the ticks that ran during this frame and emit a [`SimulationLagging`] event
+/// if the backlog has been growing for too many consecutive frames
+fn detect_simulation_lag(
+    mut state: ResMut<SimulationLagState>,
+    mut events: EventWriter<SimulationLagging>,
+    mut diagnostics: Diagnostics,
+) {
+    let ticks_this_frame = std::mem::take(&mut state.ticks_this_frame);
+    let previous_backlog = state.backlog;
+    state.backlog = (previous_backlog + ticks_this_frame).saturating_sub(state.max_ticks_per_frame);
+    let backlog = state.backlog;
+    diagnostics.add_measurement(&SimulationLagPlugin::TICKS_PER_FRAME, || {
+        ticks_this_frame as f64
+    });
+    diagnostics.add_measurement(&SimulationLagPlugin::BACKLOG, || backlog as f64);
+    if backlog <= previous_backlog {
+        state.lagging_frames = 0;
+        return;
+    }
+    state.lagging_frames += 1;
+    if state.lagging_frames >= state.lagging_frames_threshold {
+        warn!(
+            ?ticks_this_frame,
+            ?backlog,
+            lagging_frames = ?state.lagging_frames,
+            "The FixedUpdate schedule is falling behind the tick rate"
+        );
+        events.send(SimulationLagging {
+            ticks_this_frame,
+            backlog,
+            lagging_frames: state.lagging_frames,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::diagnostic::DiagnosticsStore;
+    use bevy::time::TimePlugin;
+    use bevy::utils::Duration;
+
+    #[derive(Resource, Default)]
+    struct LagEvents(Vec<SimulationLagging>);
+
+    fn record_lag_events(
+        mut lag_events: ResMut<LagEvents>,
+        mut events: EventReader<SimulationLagging>,
+    ) {
+        lag_events.0.extend(events.read().copied());
+    }
+
+    const TIMESTEP: Duration = Duration::from_millis(2);
+
+    /// App running in real time with a fixed timestep of [`TIMESTEP`]
+    fn lag_app(plugin: SimulationLagPlugin) -> App {
+        let mut app = App::new();
+        app.add_plugins((TaskPoolPlugin::default(), TimePlugin, plugin));
+        app.insert_resource(Time::<Fixed>::from_duration(TIMESTEP));
+        app.init_resource::<LagEvents>();
+        app.add_systems(Update, record_lag_events);
+        app.update();
+        app
+    }
+
+    fn backlog_history(app: &App) -> Vec<f64> {
+        app.world()
+            .resource::<DiagnosticsStore>()
+            .get(&SimulationLagPlugin::BACKLOG)
+            .unwrap()
+            .values()
+            .copied()
+            .collect()
+    }
+
+    /// Check that a [`FixedUpdate`] schedule that takes longer than the timestep makes the backlog grow
+    /// every frame, and that a lag event is emitted
+    #[test]
+    fn test_slow_fixed_update() {
+        let mut app = lag_app(SimulationLagPlugin {
+            max_ticks_per_frame: 2,
+            lagging_frames_threshold: 3,
+            ..default()
+        });
+        // every tick takes twice as long as the timestep
+        app.add_systems(FixedUpdate, || std::thread::sleep(TIMESTEP * 2));
+        // a frame hitch starts the spiral
+        std::thread::sleep(TIMESTEP * 4);
+        for _ in 0..5 {
+            app.update();
+        }
+        let events = &app.world().resource::<LagEvents>().0;
+        assert!(!events.is_empty());
+        for event in events {
+            assert!(event.ticks_this_frame > 2);
+            assert!(event.lagging_frames >= 3);
+        }
+        let backlog = backlog_history(&app);
+        assert!(backlog.windows(2).all(|w| w[1] >= w[0]), "{backlog:?}");
+        assert_eq!(
+            backlog.last().copied(),
+            events.last().map(|event| event.backlog as f64)
+        );
+    }
+
+    /// Check that running several ticks per frame because of a low frame rate is not considered as lag,
+    /// as long as the simulation keeps up
+    #[test]
+    fn test_low_frame_rate() {
+        let mut app = lag_app(SimulationLagPlugin {
+            max_ticks_per_frame: 4,
+            lagging_frames_threshold: 3,
+            ..default()
+        });
+        for _ in 0..10 {
+            // each frame takes about 3 ticks
+            std::thread::sleep(TIMESTEP * 3);
+            app.update();
+        }
+        assert!(app.world().resource::<LagEvents>().0.is_empty());
+        let ticks_per_frame = app
+            .world()
+            .resource::<DiagnosticsStore>()
+            .get(&SimulationLagPlugin::TICKS_PER_FRAME)
+            .unwrap()
+            .values()
+            .copied()
+            .fold(0.0, f64::max);
+        assert!(ticks_per_frame >= 2.0);
+    }
+}
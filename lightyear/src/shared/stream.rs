@@ -0,0 +1,777 @@
+//! Optional streaming of large byte blobs from the server to a client
+//!
+//! Regular messages are fragmented and reassembled in memory, which is not suitable for very large
+//! payloads (a procedurally generated map, a replay file, etc.).
+//!
+//! The [`StreamPlugin`] must be added to the client and server apps (after the lightyear plugins, like
+//! your protocol), since it registers the [`StreamChannel`].
+//!
+//! - the server starts a stream with [`StreamSender::send`], by providing a [`Read`] source and the total size of the blob.
+//!   The source is read progressively: every frame, up to [`StreamPlugin::chunks_per_frame`] chunks are sent
+//!   as ordered reliable messages. At most [`StreamPlugin::max_chunks_in_flight`] chunks can be waiting for
+//!   an ack from a client, so that a slow client doesn't end up with the whole blob buffered on the server.
+//! - the client writes the chunks to a [`Write`] sink as soon as they are received, so the whole blob never
+//!   needs to be held in memory. The sink is created by the [`StreamSinkFn`] provided to the plugin.
+//! - a [`StreamProgress`] event is emitted on the client every frame in which a stream made progress.
+//! - the partial streams are discarded on the client if they don't receive any data for [`StreamPlugin::timeout`],
+//!   or when the client disconnects. A [`StreamFailed`] event is emitted on the client for every stream that
+//!   is discarded before being complete.
+use std::io::{Read, Write};
+
+use bevy::prelude::*;
+use bevy::utils::{Duration, HashMap};
+use lightyear_macros::ChannelInternal;
+use serde::{Deserialize, Serialize};
+use tracing::{error, warn};
+
+use crate::channel::builder::{ChannelMode, ChannelSettings, ReliableSettings};
+use crate::client::config::ClientConfig;
+use crate::client::events::MessageEvent as ClientMessageEvent;
+use crate::client::networking::NetworkingState;
+use crate::prelude::{AppChannelExt, ChannelDirection, ChannelRegistry, ClientId, NetworkTarget};
+use crate::protocol::channel::ChannelKind;
+use crate::protocol::message::{AppMessageInternalExt, MessageType};
+use crate::server::config::ServerConfig;
+use crate::server::connection::ConnectionManager as ServerConnectionManager;
+use crate::shared::sets::{ClientMarker, InternalMainSet, ServerMarker};
+
+#[derive(ChannelInternal)]
+/// Channel used to send the chunks of the streams
+/// This is an Ordered Reliable channel
+pub struct StreamChannel;
+
+/// Identifier of a stream
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub struct StreamId(pub u32);
+
+/// Message used to send a stream in several chunks
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Reflect)]
+pub(crate) enum StreamMessage {
+    /// A new stream is starting
+    Start { id: StreamId, total_size: u64 },
+    /// A chunk of the stream. The chunks are received in order
+    Chunk { id: StreamId, data: Vec<u8> },
+    /// The stream was interrupted before all the data was sent
+    Abort { id: StreamId },
+}
+
+/// Event emitted on the client every frame in which a stream received data
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamProgress {
+    pub id: StreamId,
+    /// Number of bytes received so far
+    pub received: u64,
+    /// Total number of bytes of the stream
+    pub total_size: u64,
+}
+
+impl StreamProgress {
+    /// Returns true if all the bytes of the stream were received and written to the sink
+    pub fn is_complete(&self) -> bool {
+        self.received >= self.total_size
+    }
+}
+
+/// Reason why a stream was discarded by the client before being complete
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamFailureReason {
+    /// The stream didn't receive any data for [`StreamPlugin::timeout`]
+    Timeout,
+    /// The server interrupted the stream, for example because its source ended early
+    Aborted,
+    /// The client disconnected
+    Disconnected,
+    /// The received data could not be written to the sink
+    SinkError,
+}
+
+/// Event emitted on the client when a stream is discarded before all its data was received
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamFailed {
+    pub id: StreamId,
+    pub reason: StreamFailureReason,
+}
+
+/// Function called by the client when a new stream starts, to create the sink where the stream data will be written.
+///
+/// The sink is dropped once the stream is complete.
+pub type StreamSinkFn =
+    fn(world: &World, id: StreamId, total_size: u64) -> Box<dyn Write + Send + Sync>;
+
+/// Plugin that adds the streaming of large blobs from the server to clients
+pub struct StreamPlugin {
+    /// Number of bytes in each chunk
+    pub chunk_size: usize,
+    /// Maximum number of chunks sent per frame for each stream
+    pub chunks_per_frame: usize,
+    /// Maximum number of chunks that can be waiting for an ack from a client (across all the streams
+    /// sent to that client). No new chunks are sent to the client until some of them are acked.
+    pub max_chunks_in_flight: usize,
+    /// Duration after which the client discards a stream that hasn't received any data
+    pub timeout: Duration,
+    /// Function used by the client to create the sink of a new stream.
+    ///
+    /// If it is not set, the incoming streams are discarded.
+    pub sink: Option<StreamSinkFn>,
+}
+
+impl Default for StreamPlugin {
+    fn default() -> Self {
+        Self {
+            // small enough that a chunk fits in a single packet
+            chunk_size: 1000,
+            chunks_per_frame: 16,
+            max_chunks_in_flight: 256,
+            timeout: Duration::from_secs(30),
+            sink: None,
+        }
+    }
+}
+
+impl StreamPlugin {
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    pub fn with_chunks_per_frame(mut self, chunks_per_frame: usize) -> Self {
+        self.chunks_per_frame = chunks_per_frame;
+        self
+    }
+
+    pub fn with_max_chunks_in_flight(mut self, max_chunks_in_flight: usize) -> Self {
+        self.max_chunks_in_flight = max_chunks_in_flight;
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_sink(mut self, sink: StreamSinkFn) -> Self {
+        self.sink = Some(sink);
+        self
+    }
+}
+
+impl Plugin for StreamPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<StreamId>()
+            .register_type::<StreamMessage>();
+        app.add_channel::<StreamChannel>(ChannelSettings {
+            mode: ChannelMode::OrderedReliable(ReliableSettings::default()),
+            direction: ChannelDirection::ServerToClient,
+            send_frequency: Duration::default(),
+            priority: 1.0,
+        });
+        app.world_mut()
+            .resource_mut::<ChannelRegistry>()
+            .set_internal_channel::<StreamChannel>();
+        app.register_message_internal::<StreamMessage>(
+            ChannelDirection::ServerToClient,
+            MessageType::Normal,
+        );
+
+        if app.world().get_resource::<ClientConfig>().is_some() {
+            app.add_event::<StreamProgress>();
+            app.add_event::<StreamFailed>();
+            app.insert_resource(StreamReceiver {
+                sink: self.sink,
+                timeout: self.timeout,
+                streams: HashMap::default(),
+            });
+            app.add_systems(
+                PreUpdate,
+                receive_streams.after(InternalMainSet::<ClientMarker>::EmitEvents),
+            );
+            app.add_systems(OnEnter(NetworkingState::Disconnected), clear_streams);
+        }
+        if app.world().get_resource::<ServerConfig>().is_some() {
+            app.insert_resource(StreamSender {
+                chunk_size: self.chunk_size,
+                chunks_per_frame: self.chunks_per_frame,
+                max_chunks_in_flight: self.max_chunks_in_flight,
+                next_id: 0,
+                streams: Vec::new(),
+            });
+            app.add_systems(
+                PostUpdate,
+                send_streams.before(InternalMainSet::<ServerMarker>::Send),
+            );
+        }
+    }
+}
+
+struct OutgoingStream {
+    id: StreamId,
+    client_id: ClientId,
+    source: Box<dyn Read + Send + Sync>,
+    total_size: u64,
+    sent: u64,
+    started: bool,
+}
+
+/// Resource used by the server to stream large blobs to clients
+#[derive(Resource)]
+pub struct StreamSender {
+    chunk_size: usize,
+    chunks_per_frame: usize,
+    max_chunks_in_flight: usize,
+    next_id: u32,
+    streams: Vec<OutgoingStream>,
+}
+
+impl StreamSender {
+    /// Start streaming `total_size` bytes read from `source` to the client.
+    ///
+    /// The source is read progressively, as the chunks get sent.
+    pub fn send(
+        &mut self,
+        client_id: ClientId,
+        total_size: u64,
+        source: impl Read + Send + Sync + 'static,
+    ) -> StreamId {
+        let id = StreamId(self.next_id);
+        self.next_id = self.next_id.wrapping_add(1);
+        self.streams.push(OutgoingStream {
+            id,
+            client_id,
+            source: Box::new(source.take(total_size)),
+            total_size,
+            sent: 0,
+            started: false,
+        });
+        id
+    }
+
+    /// Returns true if the stream is still being sent
+    pub fn is_sending(&self, id: StreamId) -> bool {
+        self.streams.iter().any(|stream| stream.id == id)
+    }
+}
+
+struct IncomingStream {
+    sink: Box<dyn Write + Send + Sync>,
+    received: u64,
+    total_size: u64,
+    /// Time at which the stream last received data
+    last_received: Duration,
+}
+
+/// Resource used by the client to keep track of the streams being received
+#[derive(Resource)]
+struct StreamReceiver {
+    sink: Option<StreamSinkFn>,
+    timeout: Duration,
+    streams: HashMap<StreamId, IncomingStream>,
+}
+
+/// Send the next chunks of every stream
+fn send_streams(
+    mut sender: ResMut<StreamSender>,
+    mut connection_manager: ResMut<ServerConnectionManager>,
+) {
+    let StreamSender {
+        chunk_size,
+        chunks_per_frame,
+        max_chunks_in_flight,
+        streams,
+        ..
+    } = sender.as_mut();
+    // keep only the streams that still have data to send
+    streams.retain_mut(|stream| {
+        let id = stream.id;
+        let Ok(connection) = connection_manager.connection(stream.client_id) else {
+            warn!(
+                "Client {:?} is not connected anymore, stopping stream {id:?}",
+                stream.client_id
+            );
+            return false;
+        };
+        // the chunks that haven't been acked yet are still buffered in the reliable sender
+        // (the start message of the stream also counts towards the limit)
+        let in_flight = connection
+            .message_manager
+            .pending_reliable_count(ChannelKind::of::<StreamChannel>())
+            + usize::from(!stream.started);
+        let num_chunks = (*chunks_per_frame).min(max_chunks_in_flight.saturating_sub(in_flight));
+        let mut send = |mut message: StreamMessage| {
+            connection_manager
+                .send_message_to_target::<StreamChannel, _>(
+                    &mut message,
+                    NetworkTarget::Single(stream.client_id),
+                )
+                .inspect_err(|e| error!("Error sending stream {id:?}: {:?}", e))
+                .is_ok()
+        };
+        if !stream.started {
+            stream.started = true;
+            if !send(StreamMessage::Start {
+                id,
+                total_size: stream.total_size,
+            }) {
+                return false;
+            }
+        }
+        for _ in 0..num_chunks {
+            if stream.sent == stream.total_size {
+                return false;
+            }
+            let mut data = vec![0; *chunk_size];
+            match stream.source.read(&mut data) {
+                Ok(0) => {
+                    error!(
+                        "Stream {id:?} source ended after {} of {} bytes",
+                        stream.sent, stream.total_size
+                    );
+                    send(StreamMessage::Abort { id });
+                    return false;
+                }
+                Ok(read) => {
+                    data.truncate(read);
+                    stream.sent += read as u64;
+                }
+                Err(e) => {
+                    error!("Error reading stream {id:?} source: {:?}", e);
+                    send(StreamMessage::Abort { id });
+                    return false;
+                }
+            }
+            if !send(StreamMessage::Chunk { id, data }) {
+                return false;
+            }
+        }
+        stream.sent < stream.total_size
+    });
+}
+
+/// Write the received chunks to the stream sinks, and emit [`StreamProgress`] and [`StreamFailed`] events
+fn receive_streams(world: &mut World) {
+    let now = world.resource::<Time<Real>>().elapsed();
+    let messages: Vec<_> = world
+        .resource_mut::<Events<ClientMessageEvent<StreamMessage>>>()
+        .drain()
+        .collect();
+    world.resource_scope(|world, mut receiver: Mut<StreamReceiver>| {
+        let timeout = receiver.timeout;
+        let mut failed = Vec::new();
+        receiver.streams.retain(|id, stream| {
+            let timed_out = now.saturating_sub(stream.last_received) > timeout;
+            if timed_out {
+                warn!(
+                    "Stream {id:?} did not receive any data for {timeout:?}, discarding it after {} of {} bytes",
+                    stream.received, stream.total_size
+                );
+                failed.push(StreamFailed {
+                    id: *id,
+                    reason: StreamFailureReason::Timeout,
+                });
+            }
+            !timed_out
+        });
+        let mut updated = Vec::new();
+        for ClientMessageEvent { message, .. } in messages {
+            match message {
+                StreamMessage::Start { id, total_size } => {
+                    let Some(sink) = receiver.sink else {
+                        warn!("Received stream {id:?} but no stream sink is configured, discarding it");
+                        continue;
+                    };
+                    let sink = sink(world, id, total_size);
+                    receiver.streams.insert(
+                        id,
+                        IncomingStream {
+                            sink,
+                            received: 0,
+                            total_size,
+                            last_received: now,
+                        },
+                    );
+                    updated.push(id);
+                }
+                StreamMessage::Chunk { id, data } => {
+                    let Some(stream) = receiver.streams.get_mut(&id) else {
+                        continue;
+                    };
+                    if let Err(e) = stream.sink.write_all(&data) {
+                        error!("Error writing stream {id:?} to the sink: {:?}", e);
+                        receiver.streams.remove(&id);
+                        failed.push(StreamFailed {
+                            id,
+                            reason: StreamFailureReason::SinkError,
+                        });
+                        continue;
+                    }
+                    stream.received += data.len() as u64;
+                    stream.last_received = now;
+                    if !updated.contains(&id) {
+                        updated.push(id);
+                    }
+                }
+                StreamMessage::Abort { id } => {
+                    warn!("Stream {id:?} was aborted by the server");
+                    if receiver.streams.remove(&id).is_some() {
+                        failed.push(StreamFailed {
+                            id,
+                            reason: StreamFailureReason::Aborted,
+                        });
+                    }
+                }
+            }
+        }
+        for id in updated {
+            let Some(stream) = receiver.streams.get_mut(&id) else {
+                continue;
+            };
+            let progress = StreamProgress {
+                id,
+                received: stream.received,
+                total_size: stream.total_size,
+            };
+            if progress.is_complete() {
+                if let Err(e) = stream.sink.flush() {
+                    error!("Error flushing stream {id:?} sink: {:?}", e);
+                }
+                receiver.streams.remove(&id);
+            }
+            world.send_event(progress);
+        }
+        world.send_event_batch(failed);
+    });
+}
+
+/// Discard the partial streams when the client disconnects
+fn clear_streams(mut receiver: ResMut<StreamReceiver>, mut failed: EventWriter<StreamFailed>) {
+    if !receiver.streams.is_empty() {
+        warn!(
+            "Discarding {} partial streams because the client disconnected",
+            receiver.streams.len()
+        );
+        failed.send_batch(receiver.streams.drain().map(|(id, _)| StreamFailed {
+            id,
+            reason: StreamFailureReason::Disconnected,
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::sync::{Arc, Mutex};
+
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+    use crate::client::networking::ClientCommands;
+    use crate::prelude::client::ClientConfig;
+    use crate::prelude::{SharedConfig, TickConfig};
+    use crate::tests::stepper::{BevyStepper, TEST_CLIENT_ID};
+
+    /// Sink that writes the stream data to a buffer shared with the test
+    #[derive(Resource, Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn shared_buffer_sink(world: &World, _: StreamId, _: u64) -> Box<dyn Write + Send + Sync> {
+        Box::new(world.resource::<SharedBuffer>().clone())
+    }
+
+    #[derive(Resource, Default)]
+    struct ReceivedProgress(Vec<StreamProgress>);
+
+    #[derive(Resource, Default)]
+    struct ReceivedFailures(Vec<StreamFailed>);
+
+    fn receive_failures(
+        mut received: ResMut<ReceivedFailures>,
+        mut failures: EventReader<StreamFailed>,
+    ) {
+        received.0.extend(failures.read().copied());
+    }
+
+    fn receive_progress(
+        mut received: ResMut<ReceivedProgress>,
+        mut progress: EventReader<StreamProgress>,
+    ) {
+        received.0.extend(progress.read().copied());
+    }
+
+    /// Check that a multi-MB blob streamed to the client is written to the sink in full,
+    /// with progress events
+    #[test]
+    fn test_stream_large_blob() {
+        let frame_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(frame_duration),
+            ..Default::default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, ClientConfig::default(), frame_duration);
+        let plugin = || StreamPlugin::default().with_chunks_per_frame(64);
+        stepper.server_app.add_plugins(plugin());
+        stepper
+            .client_app
+            .add_plugins(plugin().with_sink(shared_buffer_sink));
+        stepper.client_app.init_resource::<SharedBuffer>();
+        stepper.client_app.init_resource::<ReceivedProgress>();
+        stepper.client_app.add_systems(Update, receive_progress);
+        stepper.init();
+
+        let blob: Vec<u8> = (0..2 * 1024 * 1024 + 17)
+            .map(|i: u32| (i.wrapping_mul(31) ^ (i >> 8)) as u8)
+            .collect();
+        let id = stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<StreamSender>()
+            .send(
+                ClientId::Netcode(TEST_CLIENT_ID),
+                blob.len() as u64,
+                Cursor::new(blob.clone()),
+            );
+        for _ in 0..200 {
+            stepper.frame_step();
+            if stepper
+                .client_app
+                .world()
+                .resource::<ReceivedProgress>()
+                .0
+                .last()
+                .is_some_and(StreamProgress::is_complete)
+            {
+                break;
+            }
+        }
+
+        assert!(!stepper
+            .server_app
+            .world()
+            .resource::<StreamSender>()
+            .is_sending(id));
+        let progress = &stepper.client_app.world().resource::<ReceivedProgress>().0;
+        // the stream is received over several frames, and the progress only goes up
+        assert!(progress.len() > 1);
+        assert!(progress.windows(2).all(|w| w[0].received < w[1].received));
+        assert!(progress
+            .iter()
+            .all(|p| p.id == id && p.total_size == blob.len() as u64));
+        assert!(progress.last().unwrap().is_complete());
+        assert!(
+            *stepper
+                .client_app
+                .world()
+                .resource::<SharedBuffer>()
+                .0
+                .lock()
+                .unwrap()
+                == blob
+        );
+    }
+
+    fn stream_stepper(plugin: impl Fn() -> StreamPlugin) -> BevyStepper {
+        let frame_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(frame_duration),
+            ..Default::default()
+        };
+        let mut stepper = BevyStepper::new(shared_config, ClientConfig::default(), frame_duration);
+        stepper.server_app.add_plugins(plugin());
+        stepper
+            .client_app
+            .add_plugins(plugin().with_sink(shared_buffer_sink));
+        stepper.client_app.init_resource::<SharedBuffer>();
+        stepper.client_app.init_resource::<ReceivedFailures>();
+        stepper.client_app.add_systems(Update, receive_failures);
+        stepper.init();
+        stepper
+    }
+
+    /// Check that no more than `max_chunks_in_flight` chunks are buffered on the server
+    /// while the client doesn't ack them, and that the stream resumes once they are acked
+    #[test]
+    fn test_stream_max_chunks_in_flight() {
+        let mut stepper = stream_stepper(|| {
+            StreamPlugin::default()
+                .with_chunk_size(100)
+                .with_chunks_per_frame(64)
+                .with_max_chunks_in_flight(8)
+        });
+        let blob: Vec<u8> = (0..10_000).map(|i: u32| i as u8).collect();
+        let id = stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<StreamSender>()
+            .send(
+                ClientId::Netcode(TEST_CLIENT_ID),
+                blob.len() as u64,
+                Cursor::new(blob.clone()),
+            );
+
+        // the client is not updated, so it doesn't ack any chunk
+        for _ in 0..5 {
+            stepper.advance_time(stepper.frame_duration);
+            stepper.server_app.update();
+            assert!(
+                stepper
+                    .server_app
+                    .world()
+                    .resource::<ServerConnectionManager>()
+                    .connection(ClientId::Netcode(TEST_CLIENT_ID))
+                    .unwrap()
+                    .message_manager
+                    .pending_reliable_count(ChannelKind::of::<StreamChannel>())
+                    <= 8
+            );
+        }
+
+        for _ in 0..200 {
+            stepper.frame_step();
+            if !stepper
+                .server_app
+                .world()
+                .resource::<StreamSender>()
+                .is_sending(id)
+            {
+                break;
+            }
+        }
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        assert!(
+            *stepper
+                .client_app
+                .world()
+                .resource::<SharedBuffer>()
+                .0
+                .lock()
+                .unwrap()
+                == blob
+        );
+    }
+
+    /// Check that the client discards a partial stream that doesn't receive any data for
+    /// the timeout duration
+    #[test]
+    fn test_stream_timeout() {
+        let mut stepper = stream_stepper(|| {
+            StreamPlugin::default()
+                .with_chunk_size(100)
+                .with_chunks_per_frame(1)
+                .with_timeout(Duration::from_secs(1))
+        });
+        let id = stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<StreamSender>()
+            .send(
+                ClientId::Netcode(TEST_CLIENT_ID),
+                10_000,
+                Cursor::new(vec![0; 10_000]),
+            );
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .resource::<StreamReceiver>()
+                .streams
+                .len(),
+            1
+        );
+
+        // the server stops sending the stream without aborting it
+        stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<StreamSender>()
+            .streams
+            .clear();
+        stepper.advance_time(Duration::from_secs(2));
+        stepper.frame_step();
+        assert!(stepper
+            .client_app
+            .world()
+            .resource::<StreamReceiver>()
+            .streams
+            .is_empty());
+        assert_eq!(
+            stepper.client_app.world().resource::<ReceivedFailures>().0,
+            vec![StreamFailed {
+                id,
+                reason: StreamFailureReason::Timeout
+            }]
+        );
+    }
+
+    /// Check that the partial streams are discarded when the client disconnects
+    #[test]
+    fn test_stream_cleared_on_disconnect() {
+        let mut stepper = stream_stepper(|| {
+            StreamPlugin::default()
+                .with_chunk_size(100)
+                .with_chunks_per_frame(1)
+        });
+        let id = stepper
+            .server_app
+            .world_mut()
+            .resource_mut::<StreamSender>()
+            .send(
+                ClientId::Netcode(TEST_CLIENT_ID),
+                10_000,
+                Cursor::new(vec![0; 10_000]),
+            );
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        assert_eq!(
+            stepper
+                .client_app
+                .world()
+                .resource::<StreamReceiver>()
+                .streams
+                .len(),
+            1
+        );
+
+        stepper
+            .client_app
+            .world_mut()
+            .run_system_once(|mut commands: Commands| commands.disconnect_client());
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        assert!(stepper
+            .client_app
+            .world()
+            .resource::<StreamReceiver>()
+            .streams
+            .is_empty());
+        assert_eq!(
+            stepper.client_app.world().resource::<ReceivedFailures>().0,
+            vec![StreamFailed {
+                id,
+                reason: StreamFailureReason::Disconnected
+            }]
+        );
+        // the server stops sending the stream to the disconnected client
+        assert!(stepper
+            .server_app
+            .world()
+            .resource::<StreamSender>()
+            .streams
+            .is_empty());
+    }
+}
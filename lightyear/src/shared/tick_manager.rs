@@ -3,14 +3,158 @@ use bevy::prelude::*;
 use bevy::utils::Duration;
 use tracing::trace;
 
+use bevy::app::FixedMain;
+use byteorder::WriteBytesExt;
+use serde::{Deserialize, Serialize};
+
 use crate::client::prediction::plugin::is_in_rollback;
 use crate::client::prediction::rollback::Rollback;
 use crate::prelude::FixedUpdateSet;
+use crate::serialize::reader::Reader;
+use crate::serialize::varint::{varint_len, VarIntReadExt, VarIntWriteExt};
+use crate::serialize::SerializationError;
 use crate::utils::wrapping_id::wrapping_id;
 
 // Internal id that tracks the Tick value for the server and the client
 wrapping_id!(Tick);
 
+/// A wider [`Tick`] backed by a `u32`, for long-running servers.
+///
+/// A [`Tick`] wraps around every 65536 ticks (about 11 minutes at 100Hz), whereas a `WideTick` only wraps
+/// after 2^32 ticks (more than a year at 100Hz), so it can be used to order or store events over many hours.
+///
+/// The modulus of the [`TickManager::wide_tick`] can be reduced to `2^bits` with [`TickConfig::wide_tick_bits`].
+/// The operators (`+`, `-`, comparisons) use the full 2^32 modulus, the methods with a `bits` parameter
+/// (like [`WideTick::wrapping_add`]) must be used for wide ticks with a smaller modulus.
+///
+/// The lower 16 bits of a `WideTick` are the [`Tick`], so only the [`Tick`] needs to be sent over the network:
+/// the receiver can reconstruct the `WideTick` from the [`Tick`] and a nearby `WideTick` with [`WideTick::from_tick`].
+/// When the client gets synced, its [`TickManager::wide_tick`] is initialized from the server's wide tick, so the
+/// client's own wide tick can be used as the reference.
+///
+/// The server sends its `WideTick` to each client when it connects (see [`ServerWideTick`]), and the client then
+/// counts the wraps of the server ticks it receives, so the client knows the upper bits of the server's
+/// `WideTick` even if pings are disabled (see [`PingConfig::enabled`](crate::prelude::PingConfig::enabled)).
+///
+/// The packets sent by lightyear only contain the 16-bit [`Tick`]. A `WideTick` can be included compactly in
+/// your own messages as a delta against a base `WideTick` known by both peers, with [`WideTick::to_bytes_delta`]
+/// and [`WideTick::from_bytes_delta`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize)]
+pub struct WideTick(pub u32);
+
+impl WideTick {
+    /// Smallest number of bits of a `WideTick`: the `WideTick` must be wider than the 16-bit [`Tick`]
+    pub const MIN_BITS: u8 = 17;
+    /// Largest number of bits of a `WideTick`
+    pub const MAX_BITS: u8 = 32;
+
+    /// The wrapped [`Tick`], i.e. the lower 16 bits of the `WideTick`
+    pub fn tick(self) -> Tick {
+        Tick(self.0 as u16)
+    }
+
+    /// Reconstruct the `WideTick` of `tick` using a `reference` that is less than half a wrap (32768 ticks) away
+    pub fn from_tick(tick: Tick, reference: WideTick) -> WideTick {
+        Self::from_tick_with_bits(tick, reference, Self::MAX_BITS)
+    }
+
+    /// Same as [`WideTick::from_tick`], for a `WideTick` that wraps at `2^bits`
+    pub fn from_tick_with_bits(tick: Tick, reference: WideTick, bits: u8) -> WideTick {
+        reference.wrapping_add((tick - reference.tick()) as i32, bits)
+    }
+
+    /// Add `delta` ticks to a `WideTick` that wraps at `2^bits`
+    pub fn wrapping_add(self, delta: i32, bits: u8) -> WideTick {
+        WideTick(self.0.wrapping_add_signed(delta) & Self::mask(bits))
+    }
+
+    /// Number of ticks between two `WideTick`s that wrap at `2^bits`
+    pub fn wrapping_sub(self, rhs: WideTick, bits: u8) -> i32 {
+        let shift = 32 - Self::clamp_bits(bits);
+        // sign-extend the difference from `bits` bits
+        ((self.0.wrapping_sub(rhs.0) << shift) as i32) >> shift
+    }
+
+    fn clamp_bits(bits: u8) -> u32 {
+        bits.clamp(Self::MIN_BITS, Self::MAX_BITS) as u32
+    }
+
+    fn mask(bits: u8) -> u32 {
+        u32::MAX >> (32 - Self::clamp_bits(bits))
+    }
+
+    /// Number of bytes used to write the `WideTick` as a delta against `base`
+    pub fn delta_len(self, base: WideTick) -> usize {
+        varint_len(zigzag_encode(self - base))
+    }
+
+    /// Write the `WideTick` as a variable-length delta against `base`.
+    ///
+    /// This takes 1 byte if the two ticks are less than 32 ticks apart, and at most 8 bytes.
+    pub fn to_bytes_delta<T: WriteBytesExt>(
+        self,
+        base: WideTick,
+        buffer: &mut T,
+    ) -> Result<(), SerializationError> {
+        buffer.write_varint(zigzag_encode(self - base))
+    }
+
+    /// Read a `WideTick` that was written as a delta against `base` with [`WideTick::to_bytes_delta`]
+    pub fn from_bytes_delta(
+        base: WideTick,
+        buffer: &mut Reader,
+    ) -> Result<WideTick, SerializationError> {
+        let delta = buffer.read_varint()?;
+        Ok(base + zigzag_decode(delta))
+    }
+}
+
+/// Message sent by the server to a client when it connects, with the server's current [`WideTick`].
+///
+/// The pong messages also carry the server's tick generation, but this message lets the client
+/// know it when pings are disabled.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Reflect)]
+pub struct ServerWideTick(pub WideTick);
+
+/// Map a signed delta to an unsigned integer so that small negative deltas are also encoded in few bytes
+fn zigzag_encode(delta: i32) -> u64 {
+    ((delta << 1) ^ (delta >> 31)) as u32 as u64
+}
+
+fn zigzag_decode(value: u64) -> i32 {
+    let value = value as u32;
+    ((value >> 1) as i32) ^ -((value & 1) as i32)
+}
+
+impl std::ops::Add<i32> for WideTick {
+    type Output = WideTick;
+
+    fn add(self, rhs: i32) -> Self::Output {
+        self.wrapping_add(rhs, Self::MAX_BITS)
+    }
+}
+
+impl std::ops::Sub for WideTick {
+    type Output = i32;
+
+    /// Number of ticks between the two `WideTick`s, handling wrapping
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.wrapping_sub(rhs, Self::MAX_BITS)
+    }
+}
+
+impl PartialOrd for WideTick {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for WideTick {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (*self - *other).cmp(&0)
+    }
+}
+
 pub struct TickManagerPlugin {
     pub(crate) config: TickConfig,
 }
@@ -48,11 +192,24 @@ impl Plugin for TickManagerPlugin {
 #[derive(Clone, Copy, Debug, Reflect)]
 pub struct TickConfig {
     pub tick_duration: Duration,
+    /// Number of bits of the [`TickManager::wide_tick`], which wraps every `2^wide_tick_bits` ticks.
+    ///
+    /// The value is clamped between [`WideTick::MIN_BITS`] and [`WideTick::MAX_BITS`] (the default).
+    /// It must be the same on the client and the server.
+    pub wide_tick_bits: u8,
 }
 
 impl TickConfig {
     pub fn new(tick_duration: Duration) -> Self {
-        Self { tick_duration }
+        Self {
+            tick_duration,
+            wide_tick_bits: WideTick::MAX_BITS,
+        }
+    }
+
+    pub fn with_wide_tick_bits(mut self, wide_tick_bits: u8) -> Self {
+        self.wide_tick_bits = wide_tick_bits;
+        self
     }
 }
 
@@ -64,6 +221,8 @@ pub struct TickManager {
     pub config: TickConfig,
     /// Current tick (sequence number of the FixedUpdate schedule)
     tick: Tick,
+    /// Current tick, without wrapping every 65536 ticks
+    wide_tick: WideTick,
 }
 
 impl TickManager {
@@ -71,6 +230,7 @@ impl TickManager {
         Self {
             config,
             tick: Tick(0),
+            wide_tick: WideTick(0),
         }
     }
 
@@ -78,11 +238,21 @@ impl TickManager {
     #[doc(hidden)]
     pub fn increment_tick(&mut self) {
         self.tick += 1;
+        self.wide_tick = self.wide_tick.wrapping_add(1, self.config.wide_tick_bits);
         trace!(new_tick = ?self.tick, "incremented tick")
     }
+    /// Set the wide tick from the wide tick of a remote peer that is close to our current tick
+    /// (for example, the client's wide tick is initialized from the server's wide tick when it gets synced)
+    pub(crate) fn sync_wide_tick(&mut self, reference: WideTick) {
+        self.wide_tick =
+            WideTick::from_tick_with_bits(self.tick, reference, self.config.wide_tick_bits);
+    }
+
     pub(crate) fn set_tick_to(&mut self, tick: Tick) -> TickEvent {
         let old_tick = self.tick;
         self.tick = tick;
+        self.wide_tick =
+            WideTick::from_tick_with_bits(tick, self.wide_tick, self.config.wide_tick_bits);
         // info!(?old_tick, new_tick =?tick, "tick snap event");
         TickEvent::TickSnap {
             old_tick,
@@ -95,8 +265,203 @@ impl TickManager {
         self.tick
     }
 
+    /// Get the current [`WideTick`] of the local app, which wraps much less often than the [`Tick`]
+    pub fn wide_tick(&self) -> WideTick {
+        self.wide_tick
+    }
+
     /// Get the current tick of the app; works even if we are in rollback
     pub fn tick_or_rollback_tick(&self, rollback_state: &Rollback) -> Tick {
         rollback_state.get_rollback_tick().unwrap_or(self.tick)
     }
 }
+
+/// Extension trait to advance the simulation of a single [`App`] one tick at a time.
+///
+/// This is useful to unit-test gameplay systems against a precise sequence of ticks and inputs,
+/// without having to drive full frames of a client and a server.
+/// The plugins of the app must have been built (see [`App::finish`]) before stepping.
+///
+/// See also [`AppInputStepExt`](crate::prelude::client::AppInputStepExt) to buffer an input before each tick.
+pub trait AppTickStepExt {
+    /// Run the [`FixedMain`] schedule exactly once, which increments the [`TickManager`]'s tick.
+    ///
+    /// [`Time<Fixed>`] is advanced by one timestep, and the generic [`Time`] is set to the fixed clock
+    /// while the schedule runs, like it would be in [`RunFixedMainLoop`].
+    ///
+    /// Returns the tick that was simulated.
+    fn step_tick(&mut self) -> Tick;
+}
+
+impl AppTickStepExt for App {
+    fn step_tick(&mut self) -> Tick {
+        let world = self.world_mut();
+        let timestep = world.resource::<Time<Fixed>>().timestep();
+        world.resource_mut::<Time<Fixed>>().advance_by(timestep);
+        *world.resource_mut::<Time>() = world.resource::<Time<Fixed>>().as_generic();
+        world.run_schedule(FixedMain);
+        *world.resource_mut::<Time>() = world.resource::<Time<Virtual>>().as_generic();
+        world.resource::<TickManager>().tick()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::state::app::StatesPlugin;
+
+    use crate::prelude::client::{ClientConfig, ClientPlugins};
+    use crate::prelude::SharedConfig;
+    use crate::serialize::writer::Writer;
+    use crate::tests::protocol::ProtocolPlugin;
+
+    use super::*;
+
+    /// Check that the wide tick stays consistent with the tick through many wraps
+    #[test]
+    fn test_wide_tick_wrapping() {
+        let mut tick_manager = TickManager::from_config(TickConfig::new(Duration::from_millis(10)));
+        let num_ticks = 5 * (u16::MAX as u32 + 1) + 123;
+        for _ in 0..num_ticks {
+            tick_manager.increment_tick();
+            let wide_tick = tick_manager.wide_tick();
+            assert_eq!(wide_tick.tick(), tick_manager.tick());
+            assert_eq!(
+                WideTick::from_tick(wide_tick.tick(), wide_tick + 1000),
+                wide_tick
+            );
+            assert_eq!(
+                WideTick::from_tick(wide_tick.tick(), wide_tick + -1000),
+                wide_tick
+            );
+        }
+        assert_eq!(tick_manager.wide_tick(), WideTick(num_ticks));
+
+        // snapping the tick backwards across a wrap keeps the wide tick consistent
+        let wide_tick = tick_manager.wide_tick();
+        tick_manager.set_tick_to(tick_manager.tick() - 200u16);
+        assert_eq!(tick_manager.wide_tick(), wide_tick + -200);
+        assert!(tick_manager.wide_tick() < wide_tick);
+        tick_manager.set_tick_to(tick_manager.tick() + 500i16);
+        assert_eq!(tick_manager.wide_tick(), wide_tick + 300);
+
+        // the wide tick also wraps, at 2^32
+        assert!(WideTick(u32::MAX) < WideTick(u32::MAX) + 1);
+        assert_eq!(WideTick(u32::MAX) + 1, WideTick(0));
+        assert_eq!(WideTick(2) - WideTick(u32::MAX), 3);
+    }
+
+    /// Check that the wide tick wraps at the configured modulus, and stays consistent with the tick
+    #[test]
+    fn test_wide_tick_configured_modulus() {
+        let bits = 18;
+        let modulus = 1u32 << bits;
+        let mut tick_manager = TickManager::from_config(
+            TickConfig::new(Duration::from_millis(10)).with_wide_tick_bits(bits),
+        );
+        let mut previous = tick_manager.wide_tick();
+        for _ in 0..3 * modulus + 123 {
+            tick_manager.increment_tick();
+            let wide_tick = tick_manager.wide_tick();
+            assert!(wide_tick.0 < modulus);
+            assert_eq!(wide_tick.tick(), tick_manager.tick());
+            assert_eq!(wide_tick.wrapping_sub(previous, bits), 1);
+            assert_eq!(previous.wrapping_sub(wide_tick, bits), -1);
+            previous = wide_tick;
+        }
+        assert_eq!(tick_manager.wide_tick(), WideTick(123));
+
+        // snapping the tick backwards across the wrap of the wide tick
+        tick_manager.set_tick_to(tick_manager.tick() - 200u16);
+        assert_eq!(tick_manager.wide_tick(), WideTick(modulus - 77));
+        tick_manager.set_tick_to(tick_manager.tick() + 100i16);
+        assert_eq!(tick_manager.wide_tick(), WideTick(23));
+
+        // syncing from a reference of another generation, and from a reference across the wrap
+        tick_manager.sync_wide_tick(WideTick(2 * 65_536));
+        assert_eq!(tick_manager.wide_tick(), WideTick(2 * 65_536 + 23));
+        tick_manager.sync_wide_tick(WideTick(modulus - 1));
+        assert_eq!(tick_manager.wide_tick(), WideTick(23));
+
+        assert_eq!(WideTick(modulus - 1).wrapping_add(1, bits), WideTick(0));
+        assert_eq!(WideTick(0).wrapping_add(-1, bits), WideTick(modulus - 1));
+        assert_eq!(WideTick(2).wrapping_sub(WideTick(modulus - 1), bits), 3);
+        assert_eq!(WideTick(modulus - 1).wrapping_sub(WideTick(2), bits), -3);
+        // out of range bits are clamped
+        assert_eq!(WideTick(u32::MAX).wrapping_add(1, 64), WideTick(0));
+        assert_eq!(
+            WideTick(0).wrapping_add(-1, 0),
+            WideTick((1 << WideTick::MIN_BITS) - 1)
+        );
+    }
+
+    /// Check that a wide tick written as a delta against a base is read back identically, even across wraps
+    #[test]
+    fn test_wide_tick_delta_encoding() {
+        for (wide_tick, base, len) in [
+            (WideTick(1000), WideTick(1000), 1),
+            (WideTick(1010), WideTick(1000), 1),
+            (WideTick(990), WideTick(1000), 1),
+            (WideTick(100_000), WideTick(3), 4),
+            (WideTick(3), WideTick(100_000), 4),
+            // across the u16 wrap of the tick
+            (WideTick(65_540), WideTick(65_530), 1),
+            // across the u32 wrap of the wide tick
+            (WideTick(2), WideTick(u32::MAX - 2), 1),
+            (WideTick(u32::MAX - 2), WideTick(2), 1),
+            (WideTick(u32::MAX / 2), WideTick(0), 8),
+        ] {
+            let mut writer = Writer::default();
+            wide_tick.to_bytes_delta(base, &mut writer).unwrap();
+            let bytes = writer.to_bytes();
+            assert_eq!(bytes.len(), len);
+            assert_eq!(wide_tick.delta_len(base), len);
+            let mut reader = Reader::from(bytes);
+            assert_eq!(
+                WideTick::from_bytes_delta(base, &mut reader).unwrap(),
+                wide_tick
+            );
+        }
+    }
+
+    #[derive(Resource, Default)]
+    struct FixedDeltas(Vec<Duration>);
+
+    fn record_delta(mut deltas: ResMut<FixedDeltas>, time: Res<Time>) {
+        deltas.0.push(time.delta());
+    }
+
+    /// Check that stepping a single tick advances the fixed clock by one timestep
+    #[test]
+    fn test_step_tick_advances_time() {
+        let tick_duration = Duration::from_millis(10);
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatesPlugin));
+        app.add_plugins((
+            ClientPlugins::new(ClientConfig {
+                shared: SharedConfig {
+                    tick: TickConfig::new(tick_duration),
+                    ..default()
+                },
+                ..default()
+            }),
+            ProtocolPlugin,
+        ));
+        app.init_resource::<FixedDeltas>();
+        app.add_systems(FixedUpdate, record_delta);
+        app.finish();
+        app.cleanup();
+        let start_tick = app.world().resource::<TickManager>().tick();
+        let start_elapsed = app.world().resource::<Time<Fixed>>().elapsed();
+
+        assert_eq!(app.step_tick(), start_tick + 1);
+        assert_eq!(app.step_tick(), start_tick + 2);
+        assert_eq!(
+            app.world().resource::<FixedDeltas>().0,
+            vec![tick_duration, tick_duration]
+        );
+        assert_eq!(
+            app.world().resource::<Time<Fixed>>().elapsed(),
+            start_elapsed + 2 * tick_duration
+        );
+    }
+}
@@ -5,6 +5,8 @@
 pub(crate) mod host_server_stepper;
 mod integration;
 
+pub(crate) mod log_buffer;
+
 pub(crate) mod multi_stepper;
 pub mod protocol;
 pub(crate) mod stepper;
@@ -70,6 +70,9 @@ pub(crate) fn deserialize_component2(
 #[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq, Reflect)]
 pub struct ComponentSyncModeOnce(pub f32);
 
+#[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq, Reflect)]
+pub struct ComponentOwnerOnly(pub f32);
+
 #[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq, Reflect)]
 pub struct ComponentMapEntities(pub Entity);
 
@@ -146,6 +149,13 @@ impl Diffable for ComponentDeltaCompression2 {
 #[derive(Component, Clone, Debug, PartialEq, Reflect)]
 pub struct ComponentRollback(pub f32);
 
+#[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq, Reflect)]
+pub enum ComponentStateMachine {
+    Idle,
+    Running,
+    Jumping,
+}
+
 // Resources
 #[derive(Resource, Serialize, Deserialize, Debug, PartialEq, Clone, Reflect)]
 pub struct Resource1(pub f32);
@@ -179,6 +189,7 @@ cfg_if! {
         #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy, Hash, Reflect, Actionlike)]
         pub enum LeafwingInput1 {
             Jump,
+            Dash,
         }
 
         #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy, Hash, Reflect, Actionlike)]
@@ -225,6 +236,9 @@ impl Plugin for ProtocolPlugin {
         app.register_component::<ComponentSyncModeOnce>(ChannelDirection::ServerToClient)
             .add_prediction(ComponentSyncMode::Once);
 
+        app.register_component::<ComponentOwnerOnly>(ChannelDirection::ServerToClient)
+            .owner_only();
+
         app.register_component::<ComponentMapEntities>(ChannelDirection::ServerToClient)
             .add_prediction(ComponentSyncMode::Simple)
             .add_map_entities();
@@ -238,7 +252,11 @@ impl Plugin for ProtocolPlugin {
             .add_delta_compression();
 
         app.register_component::<ComponentDeltaCompression2>(ChannelDirection::ServerToClient)
-            .add_delta_compression();
+            .add_delta_compression()
+            .add_state_transition_events();
+
+        app.register_component::<ComponentStateMachine>(ChannelDirection::ServerToClient)
+            .add_state_transition_events();
 
         app.add_rollback::<ComponentRollback>();
 
@@ -1,5 +1,7 @@
 use crate::transport::middleware::compression::CompressionConfig;
 use crate::transport::middleware::conditioner::LinkConditionerConfig;
+use crate::transport::middleware::threaded::ThreadedIoConfig;
+use crate::transport::middleware::transform::PacketTransformConfig;
 use bevy::prelude::Reflect;
 
 #[derive(Clone, Debug, Default, Reflect)]
@@ -9,6 +11,12 @@ pub struct SharedIoConfig<T> {
     pub transport: T,
     pub conditioner: Option<LinkConditionerConfig>,
     pub compression: CompressionConfig,
+    /// Optional reversible transformation applied to the bytes of every packet (after compression)
+    #[reflect(ignore)]
+    pub transform: Option<PacketTransformConfig>,
+    /// If set, the transport IO runs on background threads that exchange packets with the
+    /// main schedule via channels. See [`ThreadedIoConfig`] for the latency implications.
+    pub threaded: Option<ThreadedIoConfig>,
 }
 
 impl<T> SharedIoConfig<T> {
@@ -17,6 +25,8 @@ impl<T> SharedIoConfig<T> {
             transport,
             conditioner: None,
             compression: CompressionConfig::default(),
+            transform: None,
+            threaded: None,
         }
     }
     pub fn with_conditioner(mut self, conditioner_config: LinkConditionerConfig) -> Self {
@@ -28,4 +38,14 @@ impl<T> SharedIoConfig<T> {
         self.compression = compression_config;
         self
     }
+
+    pub fn with_transform(mut self, transform_config: PacketTransformConfig) -> Self {
+        self.transform = Some(transform_config);
+        self
+    }
+
+    pub fn with_threaded_io(mut self, threaded_config: ThreadedIoConfig) -> Self {
+        self.threaded = Some(threaded_config);
+        self
+    }
 }
@@ -12,6 +12,12 @@ pub enum Error {
     #[cfg(all(feature = "websocket", not(target_family = "wasm")))]
     #[error(transparent)]
     WebSocket(#[from] tokio_tungstenite::tungstenite::error::Error),
+    #[cfg(all(feature = "steam", not(target_family = "wasm")))]
+    #[error(transparent)]
+    SteamInvalidHandle(#[from] steamworks::networking_sockets::InvalidHandle),
+    #[cfg(all(feature = "steam", not(target_family = "wasm")))]
+    #[error(transparent)]
+    Steam(#[from] steamworks::SteamError),
     #[error("could not send message via channel: {0}")]
     Channel(String),
     #[error("requested by user")]
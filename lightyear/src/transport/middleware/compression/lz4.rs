@@ -130,6 +130,8 @@ mod tests {
             transport: config,
             conditioner: None,
             compression: CompressionConfig::Lz4,
+            transform: None,
+            threaded: None,
         };
         let mut io = io_config.connect().unwrap();
         let msg = b"hello world".as_slice();
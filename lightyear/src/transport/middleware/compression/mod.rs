@@ -1,6 +1,8 @@
 use bevy::prelude::Reflect;
 use serde::{Deserialize, Serialize};
 
+use crate::serialize::SerializationError;
+
 #[cfg(feature = "zstd")]
 pub(crate) mod zstd;
 
@@ -16,3 +18,54 @@ pub enum CompressionConfig {
     #[cfg(feature = "lz4")]
     Lz4,
 }
+
+impl CompressionConfig {
+    /// Tag written before a payload compressed with [`Self::compress_payload`]
+    fn tag(&self) -> u8 {
+        match self {
+            CompressionConfig::None => 0,
+            #[cfg(feature = "zstd")]
+            CompressionConfig::Zstd { .. } => 1,
+            #[cfg(feature = "lz4")]
+            CompressionConfig::Lz4 => 2,
+        }
+    }
+
+    /// Compress a whole payload, which can be larger than a packet (the io middleware
+    /// only compresses individual packets).
+    ///
+    /// The algorithm is written at the start of the payload, so that the remote can call
+    /// [`decompress_payload`] without knowing our config.
+    pub(crate) fn compress_payload(&self, data: &[u8]) -> Result<Vec<u8>, SerializationError> {
+        let mut payload = vec![self.tag()];
+        match self {
+            CompressionConfig::None => payload.extend_from_slice(data),
+            #[cfg(feature = "zstd")]
+            CompressionConfig::Zstd { level } => {
+                payload.extend(::zstd::bulk::compress(data, *level)?);
+            }
+            #[cfg(feature = "lz4")]
+            CompressionConfig::Lz4 => {
+                payload.extend(lz4_flex::block::compress_prepend_size(data));
+            }
+        }
+        Ok(payload)
+    }
+}
+
+/// Decompress a payload compressed with [`CompressionConfig::compress_payload`]
+pub(crate) fn decompress_payload(payload: &[u8]) -> Result<Vec<u8>, SerializationError> {
+    let Some((tag, data)) = payload.split_first() else {
+        return Err(SerializationError::InvalidValue);
+    };
+    match tag {
+        0 => Ok(data.to_vec()),
+        #[cfg(feature = "zstd")]
+        1 => Ok(::zstd::stream::decode_all(data)?),
+        #[cfg(feature = "lz4")]
+        2 => lz4_flex::block::decompress_size_prepended(data)
+            .map_err(|_| SerializationError::InvalidValue),
+        // the remote uses an algorithm that is not enabled in our features
+        _ => Err(SerializationError::InvalidValue),
+    }
+}
@@ -108,7 +108,8 @@ pub(crate) mod decompression {
 
 #[cfg(test)]
 mod tests {
-    use crate::prelude::{SharedIoConfig, TransportConfig};
+    use crate::client::io::config::ClientTransport;
+    use crate::transport::config::SharedIoConfig;
     use crate::transport::middleware::compression::CompressionConfig;
     use crate::transport::LOCAL_SOCKET;
 
@@ -116,11 +117,13 @@ mod tests {
     fn test_compression() {
         let (send, recv) = crossbeam_channel::unbounded();
 
-        let config = TransportConfig::LocalChannel { send, recv };
+        let config = ClientTransport::LocalChannel { send, recv };
         let io_config = SharedIoConfig {
             transport: config,
             conditioner: None,
             compression: CompressionConfig::Zstd { level: 0 },
+            transform: None,
+            threaded: None,
         };
         let mut io = io_config.connect().unwrap();
         let msg = b"hello world".as_slice();
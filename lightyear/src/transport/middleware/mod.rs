@@ -9,6 +9,12 @@ pub(crate) mod conditioner;
 /// Middleware that compresses packets before sending them.
 pub(crate) mod compression;
 
+/// Middleware that applies a user-provided reversible transformation to the packet bytes.
+pub(crate) mod transform;
+
+/// Middleware that runs the transport IO on background threads.
+pub(crate) mod threaded;
+
 pub trait PacketReceiverWrapper<T: PacketReceiver> {
     fn wrap(self, receiver: T) -> impl PacketReceiver;
 }
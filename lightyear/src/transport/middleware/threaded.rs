@@ -0,0 +1,258 @@
+//! Middleware that moves the IO of the transport to dedicated background threads.
+//!
+//! A background thread owns the sender and receiver of the transport (including the other middlewares,
+//! such as compression) and exchanges packets with the main schedule via crossbeam channels:
+//! - sending a packet only pushes it into a channel; a background thread sends it on the transport
+//! - a background thread polls the transport and pushes the received packets into a channel, which is
+//!   drained when the packets are received by the connection
+//!
+//! Packets are still sent and received in order (for each direction), but:
+//! - received packets are only visible to the main schedule once they've been polled by the background
+//!   thread, which adds up to one [`poll_interval`](ThreadedIoConfig::poll_interval) of latency
+//! - errors that happen while sending a packet cannot be returned to the caller anymore; they are only logged
+//!
+//! This is not supported on wasm, where the transport is used directly.
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use bevy::prelude::Reflect;
+use crossbeam_channel::{Receiver, Sender, TryRecvError};
+use tracing::error;
+
+use crate::transport::error::{Error, Result};
+use crate::transport::{BoxedReceiver, BoxedSender, PacketReceiver, PacketSender};
+
+/// Configuration for running the transport IO on background threads, that can be added to the
+/// [`SharedIoConfig`](crate::transport::config::SharedIoConfig).
+#[derive(Clone, Copy, Debug, PartialEq, Reflect)]
+pub struct ThreadedIoConfig {
+    /// How long the background thread waits before polling the transport again when no packet
+    /// was received, or when the transport returned an error.
+    ///
+    /// If it is zero, the thread yields to the OS scheduler between polls instead of sleeping.
+    pub poll_interval: Duration,
+}
+
+impl Default for ThreadedIoConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_millis(1),
+        }
+    }
+}
+
+impl ThreadedIoConfig {
+    /// Move the sender and receiver to background threads, and return a sender/receiver pair that
+    /// exchanges packets with them.
+    #[cfg(not(target_family = "wasm"))]
+    pub(crate) fn spawn(
+        self,
+        sender: BoxedSender,
+        receiver: BoxedReceiver,
+    ) -> (BoxedSender, BoxedReceiver) {
+        let (send_tx, send_rx) = crossbeam_channel::unbounded();
+        let (recv_tx, recv_rx) = crossbeam_channel::unbounded();
+        let stop = Arc::new(AtomicBool::new(false));
+        std::thread::Builder::new()
+            .name("lightyear-io-send".to_string())
+            .spawn(move || send_loop(sender, send_rx))
+            .expect("could not spawn the io send thread");
+        let thread_stop = stop.clone();
+        std::thread::Builder::new()
+            .name("lightyear-io-recv".to_string())
+            .spawn(move || recv_loop(receiver, recv_tx, thread_stop, self.poll_interval))
+            .expect("could not spawn the io recv thread");
+        (
+            Box::new(ThreadedPacketSender { sender: send_tx }),
+            Box::new(ThreadedPacketReceiver {
+                receiver: recv_rx,
+                buffer: vec![],
+                stop,
+            }),
+        )
+    }
+
+    #[cfg(target_family = "wasm")]
+    pub(crate) fn spawn(
+        self,
+        sender: BoxedSender,
+        receiver: BoxedReceiver,
+    ) -> (BoxedSender, BoxedReceiver) {
+        tracing::warn!("Threaded IO is not supported on wasm, the transport will be used directly");
+        (sender, receiver)
+    }
+}
+
+/// Send the packets pushed by the main schedule, until the [`ThreadedPacketSender`] is dropped
+fn send_loop(mut sender: BoxedSender, packets: Receiver<(Vec<u8>, SocketAddr)>) {
+    for (payload, address) in packets.iter() {
+        if let Err(e) = sender.send(&payload, &address) {
+            error!(?e, "Error sending packet from the io thread");
+        }
+    }
+}
+
+/// Poll the transport and forward the received packets, until the [`ThreadedPacketReceiver`] is dropped
+fn recv_loop(
+    mut receiver: BoxedReceiver,
+    packets: Sender<Result<(Vec<u8>, SocketAddr)>>,
+    stop: Arc<AtomicBool>,
+    poll_interval: Duration,
+) {
+    while !stop.load(Ordering::Relaxed) {
+        let packet = match receiver.recv() {
+            Ok(Some((payload, address))) => Ok((payload.to_vec(), address)),
+            Ok(None) => {
+                wait(poll_interval);
+                continue;
+            }
+            Err(e) => Err(e),
+        };
+        let is_err = packet.is_err();
+        if packets.send(packet).is_err() {
+            return;
+        }
+        // do not spin if the transport keeps returning errors
+        if is_err {
+            wait(poll_interval);
+        }
+    }
+}
+
+/// Wait before polling the transport again
+fn wait(poll_interval: Duration) {
+    if poll_interval.is_zero() {
+        std::thread::yield_now();
+    } else {
+        std::thread::sleep(poll_interval);
+    }
+}
+
+struct ThreadedPacketSender {
+    sender: Sender<(Vec<u8>, SocketAddr)>,
+}
+
+impl PacketSender for ThreadedPacketSender {
+    fn send(&mut self, payload: &[u8], address: &SocketAddr) -> Result<()> {
+        Ok(self.sender.send((payload.to_vec(), *address))?)
+    }
+}
+
+struct ThreadedPacketReceiver {
+    receiver: Receiver<Result<(Vec<u8>, SocketAddr)>>,
+    /// Holds the last received packet, so that we can return a reference to it
+    buffer: Vec<u8>,
+    stop: Arc<AtomicBool>,
+}
+
+impl PacketReceiver for ThreadedPacketReceiver {
+    fn recv(&mut self) -> Result<Option<(&mut [u8], SocketAddr)>> {
+        match self.receiver.try_recv() {
+            Ok(packet) => {
+                let (payload, address) = packet?;
+                self.buffer = payload;
+                Ok(Some((&mut self.buffer, address)))
+            }
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => {
+                Err(Error::Channel("the io recv thread has stopped".to_string()))
+            }
+        }
+    }
+}
+
+impl Drop for ThreadedPacketReceiver {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+    use std::time::Instant;
+
+    use super::*;
+    use crate::prelude::client::ClientTransport;
+    use crate::transport::config::SharedIoConfig;
+    use crate::transport::LOCAL_SOCKET;
+
+    /// Check that packets still round-trip when the io runs on background threads
+    #[test]
+    fn test_threaded_io_round_trip() {
+        let (wire_send, wire_recv) = crossbeam_channel::unbounded();
+        let (remote_send, remote_recv) = crossbeam_channel::unbounded();
+        let io_config = SharedIoConfig::from_transport(ClientTransport::LocalChannel {
+            recv: remote_recv,
+            send: wire_send,
+        })
+        .with_threaded_io(ThreadedIoConfig::default());
+        let mut io = io_config.connect().unwrap();
+
+        let messages: Vec<Vec<u8>> = (0..10u8).map(|i| vec![i; 10]).collect();
+        for msg in &messages {
+            io.sender.send(msg, &LOCAL_SOCKET).unwrap();
+        }
+        // the remote echoes the packets back
+        let deadline = Instant::now() + Duration::from_secs(5);
+        for msg in &messages {
+            let wire_bytes = wire_recv.recv_deadline(deadline).unwrap();
+            assert_eq!(&wire_bytes, msg);
+            remote_send.send(wire_bytes).unwrap();
+        }
+
+        // the packets are received in order
+        let mut received = vec![];
+        while received.len() < messages.len() {
+            assert!(Instant::now() < deadline, "packets were not received");
+            match io.receiver.recv().unwrap() {
+                Some((data, _)) => received.push(data.to_vec()),
+                None => std::thread::sleep(Duration::from_millis(1)),
+            }
+        }
+        assert_eq!(received, messages);
+    }
+
+    /// Receiver that never receives any packet, and counts how many times it was polled
+    struct CountingReceiver {
+        polls: Arc<AtomicUsize>,
+        error: bool,
+    }
+
+    impl PacketReceiver for CountingReceiver {
+        fn recv(&mut self) -> Result<Option<(&mut [u8], SocketAddr)>> {
+            self.polls.fetch_add(1, Ordering::Relaxed);
+            if self.error {
+                return Err(Error::NotConnected);
+            }
+            Ok(None)
+        }
+    }
+
+    /// Check that the background thread waits between polls when the transport is empty
+    /// or keeps returning errors, instead of spinning
+    #[test]
+    fn test_recv_loop_does_not_spin() {
+        for error in [false, true] {
+            let polls = Arc::new(AtomicUsize::new(0));
+            let receiver = Box::new(CountingReceiver {
+                polls: polls.clone(),
+                error,
+            });
+            let (recv_tx, recv_rx) = crossbeam_channel::unbounded();
+            let stop = Arc::new(AtomicBool::new(false));
+            let thread_stop = stop.clone();
+            let handle = std::thread::spawn(move || {
+                recv_loop(receiver, recv_tx, thread_stop, Duration::from_millis(20))
+            });
+            std::thread::sleep(Duration::from_millis(100));
+            stop.store(true, Ordering::Relaxed);
+            handle.join().unwrap();
+            // about 5 polls are expected; a spinning thread would poll millions of times
+            assert!(polls.load(Ordering::Relaxed) <= 10, "error: {error}");
+            assert_eq!(recv_rx.try_iter().count() > 0, error);
+        }
+    }
+}
@@ -0,0 +1,146 @@
+//! Middleware that applies a user-provided reversible transformation to the packet bytes.
+//!
+//! The transformation is applied to the final bytes of each packet right before they are sent
+//! (after compression), and reversed on the receiving side before decompression.
+//!
+//! This can be used to obfuscate the traffic, for example to avoid being throttled by deep packet inspection.
+//! It does NOT provide any security: use an encrypted transport (or netcode) for that.
+use std::fmt::{Debug, Formatter};
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use crate::connection::netcode::MAX_PKT_BUF_SIZE;
+use crate::transport::error::{Error, Result};
+use crate::transport::middleware::{PacketReceiverWrapper, PacketSenderWrapper};
+use crate::transport::{PacketReceiver, PacketSender};
+
+/// A reversible transformation applied to the bytes of every packet.
+///
+/// `decode` must exactly reverse `encode`, and both peers must use the same transform.
+pub trait PacketTransform: Send + Sync + 'static {
+    /// Transform the bytes of an outgoing packet. `output` is empty when this is called.
+    fn encode(&self, input: &[u8], output: &mut Vec<u8>);
+
+    /// Revert the transformation applied by [`encode`](PacketTransform::encode) on an incoming packet.
+    /// `output` is empty when this is called.
+    fn decode(&self, input: &[u8], output: &mut Vec<u8>) -> std::io::Result<()>;
+}
+
+/// Configuration for the packet transform middleware, that can be added to the
+/// [`SharedIoConfig`](crate::transport::config::SharedIoConfig).
+#[derive(Clone)]
+pub struct PacketTransformConfig(Arc<dyn PacketTransform>);
+
+impl PacketTransformConfig {
+    pub fn new(transform: impl PacketTransform) -> Self {
+        Self(Arc::new(transform))
+    }
+}
+
+impl Debug for PacketTransformConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PacketTransformConfig").finish()
+    }
+}
+
+struct TransformPacketSender<T: PacketSender> {
+    inner: T,
+    transform: Arc<dyn PacketTransform>,
+    buffer: Vec<u8>,
+}
+
+impl<T: PacketSender> PacketSender for TransformPacketSender<T> {
+    fn send(&mut self, payload: &[u8], address: &SocketAddr) -> Result<()> {
+        self.buffer.clear();
+        self.transform.encode(payload, &mut self.buffer);
+        self.inner.send(&self.buffer, address)
+    }
+}
+
+impl<T: PacketSender> PacketSenderWrapper<T> for PacketTransformConfig {
+    fn wrap(self, sender: T) -> impl PacketSender {
+        TransformPacketSender {
+            inner: sender,
+            transform: self.0,
+            buffer: Vec::with_capacity(MAX_PKT_BUF_SIZE),
+        }
+    }
+}
+
+struct TransformPacketReceiver<T: PacketReceiver> {
+    inner: T,
+    transform: Arc<dyn PacketTransform>,
+    buffer: Vec<u8>,
+}
+
+impl<T: PacketReceiver> PacketReceiver for TransformPacketReceiver<T> {
+    fn recv(&mut self) -> Result<Option<(&mut [u8], SocketAddr)>> {
+        if let Some((buf, addr)) = self.inner.recv()? {
+            self.buffer.clear();
+            self.transform
+                .decode(buf, &mut self.buffer)
+                .map_err(Error::Io)?;
+            Ok(Some((&mut self.buffer, addr)))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+impl<T: PacketReceiver> PacketReceiverWrapper<T> for PacketTransformConfig {
+    fn wrap(self, receiver: T) -> impl PacketReceiver {
+        TransformPacketReceiver {
+            inner: receiver,
+            transform: self.0,
+            buffer: Vec::with_capacity(MAX_PKT_BUF_SIZE),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::client::ClientTransport;
+    use crate::transport::config::SharedIoConfig;
+    use crate::transport::LOCAL_SOCKET;
+
+    struct XorTransform(u8);
+
+    impl PacketTransform for XorTransform {
+        fn encode(&self, input: &[u8], output: &mut Vec<u8>) {
+            output.extend(input.iter().map(|b| b ^ self.0));
+        }
+
+        fn decode(&self, input: &[u8], output: &mut Vec<u8>) -> std::io::Result<()> {
+            output.extend(input.iter().map(|b| b ^ self.0));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_xor_transform() {
+        let (wire_send, wire_recv) = crossbeam_channel::unbounded();
+        let (remote_send, remote_recv) = crossbeam_channel::unbounded();
+        let io_config = SharedIoConfig::from_transport(ClientTransport::LocalChannel {
+            recv: remote_recv,
+            send: wire_send,
+        })
+        .with_transform(PacketTransformConfig::new(XorTransform(0xAA)));
+        let mut io = io_config.connect().unwrap();
+        let msg = b"hello world".as_slice();
+
+        // the bytes sent on the wire are obfuscated
+        io.sender.send(msg, &LOCAL_SOCKET).unwrap();
+        let wire_bytes = wire_recv.try_recv().unwrap();
+        assert_ne!(wire_bytes.as_slice(), msg);
+        assert_eq!(
+            wire_bytes,
+            msg.iter().map(|b| b ^ 0xAA).collect::<Vec<u8>>()
+        );
+
+        // the obfuscated bytes are restored on receive
+        remote_send.send(wire_bytes).unwrap();
+        let (data, _) = io.receiver.recv().unwrap().unwrap();
+        assert_eq!(data, msg);
+    }
+}
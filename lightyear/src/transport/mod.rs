@@ -13,6 +13,8 @@ use crate::server::io::transport::ServerTransportEnum;
 use crate::transport::channels::Channels;
 use crate::transport::dummy::DummyIo;
 use crate::transport::local::LocalChannel;
+#[cfg(all(feature = "steam", not(target_family = "wasm")))]
+use crate::transport::steam::{SteamClientSocket, SteamServerSocket};
 use crate::transport::udp::UdpSocket;
 #[cfg(feature = "websocket")]
 use crate::transport::websocket::client::{WebSocketClientSocket, WebSocketClientSocketBuilder};
@@ -49,6 +51,8 @@ pub(crate) mod middleware;
 pub mod config;
 pub(crate) mod dummy;
 pub(crate) mod error;
+/// The transport is a Steam socket, with netcode running on top of it
+pub mod steam;
 #[cfg_attr(docsrs, doc(cfg(feature = "websocket")))]
 #[cfg(feature = "websocket")]
 pub(crate) mod websocket;
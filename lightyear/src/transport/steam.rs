@@ -0,0 +1,612 @@
+//! Use Steam sockets as a raw transport for netcode
+//!
+//! With this transport, Steam only carries the bytes between the peers: the connection handshake,
+//! authentication and encryption are handled by netcode, using lightyear's
+//! [`ConnectToken`](crate::connection::netcode::ConnectToken)s and private key, exactly like with a UDP socket.
+//!
+//! Steam peers are not identified by a socket address, so every peer is given a virtual [`SocketAddr`]
+//! derived from its steam id (see [`steam_id_to_addr`]). Clients that connect by IP without a Steam identity
+//! are given a virtual address from a separate range.
+//! For P2P connections, the connect token given to the client must use the virtual address of the host.
+use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+
+/// Prefix of the virtual addresses used for Steam peers (the `fd00::/64` unique local range)
+const STEAM_ADDR_PREFIX: u128 = 0xfd00 << 112;
+
+/// Virtual [`SocketAddr`] that identifies the Steam peer with the given steam id
+pub fn steam_id_to_addr(steam_id: u64) -> SocketAddr {
+    SocketAddr::new(
+        IpAddr::V6(Ipv6Addr::from(STEAM_ADDR_PREFIX | steam_id as u128)),
+        0,
+    )
+}
+
+/// Steam id of the peer identified by a virtual address created with [`steam_id_to_addr`]
+///
+/// Returns None if the address is not the virtual address of a Steam peer.
+pub fn addr_to_steam_id(addr: &SocketAddr) -> Option<u64> {
+    match addr {
+        SocketAddr::V6(addr) if u128::from(*addr.ip()) >> 64 == STEAM_ADDR_PREFIX >> 64 => {
+            Some(u128::from(*addr.ip()) as u64)
+        }
+        _ => None,
+    }
+}
+
+pub(crate) use socket::*;
+#[cfg(all(feature = "steam", not(target_family = "wasm")))]
+pub(crate) use steamworks_socket::*;
+
+/// Transport generic over the Steam connections, which only uses steamworks through
+/// the [`SteamConnection`] and [`SteamEvents`] traits
+mod socket {
+    // without the `steam` feature, the socket is only used in tests
+    #![cfg_attr(
+        not(all(feature = "steam", not(target_family = "wasm"))),
+        allow(dead_code)
+    )]
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+
+    use bevy::utils::HashMap;
+    use parking_lot::Mutex;
+
+    use crate::transport::error::{Error, Result};
+    use crate::transport::{BoxedReceiver, BoxedSender, PacketReceiver, PacketSender, Transport};
+
+    /// Connection to a single remote Steam peer
+    pub(crate) trait SteamConnection: Send {
+        /// Send an unreliable message to the peer
+        fn send_message(&self, payload: &[u8]) -> Result<()>;
+
+        /// Write the next message received from the peer in `buffer`.
+        ///
+        /// Returns false if no message is available
+        fn receive_message(&mut self, buffer: &mut Vec<u8>) -> Result<bool>;
+    }
+
+    /// Runs the Steam callbacks and keeps track of the connections of a [`SteamSocket`]
+    pub(crate) trait SteamEvents: Send {
+        type Connection: SteamConnection;
+
+        /// Run the Steam callbacks and add or remove the connections to the remote peers.
+        ///
+        /// This is called once per update, before receiving the packets
+        fn process_events(&mut self, connections: &mut HashMap<SocketAddr, Self::Connection>);
+    }
+
+    struct SteamSocketState<E: SteamEvents> {
+        events: E,
+        connections: HashMap<SocketAddr, E::Connection>,
+    }
+
+    /// Transport that exchanges the netcode packets with the remote peers over Steam connections.
+    ///
+    /// Each remote peer is identified by the virtual address of its connection.
+    pub(crate) struct SteamSocket<E: SteamEvents> {
+        local_addr: SocketAddr,
+        state: Arc<Mutex<SteamSocketState<E>>>,
+    }
+
+    impl<E: SteamEvents> SteamSocket<E> {
+        pub(crate) fn new(
+            local_addr: SocketAddr,
+            events: E,
+            connections: HashMap<SocketAddr, E::Connection>,
+        ) -> Self {
+            Self {
+                local_addr,
+                state: Arc::new(Mutex::new(SteamSocketState {
+                    events,
+                    connections,
+                })),
+            }
+        }
+    }
+
+    impl<E: SteamEvents + 'static> Transport for SteamSocket<E> {
+        fn local_addr(&self) -> SocketAddr {
+            self.local_addr
+        }
+
+        fn split(self) -> (BoxedSender, BoxedReceiver) {
+            let sender = SteamSocketSender {
+                state: self.state.clone(),
+            };
+            let receiver = SteamSocketReceiver {
+                state: self.state,
+                buffer: vec![],
+                events_processed: false,
+            };
+            (Box::new(sender), Box::new(receiver))
+        }
+    }
+
+    struct SteamSocketSender<E: SteamEvents> {
+        state: Arc<Mutex<SteamSocketState<E>>>,
+    }
+
+    impl<E: SteamEvents> PacketSender for SteamSocketSender<E> {
+        fn send(&mut self, payload: &[u8], address: &SocketAddr) -> Result<()> {
+            let state = self.state.lock();
+            let Some(connection) = state.connections.get(address) else {
+                return Err(Error::NotConnected);
+            };
+            connection.send_message(payload)
+        }
+    }
+
+    struct SteamSocketReceiver<E: SteamEvents> {
+        state: Arc<Mutex<SteamSocketState<E>>>,
+        buffer: Vec<u8>,
+        /// The receiver is drained once per update: the events are only processed
+        /// on the first call to `recv` of each update
+        events_processed: bool,
+    }
+
+    impl<E: SteamEvents> PacketReceiver for SteamSocketReceiver<E> {
+        fn recv(&mut self) -> Result<Option<(&mut [u8], SocketAddr)>> {
+            let mut state = self.state.lock();
+            let SteamSocketState {
+                events,
+                connections,
+            } = &mut *state;
+            if !self.events_processed {
+                events.process_events(connections);
+                self.events_processed = true;
+            }
+            for (addr, connection) in connections.iter_mut() {
+                match connection.receive_message(&mut self.buffer) {
+                    Ok(true) => return Ok(Some((self.buffer.as_mut_slice(), *addr))),
+                    Ok(false) => {}
+                    Err(e) => {
+                        self.events_processed = false;
+                        return Err(e);
+                    }
+                }
+            }
+            self.events_processed = false;
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(all(feature = "steam", not(target_family = "wasm")))]
+mod steamworks_socket {
+    use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+    use std::sync::Arc;
+
+    use bevy::utils::HashMap;
+    use parking_lot::RwLock;
+    use steamworks::networking_sockets::{ListenSocket, NetConnection};
+    use steamworks::networking_types::{ListenSocketEvent, NetworkingIdentity, SendFlags};
+    use steamworks::{ClientManager, SteamId};
+    use tracing::{error, info};
+
+    use crate::client::io::transport::{ClientTransportBuilder, ClientTransportEnum};
+    use crate::client::io::{ClientIoEventReceiver, ClientNetworkEventSender};
+    use crate::connection::steam::steamworks_client::SteamworksClient;
+    use crate::connection::steam::{client, server};
+    use crate::server::io::transport::{ServerTransportBuilder, ServerTransportEnum};
+    use crate::server::io::{ServerIoEventReceiver, ServerNetworkEventSender};
+    use crate::transport::error::Result;
+    use crate::transport::io::IoState;
+
+    use super::socket::{SteamConnection, SteamEvents, SteamSocket};
+    use super::steam_id_to_addr;
+
+    /// Prefix of the virtual addresses used for the peers that connect by IP without a Steam identity
+    /// (the `fd01::/64` unique local range)
+    const IP_PEER_ADDR_PREFIX: u128 = 0xfd01 << 112;
+
+    /// Virtual [`SocketAddr`] of the n-th peer that connected without a Steam identity
+    fn ip_peer_addr(id: u64) -> SocketAddr {
+        SocketAddr::new(
+            IpAddr::V6(Ipv6Addr::from(IP_PEER_ADDR_PREFIX | id as u128)),
+            0,
+        )
+    }
+
+    impl SteamConnection for NetConnection<ClientManager> {
+        fn send_message(&self, payload: &[u8]) -> Result<()> {
+            NetConnection::send_message(self, payload, SendFlags::UNRELIABLE_NO_NAGLE)?;
+            Ok(())
+        }
+
+        fn receive_message(&mut self, buffer: &mut Vec<u8>) -> Result<bool> {
+            let Some(message) = self.receive_messages(1)?.pop() else {
+                return Ok(false);
+            };
+            buffer.clear();
+            buffer.extend_from_slice(message.data());
+            Ok(true)
+        }
+    }
+
+    /// Steam events of the server: every incoming connection is accepted, since the connection
+    /// is only authenticated once the netcode handshake completes
+    pub(crate) struct SteamListener {
+        steamworks_client: Arc<RwLock<SteamworksClient>>,
+        listen_socket: ListenSocket<ClientManager>,
+        /// Id used to create the virtual address of the last peer that connected without a Steam identity
+        last_ip_peer_id: u64,
+    }
+
+    impl SteamListener {
+        /// Virtual address of a remote peer.
+        ///
+        /// Peers with a steam id are identified by their steam id; the other peers (clients that
+        /// connect by IP without a Steam identity) by an id stored in the connection user data
+        fn peer_addr(remote: &NetworkingIdentity, user_data: i64) -> SocketAddr {
+            match remote.steam_id() {
+                Some(steam_id) => steam_id_to_addr(steam_id.raw()),
+                None => ip_peer_addr(user_data as u64),
+            }
+        }
+    }
+
+    impl SteamEvents for SteamListener {
+        type Connection = NetConnection<ClientManager>;
+
+        fn process_events(&mut self, connections: &mut HashMap<SocketAddr, Self::Connection>) {
+            self.steamworks_client
+                .try_write()
+                .expect("could not get steamworks client")
+                .get_single()
+                .run_callbacks();
+            while let Some(event) = self.listen_socket.try_receive_event() {
+                match event {
+                    ListenSocketEvent::Connecting(event) => {
+                        if let Err(e) = event.accept() {
+                            error!("Failed to accept steam connection: {e}");
+                        }
+                    }
+                    ListenSocketEvent::Connected(event) => {
+                        let remote = event.remote();
+                        if remote.steam_id().is_none() {
+                            self.last_ip_peer_id += 1;
+                            if let Err(e) = event
+                                .connection()
+                                .set_connection_user_data(self.last_ip_peer_id as i64)
+                            {
+                                // dropping the connection closes it
+                                error!("Failed to identify steam connection: {e}");
+                                continue;
+                            }
+                        }
+                        let addr = Self::peer_addr(&remote, self.last_ip_peer_id as i64);
+                        connections.insert(addr, event.take_connection());
+                    }
+                    ListenSocketEvent::Disconnected(event) => {
+                        connections.remove(&Self::peer_addr(&event.remote(), event.user_data()));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Steam events of the client: the connection to the server is opened when the socket is built
+    pub(crate) struct SteamCallbacks {
+        steamworks_client: Arc<RwLock<SteamworksClient>>,
+    }
+
+    impl SteamEvents for SteamCallbacks {
+        type Connection = NetConnection<ClientManager>;
+
+        fn process_events(&mut self, _: &mut HashMap<SocketAddr, Self::Connection>) {
+            self.steamworks_client
+                .try_write()
+                .expect("could not get steamworks client")
+                .get_single()
+                .run_callbacks();
+        }
+    }
+
+    pub(crate) type SteamServerSocket = SteamSocket<SteamListener>;
+    pub(crate) type SteamClientSocket = SteamSocket<SteamCallbacks>;
+
+    pub(crate) struct SteamServerSocketBuilder {
+        pub(crate) steamworks_client: Arc<RwLock<SteamworksClient>>,
+        pub(crate) socket_config: server::SocketConfig,
+    }
+
+    impl ServerTransportBuilder for SteamServerSocketBuilder {
+        fn start(
+            self,
+        ) -> Result<(
+            ServerTransportEnum,
+            IoState,
+            Option<ServerIoEventReceiver>,
+            Option<ServerNetworkEventSender>,
+        )> {
+            let client = self
+                .steamworks_client
+                .try_read()
+                .expect("could not get steamworks client")
+                .get_client();
+            let (local_addr, listen_socket) = match self.socket_config {
+                server::SocketConfig::Ip {
+                    server_ip,
+                    game_port,
+                    ..
+                } => {
+                    let server_addr = SocketAddr::new(server_ip.into(), game_port);
+                    let listen_socket = client
+                        .networking_sockets()
+                        .create_listen_socket_ip(server_addr, vec![])?;
+                    info!("Steam socket started on {:?}", server_addr);
+                    (server_addr, listen_socket)
+                }
+                server::SocketConfig::P2P { virtual_port } => {
+                    // the clients connect via the relay network, so make sure that we have access to it
+                    client.networking_utils().init_relay_network_access();
+                    let listen_socket = client
+                        .networking_sockets()
+                        .create_listen_socket_p2p(virtual_port, vec![])?;
+                    info!(
+                        "Steam P2P socket started on virtual port: {:?}",
+                        virtual_port
+                    );
+                    (
+                        steam_id_to_addr(client.user().steam_id().raw()),
+                        listen_socket,
+                    )
+                }
+            };
+            let listener = SteamListener {
+                steamworks_client: self.steamworks_client,
+                listen_socket,
+                last_ip_peer_id: 0,
+            };
+            Ok((
+                ServerTransportEnum::Steam(SteamSocket::new(local_addr, listener, HashMap::new())),
+                IoState::Connected,
+                None,
+                None,
+            ))
+        }
+    }
+
+    pub(crate) struct SteamClientSocketBuilder {
+        pub(crate) steamworks_client: Arc<RwLock<SteamworksClient>>,
+        pub(crate) socket_config: client::SocketConfig,
+    }
+
+    impl ClientTransportBuilder for SteamClientSocketBuilder {
+        fn connect(
+            self,
+        ) -> Result<(
+            ClientTransportEnum,
+            IoState,
+            Option<ClientIoEventReceiver>,
+            Option<ClientNetworkEventSender>,
+        )> {
+            let client = self
+                .steamworks_client
+                .try_read()
+                .expect("could not get steamworks client")
+                .get_client();
+            // the packets are received from the address that the netcode client expects
+            // the server to have (the address in the connect token)
+            let (server_addr, connection) = match self.socket_config {
+                client::SocketConfig::Ip { server_addr } => {
+                    let connection = client
+                        .networking_sockets()
+                        .connect_by_ip_address(server_addr, vec![])?;
+                    info!(
+                        "Opened steam connection to server at address: {}",
+                        server_addr
+                    );
+                    (server_addr, connection)
+                }
+                client::SocketConfig::P2P {
+                    virtual_port,
+                    steam_id,
+                } => {
+                    // P2P connections go through the relay network, so make sure that we have access to it
+                    client.networking_utils().init_relay_network_access();
+                    let connection = client.networking_sockets().connect_p2p(
+                        NetworkingIdentity::new_steam_id(SteamId::from_raw(steam_id)),
+                        virtual_port,
+                        vec![],
+                    )?;
+                    info!(
+                        "Opened steam P2P connection to host {} on virtual port {}",
+                        steam_id, virtual_port
+                    );
+                    (steam_id_to_addr(steam_id), connection)
+                }
+            };
+            let local_addr = steam_id_to_addr(client.user().steam_id().raw());
+            let callbacks = SteamCallbacks {
+                steamworks_client: self.steamworks_client,
+            };
+            Ok((
+                ClientTransportEnum::Steam(SteamSocket::new(
+                    local_addr,
+                    callbacks,
+                    HashMap::from([(server_addr, connection)]),
+                )),
+                IoState::Connected,
+                None,
+                None,
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use super::*;
+
+    const CLIENT_STEAM_ID: u64 = 76561197960287931;
+
+    #[test]
+    fn test_steam_addr() {
+        let addr = steam_id_to_addr(CLIENT_STEAM_ID);
+        assert_eq!(addr_to_steam_id(&addr), Some(CLIENT_STEAM_ID));
+        assert_eq!(
+            addr_to_steam_id(&SocketAddr::from(([127, 0, 0, 1], 5000))),
+            None
+        );
+    }
+
+    mod socket {
+        use std::net::SocketAddr;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        use bevy::utils::HashMap;
+        use crossbeam_channel::{Receiver, Sender};
+
+        use crate::connection::netcode::{generate_key, NetcodeClient, NetcodeServer};
+        use crate::transport::error::Result;
+        use crate::transport::io::{IoState, IoStats};
+        use crate::transport::Transport;
+        use crate::{client, server};
+
+        use super::super::*;
+        use super::CLIENT_STEAM_ID;
+
+        const SERVER_STEAM_ID: u64 = 76561197960287930;
+
+        /// Mock of a Steam connection: the messages are exchanged over channels
+        struct MockConnection {
+            send: Sender<Vec<u8>>,
+            recv: Receiver<Vec<u8>>,
+        }
+
+        impl MockConnection {
+            fn pair() -> (Self, Self) {
+                let (send_a, recv_a) = crossbeam_channel::unbounded();
+                let (send_b, recv_b) = crossbeam_channel::unbounded();
+                (
+                    Self {
+                        send: send_a,
+                        recv: recv_b,
+                    },
+                    Self {
+                        send: send_b,
+                        recv: recv_a,
+                    },
+                )
+            }
+        }
+
+        impl SteamConnection for MockConnection {
+            fn send_message(&self, payload: &[u8]) -> Result<()> {
+                self.send.send(payload.to_vec())?;
+                Ok(())
+            }
+
+            fn receive_message(&mut self, buffer: &mut Vec<u8>) -> Result<bool> {
+                let Ok(message) = self.recv.try_recv() else {
+                    return Ok(false);
+                };
+                *buffer = message;
+                Ok(true)
+            }
+        }
+
+        /// Mock of the Steam events: the incoming connections are added on the next update
+        struct MockEvents {
+            incoming: Receiver<(SocketAddr, MockConnection)>,
+            num_updates: Arc<AtomicUsize>,
+        }
+
+        impl SteamEvents for MockEvents {
+            type Connection = MockConnection;
+
+            fn process_events(&mut self, connections: &mut HashMap<SocketAddr, MockConnection>) {
+                self.num_updates.fetch_add(1, Ordering::Relaxed);
+                connections.extend(self.incoming.try_iter());
+            }
+        }
+
+        /// Run the netcode handshake over Steam sockets: the client is authenticated
+        /// with its connect token, and the server identifies it by its steam id
+        #[test]
+        fn test_netcode_over_steam() {
+            let (server_connection, client_connection) = MockConnection::pair();
+            let (incoming_send, incoming) = crossbeam_channel::unbounded();
+            incoming_send
+                .send((steam_id_to_addr(CLIENT_STEAM_ID), server_connection))
+                .unwrap();
+            let server_updates = Arc::new(AtomicUsize::new(0));
+            let server_socket = SteamSocket::new(
+                steam_id_to_addr(SERVER_STEAM_ID),
+                MockEvents {
+                    incoming,
+                    num_updates: server_updates.clone(),
+                },
+                HashMap::new(),
+            );
+            let client_updates = Arc::new(AtomicUsize::new(0));
+            let client_socket = SteamSocket::new(
+                steam_id_to_addr(CLIENT_STEAM_ID),
+                MockEvents {
+                    incoming: crossbeam_channel::never(),
+                    num_updates: client_updates.clone(),
+                },
+                HashMap::from([(steam_id_to_addr(SERVER_STEAM_ID), client_connection)]),
+            );
+
+            let local_addr = server_socket.local_addr();
+            let (sender, receiver) = server_socket.split();
+            let mut server_io = server::io::Io {
+                local_addr,
+                sender,
+                receiver,
+                state: IoState::Connected,
+                stats: IoStats::default(),
+                context: server::io::IoContext {
+                    event_sender: None,
+                    event_receiver: None,
+                },
+            };
+            let local_addr = client_socket.local_addr();
+            let (sender, receiver) = client_socket.split();
+            let mut client_io = client::io::Io {
+                local_addr,
+                sender,
+                receiver,
+                state: IoState::Connected,
+                stats: IoStats::default(),
+                context: client::io::IoContext {
+                    event_sender: None,
+                    event_receiver: None,
+                },
+            };
+
+            let mut server = NetcodeServer::new(0, generate_key()).unwrap();
+            // the client connects to the virtual address of the host
+            let token = server
+                .token(1, steam_id_to_addr(SERVER_STEAM_ID))
+                .generate()
+                .unwrap()
+                .try_into_bytes()
+                .unwrap();
+            let mut client = NetcodeClient::new(&token).unwrap();
+            client.connect();
+            for _ in 0..10 {
+                client.update(10.0, &mut client_io);
+                server.update(10.0, &mut server_io);
+            }
+
+            assert!(client.is_connected());
+            assert_eq!(client.id(), 1);
+            assert_eq!(server.connected_client_ids().collect::<Vec<_>>(), vec![1]);
+            assert_eq!(
+                server
+                    .client_addr(1)
+                    .and_then(|addr| addr_to_steam_id(&addr)),
+                Some(CLIENT_STEAM_ID)
+            );
+            // the steam events are processed once per update, not once per received packet
+            assert_eq!(server_updates.load(Ordering::Relaxed), 10);
+            assert_eq!(client_updates.load(Ordering::Relaxed), 10);
+        }
+    }
+}